@@ -1,5 +1,6 @@
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 use fhe_math::rns::{RnsContext, RnsScaler, ScalingFactor};
+use ndarray::Array2;
 use num_bigint::BigUint;
 use rand::{thread_rng, RngCore};
 extern crate alloc;
@@ -52,6 +53,28 @@ pub fn rns_benchmark(c: &mut Criterion) {
         },
     );
 
+    let rests = Array2::from_shape_fn((q.len(), 1 << 12), |(i, _)| rng.next_u64() % q[i]);
+
+    group.bench_function(
+        BenchmarkId::new("lift", format!("{}/{}", q.len(), 1 << 12)),
+        |b| {
+            b.iter(|| {
+                rests
+                    .columns()
+                    .into_iter()
+                    .map(|c| rns_q.lift(c))
+                    .collect::<Vec<_>>()
+            });
+        },
+    );
+
+    group.bench_function(
+        BenchmarkId::new("lift_vec", format!("{}/{}", q.len(), 1 << 12)),
+        |b| {
+            b.iter(|| rns_q.lift_vec(rests.view()));
+        },
+    );
+
     group.finish();
 }
 
@@ -18,7 +18,7 @@ static MODULI: &[u64; 4] = &[
 
 static DEGREE: &[usize] = &[1024, 2048, 4096, 8192];
 
-fn create_group(c: &mut Criterion, name: String) -> BenchmarkGroup<WallTime> {
+fn create_group(c: &mut Criterion, name: String) -> BenchmarkGroup<'_, WallTime> {
     let mut group = c.benchmark_group(name);
     group.warm_up_time(Duration::from_millis(100));
     group.measurement_time(Duration::from_secs(1));
@@ -287,5 +287,38 @@ pub fn rq_benchmark(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(rq, rq_op_benchmark, rq_dot_product, rq_benchmark);
+pub fn rq_representation_benchmark(c: &mut Criterion) {
+    let mut group = create_group(c, "rq_representation".to_string());
+    let mut rng = thread_rng();
+
+    for degree in DEGREE {
+        let ctx = Arc::new(Context::new(MODULI, *degree).unwrap());
+        let mut p = Poly::random(&ctx, Representation::Ntt, &mut rng);
+        let id = format!("{}/{}", degree, ctx.modulus().bits());
+
+        let cycle = |p: &mut Poly| {
+            p.change_representation(Representation::PowerBasis);
+            p.change_representation(Representation::Ntt);
+            p.change_representation(Representation::NttShoup);
+            p.change_representation(Representation::Ntt);
+        };
+        group.bench_function(BenchmarkId::new("change_representation", &id), |b| {
+            b.iter(|| cycle(&mut p));
+        });
+
+        group.bench_function(BenchmarkId::new("to_representation", &id), |b| {
+            b.iter(|| p.to_representation(Representation::PowerBasis));
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(
+    rq,
+    rq_op_benchmark,
+    rq_dot_product,
+    rq_benchmark,
+    rq_representation_benchmark
+);
 criterion_main!(rq);
@@ -1,11 +1,14 @@
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
-use fhe_math::zq::Modulus;
+use fhe_math::zq::{detect_simd_lanes, simd_lanes, Modulus};
 use rand::thread_rng;
 
 pub fn zq_benchmark(c: &mut Criterion) {
     let mut group = c.benchmark_group("zq");
     group.sample_size(50);
 
+    group.bench_function("simd_lanes", |b| b.iter(simd_lanes));
+    group.bench_function("detect_simd_lanes", |b| b.iter(detect_simd_lanes));
+
     let p = 4611686018326724609;
     let mut rng = thread_rng();
 
@@ -44,6 +47,12 @@ pub fn zq_benchmark(c: &mut Criterion) {
             b.iter(|| q.mul_shoup_vec(&mut a, &c, &c_shoup));
         });
 
+        let mut c_montgomery = c.clone();
+        q.to_montgomery_vec(&mut c_montgomery).unwrap();
+        group.bench_function(BenchmarkId::new("mul_montgomery_vec", vector_size), |b| {
+            b.iter(|| q.mul_montgomery_vec(&mut a, &c_montgomery).unwrap());
+        });
+
         group.bench_function(BenchmarkId::new("scalar_mul_vec", vector_size), |b| {
             b.iter(|| q.scalar_mul_vec(&mut a, scalar));
         });
@@ -0,0 +1,253 @@
+#![warn(missing_docs, unused_imports)]
+
+//! Canonical embedding of the cyclotomic ring R\[x\] / (x^n + 1) into C^(n/2).
+//!
+//! The canonical embedding maps a real polynomial `m` of degree smaller than
+//! `n` to its evaluations `m(zeta^(5^j))` for `0 <= j < n/2`, where `zeta` is
+//! the primitive `2n`-th root of unity `exp(i * pi / n)`; the evaluations at
+//! the other primitive roots are the complex conjugates of these. It is the
+//! basis of approximate encodings such as the one of the CKKS scheme, and is
+//! computed in `O(n log(n))` operations using the special FFT of the HEAAN
+//! library, in double precision.
+
+use crate::{Error, Result};
+use itertools::Itertools;
+pub use num_complex::Complex64;
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+/// Canonical embedding for a specific degree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CanonicalEmbedding {
+    degree: usize,
+    rot_group: Box<[usize]>,
+    roots: Box<[Complex64]>,
+}
+
+impl CanonicalEmbedding {
+    /// Create the canonical embedding for polynomials of degree smaller than
+    /// `degree`.
+    ///
+    /// Returns an error if the degree is not a power of two larger than or
+    /// equal to 2.
+    pub fn new(degree: usize) -> Result<Self> {
+        if degree < 2 || !degree.is_power_of_two() {
+            return Err(Error::Default(
+                "The degree should be a power of two larger than or equal to 2".to_string(),
+            ));
+        }
+        let m = 2 * degree;
+        let slots = degree / 2;
+
+        let mut rot_group = Vec::with_capacity(slots);
+        let mut five_pow = 1usize;
+        for _ in 0..slots {
+            rot_group.push(five_pow);
+            five_pow = (five_pow * 5) % m;
+        }
+
+        let roots = (0..=m)
+            .map(|j| {
+                let angle = 2.0 * core::f64::consts::PI * (j as f64) / (m as f64);
+                Complex64::new(libm::cos(angle), libm::sin(angle))
+            })
+            .collect_vec();
+
+        Ok(Self {
+            degree,
+            rot_group: rot_group.into_boxed_slice(),
+            roots: roots.into_boxed_slice(),
+        })
+    }
+
+    /// Returns the degree of the polynomials.
+    pub fn degree(&self) -> usize {
+        self.degree
+    }
+
+    /// Returns the number of complex slots, equal to half the degree.
+    pub fn slots(&self) -> usize {
+        self.degree / 2
+    }
+
+    /// Compute the canonical embedding of the polynomial with coefficients
+    /// `coefficients`, i.e. its evaluations at `zeta^(5^j)` for `0 <= j <
+    /// n/2`.
+    ///
+    /// Returns an error if the number of coefficients is not equal to the
+    /// degree.
+    pub fn embed(&self, coefficients: &[f64]) -> Result<Vec<Complex64>> {
+        if coefficients.len() != self.degree {
+            return Err(Error::Default(
+                "The number of coefficients should be equal to the degree".to_string(),
+            ));
+        }
+        let slots = self.slots();
+        let mut values = (0..slots)
+            .map(|i| Complex64::new(coefficients[i], coefficients[i + slots]))
+            .collect_vec();
+        self.fft_special(&mut values);
+        Ok(values)
+    }
+
+    /// Compute the inverse of the canonical embedding, i.e. the coefficients of
+    /// the real polynomial whose evaluations at `zeta^(5^j)` are `values[j]`
+    /// for `0 <= j < n/2`.
+    ///
+    /// Returns an error if the number of values is not equal to the number of
+    /// slots.
+    pub fn embed_inverse(&self, values: &[Complex64]) -> Result<Vec<f64>> {
+        if values.len() != self.slots() {
+            return Err(Error::Default(
+                "The number of values should be equal to the number of slots".to_string(),
+            ));
+        }
+        let mut values = values.to_vec();
+        self.fft_special_inv(&mut values);
+        Ok(values
+            .iter()
+            .map(|v| v.re)
+            .chain(values.iter().map(|v| v.im))
+            .collect_vec())
+    }
+
+    /// Permute the values in bit-reversed order.
+    fn bit_reverse(values: &mut [Complex64]) {
+        let n = values.len();
+        if n < 2 {
+            return;
+        }
+        let shift = usize::BITS - n.trailing_zeros();
+        for i in 0..n {
+            let j = i.reverse_bits() >> shift;
+            if i < j {
+                values.swap(i, j);
+            }
+        }
+    }
+
+    /// Special FFT, evaluating at the roots `zeta^(5^j)`.
+    fn fft_special(&self, values: &mut [Complex64]) {
+        let n = values.len();
+        let m = 2 * self.degree;
+        Self::bit_reverse(values);
+        let mut len = 2;
+        while len <= n {
+            let lenh = len >> 1;
+            let lenq = len << 2;
+            for i in (0..n).step_by(len) {
+                for j in 0..lenh {
+                    let idx = (self.rot_group[j] % lenq) * m / lenq;
+                    let u = values[i + j];
+                    let v = values[i + j + lenh] * self.roots[idx];
+                    values[i + j] = u + v;
+                    values[i + j + lenh] = u - v;
+                }
+            }
+            len <<= 1;
+        }
+    }
+
+    /// Inverse of the special FFT.
+    fn fft_special_inv(&self, values: &mut [Complex64]) {
+        let n = values.len();
+        let m = 2 * self.degree;
+        let mut len = n;
+        while len >= 2 {
+            let lenh = len >> 1;
+            let lenq = len << 2;
+            for i in (0..n).step_by(len) {
+                for j in 0..lenh {
+                    let idx = (lenq - (self.rot_group[j] % lenq)) * m / lenq;
+                    let u = values[i + j] + values[i + j + lenh];
+                    let v = (values[i + j] - values[i + j + lenh]) * self.roots[idx];
+                    values[i + j] = u;
+                    values[i + j + lenh] = v;
+                }
+            }
+            len >>= 1;
+        }
+        Self::bit_reverse(values);
+        let n_inv = 1.0 / (n as f64);
+        values.iter_mut().for_each(|v| *v *= n_inv);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CanonicalEmbedding, Complex64};
+    use rand::{thread_rng, Rng};
+    extern crate alloc;
+    use alloc::vec::Vec;
+
+    /// Evaluate the polynomial `a` at `x`.
+    fn evaluate(a: &[f64], x: Complex64) -> Complex64 {
+        a.iter()
+            .rev()
+            .fold(Complex64::new(0.0, 0.0), |acc, ai| acc * x + *ai)
+    }
+
+    fn distance(a: &[Complex64], b: &[Complex64]) -> f64 {
+        a.iter()
+            .zip(b.iter())
+            .map(|(ai, bi)| libm::sqrt((ai - bi).norm_sqr()))
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn constructor() {
+        assert!(CanonicalEmbedding::new(0).is_err());
+        assert!(CanonicalEmbedding::new(1).is_err());
+        assert!(CanonicalEmbedding::new(12).is_err());
+        let embedding = CanonicalEmbedding::new(16).unwrap();
+        assert_eq!(embedding.degree(), 16);
+        assert_eq!(embedding.slots(), 8);
+        assert!(embedding.embed(&[0.0; 8]).is_err());
+        assert!(embedding
+            .embed_inverse(&[Complex64::new(0.0, 0.0); 16])
+            .is_err());
+    }
+
+    #[test]
+    fn evaluations() {
+        let mut rng = thread_rng();
+        for degree in [2, 4, 16, 256] {
+            let embedding = CanonicalEmbedding::new(degree).unwrap();
+            let m = 2 * degree;
+            let coefficients = (0..degree)
+                .map(|_| rng.gen_range(-10.0..10.0))
+                .collect::<Vec<f64>>();
+
+            let mut five_pow = 1;
+            let expected = (0..degree / 2)
+                .map(|_| {
+                    let angle = 2.0 * core::f64::consts::PI * (five_pow as f64) / (m as f64);
+                    five_pow = (five_pow * 5) % m;
+                    evaluate(
+                        &coefficients,
+                        Complex64::new(libm::cos(angle), libm::sin(angle)),
+                    )
+                })
+                .collect::<Vec<_>>();
+            let values = embedding.embed(&coefficients).unwrap();
+            assert!(distance(&values, &expected) < 1e-9);
+        }
+    }
+
+    #[test]
+    fn bijection() {
+        let mut rng = thread_rng();
+        for degree in [2, 4, 16, 1024] {
+            let embedding = CanonicalEmbedding::new(degree).unwrap();
+            let values = (0..degree / 2)
+                .map(|_| Complex64::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0)))
+                .collect::<Vec<_>>();
+            let coefficients = embedding.embed_inverse(&values).unwrap();
+            assert_eq!(coefficients.len(), degree);
+            let values_back = embedding.embed(&coefficients).unwrap();
+            assert!(distance(&values, &values_back) < 1e-9);
+        }
+    }
+}
@@ -7,7 +7,11 @@
 
 mod errors;
 mod proto;
+mod unchecked;
 
+pub mod embedding;
+#[cfg(feature = "metrics")]
+pub mod metrics;
 pub mod ntt;
 pub mod rns;
 pub mod rq;
@@ -0,0 +1,24 @@
+//! Counter of the Number-Theoretic Transforms, enabled by the `metrics`
+//! feature.
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+static NTTS: AtomicU64 = AtomicU64::new(0);
+
+/// Returns the number of NTTs, forward or backward, computed on the
+/// polynomials of [`crate::rq`] since the start of the program or the last
+/// call to [`reset`]. A transform of a polynomial counts once per modulus.
+pub fn ntts() -> u64 {
+    NTTS.load(Ordering::Relaxed)
+}
+
+/// Reset the counter.
+pub fn reset() {
+    NTTS.store(0, Ordering::Relaxed)
+}
+
+/// Count one NTT.
+#[inline]
+pub(crate) fn record_ntt() {
+    NTTS.fetch_add(1, Ordering::Relaxed);
+}
@@ -0,0 +1,92 @@
+use super::{CyclicNttOperator, NttOperator};
+use crate::zq::Modulus;
+extern crate alloc;
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+
+/// A cache of NTT operators.
+///
+/// Creating an operator requires precomputing the powers of a root of unity,
+/// which is more expensive than the transform itself. The cache creates each
+/// operator once per modulus and size, and returns shared references to it.
+#[derive(Debug, Default, Clone)]
+pub struct NttOperatorCache {
+    negacyclic: BTreeMap<(u64, usize), Arc<NttOperator>>,
+    cyclic: BTreeMap<(u64, usize), Arc<CyclicNttOperator>>,
+}
+
+impl NttOperatorCache {
+    /// Create an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the negacyclic NTT operator for the modulus `p` and the size
+    /// `size`, creating it if it is not in the cache.
+    ///
+    /// Returns None if the modulus does not support the NTT for this specific
+    /// size.
+    pub fn negacyclic(&mut self, p: &Modulus, size: usize) -> Option<Arc<NttOperator>> {
+        if let Some(op) = self.negacyclic.get(&(**p, size)) {
+            return Some(op.clone());
+        }
+        let op = Arc::new(NttOperator::new(p, size)?);
+        self.negacyclic.insert((**p, size), op.clone());
+        Some(op)
+    }
+
+    /// Returns the cyclic NTT operator for the modulus `p` and the size `size`,
+    /// creating it if it is not in the cache.
+    ///
+    /// Returns None if the modulus does not support the NTT for this specific
+    /// size.
+    pub fn cyclic(&mut self, p: &Modulus, size: usize) -> Option<Arc<CyclicNttOperator>> {
+        if let Some(op) = self.cyclic.get(&(**p, size)) {
+            return Some(op.clone());
+        }
+        let op = Arc::new(CyclicNttOperator::new(p, size)?);
+        self.cyclic.insert((**p, size), op.clone());
+        Some(op)
+    }
+
+    /// Returns the number of operators in the cache.
+    pub fn len(&self) -> usize {
+        self.negacyclic.len() + self.cyclic.len()
+    }
+
+    /// Returns whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NttOperatorCache;
+    use crate::zq::Modulus;
+    extern crate alloc;
+    use alloc::sync::Arc;
+
+    #[test]
+    fn cache() {
+        let mut cache = NttOperatorCache::new();
+        assert!(cache.is_empty());
+
+        let q = Modulus::new(1153).unwrap();
+        let op = cache.negacyclic(&q, 16).unwrap();
+        assert!(Arc::ptr_eq(&op, &cache.negacyclic(&q, 16).unwrap()));
+        assert_eq!(cache.len(), 1);
+
+        let cyclic_op = cache.cyclic(&q, 16).unwrap();
+        assert!(Arc::ptr_eq(&cyclic_op, &cache.cyclic(&q, 16).unwrap()));
+        assert_eq!(cache.len(), 2);
+
+        assert!(!Arc::ptr_eq(&op, &cache.negacyclic(&q, 32).unwrap()));
+        assert_eq!(cache.len(), 3);
+
+        // The modulus does not support the NTT of size 1024.
+        assert!(cache.negacyclic(&q, 1024).is_none());
+        assert!(cache.cyclic(&q, 1024).is_none());
+        assert_eq!(cache.len(), 3);
+    }
+}
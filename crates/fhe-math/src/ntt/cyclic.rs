@@ -0,0 +1,166 @@
+use super::NttOperator;
+use crate::zq::Modulus;
+use core::iter::successors;
+use itertools::Itertools;
+extern crate alloc;
+use alloc::boxed::Box;
+
+/// Cyclic Number-Theoretic Transform operator.
+///
+/// The cyclic NTT of size `n` evaluates a polynomial of `ZZ_p[x] / (x^n - 1)`
+/// at the powers `omega^j` of a primitive `n`-th root of unity `omega`. It is
+/// computed by twisting the coefficients by the powers of a square root `psi`
+/// of `omega`, and by applying the negacyclic [`NttOperator`]. The output
+/// follows the same bit-reversed order as the negacyclic transform, i.e. the
+/// `k`-th output is the evaluation at `omega^(bitrev(k))`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CyclicNttOperator {
+    p: Modulus,
+    op: NttOperator,
+    twist: Box<[u64]>,
+    twist_shoup: Box<[u64]>,
+    twist_inv: Box<[u64]>,
+    twist_inv_shoup: Box<[u64]>,
+}
+
+impl CyclicNttOperator {
+    /// Create a cyclic NTT operator given a modulus for a specific size.
+    ///
+    /// Aborts if the size is not a power of 2 that is >= 8 in debug mode.
+    /// Returns None if the modulus does not support the NTT for this specific
+    /// size.
+    pub fn new(p: &Modulus, size: usize) -> Option<Self> {
+        let op = NttOperator::new(p, size)?;
+
+        // The first output of the negacyclic NTT of x is its evaluation at psi.
+        let mut x = alloc::vec![0u64; size];
+        x[1] = 1;
+        op.forward(&mut x);
+        let psi = x[0];
+        let psi_inv = p.inv(psi)?;
+
+        let twist = successors(Some(1u64), |t| Some(p.mul(*t, psi_inv)))
+            .take(size)
+            .collect_vec();
+        let twist_inv = successors(Some(1u64), |t| Some(p.mul(*t, psi)))
+            .take(size)
+            .collect_vec();
+        let twist_shoup = p.shoup_vec(&twist);
+        let twist_inv_shoup = p.shoup_vec(&twist_inv);
+
+        Some(Self {
+            p: p.clone(),
+            op,
+            twist: twist.into_boxed_slice(),
+            twist_shoup: twist_shoup.into_boxed_slice(),
+            twist_inv: twist_inv.into_boxed_slice(),
+            twist_inv_shoup: twist_inv_shoup.into_boxed_slice(),
+        })
+    }
+
+    /// Compute the forward cyclic NTT in place.
+    /// Aborts if a is not of the size handled by the operator.
+    pub fn forward(&self, a: &mut [u64]) {
+        self.p.mul_shoup_vec(a, &self.twist, &self.twist_shoup);
+        self.op.forward(a);
+    }
+
+    /// Compute the backward cyclic NTT in place.
+    /// Aborts if a is not of the size handled by the operator.
+    pub fn backward(&self, a: &mut [u64]) {
+        self.op.backward(a);
+        self.p
+            .mul_shoup_vec(a, &self.twist_inv, &self.twist_inv_shoup);
+    }
+
+    /// Compute the forward cyclic NTT in place in variable time.
+    /// Aborts if a is not of the size handled by the operator.
+    ///
+    /// # Safety
+    /// This function is not constant time and its timing may reveal information
+    /// about the value being reduced.
+    pub unsafe fn forward_vt(&self, a: &mut [u64]) {
+        assert_eq!(a.len(), self.twist.len());
+        self.p.mul_shoup_vec_vt(a, &self.twist, &self.twist_shoup);
+        self.op.forward_vt(a.as_mut_ptr());
+    }
+
+    /// Compute the backward cyclic NTT in place in variable time.
+    /// Aborts if a is not of the size handled by the operator.
+    ///
+    /// # Safety
+    /// This function is not constant time and its timing may reveal information
+    /// about the value being reduced.
+    pub unsafe fn backward_vt(&self, a: &mut [u64]) {
+        assert_eq!(a.len(), self.twist.len());
+        self.op.backward_vt(a.as_mut_ptr());
+        self.p
+            .mul_shoup_vec_vt(a, &self.twist_inv, &self.twist_inv_shoup);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CyclicNttOperator;
+    use crate::ntt::{supports_ntt, NttOperator};
+    use crate::zq::Modulus;
+    use rand::thread_rng;
+    extern crate alloc;
+    use alloc::vec::Vec;
+
+    /// Evaluate the polynomial `a` at `x`.
+    fn evaluate(q: &Modulus, a: &[u64], x: u64) -> u64 {
+        a.iter().rev().fold(0, |acc, ai| q.add(q.mul(acc, x), *ai))
+    }
+
+    #[test]
+    fn evaluations() {
+        let mut rng = thread_rng();
+        for size in [8, 32, 1024] {
+            for p in [1153, 4611686018326724609] {
+                if !supports_ntt(p, size) {
+                    assert!(CyclicNttOperator::new(&Modulus::new(p).unwrap(), size).is_none());
+                    continue;
+                }
+                let q = Modulus::new(p).unwrap();
+                let op = NttOperator::new(&q, size).unwrap();
+                let cyclic_op = CyclicNttOperator::new(&q, size).unwrap();
+
+                let mut x = alloc::vec![0u64; size];
+                x[1] = 1;
+                op.forward(&mut x);
+                let psi = x[0];
+                let omega = q.mul(psi, psi);
+                let log_size = size.trailing_zeros();
+                let bitrev = |k: usize| k.reverse_bits() >> (usize::BITS - log_size);
+
+                let a = q.random_vec(size, &mut rng);
+
+                // The negacyclic transform evaluates at the odd powers of psi.
+                let mut b = a.clone();
+                op.forward(&mut b);
+                let expected = (0..size)
+                    .map(|k| evaluate(&q, &a, q.pow(psi, 2 * bitrev(k) as u64 + 1)))
+                    .collect::<Vec<_>>();
+                assert_eq!(b, expected);
+
+                // The cyclic transform evaluates at the powers of omega.
+                let mut c = a.clone();
+                cyclic_op.forward(&mut c);
+                let expected = (0..size)
+                    .map(|k| evaluate(&q, &a, q.pow(omega, bitrev(k) as u64)))
+                    .collect::<Vec<_>>();
+                assert_eq!(c, expected);
+
+                cyclic_op.backward(&mut c);
+                assert_eq!(c, a);
+
+                let mut d = a.clone();
+                unsafe { cyclic_op.forward_vt(&mut d) }
+                assert_eq!(d, expected);
+                unsafe { cyclic_op.backward_vt(&mut d) }
+                assert_eq!(d, a);
+            }
+        }
+    }
+}
@@ -1,8 +1,41 @@
 //! Number-Theoretic Transform in ZZ_q.
+//!
+//! This module provides the transforms used by the polynomial arithmetic of
+//! [`crate::rq`], as a standalone API to build custom encoders:
+//! - [`NttOperator`] computes the negacyclic NTT, i.e. the evaluation of a
+//!   polynomial of `ZZ_p[x] / (x^n + 1)` at the odd powers of a primitive
+//!   `2n`-th root of unity;
+//! - [`CyclicNttOperator`] computes the cyclic NTT, i.e. the evaluation of a
+//!   polynomial of `ZZ_p[x] / (x^n - 1)` at the powers of a primitive `n`-th
+//!   root of unity;
+//! - [`NttOperatorCache`] shares the operators, whose precomputations are
+//!   more expensive than the transforms.
+//!
+//! The size `n` must be a power of two larger than or equal to 8, and the
+//! modulus `p` must be a prime congruent to 1 modulo `2n` (see
+//! [`supports_ntt`]). The outputs of the forward transforms are in bit-reversed
+//! order.
+//!
+//! ```
+//! use fhe_math::{ntt::NttOperatorCache, zq::Modulus};
+//!
+//! let q = Modulus::new(1153).unwrap();
+//! let mut cache = NttOperatorCache::new();
+//! let op = cache.cyclic(&q, 16).unwrap();
+//!
+//! let a = (0..16).collect::<Vec<u64>>();
+//! let mut b = a.clone();
+//! op.forward(&mut b);
+//! op.backward(&mut b);
+//! assert_eq!(a, b);
+//! ```
 
 use fhe_util::is_prime;
 
+mod cache;
+mod cyclic;
 mod native;
+mod prime;
 
 #[cfg(any(feature = "concrete-ntt", feature = "concrete-ntt-nightly"))]
 mod concrete;
@@ -12,11 +45,15 @@ pub use concrete::NttOperator;
 #[cfg(not(any(feature = "concrete-ntt", feature = "concrete-ntt-nightly")))]
 pub use native::NttOperator;
 
+pub use cache::NttOperatorCache;
+pub use cyclic::CyclicNttOperator;
+pub use prime::{prime_ntt_order, supports_prime_ntt, PrimeNttOperator};
+
 /// Returns whether a modulus p is prime and supports the Number Theoretic
 /// Transform of size n.
 ///
 /// Aborts if n is not a power of 2 that is >= 8.
-pub(crate) fn supports_ntt(p: u64, n: usize) -> bool {
+pub fn supports_ntt(p: u64, n: usize) -> bool {
     assert!(n >= 8 && n.is_power_of_two());
 
     p % ((n as u64) << 1) == 1 && is_prime(p)
@@ -72,6 +72,8 @@ impl NttOperator {
     /// Aborts if a is not of the size handled by the operator.
     pub fn forward(&self, a: &mut [u64]) {
         debug_assert_eq!(a.len(), self.size);
+        #[cfg(target_arch = "aarch64")]
+        let neon = crate::zq::neon::is_available();
 
         let n = self.size;
         let a_ptr = a.as_mut_ptr();
@@ -97,6 +99,18 @@ impl NttOperator {
                             *ujl = self.reduce3(*ujl);
                         }
                         _ => {
+                            #[cfg(target_arch = "aarch64")]
+                            if neon {
+                                let (x, y) = (a_ptr.add(s), a_ptr.add(s + l));
+                                crate::zq::neon::butterflies(
+                                    &self.p,
+                                    core::slice::from_raw_parts_mut(x, l),
+                                    core::slice::from_raw_parts_mut(y, l),
+                                    omega,
+                                    omega_shoup,
+                                );
+                                continue;
+                            }
                             for j in s..(s + l) {
                                 self.butterfly(
                                     &mut *a_ptr.add(j),
@@ -118,6 +132,8 @@ impl NttOperator {
     /// Aborts if a is not of the size handled by the operator.
     pub fn backward(&self, a: &mut [u64]) {
         debug_assert_eq!(a.len(), self.size);
+        #[cfg(target_arch = "aarch64")]
+        let neon = crate::zq::neon::is_available();
 
         let a_ptr = a.as_mut_ptr();
 
@@ -141,6 +157,18 @@ impl NttOperator {
                             );
                         }
                         _ => {
+                            #[cfg(target_arch = "aarch64")]
+                            if neon {
+                                let (x, y) = (a_ptr.add(s), a_ptr.add(s + l));
+                                crate::zq::neon::inv_butterflies(
+                                    &self.p,
+                                    core::slice::from_raw_parts_mut(x, l),
+                                    core::slice::from_raw_parts_mut(y, l),
+                                    zeta_inv,
+                                    zeta_inv_shoup,
+                                );
+                                continue;
+                            }
                             for j in s..(s + l) {
                                 self.inv_butterfly(
                                     &mut *a_ptr.add(j),
@@ -170,6 +198,8 @@ impl NttOperator {
     /// This function is not constant time and its timing may reveal information
     /// about the value being reduced.
     pub(crate) unsafe fn forward_vt_lazy(&self, a_ptr: *mut u64) {
+        #[cfg(target_arch = "aarch64")]
+        let neon = crate::zq::neon::is_available();
         let mut l = self.size >> 1;
         let mut m = 1;
         let mut k = 1;
@@ -190,6 +220,18 @@ impl NttOperator {
                         );
                     }
                     _ => {
+                        #[cfg(target_arch = "aarch64")]
+                        if neon {
+                            let (x, y) = (a_ptr.add(s), a_ptr.add(s + l));
+                            crate::zq::neon::butterflies(
+                                &self.p,
+                                core::slice::from_raw_parts_mut(x, l),
+                                core::slice::from_raw_parts_mut(y, l),
+                                omega,
+                                omega_shoup,
+                            );
+                            continue;
+                        }
                         for j in s..(s + l) {
                             self.butterfly_vt(
                                 &mut *a_ptr.add(j),
@@ -226,6 +268,8 @@ impl NttOperator {
     /// This function is not constant time and its timing may reveal information
     /// about the value being reduced.
     pub unsafe fn backward_vt(&self, a_ptr: *mut u64) {
+        #[cfg(target_arch = "aarch64")]
+        let neon = crate::zq::neon::is_available();
         let mut k = 0;
         let mut m = self.size >> 1;
         let mut l = 1;
@@ -245,6 +289,18 @@ impl NttOperator {
                         );
                     }
                     _ => {
+                        #[cfg(target_arch = "aarch64")]
+                        if neon {
+                            let (x, y) = (a_ptr.add(s), a_ptr.add(s + l));
+                            crate::zq::neon::inv_butterflies(
+                                &self.p,
+                                core::slice::from_raw_parts_mut(x, l),
+                                core::slice::from_raw_parts_mut(y, l),
+                                zeta_inv,
+                                zeta_inv_shoup,
+                            );
+                            continue;
+                        }
                         for j in s..(s + l) {
                             self.inv_butterfly_vt(
                                 &mut *a_ptr.add(j),
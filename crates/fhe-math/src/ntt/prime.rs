@@ -0,0 +1,306 @@
+use super::{supports_ntt, CyclicNttOperator};
+use crate::zq::Modulus;
+use fhe_util::is_prime;
+use itertools::{izip, Itertools};
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Returns the size of the power-of-two cyclic convolution used by Rader's
+/// algorithm for the prime `m`, i.e. the smallest power of two >= 8 in which
+/// a cyclic convolution of size `m - 1` can be embedded.
+const fn convolution_size(m: usize) -> usize {
+    let size = (2 * m - 3).next_power_of_two();
+    if size < 8 {
+        8
+    } else {
+        size
+    }
+}
+
+/// Returns the order of the roots of unity needed by the NTT of the prime
+/// cyclotomic ring of index `m`: a modulus `p` supports this NTT if it is a
+/// prime congruent to 1 modulo this order.
+///
+/// Aborts if m is not an odd prime.
+pub fn prime_ntt_order(m: usize) -> u64 {
+    assert!(m > 2 && is_prime(m as u64));
+
+    (2 * m * convolution_size(m)) as u64
+}
+
+/// Returns whether a modulus p is prime and supports the NTT of the prime
+/// cyclotomic ring of index m.
+///
+/// Aborts if m is not an odd prime.
+pub fn supports_prime_ntt(p: u64, m: usize) -> bool {
+    p % prime_ntt_order(m) == 1 && is_prime(p)
+}
+
+/// Number-Theoretic Transform operator for the prime cyclotomic ring
+/// `ZZ_p[x] / (1 + x + ... + x^(m - 1))`, of degree `m - 1`.
+///
+/// The transform evaluates a polynomial at the primitive `m`-th roots of unity
+/// `omega^k` for `k` in `1..m`, in this order. The size `m` is prime, so the
+/// evaluations are computed with Rader's algorithm, which turns them into a
+/// cyclic convolution of size `m - 1`; as in Bluestein's algorithm, this
+/// convolution is zero-padded and computed with a power-of-two
+/// [`CyclicNttOperator`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrimeNttOperator {
+    p: Modulus,
+    m: usize,
+    op: CyclicNttOperator,
+    convolution_size: usize,
+    generator_powers: Box<[usize]>,
+    generator_inv_powers: Box<[usize]>,
+    omegas_ntt: Box<[u64]>,
+    omegas_inv_ntt: Box<[u64]>,
+    m_inv: u64,
+}
+
+impl PrimeNttOperator {
+    /// Create an NTT operator given a modulus for the prime cyclotomic ring of
+    /// index `m`.
+    ///
+    /// Returns None if `m` is not an odd prime, or if the modulus does not
+    /// support the NTT for this ring (see [`supports_prime_ntt`]).
+    pub fn new(p: &Modulus, m: usize) -> Option<Self> {
+        if m < 3 || !is_prime(m as u64) || !supports_prime_ntt(p.p, m) {
+            return None;
+        }
+        let convolution_size = convolution_size(m);
+        debug_assert!(supports_ntt(p.p, convolution_size));
+        let op = CyclicNttOperator::new(p, convolution_size)?;
+
+        let generator = (2..m).find(|g| Self::is_generator(*g, m))?;
+        let generator_inv = (1..m).find(|g| (g * generator) % m == 1)?;
+        let generator_powers = Self::powers(generator, m);
+        let generator_inv_powers = Self::powers(generator_inv, m);
+
+        // A primitive m-th root of unity, since m is prime.
+        let omega = (2..p.p)
+            .map(|x| p.pow(x, (p.p - 1) / m as u64))
+            .find(|omega| *omega != 1)?;
+        let omega_inv = p.inv(omega)?;
+
+        let m_inv = p.inv(m as u64)?;
+        let omegas_ntt = Self::convolution_kernel(&op, p, omega, &generator_powers);
+        let omegas_inv_ntt = Self::convolution_kernel(&op, p, omega_inv, &generator_powers);
+
+        Some(Self {
+            p: p.clone(),
+            m,
+            op,
+            convolution_size,
+            generator_powers: generator_powers.into_boxed_slice(),
+            generator_inv_powers: generator_inv_powers.into_boxed_slice(),
+            omegas_ntt: omegas_ntt.into_boxed_slice(),
+            omegas_inv_ntt: omegas_inv_ntt.into_boxed_slice(),
+            m_inv,
+        })
+    }
+
+    /// Returns the degree `m - 1` of the ring.
+    pub const fn degree(&self) -> usize {
+        self.m - 1
+    }
+
+    /// Compute the forward NTT in place.
+    /// Aborts if a is not of the size handled by the operator.
+    pub fn forward(&self, a: &mut [u64]) {
+        assert_eq!(a.len(), self.degree());
+
+        // Evaluate a_0 + ... + a_(m - 2) x^(m - 2) at omega^k for k in 1..m.
+        let sums = self.rader(
+            |j| if j < self.m - 1 { a[j] } else { 0 },
+            |buffer| self.convolve(buffer, &self.omegas_ntt),
+        );
+        let a0 = a[0];
+        izip!(a.iter_mut(), &sums[1..]).for_each(|(ak, s)| *ak = self.p.add(a0, *s));
+    }
+
+    /// Compute the backward NTT in place.
+    /// Aborts if a is not of the size handled by the operator.
+    pub fn backward(&self, a: &mut [u64]) {
+        assert_eq!(a.len(), self.degree());
+
+        // The inverse DFT of size m recovers a polynomial of degree < m - 1 from
+        // its evaluations at the roots omega^k for k in 1..m, and from its
+        // evaluation y_0 at 1, which is defined by the nullity of the
+        // coefficient of degree m - 1.
+        let sums = self.rader(
+            |k| a[k - 1],
+            |buffer| self.convolve(buffer, &self.omegas_inv_ntt),
+        );
+        let y0 = self.p.neg(sums[self.m - 1]);
+        let total = a.iter().fold(0, |acc, ak| self.p.add(acc, *ak));
+        a[0] = self.p.mul(self.p.add(y0, total), self.m_inv);
+        izip!(a[1..].iter_mut(), &sums[1..self.m - 1])
+            .for_each(|(ak, s)| *ak = self.p.mul(self.p.add(y0, *s), self.m_inv));
+    }
+
+    /// Compute the forward NTT in place in variable time.
+    ///
+    /// # Safety
+    /// This function assumes that a_ptr points to at least `degree` elements.
+    /// This function is not constant time and its timing may reveal information
+    /// about the value being reduced.
+    pub unsafe fn forward_vt(&self, a_ptr: *mut u64) {
+        let a = core::slice::from_raw_parts_mut(a_ptr, self.degree());
+        let sums = self.rader(
+            |j| if j < self.m - 1 { a[j] } else { 0 },
+            |buffer| self.convolve_vt(buffer, &self.omegas_ntt),
+        );
+        let a0 = a[0];
+        izip!(a.iter_mut(), &sums[1..]).for_each(|(ak, s)| *ak = self.p.add_vt(a0, *s));
+    }
+
+    /// Compute the backward NTT in place in variable time.
+    ///
+    /// # Safety
+    /// This function assumes that a_ptr points to at least `degree` elements.
+    /// This function is not constant time and its timing may reveal information
+    /// about the value being reduced.
+    pub unsafe fn backward_vt(&self, a_ptr: *mut u64) {
+        let a = core::slice::from_raw_parts_mut(a_ptr, self.degree());
+        let sums = self.rader(
+            |k| a[k - 1],
+            |buffer| self.convolve_vt(buffer, &self.omegas_inv_ntt),
+        );
+        let y0 = self.p.neg(sums[self.m - 1]);
+        let total = a.iter().fold(0, |acc, ak| self.p.add_vt(acc, *ak));
+        a[0] = self.p.add_vt(y0, total);
+        izip!(a[1..].iter_mut(), &sums[1..self.m - 1])
+            .for_each(|(ak, s)| *ak = self.p.add_vt(y0, *s));
+        self.p.scalar_mul_vec_vt(a, self.m_inv);
+    }
+
+    /// Computes `s_k = sum_(j in 1..m) x_j * omega^(j * k)` for `k` in `1..m`,
+    /// where `convolve` computes the convolution by the kernel of `omega`. The
+    /// output is indexed by `k`, and its first element is 0.
+    fn rader<F, C>(&self, x: F, convolve: C) -> Vec<u64>
+    where
+        F: Fn(usize) -> u64,
+        C: FnOnce(&mut [u64]),
+    {
+        let n = self.m - 1;
+
+        // With k = g^r and j = g^(-s), s_k = sum_s x_(g^(-s)) * omega^(g^(r - s))
+        // is a cyclic convolution of size n.
+        let mut buffer = vec![0u64; self.convolution_size];
+        izip!(buffer.iter_mut(), self.generator_inv_powers.iter()).for_each(|(b, j)| *b = x(*j));
+        convolve(&mut buffer);
+
+        let mut sums = vec![0u64; self.m];
+        for (r, k) in self.generator_powers.iter().enumerate() {
+            sums[*k] = buffer[r + n - 1];
+        }
+        sums
+    }
+
+    /// Cyclic convolution in place of `buffer` by the kernel whose NTT is
+    /// `omegas_ntt`.
+    fn convolve(&self, buffer: &mut [u64], omegas_ntt: &[u64]) {
+        self.op.forward(buffer);
+        self.p.mul_vec(buffer, omegas_ntt);
+        self.op.backward(buffer);
+    }
+
+    /// Cyclic convolution in place of `buffer` by the kernel whose NTT is
+    /// `omegas_ntt`, in variable time.
+    ///
+    /// # Safety
+    /// This function is not constant time and its timing may reveal information
+    /// about the value being reduced.
+    unsafe fn convolve_vt(&self, buffer: &mut [u64], omegas_ntt: &[u64]) {
+        self.op.forward_vt(buffer);
+        self.p.mul_vec_vt(buffer, omegas_ntt);
+        self.op.backward_vt(buffer);
+    }
+
+    /// Returns the NTT of the kernel `w` of size `2 * (m - 1) - 1` of the
+    /// convolution computed by Rader's algorithm, such that
+    /// `w_u = omega^(g^((u - m + 2) mod (m - 1)))`.
+    fn convolution_kernel(
+        op: &CyclicNttOperator,
+        p: &Modulus,
+        omega: u64,
+        generator_powers: &[usize],
+    ) -> Vec<u64> {
+        let n = generator_powers.len();
+        let mut kernel = vec![0u64; convolution_size(n + 1)];
+        for (u, w) in kernel.iter_mut().take(2 * n - 1).enumerate() {
+            *w = p.pow(omega, generator_powers[(u + 1) % n] as u64);
+        }
+        op.forward(&mut kernel);
+        kernel
+    }
+
+    /// Returns the powers `g^s mod m` for `s` in `0..m - 1`.
+    fn powers(g: usize, m: usize) -> Vec<usize> {
+        core::iter::successors(Some(1usize), |x| Some((x * g) % m))
+            .take(m - 1)
+            .collect_vec()
+    }
+
+    /// Returns whether g generates the multiplicative group modulo the prime m.
+    fn is_generator(g: usize, m: usize) -> bool {
+        Self::powers(g, m).into_iter().skip(1).all(|x| x != 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{prime_ntt_order, supports_prime_ntt, PrimeNttOperator};
+    use crate::zq::{primes::generate_prime, Modulus};
+    use rand::thread_rng;
+    extern crate alloc;
+    use alloc::vec::Vec;
+
+    /// Evaluate the polynomial `a` at `x`.
+    fn evaluate(q: &Modulus, a: &[u64], x: u64) -> u64 {
+        a.iter().rev().fold(0, |acc, ai| q.add(q.mul(acc, x), *ai))
+    }
+
+    #[test]
+    fn evaluations() {
+        let mut rng = thread_rng();
+        for m in [3, 5, 17, 31, 257] {
+            let p = generate_prime(62, prime_ntt_order(m), 1 << 62).unwrap();
+            assert!(supports_prime_ntt(p, m));
+            let q = Modulus::new(p).unwrap();
+            let op = PrimeNttOperator::new(&q, m).unwrap();
+            assert_eq!(op.degree(), m - 1);
+
+            // The evaluation of x at the first root is a primitive m-th root.
+            let mut x = alloc::vec![0u64; m - 1];
+            x[1 % (m - 1)] = 1;
+            op.forward(&mut x);
+            let omega = x[0];
+            assert_eq!(q.pow(omega, m as u64), 1);
+
+            let a = q.random_vec(m - 1, &mut rng);
+            let mut b = a.clone();
+            op.forward(&mut b);
+            let expected = (1..m)
+                .map(|k| evaluate(&q, &a, q.pow(omega, k as u64)))
+                .collect::<Vec<_>>();
+            assert_eq!(b, expected);
+
+            op.backward(&mut b);
+            assert_eq!(b, a);
+
+            let mut c = a.clone();
+            unsafe { op.forward_vt(c.as_mut_ptr()) }
+            assert_eq!(c, expected);
+            unsafe { op.backward_vt(c.as_mut_ptr()) }
+            assert_eq!(c, a);
+        }
+
+        let q = Modulus::new(4611686018326724609).unwrap();
+        assert!(PrimeNttOperator::new(&q, 17).is_none());
+        assert!(PrimeNttOperator::new(&q, 16).is_none());
+    }
+}
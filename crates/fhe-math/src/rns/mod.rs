@@ -1,13 +1,48 @@
 #![warn(missing_docs, unused_imports)]
 
 //! Residue-Number System operations.
+//!
+//! This module provides the RNS arithmetic used by the polynomials of
+//! [`crate::rq`], as a standalone API to prototype new multiplication or key
+//! switching strategies over user-chosen bases:
+//! - [`RnsContext`] represents the integers modulo a product of coprime
+//!   moduli by their rests modulo each modulus;
+//! - [`ScalingFactor`] is a rational number `numerator / denominator`;
+//! - [`RnsScaler`] maps the rests of an integer `x` in a context `from`, where
+//!   `x` is taken in the centered interval `(-product / 2, product / 2]`, to
+//!   the rests of `round(x * numerator / denominator)` in a context `to`,
+//!   without lifting `x` to a big integer.
+//!
+//! With the scaling factor [`ScalingFactor::one`], a scaler converts the
+//! centered representative of `x` from one basis to another, e.g. to extend a
+//! basis with new moduli:
+//!
+//! ```
+//! use fhe_math::rns::{RnsContext, RnsScaler, ScalingFactor};
+//! use num_bigint::BigUint;
+//! use std::sync::Arc;
+//!
+//! let q = Arc::new(RnsContext::new(&[1153, 4611686018326724609]).unwrap());
+//! let p = Arc::new(RnsContext::new(&[4611686018309947393, 4611686018282684417]).unwrap());
+//!
+//! // Convert x = 12345 from the basis q to the basis p.
+//! let x = q.project(&BigUint::from(12345u64));
+//! let converter = RnsScaler::new(&q, &p, ScalingFactor::one());
+//! assert_eq!(converter.scale_new((&x).into(), 2), p.project(&BigUint::from(12345u64)));
+//!
+//! // Compute round(x * 3 / 1000) = 37 in the basis p.
+//! let factor = ScalingFactor::new(&BigUint::from(3u64), &BigUint::from(1000u64));
+//! let scaler = RnsScaler::new(&q, &p, factor);
+//! assert_eq!(scaler.scale_new((&x).into(), 2), p.project(&BigUint::from(37u64)));
+//! ```
 
 use crate::{zq::Modulus, Error, Result};
 use alloc::fmt::Debug;
 use core::cmp::Ordering;
-use itertools::izip;
-use ndarray::ArrayView1;
-use num_bigint::BigUint;
+use fhe_util::inverse;
+use itertools::{izip, Itertools};
+use ndarray::{ArrayView1, ArrayView2, Axis};
+use num_bigint::{BigInt, BigUint};
 use num_bigint_dig::{BigInt as BigIntDig, BigUint as BigUintDig, ExtendedGcd, ModInverse};
 use num_traits::{cast::ToPrimitive, One, Zero};
 extern crate alloc;
@@ -28,7 +63,9 @@ pub struct RnsContext {
     q_tilde_shoup: Vec<u64>,
     q_star: Vec<BigUint>,
     garner: Vec<BigUint>,
+    mixed_radix_inv: Vec<Vec<u64>>,
     product: BigUint,
+    half_product: BigUint,
 }
 
 impl Debug for RnsContext {
@@ -100,6 +137,16 @@ impl RnsContext {
                 );
             }
 
+            // mixed_radix_inv[i][j] = qj^(-1) % qi for j < i
+            let mixed_radix_inv = (0..moduli_u64.len())
+                .map(|i| {
+                    (0..i)
+                        .map(|j| inverse(moduli_u64[j] % moduli_u64[i], moduli_u64[i]).unwrap())
+                        .collect_vec()
+                })
+                .collect_vec();
+
+            let half_product = &product >> 1;
             Ok(Self {
                 moduli_u64: moduli_u64.to_owned(),
                 moduli,
@@ -107,7 +154,9 @@ impl RnsContext {
                 q_tilde_shoup,
                 q_star,
                 garner,
+                mixed_radix_inv,
                 product,
+                half_product,
             })
         }
     }
@@ -117,6 +166,11 @@ impl RnsContext {
         &self.product
     }
 
+    /// Returns the moduli used when creating the RNS context.
+    pub fn moduli(&self) -> &[u64] {
+        &self.moduli_u64
+    }
+
     /// Project a BigUint into its rests.
     pub fn project(&self, a: &BigUint) -> Vec<u64> {
         let mut rests = Vec::with_capacity(self.moduli_u64.len());
@@ -137,6 +191,76 @@ impl RnsContext {
         result % &self.product
     }
 
+    /// Lift the columns of `rests` into BigUints, where the i-th row of `rests`
+    /// contains the rests modulo the i-th modulus.
+    ///
+    /// This uses Garner's mixed-radix conversion with precomputed constants:
+    /// the mixed-radix digits are computed on entire rows using the vectorized
+    /// modular arithmetic of [`Modulus`], and each value is then reconstructed
+    /// without any big integer division. This is much faster than calling
+    /// [`RnsContext::lift`] on each column, and the columns are reconstructed
+    /// in parallel when the `parallel` feature is enabled.
+    ///
+    /// Aborts if the number of rows is different than the number of moduli in
+    /// debug mode.
+    pub fn lift_vec(&self, rests: ArrayView2<u64>) -> Vec<BigUint> {
+        debug_assert_eq!(rests.nrows(), self.moduli.len());
+
+        // Compute the mixed-radix digits row by row.
+        let mut digits: Vec<Vec<u64>> = Vec::with_capacity(self.moduli.len());
+        for (i, (qi, row)) in izip!(&self.moduli, rests.axis_iter(Axis(0))).enumerate() {
+            let mut vi = row.to_vec();
+            for (vj, inv) in izip!(&digits, &self.mixed_radix_inv[i]) {
+                qi.sub_vec(&mut vi, &qi.reduce_vec_new(vj));
+                qi.scalar_mul_vec(&mut vi, *inv);
+            }
+            digits.push(vi);
+        }
+
+        // Evaluate x = v0 + q0 * (v1 + q1 * (v2 + ...)).
+        let reconstruct = |k: usize| {
+            let mut x = BigUint::zero();
+            for (vj, qj) in izip!(&digits, &self.moduli_u64).rev() {
+                x *= *qj;
+                x += vj[k];
+            }
+            x
+        };
+
+        #[cfg(not(feature = "parallel"))]
+        {
+            (0..rests.ncols()).map(reconstruct).collect_vec()
+        }
+
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+            (0..rests.ncols())
+                .into_par_iter()
+                .map(reconstruct)
+                .collect()
+        }
+    }
+
+    /// Lift the columns of `rests` into their centered representatives, i.e.
+    /// the BigInts in the interval `(-product / 2, product / 2]` where
+    /// `product` is the product of the moduli.
+    ///
+    /// Aborts if the number of rows is different than the number of moduli in
+    /// debug mode.
+    pub fn lift_vec_centered(&self, rests: ArrayView2<u64>) -> Vec<BigInt> {
+        self.lift_vec(rests)
+            .into_iter()
+            .map(|x| {
+                if x > self.half_product {
+                    BigInt::from(x) - BigInt::from(self.product.clone())
+                } else {
+                    BigInt::from(x)
+                }
+            })
+            .collect_vec()
+    }
+
     /// Getter for the i-th garner coefficient.
     pub fn get_garner(&self, i: usize) -> Option<&BigUint> {
         self.garner.get(i)
@@ -149,13 +273,14 @@ mod tests {
     use crate::Error;
 
     use super::RnsContext;
-    use ndarray::ArrayView1;
-    use num_bigint::BigUint;
+    use ndarray::{Array2, ArrayView1};
+    use num_bigint::{BigInt, BigUint};
     use rand::RngCore;
     extern crate alloc;
     
     
     use alloc::string::ToString;
+    use alloc::vec::Vec;
     
     
     
@@ -165,6 +290,10 @@ mod tests {
         assert!(RnsContext::new(&[2]).is_ok());
         assert!(RnsContext::new(&[2, 3]).is_ok());
         assert!(RnsContext::new(&[4, 15, 1153]).is_ok());
+        assert_eq!(
+            RnsContext::new(&[4, 15, 1153]).unwrap().moduli(),
+            &[4, 15, 1153]
+        );
 
         let e = RnsContext::new(&[]);
         assert!(e.is_err());
@@ -244,4 +373,58 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn lift_vec() -> Result<(), Error> {
+        let mut rng = rand::thread_rng();
+        for moduli in [
+            &[4u64, 15, 1153][..],
+            &[1153],
+            &[
+                4611686018326724609,
+                4611686018309947393,
+                4611686018232352769,
+            ],
+        ] {
+            let rns = RnsContext::new(moduli)?;
+            let product = rns.modulus().clone();
+
+            let mut values = (0..100)
+                .map(|_| {
+                    let bytes = (0..4).flat_map(|_| rng.next_u64().to_le_bytes());
+                    BigUint::from_bytes_le(&bytes.collect::<Vec<_>>()) % &product
+                })
+                .collect::<Vec<_>>();
+            values.push(BigUint::from(0u64));
+            values.push(&product - 1u64);
+            values.push(&product >> 1);
+            values.push((&product >> 1) + 1u64);
+
+            let mut rests = Array2::zeros((moduli.len(), values.len()));
+            for (k, value) in values.iter().enumerate() {
+                for (i, r) in rns.project(value).into_iter().enumerate() {
+                    rests[[i, k]] = r;
+                }
+            }
+
+            let lifted = rns.lift_vec(rests.view());
+            assert_eq!(lifted, values);
+            for (k, value) in values.iter().enumerate() {
+                assert_eq!(&rns.lift(rests.column(k)), value);
+            }
+
+            let centered = rns.lift_vec_centered(rests.view());
+            for (c, value) in centered.iter().zip(values.iter()) {
+                if value > &(&product >> 1) {
+                    assert_eq!(
+                        c,
+                        &(BigInt::from(value.clone()) - BigInt::from(product.clone()))
+                    );
+                } else {
+                    assert_eq!(c, &BigInt::from(value.clone()));
+                }
+            }
+        }
+        Ok(())
+    }
 }
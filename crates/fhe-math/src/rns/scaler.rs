@@ -15,7 +15,7 @@ use ndarray::{ArrayView1, ArrayViewMut1};
 use num_bigint::BigUint;
 use num_traits::{One, ToPrimitive, Zero};
 
-/// Scaling factor when performing a RNS scaling.
+/// Scaling factor `numerator / denominator` of a RNS scaling.
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct ScalingFactor {
     numerator: BigUint,
@@ -42,10 +42,33 @@ impl ScalingFactor {
             is_one: true,
         }
     }
+
+    /// Returns the numerator of the scaling factor.
+    pub fn numerator(&self) -> &BigUint {
+        &self.numerator
+    }
+
+    /// Returns the denominator of the scaling factor.
+    pub fn denominator(&self) -> &BigUint {
+        &self.denominator
+    }
+
+    /// Returns whether the scaling factor is equal to 1, in which case the
+    /// scaling is a conversion of the centered representative between two
+    /// bases.
+    pub fn is_one(&self) -> bool {
+        self.is_one
+    }
 }
 
-/// Scaler for a RNS context.
-/// This is a helper struct to perform RNS scaling.
+/// Scaler from a RNS context to another.
+///
+/// The scaler maps the rests of an integer `x` in the context `from`, where
+/// `x` is taken in the interval `(-product / 2, product / 2]` and `product` is
+/// the product of the moduli of `from`, to the rests of
+/// `round(x * numerator / denominator)` in the context `to`. The two contexts
+/// may share moduli or not; see the [module documentation](self) for an
+/// example.
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct RnsScaler {
     from: Arc<RnsContext>,
@@ -70,9 +93,8 @@ pub struct RnsScaler {
 }
 
 impl RnsScaler {
-    /// Create a RNS scaler by numerator / denominator.
-    ///
-    /// Aborts if denominator is equal to 0.
+    /// Create a RNS scaler from the context `from` to the context `to` by the
+    /// scaling factor `numerator / denominator`.
     pub fn new(
         from: &Arc<RnsContext>,
         to: &Arc<RnsContext>,
@@ -168,6 +190,21 @@ impl RnsScaler {
         }
     }
 
+    /// Returns the context of the inputs of the scaler.
+    pub fn from(&self) -> &Arc<RnsContext> {
+        &self.from
+    }
+
+    /// Returns the context of the outputs of the scaler.
+    pub fn to(&self) -> &Arc<RnsContext> {
+        &self.to
+    }
+
+    /// Returns the scaling factor of the scaler.
+    pub fn scaling_factor(&self) -> &ScalingFactor {
+        &self.scaling_factor
+    }
+
     // Let's define gamma = round(numerator * input / denominator)
     // and theta_gamma such that theta_gamma = numerator * input / denominator -
     // gamma. This function projects gamma in the RNS context, and scales
@@ -222,23 +259,26 @@ impl RnsScaler {
         (projected, theta_lo, theta_hi, theta_sign)
     }
 
-    /// Output the RNS representation of the rests scaled by numerator *
-    /// denominator, and either rounded or floored.
+    /// Output the rests modulo the first `size` moduli of the context `to` of
+    /// the scaling of the integer whose rests in the context `from` are
+    /// `rests`.
     ///
-    /// Aborts if the number of rests is different than the number of moduli in
-    /// debug mode, or if the size is not in [1, ..., rests.len()].
+    /// Aborts if the number of rests is different than the number of moduli of
+    /// `from` in debug mode, or if the size is not in [1, ..., n] where `n` is the
+    /// number of moduli of `to`.
     pub fn scale_new(&self, rests: ArrayView1<u64>, size: usize) -> Vec<u64> {
         let mut out = vec![0; size];
         self.scale(rests, (&mut out).into(), 0);
         out
     }
 
-    /// Compute the RNS representation of the rests scaled by numerator *
-    /// denominator, and either rounded or floored, and store the result in
-    /// `out`.
+    /// Compute the scaling of the integer whose rests in the context `from`
+    /// are `rests`, and store its rests modulo the moduli of the context `to`
+    /// starting at index `starting_index` in `out`.
     ///
-    /// Aborts if the number of rests is different than the number of moduli in
-    /// debug mode, or if the size of out is not in [1, ..., rests.len()].
+    /// Aborts if the number of rests is different than the number of moduli of
+    /// `from` in debug mode, or if `out` is empty or extends beyond the moduli
+    /// of `to`.
     pub fn scale(
         &self,
         rests: ArrayView1<u64>,
@@ -364,7 +404,14 @@ mod tests {
         let q = Arc::new(RnsContext::new(&[4, 4611686018326724609, 1153])?);
 
         let scaler = RnsScaler::new(&q, &q, ScalingFactor::one());
-        assert_eq!(scaler.from, q);
+        assert_eq!(scaler.from(), &q);
+        assert_eq!(scaler.to(), &q);
+        assert!(scaler.scaling_factor().is_one());
+
+        let factor = ScalingFactor::new(&BigUint::from(3u64), &BigUint::from(1000u64));
+        assert_eq!(factor.numerator(), &BigUint::from(3u64));
+        assert_eq!(factor.denominator(), &BigUint::from(1000u64));
+        assert!(!factor.is_one());
 
         //assert!(
         //    catch_unwind(|| ScalingFactor::new(&BigUint::from(1u64), &BigUint::zero())).is_err()
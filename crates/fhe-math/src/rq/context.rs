@@ -1,4 +1,5 @@
 use alloc::{fmt::Debug, sync::Arc};
+use core::hash::{Hash, Hasher};
 use itertools::Itertools;
 use num_bigint::BigUint;
 extern crate alloc;
@@ -8,7 +9,84 @@ use alloc::string::ToString;
 use alloc::vec;
 use alloc::vec::Vec;
 
-use crate::{ntt::NttOperator, rns::RnsContext, zq::Modulus, Error, Result};
+use crate::{
+    ntt::{NttOperator, PrimeNttOperator},
+    rns::RnsContext,
+    zq::Modulus,
+    Error, Result,
+};
+use fhe_util::is_prime;
+
+/// NTT operator of a context, which depends on its cyclotomic ring.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ContextNtt {
+    /// Negacyclic NTT of the power-of-two cyclotomic ring.
+    PowerOfTwo(NttOperator),
+    /// NTT of a prime cyclotomic ring.
+    Prime(Box<PrimeNttOperator>),
+}
+
+impl ContextNtt {
+    /// Compute the forward NTT in place.
+    pub(crate) fn forward(&self, a: &mut [u64]) {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_ntt();
+        match self {
+            ContextNtt::PowerOfTwo(op) => op.forward(a),
+            ContextNtt::Prime(op) => op.forward(a),
+        }
+    }
+
+    /// Compute the backward NTT in place.
+    pub(crate) fn backward(&self, a: &mut [u64]) {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_ntt();
+        match self {
+            ContextNtt::PowerOfTwo(op) => op.backward(a),
+            ContextNtt::Prime(op) => op.backward(a),
+        }
+    }
+
+    /// Compute the forward NTT in place in variable time.
+    ///
+    /// # Safety
+    /// See [`NttOperator::forward_vt`].
+    pub(crate) unsafe fn forward_vt(&self, a_ptr: *mut u64) {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_ntt();
+        match self {
+            ContextNtt::PowerOfTwo(op) => op.forward_vt(a_ptr),
+            ContextNtt::Prime(op) => op.forward_vt(a_ptr),
+        }
+    }
+
+    /// Compute the forward NTT in place in variable time, with outputs that
+    /// may not be reduced.
+    ///
+    /// # Safety
+    /// See [`NttOperator::forward_vt`].
+    pub(crate) unsafe fn forward_vt_lazy(&self, a_ptr: *mut u64) {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_ntt();
+        match self {
+            ContextNtt::PowerOfTwo(op) => op.forward_vt_lazy(a_ptr),
+            ContextNtt::Prime(op) => op.forward_vt(a_ptr),
+        }
+    }
+
+    /// Compute the backward NTT in place in variable time.
+    ///
+    /// # Safety
+    /// See [`NttOperator::backward_vt`].
+    pub(crate) unsafe fn backward_vt(&self, a_ptr: *mut u64) {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_ntt();
+        match self {
+            ContextNtt::PowerOfTwo(op) => op.backward_vt(a_ptr),
+            ContextNtt::Prime(op) => op.backward_vt(a_ptr),
+        }
+    }
+}
 
 /// Struct that holds the context associated with elements in rq.
 #[derive(Default, Clone, PartialEq, Eq)]
@@ -16,8 +94,9 @@ pub struct Context {
     pub(crate) moduli: Box<[u64]>,
     pub(crate) q: Box<[Modulus]>,
     pub(crate) rns: Arc<RnsContext>,
-    pub(crate) ops: Box<[NttOperator]>,
+    pub(crate) ops: Box<[ContextNtt]>,
     pub(crate) degree: usize,
+    pub(crate) cyclotomic_index: usize,
     pub(crate) bitrev: Box<[usize]>,
     pub(crate) inv_last_qi_mod_qj: Box<[u64]>,
     pub(crate) inv_last_qi_mod_qj_shoup: Box<[u64]>,
@@ -40,6 +119,17 @@ impl Debug for Context {
     }
 }
 
+// Implement the hash manually; the other fields are derived from the moduli,
+// the degree and the cyclotomic index, so that the hash is consistent with the
+// equality and does not depend on the memory layout.
+impl Hash for Context {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.moduli.hash(state);
+        self.degree.hash(state);
+        self.cyclotomic_index.hash(state);
+    }
+}
+
 impl Context {
     /// Creates a context from a list of moduli and a polynomial degree.
     ///
@@ -51,51 +141,107 @@ impl Context {
                 "The degree is not a power of two larger or equal to 8".to_string(),
             ))
         } else {
-            let mut q = Vec::with_capacity(moduli.len());
-            let rns = Arc::new(RnsContext::new(moduli)?);
-            let mut ops = Vec::with_capacity(moduli.len());
-            for modulus in moduli {
-                let qi = Modulus::new(*modulus)?;
-                if let Some(op) = NttOperator::new(&qi, degree) {
-                    q.push(qi);
-                    ops.push(op);
-                } else {
-                    return Err(Error::Default(
-                        "Impossible to construct a Ntt operator".to_string(),
-                    ));
-                }
-            }
-            let bitrev = (0..degree)
-                .map(|j| j.reverse_bits() >> (degree.leading_zeros() + 1))
-                .collect_vec();
-
-            let mut inv_last_qi_mod_qj = vec![];
-            let mut inv_last_qi_mod_qj_shoup = vec![];
-            let q_last = moduli.last().unwrap();
-            for qi in &q[..q.len() - 1] {
-                let inv = qi.inv(qi.reduce(*q_last)).unwrap();
-                inv_last_qi_mod_qj.push(inv);
-                inv_last_qi_mod_qj_shoup.push(qi.shoup(inv));
-            }
+            Self::new_cyclotomic(moduli, 2 * degree)
+        }
+    }
+
+    /// Creates a context from a list of moduli for the prime cyclotomic ring
+    /// `ZZ_q[x] / (1 + x + ... + x^(m - 1))` of degree `m - 1`.
+    ///
+    /// Returns an error if `m` is not an odd prime, or if the moduli are not
+    /// primes less than 62 bits which support the NTT of this ring (see
+    /// [`supports_prime_ntt`](crate::ntt::supports_prime_ntt)).
+    pub fn new_prime_cyclotomic(moduli: &[u64], m: usize) -> Result<Self> {
+        if m < 3 || !is_prime(m as u64) {
+            Err(Error::Default(
+                "The cyclotomic index is not an odd prime".to_string(),
+            ))
+        } else {
+            Self::new_cyclotomic(moduli, m)
+        }
+    }
+
+    /// Creates a context for the cyclotomic ring of index `m`, which must be a
+    /// power of two larger or equal to 16 or an odd prime.
+    fn new_cyclotomic(moduli: &[u64], m: usize) -> Result<Self> {
+        let is_prime_cyclotomic = m % 2 == 1;
+        let degree = if is_prime_cyclotomic { m - 1 } else { m / 2 };
 
-            let next_context = if moduli.len() >= 2 {
-                Some(Arc::new(Context::new(&moduli[..moduli.len() - 1], degree)?))
+        let mut q = Vec::with_capacity(moduli.len());
+        let rns = Arc::new(RnsContext::new(moduli)?);
+        let mut ops = Vec::with_capacity(moduli.len());
+        for modulus in moduli {
+            let qi = Modulus::new(*modulus)?;
+            let op = if is_prime_cyclotomic {
+                PrimeNttOperator::new(&qi, m).map(|op| ContextNtt::Prime(Box::new(op)))
             } else {
-                None
+                NttOperator::new(&qi, degree).map(ContextNtt::PowerOfTwo)
             };
+            if let Some(op) = op {
+                q.push(qi);
+                ops.push(op);
+            } else {
+                return Err(Error::Default(
+                    "Impossible to construct a Ntt operator".to_string(),
+                ));
+            }
+        }
+        // The substitutions are only supported in power-of-two cyclotomic rings.
+        let bitrev = if is_prime_cyclotomic {
+            vec![]
+        } else {
+            (0..degree)
+                .map(|j| j.reverse_bits() >> (degree.leading_zeros() + 1))
+                .collect_vec()
+        };
 
-            Ok(Self {
-                moduli: moduli.to_owned().into_boxed_slice(),
-                q: q.into_boxed_slice(),
-                rns,
-                ops: ops.into_boxed_slice(),
-                degree,
-                bitrev: bitrev.into_boxed_slice(),
-                inv_last_qi_mod_qj: inv_last_qi_mod_qj.into_boxed_slice(),
-                inv_last_qi_mod_qj_shoup: inv_last_qi_mod_qj_shoup.into_boxed_slice(),
-                next_context,
-            })
+        let mut inv_last_qi_mod_qj = vec![];
+        let mut inv_last_qi_mod_qj_shoup = vec![];
+        let q_last = moduli.last().unwrap();
+        for qi in &q[..q.len() - 1] {
+            let inv = qi.inv(qi.reduce(*q_last)).unwrap();
+            inv_last_qi_mod_qj.push(inv);
+            inv_last_qi_mod_qj_shoup.push(qi.shoup(inv));
         }
+
+        let next_context = if moduli.len() >= 2 {
+            Some(Arc::new(Context::new_cyclotomic(
+                &moduli[..moduli.len() - 1],
+                m,
+            )?))
+        } else {
+            None
+        };
+
+        Ok(Self {
+            moduli: moduli.to_owned().into_boxed_slice(),
+            q: q.into_boxed_slice(),
+            rns,
+            ops: ops.into_boxed_slice(),
+            degree,
+            cyclotomic_index: m,
+            bitrev: bitrev.into_boxed_slice(),
+            inv_last_qi_mod_qj: inv_last_qi_mod_qj.into_boxed_slice(),
+            inv_last_qi_mod_qj_shoup: inv_last_qi_mod_qj_shoup.into_boxed_slice(),
+            next_context,
+        })
+    }
+
+    /// Creates a context for a prime cyclotomic ring in an `Arc`.
+    pub fn new_prime_cyclotomic_arc(moduli: &[u64], m: usize) -> Result<Arc<Self>> {
+        Self::new_prime_cyclotomic(moduli, m).map(Arc::new)
+    }
+
+    /// Returns the index `m` of the cyclotomic ring of the context, i.e.
+    /// `2 * degree` for a power-of-two cyclotomic ring, and `degree + 1` for a
+    /// prime cyclotomic ring.
+    pub fn cyclotomic_index(&self) -> usize {
+        self.cyclotomic_index
+    }
+
+    /// Returns whether the context is a prime cyclotomic ring.
+    pub fn is_prime_cyclotomic(&self) -> bool {
+        self.cyclotomic_index % 2 == 1
     }
 
     /// Creates a context in an `Arc`.
@@ -165,8 +311,11 @@ mod tests {
     use crate::Error;
     use alloc::sync::Arc;
 
-    use crate::ntt::supports_ntt;
-    use crate::rq::Context;
+    use crate::ntt::{prime_ntt_order, supports_ntt};
+    use crate::rq::{Context, Poly, Representation, SubstitutionExponent};
+    use crate::zq::primes::generate_prime;
+    use alloc::vec;
+    use rand::thread_rng;
 
     const MODULI: &[u64; 5] = &[
         1153,
@@ -217,6 +366,30 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn hash() -> Result<(), Error> {
+        extern crate std;
+        use core::hash::{Hash, Hasher};
+        use std::collections::hash_map::DefaultHasher;
+
+        let hash = |ctx: &Context| {
+            let mut hasher = DefaultHasher::new();
+            ctx.hash(&mut hasher);
+            hasher.finish()
+        };
+        let context = Context::new(MODULI, 16)?;
+        assert_eq!(hash(&context), hash(&Context::new(MODULI, 16)?));
+        assert_eq!(hash(&context), hash(&context.clone()));
+        assert_ne!(hash(&context), hash(&Context::new(&MODULI[1..], 16)?));
+        assert_ne!(hash(&context), hash(&Context::new(MODULI, 8)?));
+        assert_eq!(
+            hash(context.next_context.as_ref().unwrap()),
+            hash(&Context::new(&MODULI[..MODULI.len() - 1], 16)?)
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn niterations_to() -> Result<(), Error> {
         // A context should have a children pointing to a context with one less modulus.
@@ -242,4 +415,52 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn prime_cyclotomic() -> Result<(), Error> {
+        let m = 17;
+        let p1 = generate_prime(62, prime_ntt_order(m), 1 << 62).unwrap();
+        let p2 = generate_prime(50, prime_ntt_order(m), 1 << 50).unwrap();
+        let ctx = Arc::new(Context::new_prime_cyclotomic(&[p1, p2], m)?);
+        assert_eq!(ctx.degree, 16);
+        assert_eq!(ctx.cyclotomic_index(), 17);
+        assert!(ctx.is_prime_cyclotomic());
+        assert!(!Context::new(&[p1], 16)?.is_prime_cyclotomic());
+        assert_eq!(
+            ctx.next_context,
+            Some(Arc::new(Context::new_prime_cyclotomic(&[p1], m)?))
+        );
+
+        assert!(Context::new_prime_cyclotomic(&[p1], 15).is_err());
+        assert!(Context::new_prime_cyclotomic(&[p1], 2).is_err());
+        assert!(Context::new_prime_cyclotomic(&[MODULI[1]], m).is_err());
+
+        // The product in Ntt representation matches the product modulo
+        // 1 + x + ... + x^16.
+        let mut rng = thread_rng();
+        let a = Poly::random(&ctx, Representation::PowerBasis, &mut rng);
+        let b = Poly::random(&ctx, Representation::PowerBasis, &mut rng);
+        let mut c = &a.to_representation(Representation::Ntt).into_owned()
+            * &b.to_representation(Representation::Ntt).into_owned();
+        c.change_representation(Representation::PowerBasis);
+        for (i, qi) in ctx.q.iter().enumerate() {
+            let mut expected = vec![0u64; m];
+            for j in 0..m - 1 {
+                for k in 0..m - 1 {
+                    let l = (j + k) % m;
+                    let product = qi.mul(a.coefficients()[[i, j]], b.coefficients()[[i, k]]);
+                    expected[l] = qi.add(expected[l], product);
+                }
+            }
+            let top = expected[m - 1];
+            expected.truncate(m - 1);
+            expected.iter_mut().for_each(|e| *e = qi.sub(*e, top));
+            assert_eq!(c.coefficients().row(i).to_vec(), expected);
+        }
+
+        assert!(SubstitutionExponent::new(&ctx, 3).is_err());
+        let mut a = a;
+        assert!(a.multiply_inverse_power_of_x(1).is_err());
+        Ok(())
+    }
 }
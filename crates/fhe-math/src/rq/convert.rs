@@ -5,7 +5,7 @@ use crate::{
     proto::rq::{Representation as RepresentationProto, Rq},
     Error, Result,
 };
-use itertools::{izip, Itertools};
+use itertools::izip;
 use ndarray::{Array2, ArrayView, Axis};
 use num_bigint::BigUint;
 extern crate alloc;
@@ -70,7 +70,7 @@ impl TryConvertFrom<Vec<u64>> for Poly {
                         representation: repr.unwrap(),
                         allow_variable_time_computations: variable_time,
                         coefficients,
-                        coefficients_shoup: None,
+                        coefficients_shoup: Default::default(),
                         has_lazy_coefficients: false,
                     })
                 } else {
@@ -81,16 +81,14 @@ impl TryConvertFrom<Vec<u64>> for Poly {
             }
             Some(Representation::NttShoup) => {
                 if let Ok(coefficients) = Array2::from_shape_vec((ctx.q.len(), ctx.degree), v) {
-                    let mut p = Self {
+                    Ok(Self {
                         ctx: ctx.clone(),
                         representation: repr.unwrap(),
                         allow_variable_time_computations: variable_time,
                         coefficients,
-                        coefficients_shoup: None,
+                        coefficients_shoup: Default::default(),
                         has_lazy_coefficients: false,
-                    };
-                    p.compute_coefficients_shoup();
-                    Ok(p)
+                    })
                 } else {
                     Err(Error::Default(
                         "In NttShoup representation, all coefficients must be specified"
@@ -107,7 +105,7 @@ impl TryConvertFrom<Vec<u64>> for Poly {
                         representation: repr.unwrap(),
                         allow_variable_time_computations: variable_time,
                         coefficients,
-                        coefficients_shoup: None,
+                        coefficients_shoup: Default::default(),
                         has_lazy_coefficients: false,
                     })
                 } else if v.len() <= ctx.degree {
@@ -224,18 +222,14 @@ impl TryConvertFrom<Array2<u64>> for Poly {
                 "The array of coefficient does not have the correct shape".to_string(),
             ))
         } else if let Some(repr) = representation.into() {
-            let mut p = Self {
+            Ok(Self {
                 ctx: ctx.clone(),
                 representation: repr,
                 allow_variable_time_computations: variable_time,
                 coefficients: a,
-                coefficients_shoup: None,
+                coefficients_shoup: Default::default(),
                 has_lazy_coefficients: false,
-            };
-            if p.representation == Representation::NttShoup {
-                p.compute_coefficients_shoup()
-            }
-            Ok(p)
+            })
         } else {
             Err(Error::Default("When converting from a 2-dimensional array, the representation needs to be specified".to_string()))
         }
@@ -329,23 +323,14 @@ impl<'a> TryConvertFrom<&'a [BigUint]> for Poly {
                 c.assign(&ArrayView::from(&ctx.rns.project(vi)));
             });
 
-            let mut p = Self {
+            Ok(Self {
                 ctx: ctx.clone(),
                 representation: repr.unwrap(),
                 allow_variable_time_computations: variable_time,
                 coefficients,
-                coefficients_shoup: None,
+                coefficients_shoup: Default::default(),
                 has_lazy_coefficients: false,
-            };
-
-            match p.representation {
-                Representation::PowerBasis => Ok(p),
-                Representation::Ntt => Ok(p),
-                Representation::NttShoup => {
-                    p.compute_coefficients_shoup();
-                    Ok(p)
-                }
-            }
+            })
         } else {
             Err(Error::Default(
                 "When converting from a vector, the representation needs to be specified"
@@ -419,9 +404,7 @@ impl From<&Poly> for Vec<u64> {
 
 impl From<&Poly> for Vec<BigUint> {
     fn from(p: &Poly) -> Self {
-        izip!(p.coefficients.axis_iter(Axis(1)))
-            .map(|c| p.ctx.rns.lift(c))
-            .collect_vec()
+        p.ctx.rns.lift_vec(p.coefficients.view())
     }
 }
 
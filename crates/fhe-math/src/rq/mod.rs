@@ -9,19 +9,25 @@ mod ops;
 mod serialize;
 
 pub mod scaler;
+pub mod small;
 pub mod switcher;
 pub mod traits;
 use self::{scaler::Scaler, switcher::Switcher, traits::TryConvertFrom};
+use crate::unchecked::{get, set};
 use crate::{Error, Result};
 pub use context::Context;
-use fhe_util::sample_vec_cbd;
+use fhe_util::{sample_vec_cbd, sample_vec_gaussian, sample_vec_ternary};
 use itertools::{izip, Itertools};
-use ndarray::{s, Array2, ArrayView2, Axis};
+use ndarray::{s, Array2, ArrayView1, ArrayView2, ArrayViewMut1, Axis};
+use num_bigint::{BigInt, BigUint};
+use once_cell::race::OnceBox;
 pub use ops::dot_product;
 use rand::{CryptoRng, RngCore, SeedableRng};
 use rand_chacha::ChaCha8Rng;
 use sha2::{Digest, Sha256};
 extern crate alloc;
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
 use alloc::string::ToString;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
@@ -37,10 +43,46 @@ pub enum Representation {
     /// This is the NTT representation of the PowerBasis representation.
     Ntt,
     /// This is a "Shoup" representation of the Ntt representation used for
-    /// faster multiplication.
+    /// faster multiplication. The Shoup coefficients are computed on demand,
+    /// when the polynomial is first used as a multiplicand, and cached.
     NttShoup,
 }
 
+/// Distributions of the coefficients of small polynomials.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Distribution {
+    /// Centered binomial distribution of the given variance, which must belong
+    /// to [1, ..., 16].
+    Cbd(usize),
+    /// Ternary distribution with exactly the given number of non-zero
+    /// coefficients, each equal to -1 or 1.
+    Ternary(usize),
+    /// Discrete Gaussian distribution of the given standard deviation, which
+    /// must belong to (0, 1024], sampled in constant time.
+    Gaussian(f64),
+}
+
+impl Distribution {
+    /// Sample a vector of `size` independent values from the distribution.
+    ///
+    /// Returns an error if the parameter of the distribution is invalid.
+    pub fn sample_vec<T: RngCore>(&self, size: usize, rng: &mut T) -> Result<Vec<i64>> {
+        match *self {
+            Distribution::Cbd(variance) => {
+                if !(1..=16).contains(&variance) {
+                    return Err(Error::Default(
+                        "The variance should be an integer between 1 and 16".to_string(),
+                    ));
+                }
+                sample_vec_cbd(size, variance, rng)
+            }
+            Distribution::Ternary(hamming_weight) => sample_vec_ternary(size, hamming_weight, rng),
+            Distribution::Gaussian(sigma) => sample_vec_gaussian(size, sigma, rng),
+        }
+        .map_err(|e| Error::Default(e.to_string()))
+    }
+}
+
 /// An exponent for a substitution.
 #[derive(Debug, PartialEq, Eq)]
 pub struct SubstitutionExponent {
@@ -53,8 +95,14 @@ pub struct SubstitutionExponent {
 
 impl SubstitutionExponent {
     /// Creates a substitution element from an exponent.
-    /// Returns an error if the exponent is even modulo 2 * degree.
+    /// Returns an error if the exponent is even modulo 2 * degree, or if the
+    /// context is a prime cyclotomic ring.
     pub fn new(ctx: &Arc<Context>, exponent: usize) -> Result<Self> {
+        if ctx.is_prime_cyclotomic() {
+            return Err(Error::Default(
+                "Substitutions are not supported in prime cyclotomic rings".to_string(),
+            ));
+        }
         let exponent = exponent % (2 * ctx.degree);
         if exponent & 1 == 0 {
             return Err(Error::Default(
@@ -78,6 +126,79 @@ impl SubstitutionExponent {
     }
 }
 
+/// Shoup coefficients, zeroized on drop.
+struct ShoupCoefficients(Array2<u64>);
+
+impl Drop for ShoupCoefficients {
+    fn drop(&mut self) {
+        if let Some(coeffs) = self.0.as_slice_mut() {
+            coeffs.zeroize()
+        }
+    }
+}
+
+/// Shoup coefficients of a polynomial in NttShoup representation, computed
+/// on demand and cached. The polynomials which are never multiplied, such as
+/// the unused key switching keys of a server, then only hold their
+/// coefficients, i.e. half of the memory of the precomputed representation.
+#[derive(Default)]
+struct LazyShoup(OnceBox<ShoupCoefficients>);
+
+impl LazyShoup {
+    fn new(coefficients_shoup: Array2<u64>) -> Self {
+        let lazy = Self::default();
+        let _ = lazy.0.set(Box::new(ShoupCoefficients(coefficients_shoup)));
+        lazy
+    }
+
+    /// Returns the Shoup coefficients if they have been computed.
+    fn get(&self) -> Option<&Array2<u64>> {
+        self.0.get().map(|c| &c.0)
+    }
+
+    /// Returns the Shoup coefficients of `coefficients`, computing them if
+    /// needed.
+    fn get_or_compute(&self, coefficients: &Array2<u64>, ctx: &Context) -> &Array2<u64> {
+        &self
+            .0
+            .get_or_init(|| {
+                let mut coefficients_shoup = Array2::zeros((ctx.q.len(), ctx.degree));
+                izip!(
+                    coefficients_shoup.outer_iter_mut(),
+                    coefficients.outer_iter(),
+                    ctx.q.iter()
+                )
+                .for_each(|(mut v_shoup, v, qi)| {
+                    v_shoup.zip_mut_with(&v, |s, c| *s = qi.shoup(*c))
+                });
+                Box::new(ShoupCoefficients(coefficients_shoup))
+            })
+            .0
+    }
+}
+
+impl Clone for LazyShoup {
+    fn clone(&self) -> Self {
+        self.get()
+            .map_or_else(Self::default, |c| Self::new(c.clone()))
+    }
+}
+
+// The Shoup coefficients are derived from the coefficients.
+impl PartialEq for LazyShoup {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for LazyShoup {}
+
+impl core::fmt::Debug for LazyShoup {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("LazyShoup").field(&self.get()).finish()
+    }
+}
+
 /// Struct that holds a polynomial for a specific context.
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct Poly {
@@ -86,7 +207,7 @@ pub struct Poly {
     has_lazy_coefficients: bool,
     allow_variable_time_computations: bool,
     coefficients: Array2<u64>,
-    coefficients_shoup: Option<Array2<u64>>,
+    coefficients_shoup: LazyShoup,
 }
 
 // Implements zeroization of polynomials
@@ -95,7 +216,7 @@ impl Zeroize for Poly {
         if let Some(coeffs) = self.coefficients.as_slice_mut() {
             coeffs.zeroize()
         }
-        self.zeroize_shoup()
+        self.reset_coefficients_shoup()
     }
 }
 
@@ -120,11 +241,7 @@ impl Poly {
             allow_variable_time_computations: false,
             has_lazy_coefficients: false,
             coefficients: Array2::zeros((ctx.q.len(), ctx.degree)),
-            coefficients_shoup: if representation == Representation::NttShoup {
-                Some(Array2::zeros((ctx.q.len(), ctx.degree)))
-            } else {
-                None
-            },
+            coefficients_shoup: LazyShoup::default(),
         }
     }
 
@@ -148,17 +265,45 @@ impl Poly {
         &self.representation
     }
 
-    /// Zeroize the shoup coefficients
-    fn zeroize_shoup(&mut self) {
-        if let Some(coeffs_shoup) = self
-            .coefficients_shoup
-            .as_mut()
-            .and_then(|f| f.as_slice_mut())
-        {
-            coeffs_shoup.zeroize()
+    /// Discard the Shoup coefficients, which are zeroized, so that they are
+    /// computed again from the current coefficients when needed.
+    fn reset_coefficients_shoup(&mut self) {
+        self.coefficients_shoup = LazyShoup::default()
+    }
+
+    /// Returns the Shoup coefficients of a polynomial in NttShoup
+    /// representation, computing them if needed.
+    fn coefficients_shoup(&self) -> &Array2<u64> {
+        debug_assert_eq!(self.representation, Representation::NttShoup);
+        self.coefficients_shoup
+            .get_or_compute(&self.coefficients, &self.ctx)
+    }
+
+    /// Compute the Shoup coefficients of a polynomial in NttShoup
+    /// representation now rather than at its first multiplication, e.g. to
+    /// keep this cost out of a latency-sensitive computation. This does
+    /// nothing in the other representations.
+    pub fn precompute_shoup_coefficients(&self) {
+        if self.representation == Representation::NttShoup {
+            self.coefficients_shoup();
         }
     }
 
+    /// Returns the size in bytes of the coefficients of the polynomial, i.e.
+    /// 8 bytes per coefficient modulo each modulus of its context.
+    pub fn coefficients_heap_size(&self) -> usize {
+        self.coefficients.len() * core::mem::size_of::<u64>()
+    }
+
+    /// Returns the size in bytes of the Shoup coefficients of the polynomial,
+    /// which is 0 until they are computed, and the same as
+    /// [`Poly::coefficients_heap_size`] afterwards.
+    pub fn shoup_heap_size(&self) -> usize {
+        self.coefficients_shoup
+            .get()
+            .map_or(0, |c| c.len() * core::mem::size_of::<u64>())
+    }
+
     /// Change the representation of the underlying polynomial.
     pub fn change_representation(&mut self, to: Representation) {
         match self.representation {
@@ -167,7 +312,7 @@ impl Poly {
                     Representation::Ntt => self.ntt_forward(),
                     Representation::NttShoup => {
                         self.ntt_forward();
-                        self.compute_coefficients_shoup();
+                        self.reset_coefficients_shoup();
                     }
                     Representation::PowerBasis => {} // no-op
                 }
@@ -175,7 +320,7 @@ impl Poly {
             Representation::Ntt => {
                 match to {
                     Representation::PowerBasis => self.ntt_backward(),
-                    Representation::NttShoup => self.compute_coefficients_shoup(),
+                    Representation::NttShoup => self.reset_coefficients_shoup(),
                     Representation::Ntt => {} // no-op
                 }
             }
@@ -183,8 +328,7 @@ impl Poly {
                 if to != Representation::NttShoup {
                     // We are not sure whether this polynomial was sensitive or not,
                     // so for security, we zeroize the Shoup coefficients.
-                    self.zeroize_shoup();
-                    self.coefficients_shoup = None
+                    self.reset_coefficients_shoup()
                 }
                 match to {
                     Representation::PowerBasis => self.ntt_backward(),
@@ -197,21 +341,16 @@ impl Poly {
         self.representation = to;
     }
 
-    /// Compute the Shoup representation of the coefficients.
-    fn compute_coefficients_shoup(&mut self) {
-        let mut coefficients_shoup = Array2::zeros((self.ctx.q.len(), self.ctx.degree));
-        izip!(
-            coefficients_shoup.outer_iter_mut(),
-            self.coefficients.outer_iter(),
-            self.ctx.q.iter()
-        )
-        .for_each(|(mut v_shoup, v, qi)| {
-            v_shoup
-                .as_slice_mut()
-                .unwrap()
-                .copy_from_slice(&qi.shoup_vec(v.as_slice().unwrap()))
-        });
-        self.coefficients_shoup = Some(coefficients_shoup)
+    /// Returns the polynomial in the representation `to`, borrowing it when it
+    /// is already in this representation and converting a copy otherwise.
+    pub fn to_representation(&self, to: Representation) -> Cow<'_, Poly> {
+        if self.representation == to {
+            Cow::Borrowed(self)
+        } else {
+            let mut p = self.clone();
+            p.change_representation(to);
+            Cow::Owned(p)
+        }
     }
 
     /// Override the internal representation to a given representation.
@@ -220,17 +359,11 @@ impl Poly {
     ///
     /// Prefer the `change_representation` function to safely modify the
     /// polynomial representation. If the `to` representation is NttShoup, the
-    /// coefficients are still computed correctly to avoid being in an unstable
-    /// state. If we override a polynomial with Shoup coefficients, we zeroize
-    /// them.
+    /// Shoup coefficients are computed from the current coefficients when
+    /// needed, to avoid being in an unstable state. If we override a
+    /// polynomial with Shoup coefficients, we zeroize them.
     pub unsafe fn override_representation(&mut self, to: Representation) {
-        if self.coefficients_shoup.is_some() {
-            self.zeroize_shoup();
-            self.coefficients_shoup = None
-        }
-        if to == Representation::NttShoup {
-            self.compute_coefficients_shoup()
-        }
+        self.reset_coefficients_shoup();
         self.representation = to;
     }
 
@@ -246,72 +379,119 @@ impl Poly {
                 .unwrap()
                 .copy_from_slice(&qi.random_vec(ctx.degree, rng))
         });
-        if p.representation == Representation::NttShoup {
-            p.compute_coefficients_shoup()
-        }
         p
     }
 
+    /// Hash the seed into a ChaCha8Rng seed, and returns the seeded generator.
+    fn seeded_prng(seed: <ChaCha8Rng as SeedableRng>::Seed) -> ChaCha8Rng {
+        let mut hasher = Sha256::new();
+        hasher.update(seed);
+        ChaCha8Rng::from_seed(<ChaCha8Rng as SeedableRng>::Seed::from(hasher.finalize()))
+    }
+
     /// Generate a random polynomial deterministically from a seed.
     pub fn random_from_seed(
         ctx: &Arc<Context>,
         representation: Representation,
         seed: <ChaCha8Rng as SeedableRng>::Seed,
     ) -> Self {
-        // Let's hash the seed into a ChaCha8Rng seed.
-        let mut hasher = Sha256::new();
-        hasher.update(seed);
-        let mut prng =
-            ChaCha8Rng::from_seed(<ChaCha8Rng as SeedableRng>::Seed::from(hasher.finalize()));
+        let mut prng = Self::seeded_prng(seed);
         let mut p = Poly::zero(ctx, representation);
         izip!(p.coefficients.outer_iter_mut(), ctx.q.iter()).for_each(|(mut v, qi)| {
             v.as_slice_mut()
                 .unwrap()
                 .copy_from_slice(&qi.random_vec(ctx.degree, &mut prng))
         });
-        if p.representation == Representation::NttShoup {
-            p.compute_coefficients_shoup()
-        }
         p
     }
 
-    /// Generate a small polynomial and convert into the specified
-    /// representation.
+    /// Generate a random polynomial deterministically from a seed, reducing
+    /// 128 random bits per coefficient.
+    ///
+    /// The seed is hashed with SHA-256 into the seed of a ChaCha8 generator,
+    /// and the coefficients modulo each qi are sampled in order using
+    /// [`Modulus::random_vec_wide`](crate::zq::Modulus::random_vec_wide).
+    /// Each coefficient is within statistical distance `qi / 2^128` of the
+    /// uniform distribution, and is derived from a fixed position of the
+    /// random stream, so that all parties of a protocol can verifiably derive
+    /// the same polynomial from a shared seed.
+    pub fn random_from_seed_wide(
+        ctx: &Arc<Context>,
+        representation: Representation,
+        seed: <ChaCha8Rng as SeedableRng>::Seed,
+    ) -> Self {
+        let mut prng = Self::seeded_prng(seed);
+        let mut p = Poly::zero(ctx, representation);
+        izip!(p.coefficients.outer_iter_mut(), ctx.q.iter()).for_each(|(mut v, qi)| {
+            v.as_slice_mut()
+                .unwrap()
+                .copy_from_slice(&qi.random_vec_wide(ctx.degree, &mut prng))
+        });
+        p
+    }
+
+    /// Generate a small polynomial with coefficients sampled from
+    /// `distribution`, and convert it into the specified representation.
     ///
-    /// Returns an error if the variance does not belong to [1, ..., 16].
+    /// The polynomial only depends on the state of `rng`, so that seeding it
+    /// makes the sampling reproducible. Returns an error if the parameter of
+    /// the distribution is invalid.
     pub fn small<T: RngCore>(
         ctx: &Arc<Context>,
         representation: Representation,
-        variance: usize,
+        distribution: Distribution,
         rng: &mut T,
     ) -> Result<Self> {
-        if !(1..=16).contains(&variance) {
-            Err(Error::Default(
-                "The variance should be an integer between 1 and 16".to_string(),
-            ))
-        } else {
-            let coeffs = Zeroizing::new(
-                sample_vec_cbd(ctx.degree, variance, rng)
-                    .map_err(|e| Error::Default(e.to_string()))?,
-            );
-            let mut p = Poly::try_convert_from(
-                coeffs.as_ref() as &[i64],
-                ctx,
-                false,
-                Representation::PowerBasis,
-            )?;
-            if representation != Representation::PowerBasis {
-                p.change_representation(representation);
-            }
-            Ok(p)
+        let coeffs = Zeroizing::new(distribution.sample_vec(ctx.degree, rng)?);
+        let mut p = Poly::try_convert_from(
+            coeffs.as_ref() as &[i64],
+            ctx,
+            false,
+            Representation::PowerBasis,
+        )?;
+        if representation != Representation::PowerBasis {
+            p.change_representation(representation);
         }
+        Ok(p)
     }
 
     /// Access the polynomial coefficients in RNS representation.
-    pub fn coefficients(&self) -> ArrayView2<u64> {
+    pub fn coefficients(&self) -> ArrayView2<'_, u64> {
         self.coefficients.view()
     }
 
+    /// Lift the coefficients of a polynomial in PowerBasis representation
+    /// into BigUints in the interval `[0, q)`, where `q` is the modulus of the
+    /// context, using [`RnsContext::lift_vec`](crate::rns::RnsContext::lift_vec).
+    pub fn lift_poly(&self) -> Result<Vec<BigUint>> {
+        self.check_power_basis()?;
+        Ok(self.ctx.rns.lift_vec(self.coefficients.view()))
+    }
+
+    /// Lift the coefficients of a polynomial in PowerBasis representation
+    /// into their centered representatives, i.e. the integers in the interval
+    /// `(-q/2, q/2]` where `q` is the modulus of the context.
+    pub fn lift_centered(&self) -> Result<Vec<BigInt>> {
+        self.check_power_basis()?;
+        Ok(self.ctx.rns.lift_vec_centered(self.coefficients.view()))
+    }
+
+    /// Returns the centered representatives of the coefficients of a
+    /// polynomial in PowerBasis representation; see [`Poly::lift_centered`].
+    pub fn to_signed_coefficients(&self) -> Result<Vec<BigInt>> {
+        self.lift_centered()
+    }
+
+    fn check_power_basis(&self) -> Result<()> {
+        if self.representation != Representation::PowerBasis {
+            return Err(Error::IncorrectRepresentation(
+                self.representation.clone(),
+                Representation::PowerBasis,
+            ));
+        }
+        Ok(())
+    }
+
     /// Computes the forward Ntt on the coefficients
     fn ntt_forward(&mut self) {
         if self.allow_variable_time_computations {
@@ -338,7 +518,15 @@ impl Poly {
     /// In PowerBasis representation, i can be any integer that is not a
     /// multiple of 2 * degree. In Ntt and NttShoup representation, i can be any
     /// odd integer that is not a multiple of 2 * degree.
+    ///
+    /// Returns an error if the exponent was created for a ring of a different
+    /// degree.
     pub fn substitute(&self, i: &SubstitutionExponent) -> Result<Poly> {
+        if i.ctx.degree != self.ctx.degree {
+            return Err(Error::Default(
+                "The substitution exponent was created for a different degree".to_string(),
+            ));
+        }
         let mut q = Poly::zero(&self.ctx, self.representation.clone());
         if self.allow_variable_time_computations {
             unsafe { q.allow_variable_time_computations() }
@@ -349,31 +537,21 @@ impl Poly {
                     q.coefficients.outer_iter_mut(),
                     self.coefficients.outer_iter()
                 )
-                .for_each(|(mut q_row, p_row)| {
-                    for (j, k) in izip!(self.ctx.bitrev.iter(), i.power_bitrev.iter()) {
-                        q_row[*j] = p_row[*k]
-                    }
-                });
+                .for_each(|(mut q_row, p_row)| self.permute_ntt(&mut q_row, &p_row, i));
             }
             Representation::NttShoup => {
                 izip!(
                     q.coefficients.outer_iter_mut(),
                     self.coefficients.outer_iter()
                 )
-                .for_each(|(mut q_row, p_row)| {
-                    for (j, k) in izip!(self.ctx.bitrev.iter(), i.power_bitrev.iter()) {
-                        q_row[*j] = p_row[*k]
-                    }
-                });
-                izip!(
-                    q.coefficients_shoup.as_mut().unwrap().outer_iter_mut(),
-                    self.coefficients_shoup.as_ref().unwrap().outer_iter()
-                )
-                .for_each(|(mut q_row, p_row)| {
-                    for (j, k) in izip!(self.ctx.bitrev.iter(), i.power_bitrev.iter()) {
-                        q_row[*j] = p_row[*k]
-                    }
-                });
+                .for_each(|(mut q_row, p_row)| self.permute_ntt(&mut q_row, &p_row, i));
+                // Permute the Shoup coefficients if they have been computed.
+                if let Some(coefficients_shoup) = self.coefficients_shoup.get() {
+                    let mut q_shoup = Array2::zeros((self.ctx.q.len(), self.ctx.degree));
+                    izip!(q_shoup.outer_iter_mut(), coefficients_shoup.outer_iter())
+                        .for_each(|(mut q_row, p_row)| self.permute_ntt(&mut q_row, &p_row, i));
+                    q.coefficients_shoup = LazyShoup::new(q_shoup);
+                }
             }
             Representation::PowerBasis => {
                 let mut power = 0usize;
@@ -399,6 +577,24 @@ impl Poly {
         Ok(q)
     }
 
+    /// Permute a row of coefficients in Ntt representation for the
+    /// substitution of x by x^i.
+    fn permute_ntt(
+        &self,
+        q_row: &mut ArrayViewMut1<u64>,
+        p_row: &ArrayView1<u64>,
+        i: &SubstitutionExponent,
+    ) {
+        let q_row = q_row.as_slice_mut().unwrap();
+        let p_row = p_row.as_slice().unwrap();
+        for (j, k) in izip!(self.ctx.bitrev.iter(), i.power_bitrev.iter()) {
+            // Safety: the indices are permutations of 0..degree, as the degree
+            // of the exponent is checked in `substitute`, and the rows have
+            // degree elements.
+            unsafe { set(q_row, *j, get(p_row, *k)) }
+        }
+    }
+
     /// Create a polynomial which can only be multiplied by a polynomial in
     /// NttShoup representation. All other operations may panic.
     ///
@@ -424,7 +620,7 @@ impl Poly {
             representation: Representation::Ntt,
             allow_variable_time_computations: true,
             coefficients,
-            coefficients_shoup: None,
+            coefficients_shoup: LazyShoup::default(),
             has_lazy_coefficients: true,
         }
     }
@@ -551,7 +747,15 @@ impl Poly {
     }
 
     /// Multiplies a polynomial in PowerBasis representation by x^(-power).
+    ///
+    /// Returns an error if the context is a prime cyclotomic ring.
     pub fn multiply_inverse_power_of_x(&mut self, power: usize) -> Result<()> {
+        if self.ctx.is_prime_cyclotomic() {
+            return Err(Error::Default(
+                "Multiplication by x^(-power) is not supported in prime cyclotomic rings"
+                    .to_string(),
+            ));
+        }
         if self.representation != Representation::PowerBasis {
             return Err(Error::IncorrectRepresentation(
                 self.representation.clone(),
@@ -568,12 +772,15 @@ impl Poly {
             self.ctx.q.iter()
         )
         .for_each(|(mut coeffs, orig_coeffs, qi)| {
-            for k in 0..self.ctx.degree {
+            let coeffs = coeffs.as_slice_mut().unwrap();
+            for (k, orig_coeff) in orig_coeffs.iter().enumerate() {
                 let index = shift + k;
+                // Safety: the mask reduces the index modulo the degree, which is
+                // the length of the rows.
                 if index & self.ctx.degree == 0 {
-                    coeffs[index & mask] = orig_coeffs[k];
+                    unsafe { set(coeffs, index & mask, *orig_coeff) }
                 } else {
-                    coeffs[index & mask] = qi.neg(orig_coeffs[k]);
+                    unsafe { set(coeffs, index & mask, qi.neg(*orig_coeff)) }
                 }
             }
         });
@@ -583,16 +790,19 @@ impl Poly {
 
 #[cfg(test)]
 mod tests {
-    use super::{switcher::Switcher, Context, Poly, Representation};
+    use super::{
+        switcher::Switcher, traits::TryConvertFrom, Context, Distribution, Poly, Representation,
+    };
     use crate::{rq::SubstitutionExponent, zq::Modulus};
     use fhe_util::variance;
     use itertools::Itertools;
-    use num_bigint::BigUint;
-    use num_traits::{One, Zero};
+    use num_bigint::{BigInt, BigUint};
+    use num_traits::{One, Signed, Zero};
     use rand::{thread_rng, Rng, SeedableRng};
     use rand_chacha::ChaCha8Rng;
     extern crate alloc;
     use crate::Error;
+    use alloc::borrow::Cow;
     use alloc::string::ToString;
     use alloc::sync::Arc;
     use alloc::vec;
@@ -694,6 +904,34 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn random_wide() -> Result<(), Error> {
+        for _ in 0..100 {
+            let mut seed = <ChaCha8Rng as SeedableRng>::Seed::default();
+            thread_rng().fill(&mut seed);
+
+            let ctx = Arc::new(Context::new(MODULI, 16)?);
+            let p = Poly::random_from_seed_wide(&ctx, Representation::Ntt, seed);
+            let q = Poly::random_from_seed_wide(&ctx, Representation::Ntt, seed);
+            assert_eq!(p, q);
+
+            // The coefficients are sampled modulo each qi in order from the
+            // same random stream.
+            let mut prng = Poly::seeded_prng(seed);
+            let expected = ctx
+                .q
+                .iter()
+                .flat_map(|qi| qi.random_vec_wide(16, &mut prng))
+                .collect_vec();
+            assert_eq!(p.coefficients().as_slice().unwrap(), &expected);
+
+            thread_rng().fill(&mut seed);
+            let r = Poly::random_from_seed_wide(&ctx, Representation::Ntt, seed);
+            assert_ne!(p, r);
+        }
+        Ok(())
+    }
+
     #[test]
     fn coefficients() -> Result<(), Error> {
         let mut rng = thread_rng();
@@ -713,6 +951,41 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn to_signed_coefficients() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let ctx = Arc::new(Context::new(MODULI, 16)?);
+        let p = Poly::random(&ctx, Representation::Ntt, &mut rng);
+        assert!(p.to_signed_coefficients().is_err());
+
+        let v = (0..16).map(|i| i as i64 - 8).collect_vec();
+        let p = Poly::try_convert_from(v.as_slice(), &ctx, false, Representation::PowerBasis)?;
+        let signed = p.to_signed_coefficients()?;
+        assert_eq!(signed, v.iter().map(|vi| BigInt::from(*vi)).collect_vec());
+        Ok(())
+    }
+
+    #[test]
+    fn lift() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let ctx = Arc::new(Context::new(MODULI, 16)?);
+        let p = Poly::random(&ctx, Representation::Ntt, &mut rng);
+        assert!(p.lift_poly().is_err());
+        assert!(p.lift_centered().is_err());
+
+        let modulus = BigInt::from(ctx.modulus().clone());
+        for _ in 0..50 {
+            let p = Poly::random(&ctx, Representation::PowerBasis, &mut rng);
+            let unsigned = p.lift_poly()?;
+            assert_eq!(unsigned, Vec::<BigUint>::from(&p));
+            for (si, ui) in p.lift_centered()?.iter().zip(unsigned) {
+                assert!(BigInt::from(2) * si.abs() <= modulus);
+                assert_eq!((si + &modulus) % &modulus, BigInt::from(ui));
+            }
+        }
+        Ok(())
+    }
+
     #[test]
     fn modulus() -> Result<(), Error> {
         for modulus in MODULI {
@@ -792,21 +1065,23 @@ mod tests {
         p.change_representation(Representation::PowerBasis);
         assert_eq!(p.representation, Representation::PowerBasis);
         assert_eq!(p.representation(), &Representation::PowerBasis);
-        assert!(p.coefficients_shoup.is_none());
+        assert!(p.coefficients_shoup.get().is_none());
         let q = p.clone();
 
         p.change_representation(Representation::Ntt);
         assert_eq!(p.representation, Representation::Ntt);
         assert_eq!(p.representation(), &Representation::Ntt);
         assert_ne!(p.coefficients, q.coefficients);
-        assert!(p.coefficients_shoup.is_none());
+        assert!(p.coefficients_shoup.get().is_none());
         let q_ntt = p.clone();
 
         p.change_representation(Representation::NttShoup);
         assert_eq!(p.representation, Representation::NttShoup);
         assert_eq!(p.representation(), &Representation::NttShoup);
         assert_ne!(p.coefficients, q.coefficients);
-        assert!(p.coefficients_shoup.is_some());
+        assert!(p.coefficients_shoup.get().is_none());
+        p.precompute_shoup_coefficients();
+        assert!(p.coefficients_shoup.get().is_some());
         let q_ntt_shoup = p.clone();
 
         p.change_representation(Representation::PowerBasis);
@@ -824,6 +1099,39 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn to_representation() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let ctx = Arc::new(Context::new(MODULI, 16)?);
+
+        for from in [
+            Representation::PowerBasis,
+            Representation::Ntt,
+            Representation::NttShoup,
+        ] {
+            let p = Poly::random(&ctx, from.clone(), &mut rng);
+            for to in [
+                Representation::PowerBasis,
+                Representation::Ntt,
+                Representation::NttShoup,
+            ] {
+                let q = p.to_representation(to.clone());
+                assert_eq!(matches!(q, Cow::Borrowed(_)), from == to);
+
+                let mut expected = p.clone();
+                expected.change_representation(to.clone());
+                assert_eq!(q.as_ref(), &expected);
+                assert_eq!(q.representation(), &to);
+                q.precompute_shoup_coefficients();
+                assert_eq!(
+                    q.coefficients_shoup.get().is_some(),
+                    to == Representation::NttShoup
+                );
+            }
+        }
+        Ok(())
+    }
+
     #[test]
     fn override_representation() -> Result<(), Error> {
         let mut rng = thread_rng();
@@ -837,23 +1145,53 @@ mod tests {
         assert_eq!(p.representation, Representation::Ntt);
         assert_eq!(p.representation(), &p.representation);
         assert_eq!(p.coefficients, q.coefficients);
-        assert!(p.coefficients_shoup.is_none());
+        assert!(p.coefficients_shoup.get().is_none());
 
         unsafe { p.override_representation(Representation::NttShoup) }
         assert_eq!(p.representation, Representation::NttShoup);
         assert_eq!(p.representation(), &p.representation);
         assert_eq!(p.coefficients, q.coefficients);
-        assert!(p.coefficients_shoup.is_some());
+        assert!(p.coefficients_shoup.get().is_none());
+        p.precompute_shoup_coefficients();
+        assert!(p.coefficients_shoup.get().is_some());
 
         unsafe { p.override_representation(Representation::PowerBasis) }
         assert_eq!(p, q);
 
         unsafe { p.override_representation(Representation::NttShoup) }
-        assert!(p.coefficients_shoup.is_some());
+        p.precompute_shoup_coefficients();
+        assert!(p.coefficients_shoup.get().is_some());
 
         unsafe { p.override_representation(Representation::Ntt) }
-        assert!(p.coefficients_shoup.is_none());
+        assert!(p.coefficients_shoup.get().is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn lazy_shoup_coefficients() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let ctx = Arc::new(Context::new(MODULI, 16)?);
 
+        let p = Poly::random(&ctx, Representation::NttShoup, &mut rng);
+        let mut p_ntt = p.clone();
+        unsafe { p_ntt.override_representation(Representation::Ntt) }
+        let q = Poly::random(&ctx, Representation::Ntt, &mut rng);
+
+        // The Shoup coefficients are computed at the first multiplication.
+        assert!(p.coefficients_shoup.get().is_none());
+        assert_eq!(p.shoup_heap_size(), 0);
+        assert_eq!(&q * &p, &q * &p_ntt);
+        assert!(p.coefficients_shoup.get().is_some());
+        assert_eq!(p.coefficients_heap_size(), MODULI.len() * 16 * 8);
+        assert_eq!(p.shoup_heap_size(), p.coefficients_heap_size());
+
+        // They are kept by the clones and the substitutions.
+        assert!(p.clone().coefficients_shoup.get().is_some());
+        let exponent = SubstitutionExponent::new(&ctx, 3)?;
+        let p_sub = p.substitute(&exponent)?;
+        assert!(p_sub.coefficients_shoup.get().is_some());
+        assert_eq!(&q * &p_sub, &q * &p_ntt.substitute(&exponent)?);
         Ok(())
     }
 
@@ -864,37 +1202,121 @@ mod tests {
             let ctx = Arc::new(Context::new(&[*modulus], 16)?);
             let q = Modulus::new(*modulus).unwrap();
 
-            let e = Poly::small(&ctx, Representation::PowerBasis, 0, &mut rng);
+            let e = Poly::small(
+                &ctx,
+                Representation::PowerBasis,
+                Distribution::Cbd(0),
+                &mut rng,
+            );
             assert!(e.is_err());
             assert_eq!(
                 e.unwrap_err().to_string(),
                 "The variance should be an integer between 1 and 16"
             );
-            let e = Poly::small(&ctx, Representation::PowerBasis, 17, &mut rng);
+            let e = Poly::small(
+                &ctx,
+                Representation::PowerBasis,
+                Distribution::Cbd(17),
+                &mut rng,
+            );
             assert!(e.is_err());
             assert_eq!(
                 e.unwrap_err().to_string(),
                 "The variance should be an integer between 1 and 16"
             );
+            let e = Poly::small(
+                &ctx,
+                Representation::PowerBasis,
+                Distribution::Ternary(17),
+                &mut rng,
+            );
+            assert!(e.is_err());
+            let e = Poly::small(
+                &ctx,
+                Representation::PowerBasis,
+                Distribution::Gaussian(0.0),
+                &mut rng,
+            );
+            assert!(e.is_err());
 
             for i in 1..=16 {
-                let p = Poly::small(&ctx, Representation::PowerBasis, i, &mut rng)?;
+                let p = Poly::small(
+                    &ctx,
+                    Representation::PowerBasis,
+                    Distribution::Cbd(i),
+                    &mut rng,
+                )?;
                 let coefficients = p.coefficients().to_slice().unwrap();
                 let v = unsafe { q.center_vec_vt(coefficients) };
 
                 assert!(v.iter().map(|vi| vi.abs()).max().unwrap() <= 2 * i as i64);
+
+                let p = Poly::small(
+                    &ctx,
+                    Representation::PowerBasis,
+                    Distribution::Ternary(i),
+                    &mut rng,
+                )?;
+                let coefficients = p.coefficients().to_slice().unwrap();
+                let v = unsafe { q.center_vec_vt(coefficients) };
+                assert!(v.iter().all(|vi| (-1..=1).contains(vi)));
+                assert_eq!(v.iter().filter(|vi| **vi != 0).count(), i);
             }
         }
 
         // Generate a very large polynomial to check the variance (here equal to 8).
         let ctx = Arc::new(Context::new(&[4611686018326724609], 1 << 18)?);
         let q = Modulus::new(4611686018326724609).unwrap();
-        let p = Poly::small(&ctx, Representation::PowerBasis, 16, &mut thread_rng())?;
+        let p = Poly::small(
+            &ctx,
+            Representation::PowerBasis,
+            Distribution::Cbd(16),
+            &mut thread_rng(),
+        )?;
         let coefficients = p.coefficients().to_slice().unwrap();
         let v = unsafe { q.center_vec_vt(coefficients) };
         assert!(v.iter().map(|vi| vi.abs()).max().unwrap() <= 32);
         assert_eq!(variance(&v).round(), 16.0);
 
+        // Same for the Gaussian distribution, whose variance is sigma^2.
+        let p = Poly::small(
+            &ctx,
+            Representation::PowerBasis,
+            Distribution::Gaussian(3.2),
+            &mut thread_rng(),
+        )?;
+        let coefficients = p.coefficients().to_slice().unwrap();
+        let v = unsafe { q.center_vec_vt(coefficients) };
+        assert_eq!(variance(&v).round(), 10.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn small_reproducible() -> Result<(), Error> {
+        let ctx = Arc::new(Context::new(MODULI, 16)?);
+        let mut seed = <ChaCha8Rng as SeedableRng>::Seed::default();
+        thread_rng().fill(&mut seed);
+        for distribution in [
+            Distribution::Cbd(10),
+            Distribution::Ternary(8),
+            Distribution::Gaussian(3.2),
+        ] {
+            let p = Poly::small(
+                &ctx,
+                Representation::Ntt,
+                distribution,
+                &mut ChaCha8Rng::from_seed(seed),
+            )?;
+            let q = Poly::small(
+                &ctx,
+                Representation::Ntt,
+                distribution,
+                &mut ChaCha8Rng::from_seed(seed),
+            )?;
+            assert_eq!(p, q);
+        }
+
         Ok(())
     }
 
@@ -915,6 +1337,13 @@ mod tests {
             assert!(SubstitutionExponent::new(&ctx, 2).is_err());
             assert!(SubstitutionExponent::new(&ctx, 16).is_err());
 
+            // Substitution by an exponent of another degree should fail
+            let other_ctx = Arc::new(Context::new(&[*modulus], 32)?);
+            let other = SubstitutionExponent::new(&other_ctx, 3)?;
+            assert!(p.substitute(&other).is_err());
+            assert!(p_ntt.substitute(&other).is_err());
+            assert!(p_ntt_shoup.substitute(&other).is_err());
+
             // Substitution by 1 leaves the polynomials unchanged
             assert_eq!(p, p.substitute(&SubstitutionExponent::new(&ctx, 1)?)?);
             assert_eq!(
@@ -9,7 +9,6 @@ use core::{
 use itertools::{izip, Itertools};
 use ndarray::Array2;
 use num_bigint::BigUint;
-use zeroize::Zeroize;
 extern crate alloc;
 use alloc::string::ToString;
 use alloc::vec;
@@ -44,9 +43,7 @@ impl AddAssign<&Poly> for Poly {
                 p.coefficients.outer_iter(),
                 self.ctx.q.iter()
             )
-            .for_each(|(mut v1, v2, qi)| {
-                qi.add_vec(v1.as_slice_mut().unwrap(), v2.as_slice().unwrap())
-            });
+            .for_each(|(v1, v2, qi)| qi.add_view(v1, v2));
         }
     }
 }
@@ -97,9 +94,7 @@ impl SubAssign<&Poly> for Poly {
                 p.coefficients.outer_iter(),
                 self.ctx.q.iter()
             )
-            .for_each(|(mut v1, v2, qi)| {
-                qi.sub_vec(v1.as_slice_mut().unwrap(), v2.as_slice().unwrap())
-            });
+            .for_each(|(v1, v2, qi)| qi.sub_view(v1, v2));
         }
     }
 }
@@ -155,9 +150,7 @@ impl MulAssign<&Poly> for Poly {
                         p.coefficients.outer_iter(),
                         self.ctx.q.iter()
                     )
-                    .for_each(|(mut v1, v2, qi)| {
-                        qi.mul_vec(v1.as_slice_mut().unwrap(), v2.as_slice().unwrap())
-                    });
+                    .for_each(|(v1, v2, qi)| qi.mul_view(v1, v2));
                 }
             }
             Representation::NttShoup => {
@@ -165,7 +158,7 @@ impl MulAssign<&Poly> for Poly {
                     izip!(
                         self.coefficients.outer_iter_mut(),
                         p.coefficients.outer_iter(),
-                        p.coefficients_shoup.as_ref().unwrap().outer_iter(),
+                        p.coefficients_shoup().outer_iter(),
                         self.ctx.q.iter()
                     )
                     .for_each(|(mut v1, v2, v2_shoup, qi)| unsafe {
@@ -179,16 +172,10 @@ impl MulAssign<&Poly> for Poly {
                     izip!(
                         self.coefficients.outer_iter_mut(),
                         p.coefficients.outer_iter(),
-                        p.coefficients_shoup.as_ref().unwrap().outer_iter(),
+                        p.coefficients_shoup().outer_iter(),
                         self.ctx.q.iter()
                     )
-                    .for_each(|(mut v1, v2, v2_shoup, qi)| {
-                        qi.mul_shoup_vec(
-                            v1.as_slice_mut().unwrap(),
-                            v2.as_slice().unwrap(),
-                            v2_shoup.as_slice().unwrap(),
-                        )
-                    });
+                    .for_each(|(v1, v2, v2_shoup, qi)| qi.mul_shoup_view(v1, v2, v2_shoup));
                 }
                 self.has_lazy_coefficients = false
             }
@@ -227,9 +214,7 @@ impl MulAssign<&BigUint> for Poly {
                 q.coefficients.outer_iter(),
                 self.ctx.q.iter()
             )
-            .for_each(|(mut v1, v2, qi)| {
-                qi.mul_vec(v1.as_slice_mut().unwrap(), v2.as_slice().unwrap())
-            });
+            .for_each(|(v1, v2, qi)| qi.mul_view(v1, v2));
         }
     }
 }
@@ -242,12 +227,6 @@ impl Mul<&Poly> for &Poly {
                 // TODO: To test, and do the same thing for add, sub, and neg
                 let mut q = p.clone();
                 if q.representation == Representation::NttShoup {
-                    q.coefficients_shoup
-                        .as_mut()
-                        .unwrap()
-                        .as_slice_mut()
-                        .unwrap()
-                        .zeroize();
                     unsafe { q.override_representation(Representation::Ntt) }
                 }
                 q *= self;
@@ -349,6 +328,55 @@ unsafe fn fma(out: &mut [u128], x: &[u64], y: &[u64]) {
     }
 }
 
+impl Poly {
+    /// Fused multiply-add: computes `self += a * b` without materializing the
+    /// product polynomial, reducing each coefficient once.
+    ///
+    /// The polynomials `self` and `a` must be in Ntt representation, and `b` in
+    /// Ntt or NttShoup representation; `a` may have lazy coefficients.
+    pub fn fma(&mut self, a: &Poly, b: &Poly) {
+        assert!(!self.has_lazy_coefficients && !b.has_lazy_coefficients);
+        assert_eq!(
+            self.representation,
+            Representation::Ntt,
+            "Multiply-add requires an Ntt representation."
+        );
+        assert_eq!(
+            a.representation,
+            Representation::Ntt,
+            "Multiply-add requires an Ntt representation."
+        );
+        assert_ne!(
+            b.representation,
+            Representation::PowerBasis,
+            "Multiply-add requires a multiplicand in Ntt or NttShoup representation."
+        );
+        debug_assert_eq!(self.ctx, a.ctx, "Incompatible contexts");
+        debug_assert_eq!(self.ctx, b.ctx, "Incompatible contexts");
+        self.allow_variable_time_computations |=
+            a.allow_variable_time_computations | b.allow_variable_time_computations;
+
+        izip!(
+            self.coefficients.outer_iter_mut(),
+            a.coefficients.outer_iter(),
+            b.coefficients.outer_iter(),
+            self.ctx.q.iter()
+        )
+        .for_each(|(mut v, va, vb, qi)| {
+            let it = izip!(v.iter_mut(), va.iter(), vb.iter());
+            if self.allow_variable_time_computations {
+                it.for_each(|(vi, ai, bi)| unsafe {
+                    *vi = qi.reduce_u128_vt((*vi as u128) + (*ai as u128) * (*bi as u128))
+                })
+            } else {
+                it.for_each(|(vi, ai, bi)| {
+                    *vi = qi.reduce_u128((*vi as u128) + (*ai as u128) * (*bi as u128))
+                })
+            }
+        });
+    }
+}
+
 /// Compute the dot product between two iterators of polynomials.
 /// Returna an error if the iterator counts are 0, or if any of the polynomial
 /// is not in Ntt or NttShoup representation.
@@ -379,12 +407,14 @@ where
     let mut num_acc = vec![1u128; p_first.ctx.q.len()];
     let num_acc_ptr = num_acc.as_mut_ptr();
 
-    // Maximum number of products that can be accumulated
+    // Maximum number of products that can be accumulated; lazy coefficients
+    // are up to 4 times larger than the modulus.
+    let lazy = p.clone().any(|pi| pi.has_lazy_coefficients) as u32;
     let max_acc = p_first
         .ctx
         .q
         .iter()
-        .map(|qi| 1u128 << (2 * (*qi).leading_zeros()))
+        .map(|qi| 1u128 << (2 * (*qi).leading_zeros() - 2 * lazy))
         .collect_vec();
     let max_acc_ptr = max_acc.as_ptr();
 
@@ -454,7 +484,7 @@ where
         representation: Representation::Ntt,
         allow_variable_time_computations: p_first.allow_variable_time_computations,
         coefficients: coeffs,
-        coefficients_shoup: None,
+        coefficients_shoup: Default::default(),
         has_lazy_coefficients: false,
     })
 }
@@ -593,6 +623,26 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn fma() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let ctx = Arc::new(Context::new(MODULI, 16)?);
+        for _ in 0..100 {
+            let mut p = Poly::random(&ctx, Representation::Ntt, &mut rng);
+            let a = Poly::random(&ctx, Representation::Ntt, &mut rng);
+            let mut b = Poly::random(&ctx, Representation::Ntt, &mut rng);
+            let expected = &p + &(&a * &b);
+            p.fma(&a, &b);
+            assert_eq!(p, expected);
+
+            b.change_representation(Representation::NttShoup);
+            let expected = &p + &(&a * &b);
+            p.fma(&a, &b);
+            assert_eq!(p, expected);
+        }
+        Ok(())
+    }
+
     #[test]
     fn mul_shoup() -> Result<(), Error> {
         let mut rng = thread_rng();
@@ -1,6 +1,34 @@
 #![warn(missing_docs, unused_imports)]
 
 //! Polynomial scaler.
+//!
+//! A [`Scaler`] applies a [`crate::rns::RnsScaler`] to every coefficient of a
+//! polynomial, to scale polynomials between two contexts of the same degree
+//! with user-chosen moduli, e.g. to extend the basis of a polynomial before a
+//! multiplication, and to scale the product back down:
+//!
+//! ```
+//! use fhe_math::rns::ScalingFactor;
+//! use fhe_math::rq::{scaler::Scaler, Context, Poly, Representation};
+//! use num_bigint::BigUint;
+//!
+//! let q = Context::new_arc(&[4611686018326724609], 16).unwrap();
+//! let qp = Context::new_arc(&[4611686018326724609, 4611686018309947393], 16).unwrap();
+//!
+//! // Extend the basis from q to q * p, and convert back to q.
+//! let extender = Scaler::new(&q, &qp, ScalingFactor::one()).unwrap();
+//! let converter = Scaler::new(&qp, &q, ScalingFactor::one()).unwrap();
+//! let a = Poly::random(&q, Representation::PowerBasis, &mut rand::thread_rng());
+//! let a_qp = a.scale(&extender).unwrap();
+//! assert_eq!(a_qp.ctx(), &qp);
+//! assert_eq!(a_qp.scale(&converter).unwrap(), a);
+//!
+//! // Scale from q * p down to q by 1 / p, rounding each coefficient.
+//! let p = BigUint::from(4611686018309947393u64);
+//! let down_scaler = Scaler::new(&qp, &q, ScalingFactor::new(&BigUint::from(1u64), &p)).unwrap();
+//! assert_eq!(down_scaler.scaling_factor().denominator(), &p);
+//! assert!(a_qp.scale(&down_scaler).is_ok());
+//! ```
 
 extern crate alloc;
 
@@ -11,10 +39,12 @@ use crate::{
 };
 use alloc::string::ToString;
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use itertools::izip;
 use ndarray::{s, Array2, Axis};
 
-/// Context extender.
+/// Scaler of polynomials from a context to another context of the same
+/// degree.
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct Scaler {
     from: Arc<Context>,
@@ -24,7 +54,10 @@ pub struct Scaler {
 }
 
 impl Scaler {
-    /// Create a scaler from a context `from` to a context `to`.
+    /// Create a scaler from a context `from` to a context `to`, by the scaling
+    /// factor `factor`.
+    ///
+    /// Returns an error if the contexts do not have the same degree.
     pub fn new(from: &Arc<Context>, to: &Arc<Context>, factor: ScalingFactor) -> Result<Self> {
         if from.degree != to.degree {
             return Err(Error::Default("Incompatible degrees".to_string()));
@@ -51,89 +84,166 @@ impl Scaler {
         })
     }
 
+    /// Returns the context of the inputs of the scaler.
+    pub fn from(&self) -> &Arc<Context> {
+        &self.from
+    }
+
+    /// Returns the context of the outputs of the scaler.
+    pub fn to(&self) -> &Arc<Context> {
+        &self.to
+    }
+
+    /// Returns the scaling factor of the scaler.
+    pub fn scaling_factor(&self) -> &ScalingFactor {
+        self.scaler.scaling_factor()
+    }
+
     /// Scale a polynomial
     pub(crate) fn scale(&self, p: &Poly) -> Result<Poly> {
+        let mut out = Poly::zero(&self.to, Representation::PowerBasis);
+        self.scale_with_scratch(p, &mut out, &mut None)?;
+        Ok(out)
+    }
+
+    /// Scale the polynomial `p` into the polynomial `out`, which must be
+    /// defined over the context to which the scaler scales, reusing the
+    /// storage of `out`.
+    pub fn scale_into(&self, p: &Poly, out: &mut Poly) -> Result<()> {
+        self.scale_with_scratch(p, out, &mut None)
+    }
+
+    /// Scale several polynomials, sharing the storage used to convert the
+    /// polynomials in Ntt representation back to the PowerBasis
+    /// representation. With the `parallel` feature, the polynomials are scaled
+    /// in parallel.
+    pub fn scale_many(&self, polys: &[&Poly]) -> Result<Vec<Poly>> {
+        #[cfg(not(feature = "parallel"))]
+        {
+            let mut scratch = None;
+            polys
+                .iter()
+                .map(|p| {
+                    let mut out = Poly::zero(&self.to, Representation::PowerBasis);
+                    self.scale_with_scratch(p, &mut out, &mut scratch)?;
+                    Ok(out)
+                })
+                .collect()
+        }
+
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+            polys
+                .par_iter()
+                .map_init(
+                    || None,
+                    |scratch, p| {
+                        let mut out = Poly::zero(&self.to, Representation::PowerBasis);
+                        self.scale_with_scratch(p, &mut out, scratch)?;
+                        Ok(out)
+                    },
+                )
+                .collect()
+        }
+    }
+
+    /// Scale `p` into `out`, using `scratch` to store the coefficients of `p`
+    /// in PowerBasis representation when `p` is in Ntt representation.
+    fn scale_with_scratch(
+        &self,
+        p: &Poly,
+        out: &mut Poly,
+        scratch: &mut Option<Array2<u64>>,
+    ) -> Result<()> {
         if p.ctx.as_ref() != self.from.as_ref() {
-            Err(Error::Default(
+            return Err(Error::Default(
                 "The input polynomial does not have the correct context".to_string(),
-            ))
-        } else {
-            let mut representation = p.representation.clone();
-            if representation == Representation::NttShoup {
-                representation = Representation::Ntt;
-            }
+            ));
+        }
+        if out.ctx.as_ref() != self.to.as_ref() {
+            return Err(Error::Default(
+                "The output polynomial does not have the correct context".to_string(),
+            ));
+        }
 
-            let mut new_coefficients = Array2::<u64>::zeros((self.to.q.len(), self.to.degree));
+        let mut representation = p.representation.clone();
+        if representation == Representation::NttShoup {
+            representation = Representation::Ntt;
+        }
 
-            if self.number_common_moduli > 0 {
-                new_coefficients
-                    .slice_mut(s![..self.number_common_moduli, ..])
-                    .assign(&p.coefficients.slice(s![..self.number_common_moduli, ..]));
-            }
+        // The Shoup coefficients of the output are now stale.
+        unsafe { out.override_representation(representation) };
+        out.allow_variable_time_computations = p.allow_variable_time_computations;
+        out.has_lazy_coefficients = false;
+        let new_coefficients = &mut out.coefficients;
+
+        if self.number_common_moduli > 0 {
+            new_coefficients
+                .slice_mut(s![..self.number_common_moduli, ..])
+                .assign(&p.coefficients.slice(s![..self.number_common_moduli, ..]));
+        }
 
-            if self.number_common_moduli < self.to.q.len() {
-                if p.representation == Representation::PowerBasis {
+        if self.number_common_moduli < self.to.q.len() {
+            if p.representation == Representation::PowerBasis {
+                izip!(
+                    new_coefficients
+                        .slice_mut(s![self.number_common_moduli.., ..])
+                        .axis_iter_mut(Axis(1)),
+                    p.coefficients.axis_iter(Axis(1))
+                )
+                .for_each(|(new_column, column)| {
+                    self.scaler
+                        .scale(column, new_column, self.number_common_moduli)
+                });
+            } else {
+                let p_coefficients_powerbasis = match scratch {
+                    Some(scratch) => {
+                        scratch.assign(&p.coefficients);
+                        scratch
+                    }
+                    None => scratch.insert(p.coefficients.clone()),
+                };
+                // Backward NTT
+                if p.allow_variable_time_computations {
+                    izip!(p_coefficients_powerbasis.outer_iter_mut(), p.ctx.ops.iter())
+                        .for_each(|(mut v, op)| unsafe { op.backward_vt(v.as_mut_ptr()) });
+                } else {
+                    izip!(p_coefficients_powerbasis.outer_iter_mut(), p.ctx.ops.iter())
+                        .for_each(|(mut v, op)| op.backward(v.as_slice_mut().unwrap()));
+                }
+                // Conversion
+                izip!(
+                    new_coefficients
+                        .slice_mut(s![self.number_common_moduli.., ..])
+                        .axis_iter_mut(Axis(1)),
+                    p_coefficients_powerbasis.axis_iter(Axis(1))
+                )
+                .for_each(|(new_column, column)| {
+                    self.scaler
+                        .scale(column, new_column, self.number_common_moduli)
+                });
+                // Forward NTT on the second half
+                if p.allow_variable_time_computations {
                     izip!(
                         new_coefficients
                             .slice_mut(s![self.number_common_moduli.., ..])
-                            .axis_iter_mut(Axis(1)),
-                        p.coefficients.axis_iter(Axis(1))
+                            .outer_iter_mut(),
+                        &self.to.ops[self.number_common_moduli..]
                     )
-                    .for_each(|(new_column, column)| {
-                        self.scaler
-                            .scale(column, new_column, self.number_common_moduli)
-                    });
-                } else if self.number_common_moduli < self.to.q.len() {
-                    let mut p_coefficients_powerbasis = p.coefficients.clone();
-                    // Backward NTT
-                    if p.allow_variable_time_computations {
-                        izip!(p_coefficients_powerbasis.outer_iter_mut(), p.ctx.ops.iter())
-                            .for_each(|(mut v, op)| unsafe { op.backward_vt(v.as_mut_ptr()) });
-                    } else {
-                        izip!(p_coefficients_powerbasis.outer_iter_mut(), p.ctx.ops.iter())
-                            .for_each(|(mut v, op)| op.backward(v.as_slice_mut().unwrap()));
-                    }
-                    // Conversion
+                    .for_each(|(mut v, op)| unsafe { op.forward_vt(v.as_mut_ptr()) });
+                } else {
                     izip!(
                         new_coefficients
                             .slice_mut(s![self.number_common_moduli.., ..])
-                            .axis_iter_mut(Axis(1)),
-                        p_coefficients_powerbasis.axis_iter(Axis(1))
+                            .outer_iter_mut(),
+                        &self.to.ops[self.number_common_moduli..]
                     )
-                    .for_each(|(new_column, column)| {
-                        self.scaler
-                            .scale(column, new_column, self.number_common_moduli)
-                    });
-                    // Forward NTT on the second half
-                    if p.allow_variable_time_computations {
-                        izip!(
-                            new_coefficients
-                                .slice_mut(s![self.number_common_moduli.., ..])
-                                .outer_iter_mut(),
-                            &self.to.ops[self.number_common_moduli..]
-                        )
-                        .for_each(|(mut v, op)| unsafe { op.forward_vt(v.as_mut_ptr()) });
-                    } else {
-                        izip!(
-                            new_coefficients
-                                .slice_mut(s![self.number_common_moduli.., ..])
-                                .outer_iter_mut(),
-                            &self.to.ops[self.number_common_moduli..]
-                        )
-                        .for_each(|(mut v, op)| op.forward(v.as_slice_mut().unwrap()));
-                    }
+                    .for_each(|(mut v, op)| op.forward(v.as_slice_mut().unwrap()));
                 }
             }
-
-            Ok(Poly {
-                ctx: self.to.clone(),
-                representation,
-                allow_variable_time_computations: p.allow_variable_time_computations,
-                coefficients: new_coefficients,
-                coefficients_shoup: None,
-                has_lazy_coefficients: false,
-            })
         }
+        Ok(())
     }
 }
 
@@ -175,6 +285,9 @@ mod tests {
                 let d = BigUint::from(*denominator);
 
                 let scaler = Scaler::new(&from, &to, ScalingFactor::new(&n, &d))?;
+                assert_eq!(scaler.from(), &from);
+                assert_eq!(scaler.to(), &to);
+                assert_eq!(scaler.scaling_factor(), &ScalingFactor::new(&n, &d));
 
                 for _ in 0..ntests {
                     let mut poly = Poly::random(&from, Representation::PowerBasis, &mut rng);
@@ -215,4 +328,40 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn scale_many_into() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let from = Context::new_arc(Q, 16)?;
+        let to = Context::new_arc(P, 16)?;
+        let n = BigUint::from(3u64);
+        let d = BigUint::from(1001u64);
+        let scaler = Scaler::new(&from, &to, ScalingFactor::new(&n, &d))?;
+
+        let polys = [
+            Poly::random(&from, Representation::PowerBasis, &mut rng),
+            Poly::random(&from, Representation::Ntt, &mut rng),
+            Poly::random(&from, Representation::NttShoup, &mut rng),
+            Poly::random(&from, Representation::Ntt, &mut rng),
+        ];
+        let expected = polys
+            .iter()
+            .map(|p| scaler.scale(p))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let scaled = scaler.scale_many(&polys.iter().collect_vec())?;
+        assert_eq!(scaled, expected);
+        assert!(scaler.scale_many(&[])?.is_empty());
+
+        let mut out = Poly::random(&to, Representation::NttShoup, &mut rng);
+        for (p, e) in polys.iter().zip(expected.iter()) {
+            scaler.scale_into(p, &mut out)?;
+            assert_eq!(&out, e);
+        }
+        assert!(scaler.scale_into(&polys[0], &mut polys[1].clone()).is_err());
+        assert!(scaler.scale_into(&expected[0], &mut out).is_err());
+        assert!(scaler.scale_many(&[&polys[0], &expected[0]]).is_err());
+
+        Ok(())
+    }
 }
@@ -0,0 +1,274 @@
+#![warn(missing_docs, unused_imports)]
+
+//! Stack-allocated polynomials of small degree.
+//!
+//! [`SmallPoly<N>`] stores a polynomial of `ZZ_p[x] / (x^N + 1)` in an array of
+//! `N` coefficients, for `N` a power of two between 8 and 64. Such polynomials
+//! are multiplied with the schoolbook algorithm, so that neither the NTT
+//! tables of a [`Context`] nor any allocation are needed.
+
+use super::{traits::TryConvertFrom, Context, Poly, Representation};
+use crate::{zq::Modulus, Error, Result};
+use core::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use itertools::izip;
+use rand::{CryptoRng, RngCore};
+extern crate alloc;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+
+/// Largest degree supported by [`SmallPoly`].
+pub const MAX_SMALL_DEGREE: usize = 64;
+
+/// Polynomial of `ZZ_p[x] / (x^N + 1)` in power basis representation, stored
+/// on the stack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmallPoly<const N: usize> {
+    q: Modulus,
+    coefficients: [u64; N],
+}
+
+impl<const N: usize> SmallPoly<N> {
+    /// Returns an error if `N` is not a power of two between 8 and
+    /// [`MAX_SMALL_DEGREE`].
+    fn check_degree() -> Result<()> {
+        if !N.is_power_of_two() || !(8..=MAX_SMALL_DEGREE).contains(&N) {
+            Err(Error::Default(
+                "The degree is not a power of two between 8 and 64".to_string(),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Creates a polynomial from its coefficients modulo `q`.
+    ///
+    /// Returns an error if `N` is not supported, or if a coefficient is not
+    /// reduced modulo `q`.
+    pub fn new(q: &Modulus, coefficients: [u64; N]) -> Result<Self> {
+        Self::check_degree()?;
+        if coefficients.iter().any(|c| *c >= q.p) {
+            return Err(Error::Default(
+                "The coefficients are not reduced modulo q".to_string(),
+            ));
+        }
+        Ok(Self {
+            q: q.clone(),
+            coefficients,
+        })
+    }
+
+    /// Creates a polynomial holding the constant 0.
+    ///
+    /// Returns an error if `N` is not supported.
+    pub fn zero(q: &Modulus) -> Result<Self> {
+        Self::new(q, [0; N])
+    }
+
+    /// Generate a random polynomial.
+    ///
+    /// Returns an error if `N` is not supported.
+    pub fn random<R: RngCore + CryptoRng>(q: &Modulus, rng: &mut R) -> Result<Self> {
+        let mut coefficients = [0; N];
+        q.fill_random(&mut coefficients, rng);
+        Self::new(q, coefficients)
+    }
+
+    /// Returns the modulus of the polynomial.
+    pub fn modulus(&self) -> &Modulus {
+        &self.q
+    }
+
+    /// Returns the coefficients of the polynomial.
+    pub fn coefficients(&self) -> &[u64; N] {
+        &self.coefficients
+    }
+}
+
+impl<const N: usize> AddAssign<&SmallPoly<N>> for SmallPoly<N> {
+    fn add_assign(&mut self, p: &SmallPoly<N>) {
+        assert_eq!(self.q, p.q, "Incompatible moduli");
+        izip!(self.coefficients.iter_mut(), p.coefficients.iter())
+            .for_each(|(a, b)| *a = self.q.add(*a, *b))
+    }
+}
+
+impl<const N: usize> Add<&SmallPoly<N>> for &SmallPoly<N> {
+    type Output = SmallPoly<N>;
+    fn add(self, p: &SmallPoly<N>) -> SmallPoly<N> {
+        let mut q = self.clone();
+        q += p;
+        q
+    }
+}
+
+impl<const N: usize> SubAssign<&SmallPoly<N>> for SmallPoly<N> {
+    fn sub_assign(&mut self, p: &SmallPoly<N>) {
+        assert_eq!(self.q, p.q, "Incompatible moduli");
+        izip!(self.coefficients.iter_mut(), p.coefficients.iter())
+            .for_each(|(a, b)| *a = self.q.sub(*a, *b))
+    }
+}
+
+impl<const N: usize> Sub<&SmallPoly<N>> for &SmallPoly<N> {
+    type Output = SmallPoly<N>;
+    fn sub(self, p: &SmallPoly<N>) -> SmallPoly<N> {
+        let mut q = self.clone();
+        q -= p;
+        q
+    }
+}
+
+impl<const N: usize> MulAssign<&SmallPoly<N>> for SmallPoly<N> {
+    fn mul_assign(&mut self, p: &SmallPoly<N>) {
+        *self = &*self * p
+    }
+}
+
+impl<const N: usize> Mul<&SmallPoly<N>> for &SmallPoly<N> {
+    type Output = SmallPoly<N>;
+
+    /// Negacyclic schoolbook multiplication in constant time.
+    fn mul(self, p: &SmallPoly<N>) -> SmallPoly<N> {
+        assert_eq!(self.q, p.q, "Incompatible moduli");
+        // The lazily reduced products are smaller than 2^63, so that the sums
+        // of at most 64 of them fit in a u128.
+        let mut positive = [0u128; N];
+        let mut negative = [0u128; N];
+        for (i, a) in self.coefficients.iter().enumerate() {
+            for (j, b) in p.coefficients.iter().enumerate() {
+                let c = self.q.lazy_reduce_u128((*a as u128) * (*b as u128)) as u128;
+                if i + j < N {
+                    positive[i + j] += c
+                } else {
+                    negative[i + j - N] += c
+                }
+            }
+        }
+        let mut coefficients = [0u64; N];
+        izip!(coefficients.iter_mut(), positive, negative).for_each(|(c, pos, neg)| {
+            *c = self.q.sub(self.q.reduce_u128(pos), self.q.reduce_u128(neg))
+        });
+        SmallPoly {
+            q: self.q.clone(),
+            coefficients,
+        }
+    }
+}
+
+impl<const N: usize> Neg for &SmallPoly<N> {
+    type Output = SmallPoly<N>;
+    fn neg(self) -> SmallPoly<N> {
+        let mut q = self.clone();
+        q.coefficients.iter_mut().for_each(|c| *c = self.q.neg(*c));
+        q
+    }
+}
+
+impl<const N: usize> TryFrom<&Poly> for SmallPoly<N> {
+    type Error = Error;
+
+    /// Converts a polynomial with a single modulus and of degree `N` in power
+    /// basis representation.
+    fn try_from(p: &Poly) -> Result<Self> {
+        if p.ctx.q.len() != 1 || p.ctx.degree != N {
+            return Err(Error::Default(
+                "The context does not have a single modulus and a degree N".to_string(),
+            ));
+        }
+        if p.representation != Representation::PowerBasis {
+            return Err(Error::IncorrectRepresentation(
+                p.representation.clone(),
+                Representation::PowerBasis,
+            ));
+        }
+        let mut coefficients = [0u64; N];
+        izip!(coefficients.iter_mut(), p.coefficients.iter()).for_each(|(c, v)| *c = *v);
+        Self::new(&p.ctx.q[0], coefficients)
+    }
+}
+
+impl<const N: usize> TryConvertFrom<&SmallPoly<N>> for Poly {
+    fn try_convert_from<R>(
+        p: &SmallPoly<N>,
+        ctx: &Arc<Context>,
+        variable_time: bool,
+        representation: R,
+    ) -> Result<Self>
+    where
+        R: Into<Option<Representation>>,
+    {
+        if ctx.q.len() != 1 || ctx.q[0] != p.q {
+            return Err(Error::Default(
+                "The context does not have the modulus of the polynomial as single modulus"
+                    .to_string(),
+            ));
+        }
+        match representation.into() {
+            Some(r) if r != Representation::PowerBasis => {
+                return Err(Error::IncorrectRepresentation(
+                    r,
+                    Representation::PowerBasis,
+                ))
+            }
+            _ => {}
+        }
+        Poly::try_convert_from(
+            &p.coefficients,
+            ctx,
+            variable_time,
+            Representation::PowerBasis,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SmallPoly;
+    use crate::rq::{traits::TryConvertFrom, Context, Poly, Representation};
+    use crate::zq::Modulus;
+    use crate::Error;
+    use rand::thread_rng;
+    extern crate alloc;
+    use alloc::sync::Arc;
+
+    #[test]
+    fn ops() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        for modulus in [1153, 4611686018326724609] {
+            let q = Modulus::new(modulus)?;
+            let ctx = Arc::new(Context::new(&[modulus], 16)?);
+            let a = SmallPoly::<16>::random(&q, &mut rng)?;
+            let b = SmallPoly::<16>::random(&q, &mut rng)?;
+            let a_poly = Poly::try_convert_from(&a, &ctx, false, None)?;
+            let b_poly = Poly::try_convert_from(&b, &ctx, false, Representation::PowerBasis)?;
+            assert_eq!(SmallPoly::<16>::try_from(&a_poly)?, a);
+
+            assert_eq!(SmallPoly::try_from(&(&a_poly + &b_poly))?, &a + &b);
+            assert_eq!(SmallPoly::try_from(&(&a_poly - &b_poly))?, &a - &b);
+            assert_eq!(SmallPoly::try_from(&(-&a_poly))?, -&a);
+
+            let mut a_ntt = a_poly.clone();
+            a_ntt.change_representation(Representation::Ntt);
+            let mut b_ntt = b_poly.clone();
+            b_ntt.change_representation(Representation::Ntt);
+            let mut c = &a_ntt * &b_ntt;
+            c.change_representation(Representation::PowerBasis);
+            let mut d = a.clone();
+            d *= &b;
+            assert_eq!(SmallPoly::try_from(&c)?, d);
+
+            assert!(SmallPoly::<16>::try_from(&a_ntt).is_err());
+            assert!(SmallPoly::<8>::try_from(&a_poly).is_err());
+        }
+
+        let q = Modulus::new(1153)?;
+        assert!(SmallPoly::<4>::zero(&q).is_err());
+        assert!(SmallPoly::<128>::zero(&q).is_err());
+        assert!(SmallPoly::<64>::zero(&q).is_ok());
+        assert!(SmallPoly::<8>::new(&q, [1153; 8]).is_err());
+        let ctx = Arc::new(Context::new(&[4611686018326724609], 16)?);
+        let a = SmallPoly::<16>::zero(&q)?;
+        assert!(Poly::try_convert_from(&a, &ctx, false, None).is_err());
+        Ok(())
+    }
+}
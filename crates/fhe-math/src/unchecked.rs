@@ -0,0 +1,65 @@
+//! Indexing of slices in the hot loops, without bounds checks in release mode
+//! with the `unsafe-perf` feature.
+//!
+//! The callers guarantee that the indices are in bounds, which is always
+//! checked in debug mode; without the `unsafe-perf` feature, the indexing is
+//! also checked in release mode.
+
+/// Returns `slice[i]`.
+///
+/// # Safety
+/// The caller must ensure that `i < slice.len()`.
+#[inline(always)]
+pub(crate) unsafe fn get<T: Copy>(slice: &[T], i: usize) -> T {
+    debug_assert!(i < slice.len());
+    #[cfg(feature = "unsafe-perf")]
+    {
+        *slice.get_unchecked(i)
+    }
+    #[cfg(not(feature = "unsafe-perf"))]
+    {
+        slice[i]
+    }
+}
+
+/// Sets `slice[i]` to `value`.
+///
+/// # Safety
+/// The caller must ensure that `i < slice.len()`.
+#[inline(always)]
+pub(crate) unsafe fn set<T>(slice: &mut [T], i: usize, value: T) {
+    debug_assert!(i < slice.len());
+    #[cfg(feature = "unsafe-perf")]
+    {
+        *slice.get_unchecked_mut(i) = value
+    }
+    #[cfg(not(feature = "unsafe-perf"))]
+    {
+        slice[i] = value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{get, set};
+
+    #[test]
+    fn get_and_set() {
+        let mut v = [1u64, 2, 3];
+        unsafe {
+            let sum = get(&v, 1) + get(&v, 2);
+            set(&mut v, 0, sum);
+        }
+        assert_eq!(v, [5, 2, 3]);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic]
+    fn out_of_bounds() {
+        let v = [1u64, 2, 3];
+        unsafe {
+            get(&v, 3);
+        }
+    }
+}
@@ -2,15 +2,25 @@
 
 //! Ring operations for moduli up to 62 bits.
 
+pub mod polynomial;
 pub mod primes;
 
+#[cfg(target_arch = "aarch64")]
+pub(crate) mod neon;
+#[cfg(any(test, all(feature = "cross-check", debug_assertions)))]
+mod reference;
+
+use core::num::NonZeroUsize;
 use core::ops::Deref;
 
 use crate::errors::{Error, Result};
+use crate::unchecked::{get, set};
 use fhe_util::{is_prime, transcode_from_bytes, transcode_to_bytes};
 use itertools::{izip, Itertools};
+use ndarray::{ArrayView1, ArrayViewMut1, Zip};
 use num_bigint::BigUint;
 use num_traits::cast::ToPrimitive;
+use once_cell::race::OnceNonZeroUsize;
 use pulp::Arch;
 use rand::{distributions::Uniform, CryptoRng, Rng, RngCore};
 extern crate alloc;
@@ -23,6 +33,98 @@ const fn const_time_cond_select(on_true: u64, on_false: u64, cond: bool) -> u64
     (diff & mask) ^ on_false
 }
 
+/// Returns the number of 64-bit lanes of the widest SIMD registers available
+/// at runtime, which is the chunk size used by the vectorized kernels.
+///
+/// The CPU features are detected on the first call only.
+pub fn simd_lanes() -> usize {
+    static LANES: OnceNonZeroUsize = OnceNonZeroUsize::new();
+    LANES
+        .get_or_init(|| NonZeroUsize::new(detect_simd_lanes()).unwrap())
+        .get()
+}
+
+/// Detects the number of 64-bit lanes returned by [`simd_lanes`], without
+/// caching the result.
+#[doc(hidden)]
+pub fn detect_simd_lanes() -> usize {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if pulp::x86::V3::is_available() {
+            4
+        } else {
+            1
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        if neon::is_available() {
+            2
+        } else {
+            1
+        }
+    }
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+    {
+        1
+    }
+}
+
+/// Runs `$body`, which updates the vector `$a` in place and may return early.
+/// With the `cross-check` feature in debug builds, then asserts that every
+/// `$a[i]` equals `$reference` evaluated on the index `$i` and on the input
+/// value `$ai`.
+macro_rules! cross_checked {
+    ($name:literal, $a:ident, |$i:ident, $ai:ident| $reference:expr, $body:expr) => {{
+        #[cfg(all(feature = "cross-check", debug_assertions))]
+        let input = $a.to_vec();
+        #[allow(clippy::redundant_closure_call)]
+        (|| $body)();
+        #[cfg(all(feature = "cross-check", debug_assertions))]
+        reference::check($name, $a, &input, |$i, $ai| $reference);
+    }};
+}
+
+/// Computes `a[i] = f(a[i], b[i], c[i])` by chunks of `LANES` elements, so
+/// that the compiler can map each chunk to SIMD registers.
+#[inline(always)]
+fn map3_chunks<const LANES: usize, F: Fn(u64, u64, u64) -> u64>(
+    a: &mut [u64],
+    b: &[u64],
+    c: &[u64],
+    f: F,
+) {
+    let mut a_chunks = a.chunks_exact_mut(LANES);
+    let mut b_chunks = b.chunks_exact(LANES);
+    let mut c_chunks = c.chunks_exact(LANES);
+    for (ai, bi, ci) in izip!(a_chunks.by_ref(), b_chunks.by_ref(), c_chunks.by_ref()) {
+        for j in 0..LANES {
+            // Safety: the chunks have exactly `LANES` elements.
+            unsafe {
+                let r = f(get(ai, j), get(bi, j), get(ci, j));
+                set(ai, j, r)
+            }
+        }
+    }
+    izip!(
+        a_chunks.into_remainder(),
+        b_chunks.remainder(),
+        c_chunks.remainder()
+    )
+    .for_each(|(ai, bi, ci)| *ai = f(*ai, *bi, *ci))
+}
+
+/// Computes `a[i] = f(a[i], b[i], c[i])` with the lane width selected at
+/// runtime by [`simd_lanes`].
+#[inline(always)]
+fn map3_lanes<F: Fn(u64, u64, u64) -> u64>(a: &mut [u64], b: &[u64], c: &[u64], f: F) {
+    match simd_lanes() {
+        4 => map3_chunks::<4, F>(a, b, c, f),
+        2 => map3_chunks::<2, F>(a, b, c, f),
+        _ => map3_chunks::<1, F>(a, b, c, f),
+    }
+}
+
 /// Structure encapsulating an integer modulus up to 62 bits.
 #[derive(Debug, Clone)]
 pub struct Modulus {
@@ -32,6 +134,8 @@ pub struct Modulus {
     barrett_lo: u64,
     leading_zeros: u32,
     pub(crate) supports_opt: bool,
+    montgomery_neg_inv: u64,
+    montgomery_r2: u64,
     distribution: Uniform<u64>,
     arch: Arch,
 }
@@ -45,6 +149,8 @@ impl core::cmp::PartialEq for Modulus {
             && self.barrett_lo == other.barrett_lo
             && self.leading_zeros == other.leading_zeros
             && self.supports_opt == other.supports_opt
+            && self.montgomery_neg_inv == other.montgomery_neg_inv
+            && self.montgomery_r2 == other.montgomery_r2
             && self.distribution == other.distribution
     }
 }
@@ -68,6 +174,19 @@ impl Modulus {
             Err(Error::InvalidModulus(p))
         } else {
             let barrett = ((BigUint::from(1u64) << 128usize) / p).to_u128().unwrap(); // 2^128 / p
+            let (montgomery_neg_inv, montgomery_r2) = if p & 1 == 1 {
+                // Newton iteration for p^(-1) mod 2^64, which doubles the number
+                // of correct bits from 1 at each step.
+                let mut inv = 1u64;
+                for _ in 0..6 {
+                    inv = inv.wrapping_mul(2u64.wrapping_sub(p.wrapping_mul(inv)));
+                }
+                let r = ((1u128 << 64) % (p as u128)) as u64; // 2^64 mod p
+                let r2 = ((r as u128) * (r as u128) % (p as u128)) as u64;
+                (inv.wrapping_neg(), r2)
+            } else {
+                (0, 0)
+            };
             Ok(Self {
                 p,
                 nbits: 64 - p.leading_zeros() as usize,
@@ -75,6 +194,8 @@ impl Modulus {
                 barrett_lo: barrett as u64,
                 leading_zeros: p.leading_zeros(),
                 supports_opt: primes::supports_opt(p),
+                montgomery_neg_inv,
+                montgomery_r2,
                 distribution: Uniform::from(0..p),
                 arch: Arch::new(),
             })
@@ -219,17 +340,160 @@ impl Modulus {
         r
     }
 
+    /// Returns whether the modulus supports the Montgomery representation,
+    /// i.e. whether it is odd.
+    pub const fn supports_montgomery(&self) -> bool {
+        self.p & 1 == 1
+    }
+
+    /// Montgomery reduction of x in constant time: returns x * 2^(-64) mod p.
+    /// The modulus must be odd.
+    ///
+    /// Aborts if x >= p * 2^64 or if the modulus is even in debug mode.
+    const fn montgomery_reduce(&self, x: u128) -> u64 {
+        debug_assert!(self.supports_montgomery());
+        debug_assert!(x < (self.p as u128) << 64);
+
+        let m = (x as u64).wrapping_mul(self.montgomery_neg_inv);
+        // Since p < 2^62, the sum is smaller than 2^127 and its high word is
+        // smaller than 2 * p.
+        let r = ((x + (m as u128) * (self.p as u128)) >> 64) as u64;
+        Self::reduce1(r, self.p)
+    }
+
+    /// Convert a into its Montgomery representation a * 2^64 mod p in constant
+    /// time. Returns None if the modulus is even.
+    ///
+    /// Aborts if a >= p in debug mode.
+    pub const fn to_montgomery(&self, a: u64) -> Option<u64> {
+        debug_assert!(a < self.p);
+        if self.supports_montgomery() {
+            Some(self.montgomery_reduce((a as u128) * (self.montgomery_r2 as u128)))
+        } else {
+            None
+        }
+    }
+
+    /// Convert a from its Montgomery representation in constant time. Returns
+    /// None if the modulus is even.
+    ///
+    /// Aborts if a >= p in debug mode.
+    pub const fn from_montgomery(&self, a: u64) -> Option<u64> {
+        debug_assert!(a < self.p);
+        if self.supports_montgomery() {
+            Some(self.montgomery_reduce(a as u128))
+        } else {
+            None
+        }
+    }
+
+    /// Montgomery multiplication of a and b in constant time. When a and b are
+    /// in Montgomery representation, so is the output; when only b is, the
+    /// output is the product of a by the value represented by b. Returns None
+    /// if the modulus is even.
+    ///
+    /// Aborts if a >= p or b >= p in debug mode.
+    pub const fn mul_montgomery(&self, a: u64, b: u64) -> Option<u64> {
+        debug_assert!(a < self.p && b < self.p);
+        if self.supports_montgomery() {
+            Some(self.montgomery_reduce((a as u128) * (b as u128)))
+        } else {
+            None
+        }
+    }
+
+    /// Returns an error if the modulus does not support the Montgomery
+    /// representation.
+    fn check_montgomery(&self) -> Result<()> {
+        if self.supports_montgomery() {
+            Ok(())
+        } else {
+            Err(Error::InvalidModulus(self.p))
+        }
+    }
+
+    /// Convert a vector into the Montgomery representation in place in
+    /// constant time. Returns an error if the modulus is even.
+    ///
+    /// Aborts if any of the values of the vector is >= p in debug mode.
+    pub fn to_montgomery_vec(&self, a: &mut [u64]) -> Result<()> {
+        self.check_montgomery()?;
+        let r2 = self.montgomery_r2 as u128;
+        self.arch.dispatch(|| {
+            a.iter_mut().for_each(|ai| {
+                debug_assert!(*ai < self.p);
+                *ai = self.montgomery_reduce((*ai as u128) * r2)
+            })
+        });
+        Ok(())
+    }
+
+    /// Convert a vector from the Montgomery representation in place in
+    /// constant time. Returns an error if the modulus is even.
+    ///
+    /// Aborts if any of the values of the vector is >= p in debug mode.
+    pub fn from_montgomery_vec(&self, a: &mut [u64]) -> Result<()> {
+        self.check_montgomery()?;
+        self.arch.dispatch(|| {
+            a.iter_mut().for_each(|ai| {
+                debug_assert!(*ai < self.p);
+                *ai = self.montgomery_reduce(*ai as u128)
+            })
+        });
+        Ok(())
+    }
+
+    /// Montgomery multiplication of vectors in place in constant time, see
+    /// [`Modulus::mul_montgomery`]. Returns an error if the modulus is even.
+    ///
+    /// Aborts if a and b differ in size, or if any of their values is >= p in
+    /// debug mode.
+    pub fn mul_montgomery_vec(&self, a: &mut [u64], b: &[u64]) -> Result<()> {
+        debug_assert_eq!(a.len(), b.len());
+        self.check_montgomery()?;
+        self.arch.dispatch(|| {
+            map3_lanes(a, b, b, |ai, bi, _| {
+                debug_assert!(ai < self.p && bi < self.p);
+                self.montgomery_reduce((ai as u128) * (bi as u128))
+            })
+        });
+        Ok(())
+    }
+
     /// Modular addition of vectors in place in constant time.
     ///
     /// Aborts if a and b differ in size, and if any of their values is >= p in
     /// debug mode.
     pub fn add_vec(&self, a: &mut [u64], b: &[u64]) {
         debug_assert_eq!(a.len(), b.len());
-        self.arch.dispatch(|| {
-            izip!(a.iter_mut(), b.iter()).for_each(|(ai, bi)| *ai = self.add(*ai, *bi))
+        cross_checked!("add_vec", a, |i, ai| reference::add(self.p, ai, b[i]), {
+            #[cfg(target_arch = "aarch64")]
+            if neon::is_available() {
+                return unsafe { neon::add_vec(self, a, b) };
+            }
+            self.arch.dispatch(|| {
+                izip!(a.iter_mut(), b.iter()).for_each(|(ai, bi)| *ai = self.add(*ai, *bi))
+            })
         })
     }
 
+    /// Modular addition of a vector streamed in chunks to a vector, in place in
+    /// constant time: the chunks are added to consecutive parts of a, so that
+    /// a long vector can be added without materializing it.
+    ///
+    /// Aborts if the chunks hold more values than a, and if any of their values
+    /// is >= p in debug mode.
+    pub fn add_chunks<'b, I>(&self, a: &mut [u64], chunks: I)
+    where
+        I: IntoIterator<Item = &'b [u64]>,
+    {
+        let mut offset = 0;
+        for chunk in chunks {
+            self.add_vec(&mut a[offset..offset + chunk.len()], chunk);
+            offset += chunk.len();
+        }
+    }
+
     /// Modular addition of vectors in place in variable time.
     /// Aborts if a and b differ in size, and if any of their values is >= p in
     /// debug mode.
@@ -249,32 +513,34 @@ impl Modulus {
             };
         }
 
-        if n % 16 == 0 {
-            self.arch.dispatch(|| {
-                for i in 0..n / 16 {
-                    add_at!(16 * i);
-                    add_at!(16 * i + 1);
-                    add_at!(16 * i + 2);
-                    add_at!(16 * i + 3);
-                    add_at!(16 * i + 4);
-                    add_at!(16 * i + 5);
-                    add_at!(16 * i + 6);
-                    add_at!(16 * i + 7);
-                    add_at!(16 * i + 8);
-                    add_at!(16 * i + 9);
-                    add_at!(16 * i + 10);
-                    add_at!(16 * i + 11);
-                    add_at!(16 * i + 12);
-                    add_at!(16 * i + 13);
-                    add_at!(16 * i + 14);
-                    add_at!(16 * i + 15);
-                }
-            })
-        } else {
-            self.arch.dispatch(|| {
-                izip!(a.iter_mut(), b.iter()).for_each(|(ai, bi)| *ai = self.add_vt(*ai, *bi))
-            })
-        }
+        cross_checked!("add_vec_vt", a, |i, ai| reference::add(self.p, ai, b[i]), {
+            if n % 16 == 0 {
+                self.arch.dispatch(|| {
+                    for i in 0..n / 16 {
+                        add_at!(16 * i);
+                        add_at!(16 * i + 1);
+                        add_at!(16 * i + 2);
+                        add_at!(16 * i + 3);
+                        add_at!(16 * i + 4);
+                        add_at!(16 * i + 5);
+                        add_at!(16 * i + 6);
+                        add_at!(16 * i + 7);
+                        add_at!(16 * i + 8);
+                        add_at!(16 * i + 9);
+                        add_at!(16 * i + 10);
+                        add_at!(16 * i + 11);
+                        add_at!(16 * i + 12);
+                        add_at!(16 * i + 13);
+                        add_at!(16 * i + 14);
+                        add_at!(16 * i + 15);
+                    }
+                })
+            } else {
+                self.arch.dispatch(|| {
+                    izip!(a.iter_mut(), b.iter()).for_each(|(ai, bi)| *ai = self.add_vt(*ai, *bi))
+                })
+            }
+        })
     }
 
     /// Modular subtraction of vectors in place in constant time.
@@ -283,8 +549,14 @@ impl Modulus {
     /// debug mode.
     pub fn sub_vec(&self, a: &mut [u64], b: &[u64]) {
         debug_assert_eq!(a.len(), b.len());
-        self.arch.dispatch(|| {
-            izip!(a.iter_mut(), b.iter()).for_each(|(ai, bi)| *ai = self.sub(*ai, *bi))
+        cross_checked!("sub_vec", a, |i, ai| reference::sub(self.p, ai, b[i]), {
+            #[cfg(target_arch = "aarch64")]
+            if neon::is_available() {
+                return unsafe { neon::sub_vec(self, a, b) };
+            }
+            self.arch.dispatch(|| {
+                izip!(a.iter_mut(), b.iter()).for_each(|(ai, bi)| *ai = self.sub(*ai, *bi))
+            })
         })
     }
 
@@ -307,32 +579,34 @@ impl Modulus {
             };
         }
 
-        if n % 16 == 0 {
-            self.arch.dispatch(|| {
-                for i in 0..n / 16 {
-                    sub_at!(16 * i);
-                    sub_at!(16 * i + 1);
-                    sub_at!(16 * i + 2);
-                    sub_at!(16 * i + 3);
-                    sub_at!(16 * i + 4);
-                    sub_at!(16 * i + 5);
-                    sub_at!(16 * i + 6);
-                    sub_at!(16 * i + 7);
-                    sub_at!(16 * i + 8);
-                    sub_at!(16 * i + 9);
-                    sub_at!(16 * i + 10);
-                    sub_at!(16 * i + 11);
-                    sub_at!(16 * i + 12);
-                    sub_at!(16 * i + 13);
-                    sub_at!(16 * i + 14);
-                    sub_at!(16 * i + 15);
-                }
-            })
-        } else {
-            self.arch.dispatch(|| {
-                izip!(a.iter_mut(), b.iter()).for_each(|(ai, bi)| *ai = self.sub_vt(*ai, *bi))
-            })
-        }
+        cross_checked!("sub_vec_vt", a, |i, ai| reference::sub(self.p, ai, b[i]), {
+            if n % 16 == 0 {
+                self.arch.dispatch(|| {
+                    for i in 0..n / 16 {
+                        sub_at!(16 * i);
+                        sub_at!(16 * i + 1);
+                        sub_at!(16 * i + 2);
+                        sub_at!(16 * i + 3);
+                        sub_at!(16 * i + 4);
+                        sub_at!(16 * i + 5);
+                        sub_at!(16 * i + 6);
+                        sub_at!(16 * i + 7);
+                        sub_at!(16 * i + 8);
+                        sub_at!(16 * i + 9);
+                        sub_at!(16 * i + 10);
+                        sub_at!(16 * i + 11);
+                        sub_at!(16 * i + 12);
+                        sub_at!(16 * i + 13);
+                        sub_at!(16 * i + 14);
+                        sub_at!(16 * i + 15);
+                    }
+                })
+            } else {
+                self.arch.dispatch(|| {
+                    izip!(a.iter_mut(), b.iter()).for_each(|(ai, bi)| *ai = self.sub_vt(*ai, *bi))
+                })
+            }
+        })
     }
 
     /// Modular multiplication of vectors in place in constant time.
@@ -342,14 +616,35 @@ impl Modulus {
     pub fn mul_vec(&self, a: &mut [u64], b: &[u64]) {
         debug_assert_eq!(a.len(), b.len());
 
-        if self.supports_opt {
-            self.arch.dispatch(|| {
-                izip!(a.iter_mut(), b.iter()).for_each(|(ai, bi)| *ai = self.mul_opt(*ai, *bi))
-            })
-        } else {
-            self.arch.dispatch(|| {
-                izip!(a.iter_mut(), b.iter()).for_each(|(ai, bi)| *ai = self.mul(*ai, *bi))
-            })
+        cross_checked!("mul_vec", a, |i, ai| reference::mul(self.p, ai, b[i]), {
+            #[cfg(target_arch = "aarch64")]
+            if neon::is_available() {
+                return unsafe { neon::mul_vec(self, a, b) };
+            }
+            if self.supports_opt {
+                self.arch
+                    .dispatch(|| map3_lanes(a, b, b, |ai, bi, _| self.mul_opt(ai, bi)))
+            } else {
+                self.arch
+                    .dispatch(|| map3_lanes(a, b, b, |ai, bi, _| self.mul(ai, bi)))
+            }
+        })
+    }
+
+    /// Modular multiplication of a vector by a vector streamed in chunks, in
+    /// place in constant time: the chunks multiply consecutive parts of a, so
+    /// that a long vector can be multiplied without materializing it.
+    ///
+    /// Aborts if the chunks hold more values than a, and if any of their values
+    /// is >= p in debug mode.
+    pub fn mul_chunks<'b, I>(&self, a: &mut [u64], chunks: I)
+    where
+        I: IntoIterator<Item = &'b [u64]>,
+    {
+        let mut offset = 0;
+        for chunk in chunks {
+            self.mul_vec(&mut a[offset..offset + chunk.len()], chunk);
+            offset += chunk.len();
         }
     }
 
@@ -358,10 +653,15 @@ impl Modulus {
     /// Aborts if any of the values in a is >= p in debug mode.
     pub fn scalar_mul_vec(&self, a: &mut [u64], b: u64) {
         let b_shoup = self.shoup(b);
-        self.arch.dispatch(|| {
-            a.iter_mut()
-                .for_each(|ai| *ai = self.mul_shoup(*ai, b, b_shoup))
-        })
+        cross_checked!(
+            "scalar_mul_vec",
+            a,
+            |_i, ai| reference::mul(self.p, ai, b),
+            self.arch.dispatch(|| {
+                a.iter_mut()
+                    .for_each(|ai| *ai = self.mul_shoup(*ai, b, b_shoup))
+            })
+        )
     }
 
     /// Modular scalar multiplication of vectors in place in variable time.
@@ -372,10 +672,17 @@ impl Modulus {
     /// about the values being multiplied.
     pub unsafe fn scalar_mul_vec_vt(&self, a: &mut [u64], b: u64) {
         let b_shoup = self.shoup(b);
-        self.arch.dispatch(|| {
-            a.iter_mut()
-                .for_each(|ai| *ai = self.mul_shoup_vt(*ai, b, b_shoup))
-        })
+        cross_checked!(
+            "scalar_mul_vec_vt",
+            a,
+            |_i, ai| reference::mul(self.p, ai, b),
+            {
+                self.arch.dispatch(|| {
+                    a.iter_mut()
+                        .for_each(|ai| *ai = self.mul_shoup_vt(*ai, b, b_shoup))
+                })
+            }
+        )
     }
 
     /// Modular multiplication of vectors in place in variable time.
@@ -388,23 +695,29 @@ impl Modulus {
     pub unsafe fn mul_vec_vt(&self, a: &mut [u64], b: &[u64]) {
         debug_assert_eq!(a.len(), b.len());
 
-        if self.supports_opt {
-            self.arch.dispatch(|| {
-                izip!(a.iter_mut(), b.iter()).for_each(|(ai, bi)| *ai = self.mul_opt_vt(*ai, *bi))
-            })
-        } else {
-            self.arch.dispatch(|| {
-                izip!(a.iter_mut(), b.iter()).for_each(|(ai, bi)| *ai = self.mul_vt(*ai, *bi))
-            })
-        }
+        cross_checked!("mul_vec_vt", a, |i, ai| reference::mul(self.p, ai, b[i]), {
+            if self.supports_opt {
+                self.arch
+                    .dispatch(|| map3_lanes(a, b, b, |ai, bi, _| self.mul_opt_vt(ai, bi)))
+            } else {
+                self.arch
+                    .dispatch(|| map3_lanes(a, b, b, |ai, bi, _| self.mul_vt(ai, bi)))
+            }
+        })
     }
 
     /// Compute the Shoup representation of a vector.
     ///
     /// Aborts if any of the values of the vector is >= p in debug mode.
     pub fn shoup_vec(&self, a: &[u64]) -> Vec<u64> {
-        self.arch
-            .dispatch(|| a.iter().map(|ai| self.shoup(*ai)).collect_vec())
+        let a_shoup = self
+            .arch
+            .dispatch(|| a.iter().map(|ai| self.shoup(*ai)).collect_vec());
+        #[cfg(all(feature = "cross-check", debug_assertions))]
+        reference::check("shoup_vec", &a_shoup, a, |_, ai| {
+            reference::shoup(self.p, ai)
+        });
+        a_shoup
     }
 
     /// Shoup modular multiplication of vectors in place in constant time.
@@ -416,10 +729,22 @@ impl Modulus {
         debug_assert_eq!(a.len(), b_shoup.len());
         debug_assert_eq!(&b_shoup, &self.shoup_vec(b));
 
-        self.arch.dispatch(|| {
-            izip!(a.iter_mut(), b.iter(), b_shoup.iter())
-                .for_each(|(ai, bi, bi_shoup)| *ai = self.mul_shoup(*ai, *bi, *bi_shoup))
-        })
+        cross_checked!(
+            "mul_shoup_vec",
+            a,
+            |i, ai| reference::mul(self.p, ai, b[i]),
+            {
+                #[cfg(target_arch = "aarch64")]
+                if neon::is_available() {
+                    return unsafe { neon::mul_shoup_vec(self, a, b, b_shoup) };
+                }
+                self.arch.dispatch(|| {
+                    map3_lanes(a, b, b_shoup, |ai, bi, bi_shoup| {
+                        self.mul_shoup(ai, bi, bi_shoup)
+                    })
+                })
+            }
+        )
     }
 
     /// Shoup modular multiplication of vectors in place in variable time.
@@ -434,9 +759,113 @@ impl Modulus {
         debug_assert_eq!(a.len(), b_shoup.len());
         debug_assert_eq!(&b_shoup, &self.shoup_vec(b));
 
+        cross_checked!(
+            "mul_shoup_vec_vt",
+            a,
+            |i, ai| reference::mul(self.p, ai, b[i]),
+            {
+                self.arch.dispatch(|| {
+                    map3_lanes(a, b, b_shoup, |ai, bi, bi_shoup| {
+                        self.mul_shoup_vt(ai, bi, bi_shoup)
+                    })
+                })
+            }
+        )
+    }
+
+    /// Modular addition of strided vectors in place in constant time, e.g. of
+    /// the columns of a matrix.
+    ///
+    /// Aborts if a and b differ in size, and if any of their values is >= p in
+    /// debug mode.
+    pub fn add_view(&self, mut a: ArrayViewMut1<u64>, b: ArrayView1<u64>) {
+        debug_assert_eq!(a.len(), b.len());
+        if let (Some(a), Some(b)) = (a.as_slice_mut(), b.as_slice()) {
+            return self.add_vec(a, b);
+        }
+        self.arch.dispatch(|| {
+            Zip::from(&mut a)
+                .and(&b)
+                .for_each(|ai, bi| *ai = self.add(*ai, *bi))
+        })
+    }
+
+    /// Modular subtraction of strided vectors in place in constant time.
+    ///
+    /// Aborts if a and b differ in size, and if any of their values is >= p in
+    /// debug mode.
+    pub fn sub_view(&self, mut a: ArrayViewMut1<u64>, b: ArrayView1<u64>) {
+        debug_assert_eq!(a.len(), b.len());
+        if let (Some(a), Some(b)) = (a.as_slice_mut(), b.as_slice()) {
+            return self.sub_vec(a, b);
+        }
+        self.arch.dispatch(|| {
+            Zip::from(&mut a)
+                .and(&b)
+                .for_each(|ai, bi| *ai = self.sub(*ai, *bi))
+        })
+    }
+
+    /// Modular multiplication of strided vectors in place in constant time.
+    ///
+    /// Aborts if a and b differ in size, and if any of their values is >= p in
+    /// debug mode.
+    pub fn mul_view(&self, mut a: ArrayViewMut1<u64>, b: ArrayView1<u64>) {
+        debug_assert_eq!(a.len(), b.len());
+        if let (Some(a), Some(b)) = (a.as_slice_mut(), b.as_slice()) {
+            return self.mul_vec(a, b);
+        }
         self.arch.dispatch(|| {
-            izip!(a.iter_mut(), b.iter(), b_shoup.iter())
-                .for_each(|(ai, bi, bi_shoup)| *ai = self.mul_shoup_vt(*ai, *bi, *bi_shoup))
+            Zip::from(&mut a)
+                .and(&b)
+                .for_each(|ai, bi| *ai = self.mul(*ai, *bi))
+        })
+    }
+
+    /// Modular scalar multiplication of a strided vector in place in constant
+    /// time.
+    ///
+    /// Aborts if any of the values in a is >= p in debug mode.
+    pub fn scalar_mul_view(&self, mut a: ArrayViewMut1<u64>, b: u64) {
+        if let Some(a) = a.as_slice_mut() {
+            return self.scalar_mul_vec(a, b);
+        }
+        let b_shoup = self.shoup(b);
+        self.arch
+            .dispatch(|| a.map_inplace(|ai| *ai = self.mul_shoup(*ai, b, b_shoup)))
+    }
+
+    /// Compute the Shoup representation of a strided vector.
+    ///
+    /// Aborts if any of the values of the vector is >= p in debug mode.
+    pub fn shoup_view(&self, a: ArrayView1<u64>) -> Vec<u64> {
+        self.arch
+            .dispatch(|| a.iter().map(|ai| self.shoup(*ai)).collect_vec())
+    }
+
+    /// Shoup modular multiplication of strided vectors in place in constant
+    /// time.
+    ///
+    /// Aborts if a, b and b_shoup differ in size, and if any of their values
+    /// is >= p in debug mode.
+    pub fn mul_shoup_view(
+        &self,
+        mut a: ArrayViewMut1<u64>,
+        b: ArrayView1<u64>,
+        b_shoup: ArrayView1<u64>,
+    ) {
+        debug_assert_eq!(a.len(), b.len());
+        debug_assert_eq!(a.len(), b_shoup.len());
+        if let (Some(a), Some(b), Some(b_shoup)) =
+            (a.as_slice_mut(), b.as_slice(), b_shoup.as_slice())
+        {
+            return self.mul_shoup_vec(a, b, b_shoup);
+        }
+        self.arch.dispatch(|| {
+            Zip::from(&mut a)
+                .and(&b)
+                .and(&b_shoup)
+                .for_each(|ai, bi, bi_shoup| *ai = self.mul_shoup(*ai, *bi, *bi_shoup))
         })
     }
 
@@ -446,8 +875,36 @@ impl Modulus {
             .dispatch(|| a.iter_mut().for_each(|ai| *ai = self.reduce(*ai)))
     }
 
+    /// Reduce the values of an iterator in constant time, lazily, e.g. to
+    /// encode a long stream of values without materializing it.
+    pub fn reduce_iter<'a, I>(&'a self, a: I) -> impl Iterator<Item = u64> + 'a
+    where
+        I: IntoIterator<Item = u64>,
+        I::IntoIter: 'a,
+    {
+        a.into_iter().map(|ai| self.reduce(ai))
+    }
+
+    /// Center a value modulo p as i64 in constant time, i.e. returns `a - p`
+    /// if `a >= p >> 1` and `a` otherwise.
+    ///
+    /// Aborts if a >= p in debug mode.
+    pub const fn center(&self, a: u64) -> i64 {
+        debug_assert!(a < self.p);
+
+        // Both a and p >> 1 are below 2^62, so the subtraction underflows
+        // (and sets the top bit) if and only if a < p >> 1.
+        let cond = (a.wrapping_sub(self.p >> 1) >> 63) == 0;
+        const_time_cond_select(a.wrapping_sub(self.p), a, cond) as i64
+    }
+
+    /// Center a vector in constant time.
+    pub fn center_vec(&self, a: &[u64]) -> Vec<i64> {
+        self.arch
+            .dispatch(|| a.iter().map(|ai| self.center(*ai)).collect_vec())
+    }
+
     /// Center a value modulo p as i64 in variable time.
-    /// TODO: To test and to make constant time?
     ///
     /// # Safety
     /// This function is not constant time and its timing may reveal information
@@ -585,6 +1042,34 @@ impl Modulus {
         }
     }
 
+    /// Modular inversion of a vector in place in variable time, using
+    /// Montgomery's trick to compute a single inversion.
+    ///
+    /// Returns None and leaves `a` unchanged if p is not prime or if any value
+    /// of `a` is 0. Aborts if any of the values in a is >= p in debug mode.
+    pub fn inv_vec(&self, a: &mut [u64]) -> core::option::Option<()> {
+        if a.is_empty() {
+            return Some(());
+        }
+
+        // prefix[i] = a[0] * ... * a[i - 1]
+        let mut prefix = Vec::with_capacity(a.len());
+        let mut acc = 1u64;
+        for ai in a.iter() {
+            debug_assert!(*ai < self.p);
+            prefix.push(acc);
+            acc = self.mul(acc, *ai);
+        }
+        let mut acc_inv = self.inv(acc)?;
+
+        for (ai, prefix_i) in a.iter_mut().zip(prefix.iter()).rev() {
+            let ai_inv = self.mul(acc_inv, *prefix_i);
+            acc_inv = self.mul(acc_inv, *ai);
+            *ai = ai_inv;
+        }
+        Some(())
+    }
+
     /// Modular reduction of a u128 in constant time.
     pub const fn reduce_u128(&self, a: u128) -> u64 {
         Self::reduce1(self.lazy_reduce_u128(a), self.p)
@@ -756,6 +1241,30 @@ impl Modulus {
         rng.sample_iter(self.distribution).take(size).collect_vec()
     }
 
+    /// Fill a vector with random values in place.
+    pub fn fill_random<R: RngCore + CryptoRng>(&self, a: &mut [u64], rng: &mut R) {
+        izip!(a.iter_mut(), rng.sample_iter(self.distribution)).for_each(|(ai, v)| *ai = v)
+    }
+
+    /// Returns a random vector, where each element is the reduction modulo p
+    /// of a 128-bit integer formed by two consecutive outputs of
+    /// `rng.next_u64()`, least significant first.
+    ///
+    /// Unlike [`Self::random_vec`], which uses rejection sampling, this
+    /// consumes exactly `2 * size` outputs of `rng`, so that the vector is a
+    /// simple function of the random stream that can be verifiably recomputed
+    /// from a seed. The statistical distance between each element and the
+    /// uniform distribution modulo p is at most `p / 2^128 < 2^(-64)`.
+    pub fn random_vec_wide<R: RngCore + CryptoRng>(&self, size: usize, rng: &mut R) -> Vec<u64> {
+        (0..size)
+            .map(|_| {
+                let lo = rng.next_u64() as u128;
+                let hi = rng.next_u64() as u128;
+                self.reduce_u128((hi << 64) | lo)
+            })
+            .collect_vec()
+    }
+
     /// Length of the serialization of a vector of size `size`.
     ///
     /// Panics if the size is not a multiple of 8.
@@ -784,9 +1293,11 @@ impl Modulus {
 mod tests {
     use super::{primes, Modulus};
     use itertools::{izip, Itertools};
+    use ndarray::Array2;
     use proptest::collection::vec as prop_vec;
     use proptest::prelude::{any, BoxedStrategy, Just, Strategy};
-    use rand::{thread_rng, RngCore};
+    use rand::{thread_rng, RngCore, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
     extern crate alloc;
     
     
@@ -874,6 +1385,18 @@ mod tests {
             //}
         }
 
+        #[test]
+        fn montgomery(p in valid_moduli(), mut a: u64, mut b: u64) {
+            prop_assume!(p.supports_montgomery());
+            a = p.reduce(a);
+            b = p.reduce(b);
+            let a_montgomery = p.to_montgomery(a).unwrap();
+            let b_montgomery = p.to_montgomery(b).unwrap();
+            prop_assert_eq!(p.from_montgomery(a_montgomery), Some(a));
+            prop_assert_eq!(p.from_montgomery(p.mul_montgomery(a_montgomery, b_montgomery).unwrap()), Some(p.mul(a, b)));
+            prop_assert_eq!(p.mul_montgomery(a, b_montgomery), Some(p.mul(a, b)));
+        }
+
         #[test]
         fn mul(p in valid_moduli(), mut a: u64, mut b: u64) {
             a = p.reduce(a);
@@ -964,6 +1487,28 @@ mod tests {
             prop_assert_eq!(a, izip!(b.iter(), c.iter()).map(|(bi, ci)| p.add(*bi, *ci)).collect_vec());
         }
 
+        #[test]
+        fn add_mul_chunks(p in valid_moduli(), (mut a, mut b) in vecs(), chunk_size in 1..10usize) {
+            p.reduce_vec(&mut a);
+            p.reduce_vec(&mut b);
+            let mut c = a.clone();
+            p.add_chunks(&mut c, b.chunks(chunk_size));
+            let mut expected = a.clone();
+            p.add_vec(&mut expected, &b);
+            prop_assert_eq!(&c, &expected);
+
+            c.clone_from(&a);
+            p.mul_chunks(&mut c, b.chunks(chunk_size));
+            expected.clone_from(&a);
+            p.mul_vec(&mut expected, &b);
+            prop_assert_eq!(&c, &expected);
+
+            // The chunks may only cover the first values.
+            c.clone_from(&a);
+            p.add_chunks(&mut c, b[..b.len() / 2].chunks(chunk_size));
+            prop_assert_eq!(&c[b.len() / 2..], &a[b.len() / 2..]);
+        }
+
         #[test]
         fn sub_vec(p in valid_moduli(), (mut a, mut b) in vecs()) {
             p.reduce_vec(&mut a);
@@ -1034,6 +1579,11 @@ mod tests {
             prop_assert!(izip!(a, b).all(|(ai, bi)| bi % *p == ai % *p));
         }
 
+        #[test]
+        fn reduce_iter(p in valid_moduli(), a: Vec<u64>) {
+            prop_assert_eq!(p.reduce_iter(a.iter().copied()).collect_vec(), p.reduce_vec_new(&a));
+        }
+
         #[test]
         fn reduce_vec_new(p in valid_moduli(), a: Vec<u64>) {
             let b = p.reduce_vec_new(&a);
@@ -1049,6 +1599,16 @@ mod tests {
             prop_assert_eq!(b, a.iter().map(|ai| p.reduce_i64(*ai)).collect_vec());
         }
 
+        #[test]
+        fn center_vec(p in valid_moduli(), mut a: Vec<u64>) {
+            p.reduce_vec(&mut a);
+            let b = p.center_vec(&a);
+            prop_assert_eq!(&b, &unsafe { p.center_vec_vt(&a) });
+            let (lo, hi) = (-(((*p + 1) >> 1) as i64), (*p >> 1) as i64);
+            prop_assert!(b.iter().all(|bi| (lo..hi).contains(bi)));
+            prop_assert_eq!(p.reduce_vec_i64(&b), a);
+        }
+
         #[test]
         fn neg_vec(p in valid_moduli(), mut a: Vec<u64>) {
             p.reduce_vec(&mut a);
@@ -1070,11 +1630,33 @@ mod tests {
             let w = p.random_vec(size, &mut rng);
             prop_assert_eq!(w.len(), size);
 
+            let mut x = alloc::vec![0u64; size];
+            p.fill_random(&mut x, &mut rng);
+            prop_assert!(x.iter().all(|xi| *xi < *p));
+
             if (*p).leading_zeros() <= 30 {
+                prop_assert_ne!(&x, &w);
                 prop_assert_ne!(v, w); // This will hold with probability at least 2^(-30)
             }
         }
 
+        #[test]
+        fn random_vec_wide(p in valid_moduli(), size in 1..1000usize, seed: [u8; 32]) {
+            let v = p.random_vec_wide(size, &mut ChaCha8Rng::from_seed(seed));
+            prop_assert_eq!(v.len(), size);
+            prop_assert!(v.iter().all(|vi| *vi < *p));
+
+            let mut rng = ChaCha8Rng::from_seed(seed);
+            let expected = (0..size)
+                .map(|_| {
+                    let lo = rng.next_u64() as u128;
+                    let hi = rng.next_u64() as u128;
+                    (((hi << 64) | lo) % (*p as u128)) as u64
+                })
+                .collect_vec();
+            prop_assert_eq!(v, expected);
+        }
+
         #[test]
         fn serialize(p in valid_moduli(), mut a in prop_vec(any::<u64>(), 8)) {
             p.reduce_vec(&mut a);
@@ -1157,7 +1739,130 @@ mod tests {
         }
     }
 
+    #[test]
+    fn inv_vec() {
+        let mut rng = rand::thread_rng();
+
+        for p in [2u64, 3, 17, 1987, 4611686018326724609] {
+            let q = Modulus::new(p).unwrap();
+
+            let mut a: Vec<u64> = Vec::new();
+            assert!(q.inv_vec(&mut a).is_some());
+
+            for size in [1, 2, 17] {
+                let a = (0..size)
+                    .map(|_| 1 + rng.next_u64() % (p - 1))
+                    .collect_vec();
+                let mut b = a.clone();
+                assert!(q.inv_vec(&mut b).is_some());
+                assert_eq!(b, a.iter().map(|ai| q.inv(*ai).unwrap()).collect_vec());
+
+                let mut c = a.clone();
+                c[size / 2] = 0;
+                let d = c.clone();
+                assert!(q.inv_vec(&mut c).is_none());
+                assert_eq!(c, d);
+            }
+        }
+
+        let q = Modulus::new(16).unwrap();
+        assert!(q.inv_vec(&mut [3, 5]).is_none());
+    }
+
+    #[test]
+    fn montgomery_vec() {
+        let mut rng = thread_rng();
+        for p in [3u64, 17, 1987, 4611686018326724609] {
+            let q = Modulus::new(p).unwrap();
+            assert!(q.supports_montgomery());
+            let a = q.random_vec(37, &mut rng);
+            let b = q.random_vec(37, &mut rng);
+            let mut expected = a.clone();
+            q.mul_vec(&mut expected, &b);
+
+            let mut a_montgomery = a.clone();
+            let mut b_montgomery = b.clone();
+            q.to_montgomery_vec(&mut a_montgomery).unwrap();
+            q.to_montgomery_vec(&mut b_montgomery).unwrap();
+            q.mul_montgomery_vec(&mut a_montgomery, &b_montgomery)
+                .unwrap();
+            q.from_montgomery_vec(&mut a_montgomery).unwrap();
+            assert_eq!(a_montgomery, expected);
+
+            let mut c = a.clone();
+            q.mul_montgomery_vec(&mut c, &b_montgomery).unwrap();
+            assert_eq!(c, expected);
+        }
+
+        // Even moduli do not support the Montgomery representation.
+        let q = Modulus::new(1 << 20).unwrap();
+        assert!(!q.supports_montgomery());
+        assert!(q.to_montgomery(3).is_none());
+        assert!(q.from_montgomery(3).is_none());
+        assert!(q.mul_montgomery(3, 5).is_none());
+        let mut a = [3, 5];
+        assert!(q.to_montgomery_vec(&mut a).is_err());
+        assert!(q.from_montgomery_vec(&mut a).is_err());
+        assert!(q.mul_montgomery_vec(&mut a, &[7, 11]).is_err());
+        assert_eq!(a, [3, 5]);
+    }
+
     // TODO: Make a proptest.
+    #[test]
+    fn views() {
+        let mut rng = thread_rng();
+        for p in [2u64, 3, 17, 1987, 4611686018326724609] {
+            let q = Modulus::new(p).unwrap();
+            let a = Array2::from_shape_vec((8, 3), q.random_vec(24, &mut rng)).unwrap();
+            let b = Array2::from_shape_vec((8, 3), q.random_vec(24, &mut rng)).unwrap();
+            let b_shoup = b.mapv(|bi| q.shoup(bi));
+            let c = q.random_vec(1, &mut rng)[0];
+
+            // Columns are strided views of the matrices.
+            for j in 0..3 {
+                let (aj, bj) = (a.column(j).to_vec(), b.column(j).to_vec());
+                let mut expected = aj.clone();
+                let mut out = a.clone();
+
+                q.add_vec(&mut expected, &bj);
+                q.add_view(out.column_mut(j), b.column(j));
+                assert_eq!(out.column(j).to_vec(), expected);
+
+                expected.clone_from(&aj);
+                out.assign(&a);
+                q.sub_vec(&mut expected, &bj);
+                q.sub_view(out.column_mut(j), b.column(j));
+                assert_eq!(out.column(j).to_vec(), expected);
+
+                expected.clone_from(&aj);
+                out.assign(&a);
+                q.mul_vec(&mut expected, &bj);
+                q.mul_view(out.column_mut(j), b.column(j));
+                assert_eq!(out.column(j).to_vec(), expected);
+
+                out.assign(&a);
+                q.mul_shoup_view(out.column_mut(j), b.column(j), b_shoup.column(j));
+                assert_eq!(out.column(j).to_vec(), expected);
+
+                expected.clone_from(&aj);
+                out.assign(&a);
+                q.scalar_mul_vec(&mut expected, c);
+                q.scalar_mul_view(out.column_mut(j), c);
+                assert_eq!(out.column(j).to_vec(), expected);
+
+                assert_eq!(q.shoup_view(b.column(j)), q.shoup_vec(&bj));
+                assert_eq!(out.column((j + 1) % 3), a.column((j + 1) % 3));
+            }
+
+            // Contiguous views use the vectorized kernels.
+            let mut out = a.clone();
+            let mut expected = a.row(0).to_vec();
+            q.add_vec(&mut expected, b.row(0).as_slice().unwrap());
+            q.add_view(out.row_mut(0), b.row(0));
+            assert_eq!(out.row(0).to_vec(), expected);
+        }
+    }
+
     #[test]
     fn inv() {
         let ntests = 100;
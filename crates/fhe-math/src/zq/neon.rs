@@ -0,0 +1,324 @@
+//! NEON kernels for aarch64.
+//!
+//! NEON has no 64-bit multiplication, so the 128-bit products of the lanes are
+//! computed from the products of their 32-bit halves, and the Barrett and
+//! Shoup reductions are performed on these products in the 128-bit registers.
+//!
+//! The kernels must only be called when [`is_available`] returns true.
+
+use super::Modulus;
+use core::arch::aarch64::*;
+use itertools::izip;
+
+/// Returns whether the NEON kernels can be used on this CPU.
+pub(crate) fn is_available() -> bool {
+    pulp::aarch64::Neon::is_available()
+}
+
+/// Reduce the two lanes of x modulo p in constant time.
+/// The lanes must be < 2 * p.
+#[inline(always)]
+unsafe fn reduce1(x: uint64x2_t, p: uint64x2_t) -> uint64x2_t {
+    vsubq_u64(x, vandq_u64(vcgeq_u64(x, p), p))
+}
+
+/// Returns the low and high words of the 128-bit products of the lanes of a
+/// and b.
+#[inline(always)]
+unsafe fn mul_wide(a: uint64x2_t, b: uint64x2_t) -> (uint64x2_t, uint64x2_t) {
+    let (a0, a1) = (vmovn_u64(a), vshrn_n_u64::<32>(a));
+    let (b0, b1) = (vmovn_u64(b), vshrn_n_u64::<32>(b));
+    let mask = vdupq_n_u64(u32::MAX as u64);
+    let p00 = vmull_u32(a0, b0);
+    let p01 = vmull_u32(a0, b1);
+    let p10 = vmull_u32(a1, b0);
+    let p11 = vmull_u32(a1, b1);
+    // The middle column is smaller than 3 * 2^32, so that it does not overflow.
+    let mid = vaddq_u64(
+        vaddq_u64(vshrq_n_u64::<32>(p00), vandq_u64(p01, mask)),
+        vandq_u64(p10, mask),
+    );
+    let lo = vorrq_u64(vshlq_n_u64::<32>(mid), vandq_u64(p00, mask));
+    let hi = vaddq_u64(
+        vaddq_u64(p11, vshrq_n_u64::<32>(mid)),
+        vaddq_u64(vshrq_n_u64::<32>(p01), vshrq_n_u64::<32>(p10)),
+    );
+    (lo, hi)
+}
+
+/// Returns the low words of the products of the lanes of a and b.
+#[inline(always)]
+unsafe fn mul_lo(a: uint64x2_t, b: uint64x2_t) -> uint64x2_t {
+    let (a0, a1) = (vmovn_u64(a), vshrn_n_u64::<32>(a));
+    let (b0, b1) = (vmovn_u64(b), vshrn_n_u64::<32>(b));
+    let cross = vaddq_u64(vmull_u32(a0, b1), vmull_u32(a1, b0));
+    vaddq_u64(vmull_u32(a0, b0), vshlq_n_u64::<32>(cross))
+}
+
+/// Returns the sum of the lanes of x and y, and the carries of the sums.
+#[inline(always)]
+unsafe fn add_carry(x: uint64x2_t, y: uint64x2_t) -> (uint64x2_t, uint64x2_t) {
+    let s = vaddq_u64(x, y);
+    (s, vshrq_n_u64::<63>(vcltq_u64(s, x)))
+}
+
+/// Constants of a [`Modulus`] broadcast in registers.
+#[derive(Clone, Copy)]
+struct Constants {
+    p: uint64x2_t,
+    barrett_lo: uint64x2_t,
+    barrett_hi: uint64x2_t,
+    shift: int64x2_t,
+    shift_back: int64x2_t,
+}
+
+impl Constants {
+    #[inline(always)]
+    unsafe fn new(q: &Modulus) -> Self {
+        Self {
+            p: vdupq_n_u64(q.p),
+            barrett_lo: vdupq_n_u64(q.barrett_lo),
+            barrett_hi: vdupq_n_u64(q.barrett_hi),
+            shift: vdupq_n_s64(q.leading_zeros as i64),
+            shift_back: vdupq_n_s64(q.leading_zeros as i64 - 64),
+        }
+    }
+
+    /// Lazy Barrett reduction of the 128-bit lanes `(lo, hi)`, see
+    /// [`Modulus::lazy_reduce_u128`].
+    #[inline(always)]
+    unsafe fn lazy_reduce_u128(&self, lo: uint64x2_t, hi: uint64x2_t) -> uint64x2_t {
+        let (_, p_lo_lo) = mul_wide(lo, self.barrett_lo);
+        let (l1, h1) = mul_wide(hi, self.barrett_lo);
+        let (l2, h2) = mul_wide(lo, self.barrett_hi);
+        let (s, c1) = add_carry(l1, l2);
+        let (_, c2) = add_carry(s, p_lo_lo);
+        let q = vaddq_u64(
+            vaddq_u64(h1, h2),
+            vaddq_u64(vaddq_u64(c1, c2), mul_lo(hi, self.barrett_hi)),
+        );
+        vsubq_u64(lo, mul_lo(q, self.p))
+    }
+
+    /// Lazy optimized reduction of the 128-bit lanes `(lo, hi)`, see
+    /// [`Modulus::lazy_reduce_opt_u128`].
+    #[inline(always)]
+    unsafe fn lazy_reduce_opt_u128(&self, lo: uint64x2_t, hi: uint64x2_t) -> uint64x2_t {
+        // The shift right by 64 bits when the modulus has no leading zeros
+        // outputs 0.
+        let lo_shifted = vshlq_u64(lo, self.shift);
+        let hi_shifted = vorrq_u64(vshlq_u64(hi, self.shift), vshlq_u64(lo, self.shift_back));
+        let (l, h) = mul_wide(hi, self.barrett_lo);
+        let (_, c) = add_carry(l, lo_shifted);
+        let q = vaddq_u64(vaddq_u64(h, hi_shifted), c);
+        vsubq_u64(lo, mul_lo(q, self.p))
+    }
+
+    /// Lazy Shoup multiplication of the lanes, see [`Modulus::lazy_mul_shoup`].
+    #[inline(always)]
+    unsafe fn lazy_mul_shoup(
+        &self,
+        a: uint64x2_t,
+        b: uint64x2_t,
+        b_shoup: uint64x2_t,
+    ) -> uint64x2_t {
+        let (_, q) = mul_wide(a, b_shoup);
+        vsubq_u64(mul_lo(a, b), mul_lo(q, self.p))
+    }
+}
+
+/// Modular addition of vectors in place in constant time.
+#[target_feature(enable = "neon")]
+pub(crate) unsafe fn add_vec(q: &Modulus, a: &mut [u64], b: &[u64]) {
+    let p = vdupq_n_u64(q.p);
+    let mut a_chunks = a.chunks_exact_mut(2);
+    let mut b_chunks = b.chunks_exact(2);
+    for (ai, bi) in izip!(a_chunks.by_ref(), b_chunks.by_ref()) {
+        let x = vaddq_u64(vld1q_u64(ai.as_ptr()), vld1q_u64(bi.as_ptr()));
+        vst1q_u64(ai.as_mut_ptr(), reduce1(x, p));
+    }
+    izip!(a_chunks.into_remainder(), b_chunks.remainder())
+        .for_each(|(ai, bi)| *ai = q.add(*ai, *bi))
+}
+
+/// Modular subtraction of vectors in place in constant time.
+#[target_feature(enable = "neon")]
+pub(crate) unsafe fn sub_vec(q: &Modulus, a: &mut [u64], b: &[u64]) {
+    let p = vdupq_n_u64(q.p);
+    let mut a_chunks = a.chunks_exact_mut(2);
+    let mut b_chunks = b.chunks_exact(2);
+    for (ai, bi) in izip!(a_chunks.by_ref(), b_chunks.by_ref()) {
+        let x = vsubq_u64(vaddq_u64(vld1q_u64(ai.as_ptr()), p), vld1q_u64(bi.as_ptr()));
+        vst1q_u64(ai.as_mut_ptr(), reduce1(x, p));
+    }
+    izip!(a_chunks.into_remainder(), b_chunks.remainder())
+        .for_each(|(ai, bi)| *ai = q.sub(*ai, *bi))
+}
+
+/// Modular multiplication of vectors in place in constant time.
+#[target_feature(enable = "neon")]
+pub(crate) unsafe fn mul_vec(q: &Modulus, a: &mut [u64], b: &[u64]) {
+    let k = Constants::new(q);
+    let mut a_chunks = a.chunks_exact_mut(2);
+    let mut b_chunks = b.chunks_exact(2);
+    for (ai, bi) in izip!(a_chunks.by_ref(), b_chunks.by_ref()) {
+        let (lo, hi) = mul_wide(vld1q_u64(ai.as_ptr()), vld1q_u64(bi.as_ptr()));
+        let r = if q.supports_opt {
+            k.lazy_reduce_opt_u128(lo, hi)
+        } else {
+            k.lazy_reduce_u128(lo, hi)
+        };
+        vst1q_u64(ai.as_mut_ptr(), reduce1(r, k.p));
+    }
+    izip!(a_chunks.into_remainder(), b_chunks.remainder())
+        .for_each(|(ai, bi)| *ai = q.mul(*ai, *bi))
+}
+
+/// Shoup modular multiplication of vectors in place in constant time.
+#[target_feature(enable = "neon")]
+pub(crate) unsafe fn mul_shoup_vec(q: &Modulus, a: &mut [u64], b: &[u64], b_shoup: &[u64]) {
+    let k = Constants::new(q);
+    let mut a_chunks = a.chunks_exact_mut(2);
+    let mut b_chunks = b.chunks_exact(2);
+    let mut b_shoup_chunks = b_shoup.chunks_exact(2);
+    for (ai, bi, bi_shoup) in izip!(
+        a_chunks.by_ref(),
+        b_chunks.by_ref(),
+        b_shoup_chunks.by_ref()
+    ) {
+        let r = k.lazy_mul_shoup(
+            vld1q_u64(ai.as_ptr()),
+            vld1q_u64(bi.as_ptr()),
+            vld1q_u64(bi_shoup.as_ptr()),
+        );
+        vst1q_u64(ai.as_mut_ptr(), reduce1(r, k.p));
+    }
+    izip!(
+        a_chunks.into_remainder(),
+        b_chunks.remainder(),
+        b_shoup_chunks.remainder()
+    )
+    .for_each(|(ai, bi, bi_shoup)| *ai = q.mul_shoup(*ai, *bi, *bi_shoup))
+}
+
+/// Forward NTT butterflies on the pairs `(x[i], y[i])` with the twiddle
+/// factor `w`, with inputs and outputs smaller than `4 * p`.
+///
+/// Aborts if x and y differ in size or if their size is odd in debug mode.
+#[target_feature(enable = "neon")]
+pub(crate) unsafe fn butterflies(q: &Modulus, x: &mut [u64], y: &mut [u64], w: u64, w_shoup: u64) {
+    debug_assert_eq!(x.len(), y.len());
+    debug_assert_eq!(x.len() % 2, 0);
+
+    let k = Constants::new(q);
+    let (w, w_shoup) = (vdupq_n_u64(w), vdupq_n_u64(w_shoup));
+    let p_twice = vdupq_n_u64(2 * q.p);
+    for (xi, yi) in izip!(x.chunks_exact_mut(2), y.chunks_exact_mut(2)) {
+        let u = reduce1(vld1q_u64(xi.as_ptr()), p_twice);
+        let t = k.lazy_mul_shoup(vld1q_u64(yi.as_ptr()), w, w_shoup);
+        vst1q_u64(yi.as_mut_ptr(), vsubq_u64(vaddq_u64(u, p_twice), t));
+        vst1q_u64(xi.as_mut_ptr(), vaddq_u64(u, t));
+    }
+}
+
+/// Inverse NTT butterflies on the pairs `(x[i], y[i])` with the twiddle
+/// factor `z`, with inputs and outputs smaller than `2 * p`.
+///
+/// Aborts if x and y differ in size or if their size is odd in debug mode.
+#[target_feature(enable = "neon")]
+pub(crate) unsafe fn inv_butterflies(
+    q: &Modulus,
+    x: &mut [u64],
+    y: &mut [u64],
+    z: u64,
+    z_shoup: u64,
+) {
+    debug_assert_eq!(x.len(), y.len());
+    debug_assert_eq!(x.len() % 2, 0);
+
+    let k = Constants::new(q);
+    let (z, z_shoup) = (vdupq_n_u64(z), vdupq_n_u64(z_shoup));
+    let p_twice = vdupq_n_u64(2 * q.p);
+    for (xi, yi) in izip!(x.chunks_exact_mut(2), y.chunks_exact_mut(2)) {
+        let u = vld1q_u64(xi.as_ptr());
+        let v = vld1q_u64(yi.as_ptr());
+        vst1q_u64(xi.as_mut_ptr(), reduce1(vaddq_u64(u, v), p_twice));
+        let t = vsubq_u64(vaddq_u64(u, p_twice), v);
+        vst1q_u64(yi.as_mut_ptr(), k.lazy_mul_shoup(t, z, z_shoup));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        add_vec, butterflies, inv_butterflies, is_available, mul_shoup_vec, mul_vec, sub_vec,
+    };
+    use crate::zq::{primes, Modulus};
+    use itertools::izip;
+    use rand::thread_rng;
+    extern crate alloc;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn kernels() {
+        if !is_available() {
+            return;
+        }
+        let mut rng = thread_rng();
+        for p in [
+            4611686018326724609,
+            primes::generate_prime(50, 32, 1 << 50).unwrap(),
+            1153,
+        ] {
+            let q = Modulus::new(p).unwrap();
+            for size in [1, 2, 7, 32] {
+                let a = q.random_vec(size, &mut rng);
+                let b = q.random_vec(size, &mut rng);
+                let b_shoup = q.shoup_vec(&b);
+
+                let mut c = a.clone();
+                unsafe { add_vec(&q, &mut c, &b) };
+                let expected = izip!(&a, &b)
+                    .map(|(ai, bi)| q.add(*ai, *bi))
+                    .collect::<Vec<_>>();
+                assert_eq!(c, expected);
+
+                let mut c = a.clone();
+                unsafe { sub_vec(&q, &mut c, &b) };
+                let expected = izip!(&a, &b)
+                    .map(|(ai, bi)| q.sub(*ai, *bi))
+                    .collect::<Vec<_>>();
+                assert_eq!(c, expected);
+
+                let mut c = a.clone();
+                unsafe { mul_vec(&q, &mut c, &b) };
+                let expected = izip!(&a, &b)
+                    .map(|(ai, bi)| q.mul(*ai, *bi))
+                    .collect::<Vec<_>>();
+                assert_eq!(c, expected);
+
+                let mut c = a.clone();
+                unsafe { mul_shoup_vec(&q, &mut c, &b, &b_shoup) };
+                assert_eq!(c, expected);
+
+                if size % 2 == 0 {
+                    let (w, w_shoup) = (b[0], b_shoup[0]);
+                    let (mut x, mut y) = (a.clone(), b.clone());
+                    unsafe { butterflies(&q, &mut x, &mut y, w, w_shoup) };
+                    for i in 0..size {
+                        let t = q.mul(b[i], w);
+                        assert_eq!(q.reduce(x[i]), q.add(a[i], t));
+                        assert_eq!(q.reduce(y[i]), q.sub(a[i], t));
+                    }
+
+                    let (mut x, mut y) = (a.clone(), b.clone());
+                    unsafe { inv_butterflies(&q, &mut x, &mut y, w, w_shoup) };
+                    for i in 0..size {
+                        assert_eq!(q.reduce(x[i]), q.add(a[i], b[i]));
+                        assert_eq!(q.reduce(y[i]), q.mul(q.sub(a[i], b[i]), w));
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,396 @@
+//! Arithmetic, multipoint evaluation and interpolation of polynomials over
+//! `Z_t`.
+//!
+//! The polynomials are represented by their coefficients in increasing degree.
+//! The products are computed with the NTT modulo a 62-bit prime as long as the
+//! coefficients of the product over the integers fit below this prime, which
+//! holds for degrees up to `2^21` when `t < 2^20`, and schoolbook
+//! multiplication otherwise. Multipoint evaluation and interpolation use
+//! subproduct trees, and therefore run in quasi-linear time in the number of
+//! points.
+
+use super::Modulus;
+use crate::errors::{Error, Result};
+use crate::ntt::NttOperatorCache;
+extern crate alloc;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// A 62-bit prime congruent to 1 modulo 2^27, which supports the NTT of
+/// sizes up to 2^26.
+const NTT_PRIME: u64 = 4611686009971671041;
+
+/// Below this length, polynomials are multiplied and divided naively.
+const NAIVE_THRESHOLD: usize = 32;
+
+/// Multiply the polynomials `a` and `b` modulo `t`.
+pub fn mul(a: &[u64], b: &[u64], t: &Modulus) -> Vec<u64> {
+    Arithmetic::new(t).mul(a, b)
+}
+
+/// Divide the polynomial `a` by the polynomial `b` modulo `t`, and returns the
+/// quotient and the remainder.
+///
+/// Returns an error if `b` is zero or if its leading coefficient is not
+/// invertible modulo `t`.
+pub fn div_rem(a: &[u64], b: &[u64], t: &Modulus) -> Result<(Vec<u64>, Vec<u64>)> {
+    Arithmetic::new(t).div_rem(a, b)
+}
+
+/// Evaluate the polynomial `poly` at each of the `points` modulo `t`.
+pub fn evaluate(poly: &[u64], points: &[u64], t: &Modulus) -> Vec<u64> {
+    if points.is_empty() {
+        return Vec::new();
+    }
+    let mut arithmetic = Arithmetic::new(t);
+    let tree = arithmetic.subproduct_tree(points);
+    arithmetic.evaluate(poly, &tree)
+}
+
+/// Compute the unique polynomial of degree smaller than `points.len()` which
+/// takes the value `values[i]` at `points[i]` modulo `t`.
+///
+/// Returns an error if the number of points and values differ, or if the
+/// difference between two points is not invertible modulo `t`, e.g. if two
+/// points are equal.
+pub fn interpolate(points: &[u64], values: &[u64], t: &Modulus) -> Result<Vec<u64>> {
+    if points.len() != values.len() {
+        return Err(Error::Default(
+            "The number of points and values differ".to_string(),
+        ));
+    }
+    if points.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut arithmetic = Arithmetic::new(t);
+    let tree = arithmetic.subproduct_tree(points);
+
+    // The Lagrange weights are the inverses of the derivative of the product
+    // of the (x - points[i]) at the points.
+    let root = &tree[tree.len() - 1][0];
+    let derivative = root
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, c)| t.mul(*c, t.reduce(i as u64)))
+        .collect::<Vec<u64>>();
+    let mut weights = arithmetic.evaluate(&derivative, &tree);
+    t.inv_vec(&mut weights).ok_or_else(|| {
+        Error::Default("The points must be distinct and their differences invertible".to_string())
+    })?;
+    let mut layer = values
+        .iter()
+        .zip(weights.iter())
+        .map(|(v, w)| vec![t.mul(t.reduce(*v), *w)])
+        .collect::<Vec<_>>();
+
+    // Combine the layers from the leaves to the root.
+    for level in &tree[..tree.len() - 1] {
+        layer = layer
+            .chunks(2)
+            .zip(level.chunks(2))
+            .map(|(r, m)| {
+                if r.len() == 1 {
+                    r[0].clone()
+                } else {
+                    let mut left = arithmetic.mul(&r[0], &m[1]);
+                    let right = arithmetic.mul(&r[1], &m[0]);
+                    add_assign(&mut left, &right, t);
+                    left
+                }
+            })
+            .collect();
+    }
+    let mut poly = layer.pop().unwrap();
+    trim(&mut poly);
+    Ok(poly)
+}
+
+/// Removes the leading zero coefficients.
+fn trim(a: &mut Vec<u64>) {
+    while a.last() == Some(&0) {
+        a.pop();
+    }
+}
+
+fn add_assign(a: &mut Vec<u64>, b: &[u64], t: &Modulus) {
+    if a.len() < b.len() {
+        a.resize(b.len(), 0)
+    }
+    a.iter_mut()
+        .zip(b)
+        .for_each(|(ai, bi)| *ai = t.add(*ai, *bi));
+}
+
+/// Polynomial arithmetic modulo `t`, which shares the NTT operators across
+/// the products.
+struct Arithmetic<'a> {
+    t: &'a Modulus,
+    p: Modulus,
+    cache: NttOperatorCache,
+}
+
+impl<'a> Arithmetic<'a> {
+    fn new(t: &'a Modulus) -> Self {
+        Self {
+            t,
+            p: Modulus::new(NTT_PRIME).unwrap(),
+            cache: NttOperatorCache::new(),
+        }
+    }
+
+    fn mul(&mut self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        if a.is_empty() || b.is_empty() {
+            return Vec::new();
+        }
+        let len = a.len() + b.len() - 1;
+        let size = len.next_power_of_two().max(8);
+        let bound = ((**self.t - 1) as u128)
+            .pow(2)
+            .saturating_mul(a.len().min(b.len()) as u128);
+        if a.len().min(b.len()) < NAIVE_THRESHOLD || bound >= NTT_PRIME as u128 {
+            return self.mul_naive(a, b);
+        }
+        let Some(op) = self.cache.negacyclic(&self.p, size) else {
+            return self.mul_naive(a, b);
+        };
+        let mut a_ntt = vec![0u64; size];
+        let mut b_ntt = vec![0u64; size];
+        a_ntt[..a.len()].copy_from_slice(a);
+        b_ntt[..b.len()].copy_from_slice(b);
+        self.t.reduce_vec(&mut a_ntt);
+        self.t.reduce_vec(&mut b_ntt);
+        op.forward(&mut a_ntt);
+        op.forward(&mut b_ntt);
+        self.p.mul_vec(&mut a_ntt, &b_ntt);
+        op.backward(&mut a_ntt);
+        a_ntt.truncate(len);
+        self.t.reduce_vec(&mut a_ntt);
+        a_ntt
+    }
+
+    fn mul_naive(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let mut c = vec![0u64; a.len() + b.len() - 1];
+        for (i, ai) in a.iter().enumerate() {
+            for (j, bj) in b.iter().enumerate() {
+                c[i + j] = self
+                    .t
+                    .add(c[i + j], self.t.mul(self.t.reduce(*ai), self.t.reduce(*bj)));
+            }
+        }
+        c
+    }
+
+    /// Inverse of `a` modulo `x^n`, given the inverse of `a[0]`.
+    fn inverse_series(&mut self, a: &[u64], n: usize, a0_inv: u64) -> Vec<u64> {
+        let mut inv = vec![a0_inv];
+        let mut k = 1;
+        while k < n {
+            k = (2 * k).min(n);
+            // inv <- inv * (2 - a * inv) mod x^k
+            let mut e = self.mul(&a[..k.min(a.len())], &inv);
+            e.truncate(k);
+            e.iter_mut().for_each(|ei| *ei = self.t.neg(*ei));
+            e[0] = self.t.add(e[0], 2 % **self.t);
+            inv = self.mul(&inv, &e);
+            inv.truncate(k);
+        }
+        inv
+    }
+
+    fn div_rem(&mut self, a: &[u64], b: &[u64]) -> Result<(Vec<u64>, Vec<u64>)> {
+        let mut a = self.t.reduce_vec_new(a);
+        let mut b = self.t.reduce_vec_new(b);
+        trim(&mut a);
+        trim(&mut b);
+        // The divisors of the subproduct trees are monic, which avoids an
+        // inversion (and its primality test) per division.
+        let lead_inv = match b.last() {
+            Some(1) => Some(1),
+            Some(lead) => self.t.inv(*lead),
+            None => None,
+        }
+        .ok_or_else(|| {
+            Error::Default("The leading coefficient of the divisor is not invertible".to_string())
+        })?;
+        if a.len() < b.len() {
+            return Ok((Vec::new(), a));
+        }
+        let m = a.len() - b.len() + 1;
+        let quotient = if m < NAIVE_THRESHOLD || b.len() < NAIVE_THRESHOLD {
+            let mut r = a.clone();
+            let mut q = vec![0u64; m];
+            for i in (0..m).rev() {
+                let qi = self.t.mul(r[i + b.len() - 1], lead_inv);
+                q[i] = qi;
+                for (j, bj) in b.iter().enumerate() {
+                    r[i + j] = self.t.sub(r[i + j], self.t.mul(qi, *bj));
+                }
+            }
+            q
+        } else {
+            // The reversed quotient is the reversed dividend divided by the
+            // reversed divisor modulo x^m.
+            let a_rev = a.iter().rev().take(m).copied().collect::<Vec<u64>>();
+            let b_rev = b.iter().rev().copied().collect::<Vec<u64>>();
+            let b_rev_inv = self.inverse_series(&b_rev, m, lead_inv);
+            let mut q = self.mul(&a_rev, &b_rev_inv);
+            q.truncate(m);
+            q.resize(m, 0);
+            q.reverse();
+            q
+        };
+        let qb = self.mul(&quotient, &b);
+        let mut remainder = a[..b.len() - 1]
+            .iter()
+            .zip(qb.iter())
+            .map(|(ai, qbi)| self.t.sub(*ai, *qbi))
+            .collect::<Vec<u64>>();
+        let mut quotient = quotient;
+        trim(&mut quotient);
+        trim(&mut remainder);
+        Ok((quotient, remainder))
+    }
+
+    /// The subproduct tree of the points: the first level contains the
+    /// polynomials `x - points[i]`, and each following level contains the
+    /// products of pairs of polynomials of the previous level.
+    fn subproduct_tree(&mut self, points: &[u64]) -> Vec<Vec<Vec<u64>>> {
+        let mut tree = vec![points
+            .iter()
+            .map(|a| vec![self.t.neg(self.t.reduce(*a)), 1])
+            .collect::<Vec<_>>()];
+        while tree[tree.len() - 1].len() > 1 {
+            let level = tree[tree.len() - 1]
+                .chunks(2)
+                .map(|m| {
+                    if m.len() == 1 {
+                        m[0].clone()
+                    } else {
+                        self.mul(&m[0], &m[1])
+                    }
+                })
+                .collect();
+            tree.push(level);
+        }
+        tree
+    }
+
+    /// Evaluate `poly` at the points of the subproduct tree, by reducing it
+    /// modulo the polynomials of the tree from the root to the leaves.
+    fn evaluate(&mut self, poly: &[u64], tree: &[Vec<Vec<u64>>]) -> Vec<u64> {
+        let mut remainders = vec![poly.to_vec()];
+        for level in tree.iter().rev() {
+            remainders = level
+                .iter()
+                .enumerate()
+                .map(|(i, m)| {
+                    // The divisors are monic, so the division cannot fail.
+                    self.div_rem(&remainders[i / 2], m).unwrap().1
+                })
+                .collect();
+        }
+        remainders
+            .iter()
+            .map(|r| r.first().copied().unwrap_or_default())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{div_rem, evaluate, interpolate, mul, NTT_PRIME};
+    use crate::{ntt::supports_ntt, zq::Modulus};
+    use rand::{thread_rng, Rng};
+    extern crate alloc;
+    use alloc::vec::Vec;
+
+    fn horner(poly: &[u64], x: u64, t: &Modulus) -> u64 {
+        poly.iter().rev().fold(0, |acc, c| t.add(t.mul(acc, x), *c))
+    }
+
+    #[test]
+    fn ntt_prime() {
+        assert!(supports_ntt(NTT_PRIME, 1 << 26));
+    }
+
+    #[test]
+    fn mul_div_rem() {
+        let mut rng = thread_rng();
+        for p in [97u64, 1153, 1048573, 4611686018326724609] {
+            let t = Modulus::new(p).unwrap();
+            for (la, lb) in [(1, 1), (10, 3), (100, 40), (300, 200)] {
+                let a = t.random_vec(la, &mut rng);
+                let mut b = t.random_vec(lb, &mut rng);
+                b[lb - 1] = 1;
+                let ab = mul(&a, &b, &t);
+                assert_eq!(ab.len(), la + lb - 1);
+                let x = rng.gen_range(0..p);
+                assert_eq!(
+                    horner(&ab, x, &t),
+                    t.mul(horner(&a, x, &t), horner(&b, x, &t))
+                );
+
+                let (q, r) = div_rem(&ab, &b, &t).unwrap();
+                let mut expected = a.clone();
+                while expected.last() == Some(&0) {
+                    expected.pop();
+                }
+                assert_eq!(q, expected);
+                assert!(r.is_empty());
+
+                let (q, r) = div_rem(&a, &b, &t).unwrap();
+                assert!(r.len() < lb);
+                let mut qb = mul(&q, &b, &t);
+                qb.resize(la.max(qb.len()), 0);
+                r.iter()
+                    .enumerate()
+                    .for_each(|(i, ri)| qb[i] = t.add(qb[i], *ri));
+                assert_eq!(qb[..la], a[..]);
+            }
+        }
+        let t = Modulus::new(97).unwrap();
+        assert!(div_rem(&[1, 2], &[0, 0], &t).is_err());
+    }
+
+    #[test]
+    fn evaluate_interpolate() {
+        let mut rng = thread_rng();
+        for p in [97u64, 1153, 1048573] {
+            let t = Modulus::new(p).unwrap();
+            for n in [1, 2, 7, 64, 97] {
+                let mut points = (0..p).collect::<Vec<u64>>();
+                for i in 0..n {
+                    points.swap(i, rng.gen_range(i..p as usize));
+                }
+                points.truncate(n);
+                let values = t.random_vec(n, &mut rng);
+
+                let poly = interpolate(&points, &values, &t).unwrap();
+                assert!(poly.len() <= n);
+                assert_eq!(evaluate(&poly, &points, &t), values);
+                for (x, v) in points.iter().zip(values.iter()) {
+                    assert_eq!(horner(&poly, *x, &t), *v);
+                }
+            }
+        }
+
+        let t = Modulus::new(97).unwrap();
+        assert!(interpolate(&[1, 2, 1], &[0, 1, 2], &t).is_err());
+        assert!(interpolate(&[1, 2], &[0], &t).is_err());
+        assert!(interpolate(&[], &[], &t).unwrap().is_empty());
+        assert!(evaluate(&[1, 2, 3], &[], &t).is_empty());
+    }
+
+    #[test]
+    fn interpolate_full_domain() {
+        let mut rng = thread_rng();
+        let t = Modulus::new(12289).unwrap();
+        let points = (0..*t).collect::<Vec<u64>>();
+        let values = t.random_vec(points.len(), &mut rng);
+        let poly = interpolate(&points, &values, &t).unwrap();
+        assert!(poly.len() <= points.len());
+        assert_eq!(evaluate(&poly, &points, &t), values);
+    }
+}
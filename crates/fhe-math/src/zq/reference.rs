@@ -0,0 +1,72 @@
+//! Reference implementation of the modular arithmetic, against which the
+//! `cross-check` feature validates the vectorized operations of
+//! [`super::Modulus`] in debug builds.
+//!
+//! The functions compute over 128-bit integers with the `%` operator, without
+//! any precomputation, so that they are straightforward to audit.
+
+use itertools::izip;
+
+/// Returns `(a + b) mod p`.
+pub(crate) fn add(p: u64, a: u64, b: u64) -> u64 {
+    ((a as u128 + b as u128) % p as u128) as u64
+}
+
+/// Returns `(a - b) mod p`.
+pub(crate) fn sub(p: u64, a: u64, b: u64) -> u64 {
+    ((a as u128 + p as u128 - (b % p) as u128) % p as u128) as u64
+}
+
+/// Returns `(a * b) mod p`.
+pub(crate) fn mul(p: u64, a: u64, b: u64) -> u64 {
+    ((a as u128 * b as u128) % p as u128) as u64
+}
+
+/// Returns the Shoup representation `floor(a * 2^64 / p)` of `a`.
+pub(crate) fn shoup(p: u64, a: u64) -> u64 {
+    (((a as u128) << 64) / p as u128) as u64
+}
+
+/// Asserts that `out[i] == f(i, input[i])` for every index `i`, where `name`
+/// is the name of the checked operation.
+pub(crate) fn check<F: Fn(usize, u64) -> u64>(name: &str, out: &[u64], input: &[u64], f: F) {
+    assert_eq!(out.len(), input.len());
+    for (i, (oi, xi)) in izip!(out, input).enumerate() {
+        assert_eq!(
+            *oi,
+            f(i, *xi),
+            "{name} differs from the reference implementation at index {i}"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{add, check, mul, shoup, sub};
+    use num_bigint::BigUint;
+    use num_traits::ToPrimitive;
+    use rand::{thread_rng, RngCore};
+
+    #[test]
+    fn reference() {
+        let mut rng = thread_rng();
+        for p in [2u64, 3, 17, 1153, 4611686018326724609] {
+            for _ in 0..100 {
+                let a = rng.next_u64() % p;
+                let b = rng.next_u64() % p;
+                assert_eq!(add(p, a, b), (a as u128 + b as u128) as u64 % p);
+                assert_eq!(add(p, sub(p, a, b), b), a);
+                let expected = (BigUint::from(a) * b) % p;
+                assert_eq!(mul(p, a, b), expected.to_u64().unwrap());
+                let expected: BigUint = (BigUint::from(a) << 64) / p;
+                assert_eq!(shoup(p, a), expected.to_u64().unwrap());
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "add_vec differs from the reference implementation at index 1")]
+    fn check_mismatch() {
+        check("add_vec", &[2, 4], &[1, 2], |_, x| add(17, x, 1))
+    }
+}
@@ -0,0 +1,73 @@
+//! Allocations of the representation conversions of polynomials, counted by a
+//! global allocator, which is why these checks live in their own test binary.
+
+use fhe_math::rq::{Context, Poly, Representation};
+use rand::thread_rng;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::sync::Arc;
+
+/// Global allocator counting the allocations of each thread, so that the
+/// allocations of the other threads of the test harness are not counted.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|a| a.set(a.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|a| a.set(a.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Returns the number of allocations performed by `f` on the current thread.
+fn count_allocations<F: FnOnce()>(f: F) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+#[test]
+fn representation_allocations() -> Result<(), fhe_math::Error> {
+    let mut rng = thread_rng();
+    let ctx = Arc::new(Context::new(
+        &[4611686018326724609, 4611686018309947393],
+        1024,
+    )?);
+    let mut p = Poly::random(&ctx, Representation::Ntt, &mut rng);
+    assert!(count_allocations(|| drop(p.clone())) > 0);
+
+    // The conversions between representations are in place.
+    let allocations = count_allocations(|| {
+        p.change_representation(Representation::PowerBasis);
+        p.change_representation(Representation::Ntt);
+        p.change_representation(Representation::NttShoup);
+        p.change_representation(Representation::Ntt);
+    });
+    assert_eq!(allocations, 0);
+
+    // The polynomial is borrowed when already in the requested representation.
+    let allocations = count_allocations(|| {
+        assert!(matches!(
+            p.to_representation(Representation::Ntt),
+            Cow::Borrowed(_)
+        ))
+    });
+    assert_eq!(allocations, 0);
+    Ok(())
+}
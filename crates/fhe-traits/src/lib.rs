@@ -9,7 +9,7 @@ extern crate alloc;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 
-use rand::RngCore;
+use rand::{CryptoRng, RngCore};
 
 /// The homomorphic encryption parameters.
 pub trait FheParameters {}
@@ -112,7 +112,7 @@ pub trait FheEncrypter<
     type Error;
 
     /// Try to encrypt an [`FhePlaintext`] into an [`FheCiphertext`].
-    fn try_encrypt<R: RngCore>(&self, pt: &P, rng: &mut R) -> Result<C, Self::Error>;
+    fn try_encrypt<R: RngCore + CryptoRng>(&self, pt: &P, rng: &mut R) -> Result<C, Self::Error>;
 }
 
 /// Decrypt a ciphertext into a plaintext
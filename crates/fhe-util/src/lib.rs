@@ -8,7 +8,7 @@
 #[cfg(test)]
 extern crate proptest;
 
-use rand::RngCore;
+use rand::{Rng, RngCore};
 
 use num_bigint_dig::{prime::probably_prime, BigUint, ModInverse};
 use num_traits::{cast::ToPrimitive, PrimInt};
@@ -57,6 +57,82 @@ pub fn sample_vec_cbd<R: RngCore>(
     Ok(out)
 }
 
+/// Sample a vector of ternary values with exactly `hamming_weight` non-zero
+/// coefficients, each equal to -1 or 1 with equal probability. Returns an error
+/// if the Hamming weight is strictly larger than the vector size.
+pub fn sample_vec_ternary<R: RngCore>(
+    vector_size: usize,
+    hamming_weight: usize,
+    rng: &mut R,
+) -> Result<Vec<i64>, &'static str> {
+    if hamming_weight > vector_size {
+        return Err("The Hamming weight should be at most the vector size");
+    }
+
+    // Partial Fisher-Yates shuffle selecting the positions of the non-zero
+    // coefficients.
+    let mut indices = (0..vector_size).collect::<Vec<_>>();
+    let mut out = alloc::vec![0i64; vector_size];
+    for i in 0..hamming_weight {
+        let j = rng.gen_range(i..vector_size);
+        indices.swap(i, j);
+        out[indices[i]] = if rng.next_u32() & 1 == 0 { -1 } else { 1 };
+    }
+
+    Ok(out)
+}
+
+/// Sample a vector of independent discrete Gaussian values of standard
+/// deviation `sigma`. Returns an error if `sigma` is not a finite number in
+/// (0, 1024].
+///
+/// The values are sampled in constant time by inversion of a cumulative
+/// distribution table with 63 bits of precision: every sample compares a
+/// uniform value with all the entries of the table, so that the running time
+/// only depends on `vector_size` and `sigma`. The samples are bounded in
+/// absolute value by `ceil(12 * sigma)`.
+pub fn sample_vec_gaussian<R: RngCore>(
+    vector_size: usize,
+    sigma: f64,
+    rng: &mut R,
+) -> Result<Vec<i64>, &'static str> {
+    if !(sigma.is_finite() && sigma > 0.0 && sigma <= 1024.0) {
+        return Err("The standard deviation should be in (0, 1024]");
+    }
+
+    // The table only depends on sigma: entry k is 2^63 times the probability
+    // that the absolute value of a sample is at most k.
+    let tail = libm::ceil(12.0 * sigma) as usize;
+    let rho = (0..tail)
+        .map(|k| libm::exp(-((k * k) as f64) / (2.0 * sigma * sigma)))
+        .collect::<Vec<_>>();
+    let total = 2.0 * rho.iter().sum::<f64>() - 1.0;
+    let mut cumulative = 0.0;
+    let table = rho
+        .iter()
+        .enumerate()
+        .map(|(k, rho_k)| {
+            cumulative += if k == 0 { *rho_k } else { 2.0 * rho_k };
+            libm::round(cumulative / total * (1u64 << 63) as f64) as u64
+        })
+        .collect::<Vec<_>>();
+
+    let mut out = Vec::with_capacity(vector_size);
+    for _ in 0..vector_size {
+        let r = rng.next_u64();
+        let (uniform, sign) = ((r >> 1) as i64, (r & 1) as i64);
+        // The absolute value is the number of entries at most `uniform`; both
+        // are below 2^63, so the difference is negative iff uniform < entry.
+        let absolute = table
+            .iter()
+            .map(|entry| 1 + (uniform.wrapping_sub(*entry as i64) >> 63))
+            .sum::<i64>();
+        out.push((absolute ^ -sign) + sign);
+    }
+
+    Ok(out)
+}
+
 /// Transcodes a vector of u64 of `nbits`-bit numbers into a vector of bytes.
 pub fn transcode_to_bytes(a: &[u64], nbits: usize) -> Vec<u8> {
     assert!(0 < nbits && nbits <= 64);
@@ -189,8 +265,8 @@ mod tests {
     use crate::variance;
 
     use super::{
-        inverse, is_prime, sample_vec_cbd, transcode_bidirectional, transcode_from_bytes,
-        transcode_to_bytes,
+        inverse, is_prime, sample_vec_cbd, sample_vec_gaussian, sample_vec_ternary,
+        transcode_bidirectional, transcode_from_bytes, transcode_to_bytes,
     };
 
     #[test]
@@ -232,6 +308,43 @@ mod tests {
         }
     }
 
+    #[test]
+    fn sample_ternary() {
+        assert!(sample_vec_ternary(10, 11, &mut thread_rng()).is_err());
+
+        for size in 0..=100 {
+            for hamming_weight in [0, size / 2, size] {
+                let v = sample_vec_ternary(size, hamming_weight, &mut thread_rng()).unwrap();
+                assert_eq!(v.len(), size);
+                assert!(v.iter().all(|vi| (-1..=1).contains(vi)));
+                assert_eq!(v.iter().filter(|vi| **vi != 0).count(), hamming_weight);
+            }
+        }
+    }
+
+    #[test]
+    fn sample_gaussian() {
+        assert!(sample_vec_gaussian(10, 0.0, &mut thread_rng()).is_err());
+        assert!(sample_vec_gaussian(10, -1.0, &mut thread_rng()).is_err());
+        assert!(sample_vec_gaussian(10, f64::NAN, &mut thread_rng()).is_err());
+        assert!(sample_vec_gaussian(10, 2048.0, &mut thread_rng()).is_err());
+
+        for size in 0..=100 {
+            let v = sample_vec_gaussian(size, 3.2, &mut thread_rng()).unwrap();
+            assert_eq!(v.len(), size);
+        }
+
+        // Verifies that the variance is close to sigma^2, and that the samples
+        // are symmetric.
+        for sigma in [3.2f64, 10.0] {
+            let v = sample_vec_gaussian(100000, sigma, &mut thread_rng()).unwrap();
+            let expected = sigma * sigma;
+            assert!((variance(&v) - expected).abs() < 0.05 * expected);
+            assert!(v.iter().map(|vi| vi.abs()).max().unwrap() as f64 <= 12.0 * sigma);
+            assert!((v.iter().sum::<i64>() as f64 / 100000.0).abs() < 0.05 * sigma);
+        }
+    }
+
     #[test]
     fn transcode_self_consistency() {
         let mut rng = thread_rng();
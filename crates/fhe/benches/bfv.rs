@@ -1,4 +1,10 @@
+#![cfg_attr(
+    any(feature = "client", feature = "server"),
+    allow(dead_code, unused_imports)
+)]
+
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+#[cfg(not(any(feature = "client", feature = "server")))]
 use fhe::bfv::{
     BfvParameters, Ciphertext, Encoding, EvaluationKeyBuilder, Multiplicator, Plaintext, PublicKey,
     RelinearizationKey, SecretKey,
@@ -11,6 +17,7 @@ use num_bigint::BigUint;
 use rand::{rngs::OsRng, thread_rng};
 use std::time::Duration;
 
+#[cfg(not(any(feature = "client", feature = "server")))]
 pub fn bfv_benchmark(c: &mut Criterion) {
     let mut rng = thread_rng();
     let mut group = c.benchmark_group("bfv");
@@ -283,5 +290,11 @@ pub fn bfv_benchmark(c: &mut Criterion) {
     group.finish();
 }
 
+#[cfg(not(any(feature = "client", feature = "server")))]
 criterion_group!(bfv, bfv_benchmark);
+#[cfg(not(any(feature = "client", feature = "server")))]
 criterion_main!(bfv);
+
+// The benchmarks require the secret key, which the `client` and `server` features exclude.
+#[cfg(any(feature = "client", feature = "server"))]
+fn main() {}
@@ -1,10 +1,17 @@
+#![cfg_attr(
+    any(feature = "client", feature = "server"),
+    allow(dead_code, unused_imports)
+)]
+
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+#[cfg(not(any(feature = "client", feature = "server")))]
 use fhe::bfv::{dot_product_scalar, BfvParameters, Ciphertext, Encoding, Plaintext, SecretKey};
 use fhe_traits::{FheEncoder, FheEncrypter};
 use itertools::{izip, Itertools};
 use rand::{rngs::OsRng, thread_rng};
 use std::time::Duration;
 
+#[cfg(not(any(feature = "client", feature = "server")))]
 pub fn bfv_benchmark(c: &mut Criterion) {
     let mut rng = thread_rng();
     let mut group = c.benchmark_group("bfv_optimized_ops");
@@ -68,5 +75,11 @@ pub fn bfv_benchmark(c: &mut Criterion) {
     group.finish();
 }
 
+#[cfg(not(any(feature = "client", feature = "server")))]
 criterion_group!(bfv, bfv_benchmark);
+#[cfg(not(any(feature = "client", feature = "server")))]
 criterion_main!(bfv);
+
+// The benchmarks require the secret key, which the `client` and `server` features exclude.
+#[cfg(any(feature = "client", feature = "server"))]
+fn main() {}
@@ -1,10 +1,17 @@
+#![cfg_attr(
+    any(feature = "client", feature = "server"),
+    allow(dead_code, unused_imports)
+)]
+
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+#[cfg(not(any(feature = "client", feature = "server")))]
 use fhe::bfv::{BfvParameters, Ciphertext, Encoding, Plaintext, RGSWCiphertext, SecretKey};
 use fhe_traits::{FheEncoder, FheEncrypter};
 use itertools::Itertools;
 use rand::{rngs::OsRng, thread_rng};
 use std::time::Duration;
 
+#[cfg(not(any(feature = "client", feature = "server")))]
 pub fn bfv_rgsw_benchmark(c: &mut Criterion) {
     let mut group = c.benchmark_group("bfv_rgsw");
     group.sample_size(10);
@@ -32,5 +39,11 @@ pub fn bfv_rgsw_benchmark(c: &mut Criterion) {
     group.finish();
 }
 
+#[cfg(not(any(feature = "client", feature = "server")))]
 criterion_group!(bfv_rgsw, bfv_rgsw_benchmark);
+#[cfg(not(any(feature = "client", feature = "server")))]
 criterion_main!(bfv_rgsw);
+
+// The benchmarks require the secret key, which the `client` and `server` features exclude.
+#[cfg(any(feature = "client", feature = "server"))]
+fn main() {}
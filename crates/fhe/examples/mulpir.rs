@@ -6,6 +6,11 @@
 // We use the same parameters as in the paper to enable an apple-to-apple
 // comparison.
 
+#![cfg_attr(
+    any(feature = "client", feature = "server"),
+    allow(dead_code, unused_imports)
+)]
+
 mod pir;
 mod util;
 
@@ -24,6 +29,7 @@ use util::{
     timeit::{timeit, timeit_n},
 };
 
+#[cfg(not(any(feature = "client", feature = "server")))]
 fn main() -> Result<(), Error> {
     // We use the parameters reported in Table 1 of https://eprint.iacr.org/2019/1483.pdf.
     let degree = 8192;
@@ -203,3 +209,11 @@ fn main() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[cfg(any(feature = "client", feature = "server"))]
+fn main() {
+    eprintln!(
+        "The MulPIR example requires the secret key, which the `client` and `server` \
+         features exclude."
+    );
+}
@@ -6,6 +6,11 @@
 // We use the same parameters as in Microsoft's public implementation
 // <https://github.com/microsoft/SealPIR> to enable an apple-to-apple comparison.
 
+#![cfg_attr(
+    any(feature = "client", feature = "server"),
+    allow(dead_code, unused_imports)
+)]
+
 mod pir;
 mod util;
 
@@ -27,6 +32,7 @@ use util::{
     timeit::{timeit, timeit_n},
 };
 
+#[cfg(not(any(feature = "client", feature = "server")))]
 fn main() -> Result<(), Error> {
     env_logger::init();
 
@@ -276,3 +282,11 @@ fn main() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[cfg(any(feature = "client", feature = "server"))]
+fn main() {
+    eprintln!(
+        "The SealPIR example requires the secret key, which the `client` and `server` \
+         features exclude."
+    );
+}
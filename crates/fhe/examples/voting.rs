@@ -1,10 +1,16 @@
 // Implementation of multiparty voting using the `fhe` crate.
 
+#![cfg_attr(
+    any(feature = "client", feature = "server"),
+    allow(dead_code, unused_imports)
+)]
+
 mod util;
 
 use std::{env, process::exit, sync::Arc};
 
 use console::style;
+#[cfg(not(any(feature = "client", feature = "server")))]
 use fhe::{
     bfv::{self, Ciphertext, Encoding, Plaintext, PublicKey, SecretKey},
     mbfv::{AggregateIter, CommonRandomPoly, DecryptionShare, PublicKeyShare},
@@ -14,6 +20,7 @@ use fhe_traits::{FheDecoder, FheEncoder, FheEncrypter};
 use rand::{distributions::Uniform, prelude::Distribution, rngs::OsRng, thread_rng};
 use util::timeit::{timeit, timeit_n};
 
+#[cfg(not(any(feature = "client", feature = "server")))]
 fn print_notice_and_exit(error: Option<String>) {
     println!(
         "{} Voting with fhe.rs",
@@ -35,6 +42,7 @@ fn print_notice_and_exit(error: Option<String>) {
     exit(0);
 }
 
+#[cfg(not(any(feature = "client", feature = "server")))]
 fn main() -> Result<(), Error> {
     let degree = 4096;
     let plaintext_modulus: u64 = 4096;
@@ -169,8 +177,16 @@ fn main() -> Result<(), Error> {
     // Show vote result
     println!("Vote result = {} / {}", tally_result, num_voters);
 
-    let expected_tally = votes.iter().sum();
+    let expected_tally: u64 = votes.iter().sum();
     assert_eq!(tally_result, expected_tally);
 
     Ok(())
 }
+
+#[cfg(any(feature = "client", feature = "server"))]
+fn main() {
+    eprintln!(
+        "The voting example requires the secret key, which the `client` and `server` \
+         features exclude."
+    );
+}
@@ -0,0 +1,179 @@
+//! Throughput report of the main operations on the current machine.
+
+extern crate alloc;
+extern crate std;
+
+use crate::bfv::{
+    BfvParameters, Ciphertext, Encoding, EvaluationKeyBuilder, Plaintext, RelinearizationKey,
+    SecretKey,
+};
+use crate::Result;
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use fhe_traits::{FheEncoder, FheEncrypter};
+use itertools::Itertools;
+use rand::rngs::OsRng;
+use std::time::Instant;
+
+/// Throughput of an operation measured by [`bench_report`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchEntry {
+    /// The name of the operation.
+    pub operation: &'static str,
+    /// The number of times the operation was run.
+    pub iterations: usize,
+    /// The number of operations per second.
+    pub ops_per_sec: f64,
+}
+
+/// Report of the throughput of the main operations for a parameter set,
+/// computed by [`bench_report`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchReport {
+    /// The identifier of the parameters, see [`BfvParameters::id`].
+    pub parameters: u64,
+    /// The degree of the parameters.
+    pub degree: usize,
+    /// The number of bits of the ciphertext modulus.
+    pub modulus_bits: usize,
+    /// The plaintext modulus.
+    pub plaintext_modulus: u64,
+    /// The measured operations.
+    pub entries: Vec<BenchEntry>,
+}
+
+impl BenchReport {
+    /// Returns the throughput of the operation `operation`, if it was measured.
+    pub fn ops_per_sec(&self, operation: &str) -> Option<f64> {
+        self.entries
+            .iter()
+            .find(|e| e.operation == operation)
+            .map(|e| e.ops_per_sec)
+    }
+
+    /// Export the report as a JSON object.
+    pub fn to_json(&self) -> String {
+        let entries = self
+            .entries
+            .iter()
+            .map(|e| {
+                format!(
+                    "{{\"operation\":\"{}\",\"iterations\":{},\"ops_per_sec\":{:.3}}}",
+                    e.operation, e.iterations, e.ops_per_sec
+                )
+            })
+            .join(",");
+        format!(
+            "{{\"parameters\":\"{:016x}\",\"degree\":{},\"modulus_bits\":{},\"plaintext_modulus\":{},\"entries\":[{}]}}",
+            self.parameters, self.degree, self.modulus_bits, self.plaintext_modulus, entries
+        )
+    }
+}
+
+/// Run `f` `iterations` times and returns its throughput.
+fn measure<F: FnMut() -> Result<()>>(
+    operation: &'static str,
+    iterations: usize,
+    mut f: F,
+) -> Result<BenchEntry> {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f()?;
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+    Ok(BenchEntry {
+        operation,
+        iterations,
+        ops_per_sec: iterations as f64 / elapsed.max(f64::MIN_POSITIVE),
+    })
+}
+
+/// Measure the throughput of the key generation, encryption, addition,
+/// multiplication and rotation on the current machine for the parameters
+/// `par`, running each operation `iterations` times. The report can be
+/// exported as JSON with [`BenchReport::to_json`].
+///
+/// The operations are measured on the largest level of the parameters, which
+/// must have at least two moduli to support key switching.
+pub fn bench_report(par: &Arc<BfvParameters>, iterations: usize) -> Result<BenchReport> {
+    let mut rng = OsRng;
+    let iterations = iterations.max(1);
+
+    let sk = SecretKey::random(par, &mut rng);
+    let rk = RelinearizationKey::new(&sk, &mut rng)?;
+    let ek = EvaluationKeyBuilder::new(&sk)?
+        .enable_column_rotation(1)?
+        .build(&mut rng)?;
+    let values = par.plaintext.random_vec(par.degree(), &mut rng);
+    let pt = Plaintext::try_encode(&values, Encoding::simd(), par)?;
+    let ct: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+
+    let entries = Vec::from([
+        measure("secret_key_gen", iterations, || {
+            SecretKey::random(par, &mut rng);
+            Ok(())
+        })?,
+        measure("relinearization_key_gen", iterations, || {
+            RelinearizationKey::new(&sk, &mut rng).map(|_| ())
+        })?,
+        measure("encrypt", iterations, || {
+            sk.try_encrypt(&pt, &mut rng).map(|_: Ciphertext| ())
+        })?,
+        measure("add", iterations, || {
+            let _ = &ct + &ct;
+            Ok(())
+        })?,
+        measure("mul", iterations, || {
+            let mut product = &ct * &ct;
+            rk.relinearizes(&mut product)
+        })?,
+        measure("rotate", iterations, || {
+            ek.rotates_columns_by(&ct, 1).map(|_| ())
+        })?,
+    ]);
+
+    Ok(BenchReport {
+        parameters: par.id(),
+        degree: par.degree(),
+        modulus_bits: par.moduli_sizes().iter().sum(),
+        plaintext_modulus: par.plaintext(),
+        entries,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::bench_report;
+    use crate::bfv::BfvParameters;
+    use crate::Error;
+    extern crate alloc;
+
+    #[test]
+    fn report() -> Result<(), Error> {
+        let par = BfvParameters::default_arc(2, 16);
+        let report = bench_report(&par, 2)?;
+        assert_eq!(report.parameters, par.id());
+        assert_eq!(report.degree, 16);
+        assert_eq!(report.entries.len(), 6);
+        for operation in [
+            "secret_key_gen",
+            "relinearization_key_gen",
+            "encrypt",
+            "add",
+            "mul",
+            "rotate",
+        ] {
+            assert!(report.ops_per_sec(operation).unwrap() > 0.0);
+        }
+        assert!(report.ops_per_sec("decrypt").is_none());
+
+        let json = report.to_json();
+        assert!(json.starts_with(&alloc::format!("{{\"parameters\":\"{:016x}\"", par.id())));
+        assert!(json.contains("\"operation\":\"rotate\",\"iterations\":2,"));
+
+        assert!(bench_report(&BfvParameters::default_arc(1, 16), 1).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,147 @@
+//! Parameters tuned for the aggregation of many small values followed by a
+//! single multiplication, e.g. in secure aggregation.
+
+use crate::bfv::{BfvParameters, BfvParametersBuilder};
+use crate::{Error, ParametersError, Result};
+use fhe_math::zq::primes::generate_prime;
+extern crate alloc;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec;
+
+/// Maximum size of the ciphertext modulus for each degree, for about 128 bits
+/// of classical security according to the <https://homomorphicencryption.org>
+/// standard.
+const MAX_MODULUS_SIZES: [(usize, usize); 5] = [
+    (2048, 54),
+    (4096, 109),
+    (8192, 218),
+    (16384, 438),
+    (32768, 881),
+];
+
+/// Profile of an aggregation: the sum of `num_summands` ciphertexts
+/// encrypting values smaller than `max_value`, multiplied once by a
+/// ciphertext encrypting values smaller than `max_factor`, as computed by
+/// [`aggregate_then_multiply`](crate::bfv::aggregate_then_multiply).
+///
+/// The multiplication-oriented parameters, with moduli for the
+/// relinearization and the modulus switchings, waste bandwidth for this
+/// profile, which needs neither. [`AggregationProfile::parameters`] selects
+/// instead the smallest parameters whose plaintext modulus holds the result
+/// without wrapping around, and whose ciphertext modulus has just enough noise
+/// budget for the aggregation, with as few moduli as possible.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AggregationProfile {
+    num_summands: u64,
+    max_value: u64,
+    max_factor: u64,
+}
+
+impl AggregationProfile {
+    /// Create the profile of the sum of `num_summands` values smaller than
+    /// `max_value`, multiplied by a value smaller than `max_factor`.
+    ///
+    /// Returns an error if one of the bounds is 0, or if the result does not
+    /// fit in a plaintext modulus of 62 bits.
+    pub fn new(num_summands: u64, max_value: u64, max_factor: u64) -> Result<Self> {
+        if num_summands == 0 || max_value == 0 || max_factor == 0 {
+            return Err(Error::DefaultError(
+                "Invalid aggregation profile".to_string(),
+            ));
+        }
+        let profile = Self {
+            num_summands,
+            max_value,
+            max_factor,
+        };
+        if profile.result_bits() > 61 {
+            return Err(Error::ParametersError(ParametersError::InvalidPlaintext(
+                "The result does not fit in the plaintext modulus".to_string(),
+            )));
+        }
+        Ok(profile)
+    }
+
+    /// Number of bits of the largest result, i.e. of `num_summands *
+    /// (max_value - 1) * (max_factor - 1)`.
+    fn result_bits(&self) -> usize {
+        let max = (self.num_summands as u128)
+            * (self.max_value as u128 - 1)
+            * (self.max_factor as u128 - 1);
+        (128 - max.leading_zeros()) as usize
+    }
+
+    /// Select the smallest parameters supporting the profile, with the SIMD
+    /// encoding, a plaintext modulus larger than the results, and about 128
+    /// bits of classical security.
+    ///
+    /// The noise budget follows the worst-case estimates of
+    /// [`NoiseTable::worst_case`](crate::bfv::NoiseTable::worst_case): the
+    /// noise of a fresh encryption under a public key, one bit per doubling of
+    /// the number of summands, and the growth of a multiplication, plus the
+    /// size of the plaintext modulus to decrypt the product.
+    pub fn parameters(&self) -> Result<Arc<BfvParameters>> {
+        let variance = 10usize;
+        // A bound on the error, i.e. about 6 standard deviations.
+        let sigma_bits = (36 * variance).ilog2() as usize / 2 + 1;
+        let sum_bits = self.num_summands.next_power_of_two().ilog2() as usize;
+        for (degree, max_size) in MAX_MODULUS_SIZES {
+            let degree_bits = degree.ilog2() as usize;
+            // The SIMD encoding requires a prime congruent to 1 modulo 2 * n.
+            let plaintext_bits = (self.result_bits() + 1).max(degree_bits + 2);
+            if plaintext_bits > 62 {
+                break;
+            }
+            let noise =
+                2 * sigma_bits + degree_bits / 2 + sum_bits + plaintext_bits + degree_bits + 1;
+            let size = noise + plaintext_bits + 2;
+            let num_moduli = size.div_ceil(62);
+            let moduli_size = size.div_ceil(num_moduli).max(10);
+            if num_moduli * moduli_size > max_size {
+                continue;
+            }
+            // The ciphertext moduli are the largest primes of their size, so
+            // that a plaintext modulus of the same size is taken lower.
+            let plaintext_modulus =
+                generate_prime(plaintext_bits, 2 * degree as u64, 3 << (plaintext_bits - 2))
+                    .ok_or(Error::ParametersError(ParametersError::InvalidPlaintext(
+                        "No suitable plaintext modulus".to_string(),
+                    )))?;
+            return BfvParametersBuilder::new()
+                .set_degree(degree)
+                .set_plaintext_modulus(plaintext_modulus)
+                .set_moduli_sizes(&vec![moduli_size; num_moduli])
+                .set_variance(variance)
+                .build_arc();
+        }
+        Err(Error::DefaultError(
+            "The aggregation profile is too large for this security level".to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AggregationProfile;
+    use crate::Error;
+
+    #[test]
+    fn parameters() -> Result<(), Error> {
+        // The ciphertext modulus is smaller than the 109 bits of the default
+        // parameters of degree 4096.
+        let params = AggregationProfile::new(100, 1 << 8, 1 << 8)?.parameters()?;
+        assert_eq!(params.degree(), 4096);
+        assert!(params.moduli_sizes().iter().sum::<usize>() < 109);
+        assert!(params.plaintext() > 100 * 255 * 255);
+
+        // The parameters grow with the size of the results.
+        let params = AggregationProfile::new(1 << 20, 1 << 16, 1 << 16)?.parameters()?;
+        assert!(params.degree() >= 4096);
+        assert!(params.plaintext() >= 1 << 52);
+
+        assert!(AggregationProfile::new(0, 1, 1).is_err());
+        assert!(AggregationProfile::new(1 << 30, 1 << 20, 1 << 20).is_err());
+        Ok(())
+    }
+}
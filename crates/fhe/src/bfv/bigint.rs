@@ -0,0 +1,208 @@
+//! Multi-precision integers stored across groups of SIMD slots.
+//!
+//! A big integer is decomposed in `num_limbs` limbs of `limb_bits` bits, and
+//! stored in a group of `2 * num_limbs` consecutive slots of a row; the upper
+//! half of the group is zero at encoding, and provides room for the limbs of
+//! a product. Homomorphic operations never propagate carries: the limbs are
+//! allowed to grow, and the carries are propagated when decoding. Decoding is
+//! therefore exact as long as every limb remains smaller than the plaintext
+//! modulus `t`:
+//! - a fresh encoding has limbs smaller than `2^limb_bits`;
+//! - the sum of `k` encodings has limbs smaller than `k * 2^limb_bits`;
+//! - the product by a plaintext integer has limbs smaller than `num_limbs *
+//!   2^(2 * limb_bits)`.
+
+use super::encoding::EncodingEnum;
+use crate::bfv::{BfvParameters, Ciphertext, Encoding, EvaluationKey, Plaintext};
+use crate::{Error, Result};
+use fhe_traits::{FheDecoder, FheEncoder};
+use num_bigint::BigUint;
+use num_traits::{ToPrimitive, Zero};
+extern crate alloc;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Encoder of multi-precision integers across groups of SIMD slots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BigIntEncoder {
+    par: Arc<BfvParameters>,
+    limb_bits: usize,
+    num_limbs: usize,
+}
+
+impl BigIntEncoder {
+    /// Create an encoder of integers of `limb_bits * num_limbs` bits.
+    ///
+    /// Returns an error if the parameters do not support the SIMD encoding, if
+    /// `2^limb_bits` is not smaller than the plaintext modulus, or if
+    /// `2 * num_limbs` does not divide the number of slots in a row.
+    pub fn new(par: &Arc<BfvParameters>, limb_bits: usize, num_limbs: usize) -> Result<Self> {
+        if par.op.is_none() {
+            return Err(Error::EncodingNotSupported(EncodingEnum::Simd.to_string()));
+        }
+        if limb_bits == 0 || limb_bits >= 64 || (1u64 << limb_bits) >= par.plaintext() {
+            return Err(Error::DefaultError("Invalid limb size".to_string()));
+        }
+        if num_limbs == 0 || (par.degree() / 2) % (2 * num_limbs) != 0 {
+            return Err(Error::DefaultError("Invalid number of limbs".to_string()));
+        }
+        Ok(Self {
+            par: par.clone(),
+            limb_bits,
+            num_limbs,
+        })
+    }
+
+    /// Returns the number of slots of a group.
+    fn group_size(&self) -> usize {
+        2 * self.num_limbs
+    }
+
+    /// Returns the number of integers stored in a plaintext.
+    pub fn num_groups(&self) -> usize {
+        self.par.degree() / self.group_size()
+    }
+
+    /// Returns the slots storing the lower `num_limbs` limbs of `values`.
+    fn limbs(&self, values: &[BigUint]) -> Result<Vec<u64>> {
+        if values.len() > self.num_groups() {
+            return Err(Error::TooManyValues(values.len(), self.num_groups()));
+        }
+        let mask = (1u64 << self.limb_bits) - 1;
+        let mut slots = vec![0u64; self.par.degree()];
+        for (group, value) in values.iter().enumerate() {
+            if value.bits() as usize > self.limb_bits * self.num_limbs {
+                return Err(Error::DefaultError("Value too large".to_string()));
+            }
+            for i in 0..self.num_limbs {
+                let limb = (value >> (i * self.limb_bits)).to_u64().unwrap() & mask;
+                slots[group * self.group_size() + i] = limb;
+            }
+        }
+        Ok(slots)
+    }
+
+    /// Encode integers of at most `limb_bits * num_limbs` bits in a SIMD
+    /// [`Plaintext`] at level `level`.
+    pub fn try_encode(&self, values: &[BigUint], level: usize) -> Result<Plaintext> {
+        let slots = self.limbs(values)?;
+        Plaintext::try_encode(&slots, Encoding::simd_at_level(level), &self.par)
+    }
+
+    /// Decode a SIMD [`Plaintext`], propagating the carries of the limbs.
+    pub fn try_decode(&self, pt: &Plaintext) -> Result<Vec<BigUint>> {
+        let slots = Vec::<u64>::try_decode(pt, Encoding::simd_at_level(pt.level))?;
+        Ok(slots
+            .chunks_exact(self.group_size())
+            .map(|limbs| {
+                limbs.iter().rev().fold(BigUint::zero(), |acc, limb| {
+                    (acc << self.limb_bits) + BigUint::from(*limb)
+                })
+            })
+            .collect())
+    }
+
+    /// Homomorphically add two encrypted integers.
+    pub fn add_bigint(&self, lhs: &Ciphertext, rhs: &Ciphertext) -> Result<Ciphertext> {
+        if lhs.par != self.par || rhs.par != self.par {
+            return Err(Error::DefaultError("Mismatched parameters".to_string()));
+        }
+        if lhs.level != rhs.level || lhs.len() != rhs.len() {
+            return Err(Error::DefaultError(
+                "Mismatched levels or sizes".to_string(),
+            ));
+        }
+        Ok(lhs + rhs)
+    }
+
+    /// Homomorphically multiply encrypted integers by plaintext integers.
+    ///
+    /// This performs a schoolbook multiplication: the ciphertext is multiplied
+    /// by each limb of `values` and shifted by the limb position. The
+    /// evaluation key must support the column rotations by `row_size - j` for
+    /// `j` in `1..num_limbs`, where `row_size` is half the degree. The
+    /// encrypted integers must have been freshly encoded or only added
+    /// together, i.e. their upper `num_limbs` limbs must be zero.
+    pub fn mul_bigint_plain(
+        &self,
+        ct: &Ciphertext,
+        values: &[BigUint],
+        ek: &EvaluationKey,
+    ) -> Result<Ciphertext> {
+        if ct.par != self.par {
+            return Err(Error::DefaultError("Mismatched parameters".to_string()));
+        }
+        let row_size = self.par.degree() / 2;
+        let limbs = self.limbs(values)?;
+        let mut out = Ciphertext::zero(&self.par);
+        for j in 0..self.num_limbs {
+            // Broadcast the j-th limb of each value over the lower half of
+            // its group.
+            let mut slots = vec![0u64; self.par.degree()];
+            for (group, chunk) in slots.chunks_exact_mut(self.group_size()).enumerate() {
+                chunk[..self.num_limbs].fill(limbs[group * self.group_size() + j]);
+            }
+            let pt = Plaintext::try_encode(&slots, Encoding::simd_at_level(ct.level), &self.par)?;
+            let mut product = ct * &pt;
+            if j > 0 {
+                product = ek.rotates_columns_by(&product, row_size - j)?;
+            }
+            out += &product;
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(all(test, not(feature = "server")))]
+mod tests {
+    use super::BigIntEncoder;
+    use crate::bfv::{BfvParameters, Ciphertext, EvaluationKeyBuilder, SecretKey};
+    use crate::Error;
+    use fhe_traits::{FheDecrypter, FheEncrypter};
+    use itertools::Itertools;
+    use num_bigint::BigUint;
+    use rand::{thread_rng, Rng};
+
+    #[test]
+    fn bigint_ops() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(2, 16);
+        assert!(BigIntEncoder::new(&params, 11, 4).is_err());
+        assert!(BigIntEncoder::new(&params, 4, 3).is_err());
+        let encoder = BigIntEncoder::new(&params, 4, 4)?;
+        assert_eq!(encoder.num_groups(), 2);
+
+        let sk = SecretKey::random(&params, &mut rng);
+        let mut builder = EvaluationKeyBuilder::new(&sk)?;
+        for j in 1..4 {
+            builder.enable_column_rotation(8 - j)?;
+        }
+        let ek = builder.build(&mut rng)?;
+
+        let random = |rng: &mut rand::rngs::ThreadRng| {
+            (0..2)
+                .map(|_| BigUint::from(rng.gen::<u16>()))
+                .collect_vec()
+        };
+        let a = random(&mut rng);
+        let b = random(&mut rng);
+        let c = random(&mut rng);
+        assert!(encoder.try_encode(&[BigUint::from(1u32 << 16)], 0).is_err());
+
+        let ct_a: Ciphertext = sk.try_encrypt(&encoder.try_encode(&a, 0)?, &mut rng)?;
+        let ct_b: Ciphertext = sk.try_encrypt(&encoder.try_encode(&b, 0)?, &mut rng)?;
+        assert_eq!(encoder.try_decode(&sk.try_decrypt(&ct_a)?)?, a);
+
+        let ct_sum = encoder.add_bigint(&ct_a, &ct_b)?;
+        let expected = a.iter().zip(b.iter()).map(|(ai, bi)| ai + bi).collect_vec();
+        assert_eq!(encoder.try_decode(&sk.try_decrypt(&ct_sum)?)?, expected);
+
+        let ct_prod = encoder.mul_bigint_plain(&ct_a, &c, &ek)?;
+        let expected = a.iter().zip(c.iter()).map(|(ai, ci)| ai * ci).collect_vec();
+        assert_eq!(encoder.try_decode(&sk.try_decrypt(&ct_prod)?)?, expected);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,239 @@
+//! Encryption of arbitrary byte payloads.
+//!
+//! The bytes are packed in the slots of the plaintexts, `bytes_per_slot`
+//! consecutive bytes per slot in little-endian order, where `bytes_per_slot`
+//! is the largest number of bytes whose values are all smaller than the
+//! plaintext modulus. The SIMD encoding is used when the parameters support it,
+//! so that a field of the payload stays in known slots and can later be
+//! operated on homomorphically.
+
+#[cfg(not(feature = "server"))]
+use crate::bfv::SecretKey;
+use crate::bfv::{BfvParameters, Ciphertext, Encoding, Plaintext, PublicKey};
+use crate::{Error, Result};
+#[cfg(not(feature = "server"))]
+use fhe_traits::{FheDecoder, FheDecrypter};
+use fhe_traits::{FheEncoder, FheEncrypter};
+use rand::{CryptoRng, RngCore};
+extern crate alloc;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+/// Layout of a byte payload encrypted by [`encrypt_bytes`], needed to decrypt
+/// it with [`decrypt_bytes`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteLayout {
+    len: usize,
+    bytes_per_slot: usize,
+    slots_per_ciphertext: usize,
+    simd: bool,
+}
+
+impl ByteLayout {
+    /// Compute the layout of a payload of `len` bytes for the parameters
+    /// `par`.
+    fn new(par: &BfvParameters, len: usize) -> Result<Self> {
+        let bytes_per_slot = (par.plaintext().ilog2() as usize / 8).min(8);
+        if bytes_per_slot == 0 {
+            return Err(Error::DefaultError(
+                "The plaintext modulus is too small to store bytes".to_string(),
+            ));
+        }
+        Ok(Self {
+            len,
+            bytes_per_slot,
+            slots_per_ciphertext: par.degree(),
+            simd: par.op.is_some(),
+        })
+    }
+
+    /// Returns the number of bytes of the payload.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the payload is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of bytes stored in each slot.
+    pub fn bytes_per_slot(&self) -> usize {
+        self.bytes_per_slot
+    }
+
+    /// Returns the number of ciphertexts storing the payload.
+    pub fn num_ciphertexts(&self) -> usize {
+        self.len
+            .div_ceil(self.bytes_per_slot * self.slots_per_ciphertext)
+    }
+
+    /// Returns the index of the ciphertext and of the slot storing the byte at
+    /// `index` in the payload, or `None` if the index is out of bounds.
+    pub fn position(&self, index: usize) -> Option<(usize, usize)> {
+        if index >= self.len {
+            None
+        } else {
+            let slot = index / self.bytes_per_slot;
+            Some((
+                slot / self.slots_per_ciphertext,
+                slot % self.slots_per_ciphertext,
+            ))
+        }
+    }
+
+    /// Returns the encoding of the plaintexts.
+    pub fn encoding(&self) -> Encoding {
+        if self.simd {
+            Encoding::simd()
+        } else {
+            Encoding::poly()
+        }
+    }
+}
+
+/// Encrypt the byte payload `bytes` under the public key `pk`, and returns
+/// the ciphertexts along with the layout of the payload.
+///
+/// Returns an error if the plaintext modulus is smaller than 256.
+pub fn encrypt_bytes<R: RngCore + CryptoRng>(
+    pk: &PublicKey,
+    bytes: &[u8],
+    rng: &mut R,
+) -> Result<(Vec<Ciphertext>, ByteLayout)> {
+    let layout = ByteLayout::new(&pk.par, bytes.len())?;
+    let ciphertexts = bytes
+        .chunks(layout.bytes_per_slot * layout.slots_per_ciphertext)
+        .map(|chunk| {
+            let slots = chunk
+                .chunks(layout.bytes_per_slot)
+                .map(|slot| {
+                    slot.iter()
+                        .rev()
+                        .fold(0u64, |acc, byte| (acc << 8) | *byte as u64)
+                })
+                .collect::<Vec<_>>();
+            let pt = Plaintext::try_encode(&slots, layout.encoding(), &pk.par)?;
+            pk.try_encrypt(&pt, rng)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok((ciphertexts, layout))
+}
+
+/// Decrypt a byte payload encrypted by [`encrypt_bytes`] with the layout
+/// `layout`.
+///
+/// Returns an error if the number of ciphertexts does not match the layout,
+/// or if a slot does not decrypt to `bytes_per_slot` bytes, e.g. when a
+/// homomorphic operation overflowed it.
+#[cfg(not(feature = "server"))]
+pub fn decrypt_bytes(
+    sk: &SecretKey,
+    ciphertexts: &[Ciphertext],
+    layout: &ByteLayout,
+) -> Result<Vec<u8>> {
+    if ciphertexts.len() != layout.num_ciphertexts() {
+        return Err(Error::DefaultError(
+            "The number of ciphertexts does not match the layout".to_string(),
+        ));
+    }
+    let bits = 8 * layout.bytes_per_slot;
+    let mut bytes = Vec::with_capacity(layout.len);
+    for ct in ciphertexts {
+        let pt = sk.try_decrypt(ct)?;
+        for slot in Vec::<u64>::try_decode(&pt, layout.encoding())? {
+            if bits < 64 && slot >> bits != 0 {
+                return Err(Error::DefaultError(
+                    "A slot does not store a valid byte sequence".to_string(),
+                ));
+            }
+            bytes.extend_from_slice(&slot.to_le_bytes()[..layout.bytes_per_slot]);
+        }
+    }
+    bytes.truncate(layout.len);
+    Ok(bytes)
+}
+
+#[cfg(all(test, not(feature = "server")))]
+mod tests {
+    use super::{decrypt_bytes, encrypt_bytes};
+    use crate::bfv::{
+        BfvParameters, BfvParametersBuilder, Encoding, Plaintext, PublicKey, SecretKey,
+    };
+    use crate::Error;
+    use fhe_traits::FheEncoder;
+    use rand::{thread_rng, RngCore};
+    extern crate alloc;
+    use alloc::vec;
+
+    #[test]
+    fn encrypt_decrypt() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        for par in [
+            BfvParameters::default_arc(1, 16),
+            BfvParametersBuilder::new()
+                .set_degree(16)
+                .set_plaintext_modulus(1 << 20)
+                .set_moduli_sizes(&[62, 62])
+                .build_arc()?,
+        ] {
+            let sk = SecretKey::random(&par, &mut rng);
+            let pk = PublicKey::new(&sk, &mut rng);
+            for len in [0, 1, 15, 16, 17, 100] {
+                let mut bytes = vec![0u8; len];
+                rng.fill_bytes(&mut bytes);
+                let (ciphertexts, layout) = encrypt_bytes(&pk, &bytes, &mut rng)?;
+                assert_eq!(layout.len(), len);
+                assert_eq!(ciphertexts.len(), layout.num_ciphertexts());
+                assert_eq!(decrypt_bytes(&sk, &ciphertexts, &layout)?, bytes);
+                if len > 1 {
+                    assert!(decrypt_bytes(&sk, &ciphertexts[1..], &layout).is_err());
+                }
+                assert_eq!(layout.position(len), None);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn layout() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let par = BfvParametersBuilder::new()
+            .set_degree(16)
+            .set_plaintext_modulus(65537)
+            .set_moduli_sizes(&[62, 62])
+            .build_arc()?;
+        let sk = SecretKey::random(&par, &mut rng);
+        let pk = PublicKey::new(&sk, &mut rng);
+        let bytes = (0..40u8).collect::<alloc::vec::Vec<_>>();
+        let (mut ciphertexts, layout) = encrypt_bytes(&pk, &bytes, &mut rng)?;
+        assert_eq!(layout.bytes_per_slot(), 2);
+        assert_eq!(layout.num_ciphertexts(), 2);
+        assert_eq!(layout.encoding(), Encoding::simd());
+        assert_eq!(layout.position(0), Some((0, 0)));
+        assert_eq!(layout.position(33), Some((1, 0)));
+
+        // Add 1 to the byte at index 34 homomorphically.
+        let (ct, slot) = layout.position(34).unwrap();
+        let mut delta = vec![0u64; par.degree()];
+        delta[slot] = 1;
+        ciphertexts[ct] += &Plaintext::try_encode(&delta, layout.encoding(), &par)?;
+        let mut expected = bytes.clone();
+        expected[34] += 1;
+        assert_eq!(decrypt_bytes(&sk, &ciphertexts, &layout)?, expected);
+
+        // Overflow the slot.
+        delta[slot] = 65536 - (35 * 256 + 35);
+        ciphertexts[ct] += &Plaintext::try_encode(&delta, layout.encoding(), &par)?;
+        assert!(decrypt_bytes(&sk, &ciphertexts, &layout).is_err());
+
+        let small = BfvParametersBuilder::new()
+            .set_degree(16)
+            .set_plaintext_modulus(97)
+            .set_moduli_sizes(&[62])
+            .build_arc()?;
+        let pk = PublicKey::new(&SecretKey::random(&small, &mut rng), &mut rng);
+        assert!(encrypt_bytes(&pk, &bytes, &mut rng).is_err());
+        Ok(())
+    }
+}
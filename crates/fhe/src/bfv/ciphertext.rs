@@ -3,11 +3,13 @@
 extern crate alloc;
 
 use crate::bfv::{parameters::BfvParameters, traits::TryConvertFrom};
-use crate::proto::bfv::Ciphertext as CiphertextProto;
+use crate::proto::bfv::{Ciphertext as CiphertextProto, LegacyCiphertext as LegacyCiphertextProto};
 use crate::{Error, Result};
-use alloc::string::ToString;
+use alloc::format;
+use alloc::string::{String, ToString};
 use alloc::sync::Arc;
 use alloc::vec::Vec;
+use core::fmt::Debug;
 use core::ops::{Deref, DerefMut};
 use fhe_math::rq::{Poly, Representation};
 use fhe_traits::{
@@ -16,9 +18,17 @@ use fhe_traits::{
 use prost::Message;
 use rand::SeedableRng;
 use rand_chacha::ChaCha8Rng;
+use smallvec::SmallVec;
+
+/// The elements of a ciphertext, stored inline for the ciphertexts of size 2
+/// and 3, i.e. the fresh ciphertexts and the products before relinearization.
+pub(crate) type CiphertextElements = SmallVec<[Poly; 3]>;
 
 /// A ciphertext encrypting a plaintext.
-#[derive(Debug, Clone, PartialEq, Eq)]
+///
+/// The [`Debug`] implementation only prints a summary of the ciphertext; use
+/// [`Ciphertext::dump`] to print its polynomials.
+#[derive(Clone)]
 pub struct Ciphertext {
     /// The parameters of the underlying BFV encryption scheme.
     pub(crate) par: Arc<BfvParameters>,
@@ -27,10 +37,55 @@ pub struct Ciphertext {
     pub(crate) seed: Option<<ChaCha8Rng as SeedableRng>::Seed>,
 
     /// The ciphertext elements.
-    pub(crate) c: Vec<Poly>,
+    pub(crate) c: CiphertextElements,
 
     /// The ciphertext level
     pub(crate) level: usize,
+
+    /// The tag attached to the ciphertext, empty if there is none.
+    pub(crate) tag: Vec<u8>,
+
+    /// The noise estimate, in bits, of the evaluator which computed the
+    /// ciphertext, if it records the provenance of its ciphertexts.
+    pub(crate) noise_estimate: Option<usize>,
+}
+
+// The noise estimate is not part of the ciphertext, so that it does not
+// affect the equality.
+impl PartialEq for Ciphertext {
+    fn eq(&self, other: &Self) -> bool {
+        self.par == other.par
+            && self.seed == other.seed
+            && self.c == other.c
+            && self.level == other.level
+            && self.tag == other.tag
+    }
+}
+
+impl Eq for Ciphertext {}
+
+impl Debug for Ciphertext {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Ciphertext")
+            .field("params", &format_args!("{:016x}", self.par.id()))
+            .field("level", &self.level)
+            .field("size", &self.c.len())
+            .field("seeded", &self.seed.is_some())
+            .field("tag_len", &self.tag.len())
+            .field("noise_estimate", &self.noise_estimate)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Ciphertext {
+    /// Returns the full contents of the ciphertext, including its
+    /// polynomials, for debugging purposes.
+    pub fn dump(&self) -> String {
+        format!(
+            "Ciphertext {{ par: {:?}, seed: {:?}, c: {:?}, level: {}, tag: {:?} }}",
+            self.par, self.seed, self.c, self.level, self.tag
+        )
+    }
 }
 
 impl Deref for Ciphertext {
@@ -64,6 +119,7 @@ impl Ciphertext {
     }
 
     /// Truncate the underlying vector of polynomials.
+    #[cfg(not(feature = "client"))]
     pub(crate) fn truncate(&mut self, len: usize) {
         self.c.truncate(len)
     }
@@ -109,10 +165,137 @@ impl Ciphertext {
         Ok(Self {
             par: par.clone(),
             seed: None,
-            c,
+            c: c.into(),
+            level,
+            tag: Vec::new(),
+            noise_estimate: None,
+        })
+    }
+
+    /// Create a ciphertext at level `level` from raw polynomials, e.g. RLWE
+    /// samples generated by other code.
+    ///
+    /// This is an advanced API: the polynomials must be defined over the
+    /// context of the parameters at this level, and are converted to the Ntt
+    /// representation, but nothing ensures that they form a valid encryption
+    /// under the parameters.
+    pub fn from_polys(mut c: Vec<Poly>, level: usize, par: &Arc<BfvParameters>) -> Result<Self> {
+        if c.len() < 2 {
+            return Err(Error::TooFewValues(c.len(), 2));
+        }
+        let ctx = par.ctx_at_level(level)?;
+        for ci in c.iter_mut() {
+            if ci.ctx() != ctx {
+                return Err(Error::MathError(fhe_math::Error::InvalidContext));
+            }
+            ci.change_representation(Representation::Ntt);
+        }
+
+        Ok(Self {
+            par: par.clone(),
+            seed: None,
+            c: c.into(),
             level,
+            tag: Vec::new(),
+            noise_estimate: None,
         })
     }
+
+    /// Returns the polynomials of the ciphertext, in Ntt representation; see
+    /// [`Ciphertext::from_polys`].
+    pub fn into_polys(self) -> Vec<Poly> {
+        self.c.into_vec()
+    }
+
+    /// Returns the level of the ciphertext.
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    /// Put the ciphertext in a canonical form, which does not change the
+    /// plaintext it encrypts: the seed of `c1` is dropped, so that `c1` is
+    /// serialized in full, and the polynomials are in Ntt representation and
+    /// disallow variable time computations.
+    ///
+    /// Two ciphertexts with equal polynomials may differ in these respects,
+    /// e.g. a fresh ciphertext and its copy rebuilt with
+    /// [`Ciphertext::from_polys`]; see [`Ciphertext::eq_ignoring_seed`].
+    pub fn canonicalize(&mut self) {
+        self.seed = None;
+        for poly in self.c.iter_mut() {
+            poly.change_representation(Representation::Ntt);
+            poly.disallow_variable_time_computations();
+        }
+    }
+
+    /// Reports whether the ciphertexts are equal once canonicalized (see
+    /// [`Ciphertext::canonicalize`]), i.e. whether they have the same
+    /// parameters, level, tag and polynomials, regardless of whether `c1` is
+    /// seeded. This compares the ciphertexts structurally, without decrypting
+    /// them, e.g. in round-trip tests.
+    pub fn eq_ignoring_seed(&self, other: &Self) -> bool {
+        let mut lhs = self.clone();
+        let mut rhs = other.clone();
+        lhs.canonicalize();
+        rhs.canonicalize();
+        lhs == rhs
+    }
+
+    /// Attach a tag to the ciphertext, such as a client or a query identifier.
+    ///
+    /// The tag is serialized with the ciphertext, and is preserved by the
+    /// homomorphic operations on a single ciphertext. The result of an
+    /// operation on several ciphertexts keeps their tag only if all of them
+    /// have the same tag, and is untagged otherwise, so that tags are never
+    /// mixed. The tag is not authenticated nor encrypted.
+    ///
+    /// Returns an error if the tag is larger than [`Ciphertext::MAX_TAG_SIZE`]
+    /// bytes.
+    pub fn set_tag(&mut self, tag: &[u8]) -> Result<()> {
+        if tag.len() > Self::MAX_TAG_SIZE {
+            return Err(Error::TooManyValues(tag.len(), Self::MAX_TAG_SIZE));
+        }
+        self.tag = tag.to_vec();
+        Ok(())
+    }
+
+    /// Returns the tag attached to the ciphertext, which is empty if there is
+    /// none.
+    pub fn tag(&self) -> &[u8] {
+        &self.tag
+    }
+
+    /// Remove the tag attached to the ciphertext.
+    pub fn clear_tag(&mut self) {
+        self.tag.clear()
+    }
+
+    /// Maximum size of a tag, in bytes.
+    pub const MAX_TAG_SIZE: usize = 64;
+
+    /// Keep the tag only if it is equal to the tag of `other`.
+    #[cfg(not(feature = "client"))]
+    pub(crate) fn merge_tag(&mut self, other: &Ciphertext) {
+        if self.tag != other.tag {
+            self.tag.clear()
+        }
+    }
+
+    /// Returns the tag shared by all the ciphertexts, or an empty tag if the
+    /// ciphertexts do not all have the same tag.
+    #[cfg(not(feature = "client"))]
+    pub(crate) fn common_tag<'a, I>(cts: I) -> Vec<u8>
+    where
+        I: IntoIterator<Item = &'a Ciphertext>,
+    {
+        let mut cts = cts.into_iter();
+        let tag = cts.next().map(|ct| ct.tag.clone()).unwrap_or_default();
+        if cts.all(|ct| ct.tag == tag) {
+            tag
+        } else {
+            Vec::new()
+        }
+    }
 }
 
 impl FheCiphertext for Ciphertext {}
@@ -123,14 +306,23 @@ impl FheParametrized for Ciphertext {
 
 impl Serialize for Ciphertext {
     fn to_bytes(&self) -> Vec<u8> {
-        CiphertextProto::from(self).encode_to_vec()
+        let bytes = CiphertextProto::from(self).encode_to_vec();
+        #[cfg(feature = "metrics")]
+        crate::metrics::record(crate::metrics::Metric::BytesSerialized, bytes.len() as u64);
+        bytes
     }
 }
 
 impl DeserializeParametrized for Ciphertext {
+    // The ciphertexts serialized by the legacy `bfv` crate are detected and
+    // upgraded, see `Ciphertext::from_legacy_bytes`.
     fn from_bytes(bytes: &[u8], par: &Arc<BfvParameters>) -> Result<Self> {
         if let Ok(ctp) = Message::decode(bytes) {
-            Ciphertext::try_convert_from(&ctp, par)
+            if Ciphertext::is_legacy(&ctp) {
+                Ciphertext::from_legacy_bytes(bytes, par)
+            } else {
+                Ciphertext::try_convert_from(&ctp, par)
+            }
         } else {
             Err(Error::SerializationError)
         }
@@ -147,8 +339,35 @@ impl Ciphertext {
             seed: None,
             c: Default::default(),
             level: 0,
+            tag: Vec::new(),
+            noise_estimate: None,
         }
     }
+
+    /// Deserialize a ciphertext serialized by the legacy `bfv` crate, made of
+    /// the pair of polynomials `(c0, c1)` at level 0, into a ciphertext at
+    /// level 0. The ciphertexts serialized in the current format are
+    /// rejected.
+    ///
+    /// [`DeserializeParametrized::from_bytes`] detects and upgrades the legacy
+    /// ciphertexts, so that this function is only needed to force the legacy
+    /// format.
+    pub fn from_legacy_bytes(bytes: &[u8], par: &Arc<BfvParameters>) -> Result<Self> {
+        let proto: LegacyCiphertextProto =
+            Message::decode(bytes).map_err(|_| Error::SerializationError)?;
+        Ciphertext::try_convert_from(&proto, par)
+    }
+
+    /// Returns whether the message decoded as a ciphertext is a legacy
+    /// ciphertext, whose second polynomial is then decoded as the seed: legacy
+    /// ciphertexts have no level nor tag, and the seeds have a fixed size.
+    fn is_legacy(value: &CiphertextProto) -> bool {
+        value.c.len() == 1
+            && !value.seed.is_empty()
+            && value.seed.len() != <ChaCha8Rng as SeedableRng>::Seed::default().len()
+            && value.level == 0
+            && value.tag.is_empty()
+    }
 }
 
 /// Conversions from and to protobuf.
@@ -164,6 +383,7 @@ impl From<&Ciphertext> for CiphertextProto {
             proto.c.push(ct[ct.len() - 1].to_bytes())
         }
         proto.level = ct.level as u32;
+        proto.tag = ct.tag.clone();
         proto
     }
 }
@@ -178,9 +398,13 @@ impl TryConvertFrom<&CiphertextProto> for Ciphertext {
             return Err(Error::DefaultError("Invalid level".to_string()));
         }
 
+        if value.tag.len() > Ciphertext::MAX_TAG_SIZE {
+            return Err(Error::DefaultError("Invalid tag".to_string()));
+        }
+
         let ctx = par.ctx_at_level(value.level as usize)?;
 
-        let mut c = Vec::with_capacity(value.c.len() + 1);
+        let mut c = CiphertextElements::with_capacity(value.c.len() + 1);
         for cip in &value.c {
             c.push(Poly::from_bytes(cip, ctx)?)
         }
@@ -205,23 +429,97 @@ impl TryConvertFrom<&CiphertextProto> for Ciphertext {
             seed,
             c,
             level: value.level as usize,
+            tag: value.tag.clone(),
+            noise_estimate: None,
         })
     }
 }
 
+impl TryConvertFrom<&LegacyCiphertextProto> for Ciphertext {
+    fn try_convert_from(value: &LegacyCiphertextProto, par: &Arc<BfvParameters>) -> Result<Self> {
+        let ctx = par.ctx_at_level(0)?;
+        let c0 = Poly::from_bytes(&value.c0, ctx).map_err(|_| Error::SerializationError)?;
+        let c1 = Poly::from_bytes(&value.c1, ctx).map_err(|_| Error::SerializationError)?;
+        Ciphertext::new(alloc::vec![c0, c1], par)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::bfv::{
-        traits::TryConvertFrom, BfvParameters, Ciphertext, Encoding, Plaintext, SecretKey,
-    };
+    use crate::bfv::{traits::TryConvertFrom, BfvParameters, Ciphertext};
+    #[cfg(not(any(feature = "client", feature = "server")))]
+    use crate::bfv::{Encoding, Plaintext, PublicKey, SecretKey};
     use crate::proto::bfv::Ciphertext as CiphertextProto;
+    #[cfg(not(any(feature = "client", feature = "server")))]
+    use crate::proto::bfv::LegacyCiphertext as LegacyCiphertextProto;
     use crate::Error;
-    use fhe_traits::FheDecrypter;
-    use fhe_traits::{DeserializeParametrized, FheEncoder, FheEncrypter, Serialize};
+    use fhe_math::rq::{Poly, Representation};
+    use fhe_traits::{DeserializeParametrized, Serialize};
+    #[cfg(not(any(feature = "client", feature = "server")))]
+    use fhe_traits::{FheDecrypter, FheEncoder, FheEncrypter};
+    #[cfg(not(any(feature = "client", feature = "server")))]
+    use prost::Message;
     use rand::thread_rng;
     extern crate alloc;
+    #[cfg(not(any(feature = "client", feature = "server")))]
     use alloc::vec;
+    use alloc::vec::Vec;
 
+    #[test]
+    fn random_polys() -> Result<(), Error> {
+        // The ciphertexts built from polynomials do not need a secret key.
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(3, 16);
+        let ctx = params.ctx_at_level(1)?;
+        let polys = (0..2)
+            .map(|_| Poly::random(ctx, Representation::Ntt, &mut rng))
+            .collect::<Vec<_>>();
+        let mut ct = Ciphertext::from_polys(polys, 1, &params)?;
+        assert_eq!(ct.level(), 1);
+        assert_eq!(
+            alloc::format!("{ct:?}"),
+            alloc::format!(
+                "Ciphertext {{ params: {:016x}, level: 1, size: 2, seeded: false, tag_len: 0, noise_estimate: None, .. }}",
+                params.id()
+            )
+        );
+
+        ct.set_tag(b"tag")?;
+        assert_eq!(Ciphertext::from_bytes(&ct.to_bytes(), &params)?, ct);
+        let ct_proto = CiphertextProto::from(&ct);
+        assert_eq!(Ciphertext::try_convert_from(&ct_proto, &params)?, ct);
+
+        ct.mod_switch_to_last_level()?;
+        assert_eq!(ct.level(), params.max_level());
+        assert_eq!(ct.tag(), b"tag");
+        Ok(())
+    }
+
+    #[cfg(not(any(feature = "client", feature = "server")))]
+    #[test]
+    fn debug() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(2, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let v = params.plaintext.random_vec(params.degree(), &mut rng);
+        let pt = Plaintext::try_encode(&v, Encoding::poly(), &params)?;
+        let ct: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+
+        let debug = alloc::format!("{ct:?}");
+        assert_eq!(
+            debug,
+            alloc::format!(
+                "Ciphertext {{ params: {:016x}, level: 0, size: 2, seeded: true, tag_len: 0, noise_estimate: None, .. }}",
+                params.id()
+            )
+        );
+        let dump = ct.dump();
+        assert!(dump.len() > debug.len());
+        assert!(dump.contains(&alloc::format!("{:?}", ct[0])));
+        Ok(())
+    }
+
+    #[cfg(not(any(feature = "client", feature = "server")))]
     #[test]
     fn proto_conversion() -> Result<(), Error> {
         let mut rng = thread_rng();
@@ -243,6 +541,40 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(not(any(feature = "client", feature = "server")))]
+    #[test]
+    fn canonicalize() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(2, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let v = params.plaintext.random_vec(params.degree(), &mut rng);
+        let pt = Plaintext::try_encode(&v, Encoding::simd(), &params)?;
+        let ct: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+        assert!(ct.seed.is_some());
+
+        // The copy rebuilt from the polynomials is not seeded.
+        let copy = Ciphertext::from_polys(ct.clone().into_polys(), 0, &params)?;
+        assert_ne!(ct, copy);
+        assert!(ct.eq_ignoring_seed(&copy));
+        assert!(copy.eq_ignoring_seed(&ct));
+        let proto = CiphertextProto::from(&copy);
+        assert!(Ciphertext::try_convert_from(&proto, &params)?.eq_ignoring_seed(&ct));
+
+        let mut canonical = ct.clone();
+        canonical.canonicalize();
+        assert!(canonical.seed.is_none());
+        assert!(canonical.eq_ignoring_seed(&ct));
+        assert_eq!(sk.try_decrypt(&canonical)?, pt);
+
+        let other: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+        assert!(!ct.eq_ignoring_seed(&other));
+        let mut tagged = copy.clone();
+        tagged.set_tag(b"tag")?;
+        assert!(!ct.eq_ignoring_seed(&tagged));
+        Ok(())
+    }
+
+    #[cfg(not(any(feature = "client", feature = "server")))]
     #[test]
     fn serialize() -> Result<(), Error> {
         let mut rng = thread_rng();
@@ -260,6 +592,84 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(not(any(feature = "client", feature = "server")))]
+    #[test]
+    fn legacy() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        for params in [
+            BfvParameters::default_arc(1, 16),
+            BfvParameters::default_arc(6, 16),
+        ] {
+            let sk = SecretKey::random(&params, &mut rng);
+            let pk = PublicKey::new(&sk, &mut rng);
+            let v = params.plaintext.random_vec(params.degree(), &mut rng);
+            let pt = Plaintext::try_encode(&v, Encoding::simd(), &params)?;
+            let ct: Ciphertext = pk.try_encrypt(&pt, &mut rng)?;
+            let legacy_bytes = LegacyCiphertextProto {
+                c0: ct[0].to_bytes(),
+                c1: ct[1].to_bytes(),
+            }
+            .encode_to_vec();
+
+            assert_eq!(Ciphertext::from_legacy_bytes(&legacy_bytes, &params)?, ct);
+            let upgraded = Ciphertext::from_bytes(&legacy_bytes, &params)?;
+            assert_eq!(upgraded, ct);
+            assert_eq!(sk.try_decrypt(&upgraded)?, pt);
+            // Once upgraded, the ciphertext is serialized in the current format.
+            assert_eq!(Ciphertext::from_bytes(&upgraded.to_bytes(), &params)?, ct);
+            assert!(Ciphertext::from_legacy_bytes(&upgraded.to_bytes(), &params).is_err());
+
+            // The seeded ciphertexts are not mistaken for legacy ones.
+            let ct: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+            assert_eq!(Ciphertext::from_bytes(&ct.to_bytes(), &params)?, ct);
+        }
+        Ok(())
+    }
+
+    #[cfg(not(any(feature = "client", feature = "server")))]
+    #[test]
+    fn tag() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(6, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let v = params.plaintext.random_vec(params.degree(), &mut rng);
+        let pt = Plaintext::try_encode(&v, Encoding::simd(), &params)?;
+        let mut ct: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+        assert!(ct.tag().is_empty());
+
+        assert!(ct.set_tag(&[0u8; Ciphertext::MAX_TAG_SIZE + 1]).is_err());
+        ct.set_tag(b"client 1")?;
+        assert_eq!(ct.tag(), b"client 1");
+
+        // The tag survives serialization.
+        let ct_bytes = ct.to_bytes();
+        assert_eq!(
+            Ciphertext::from_bytes(&ct_bytes, &params)?.tag(),
+            b"client 1"
+        );
+
+        // The tag is preserved by operations on a single ciphertext...
+        assert_eq!((-&ct).tag(), b"client 1");
+        assert_eq!((&ct * &pt).tag(), b"client 1");
+        assert_eq!((&ct + &ct).tag(), b"client 1");
+        let mut ct2 = &ct * &ct;
+        assert_eq!(ct2.tag(), b"client 1");
+        ct2.mod_switch_to_last_level()?;
+        assert_eq!(ct2.tag(), b"client 1");
+
+        // ... but never mixed.
+        let mut other: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+        assert!((&ct + &other).tag().is_empty());
+        other.set_tag(b"client 2")?;
+        assert!((&ct - &other).tag().is_empty());
+        assert!((&ct * &other).tag().is_empty());
+
+        ct.clear_tag();
+        assert!(ct.tag().is_empty());
+        Ok(())
+    }
+
+    #[cfg(not(any(feature = "client", feature = "server")))]
     #[test]
     fn new() -> Result<(), Error> {
         let mut rng = thread_rng();
@@ -298,6 +708,37 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(not(any(feature = "client", feature = "server")))]
+    #[test]
+    fn from_polys() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(3, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let v = params.plaintext.random_vec(params.degree(), &mut rng);
+        let pt = Plaintext::try_encode(&v, Encoding::simd(), &params)?;
+        let mut ct: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+        ct.mod_switch_to_next_level()?;
+        assert_eq!(ct.level(), 1);
+
+        let mut polys = ct.clone().into_polys();
+        assert_eq!(Ciphertext::from_polys(polys.clone(), 1, &params)?, ct);
+
+        // The polynomials are converted to the Ntt representation.
+        polys
+            .iter_mut()
+            .for_each(|p| p.change_representation(Representation::PowerBasis));
+        let ct2 = Ciphertext::from_polys(polys.clone(), 1, &params)?;
+        assert_eq!(ct2, ct);
+        assert_eq!(sk.try_decrypt(&ct2)?, pt);
+
+        assert!(Ciphertext::from_polys(polys.clone(), 0, &params).is_err());
+        assert!(Ciphertext::from_polys(polys.clone(), 5, &params).is_err());
+        assert!(Ciphertext::from_polys(polys[..1].to_vec(), 1, &params).is_err());
+
+        Ok(())
+    }
+
+    #[cfg(not(any(feature = "client", feature = "server")))]
     #[test]
     fn mod_switch_to_last_level() -> Result<(), Error> {
         let mut rng = thread_rng();
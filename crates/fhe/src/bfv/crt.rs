@@ -0,0 +1,410 @@
+//! Parallel tracks of ciphertexts with distinct plaintext moduli.
+//!
+//! An integer is encrypted as its residues modulo `k` pairwise coprime
+//! plaintext moduli `t_1, ..., t_k`, one ciphertext per residue, all with the
+//! same ciphertext moduli and the same secret key. The homomorphic operations
+//! are computed independently on each track, and the results are recombined
+//! by the Chinese Remainder Theorem at decryption, so that the arithmetic is
+//! exact modulo `t_1 * ... * t_k`, e.g. a message space of more than 120 bits
+//! with 6 plaintext moduli of 20 bits.
+//!
+//! Unlike the limbs of a [`super::BigIntEncoder`], the residues never carry
+//! into each other, so that ciphertexts can be multiplied together; the price
+//! is one ciphertext, and one evaluation, per track.
+
+#[cfg(not(feature = "server"))]
+use crate::bfv::SecretKey;
+use crate::bfv::{
+    BfvParameters, BfvParametersBuilder, Ciphertext, Encoding, Evaluator, Plaintext,
+    RelinearizationKey,
+};
+use crate::{Error, Result};
+#[cfg(not(feature = "server"))]
+use core::fmt::Debug;
+use fhe_math::rns::RnsContext;
+#[cfg(not(feature = "server"))]
+use fhe_traits::FheDecrypter;
+#[cfg(not(feature = "server"))]
+use fhe_traits::FheEncrypter;
+use fhe_traits::{FheDecoder, FheEncoder};
+use itertools::{izip, Itertools};
+use ndarray::Array2;
+use num_bigint::BigUint;
+#[cfg(not(feature = "server"))]
+use rand::{CryptoRng, RngCore};
+extern crate alloc;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+#[cfg(not(feature = "server"))]
+use zeroize::Zeroizing;
+
+/// Parameters of the tracks: one set of [`BfvParameters`] per plaintext
+/// modulus, which only differ by their plaintext modulus.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CrtParameters {
+    tracks: Vec<Arc<BfvParameters>>,
+    rns: RnsContext,
+}
+
+impl CrtParameters {
+    /// Create the parameters of tracks with the ring, the ciphertext moduli
+    /// and the variance of `par`, and the plaintext moduli
+    /// `plaintext_moduli`.
+    ///
+    /// Returns an error if the plaintext moduli are not pairwise coprime, or
+    /// if one of them is not a valid plaintext modulus for `par`.
+    pub fn new(par: &BfvParameters, plaintext_moduli: &[u64]) -> Result<Arc<Self>> {
+        let rns = RnsContext::new(plaintext_moduli)?;
+        let tracks = plaintext_moduli
+            .iter()
+            .map(|t| {
+                let mut builder = BfvParametersBuilder::new();
+                if par.cyclotomic_index() == 2 * par.degree() {
+                    builder.set_degree(par.degree());
+                } else {
+                    builder.set_prime_cyclotomic_index(par.cyclotomic_index());
+                }
+                builder
+                    .set_plaintext_modulus(*t)
+                    .set_moduli(par.moduli())
+                    .set_variance(par.variance)
+                    .build_arc()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Arc::new(Self { tracks, rns }))
+    }
+
+    /// Returns the parameters of each track.
+    pub fn tracks(&self) -> &[Arc<BfvParameters>] {
+        &self.tracks
+    }
+
+    /// Returns the effective plaintext modulus, i.e. the product of the
+    /// plaintext moduli of the tracks.
+    pub fn plaintext_modulus(&self) -> &BigUint {
+        self.rns.modulus()
+    }
+
+    /// Encode integers, reduced modulo the effective plaintext modulus, in
+    /// one [`Plaintext`] per track.
+    pub fn try_encode(&self, values: &[BigUint], encoding: Encoding) -> Result<Vec<Plaintext>> {
+        let residues = values.iter().map(|v| self.rns.project(v)).collect_vec();
+        self.tracks
+            .iter()
+            .enumerate()
+            .map(|(i, par)| {
+                let v = residues.iter().map(|r| r[i]).collect_vec();
+                Plaintext::try_encode(&v, encoding.clone(), par)
+            })
+            .collect()
+    }
+
+    /// Decode the plaintexts of the tracks, recombining the values by CRT.
+    pub fn try_decode(&self, pts: &[Plaintext], encoding: Encoding) -> Result<Vec<BigUint>> {
+        if pts.len() != self.tracks.len()
+            || izip!(pts, &self.tracks).any(|(pt, par)| &pt.par != par)
+        {
+            return Err(Error::DefaultError("Mismatched parameters".to_string()));
+        }
+        let residues = pts
+            .iter()
+            .map(|pt| Vec::<u64>::try_decode(pt, encoding.clone()))
+            .collect::<Result<Vec<_>>>()?;
+        let rests = Array2::from_shape_vec((residues.len(), residues[0].len()), residues.concat())
+            .map_err(|_| Error::DefaultError("Mismatched encodings".to_string()))?;
+        Ok(self.rns.lift_vec(rests.view()))
+    }
+}
+
+/// An integer encrypted as one [`Ciphertext`] per track.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrtCiphertext {
+    par: Arc<CrtParameters>,
+    tracks: Vec<Ciphertext>,
+}
+
+impl CrtCiphertext {
+    /// Create a ciphertext from the ciphertexts of the tracks, e.g. encrypted
+    /// with a [`super::PublicKey`] of each track.
+    pub fn new(par: &Arc<CrtParameters>, tracks: Vec<Ciphertext>) -> Result<Self> {
+        if tracks.len() != par.tracks.len()
+            || izip!(&tracks, &par.tracks).any(|(ct, par)| &ct.par != par)
+            || tracks.iter().any(|ct| ct.level != tracks[0].level)
+        {
+            return Err(Error::DefaultError(
+                "Mismatched parameters or levels".to_string(),
+            ));
+        }
+        Ok(Self {
+            par: par.clone(),
+            tracks,
+        })
+    }
+
+    /// Returns the ciphertexts of the tracks.
+    pub fn tracks(&self) -> &[Ciphertext] {
+        &self.tracks
+    }
+}
+
+/// Secret key of the tracks, with the same secret coefficients in each track.
+#[cfg(not(feature = "server"))]
+#[derive(PartialEq, Eq)]
+pub struct CrtSecretKey {
+    par: Arc<CrtParameters>,
+    tracks: Vec<SecretKey>,
+}
+
+#[cfg(not(feature = "server"))]
+impl Debug for CrtSecretKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CrtSecretKey")
+            .field("tracks", &self.tracks.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(not(feature = "server"))]
+impl CrtSecretKey {
+    /// Generate a random secret key.
+    pub fn random<R: RngCore + CryptoRng>(par: &Arc<CrtParameters>, rng: &mut R) -> Self {
+        let sk = SecretKey::random(&par.tracks[0], rng);
+        let tracks = par
+            .tracks
+            .iter()
+            .map(|track| SecretKey::new(sk.coeffs.to_vec(), track))
+            .collect();
+        Self {
+            par: par.clone(),
+            tracks,
+        }
+    }
+
+    /// Returns the secret keys of the tracks, e.g. to generate their public
+    /// keys or their evaluation keys.
+    pub fn tracks(&self) -> &[SecretKey] {
+        &self.tracks
+    }
+
+    /// Generate the relinearization keys of the tracks.
+    pub fn relinearization_keys<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+    ) -> Result<Vec<RelinearizationKey>> {
+        self.tracks
+            .iter()
+            .map(|sk| RelinearizationKey::new(sk, rng))
+            .collect()
+    }
+
+    /// Encrypt integers, reduced modulo the effective plaintext modulus.
+    pub fn try_encrypt<R: RngCore + CryptoRng>(
+        &self,
+        values: &[BigUint],
+        encoding: Encoding,
+        rng: &mut R,
+    ) -> Result<CrtCiphertext> {
+        let pts = Zeroizing::new(self.par.try_encode(values, encoding)?);
+        let tracks = izip!(&self.tracks, pts.iter())
+            .map(|(sk, pt)| sk.try_encrypt(pt, rng))
+            .collect::<Result<Vec<_>>>()?;
+        CrtCiphertext::new(&self.par, tracks)
+    }
+
+    /// Decrypt a ciphertext, and recombine the integers by CRT.
+    pub fn try_decrypt(&self, ct: &CrtCiphertext, encoding: Encoding) -> Result<Vec<BigUint>> {
+        if ct.par != self.par {
+            return Err(Error::DefaultError("Mismatched parameters".to_string()));
+        }
+        let pts = Zeroizing::new(
+            izip!(&self.tracks, &ct.tracks)
+                .map(|(sk, ct)| sk.try_decrypt(ct))
+                .collect::<Result<Vec<_>>>()?,
+        );
+        self.par.try_decode(&pts, encoding)
+    }
+}
+
+/// Evaluator of homomorphic operations on [`CrtCiphertext`]s, holding one
+/// [`Evaluator`] per track.
+#[derive(Debug)]
+pub struct CrtEvaluator {
+    par: Arc<CrtParameters>,
+    tracks: Vec<Evaluator>,
+}
+
+impl CrtEvaluator {
+    /// Create an evaluator without keys.
+    pub fn new(par: &Arc<CrtParameters>) -> Self {
+        Self {
+            par: par.clone(),
+            tracks: par.tracks.iter().map(Evaluator::new).collect(),
+        }
+    }
+
+    /// Use the relinearization keys `rks` of the tracks to relinearize the
+    /// products of ciphertexts.
+    pub fn with_relinearization_keys(mut self, rks: &[RelinearizationKey]) -> Result<Self> {
+        if rks.len() != self.tracks.len() {
+            return Err(Error::TooFewValues(rks.len(), self.tracks.len()));
+        }
+        self.tracks = self
+            .tracks
+            .into_iter()
+            .zip(rks)
+            .map(|(evaluator, rk)| evaluator.with_relinearization_key(rk))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(self)
+    }
+
+    /// Returns the evaluators of the tracks, e.g. to add their evaluation keys.
+    pub fn tracks_mut(&mut self) -> &mut [Evaluator] {
+        &mut self.tracks
+    }
+
+    /// Add two ciphertexts.
+    pub fn add(&mut self, a: &CrtCiphertext, b: &CrtCiphertext) -> Result<CrtCiphertext> {
+        self.binary(a, b, Evaluator::add)
+    }
+
+    /// Subtract the ciphertext `b` from the ciphertext `a`.
+    pub fn sub(&mut self, a: &CrtCiphertext, b: &CrtCiphertext) -> Result<CrtCiphertext> {
+        self.binary(a, b, Evaluator::sub)
+    }
+
+    /// Multiply two ciphertexts.
+    pub fn mul(&mut self, a: &CrtCiphertext, b: &CrtCiphertext) -> Result<CrtCiphertext> {
+        self.binary(a, b, Evaluator::mul)
+    }
+
+    /// Negate a ciphertext.
+    pub fn neg(&mut self, a: &CrtCiphertext) -> Result<CrtCiphertext> {
+        self.check(a)?;
+        let tracks = izip!(&mut self.tracks, &a.tracks)
+            .map(|(evaluator, ct)| evaluator.neg(ct))
+            .collect::<Result<Vec<_>>>()?;
+        CrtCiphertext::new(&self.par, tracks)
+    }
+
+    /// Add integers, encoded with `encoding` at the level of `a`, to a
+    /// ciphertext.
+    pub fn add_plain(
+        &mut self,
+        a: &CrtCiphertext,
+        values: &[BigUint],
+        encoding: Encoding,
+    ) -> Result<CrtCiphertext> {
+        self.plain(a, values, encoding, Evaluator::add_plaintext)
+    }
+
+    /// Multiply a ciphertext by integers, encoded with `encoding` at the level
+    /// of `a`.
+    pub fn mul_plain(
+        &mut self,
+        a: &CrtCiphertext,
+        values: &[BigUint],
+        encoding: Encoding,
+    ) -> Result<CrtCiphertext> {
+        self.plain(a, values, encoding, Evaluator::mul_plaintext)
+    }
+
+    fn check(&self, a: &CrtCiphertext) -> Result<()> {
+        if a.par != self.par {
+            return Err(Error::DefaultError("Mismatched parameters".to_string()));
+        }
+        Ok(())
+    }
+
+    fn binary<F>(&mut self, a: &CrtCiphertext, b: &CrtCiphertext, op: F) -> Result<CrtCiphertext>
+    where
+        F: Fn(&mut Evaluator, &Ciphertext, &Ciphertext) -> Result<Ciphertext>,
+    {
+        self.check(a)?;
+        self.check(b)?;
+        let tracks = izip!(&mut self.tracks, &a.tracks, &b.tracks)
+            .map(|(evaluator, a, b)| op(evaluator, a, b))
+            .collect::<Result<Vec<_>>>()?;
+        CrtCiphertext::new(&self.par, tracks)
+    }
+
+    fn plain<F>(
+        &mut self,
+        a: &CrtCiphertext,
+        values: &[BigUint],
+        encoding: Encoding,
+        op: F,
+    ) -> Result<CrtCiphertext>
+    where
+        F: Fn(&mut Evaluator, &Ciphertext, &Plaintext) -> Result<Ciphertext>,
+    {
+        self.check(a)?;
+        let pts = self.par.try_encode(values, encoding)?;
+        let tracks = izip!(&mut self.tracks, &a.tracks, &pts)
+            .map(|(evaluator, ct, pt)| op(evaluator, ct, pt))
+            .collect::<Result<Vec<_>>>()?;
+        CrtCiphertext::new(&self.par, tracks)
+    }
+}
+
+#[cfg(all(test, not(feature = "server")))]
+mod tests {
+    use super::{CrtEvaluator, CrtParameters, CrtSecretKey};
+    use crate::bfv::{BfvParameters, Encoding};
+    use crate::Error;
+    use fhe_math::zq::primes::generate_prime;
+    use itertools::{izip, Itertools};
+    use num_bigint::BigUint;
+    use rand::{thread_rng, Rng};
+    extern crate alloc;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn tracks() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(3, 16);
+        // Three primes of 20 bits supporting the SIMD encoding.
+        let mut plaintext_moduli = Vec::new();
+        let mut upper_bound = 1 << 20;
+        while plaintext_moduli.len() < 3 {
+            upper_bound = generate_prime(20, 32, upper_bound).unwrap();
+            plaintext_moduli.push(upper_bound);
+        }
+        let par = CrtParameters::new(&params, &plaintext_moduli)?;
+        assert_eq!(par.tracks().len(), 3);
+        assert!(par.plaintext_modulus().bits() > 57);
+        assert!(CrtParameters::new(&params, &[plaintext_moduli[0]; 2]).is_err());
+
+        let sk = CrtSecretKey::random(&par, &mut rng);
+        assert_eq!(alloc::format!("{sk:?}"), "CrtSecretKey { tracks: 3, .. }");
+        let rks = sk.relinearization_keys(&mut rng)?;
+        let mut evaluator = CrtEvaluator::new(&par).with_relinearization_keys(&rks)?;
+
+        // Values of 28 bits, whose products have 56 bits.
+        let random = |rng: &mut rand::rngs::ThreadRng| {
+            (0..16)
+                .map(|_| BigUint::from(rng.gen_range(0u64..1 << 28)))
+                .collect_vec()
+        };
+        let (a, b, c) = (random(&mut rng), random(&mut rng), random(&mut rng));
+        let ct_a = sk.try_encrypt(&a, Encoding::simd(), &mut rng)?;
+        let ct_b = sk.try_encrypt(&b, Encoding::simd(), &mut rng)?;
+        assert_eq!(sk.try_decrypt(&ct_a, Encoding::simd())?, a);
+
+        let product = evaluator.mul(&ct_a, &ct_b)?;
+        let result = evaluator.add_plain(&product, &c, Encoding::simd())?;
+        let expected = izip!(&a, &b, &c)
+            .map(|(ai, bi, ci)| ai * bi + ci)
+            .collect_vec();
+        assert_eq!(sk.try_decrypt(&result, Encoding::simd())?, expected);
+
+        let negation = evaluator.neg(&ct_a)?;
+        let difference = evaluator.sub(&negation, &ct_b)?;
+        let modulus = par.plaintext_modulus();
+        let expected = izip!(&a, &b)
+            .map(|(ai, bi)| (modulus * 2u32 - ai - bi) % modulus)
+            .collect_vec();
+        assert_eq!(sk.try_decrypt(&difference, Encoding::simd())?, expected);
+        Ok(())
+    }
+}
@@ -1,12 +1,13 @@
 //! The encoding type for BFV.
 
 use alloc::fmt::Display;
+use core::hash::{Hash, Hasher};
 extern crate alloc;
 use alloc::string::String;
 
 use fhe_traits::FhePlaintextEncoding;
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub(crate) enum EncodingEnum {
     Poly,
     Simd,
@@ -18,11 +19,66 @@ impl Display for EncodingEnum {
     }
 }
 
+/// Behavior of the encoders when a value to encode is out of range.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum OutOfRange {
+    /// Return an error.
+    #[default]
+    Error,
+    /// Silently reduce the value modulo the plaintext modulus.
+    Reduce,
+}
+
+/// Options of the encoders.
+///
+/// By default, the encoders return an error when a value is out of range,
+/// i.e. when an unsigned value is larger than or equal to the plaintext
+/// modulus `t`, or when a signed value is not in the interval `[-(t - t / 2),
+/// t / 2)` decoded by the signed decoder.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub struct EncodeOptions {
+    /// Behavior of the encoders when a value is out of range.
+    pub on_out_of_range: OutOfRange,
+    /// Number of bits of headroom reserved for the growth of the values, e.g.
+    /// in a fixed-point pipeline: the range is computed with `t / 2^headroom_bits`
+    /// instead of `t`, so that the values can be multiplied by up to
+    /// `2^headroom_bits` without wrapping around. The headroom is only
+    /// enforced when `on_out_of_range` is [`OutOfRange::Error`].
+    pub headroom_bits: usize,
+}
+
+impl EncodeOptions {
+    /// Returns the bound of the range of the values, i.e. the plaintext
+    /// modulus `t` divided by `2^headroom_bits`.
+    pub(crate) fn range_bound(&self, t: u64) -> u64 {
+        t.checked_shr(self.headroom_bits as u32).unwrap_or(0)
+    }
+}
+
 /// An encoding for the plaintext.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq)]
 pub struct Encoding {
     pub(crate) encoding: EncodingEnum,
     pub(crate) level: usize,
+    pub(crate) options: EncodeOptions,
+    /// Maximum degree of the encoded polynomials, for the Poly encoding.
+    pub(crate) max_degree: Option<usize>,
+}
+
+// Implement the equality and the hash manually; the options and the maximum
+// degree only affect the validation of the values at encoding time, not how
+// they are encoded.
+impl PartialEq for Encoding {
+    fn eq(&self, other: &Self) -> bool {
+        self.encoding == other.encoding && self.level == other.level
+    }
+}
+
+impl Hash for Encoding {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.encoding.hash(state);
+        self.level.hash(state);
+    }
 }
 
 impl Encoding {
@@ -32,6 +88,27 @@ impl Encoding {
         Self {
             encoding: EncodingEnum::Poly,
             level: 0,
+            options: EncodeOptions::default(),
+            max_degree: None,
+        }
+    }
+
+    /// A Poly encoding of polynomials of degree at most `max_degree`, so that
+    /// the product of two such polynomials does not wrap around.
+    ///
+    /// The Poly encoding multiplies polynomials modulo `x^n + 1`: the
+    /// coefficients of degree `n + i` of a product are subtracted from the
+    /// coefficients of degree `i`. The encoders return an error if a value has
+    /// a non-zero coefficient of degree larger than `max_degree`, or if
+    /// `2 * max_degree` is not smaller than the degree `n`, so that the
+    /// product of two encoded values, or of their encryptions, is the product
+    /// of the polynomials without reduction.
+    pub fn poly_mul_safe(max_degree: usize) -> Self {
+        Self {
+            encoding: EncodingEnum::Poly,
+            level: 0,
+            options: EncodeOptions::default(),
+            max_degree: Some(max_degree),
         }
     }
 
@@ -43,6 +120,8 @@ impl Encoding {
         Self {
             encoding: EncodingEnum::Simd,
             level: 0,
+            options: EncodeOptions::default(),
+            max_degree: None,
         }
     }
 
@@ -51,6 +130,8 @@ impl Encoding {
         Self {
             encoding: EncodingEnum::Poly,
             level,
+            options: EncodeOptions::default(),
+            max_degree: None,
         }
     }
 
@@ -59,8 +140,27 @@ impl Encoding {
         Self {
             encoding: EncodingEnum::Simd,
             level,
+            options: EncodeOptions::default(),
+            max_degree: None,
         }
     }
+
+    /// Set the options of the encoders.
+    pub fn with_options(mut self, options: EncodeOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Returns the options of the encoders.
+    pub fn options(&self) -> EncodeOptions {
+        self.options
+    }
+
+    /// Returns the maximum degree of the encoded polynomials, set by
+    /// [`Encoding::poly_mul_safe`].
+    pub fn max_degree(&self) -> Option<usize> {
+        self.max_degree
+    }
 }
 
 impl From<Encoding> for String {
@@ -0,0 +1,231 @@
+//! Checkpoints of the state of an [`super::Evaluator`], to resume a
+//! long-running computation after a crash.
+
+use super::Operation;
+use crate::bfv::{traits::TryConvertFrom, BfvParameters, Ciphertext};
+use crate::proto::bfv::{
+    Ciphertext as CiphertextProto, EvaluatorCheckpoint as EvaluatorCheckpointProto,
+};
+use crate::{Error, Result};
+use fhe_traits::{DeserializeParametrized, FheParametrized, Serialize};
+use prost::Message;
+use sha2::{Digest, Sha256};
+extern crate alloc;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+/// Counters of the operations computed by an [`super::Evaluator`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OperationCounts {
+    /// Number of operations, including the multiplications and rotations.
+    pub operations: u64,
+    /// Number of multiplications of two ciphertexts.
+    pub multiplications: u64,
+    /// Number of rotations of the rows or of the columns.
+    pub rotations: u64,
+}
+
+impl OperationCounts {
+    pub(crate) fn count(&mut self, op: &Operation) {
+        self.operations += 1;
+        match op {
+            Operation::Mul => self.multiplications += 1,
+            Operation::RotateRows | Operation::RotateColumns(_) => self.rotations += 1,
+            _ => {}
+        }
+    }
+}
+
+/// Checkpoint of the state of a long-running computation of an
+/// [`super::Evaluator`], e.g. an aggregation: its accumulator ciphertexts and
+/// the operation counters of the evaluator, created with
+/// [`super::Evaluator::checkpoint`] and restored with
+/// [`super::Evaluator::resume`].
+///
+/// The serialization records the identifier of the parameters, the level of
+/// each accumulator and a SHA-256 digest of the checkpoint, which are all
+/// verified when deserializing, so that a corrupted or mismatched checkpoint
+/// is not silently resumed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvaluatorCheckpoint {
+    pub(crate) par: Arc<BfvParameters>,
+    pub(crate) accumulators: Vec<Ciphertext>,
+    pub(crate) counts: OperationCounts,
+}
+
+impl EvaluatorCheckpoint {
+    /// Returns the accumulator ciphertexts.
+    pub fn accumulators(&self) -> &[Ciphertext] {
+        &self.accumulators
+    }
+
+    /// Returns the operation counters of the evaluator.
+    pub fn operation_counts(&self) -> OperationCounts {
+        self.counts
+    }
+
+    /// Compute the digest of a serialized checkpoint whose digest is empty.
+    fn digest(proto: &EvaluatorCheckpointProto) -> Vec<u8> {
+        Sha256::digest(proto.encode_to_vec()).to_vec()
+    }
+}
+
+impl FheParametrized for EvaluatorCheckpoint {
+    type Parameters = BfvParameters;
+}
+
+impl Serialize for EvaluatorCheckpoint {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut proto = EvaluatorCheckpointProto {
+            params_id: self.par.id(),
+            accumulators: self
+                .accumulators
+                .iter()
+                .map(CiphertextProto::from)
+                .collect(),
+            levels: self.accumulators.iter().map(|ct| ct.level as u32).collect(),
+            operations: self.counts.operations,
+            multiplications: self.counts.multiplications,
+            rotations: self.counts.rotations,
+            digest: Vec::new(),
+        };
+        proto.digest = Self::digest(&proto);
+        proto.encode_to_vec()
+    }
+}
+
+impl DeserializeParametrized for EvaluatorCheckpoint {
+    type Error = Error;
+
+    /// Deserialize an [`EvaluatorCheckpoint`].
+    ///
+    /// Returns [`Error::IntegrityCheckFailed`] if the digest does not match
+    /// the checkpoint, and [`Error::SerializationError`] if the checkpoint was
+    /// created for other parameters, or if an accumulator is not at its
+    /// recorded level.
+    fn from_bytes(bytes: &[u8], par: &Arc<BfvParameters>) -> Result<Self> {
+        let mut proto: EvaluatorCheckpointProto =
+            Message::decode(bytes).map_err(|_| Error::SerializationError)?;
+        let digest = core::mem::take(&mut proto.digest);
+        if digest != Self::digest(&proto) {
+            return Err(Error::IntegrityCheckFailed);
+        }
+        if proto.params_id != par.id() || proto.levels.len() != proto.accumulators.len() {
+            return Err(Error::SerializationError);
+        }
+        let accumulators = proto
+            .accumulators
+            .iter()
+            .zip(&proto.levels)
+            .map(|(ct, level)| {
+                let ct = Ciphertext::try_convert_from(ct, par)?;
+                if ct.level != *level as usize {
+                    return Err(Error::SerializationError);
+                }
+                Ok(ct)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            par: par.clone(),
+            accumulators,
+            counts: OperationCounts {
+                operations: proto.operations,
+                multiplications: proto.multiplications,
+                rotations: proto.rotations,
+            },
+        })
+    }
+}
+
+#[cfg(all(test, not(feature = "server")))]
+mod tests {
+    use super::EvaluatorCheckpoint;
+    use crate::bfv::{
+        BfvParameters, Ciphertext, Encoding, Evaluator, Plaintext, RelinearizationKey, SecretKey,
+    };
+    use crate::Error;
+    use fhe_traits::{
+        DeserializeParametrized, FheDecoder, FheDecrypter, FheEncoder, FheEncrypter, Serialize,
+    };
+    use rand::thread_rng;
+    extern crate alloc;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn checkpoint_and_resume() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(3, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let rk = RelinearizationKey::new(&sk, &mut rng)?;
+        let t = &params.plaintext;
+        let values = (0..6)
+            .map(|_| t.random_vec(params.degree(), &mut rng))
+            .collect::<Vec<_>>();
+        let cts = values
+            .iter()
+            .map(|v| {
+                let pt = Plaintext::try_encode(v, Encoding::simd(), &params)?;
+                sk.try_encrypt(&pt, &mut rng)
+            })
+            .collect::<Result<Vec<Ciphertext>, Error>>()?;
+
+        // Accumulate the sum and the sum of squares of the first half.
+        let mut evaluator = Evaluator::new(&params).with_relinearization_key(&rk)?;
+        let mut sum = cts[0].clone();
+        let mut squares = evaluator.mul(&cts[0], &cts[0])?;
+        for ct in &cts[1..3] {
+            sum = evaluator.add(&sum, ct)?;
+            let square = evaluator.mul(ct, ct)?;
+            squares = evaluator.add(&squares, &square)?;
+        }
+        let squares = evaluator.mod_switch_to_next_level(&squares)?;
+        let counts = evaluator.operation_counts();
+        assert_eq!(counts.operations, 8);
+        assert_eq!(counts.multiplications, 3);
+        assert_eq!(counts.rotations, 0);
+        let bytes = evaluator.checkpoint(&[sum, squares])?.to_bytes();
+
+        // Resume with a new evaluator after a crash.
+        let checkpoint = EvaluatorCheckpoint::from_bytes(&bytes, &params)?;
+        assert_eq!(checkpoint.operation_counts(), counts);
+        let mut evaluator = Evaluator::new(&params).with_relinearization_key(&rk)?;
+        let accumulators = evaluator.resume(checkpoint)?;
+        assert_eq!(evaluator.operation_counts(), counts);
+        let (mut sum, mut squares) = (accumulators[0].clone(), accumulators[1].clone());
+        assert_eq!(squares.level, 1);
+        for ct in &cts[3..] {
+            sum = evaluator.add(&sum, ct)?;
+            let square = evaluator.mul(ct, ct)?;
+            let square = evaluator.mod_switch_to_next_level(&square)?;
+            squares = evaluator.add(&squares, &square)?;
+        }
+        assert_eq!(evaluator.operation_counts().multiplications, 6);
+
+        let decrypt = |ct: &Ciphertext| -> Result<Vec<u64>, Error> {
+            Vec::<u64>::try_decode(&sk.try_decrypt(ct)?, Encoding::simd())
+        };
+        let (sum, squares) = (decrypt(&sum)?, decrypt(&squares)?);
+        for j in 0..params.degree() {
+            assert_eq!(sum[j], values.iter().fold(0, |acc, v| t.add(acc, v[j])));
+            assert_eq!(
+                squares[j],
+                values
+                    .iter()
+                    .fold(0, |acc, v| t.add(acc, t.mul(v[j], v[j])))
+            );
+        }
+
+        // The integrity and the parameters of the checkpoint are verified.
+        let mut corrupted = bytes.clone();
+        let last = corrupted.len() - 40;
+        corrupted[last] ^= 1;
+        assert!(EvaluatorCheckpoint::from_bytes(&corrupted, &params).is_err());
+        let other = BfvParameters::default_arc(2, 16);
+        assert!(matches!(
+            EvaluatorCheckpoint::from_bytes(&bytes, &other),
+            Err(Error::SerializationError)
+        ));
+        assert!(Evaluator::new(&other).checkpoint(&cts).is_err());
+        Ok(())
+    }
+}
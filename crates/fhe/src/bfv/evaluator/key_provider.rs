@@ -0,0 +1,31 @@
+//! Providers of the keys of an evaluator stored outside of the memory.
+
+use crate::bfv::{GaloisKey, RelinearizationKey};
+use crate::Result;
+extern crate alloc;
+use alloc::sync::Arc;
+use core::fmt::Debug;
+
+/// Provider of the keys of an [`Evaluator`](super::Evaluator), so that large
+/// key sets can be stored externally, e.g. in a database or an object store,
+/// and fetched on demand rather than being fully resident in memory.
+///
+/// The keys can be serialized and deserialized with the [`Serialize`] and
+/// [`DeserializeParametrized`] traits. The provider is responsible for any
+/// caching of the fetched keys: the evaluator only keeps the relinearization
+/// key, which it fetches once, and fetches the Galois keys for each rotation
+/// which is not supported by its evaluation key. The provider is shared, e.g.
+/// between the evaluators of several threads, and must therefore be `Send`
+/// and `Sync`.
+///
+/// [`Serialize`]: fhe_traits::Serialize
+/// [`DeserializeParametrized`]: fhe_traits::DeserializeParametrized
+pub trait KeyProvider: Debug + Send + Sync {
+    /// Returns the Galois key of exponent `element` for ciphertexts at level
+    /// `level`, i.e. generated with `ciphertext_level = level`, if the
+    /// provider has it.
+    fn galois_key(&self, element: usize, level: usize) -> Result<Option<Arc<GaloisKey>>>;
+
+    /// Returns the relinearization key, if the provider has it.
+    fn relin_key(&self) -> Result<Option<Arc<RelinearizationKey>>>;
+}
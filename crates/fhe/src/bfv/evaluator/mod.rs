@@ -0,0 +1,1230 @@
+//! Evaluator of homomorphic operations for the BFV encryption scheme.
+
+mod checkpoint;
+mod key_provider;
+mod multiplex;
+mod noise_table;
+mod provenance;
+
+pub use checkpoint::{EvaluatorCheckpoint, OperationCounts};
+pub use key_provider::KeyProvider;
+pub use multiplex::SlotMultiplexer;
+pub use noise_table::NoiseTable;
+pub use provenance::{Operation, ProvenanceNode, ProvenanceRecorder};
+
+#[cfg(not(feature = "server"))]
+use crate::bfv::SecretKey;
+use crate::bfv::{
+    keys::GaloisKey, BfvParameters, Ciphertext, Encoding, EvaluationKey, Multiplicator, Plaintext,
+    RelinearizationKey,
+};
+use crate::{Error, Result};
+use core::fmt::Debug;
+use fhe_math::zq::Modulus;
+use fhe_traits::FheEncoder;
+use hashbrown::HashMap;
+#[cfg(not(feature = "server"))]
+use rand::{CryptoRng, Rng, RngCore, SeedableRng};
+#[cfg(not(feature = "server"))]
+use rand_chacha::ChaCha8Rng;
+extern crate alloc;
+use alloc::borrow::Cow;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+/// Policy of the [`Evaluator`] for the modulus switching.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ModSwitchPolicy {
+    /// The ciphertexts are only switched to the next level explicitly, using
+    /// [`Evaluator::mod_switch_to_next_level`].
+    #[default]
+    Manual,
+    /// The operands of a multiplication are switched to the next level before
+    /// (rather than after) the multiplication when the estimated noise budget
+    /// allows it, which reduces the size of the basis of the tensor product.
+    /// The operands are not switched if this would prevent relinearizing the
+    /// product.
+    BeforeMultiplication,
+}
+
+/// Evaluator of homomorphic operations on [`Ciphertext`]s.
+///
+/// The evaluator holds the keys required by the operations, and can record
+/// the provenance of the ciphertexts it computes in a [`ProvenanceRecorder`].
+/// When the secret key is available, e.g. for a client-side evaluation, the
+/// Galois keys required by the rotations are generated on demand. The keys
+/// can also be fetched on demand from a [`KeyProvider`].
+pub struct Evaluator {
+    par: Arc<BfvParameters>,
+    rk: Option<RelinearizationKey>,
+    multiplicator: Option<Multiplicator>,
+    ek: Option<EvaluationKey>,
+    delay_relinearization: bool,
+    mod_switch_policy: ModSwitchPolicy,
+    recorder: Option<ProvenanceRecorder>,
+    noise: NoiseTable,
+    #[cfg(not(feature = "server"))]
+    keygen: Option<(SecretKey, ChaCha8Rng)>,
+    /// Galois keys generated on demand, indexed by exponent and level.
+    galois_keys: HashMap<(usize, usize), Arc<GaloisKey>>,
+    provider: Option<Arc<dyn KeyProvider>>,
+    counts: OperationCounts,
+}
+
+// The secret key is redacted from the debug output.
+impl Debug for Evaluator {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut s = f.debug_struct("Evaluator");
+        s.field("par", &self.par)
+            .field("rk", &self.rk)
+            .field("multiplicator", &self.multiplicator)
+            .field("ek", &self.ek)
+            .field("delay_relinearization", &self.delay_relinearization)
+            .field("mod_switch_policy", &self.mod_switch_policy)
+            .field("recorder", &self.recorder)
+            .field("noise", &self.noise);
+        #[cfg(not(feature = "server"))]
+        s.field("secret_key", &self.keygen.is_some());
+        s.field("galois_keys", &self.galois_keys)
+            .field("provider", &self.provider)
+            .field("counts", &self.counts)
+            .finish()
+    }
+}
+
+impl Evaluator {
+    /// Create an evaluator without keys; such an evaluator only supports
+    /// linear operations and multiplications without relinearization.
+    pub fn new(par: &Arc<BfvParameters>) -> Self {
+        Self {
+            par: par.clone(),
+            rk: None,
+            multiplicator: None,
+            ek: None,
+            delay_relinearization: false,
+            mod_switch_policy: ModSwitchPolicy::Manual,
+            recorder: None,
+            noise: NoiseTable::worst_case(par),
+            #[cfg(not(feature = "server"))]
+            keygen: None,
+            galois_keys: HashMap::default(),
+            provider: None,
+            counts: OperationCounts::default(),
+        }
+    }
+
+    /// Use the relinearization key `rk` to relinearize the products of
+    /// ciphertexts.
+    pub fn with_relinearization_key(mut self, rk: &RelinearizationKey) -> Result<Self> {
+        self.set_relinearization_key(rk)?;
+        Ok(self)
+    }
+
+    fn set_relinearization_key(&mut self, rk: &RelinearizationKey) -> Result<()> {
+        if rk.ksk.par != self.par {
+            return Err(Error::DefaultError(
+                "Incompatible BFV parameters".to_string(),
+            ));
+        }
+        let mut multiplicator = Multiplicator::default(rk)?;
+        if self.delay_relinearization {
+            multiplicator.enable_delayed_relinearization()
+        }
+        self.multiplicator = Some(multiplicator);
+        self.rk = Some(rk.clone());
+        Ok(())
+    }
+
+    /// Delay the relinearization of the products of ciphertexts: they are
+    /// relinearized only when required, i.e. before a rotation, before a
+    /// product whose size would exceed what the relinearization key supports,
+    /// or when calling [`Evaluator::finalize`], e.g. before a serialization.
+    pub fn with_delayed_relinearization(mut self) -> Self {
+        self.delay_relinearization = true;
+        if let Some(multiplicator) = self.multiplicator.as_mut() {
+            multiplicator.enable_delayed_relinearization()
+        }
+        self
+    }
+
+    /// Use the evaluation key `ek` to rotate ciphertexts.
+    pub fn with_evaluation_key(mut self, ek: EvaluationKey) -> Result<Self> {
+        if ek.par != self.par {
+            return Err(Error::DefaultError(
+                "Incompatible BFV parameters".to_string(),
+            ));
+        }
+        self.ek = Some(ek);
+        Ok(self)
+    }
+
+    /// Fetch the keys from `provider` when they are needed: the relinearization
+    /// key, unless set with [`Evaluator::with_relinearization_key`], and the
+    /// Galois keys of the rotations which are not supported by the evaluation
+    /// key. The provider takes precedence over the generation of the Galois
+    /// keys with the secret key.
+    pub fn with_key_provider(mut self, provider: Arc<dyn KeyProvider>) -> Self {
+        self.provider = Some(provider);
+        self
+    }
+
+    /// Estimate the noise of the ciphertexts with the table `table`, e.g.
+    /// calibrated for the parameters, instead of the worst-case estimates of
+    /// [`NoiseTable::worst_case`].
+    pub fn with_noise_table(mut self, table: NoiseTable) -> Result<Self> {
+        if table.par != self.par || table.key_switching.len() != self.par.max_level() + 1 {
+            return Err(Error::DefaultError("Incompatible noise table".to_string()));
+        }
+        self.noise = table;
+        Ok(self)
+    }
+
+    /// Set the modulus switching policy. The noise budget is estimated using
+    /// the provenance recording, which is therefore enabled by the
+    /// [`ModSwitchPolicy::BeforeMultiplication`] policy; the ciphertexts of
+    /// unknown provenance are assumed to be fresh encryptions.
+    pub fn with_mod_switch_policy(mut self, policy: ModSwitchPolicy) -> Self {
+        self.mod_switch_policy = policy;
+        if policy == ModSwitchPolicy::BeforeMultiplication && self.recorder.is_none() {
+            self.enable_provenance()
+        }
+        self
+    }
+
+    /// Use the secret key `sk` to generate, the first time they are needed,
+    /// the Galois keys of the rotations which are not supported by the
+    /// evaluation key. The generated keys are cached, so that interactive
+    /// applications do not pay the cost of generating all the keys upfront.
+    #[cfg(not(feature = "server"))]
+    pub fn with_secret_key<R: RngCore + CryptoRng>(
+        mut self,
+        sk: &SecretKey,
+        rng: &mut R,
+    ) -> Result<Self> {
+        if sk.par != self.par {
+            return Err(Error::DefaultError(
+                "Incompatible BFV parameters".to_string(),
+            ));
+        }
+        let mut seed = <ChaCha8Rng as SeedableRng>::Seed::default();
+        rng.fill(&mut seed);
+        self.keygen = Some((sk.clone(), ChaCha8Rng::from_seed(seed)));
+        Ok(self)
+    }
+
+    /// Returns the number of Galois keys generated on demand so far.
+    pub fn num_generated_galois_keys(&self) -> usize {
+        self.galois_keys.len()
+    }
+
+    /// Start recording the provenance of the ciphertexts computed by the
+    /// evaluator, discarding any previous recording. The computed ciphertexts
+    /// then carry their noise estimate, which their
+    /// [`Debug`](core::fmt::Debug) output prints.
+    pub fn enable_provenance(&mut self) {
+        self.recorder = Some(ProvenanceRecorder::new())
+    }
+
+    /// Returns the provenance recorder, if the recording is enabled.
+    pub fn provenance(&self) -> Option<&ProvenanceRecorder> {
+        self.recorder.as_ref()
+    }
+
+    /// Stop recording the provenance, and returns the recorder.
+    pub fn take_provenance(&mut self) -> Option<ProvenanceRecorder> {
+        self.recorder.take()
+    }
+
+    /// Returns the counters of the operations computed by the evaluator.
+    pub fn operation_counts(&self) -> OperationCounts {
+        self.counts
+    }
+
+    /// Create a checkpoint of the accumulator ciphertexts `accumulators` of a
+    /// long-running computation and of the operation counters, to be
+    /// serialized, e.g. to disk, and resumed with [`Evaluator::resume`] after
+    /// a crash.
+    pub fn checkpoint(&self, accumulators: &[Ciphertext]) -> Result<EvaluatorCheckpoint> {
+        accumulators.iter().try_for_each(|ct| self.check(ct))?;
+        Ok(EvaluatorCheckpoint {
+            par: self.par.clone(),
+            accumulators: accumulators.to_vec(),
+            counts: self.counts,
+        })
+    }
+
+    /// Resume a computation from a checkpoint: restore the operation counters
+    /// and returns the accumulator ciphertexts.
+    ///
+    /// Returns an error if the checkpoint was created for other parameters.
+    pub fn resume(&mut self, checkpoint: EvaluatorCheckpoint) -> Result<Vec<Ciphertext>> {
+        if checkpoint.par != self.par {
+            return Err(Error::DefaultError(
+                "Incompatible BFV parameters".to_string(),
+            ));
+        }
+        self.counts = checkpoint.counts;
+        Ok(checkpoint.accumulators)
+    }
+
+    /// Add two ciphertexts.
+    pub fn add(&mut self, a: &Ciphertext, b: &Ciphertext) -> Result<Ciphertext> {
+        self.check_pair(a, b)?;
+        let mut ct = a + b;
+        let growth = self.noise.add;
+        self.record_binary(Operation::Add, a, b, &mut ct, |na, nb| na.max(nb) + growth);
+        Ok(ct)
+    }
+
+    /// Subtract the ciphertext `b` from the ciphertext `a`.
+    pub fn sub(&mut self, a: &Ciphertext, b: &Ciphertext) -> Result<Ciphertext> {
+        self.check_pair(a, b)?;
+        let mut ct = a - b;
+        let growth = self.noise.add;
+        self.record_binary(Operation::Sub, a, b, &mut ct, |na, nb| na.max(nb) + growth);
+        Ok(ct)
+    }
+
+    /// Negate a ciphertext.
+    pub fn neg(&mut self, a: &Ciphertext) -> Result<Ciphertext> {
+        self.check(a)?;
+        let mut ct = -a;
+        self.record_unary(Operation::Neg, a, &mut ct, |na| na);
+        Ok(ct)
+    }
+
+    /// Add a plaintext to a ciphertext.
+    pub fn add_plaintext(&mut self, a: &Ciphertext, pt: &Plaintext) -> Result<Ciphertext> {
+        self.check_plaintext(a, pt)?;
+        let mut ct = a + pt;
+        let (t_bits, growth) = (self.plaintext_bits(), self.noise.add);
+        self.record_unary(Operation::AddPlaintext, a, &mut ct, |na| {
+            na.max(t_bits) + growth
+        });
+        Ok(ct)
+    }
+
+    /// Subtract a plaintext from a ciphertext.
+    pub fn sub_plaintext(&mut self, a: &Ciphertext, pt: &Plaintext) -> Result<Ciphertext> {
+        self.check_plaintext(a, pt)?;
+        let mut ct = a - pt;
+        let (t_bits, growth) = (self.plaintext_bits(), self.noise.add);
+        self.record_unary(Operation::SubPlaintext, a, &mut ct, |na| {
+            na.max(t_bits) + growth
+        });
+        Ok(ct)
+    }
+
+    /// Multiply a ciphertext by a plaintext.
+    pub fn mul_plaintext(&mut self, a: &Ciphertext, pt: &Plaintext) -> Result<Ciphertext> {
+        self.check_plaintext(a, pt)?;
+        let mut ct = a * pt;
+        let growth = self.noise.mul_plaintext;
+        self.record_unary(Operation::MulPlaintext, a, &mut ct, |na| na + growth);
+        Ok(ct)
+    }
+
+    /// Multiply two ciphertexts. The product is relinearized if the evaluator
+    /// has a relinearization key for the level of the ciphertexts.
+    pub fn mul(&mut self, a: &Ciphertext, b: &Ciphertext) -> Result<Ciphertext> {
+        self.check_pair(a, b)?;
+        self.fetch_relinearization_key()?;
+        let (a, b) = if self.mod_switches_before_mul(a, b) {
+            (
+                Cow::Owned(self.mod_switch_to_next_level(a)?),
+                Cow::Owned(self.mod_switch_to_next_level(b)?),
+            )
+        } else {
+            (Cow::Borrowed(a), Cow::Borrowed(b))
+        };
+        let (a, b) = (a.as_ref(), b.as_ref());
+        // Relinearize the operands if the product could not be relinearized.
+        let (a, b) = match self.max_relinearizable_size(a.level) {
+            Some(max_size) if a.len() + b.len() - 1 > max_size => {
+                (self.relinearized(a)?, self.relinearized(b)?)
+            }
+            _ => (Cow::Borrowed(a), Cow::Borrowed(b)),
+        };
+        let (a, b) = (a.as_ref(), b.as_ref());
+        let growth = self.mul_growth();
+        match (&self.multiplicator, &self.rk) {
+            (Some(multiplicator), Some(rk))
+                if a.len() == 2 && b.len() == 2 && a.level == rk.ksk.ciphertext_level =>
+            {
+                let mut ct = multiplicator.multiply(a, b)?;
+                let ks = self.key_switching_noise(a.level);
+                self.record_binary(Operation::Mul, a, b, &mut ct, |na, nb| {
+                    (na.max(nb) + growth).max(ks)
+                });
+                Ok(ct)
+            }
+            _ => {
+                let mut ct = a * b;
+                self.record_binary(Operation::Mul, a, b, &mut ct, |na, nb| na.max(nb) + growth);
+                Ok(ct)
+            }
+        }
+    }
+
+    /// Relinearize a ciphertext of size 3, or larger if the relinearization
+    /// key supports it, into a ciphertext of size 2.
+    pub fn relinearize(&mut self, a: &Ciphertext) -> Result<Ciphertext> {
+        self.check(a)?;
+        self.fetch_relinearization_key()?;
+        let rk = self
+            .rk
+            .as_ref()
+            .ok_or(Error::DefaultError("No relinearization key".to_string()))?;
+        let mut ct = a.clone();
+        rk.relinearize_to_size_2(&mut ct)?;
+        let ks = self.key_switching_noise(a.level);
+        self.record_unary(Operation::Relinearize, a, &mut ct, |na| na.max(ks));
+        Ok(ct)
+    }
+
+    /// Relinearize a ciphertext to size 2 if needed, e.g. before serializing
+    /// a ciphertext computed with delayed relinearization.
+    pub fn finalize(&mut self, a: &Ciphertext) -> Result<Ciphertext> {
+        self.check(a)?;
+        Ok(self.relinearized(a)?.into_owned())
+    }
+
+    /// Rotate the rows of a ciphertext.
+    ///
+    /// With the SIMD encoding, the `n` slots form a matrix of 2 rows of `n / 2`
+    /// slots, the first row holding the slots `0..n / 2`; this rotation swaps
+    /// the two rows.
+    pub fn rotate_rows(&mut self, a: &Ciphertext) -> Result<Ciphertext> {
+        self.check(a)?;
+        let a = self.relinearized(a)?;
+        let a = a.as_ref();
+        let mut ct = match &self.ek {
+            Some(ek) if ek.ciphertext_level == a.level && ek.supports_row_rotation() => {
+                ek.rotates_rows(a)?
+            }
+            _ => self
+                .galois_key(2 * self.par.degree() - 1, a.level)?
+                .relinearize(a)?,
+        };
+        let ks = self.key_switching_noise(a.level);
+        self.record_unary(Operation::RotateRows, a, &mut ct, |na| na.max(ks));
+        Ok(ct)
+    }
+
+    /// Rotate the columns of a ciphertext by `i`.
+    ///
+    /// With the SIMD encoding, each of the two rows of `n / 2` slots is rotated
+    /// to the left by `i` independently, i.e. the slot `j` of a row receives
+    /// the slot `(j + i) mod (n / 2)` of the same row. Returns an error unless
+    /// `0 < i < n / 2`.
+    ///
+    /// When the evaluation key does not support the rotation by `i` but
+    /// supports the rotations by the powers of two of its binary decomposition
+    /// (see [`EvaluationKey::supports_composed_column_rotation_by`]), the
+    /// rotation is computed as a composition of these rotations.
+    pub fn rotate_columns(&mut self, a: &Ciphertext, i: usize) -> Result<Ciphertext> {
+        self.check(a)?;
+        let a = self.relinearized(a)?;
+        let a = a.as_ref();
+        let mut composed = 0;
+        let mut ct = match &self.ek {
+            Some(ek) if ek.ciphertext_level == a.level && ek.supports_column_rotation_by(i) => {
+                ek.rotates_columns_by(a, i)?
+            }
+            Some(ek)
+                if ek.ciphertext_level == a.level && ek.supports_composed_column_rotation_by(i) =>
+            {
+                composed = i.count_ones() as usize - 1;
+                ek.composes_column_rotation_by(a, i)?
+            }
+            _ => {
+                if i == 0 || i >= self.par.degree() / 2 {
+                    return Err(Error::DefaultError("Invalid rotation index".to_string()));
+                }
+                let q = Modulus::new(2 * self.par.degree() as u64)?;
+                self.galois_key(q.pow(3, i as u64) as usize, a.level)?
+                    .relinearize(a)?
+            }
+        };
+        let ks = self.key_switching_noise(a.level);
+        self.record_unary(Operation::RotateColumns(i), a, &mut ct, |na| {
+            na.max(ks) + composed
+        });
+        Ok(ct)
+    }
+
+    /// Rotate the `n` slots of a ciphertext to the left by `steps`, as a flat
+    /// vector: the slot `j` receives the slot `(j + steps) mod n`, the first
+    /// slots wrapping around to the end of the vector.
+    ///
+    /// This rotation is emulated with the matrix rotations: the columns are
+    /// rotated by `steps mod (n / 2)`, and the slots which must cross over to
+    /// the other row are selected with two plaintext masks and swapped with a
+    /// row rotation. Unless `steps` is a multiple of `n / 2`, it therefore
+    /// costs three key switchings and two multiplications by a plaintext, and
+    /// requires the parameters to support the SIMD encoding.
+    pub fn rotate_flat(&mut self, a: &Ciphertext, steps: usize) -> Result<Ciphertext> {
+        self.check(a)?;
+        let n = self.par.degree();
+        let half = n / 2;
+        let steps = steps % n;
+        let a = if steps >= half {
+            Cow::Owned(self.rotate_rows(a)?)
+        } else {
+            Cow::Borrowed(a)
+        };
+        let k = steps % half;
+        if k == 0 {
+            return Ok(a.into_owned());
+        }
+
+        let c = self.rotate_columns(&a, k)?;
+        let crossing = self.rotate_rows(&c)?;
+        let (mask, crossing_mask): (Vec<u64>, Vec<u64>) = (0..n)
+            .map(|j| if j % half < half - k { (1, 0) } else { (0, 1) })
+            .unzip();
+        let encoding = Encoding::simd_at_level(a.level);
+        let mask = Plaintext::try_encode(&mask, encoding.clone(), &self.par)?;
+        let crossing_mask = Plaintext::try_encode(&crossing_mask, encoding, &self.par)?;
+        let staying = self.mul_plaintext(&c, &mask)?;
+        let crossing = self.mul_plaintext(&crossing, &crossing_mask)?;
+        self.add(&staying, &crossing)
+    }
+
+    /// Switch a ciphertext to the next level.
+    pub fn mod_switch_to_next_level(&mut self, a: &Ciphertext) -> Result<Ciphertext> {
+        self.check(a)?;
+        let mut ct = a.clone();
+        ct.mod_switch_to_next_level()?;
+        let (dropped_bits, rounding) = if ct.level != a.level {
+            (self.dropped_bits(a.level), self.mod_switch_rounding_noise())
+        } else {
+            (0, 0)
+        };
+        self.record_unary(Operation::ModSwitch, a, &mut ct, |na| {
+            na.saturating_sub(dropped_bits).max(rounding)
+        });
+        Ok(ct)
+    }
+
+    /// Reports whether the operands of a multiplication should be switched to
+    /// the next level before the multiplication, according to the policy and
+    /// the noise model.
+    fn mod_switches_before_mul(&self, a: &Ciphertext, b: &Ciphertext) -> bool {
+        if self.mod_switch_policy != ModSwitchPolicy::BeforeMultiplication
+            || a.level >= self.par.max_level()
+            || self.max_relinearizable_size(a.level).is_some()
+        {
+            return false;
+        }
+        let dropped_bits = self.dropped_bits(a.level);
+        let rounding = self.mod_switch_rounding_noise();
+        let na = self.noise_of(a).saturating_sub(dropped_bits).max(rounding);
+        let nb = self.noise_of(b).saturating_sub(dropped_bits).max(rounding);
+        let mut noise = na.max(nb) + self.mul_growth();
+        if self.max_relinearizable_size(a.level + 1).is_some() {
+            noise = noise.max(self.key_switching_noise(a.level + 1))
+        }
+        noise + self.plaintext_bits() + 1 < self.modulus_bits(a.level + 1)
+    }
+
+    /// Returns the ciphertext `a` relinearized to size 2, if needed.
+    fn relinearized<'a>(&mut self, a: &'a Ciphertext) -> Result<Cow<'a, Ciphertext>> {
+        if a.len() > 2 {
+            Ok(Cow::Owned(self.relinearize(a)?))
+        } else {
+            Ok(Cow::Borrowed(a))
+        }
+    }
+
+    /// Returns the largest size of the ciphertexts at level `level` which can
+    /// be relinearized, if any.
+    fn max_relinearizable_size(&self, level: usize) -> Option<usize> {
+        self.rk
+            .as_ref()
+            .filter(|rk| rk.ksk.ciphertext_level == level)
+            .map(|rk| rk.max_power() + 1)
+    }
+
+    /// Fetch the relinearization key from the key provider, if the evaluator
+    /// does not have one yet.
+    fn fetch_relinearization_key(&mut self) -> Result<()> {
+        if self.rk.is_some() {
+            return Ok(());
+        }
+        let rk = match self.provider.as_ref() {
+            Some(provider) => provider.relin_key()?,
+            None => None,
+        };
+        if let Some(rk) = rk {
+            self.set_relinearization_key(&rk)?
+        }
+        Ok(())
+    }
+
+    /// Returns the Galois key of exponent `exponent` for ciphertexts at level
+    /// `level` fetched from the key provider, if any.
+    fn fetch_galois_key(&self, exponent: usize, level: usize) -> Result<Option<Arc<GaloisKey>>> {
+        let Some(provider) = self.provider.as_ref() else {
+            return Ok(None);
+        };
+        match provider.galois_key(exponent, level)? {
+            Some(gk) if gk.ksk.par != self.par || gk.ksk.ciphertext_level != level => Err(
+                Error::DefaultError("Incompatible Galois key from the key provider".to_string()),
+            ),
+            gk => Ok(gk),
+        }
+    }
+
+    /// Returns the Galois key of exponent `exponent` for ciphertexts at level
+    /// `level`, fetching it from the key provider or generating it if needed.
+    #[cfg(not(feature = "server"))]
+    fn galois_key(&mut self, exponent: usize, level: usize) -> Result<Arc<GaloisKey>> {
+        if let Some(gk) = self.fetch_galois_key(exponent, level)? {
+            return Ok(gk);
+        }
+        let Some((sk, rng)) = self.keygen.as_mut() else {
+            return Err(Error::DefaultError(
+                "No evaluation key or secret key supporting this rotation".to_string(),
+            ));
+        };
+        if !self.galois_keys.contains_key(&(exponent, level)) {
+            let gk = GaloisKey::new(sk, exponent, level, level, rng)?;
+            self.galois_keys.insert((exponent, level), Arc::new(gk));
+        }
+        Ok(self.galois_keys[&(exponent, level)].clone())
+    }
+
+    /// Returns the Galois key of exponent `exponent` for ciphertexts at level
+    /// `level` fetched from the key provider; without the secret key, no key
+    /// can be generated.
+    #[cfg(feature = "server")]
+    fn galois_key(&mut self, exponent: usize, level: usize) -> Result<Arc<GaloisKey>> {
+        self.fetch_galois_key(exponent, level)?
+            .ok_or(Error::DefaultError(
+                "No evaluation key supporting this rotation".to_string(),
+            ))
+    }
+
+    fn check(&self, a: &Ciphertext) -> Result<()> {
+        if a.par != self.par {
+            return Err(Error::DefaultError(
+                "Incompatible BFV parameters".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_pair(&self, a: &Ciphertext, b: &Ciphertext) -> Result<()> {
+        self.check(a)?;
+        self.check(b)?;
+        if a.level != b.level {
+            return Err(Error::DefaultError("Mismatched levels".to_string()));
+        }
+        Ok(())
+    }
+
+    fn check_plaintext(&self, a: &Ciphertext, pt: &Plaintext) -> Result<()> {
+        self.check(a)?;
+        if pt.par != self.par || pt.level != a.level {
+            return Err(Error::DefaultError(
+                "Mismatched parameters or levels".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Estimated noise of the ciphertext `a`.
+    fn noise_of(&self, a: &Ciphertext) -> usize {
+        self.recorder
+            .as_ref()
+            .and_then(|recorder| recorder.node_of(a))
+            .map(|node| node.estimated_noise)
+            .unwrap_or_else(|| self.fresh_noise())
+    }
+
+    /// Number of bits of the ciphertext modulus at level `level`.
+    fn modulus_bits(&self, level: usize) -> usize {
+        self.par.moduli_sizes()[..self.par.moduli().len() - level]
+            .iter()
+            .sum()
+    }
+
+    /// Number of bits of the modulus dropped when switching from the level
+    /// `level` to the next level.
+    fn dropped_bits(&self, level: usize) -> usize {
+        self.par.moduli_sizes()[self.par.moduli().len() - 1 - level]
+    }
+
+    /// Estimated noise added by the rounding of a modulus switching.
+    fn mod_switch_rounding_noise(&self) -> usize {
+        self.noise.mod_switch
+    }
+
+    /// Estimated growth of the noise in a multiplication.
+    fn mul_growth(&self) -> usize {
+        self.noise.mul
+    }
+
+    /// Number of bits of the plaintext modulus.
+    fn plaintext_bits(&self) -> usize {
+        (64 - self.par.plaintext.leading_zeros()) as usize
+    }
+
+    /// Estimated noise of a fresh encryption under a public key, which is
+    /// also used for the inputs of unknown provenance.
+    fn fresh_noise(&self) -> usize {
+        self.noise.fresh
+    }
+
+    /// Estimated noise added by a key switching at the level `level`.
+    fn key_switching_noise(&self, level: usize) -> usize {
+        self.noise.key_switching[level]
+    }
+
+    /// Reports to the metrics when the estimated noise of `ct` exhausts its
+    /// noise budget.
+    #[cfg(feature = "metrics")]
+    fn check_noise_budget(&self, ct: &Ciphertext, estimated_noise: usize) {
+        if estimated_noise + self.plaintext_bits() + 1 >= self.modulus_bits(ct.level) {
+            crate::metrics::record(crate::metrics::Metric::NoiseBudgetExhaustion, 1)
+        }
+    }
+
+    fn record_unary<F>(&mut self, op: Operation, a: &Ciphertext, ct: &mut Ciphertext, noise: F)
+    where
+        F: FnOnce(usize) -> usize,
+    {
+        self.counts.count(&op);
+        let fresh = self.fresh_noise();
+        if let Some(recorder) = self.recorder.as_mut() {
+            let ia = recorder.operand(a, fresh);
+            let estimated_noise = noise(recorder.estimated_noise(ia));
+            recorder.push(op, &[ia], a.level, ct, estimated_noise);
+            ct.noise_estimate = Some(estimated_noise);
+            #[cfg(feature = "metrics")]
+            self.check_noise_budget(ct, estimated_noise);
+        }
+    }
+
+    fn record_binary<F>(
+        &mut self,
+        op: Operation,
+        a: &Ciphertext,
+        b: &Ciphertext,
+        ct: &mut Ciphertext,
+        noise: F,
+    ) where
+        F: FnOnce(usize, usize) -> usize,
+    {
+        self.counts.count(&op);
+        let fresh = self.fresh_noise();
+        if let Some(recorder) = self.recorder.as_mut() {
+            let ia = recorder.operand(a, fresh);
+            let ib = recorder.operand(b, fresh);
+            let estimated_noise = noise(recorder.estimated_noise(ia), recorder.estimated_noise(ib));
+            recorder.push(op, &[ia, ib], a.level.max(b.level), ct, estimated_noise);
+            ct.noise_estimate = Some(estimated_noise);
+            #[cfg(feature = "metrics")]
+            self.check_noise_budget(ct, estimated_noise);
+        }
+    }
+}
+
+#[cfg(all(test, not(feature = "server")))]
+mod tests {
+    use super::{Evaluator, KeyProvider, ModSwitchPolicy, Operation};
+    use crate::bfv::{
+        BfvParameters, Ciphertext, Encoding, EvaluationKeyBuilder, GaloisKey, Plaintext,
+        RelinearizationKey, SecretKey,
+    };
+    use crate::Error;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use fhe_traits::{
+        DeserializeParametrized, FheDecoder, FheDecrypter, FheEncoder, FheEncrypter, Serialize,
+    };
+    use hashbrown::HashMap;
+    use rand::thread_rng;
+    extern crate alloc;
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn operations() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(3, 16);
+        let t = &params.plaintext;
+        let sk = SecretKey::random(&params, &mut rng);
+        let rk = RelinearizationKey::new(&sk, &mut rng)?;
+        let ek = EvaluationKeyBuilder::new(&sk)?
+            .enable_column_rotation(1)?
+            .build(&mut rng)?;
+        let mut evaluator = Evaluator::new(&params)
+            .with_relinearization_key(&rk)?
+            .with_evaluation_key(ek)?;
+
+        let a = t.random_vec(params.degree(), &mut rng);
+        let b = t.random_vec(params.degree(), &mut rng);
+        let pt_a = Plaintext::try_encode(&a, Encoding::simd(), &params)?;
+        let pt_b = Plaintext::try_encode(&b, Encoding::simd(), &params)?;
+        let ct_a: Ciphertext = sk.try_encrypt(&pt_a, &mut rng)?;
+        let ct_b: Ciphertext = sk.try_encrypt(&pt_b, &mut rng)?;
+        let decrypt = |ct: &Ciphertext| -> Result<Vec<u64>, Error> {
+            Vec::<u64>::try_decode(&sk.try_decrypt(ct)?, Encoding::simd())
+        };
+
+        let mut expected = a.clone();
+        t.add_vec(&mut expected, &b);
+        assert_eq!(decrypt(&evaluator.add(&ct_a, &ct_b)?)?, expected);
+
+        let mut expected = a.clone();
+        t.sub_vec(&mut expected, &b);
+        assert_eq!(decrypt(&evaluator.sub(&ct_a, &ct_b)?)?, expected);
+        assert_eq!(decrypt(&evaluator.sub_plaintext(&ct_a, &pt_b)?)?, expected);
+
+        let mut expected = a.clone();
+        t.neg_vec(&mut expected);
+        assert_eq!(decrypt(&evaluator.neg(&ct_a)?)?, expected);
+
+        let mut expected = a.clone();
+        t.mul_vec(&mut expected, &b);
+        let ct_ab = evaluator.mul(&ct_a, &ct_b)?;
+        assert_eq!(ct_ab.len(), 2);
+        assert_eq!(decrypt(&ct_ab)?, expected);
+        assert_eq!(decrypt(&evaluator.mul_plaintext(&ct_a, &pt_b)?)?, expected);
+
+        let ct_ab = evaluator.mod_switch_to_next_level(&ct_ab)?;
+        assert_eq!(ct_ab.level, 1);
+        assert_eq!(decrypt(&ct_ab)?, expected);
+
+        // Without relinearization at this level, the product has size 3.
+        let ct_ab2 = evaluator.mul(&ct_ab, &ct_ab)?;
+        assert_eq!(ct_ab2.len(), 3);
+        assert!(evaluator.relinearize(&ct_ab2).is_err());
+
+        let mut expected = a.clone();
+        t.add_vec(&mut expected, &b);
+        assert_eq!(decrypt(&evaluator.add_plaintext(&ct_a, &pt_b)?)?, expected);
+
+        let ct_rot = evaluator.rotate_columns(&ct_a, 1)?;
+        let rotated = decrypt(&ct_rot)?;
+        let half = params.degree() / 2;
+        for i in 0..half {
+            assert_eq!(rotated[i], a[(i + 1) % half]);
+        }
+        assert!(evaluator.rotate_columns(&ct_a, 2).is_err());
+        assert!(evaluator.rotate_rows(&ct_a).is_err());
+
+        // No provenance is recorded by default.
+        assert!(evaluator.provenance().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn delayed_relinearization() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(3, 16);
+        let t = &params.plaintext;
+        let sk = SecretKey::random(&params, &mut rng);
+        let rk = RelinearizationKey::new(&sk, &mut rng)?;
+        let ek = EvaluationKeyBuilder::new(&sk)?
+            .enable_column_rotation(1)?
+            .build(&mut rng)?;
+        let mut evaluator = Evaluator::new(&params)
+            .with_delayed_relinearization()
+            .with_relinearization_key(&rk)?
+            .with_evaluation_key(ek)?;
+        evaluator.enable_provenance();
+
+        let a = t.random_vec(params.degree(), &mut rng);
+        let b = t.random_vec(params.degree(), &mut rng);
+        let pt_a = Plaintext::try_encode(&a, Encoding::simd(), &params)?;
+        let pt_b = Plaintext::try_encode(&b, Encoding::simd(), &params)?;
+        let ct_a: Ciphertext = sk.try_encrypt(&pt_a, &mut rng)?;
+        let ct_b: Ciphertext = sk.try_encrypt(&pt_b, &mut rng)?;
+        let decrypt = |ct: &Ciphertext| -> Result<Vec<u64>, Error> {
+            Vec::<u64>::try_decode(&sk.try_decrypt(ct)?, Encoding::simd())
+        };
+
+        // The product followed by linear operations is not relinearized.
+        let mut ab = a.clone();
+        t.mul_vec(&mut ab, &b);
+        let ct_ab = evaluator.mul(&ct_a, &ct_b)?;
+        assert_eq!(ct_ab.len(), 3);
+        let ct_sum = evaluator.add(&ct_ab, &ct_ab)?;
+        assert_eq!(ct_sum.len(), 3);
+        let mut expected = ab.clone();
+        t.add_vec(&mut expected, &ab);
+        assert_eq!(decrypt(&ct_sum)?, expected);
+
+        // The ciphertext is relinearized before a rotation.
+        let rotated = decrypt(&evaluator.rotate_columns(&ct_ab, 1)?)?;
+        let half = params.degree() / 2;
+        for i in 0..half {
+            assert_eq!(rotated[i], ab[(i + 1) % half]);
+        }
+
+        // The operand is relinearized before a product which would be too large.
+        let ct_abb = evaluator.mul(&ct_ab, &ct_b)?;
+        assert_eq!(ct_abb.len(), 3);
+        let mut expected = ab.clone();
+        t.mul_vec(&mut expected, &b);
+        assert_eq!(decrypt(&ct_abb)?, expected);
+
+        let ct_abb = evaluator.finalize(&ct_abb)?;
+        assert_eq!(ct_abb.len(), 2);
+        assert_eq!(decrypt(&ct_abb)?, expected);
+        assert_eq!(evaluator.finalize(&ct_a)?, ct_a);
+
+        let relinearizations = evaluator
+            .provenance()
+            .unwrap()
+            .nodes()
+            .iter()
+            .filter(|n| n.op == Operation::Relinearize)
+            .count();
+        assert_eq!(relinearizations, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn mod_switch_before_mul() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(3, 16);
+        let t = &params.plaintext;
+        let sk = SecretKey::random(&params, &mut rng);
+        let rk = RelinearizationKey::new_leveled(&sk, 1, 1, &mut rng)?;
+        let mut evaluator = Evaluator::new(&params)
+            .with_relinearization_key(&rk)?
+            .with_mod_switch_policy(ModSwitchPolicy::BeforeMultiplication);
+        assert!(evaluator.provenance().is_some());
+
+        let a = t.random_vec(params.degree(), &mut rng);
+        let pt = Plaintext::try_encode(&a, Encoding::simd(), &params)?;
+        let ct: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+        let decrypt = |ct: &Ciphertext| -> Result<Vec<u64>, Error> {
+            Vec::<u64>::try_decode(&sk.try_decrypt(ct)?, Encoding::simd())
+        };
+
+        // The operands are switched to level 1, where the product is
+        // relinearized.
+        let mut expected = a.clone();
+        t.mul_vec(&mut expected, &a);
+        let ct2 = evaluator.mul(&ct, &ct)?;
+        assert_eq!((ct2.level, ct2.len()), (1, 2));
+        assert_eq!(decrypt(&ct2)?, expected);
+
+        // The operands are not switched further, since there is a
+        // relinearization key at level 1.
+        t.mul_vec(&mut expected, &a);
+        let ct_1 = evaluator.mod_switch_to_next_level(&ct)?;
+        let ct3 = evaluator.mul(&ct2, &ct_1)?;
+        assert_eq!((ct3.level, ct3.len()), (1, 2));
+        assert_eq!(decrypt(&ct3)?, expected);
+
+        let ops = evaluator
+            .provenance()
+            .unwrap()
+            .nodes()
+            .iter()
+            .map(|n| n.op)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ops,
+            [
+                Operation::Input,
+                Operation::ModSwitch,
+                Operation::ModSwitch,
+                Operation::Mul,
+                Operation::ModSwitch,
+                Operation::Mul
+            ]
+        );
+
+        // The noise budget does not allow to switch a noisy ciphertext.
+        let mut evaluator =
+            Evaluator::new(&params).with_mod_switch_policy(ModSwitchPolicy::BeforeMultiplication);
+        let mut noisy = ct.clone();
+        for _ in 0..12 {
+            noisy = evaluator.mul_plaintext(&noisy, &pt)?;
+        }
+        assert_eq!(evaluator.mul(&noisy, &ct)?.level, 0);
+        assert_eq!(evaluator.mul(&ct, &ct)?.level, 1);
+
+        // The modulus is not switched with the default policy.
+        let mut evaluator = Evaluator::new(&params);
+        assert_eq!(evaluator.mul(&ct, &ct)?.level, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn lazy_galois_keys() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(3, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let ek = EvaluationKeyBuilder::new(&sk)?
+            .enable_column_rotation(1)?
+            .build(&mut rng)?;
+        let mut evaluator = Evaluator::new(&params)
+            .with_evaluation_key(ek)?
+            .with_secret_key(&sk, &mut rng)?;
+
+        let a = params.plaintext.random_vec(params.degree(), &mut rng);
+        let pt = Plaintext::try_encode(&a, Encoding::simd(), &params)?;
+        let ct: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+        let decrypt = |ct: &Ciphertext| -> Result<Vec<u64>, Error> {
+            Vec::<u64>::try_decode(&sk.try_decrypt(ct)?, Encoding::simd())
+        };
+        let half = params.degree() / 2;
+
+        // The evaluation key supports this rotation.
+        evaluator.rotate_columns(&ct, 1)?;
+        assert_eq!(evaluator.num_generated_galois_keys(), 0);
+
+        for i in [2, 3, 2] {
+            let rotated = decrypt(&evaluator.rotate_columns(&ct, i)?)?;
+            for j in 0..half {
+                assert_eq!(rotated[j], a[(j + i) % half]);
+                assert_eq!(rotated[half + j], a[half + (j + i) % half]);
+            }
+        }
+        assert_eq!(evaluator.num_generated_galois_keys(), 2);
+
+        let rotated = decrypt(&evaluator.rotate_rows(&ct)?)?;
+        assert_eq!(rotated[..half], a[half..]);
+        assert_eq!(rotated[half..], a[..half]);
+        assert_eq!(evaluator.num_generated_galois_keys(), 3);
+
+        // The keys are generated at the level of the ciphertext.
+        let ct = evaluator.mod_switch_to_next_level(&ct)?;
+        let rotated = decrypt(&evaluator.rotate_columns(&ct, 1)?)?;
+        for j in 0..half {
+            assert_eq!(rotated[j], a[(j + 1) % half]);
+        }
+        assert_eq!(evaluator.num_generated_galois_keys(), 4);
+
+        assert!(evaluator.rotate_columns(&ct, 0).is_err());
+        assert!(evaluator.rotate_columns(&ct, half).is_err());
+        Ok(())
+    }
+
+    /// A key provider deserializing the keys from a store on each fetch.
+    #[derive(Debug)]
+    struct SerializedKeys {
+        par: Arc<BfvParameters>,
+        galois_keys: HashMap<(usize, usize), Vec<u8>>,
+        rk: Vec<u8>,
+        fetches: AtomicUsize,
+    }
+
+    impl KeyProvider for SerializedKeys {
+        fn galois_key(
+            &self,
+            element: usize,
+            level: usize,
+        ) -> crate::Result<Option<Arc<GaloisKey>>> {
+            self.fetches.fetch_add(1, Ordering::Relaxed);
+            self.galois_keys
+                .get(&(element, level))
+                .map(|bytes| Ok(Arc::new(GaloisKey::from_bytes(bytes, &self.par)?)))
+                .transpose()
+        }
+
+        fn relin_key(&self) -> crate::Result<Option<Arc<RelinearizationKey>>> {
+            self.fetches.fetch_add(1, Ordering::Relaxed);
+            Ok(Some(Arc::new(RelinearizationKey::from_bytes(
+                &self.rk, &self.par,
+            )?)))
+        }
+    }
+
+    #[test]
+    fn key_provider() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(3, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let gk = GaloisKey::new(&sk, 3, 0, 0, &mut rng)?;
+        let provider = Arc::new(SerializedKeys {
+            par: params.clone(),
+            galois_keys: HashMap::from_iter([((3, 0), gk.to_bytes())]),
+            rk: RelinearizationKey::new(&sk, &mut rng)?.to_bytes(),
+            fetches: AtomicUsize::new(0),
+        });
+        let mut evaluator = Evaluator::new(&params).with_key_provider(provider.clone());
+
+        let a = params.plaintext.random_vec(params.degree(), &mut rng);
+        let pt = Plaintext::try_encode(&a, Encoding::simd(), &params)?;
+        let ct: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+        let decrypt = |ct: &Ciphertext| -> Result<Vec<u64>, Error> {
+            Vec::<u64>::try_decode(&sk.try_decrypt(ct)?, Encoding::simd())
+        };
+        let half = params.degree() / 2;
+
+        // The Galois keys are fetched for each rotation.
+        for _ in 0..2 {
+            let rotated = decrypt(&evaluator.rotate_columns(&ct, 1)?)?;
+            for j in 0..half {
+                assert_eq!(rotated[j], a[(j + 1) % half]);
+            }
+        }
+        assert_eq!(provider.fetches.load(Ordering::Relaxed), 2);
+        assert!(evaluator.rotate_rows(&ct).is_err());
+        assert_eq!(evaluator.num_generated_galois_keys(), 0);
+
+        // The relinearization key is only fetched once.
+        for _ in 0..2 {
+            let product = evaluator.mul(&ct, &ct)?;
+            assert_eq!(product.len(), 2);
+            let decrypted = decrypt(&product)?;
+            for j in 0..params.degree() {
+                assert_eq!(decrypted[j], params.plaintext.mul(a[j], a[j]));
+            }
+        }
+        assert_eq!(provider.fetches.load(Ordering::Relaxed), 4);
+
+        // The secret key generates the keys the provider does not have.
+        let mut evaluator = Evaluator::new(&params)
+            .with_key_provider(provider.clone())
+            .with_secret_key(&sk, &mut rng)?;
+        evaluator.rotate_columns(&ct, 1)?;
+        evaluator.rotate_rows(&ct)?;
+        assert_eq!(evaluator.num_generated_galois_keys(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn composed_rotations() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(3, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let ek = EvaluationKeyBuilder::new(&sk)?
+            .enable_power_of_two_rotations()?
+            .build(&mut rng)?;
+        let mut evaluator = Evaluator::new(&params).with_evaluation_key(ek)?;
+
+        let a = params.plaintext.random_vec(params.degree(), &mut rng);
+        let pt = Plaintext::try_encode(&a, Encoding::simd(), &params)?;
+        let ct: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+        let half = params.degree() / 2;
+        for i in 1..half {
+            let rotated = evaluator.rotate_columns(&ct, i)?;
+            let rotated = Vec::<u64>::try_decode(&sk.try_decrypt(&rotated)?, Encoding::simd())?;
+            for j in 0..half {
+                assert_eq!(rotated[j], a[(j + i) % half]);
+                assert_eq!(rotated[half + j], a[half + (j + i) % half]);
+            }
+        }
+        assert!(evaluator.rotate_rows(&ct).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn debug() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(1, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let evaluator = Evaluator::new(&params).with_secret_key(&sk, &mut rng)?;
+
+        let debug = alloc::format!("{evaluator:?}");
+        assert!(debug.contains("secret_key: true"));
+        assert!(!debug.contains(&alloc::format!("{:?}", sk.coeffs)));
+        Ok(())
+    }
+
+    #[test]
+    fn rotate_flat() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(3, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let mut evaluator = Evaluator::new(&params).with_secret_key(&sk, &mut rng)?;
+
+        let n = params.degree();
+        let a = params.plaintext.random_vec(n, &mut rng);
+        let pt = Plaintext::try_encode(&a, Encoding::simd(), &params)?;
+        let ct: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+        for steps in [0, 1, 5, n / 2, n / 2 + 3, n - 1, n + 2] {
+            let rotated = evaluator.rotate_flat(&ct, steps)?;
+            let rotated = Vec::<u64>::try_decode(&sk.try_decrypt(&rotated)?, Encoding::simd())?;
+            for j in 0..n {
+                assert_eq!(rotated[j], a[(j + steps) % n]);
+            }
+        }
+
+        // The rotation also works at a lower level.
+        let ct = evaluator.mod_switch_to_next_level(&ct)?;
+        let rotated = evaluator.rotate_flat(&ct, 3)?;
+        assert_eq!(rotated.level, 1);
+        let rotated = Vec::<u64>::try_decode(&sk.try_decrypt(&rotated)?, Encoding::simd())?;
+        for j in 0..n {
+            assert_eq!(rotated[j], a[(j + 3) % n]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn provenance() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(3, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let rk = RelinearizationKey::new(&sk, &mut rng)?;
+        let mut evaluator = Evaluator::new(&params).with_relinearization_key(&rk)?;
+        evaluator.enable_provenance();
+
+        let v = params.plaintext.random_vec(params.degree(), &mut rng);
+        let pt = Plaintext::try_encode(&v, Encoding::simd(), &params)?;
+        let ct_a: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+        let ct_b: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+        let ct_c: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+
+        let ct_ab = evaluator.mul(&ct_a, &ct_b)?;
+        let ct_abc = evaluator.add(&ct_ab, &ct_c)?;
+        let ct_out = evaluator.mod_switch_to_next_level(&ct_abc)?;
+        let ct_other = evaluator.neg(&ct_c)?;
+
+        let recorder = evaluator.take_provenance().unwrap();
+        assert!(evaluator.provenance().is_none());
+        let ops = recorder.nodes().iter().map(|n| n.op).collect::<Vec<_>>();
+        assert_eq!(
+            ops,
+            [
+                Operation::Input,
+                Operation::Input,
+                Operation::Mul,
+                Operation::Input,
+                Operation::Add,
+                Operation::ModSwitch,
+                Operation::Neg
+            ]
+        );
+
+        let out = recorder.node_of(&ct_out).unwrap();
+        assert_eq!(out.op, Operation::ModSwitch);
+        assert_eq!(out.operands, [4]);
+        assert_eq!((out.level_before, out.level_after), (0, 1));
+        let mul = &recorder.nodes()[2];
+        assert_eq!(mul.operands, [0, 1]);
+        assert!(mul.estimated_noise > recorder.nodes()[0].estimated_noise);
+        assert_eq!(ct_ab.noise_estimate, Some(mul.estimated_noise));
+        assert_eq!(ct_out.noise_estimate, Some(out.estimated_noise));
+        assert!(ct_a.noise_estimate.is_none());
+
+        // The history of the output does not include the negation.
+        let history = recorder
+            .history_of(&ct_out)
+            .iter()
+            .map(|n| n.id)
+            .collect::<Vec<_>>();
+        assert_eq!(history, [0, 1, 2, 3, 4, 5]);
+        assert_eq!(recorder.history_of(&ct_other).len(), 2);
+
+        let json = recorder.to_json();
+        assert!(json.starts_with("[{\"id\":0,\"op\":\"Input\",\"operands\":[]"));
+        assert!(json.contains("{\"id\":4,\"op\":\"Add\",\"operands\":[2,3],"));
+        let dot = recorder.to_dot();
+        assert!(dot.starts_with("digraph provenance {"));
+        assert!(dot.contains("n2 -> n4;"));
+        assert!(dot.contains("n3 -> n4;"));
+        Ok(())
+    }
+}
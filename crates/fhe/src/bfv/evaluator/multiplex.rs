@@ -0,0 +1,247 @@
+//! Multiplexing of independent queries in the slots of shared ciphertexts.
+
+use super::Evaluator;
+use crate::bfv::encoding::EncodingEnum;
+use crate::bfv::{BfvParameters, Ciphertext, Encoding, Plaintext};
+use crate::{Error, Result};
+use fhe_traits::FheEncoder;
+extern crate alloc;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Multiplexer of independent queries, e.g. of several clients sharing a key,
+/// in the SIMD slots of shared ciphertexts, so that a server evaluates a
+/// circuit once for several queries, amortizing the rotations and the products
+/// by plaintexts.
+///
+/// Each query is a ciphertext whose values are in its first `block_size`
+/// slots, seen as a flat vector; the other slots are ignored. The queries are
+/// moved to consecutive blocks of `block_size` slots of the multiplexed
+/// ciphertexts, each holding up to [`SlotMultiplexer::capacity`] queries, and
+/// are moved back to the first slots of separate ciphertexts after the
+/// evaluation, with the other slots set to 0.
+///
+/// The noise of a response still depends on the other queries, so that its
+/// decryption may leak information about them: the multiplexed queries must
+/// come from parties which trust each other, since no smudging noise is added
+/// to the responses.
+///
+/// The circuit must act independently on each block, e.g. with slot-wise
+/// operations and products by plaintexts encoded with
+/// [`SlotMultiplexer::try_encode`], which replicates their values in every
+/// block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlotMultiplexer {
+    par: Arc<BfvParameters>,
+    block_size: usize,
+}
+
+impl SlotMultiplexer {
+    /// Create a multiplexer of queries of `block_size` slots.
+    ///
+    /// Returns an error if the parameters do not support the SIMD encoding, or
+    /// if `block_size` does not divide the degree.
+    pub fn new(par: &Arc<BfvParameters>, block_size: usize) -> Result<Self> {
+        if par.op.is_none() {
+            return Err(Error::EncodingNotSupported(EncodingEnum::Simd.to_string()));
+        }
+        if block_size == 0 || par.degree() % block_size != 0 {
+            return Err(Error::DefaultError("Invalid block size".to_string()));
+        }
+        Ok(Self {
+            par: par.clone(),
+            block_size,
+        })
+    }
+
+    /// Returns the number of slots of a query.
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// Returns the number of queries multiplexed in a ciphertext.
+    pub fn capacity(&self) -> usize {
+        self.par.degree() / self.block_size
+    }
+
+    /// Encode the `block_size` values of a block in a SIMD [`Plaintext`] at
+    /// level `level`, replicating them in every block.
+    pub fn try_encode(&self, values: &[u64], level: usize) -> Result<Plaintext> {
+        if values.len() > self.block_size {
+            return Err(Error::TooManyValues(values.len(), self.block_size));
+        }
+        let mut block = values.to_vec();
+        block.resize(self.block_size, 0);
+        Plaintext::try_encode(
+            &block.repeat(self.capacity()),
+            Encoding::simd_at_level(level),
+            &self.par,
+        )
+    }
+
+    /// Returns the mask of the first block of slots at level `level`.
+    fn first_block(&self, level: usize) -> Result<Plaintext> {
+        let mut mask = vec![0u64; self.par.degree()];
+        mask[..self.block_size].fill(1);
+        Plaintext::try_encode(&mask, Encoding::simd_at_level(level), &self.par)
+    }
+
+    /// Multiplex the queries in `ceil(queries.len() / capacity)` ciphertexts,
+    /// the query `i` being moved to the block `i mod capacity` of the
+    /// ciphertext `i / capacity`.
+    ///
+    /// Returns an error if the queries are not at the same level, or if the
+    /// evaluator does not support the rotations (see
+    /// [`Evaluator::rotate_flat`]).
+    pub fn multiplex(
+        &self,
+        evaluator: &mut Evaluator,
+        queries: &[Ciphertext],
+    ) -> Result<Vec<Ciphertext>> {
+        let Some(first) = queries.first() else {
+            return Ok(vec![]);
+        };
+        let mask = self.first_block(first.level)?;
+        let n = self.par.degree();
+        queries
+            .chunks(self.capacity())
+            .map(|chunk| {
+                let mut multiplexed = Ciphertext::zero(&self.par);
+                for (i, query) in chunk.iter().enumerate() {
+                    let block = evaluator.mul_plaintext(query, &mask)?;
+                    let block = evaluator.rotate_flat(&block, n - i * self.block_size)?;
+                    multiplexed = if i == 0 {
+                        block
+                    } else {
+                        evaluator.add(&multiplexed, &block)?
+                    };
+                }
+                Ok(multiplexed)
+            })
+            .collect()
+    }
+
+    /// Demultiplex the first `count` queries of multiplexed ciphertexts, i.e.
+    /// move the block of each query to the first slots of a separate
+    /// ciphertext, and set the other slots to 0.
+    ///
+    /// Returns an error if the ciphertexts hold fewer than `count` queries.
+    pub fn demultiplex(
+        &self,
+        evaluator: &mut Evaluator,
+        multiplexed: &[Ciphertext],
+        count: usize,
+    ) -> Result<Vec<Ciphertext>> {
+        if count > multiplexed.len() * self.capacity() {
+            return Err(Error::TooManyValues(
+                count,
+                multiplexed.len() * self.capacity(),
+            ));
+        }
+        (0..count)
+            .map(|i| {
+                let ct = &multiplexed[i / self.capacity()];
+                let block = i % self.capacity();
+                let ct = evaluator.rotate_flat(ct, block * self.block_size)?;
+                evaluator.mul_plaintext(&ct, &self.first_block(ct.level)?)
+            })
+            .collect()
+    }
+
+    /// Evaluates `circuit` over the queries in SIMD lockstep: the queries are
+    /// multiplexed, the circuit is evaluated once per multiplexed ciphertext,
+    /// and the results are demultiplexed, one per query.
+    pub fn evaluate<F>(
+        &self,
+        evaluator: &mut Evaluator,
+        queries: &[Ciphertext],
+        mut circuit: F,
+    ) -> Result<Vec<Ciphertext>>
+    where
+        F: FnMut(&mut Evaluator, &Ciphertext) -> Result<Ciphertext>,
+    {
+        let multiplexed = self
+            .multiplex(evaluator, queries)?
+            .iter()
+            .map(|ct| circuit(evaluator, ct))
+            .collect::<Result<Vec<Ciphertext>>>()?;
+        self.demultiplex(evaluator, &multiplexed, queries.len())
+    }
+}
+
+#[cfg(all(test, not(feature = "server")))]
+mod tests {
+    use super::SlotMultiplexer;
+    use crate::bfv::{
+        BfvParameters, BfvParametersBuilder, Ciphertext, Encoding, Evaluator, Plaintext,
+        RelinearizationKey, SecretKey,
+    };
+    use crate::Error;
+    use fhe_traits::{FheDecoder, FheDecrypter, FheEncoder, FheEncrypter};
+    use rand::thread_rng;
+    extern crate alloc;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn multiplexing() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(4, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let rk = RelinearizationKey::new(&sk, &mut rng)?;
+        let mut evaluator = Evaluator::new(&params)
+            .with_relinearization_key(&rk)?
+            .with_secret_key(&sk, &mut rng)?;
+        let multiplexer = SlotMultiplexer::new(&params, 4)?;
+        assert_eq!(multiplexer.block_size(), 4);
+        assert_eq!(multiplexer.capacity(), 4);
+
+        // Five queries, whose slots beyond the first block are not zero, span
+        // two multiplexed ciphertexts.
+        let values = (0..5)
+            .map(|_| params.plaintext.random_vec(params.degree(), &mut rng))
+            .collect::<Vec<_>>();
+        let queries = values
+            .iter()
+            .map(|v| {
+                let pt = Plaintext::try_encode(v, Encoding::simd(), &params)?;
+                sk.try_encrypt(&pt, &mut rng)
+            })
+            .collect::<Result<Vec<Ciphertext>, Error>>()?;
+        let b = params.plaintext.random_vec(4, &mut rng);
+        let pt = multiplexer.try_encode(&b, 0)?;
+        assert_eq!(multiplexer.multiplex(&mut evaluator, &queries)?.len(), 2);
+
+        // Computes x^2 + b slot-wise.
+        let responses = multiplexer.evaluate(&mut evaluator, &queries, |evaluator, ct| {
+            let square = evaluator.mul(ct, ct)?;
+            evaluator.add_plaintext(&square, &pt)
+        })?;
+        assert_eq!(responses.len(), 5);
+        for (response, v) in responses.iter().zip(&values) {
+            let decoded = Vec::<u64>::try_decode(&sk.try_decrypt(response)?, Encoding::simd())?;
+            for j in 0..params.degree() {
+                let expected = if j < 4 {
+                    params.plaintext.add(params.plaintext.mul(v[j], v[j]), b[j])
+                } else {
+                    0
+                };
+                assert_eq!(decoded[j], expected);
+            }
+        }
+
+        assert!(multiplexer.multiplex(&mut evaluator, &[])?.is_empty());
+        assert!(multiplexer.demultiplex(&mut evaluator, &[], 1).is_err());
+        assert!(multiplexer.try_encode(&[0; 5], 0).is_err());
+        assert!(SlotMultiplexer::new(&params, 3).is_err());
+        let no_simd = BfvParametersBuilder::new()
+            .set_degree(16)
+            .set_plaintext_modulus(1153)
+            .set_moduli_sizes(&[62])
+            .disable_batching()
+            .build_arc()?;
+        assert!(SlotMultiplexer::new(&no_simd, 4).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,252 @@
+//! Table of the noise growth used by the noise estimator of an
+//! [`super::Evaluator`].
+
+use crate::bfv::BfvParameters;
+#[cfg(all(feature = "calibration", not(feature = "server")))]
+use crate::bfv::{keys::GaloisKey, Ciphertext, Encoding, Plaintext, PublicKey, SecretKey};
+use crate::proto::bfv::NoiseTable as NoiseTableProto;
+use crate::{Error, Result};
+use fhe_traits::{DeserializeParametrized, FheParametrized, Serialize};
+#[cfg(all(feature = "calibration", not(feature = "server")))]
+use fhe_traits::{FheEncoder, FheEncrypter};
+use prost::Message;
+#[cfg(all(feature = "calibration", not(feature = "server")))]
+use rand::{CryptoRng, RngCore};
+extern crate alloc;
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use itertools::Itertools;
+
+/// Noise, in bits, of the fresh ciphertexts and of the outputs of the
+/// homomorphic operations for a parameter set, used by an
+/// [`super::Evaluator`] to estimate the noise of the ciphertexts it computes.
+///
+/// The estimates are either the worst-case bounds of
+/// [`NoiseTable::worst_case`], or, with the `calibration` feature, averages
+/// measured on random inputs plus a safety margin (see
+/// [`NoiseTable::calibrate`]), which are tighter and let the
+/// [`super::ModSwitchPolicy::BeforeMultiplication`] policy switch the modulus
+/// earlier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoiseTable {
+    pub(crate) par: Arc<BfvParameters>,
+    /// Noise of a fresh encryption under a public key, also used for the
+    /// inputs of unknown provenance.
+    pub fresh: usize,
+    /// Growth of the noise in an addition of two ciphertexts.
+    pub add: usize,
+    /// Growth of the noise in a multiplication by a plaintext.
+    pub mul_plaintext: usize,
+    /// Growth of the noise in a multiplication of two ciphertexts.
+    pub mul: usize,
+    /// Noise added by a key switching, for each level.
+    pub key_switching: Vec<usize>,
+    /// Noise added by the rounding of a modulus switching.
+    pub mod_switch: usize,
+}
+
+impl NoiseTable {
+    /// Worst-case estimates of the noise, derived from the parameters only.
+    pub fn worst_case(par: &Arc<BfvParameters>) -> Self {
+        let plaintext_bits = (64 - par.plaintext.leading_zeros()) as usize;
+        let degree_bits = par.degree().ilog2() as usize;
+        // A bound on the error, i.e. about 6 standard deviations.
+        let sigma_bits = (36 * par.variance).ilog2() as usize / 2 + 1;
+        let key_switching = (0..=par.max_level())
+            .map(|level| {
+                let num_moduli = par.moduli().len() - level;
+                let max_modulus_bits = par.moduli_sizes()[..num_moduli]
+                    .iter()
+                    .max()
+                    .copied()
+                    .unwrap_or_default();
+                max_modulus_bits + degree_bits / 2 + sigma_bits
+            })
+            .collect();
+        Self {
+            par: par.clone(),
+            fresh: 2 * sigma_bits + degree_bits / 2,
+            add: 1,
+            mul_plaintext: plaintext_bits + degree_bits / 2,
+            mul: plaintext_bits + degree_bits + 1,
+            key_switching,
+            mod_switch: plaintext_bits + degree_bits / 2 + sigma_bits,
+        }
+    }
+
+    /// Returns the parameters of the table.
+    pub fn parameters(&self) -> &Arc<BfvParameters> {
+        &self.par
+    }
+
+    /// Format the table in JSON, e.g. to compare the calibrated estimates with
+    /// the worst-case ones.
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"parameters_id\":{},\"fresh\":{},\"add\":{},\"mul_plaintext\":{},\"mul\":{},\"key_switching\":[{}],\"mod_switch\":{}}}",
+            self.par.id(),
+            self.fresh,
+            self.add,
+            self.mul_plaintext,
+            self.mul,
+            self.key_switching.iter().join(","),
+            self.mod_switch
+        )
+    }
+}
+
+#[cfg(all(feature = "calibration", not(feature = "server")))]
+impl NoiseTable {
+    /// Measure the noise of the operations on `samples` random inputs with
+    /// fresh keys, and returns a table of the averages of the measurements,
+    /// rounded up, plus `margin` bits.
+    ///
+    /// The averages are much smaller than the worst-case bounds; the margin
+    /// accounts for the variance of the noise, and should be of at least a
+    /// few bits when the estimates are used to make decisions such as
+    /// switching the modulus early.
+    pub fn calibrate<R: RngCore + CryptoRng>(
+        par: &Arc<BfvParameters>,
+        samples: usize,
+        margin: usize,
+        rng: &mut R,
+    ) -> Result<Self> {
+        if samples == 0 {
+            return Err(Error::TooFewValues(0, 1));
+        }
+        let sk = SecretKey::random(par, rng);
+        let pk = PublicKey::new(&sk, rng);
+        // Safety: the calibration measures the noise on random data, which
+        // does not need to be protected against timing attacks.
+        let noise = |ct: &Ciphertext| unsafe { sk.measure_noise(ct) };
+        let encrypt = |level: usize, rng: &mut R| -> Result<Ciphertext> {
+            let v = par.plaintext.random_vec(par.degree(), rng);
+            let pt = Plaintext::try_encode(&v, Encoding::poly_at_level(level), par)?;
+            pk.try_encrypt(&pt, rng)
+        };
+        let average = |sum: usize| sum.div_ceil(samples) + margin;
+
+        let (mut fresh, mut add, mut mul_plaintext, mut mul, mut mod_switch) = (0, 0, 0, 0, 0);
+        for _ in 0..samples {
+            let a = encrypt(0, rng)?;
+            let b = encrypt(0, rng)?;
+            let (na, nb) = (noise(&a)?, noise(&b)?);
+            fresh += na;
+            add += noise(&(&a + &b))?.saturating_sub(na.max(nb));
+            let v = par.plaintext.random_vec(par.degree(), rng);
+            let pt = Plaintext::try_encode(&v, Encoding::poly(), par)?;
+            mul_plaintext += noise(&(&a * &pt))?.saturating_sub(na);
+            mul += noise(&(&a * &b))?.saturating_sub(na.max(nb));
+            if par.max_level() > 0 {
+                let mut c = a.clone();
+                c.mod_switch_to_next_level()?;
+                mod_switch += noise(&c)?;
+            }
+        }
+
+        let mut key_switching = Vec::with_capacity(par.max_level() + 1);
+        for level in 0..=par.max_level() {
+            let gk = GaloisKey::new(&sk, 3, level, level, rng)?;
+            let mut sum = 0;
+            for _ in 0..samples {
+                sum += noise(&gk.relinearize(&encrypt(level, rng)?)?)?;
+            }
+            key_switching.push(average(sum));
+        }
+
+        Ok(Self {
+            par: par.clone(),
+            fresh: average(fresh),
+            add: average(add),
+            mul_plaintext: average(mul_plaintext),
+            mul: average(mul),
+            key_switching,
+            mod_switch: average(mod_switch),
+        })
+    }
+}
+
+impl FheParametrized for NoiseTable {
+    type Parameters = BfvParameters;
+}
+
+impl Serialize for NoiseTable {
+    fn to_bytes(&self) -> Vec<u8> {
+        NoiseTableProto {
+            parameters_id: self.par.id(),
+            fresh: self.fresh as u32,
+            add: self.add as u32,
+            mul_plaintext: self.mul_plaintext as u32,
+            mul: self.mul as u32,
+            key_switching: self.key_switching.iter().map(|n| *n as u32).collect(),
+            mod_switch: self.mod_switch as u32,
+        }
+        .encode_to_vec()
+    }
+}
+
+impl DeserializeParametrized for NoiseTable {
+    type Error = Error;
+
+    fn from_bytes(bytes: &[u8], par: &Arc<BfvParameters>) -> Result<Self> {
+        let proto: NoiseTableProto =
+            Message::decode(bytes).map_err(|_| Error::SerializationError)?;
+        if proto.parameters_id != par.id() || proto.key_switching.len() != par.max_level() + 1 {
+            return Err(Error::SerializationError);
+        }
+        Ok(Self {
+            par: par.clone(),
+            fresh: proto.fresh as usize,
+            add: proto.add as usize,
+            mul_plaintext: proto.mul_plaintext as usize,
+            mul: proto.mul as usize,
+            key_switching: proto.key_switching.iter().map(|n| *n as usize).collect(),
+            mod_switch: proto.mod_switch as usize,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NoiseTable;
+    use crate::bfv::{BfvParameters, Evaluator};
+    use crate::Error;
+    use fhe_traits::{DeserializeParametrized, Serialize};
+
+    #[test]
+    fn serialize() -> Result<(), Error> {
+        let params = BfvParameters::default_arc(3, 16);
+        let table = NoiseTable::worst_case(&params);
+        assert_eq!(table.key_switching.len(), 3);
+        assert_eq!(NoiseTable::from_bytes(&table.to_bytes(), &params)?, table);
+        assert!(table.to_json().contains("\"key_switching\":["));
+
+        let other = BfvParameters::default_arc(2, 16);
+        assert_eq!(
+            NoiseTable::from_bytes(&table.to_bytes(), &other).unwrap_err(),
+            Error::SerializationError
+        );
+        assert!(Evaluator::new(&params)
+            .with_noise_table(table.clone())
+            .is_ok());
+        assert!(Evaluator::new(&other).with_noise_table(table).is_err());
+        Ok(())
+    }
+
+    #[cfg(all(feature = "calibration", not(feature = "server")))]
+    #[test]
+    fn calibrate() -> Result<(), Error> {
+        let mut rng = rand::thread_rng();
+        let params = BfvParameters::default_arc(3, 16);
+        let worst_case = NoiseTable::worst_case(&params);
+        let table = NoiseTable::calibrate(&params, 8, 2, &mut rng)?;
+        assert!(table.fresh <= worst_case.fresh + 2);
+        assert!(table.mul <= worst_case.mul + 2);
+        assert!(table.mod_switch <= worst_case.mod_switch + 2);
+        assert_eq!(table.key_switching.len(), worst_case.key_switching.len());
+        assert!(NoiseTable::calibrate(&params, 0, 2, &mut rng).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,215 @@
+//! Provenance recording of the operations computed by an [`super::Evaluator`].
+
+use crate::bfv::Ciphertext;
+extern crate alloc;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use itertools::Itertools;
+use sha2::{Digest, Sha256};
+
+/// An operation recorded by a [`ProvenanceRecorder`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    /// A ciphertext which was not computed by the evaluator.
+    Input,
+    /// Addition of two ciphertexts.
+    Add,
+    /// Subtraction of two ciphertexts.
+    Sub,
+    /// Negation of a ciphertext.
+    Neg,
+    /// Addition of a plaintext to a ciphertext.
+    AddPlaintext,
+    /// Subtraction of a plaintext from a ciphertext.
+    SubPlaintext,
+    /// Multiplication of a ciphertext by a plaintext.
+    MulPlaintext,
+    /// Multiplication of two ciphertexts.
+    Mul,
+    /// Relinearization of a ciphertext.
+    Relinearize,
+    /// Rotation of the rows of a ciphertext.
+    RotateRows,
+    /// Rotation of the columns of a ciphertext by the given index.
+    RotateColumns(usize),
+    /// Modulus switching to the next level.
+    ModSwitch,
+}
+
+impl Operation {
+    fn name(&self) -> String {
+        match self {
+            Operation::RotateColumns(i) => format!("RotateColumns({i})"),
+            op => format!("{op:?}"),
+        }
+    }
+}
+
+/// A node of the graph of operations recorded by a [`ProvenanceRecorder`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProvenanceNode {
+    /// The identifier of the node, i.e. its index in the log.
+    pub id: usize,
+    /// The operation which computed the ciphertext.
+    pub op: Operation,
+    /// The identifiers of the nodes of the ciphertext operands.
+    pub operands: Vec<usize>,
+    /// The largest level of the operands before the operation.
+    pub level_before: usize,
+    /// The level of the ciphertext after the operation.
+    pub level_after: usize,
+    /// An estimate of the number of bits of noise in the ciphertext.
+    pub estimated_noise: usize,
+}
+
+/// A recorder of the graph of operations computed by an [`super::Evaluator`],
+/// to debug and audit server-side computations.
+///
+/// Every operation appends a node to the log. The operands are identified by
+/// a fingerprint of the ciphertexts, so that a ciphertext which was not
+/// computed by the evaluator is recorded as an [`Operation::Input`] node the
+/// first time it is used. The log can be exported as JSON or in the graphviz
+/// DOT format.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProvenanceRecorder {
+    nodes: Vec<ProvenanceNode>,
+    fingerprints: BTreeMap<[u8; 32], usize>,
+}
+
+impl ProvenanceRecorder {
+    /// Create an empty recorder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the nodes of the log, in the order in which they were recorded.
+    pub fn nodes(&self) -> &[ProvenanceNode] {
+        &self.nodes
+    }
+
+    /// Returns the node of the last operation which computed `ct`, if any.
+    pub fn node_of(&self, ct: &Ciphertext) -> Option<&ProvenanceNode> {
+        self.fingerprints
+            .get(&Self::fingerprint(ct))
+            .map(|id| &self.nodes[*id])
+    }
+
+    /// Returns the nodes of the operations which contributed to `ct`, in the
+    /// order in which they were recorded.
+    pub fn history_of(&self, ct: &Ciphertext) -> Vec<&ProvenanceNode> {
+        let Some(node) = self.node_of(ct) else {
+            return Vec::new();
+        };
+        let mut visited = alloc::vec![false; node.id + 1];
+        let mut stack = alloc::vec![node.id];
+        while let Some(id) = stack.pop() {
+            if !visited[id] {
+                visited[id] = true;
+                stack.extend_from_slice(&self.nodes[id].operands);
+            }
+        }
+        visited
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| **v)
+            .map(|(id, _)| &self.nodes[id])
+            .collect_vec()
+    }
+
+    /// Export the log as a JSON array of nodes.
+    pub fn to_json(&self) -> String {
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|node| {
+                format!(
+                    "{{\"id\":{},\"op\":\"{}\",\"operands\":[{}],\"level_before\":{},\"level_after\":{},\"estimated_noise\":{}}}",
+                    node.id,
+                    node.op.name(),
+                    node.operands.iter().join(","),
+                    node.level_before,
+                    node.level_after,
+                    node.estimated_noise
+                )
+            })
+            .join(",");
+        format!("[{nodes}]")
+    }
+
+    /// Export the log as a directed graph in the graphviz DOT format.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph provenance {\n");
+        for node in &self.nodes {
+            let _ = writeln!(
+                dot,
+                "  n{} [label=\"{}\\nlevel {} -> {}\\nnoise ~{} bits\"];",
+                node.id,
+                node.op.name(),
+                node.level_before,
+                node.level_after,
+                node.estimated_noise
+            );
+            for operand in &node.operands {
+                let _ = writeln!(dot, "  n{} -> n{};", operand, node.id);
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Returns the node identifier of the ciphertext `ct`, recording it as an
+    /// input with noise `estimated_noise` if it is unknown.
+    pub(crate) fn operand(&mut self, ct: &Ciphertext, estimated_noise: usize) -> usize {
+        let fingerprint = Self::fingerprint(ct);
+        if let Some(id) = self.fingerprints.get(&fingerprint) {
+            *id
+        } else {
+            self.push(Operation::Input, &[], ct.level, ct, estimated_noise)
+        }
+    }
+
+    /// Returns the estimated noise of the node `id`.
+    pub(crate) fn estimated_noise(&self, id: usize) -> usize {
+        self.nodes[id].estimated_noise
+    }
+
+    /// Record the operation `op` on the operands `operands`, which computed
+    /// `ct`, and returns the identifier of the new node.
+    pub(crate) fn push(
+        &mut self,
+        op: Operation,
+        operands: &[usize],
+        level_before: usize,
+        ct: &Ciphertext,
+        estimated_noise: usize,
+    ) -> usize {
+        let id = self.nodes.len();
+        self.nodes.push(ProvenanceNode {
+            id,
+            op,
+            operands: operands.to_vec(),
+            level_before,
+            level_after: ct.level,
+            estimated_noise,
+        });
+        self.fingerprints.insert(Self::fingerprint(ct), id);
+        id
+    }
+
+    /// Fingerprint of a ciphertext, computed from its level, its size, and the
+    /// coefficients of its polynomials modulo the first modulus.
+    fn fingerprint(ct: &Ciphertext) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update((ct.level as u64).to_le_bytes());
+        hasher.update((ct.len() as u64).to_le_bytes());
+        for ci in ct.iter() {
+            for coeff in ci.coefficients().row(0) {
+                hasher.update(coeff.to_le_bytes());
+            }
+        }
+        hasher.finalize().into()
+    }
+}
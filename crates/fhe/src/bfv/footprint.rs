@@ -0,0 +1,225 @@
+//! Memory footprint of the ciphertexts, plaintexts and keys.
+
+#[cfg(not(any(feature = "client", feature = "server")))]
+use crate::bfv::SecretKey;
+#[cfg(not(feature = "client"))]
+use crate::bfv::{keys::KeySwitchingKey, EvaluationKey, RelinearizationKey};
+use crate::bfv::{Ciphertext, Plaintext, PublicKey};
+use crate::proto::bfv::Ciphertext as CiphertextProto;
+use fhe_math::rq::Poly;
+use fhe_traits::Serialize;
+use prost::Message;
+extern crate alloc;
+use alloc::vec::Vec;
+use zeroize::Zeroizing;
+
+/// Breakdown of the memory used by a ciphertext, a plaintext or a key, in
+/// bytes, e.g. to plan the capacity of a service storing many ciphertexts.
+///
+/// The sizes of the polynomials are proportional to the degree and to the
+/// number of moduli at their level, so that the footprint of a ciphertext
+/// decreases when its modulus is switched. The sizes only count the heap
+/// allocations owned by the value, and not the shared parameters.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryFootprint {
+    /// Size of the coefficients of each polynomial.
+    pub polys: Vec<usize>,
+    /// Size of the Shoup coefficients of the polynomials in NttShoup
+    /// representation, which are computed on first use and then double the
+    /// size of these polynomials.
+    pub shoup: usize,
+    /// Size of the other heap allocations, e.g. the tags and the encoded
+    /// values of the plaintexts.
+    pub other: usize,
+    /// Size of the serialization.
+    pub serialized: usize,
+}
+
+impl MemoryFootprint {
+    /// Returns the total size of the heap allocations, i.e. of the
+    /// polynomials, of their Shoup coefficients and of the other allocations.
+    pub fn heap_bytes(&self) -> usize {
+        self.polys.iter().sum::<usize>() + self.shoup + self.other
+    }
+
+    fn add_poly(&mut self, poly: &Poly) {
+        self.polys.push(poly.coefficients_heap_size());
+        self.shoup += poly.shoup_heap_size();
+    }
+
+    #[cfg(not(feature = "client"))]
+    fn add_key_switching_key(&mut self, ksk: &KeySwitchingKey) {
+        ksk.c0
+            .iter()
+            .chain(ksk.c1.iter())
+            .for_each(|poly| self.add_poly(poly));
+    }
+}
+
+impl Ciphertext {
+    /// Returns the memory footprint of the ciphertext. The serialized size
+    /// accounts for the seed replacing the polynomial `c1` of the fresh
+    /// ciphertexts.
+    pub fn memory_footprint(&self) -> MemoryFootprint {
+        let mut footprint = MemoryFootprint {
+            other: self.tag.len(),
+            serialized: CiphertextProto::from(self).encoded_len(),
+            ..Default::default()
+        };
+        self.c.iter().for_each(|poly| footprint.add_poly(poly));
+        footprint
+    }
+}
+
+impl Plaintext {
+    /// Returns the memory footprint of the plaintext, including the scaled
+    /// polynomial cached by the multiplications once computed.
+    pub fn memory_footprint(&self) -> MemoryFootprint {
+        let mut footprint = MemoryFootprint {
+            other: self.value.len() * core::mem::size_of::<u64>(),
+            serialized: Zeroizing::new(self.to_bytes()).len(),
+            ..Default::default()
+        };
+        footprint.add_poly(&self.poly_ntt);
+        if let Some(poly) = self.scaled_poly.0.get() {
+            footprint.add_poly(poly);
+        }
+        footprint
+    }
+}
+
+#[cfg(not(any(feature = "client", feature = "server")))]
+impl SecretKey {
+    /// Returns the memory footprint of the secret key, whose coefficients are
+    /// stored as signed 64-bit integers.
+    pub fn memory_footprint(&self) -> MemoryFootprint {
+        MemoryFootprint {
+            other: self.coeffs.len() * core::mem::size_of::<i64>(),
+            serialized: Zeroizing::new(self.to_bytes()).len(),
+            ..Default::default()
+        }
+    }
+}
+
+impl PublicKey {
+    /// Returns the memory footprint of the public key.
+    pub fn memory_footprint(&self) -> MemoryFootprint {
+        MemoryFootprint {
+            serialized: self.to_bytes().len(),
+            ..self.c.memory_footprint()
+        }
+    }
+}
+
+#[cfg(not(feature = "client"))]
+impl RelinearizationKey {
+    /// Returns the memory footprint of the relinearization key, including the
+    /// keys of the higher powers of the secret key, if any.
+    pub fn memory_footprint(&self) -> MemoryFootprint {
+        let mut footprint = MemoryFootprint {
+            serialized: self.to_bytes().len(),
+            ..Default::default()
+        };
+        footprint.add_key_switching_key(&self.ksk);
+        self.ksk_powers
+            .iter()
+            .for_each(|ksk| footprint.add_key_switching_key(ksk));
+        footprint
+    }
+}
+
+#[cfg(not(feature = "client"))]
+impl EvaluationKey {
+    /// Returns the memory footprint of the evaluation key, i.e. of its Galois
+    /// keys and of the monomials used by the expansion.
+    pub fn memory_footprint(&self) -> MemoryFootprint {
+        let mut footprint = MemoryFootprint {
+            serialized: self.to_bytes().len(),
+            ..Default::default()
+        };
+        self.gk
+            .values()
+            .for_each(|gk| footprint.add_key_switching_key(&gk.ksk));
+        self.monomials
+            .iter()
+            .for_each(|poly| footprint.add_poly(poly));
+        footprint
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bfv::{BfvParameters, Encoding, Plaintext};
+    #[cfg(not(any(feature = "client", feature = "server")))]
+    use crate::bfv::{Ciphertext, EvaluationKeyBuilder, PublicKey, RelinearizationKey, SecretKey};
+    use crate::Error;
+    #[cfg(not(any(feature = "client", feature = "server")))]
+    use fhe_traits::FheEncrypter;
+    use fhe_traits::{FheEncoder, Serialize};
+    use rand::thread_rng;
+
+    #[test]
+    fn plaintext_memory_footprint() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(3, 16);
+        let v = params.plaintext.random_vec(params.degree(), &mut rng);
+        let pt = Plaintext::try_encode(&v, Encoding::simd(), &params)?;
+        let footprint = pt.memory_footprint();
+        assert_eq!(footprint.polys, [3 * 16 * 8]);
+        assert_eq!(footprint.other, 16 * 8);
+        assert_eq!(footprint.serialized, pt.to_bytes().len());
+        Ok(())
+    }
+
+    #[cfg(not(any(feature = "client", feature = "server")))]
+    #[test]
+    fn memory_footprint() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(3, 16);
+        let poly_size = |level: usize| (3 - level) * 16 * 8;
+        let sk = SecretKey::random(&params, &mut rng);
+        assert_eq!(sk.memory_footprint().heap_bytes(), 16 * 8);
+
+        let v = params.plaintext.random_vec(params.degree(), &mut rng);
+        let pt = Plaintext::try_encode(&v, Encoding::simd(), &params)?;
+
+        // The seeded ciphertexts are smaller once serialized.
+        let mut ct: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+        let footprint = ct.memory_footprint();
+        assert_eq!(footprint.polys, [poly_size(0); 2]);
+        assert_eq!(footprint.shoup, 0);
+        assert_eq!(footprint.heap_bytes(), 2 * poly_size(0));
+        assert_eq!(footprint.serialized, ct.to_bytes().len());
+        assert!(footprint.serialized < footprint.heap_bytes());
+
+        // The footprint decreases with the level.
+        ct.mod_switch_to_next_level()?;
+        ct.set_tag(b"tag")?;
+        let footprint = ct.memory_footprint();
+        assert_eq!(footprint.polys, [poly_size(1); 2]);
+        assert_eq!(footprint.heap_bytes(), 2 * poly_size(1) + 3);
+        assert_eq!(footprint.serialized, ct.to_bytes().len());
+
+        let pk = PublicKey::new(&sk, &mut rng);
+        assert_eq!(pk.memory_footprint().polys, [poly_size(0); 2]);
+
+        // The key switching keys are made of pairs of polynomials in NttShoup
+        // representation.
+        let rk = RelinearizationKey::new(&sk, &mut rng)?;
+        let footprint = rk.memory_footprint();
+        assert_eq!(footprint.polys.len() % 2, 0);
+        assert!(footprint.heap_bytes() >= footprint.polys.iter().sum::<usize>());
+        assert_eq!(footprint.serialized, rk.to_bytes().len());
+
+        let ek = EvaluationKeyBuilder::new(&sk)?
+            .enable_row_rotation()?
+            .build(&mut rng)?;
+        let ek_with_more_keys = EvaluationKeyBuilder::new(&sk)?
+            .enable_inner_sum()?
+            .build(&mut rng)?;
+        assert!(
+            ek.memory_footprint().heap_bytes() < ek_with_more_keys.memory_footprint().heap_bytes()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,194 @@
+//! Plaintext integrity tags for the BFV encryption scheme.
+//!
+//! An [`IntegrityKey`] reserves the last slots of a SIMD plaintext to store a
+//! tag computed as secret linear combinations of the data slots. When the
+//! ciphertext noise overflows, the decrypted slots are essentially random, and
+//! the tag verification fails with [`Error::IntegrityCheckFailed`] instead of
+//! silently returning incorrect values. To detect the noise overflows
+//! without a secret, and through multiplications, see
+//! [`Redundancy`](crate::bfv::Redundancy).
+//!
+//! Since the tag is linear, it is preserved by additions and subtractions of
+//! tagged ciphertexts and plaintexts, and by multiplications by a plaintext
+//! encoding the same constant in all slots. It is not preserved by slot-wise
+//! multiplications or rotations.
+
+use super::encoding::EncodingEnum;
+use crate::bfv::{BfvParameters, Encoding, Plaintext};
+#[cfg(not(feature = "server"))]
+use crate::bfv::{Ciphertext, SecretKey};
+use crate::{Error, Result};
+use core::fmt::Debug;
+#[cfg(not(feature = "server"))]
+use fhe_traits::FheDecrypter;
+use fhe_traits::{FheDecoder, FheEncoder};
+use rand::{CryptoRng, RngCore};
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use zeroize::Zeroizing;
+use zeroize_derive::{Zeroize, ZeroizeOnDrop};
+
+/// Secret key used to compute and verify plaintext integrity tags.
+#[derive(PartialEq, Eq, Clone, Zeroize, ZeroizeOnDrop)]
+pub struct IntegrityKey {
+    #[zeroize(skip)]
+    par: Arc<BfvParameters>,
+    tag_slots: usize,
+    weights: Box<[u64]>,
+}
+
+impl Debug for IntegrityKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("IntegrityKey")
+            .field("params", &format_args!("{:016x}", self.par.id()))
+            .field("tag_slots", &self.tag_slots)
+            .finish_non_exhaustive()
+    }
+}
+
+impl IntegrityKey {
+    /// Generate a random [`IntegrityKey`] reserving `tag_slots` slots. A
+    /// corrupted plaintext passes the verification with probability about
+    /// `t^(-tag_slots)`, where `t` is the plaintext modulus.
+    ///
+    /// Returns an error if the parameters do not support the SIMD encoding,
+    /// or if `tag_slots` is 0 or not smaller than the degree.
+    pub fn random<R: RngCore + CryptoRng>(
+        par: &Arc<BfvParameters>,
+        tag_slots: usize,
+        rng: &mut R,
+    ) -> Result<Self> {
+        if par.op.is_none() {
+            return Err(Error::EncodingNotSupported(EncodingEnum::Simd.to_string()));
+        }
+        if tag_slots == 0 || tag_slots >= par.degree() {
+            return Err(Error::DefaultError(
+                "Invalid number of tag slots".to_string(),
+            ));
+        }
+        let data_slots = par.degree() - tag_slots;
+        let weights = par.plaintext.random_vec(tag_slots * data_slots, rng);
+        Ok(Self {
+            par: par.clone(),
+            tag_slots,
+            weights: weights.into_boxed_slice(),
+        })
+    }
+
+    /// Returns the number of slots available for data.
+    pub fn data_slots(&self) -> usize {
+        self.par.degree() - self.tag_slots
+    }
+
+    /// Compute the tag of the data slots.
+    fn tag(&self, data: &[u64]) -> Vec<u64> {
+        let p = &self.par.plaintext;
+        self.weights
+            .chunks_exact(self.data_slots())
+            .map(|w| {
+                w.iter()
+                    .zip(data.iter())
+                    .fold(0u64, |acc, (wi, di)| p.add(acc, p.mul(*wi, *di)))
+            })
+            .collect()
+    }
+
+    /// Encode `values` in a SIMD [`Plaintext`] at level `level`, and store the
+    /// integrity tag in the last slots.
+    pub fn try_encode(&self, values: &[u64], level: usize) -> Result<Plaintext> {
+        if values.len() > self.data_slots() {
+            return Err(Error::TooManyValues(values.len(), self.data_slots()));
+        }
+        let mut v = Zeroizing::new(self.par.plaintext.reduce_vec_new(values));
+        v.resize(self.data_slots(), 0);
+        let tag = Zeroizing::new(self.tag(&v));
+        v.extend_from_slice(&tag);
+        Plaintext::try_encode(v.as_slice(), Encoding::simd_at_level(level), &self.par)
+    }
+
+    /// Decode a SIMD [`Plaintext`] and verify its integrity tag. Returns the
+    /// data slots, or [`Error::IntegrityCheckFailed`] if the tag is incorrect.
+    pub fn try_decode(&self, pt: &Plaintext) -> Result<Vec<u64>> {
+        let mut v = Vec::<u64>::try_decode(pt, Encoding::simd_at_level(pt.level))?;
+        let tag = Zeroizing::new(v.split_off(self.data_slots()));
+        let expected = Zeroizing::new(self.tag(&v));
+        // Compare the tags without branching on their values.
+        let diff = tag
+            .iter()
+            .zip(expected.iter())
+            .fold(0u64, |acc, (a, b)| acc | (a ^ b));
+        if diff == 0 {
+            Ok(v)
+        } else {
+            Err(Error::IntegrityCheckFailed)
+        }
+    }
+
+    /// Decrypt a [`Ciphertext`] with the secret key `sk` and verify the
+    /// integrity tag of the underlying plaintext.
+    #[cfg(not(feature = "server"))]
+    pub fn try_decrypt(&self, sk: &SecretKey, ct: &Ciphertext) -> Result<Vec<u64>> {
+        if self.par != ct.par {
+            return Err(Error::DefaultError(
+                "Incompatible BFV parameters".to_string(),
+            ));
+        }
+        let pt = Zeroizing::new(sk.try_decrypt(ct)?);
+        self.try_decode(&pt)
+    }
+}
+
+#[cfg(all(test, not(feature = "server")))]
+mod tests {
+    use super::IntegrityKey;
+    use crate::bfv::{BfvParameters, Ciphertext, Encoding, Plaintext, SecretKey};
+    use crate::Error;
+    use fhe_traits::{FheEncoder, FheEncrypter};
+    use rand::thread_rng;
+    extern crate alloc;
+    use alloc::format;
+
+    #[test]
+    fn integrity() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(2, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let ik = IntegrityKey::random(&params, 2, &mut rng)?;
+        assert!(format!("{ik:?}").ends_with("tag_slots: 2, .. }"));
+        assert_eq!(ik.data_slots(), 14);
+        assert!(IntegrityKey::random(&params, 0, &mut rng).is_err());
+        assert!(IntegrityKey::random(&params, 16, &mut rng).is_err());
+
+        let a = params.plaintext.random_vec(ik.data_slots(), &mut rng);
+        let b = params.plaintext.random_vec(ik.data_slots(), &mut rng);
+        assert!(ik.try_encode(&[0u64; 15], 0).is_err());
+        let ct_a: Ciphertext = sk.try_encrypt(&ik.try_encode(&a, 0)?, &mut rng)?;
+        let ct_b: Ciphertext = sk.try_encrypt(&ik.try_encode(&b, 0)?, &mut rng)?;
+        assert_eq!(ik.try_decrypt(&sk, &ct_a)?, a);
+
+        // The tag is preserved by additions.
+        let mut expected = a.clone();
+        params.plaintext.add_vec(&mut expected, &b);
+        assert_eq!(ik.try_decrypt(&sk, &(&ct_a + &ct_b))?, expected);
+
+        // Adding an untagged plaintext corrupts the tag.
+        let c = params.plaintext.random_vec(params.degree(), &mut rng);
+        let pt_c = Plaintext::try_encode(&c, Encoding::simd(), &params)?;
+        assert_eq!(
+            ik.try_decrypt(&sk, &(&ct_a + &pt_c)),
+            Err(Error::IntegrityCheckFailed)
+        );
+
+        // Decrypting with the wrong key returns random values.
+        let other_sk = SecretKey::random(&params, &mut rng);
+        assert_eq!(
+            ik.try_decrypt(&other_sk, &ct_a),
+            Err(Error::IntegrityCheckFailed)
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,389 @@
+//! Known-answer tests for the BFV encryption scheme.
+//!
+//! A [`KatSuite`] is a list of [`KatVector`]s, stored in JSON, each of which
+//! specifies parameters, a secret key, plaintexts encrypted deterministically
+//! from seeds, and optionally an operation on the ciphertexts. The expected
+//! ciphertexts and the expected decryption of the result of the operation are
+//! pinned in the vector, so that downstream packagers and other
+//! implementations can check that they behave identically.
+//!
+//! ```
+//! use fhe::bfv::kat::{KatEncoding, KatInput, KatOperation, KatSuite, KatVector};
+//!
+//! let mut vector = KatVector {
+//!     name: "add".into(),
+//!     degree: 16,
+//!     plaintext_modulus: 1153,
+//!     moduli: vec![4611686018427387617, 4611686018427387329],
+//!     variance: 10,
+//!     secret_key: vec![1, 0, -1, 2, 0, 0, 1, -2, 0, 1, 0, 0, -1, 0, 1, 0],
+//!     encoding: KatEncoding::Simd,
+//!     inputs: vec![
+//!         KatInput::new((0..16).collect(), [1u8; 32]),
+//!         KatInput::new((16..32).collect(), [2u8; 32]),
+//!     ],
+//!     operation: Some(KatOperation::Add),
+//!     output: None,
+//! };
+//! vector.compute_expected()?;
+//!
+//! let suite = KatSuite { vectors: vec![vector] };
+//! let suite = KatSuite::from_json(&suite.to_json()?)?;
+//! assert!(suite.run().iter().all(|(_, result)| result.is_ok()));
+//! # Ok::<(), fhe::Error>(())
+//! ```
+
+use crate::bfv::{BfvParameters, BfvParametersBuilder, Ciphertext, Encoding, Plaintext, SecretKey};
+use crate::{Error, Result};
+use fhe_traits::{FheDecoder, FheDecrypter, FheEncoder, Serialize as FheSerialize};
+use serde::{Deserialize, Serialize};
+extern crate alloc;
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+/// A suite of known-answer tests.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KatSuite {
+    /// The test vectors.
+    pub vectors: Vec<KatVector>,
+}
+
+impl KatSuite {
+    /// Parse a suite from its JSON representation.
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json)
+            .map_err(|e| Error::DefaultError(format!("Invalid test vectors: {e}")))
+    }
+
+    /// Returns the JSON representation of the suite.
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).map_err(|_| Error::SerializationError)
+    }
+
+    /// Run all the test vectors, and returns the result of each of them along
+    /// with its name.
+    pub fn run(&self) -> Vec<(String, Result<()>)> {
+        self.vectors
+            .iter()
+            .map(|vector| (vector.name.clone(), vector.run()))
+            .collect()
+    }
+}
+
+/// The encoding of the plaintexts of a [`KatVector`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KatEncoding {
+    /// Coefficient encoding, see [`Encoding::poly`].
+    Poly,
+    /// SIMD encoding, see [`Encoding::simd`].
+    Simd,
+}
+
+impl From<KatEncoding> for Encoding {
+    fn from(value: KatEncoding) -> Self {
+        match value {
+            KatEncoding::Poly => Encoding::poly(),
+            KatEncoding::Simd => Encoding::simd(),
+        }
+    }
+}
+
+/// The operation computed on the input ciphertexts of a [`KatVector`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KatOperation {
+    /// Addition of the two inputs.
+    Add,
+    /// Subtraction of the second input from the first input.
+    Sub,
+    /// Negation of the input.
+    Neg,
+    /// Multiplication of the two inputs, without relinearization.
+    Mul,
+}
+
+/// An input of a [`KatVector`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KatInput {
+    /// The values encoded in the plaintext.
+    pub values: Vec<u64>,
+    /// The seed of the deterministic encryption, in hexadecimal.
+    pub seed: String,
+    /// The expected serialization of the ciphertext, in hexadecimal.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ciphertext: Option<String>,
+}
+
+impl KatInput {
+    /// Create an input encrypting `values` from `seed`, without expected
+    /// ciphertext.
+    pub fn new(values: Vec<u64>, seed: [u8; 32]) -> Self {
+        Self {
+            values,
+            seed: to_hex(&seed),
+            ciphertext: None,
+        }
+    }
+}
+
+/// The expected output of the operation of a [`KatVector`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KatOutput {
+    /// The expected decrypted values.
+    pub values: Vec<u64>,
+    /// The expected serialization of the ciphertext, in hexadecimal.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ciphertext: Option<String>,
+}
+
+/// A known-answer test vector.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KatVector {
+    /// The name of the test vector.
+    pub name: String,
+    /// The degree of the parameters.
+    pub degree: usize,
+    /// The plaintext modulus of the parameters.
+    pub plaintext_modulus: u64,
+    /// The ciphertext moduli of the parameters.
+    pub moduli: Vec<u64>,
+    /// The variance of the error distribution of the parameters.
+    pub variance: usize,
+    /// The coefficients of the secret key.
+    pub secret_key: Vec<i64>,
+    /// The encoding of the plaintexts.
+    pub encoding: KatEncoding,
+    /// The inputs, encrypted deterministically under the secret key.
+    pub inputs: Vec<KatInput>,
+    /// The operation computed on the input ciphertexts, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operation: Option<KatOperation>,
+    /// The expected output of the operation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<KatOutput>,
+}
+
+impl KatVector {
+    /// Run the test vector, and returns an error describing the first
+    /// mismatch, if any.
+    pub fn run(&self) -> Result<()> {
+        let (par, sk) = self.setup()?;
+        let cts = self.encrypt_inputs(&par, &sk)?;
+        for (i, (input, ct)) in self.inputs.iter().zip(cts.iter()).enumerate() {
+            if let Some(expected) = &input.ciphertext {
+                if &to_hex(&ct.to_bytes()) != expected {
+                    return Err(self.mismatch(&format!("ciphertext of input {i}")));
+                }
+            }
+            if Vec::<u64>::try_decode(&sk.try_decrypt(ct)?, Encoding::from(self.encoding))?
+                != input.values
+            {
+                return Err(self.mismatch(&format!("decryption of input {i}")));
+            }
+        }
+
+        if let Some(operation) = self.operation {
+            let ct = self.compute(operation, &cts)?;
+            let output = self.output.as_ref().ok_or(Error::DefaultError(format!(
+                "Test vector {}: missing expected output",
+                self.name
+            )))?;
+            if let Some(expected) = &output.ciphertext {
+                if &to_hex(&ct.to_bytes()) != expected {
+                    return Err(self.mismatch("ciphertext of the output"));
+                }
+            }
+            if Vec::<u64>::try_decode(&sk.try_decrypt(&ct)?, Encoding::from(self.encoding))?
+                != output.values
+            {
+                return Err(self.mismatch("decryption of the output"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Compute the expected ciphertexts and output of the test vector with
+    /// this implementation, e.g. to generate test vectors for a release.
+    pub fn compute_expected(&mut self) -> Result<()> {
+        let (par, sk) = self.setup()?;
+        let cts = self.encrypt_inputs(&par, &sk)?;
+        for (input, ct) in self.inputs.iter_mut().zip(cts.iter()) {
+            input.ciphertext = Some(to_hex(&ct.to_bytes()));
+        }
+        self.output = match self.operation {
+            Some(operation) => {
+                let ct = self.compute(operation, &cts)?;
+                Some(KatOutput {
+                    values: Vec::<u64>::try_decode(
+                        &sk.try_decrypt(&ct)?,
+                        Encoding::from(self.encoding),
+                    )?,
+                    ciphertext: Some(to_hex(&ct.to_bytes())),
+                })
+            }
+            None => None,
+        };
+        Ok(())
+    }
+
+    fn setup(&self) -> Result<(Arc<BfvParameters>, SecretKey)> {
+        let par = BfvParametersBuilder::new()
+            .set_degree(self.degree)
+            .set_plaintext_modulus(self.plaintext_modulus)
+            .set_moduli(&self.moduli)
+            .set_variance(self.variance)
+            .build_arc()?;
+        if self.secret_key.len() != par.degree() {
+            return Err(Error::DefaultError(format!(
+                "Test vector {}: invalid secret key length",
+                self.name
+            )));
+        }
+        let sk = SecretKey::new(self.secret_key.clone(), &par);
+        Ok((par, sk))
+    }
+
+    fn encrypt_inputs(&self, par: &Arc<BfvParameters>, sk: &SecretKey) -> Result<Vec<Ciphertext>> {
+        self.inputs
+            .iter()
+            .map(|input| {
+                let seed = from_hex(&input.seed)?.try_into().map_err(|_| {
+                    Error::DefaultError(format!("Test vector {}: invalid seed", self.name))
+                })?;
+                let pt = Plaintext::try_encode(&input.values, Encoding::from(self.encoding), par)?;
+                sk.try_encrypt_deterministic(&pt, seed)
+            })
+            .collect()
+    }
+
+    fn compute(&self, operation: KatOperation, cts: &[Ciphertext]) -> Result<Ciphertext> {
+        let arity = if operation == KatOperation::Neg { 1 } else { 2 };
+        if cts.len() != arity {
+            return Err(Error::DefaultError(format!(
+                "Test vector {}: the operation expects {arity} inputs",
+                self.name
+            )));
+        }
+        Ok(match operation {
+            KatOperation::Add => &cts[0] + &cts[1],
+            KatOperation::Sub => &cts[0] - &cts[1],
+            KatOperation::Neg => -&cts[0],
+            KatOperation::Mul => &cts[0] * &cts[1],
+        })
+    }
+
+    fn mismatch(&self, what: &str) -> Error {
+        Error::DefaultError(format!("Test vector {}: mismatch in the {what}", self.name))
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn from_hex(hex: &str) -> Result<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return Err(Error::SerializationError);
+    }
+    // The digits are decoded one by one, since `u8::from_str_radix` accepts a
+    // leading sign.
+    let digit = |c: u8| (c as char).to_digit(16).ok_or(Error::SerializationError);
+    hex.as_bytes()
+        .chunks_exact(2)
+        .map(|pair| Ok(((digit(pair[0])? << 4) | digit(pair[1])?) as u8))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_hex, to_hex, KatEncoding, KatInput, KatOperation, KatSuite, KatVector};
+    use crate::Error;
+    extern crate alloc;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    fn vector(encoding: KatEncoding, operation: Option<KatOperation>) -> KatVector {
+        KatVector {
+            name: "test".to_string(),
+            degree: 16,
+            plaintext_modulus: 1153,
+            moduli: vec![4611686018427387617, 4611686018427387329],
+            variance: 10,
+            secret_key: vec![1, 0, -1, 2, 0, 0, 1, -2, 0, 1, 0, 0, -1, 0, 1, 0],
+            encoding,
+            inputs: vec![
+                KatInput::new((0..16).collect(), [1u8; 32]),
+                KatInput::new((100..116).collect(), [2u8; 32]),
+            ],
+            operation,
+            output: None,
+        }
+    }
+
+    #[test]
+    fn hex() -> Result<(), Error> {
+        assert_eq!(to_hex(&[0, 1, 171, 255]), "0001abff");
+        assert_eq!(from_hex("0001abff")?, [0, 1, 171, 255]);
+        assert!(from_hex("abc").is_err());
+        assert!(from_hex("zz").is_err());
+        assert!(from_hex("+1").is_err());
+        assert!(from_hex("-1").is_err());
+        assert!(from_hex("é").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn run() -> Result<(), Error> {
+        for encoding in [KatEncoding::Poly, KatEncoding::Simd] {
+            for operation in [KatOperation::Add, KatOperation::Sub, KatOperation::Mul] {
+                let mut v = vector(encoding, Some(operation));
+                assert!(v.run().is_err());
+                v.compute_expected()?;
+                v.run()?;
+            }
+            let mut v = vector(encoding, Some(KatOperation::Neg));
+            v.inputs.truncate(1);
+            v.compute_expected()?;
+            v.run()?;
+        }
+
+        // Tampering with any expected value is detected.
+        let mut v = vector(KatEncoding::Simd, Some(KatOperation::Mul));
+        v.compute_expected()?;
+        let mut tampered = v.clone();
+        tampered.inputs[0].seed = to_hex(&[3u8; 32]);
+        assert!(tampered.run().is_err());
+        let mut tampered = v.clone();
+        tampered.output.as_mut().unwrap().values[0] += 1;
+        assert!(tampered.run().is_err());
+        let mut tampered = v.clone();
+        tampered.secret_key[0] = 0;
+        assert!(tampered.run().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn json() -> Result<(), Error> {
+        let mut v = vector(KatEncoding::Poly, None);
+        v.compute_expected()?;
+        let suite = KatSuite { vectors: vec![v] };
+        let json = suite.to_json()?;
+        assert!(json.contains("\"encoding\": \"poly\""));
+        assert!(!json.contains("\"operation\""));
+        assert_eq!(KatSuite::from_json(&json)?, suite);
+        assert!(KatSuite::from_json("{").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn pinned_vectors() {
+        let suite = KatSuite::from_json(include_str!("../../kat/bfv.json")).unwrap();
+        assert!(!suite.vectors.is_empty());
+        for (name, result) in suite.run() {
+            assert!(result.is_ok(), "{name}: {result:?}");
+        }
+    }
+}
@@ -1,6 +1,8 @@
 //! Leveled evaluation keys for the BFV encryption scheme.
 
-use crate::bfv::{keys::GaloisKey, traits::TryConvertFrom, BfvParameters, Ciphertext, SecretKey};
+#[cfg(not(feature = "server"))]
+use crate::bfv::SecretKey;
+use crate::bfv::{keys::GaloisKey, traits::TryConvertFrom, BfvParameters, Ciphertext};
 use crate::proto::bfv::{EvaluationKey as EvaluationKeyProto, GaloisKey as GaloisKeyProto};
 use crate::{Error, Result};
 use alloc::vec;
@@ -9,12 +11,23 @@ use fhe_math::rq::{traits::TryConvertFrom as TryConvertFromPoly, Poly, Represent
 use fhe_math::zq::Modulus;
 use fhe_traits::{DeserializeParametrized, FheParametrized, Serialize};
 use hashbrown::HashMap;
+#[cfg(not(feature = "server"))]
 use hashbrown::HashSet;
+#[cfg(all(feature = "parallel", not(feature = "server")))]
+use itertools::Itertools;
 use prost::Message;
+#[cfg(not(feature = "server"))]
 use rand::{CryptoRng, RngCore};
+#[cfg(all(feature = "parallel", not(feature = "server")))]
+use rand::{Rng, SeedableRng};
+#[cfg(all(feature = "parallel", not(feature = "server")))]
+use rand_chacha::ChaCha8Rng;
+#[cfg(all(feature = "parallel", not(feature = "server")))]
+use zeroize::Zeroizing;
 extern crate alloc;
 use alloc::string::ToString;
 use alloc::sync::Arc;
+#[cfg(not(feature = "server"))]
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
 /// Evaluation key for the BFV encryption scheme.
@@ -22,23 +35,25 @@ use zeroize::{Zeroize, ZeroizeOnDrop};
 /// An evaluation key enables one or several of the following operations:
 /// - column rotation
 /// - row rotation
+/// - conjugation
 /// - oblivious expansion
 /// - inner sum
+/// - prefix sum
 #[derive(Debug, PartialEq, Eq)]
 pub struct EvaluationKey {
-    par: Arc<BfvParameters>,
+    pub(crate) par: Arc<BfvParameters>,
 
-    ciphertext_level: usize,
+    pub(crate) ciphertext_level: usize,
     evaluation_key_level: usize,
 
     /// Map from Galois keys exponents to Galois keys
-    gk: HashMap<usize, GaloisKey>,
+    pub(crate) gk: HashMap<usize, GaloisKey>,
 
     /// Map from rotation index to Galois key exponent
     rot_to_gk_exponent: HashMap<usize, usize>,
 
     /// Monomials used in expansion
-    monomials: Vec<Poly>,
+    pub(crate) monomials: Vec<Poly>,
 }
 
 impl EvaluationKey {
@@ -82,12 +97,33 @@ impl EvaluationKey {
         }
     }
 
+    /// Reports whether the evaluation key enables to compute homomorphic
+    /// prefix sums, i.e. to rotate the rows of the plaintext and its columns by
+    /// `n / 2 - 2^i` for all `2^i < n / 2`.
+    pub fn supports_prefix_sum(&self) -> bool {
+        let mut ret = self.supports_row_rotation();
+        let half = self.par.degree() / 2;
+        let mut i = 1;
+        while i < half {
+            ret &= self.supports_column_rotation_by(half - i);
+            i *= 2
+        }
+        ret
+    }
+
     /// Reports whether the evaluation key enables to rotate the rows of the
     /// plaintext.
     pub fn supports_row_rotation(&self) -> bool {
         self.gk.contains_key(&(self.par.degree() * 2 - 1))
     }
 
+    /// Reports whether the evaluation key enables to apply the conjugation
+    /// automorphism `x -> x^(-1)` (see [`crate::bfv::conjugate`]), which uses
+    /// the same Galois key as the row rotation.
+    pub fn supports_conjugation(&self) -> bool {
+        self.supports_row_rotation()
+    }
+
     /// Homomorphically rotate the rows of the plaintext
     pub fn rotates_rows(&self, ct: &Ciphertext) -> Result<Ciphertext> {
         if !self.supports_row_rotation() {
@@ -125,6 +161,61 @@ impl EvaluationKey {
         }
     }
 
+    /// Reports whether the evaluation key enables to rotate the columns of the
+    /// plaintext by `i` as a composition of rotations by powers of two, i.e.
+    /// whether it supports the rotations by all the powers of two in the
+    /// binary decomposition of `i`.
+    pub fn supports_composed_column_rotation_by(&self, i: usize) -> bool {
+        i > 0
+            && i < self.par.degree() / 2
+            && (0..usize::BITS)
+                .filter(|b| (i >> b) & 1 == 1)
+                .all(|b| self.supports_column_rotation_by(1 << b))
+    }
+
+    /// Homomorphically rotate the columns of the plaintext by `i`, by
+    /// composing the rotations by the powers of two in the binary
+    /// decomposition of `i`. This costs one key switching per bit set in `i`.
+    pub fn composes_column_rotation_by(&self, ct: &Ciphertext, i: usize) -> Result<Ciphertext> {
+        if !self.supports_composed_column_rotation_by(i) {
+            return Err(Error::DefaultError(
+                "This key does not support rotating the columns by this index".to_string(),
+            ));
+        }
+        let mut out = ct.clone();
+        for b in (0..usize::BITS).filter(|b| (i >> b) & 1 == 1) {
+            out = self.rotates_columns_by(&out, 1 << b)?;
+        }
+        Ok(out)
+    }
+
+    /// Truncate the key so that it applies to ciphertexts at level `level`,
+    /// by dropping the components and the moduli of the Galois keys that are
+    /// not used at that level. This reduces the size of the key and of its
+    /// serialization when the operations are run at a low level.
+    ///
+    /// Returns an error if `level` is smaller than the ciphertext level of the
+    /// key or has a single modulus, or if the evaluation key level differs
+    /// from the ciphertext level.
+    pub fn truncate_to_level(&mut self, level: usize) -> Result<()> {
+        if level == self.ciphertext_level && level == self.evaluation_key_level {
+            return Ok(());
+        }
+        if level < self.ciphertext_level || self.evaluation_key_level != self.ciphertext_level {
+            return Err(Error::DefaultError(
+                "The key cannot be truncated to this level".to_string(),
+            ));
+        }
+        let monomials = EvaluationKey::construct_monomials(&self.par, level)?;
+        self.gk
+            .values_mut()
+            .try_for_each(|gk| gk.truncate_to_level(level))?;
+        self.monomials = monomials;
+        self.ciphertext_level = level;
+        self.evaluation_key_level = level;
+        Ok(())
+    }
+
     /// Reports whether the evaluation key supports oblivious expansion.
     pub fn supports_expansion(&self, level: usize) -> bool {
         if level == 0 {
@@ -180,7 +271,7 @@ impl EvaluationKey {
         }
     }
 
-    fn construct_rot_to_gk_exponent(par: &Arc<BfvParameters>) -> HashMap<usize, usize> {
+    pub(crate) fn construct_rot_to_gk_exponent(par: &Arc<BfvParameters>) -> HashMap<usize, usize> {
         let mut m = HashMap::new();
         let q = Modulus::new(2 * par.degree() as u64).unwrap();
         for i in 1..par.degree() / 2 {
@@ -189,6 +280,26 @@ impl EvaluationKey {
         }
         m
     }
+
+    /// Construct the monomials used in expansion at the ciphertext level.
+    fn construct_monomials(par: &Arc<BfvParameters>, ciphertext_level: usize) -> Result<Vec<Poly>> {
+        let ciphertext_ctx = par.ctx_at_level(ciphertext_level)?;
+        let mut monomials = Vec::with_capacity(par.degree().ilog2() as usize);
+        for l in 0..par.degree().ilog2() {
+            let mut monomial = vec![0i64; par.degree()];
+            monomial[par.degree() - (1 << l)] = -1;
+            let mut monomial = Poly::try_convert_from(
+                &monomial,
+                ciphertext_ctx,
+                true,
+                Representation::PowerBasis,
+            )?;
+            unsafe { monomial.allow_variable_time_computations() }
+            monomial.change_representation(Representation::NttShoup);
+            monomials.push(monomial);
+        }
+        Ok(monomials)
+    }
 }
 
 impl FheParametrized for EvaluationKey {
@@ -215,26 +326,32 @@ impl DeserializeParametrized for EvaluationKey {
 }
 
 /// Builder for a leveled evaluation key from the secret key.
+#[cfg(not(feature = "server"))]
 #[derive(Debug)]
 pub struct EvaluationKeyBuilder {
     sk: SecretKey,
     ciphertext_level: usize,
     evaluation_key_level: usize,
     inner_sum: bool,
+    prefix_sum: bool,
+    power_of_two_rotations: bool,
     row_rotation: bool,
     expansion_level: usize,
     column_rotation: HashSet<usize>,
     rot_to_gk_exponent: HashMap<usize, usize>,
 }
 
+#[cfg(not(feature = "server"))]
 impl Zeroize for EvaluationKeyBuilder {
     fn zeroize(&mut self) {
         self.sk.zeroize()
     }
 }
 
+#[cfg(not(feature = "server"))]
 impl ZeroizeOnDrop for EvaluationKeyBuilder {}
 
+#[cfg(not(feature = "server"))]
 impl EvaluationKeyBuilder {
     /// Creates a new builder from the [`SecretKey`].
     pub fn new(sk: &SecretKey) -> Result<Self> {
@@ -243,6 +360,8 @@ impl EvaluationKeyBuilder {
             ciphertext_level: 0,
             evaluation_key_level: 0,
             inner_sum: false,
+            prefix_sum: false,
+            power_of_two_rotations: false,
             row_rotation: false,
             expansion_level: 0,
             column_rotation: HashSet::new(),
@@ -269,6 +388,8 @@ impl EvaluationKeyBuilder {
             ciphertext_level,
             evaluation_key_level,
             inner_sum: false,
+            prefix_sum: false,
+            power_of_two_rotations: false,
             row_rotation: false,
             expansion_level: 0,
             column_rotation: HashSet::new(),
@@ -294,6 +415,24 @@ impl EvaluationKeyBuilder {
         Ok(self)
     }
 
+    /// Allow this evaluation key to compute homomorphic prefix sums.
+    #[allow(unused_must_use)]
+    pub fn enable_prefix_sum(&mut self) -> Result<&mut Self> {
+        self.prefix_sum = true;
+        Ok(self)
+    }
+
+    /// Allow this evaluation key to homomorphically rotate the plaintext
+    /// columns by the powers of two `1, 2, 4, ..., n / 4`, and therefore by any
+    /// index as a composition of these rotations (see
+    /// [`EvaluationKey::composes_column_rotation_by`]). This requires
+    /// `log2(n) - 1` Galois keys instead of one key per rotation index.
+    #[allow(unused_must_use)]
+    pub fn enable_power_of_two_rotations(&mut self) -> Result<&mut Self> {
+        self.power_of_two_rotations = true;
+        Ok(self)
+    }
+
     /// Allow this evaluation key to homomorphically rotate the plaintext rows.
     #[allow(unused_must_use)]
     pub fn enable_row_rotation(&mut self) -> Result<&mut Self> {
@@ -301,6 +440,15 @@ impl EvaluationKeyBuilder {
         Ok(self)
     }
 
+    /// Allow this evaluation key to apply the conjugation automorphism
+    /// `x -> x^(-1)` (see [`crate::bfv::conjugate`]). In the SIMD layout, the
+    /// conjugation swaps the rows of the plaintext, so that this enables the
+    /// row rotation as well.
+    #[allow(unused_must_use)]
+    pub fn enable_conjugation(&mut self) -> Result<&mut Self> {
+        self.enable_row_rotation()
+    }
+
     /// Allow this evaluation key to homomorphically rotate the plaintext
     /// columns.
     #[allow(unused_must_use)]
@@ -319,7 +467,7 @@ impl EvaluationKeyBuilder {
             gk: HashMap::default(),
             par: self.sk.par.clone(),
             rot_to_gk_exponent: self.rot_to_gk_exponent.clone(),
-            monomials: Vec::with_capacity(self.sk.par.degree().ilog2() as usize),
+            monomials: Vec::new(),
             ciphertext_level: self.ciphertext_level,
             evaluation_key_level: self.evaluation_key_level,
         };
@@ -340,25 +488,31 @@ impl EvaluationKeyBuilder {
             }
         }
 
+        if self.prefix_sum {
+            indices.insert(self.sk.par.degree() * 2 - 1);
+            let half = self.sk.par.degree() / 2;
+            let mut i = 1;
+            while i < half {
+                indices.insert(*ek.rot_to_gk_exponent.get(&(half - i)).unwrap());
+                i *= 2
+            }
+        }
+
+        if self.power_of_two_rotations {
+            let mut i = 1;
+            while i < self.sk.par.degree() / 2 {
+                indices.insert(*ek.rot_to_gk_exponent.get(&i).unwrap());
+                i *= 2
+            }
+        }
+
         for l in 0..self.expansion_level {
             indices.insert((self.sk.par.degree() >> l) + 1);
         }
 
-        let ciphertext_ctx = self.sk.par.ctx_at_level(self.ciphertext_level)?;
-        for l in 0..self.sk.par.degree().ilog2() {
-            let mut monomial = vec![0i64; self.sk.par.degree()];
-            monomial[self.sk.par.degree() - (1 << l)] = -1;
-            let mut monomial = Poly::try_convert_from(
-                &monomial,
-                ciphertext_ctx,
-                true,
-                Representation::PowerBasis,
-            )?;
-            unsafe { monomial.allow_variable_time_computations() }
-            monomial.change_representation(Representation::NttShoup);
-            ek.monomials.push(monomial);
-        }
+        ek.monomials = EvaluationKey::construct_monomials(&self.sk.par, self.ciphertext_level)?;
 
+        #[cfg(not(feature = "parallel"))]
         for index in indices {
             ek.gk.insert(
                 index,
@@ -372,6 +526,40 @@ impl EvaluationKeyBuilder {
             );
         }
 
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+
+            // Derive one seed per Galois key from `rng` so that the keys can be
+            // generated independently.
+            let seeds = Zeroizing::new(
+                indices
+                    .into_iter()
+                    .map(|index| {
+                        let mut seed = <ChaCha8Rng as SeedableRng>::Seed::default();
+                        rng.fill(&mut seed);
+                        (index, seed)
+                    })
+                    .collect_vec(),
+            );
+            ek.gk = seeds
+                .par_iter()
+                .map(|(index, seed)| {
+                    let mut rng = ChaCha8Rng::from_seed(*seed);
+                    GaloisKey::new(
+                        &self.sk,
+                        *index,
+                        self.ciphertext_level,
+                        self.evaluation_key_level,
+                        &mut rng,
+                    )
+                    .map(|gk| (*index, gk))
+                })
+                .collect::<Result<Vec<_>>>()?
+                .into_iter()
+                .collect();
+        }
+
         Ok(ek)
     }
 }
@@ -406,21 +594,7 @@ impl TryConvertFrom<&EvaluationKeyProto> for EvaluationKey {
             gk.insert(key.element.exponent, key);
         }
 
-        let ciphertext_ctx = par.ctx_at_level(value.ciphertext_level as usize)?;
-        let mut monomials = Vec::with_capacity(par.degree().ilog2() as usize);
-        for l in 0..par.degree().ilog2() {
-            let mut monomial = vec![0i64; par.degree()];
-            monomial[par.degree() - (1 << l)] = -1;
-            let mut monomial = Poly::try_convert_from(
-                &monomial,
-                ciphertext_ctx,
-                true,
-                Representation::PowerBasis,
-            )?;
-            unsafe { monomial.allow_variable_time_computations() }
-            monomial.change_representation(Representation::NttShoup);
-            monomials.push(monomial);
-        }
+        let monomials = EvaluationKey::construct_monomials(par, value.ciphertext_level as usize)?;
 
         Ok(EvaluationKey {
             gk,
@@ -433,7 +607,7 @@ impl TryConvertFrom<&EvaluationKeyProto> for EvaluationKey {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, not(feature = "server")))]
 mod tests {
     use super::{EvaluationKey, EvaluationKeyBuilder};
     use crate::bfv::{traits::TryConvertFrom, BfvParameters, Encoding, Plaintext, SecretKey};
@@ -667,6 +841,43 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn power_of_two_rotations() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(6, 32);
+        let row_size = params.degree() >> 1;
+        let sk = SecretKey::random(&params, &mut rng);
+        let ek = EvaluationKeyBuilder::new(&sk)?
+            .enable_power_of_two_rotations()?
+            .build(&mut rng)?;
+        assert_eq!(ek.gk.len(), 4);
+        assert!(!ek.supports_row_rotation());
+
+        let v = params.plaintext.random_vec(params.degree(), &mut rng);
+        let pt = Plaintext::try_encode(&v, Encoding::simd(), &params)?;
+        let ct = sk.try_encrypt(&pt, &mut rng)?;
+        for i in 1..row_size {
+            assert!(ek.supports_composed_column_rotation_by(i));
+            assert_eq!(ek.supports_column_rotation_by(i), i.is_power_of_two());
+            let pt = sk.try_decrypt(&ek.composes_column_rotation_by(&ct, i)?)?;
+            let rotated = Vec::<u64>::try_decode(&pt, Encoding::simd())?;
+            for j in 0..row_size {
+                assert_eq!(rotated[j], v[(j + i) % row_size]);
+                assert_eq!(rotated[row_size + j], v[row_size + (j + i) % row_size]);
+            }
+        }
+        assert!(!ek.supports_composed_column_rotation_by(0));
+        assert!(!ek.supports_composed_column_rotation_by(row_size));
+        assert!(ek.composes_column_rotation_by(&ct, row_size).is_err());
+
+        let ek = EvaluationKeyBuilder::new(&sk)?
+            .enable_column_rotation(1)?
+            .build(&mut rng)?;
+        assert!(ek.supports_composed_column_rotation_by(1));
+        assert!(!ek.supports_composed_column_rotation_by(3));
+        Ok(())
+    }
+
     #[test]
     fn expansion() -> Result<(), Error> {
         let mut rng = thread_rng();
@@ -807,4 +1018,73 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn truncate_to_level() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(6, 16);
+        let row_size = params.degree() >> 1;
+        let sk = SecretKey::random(&params, &mut rng);
+        let ek = EvaluationKeyBuilder::new(&sk)?
+            .enable_row_rotation()?
+            .enable_column_rotation(1)?
+            .enable_expansion(2)?
+            .build(&mut rng)?;
+        let size = ek.to_bytes().len();
+
+        for level in 1..params.max_level() {
+            let mut truncated = EvaluationKey::from_bytes(&ek.to_bytes(), &params)?;
+            truncated.truncate_to_level(level)?;
+            assert!(truncated.to_bytes().len() < size);
+            assert_eq!(
+                truncated,
+                EvaluationKey::from_bytes(&truncated.to_bytes(), &params)?
+            );
+            assert!(truncated.supports_row_rotation());
+            assert!(truncated.supports_column_rotation_by(1));
+            assert!(truncated.supports_expansion(2));
+
+            let v = params.plaintext.random_vec(params.degree(), &mut rng);
+            let pt = Plaintext::try_encode(&v, Encoding::simd_at_level(level), &params)?;
+            let ct = sk.try_encrypt(&pt, &mut rng)?;
+
+            let mut expected = vec![0u64; params.degree()];
+            expected[..row_size].copy_from_slice(&v[row_size..]);
+            expected[row_size..].copy_from_slice(&v[..row_size]);
+            let pt = sk.try_decrypt(&truncated.rotates_rows(&ct)?)?;
+            assert_eq!(
+                Vec::<u64>::try_decode(&pt, Encoding::simd_at_level(level))?,
+                expected
+            );
+
+            let pt = sk.try_decrypt(&truncated.rotates_columns_by(&ct, 1)?)?;
+            let w = Vec::<u64>::try_decode(&pt, Encoding::simd_at_level(level))?;
+            for i in 0..row_size {
+                assert_eq!(w[i], v[(i + 1) % row_size]);
+                assert_eq!(w[row_size + i], v[row_size + (i + 1) % row_size]);
+            }
+
+            let pt = Plaintext::try_encode(&v[..4], Encoding::poly_at_level(level), &params)?;
+            let ct = sk.try_encrypt(&pt, &mut rng)?;
+            for (vi, ct) in izip!(&v[..4], &truncated.expands(&ct, 4)?) {
+                let mut expected = vec![0u64; params.degree()];
+                expected[0] = params.plaintext.mul(*vi, 4);
+                let pt = sk.try_decrypt(ct)?;
+                assert_eq!(
+                    Vec::<u64>::try_decode(&pt, Encoding::poly_at_level(level))?,
+                    expected
+                );
+            }
+
+            assert!(truncated.truncate_to_level(level - 1).is_err());
+        }
+
+        let mut truncated = EvaluationKey::from_bytes(&ek.to_bytes(), &params)?;
+        assert!(truncated.truncate_to_level(params.max_level()).is_err());
+        let mut leveled = EvaluationKeyBuilder::new_leveled(&sk, 1, 0)?
+            .enable_row_rotation()?
+            .build(&mut rng)?;
+        assert!(leveled.truncate_to_level(2).is_err());
+        Ok(())
+    }
 }
@@ -1,18 +1,24 @@
 //! Galois keys for the BFV encryption scheme
 
 use super::key_switching_key::KeySwitchingKey;
-use crate::bfv::{traits::TryConvertFrom, BfvParameters, Ciphertext, SecretKey};
+#[cfg(not(feature = "server"))]
+use crate::bfv::SecretKey;
+use crate::bfv::{traits::TryConvertFrom, BfvParameters, Ciphertext};
 use crate::proto::bfv::{GaloisKey as GaloisKeyProto, KeySwitchingKey as KeySwitchingKeyProto};
 use crate::{Error, Result};
 extern crate alloc;
-use alloc::sync::Arc;
 use alloc::string::ToString;
-use alloc::vec;
-use fhe_math::rq::{
-    switcher::Switcher, traits::TryConvertFrom as TryConvertFromPoly, Poly, Representation,
-    SubstitutionExponent,
-};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+#[cfg(not(feature = "server"))]
+use fhe_math::rq::{switcher::Switcher, traits::TryConvertFrom as TryConvertFromPoly, Poly};
+use fhe_math::rq::{Representation, SubstitutionExponent};
+use fhe_traits::{DeserializeParametrized, FheParametrized, Serialize};
+use prost::Message;
+#[cfg(not(feature = "server"))]
 use rand::{CryptoRng, RngCore};
+use smallvec::smallvec;
+#[cfg(not(feature = "server"))]
 use zeroize::Zeroizing;
 
 /// Galois key for the BFV encryption scheme.
@@ -26,6 +32,7 @@ pub struct GaloisKey {
 
 impl GaloisKey {
     /// Generate a [`GaloisKey`] from a [`SecretKey`].
+    #[cfg(not(feature = "server"))]
     pub fn new<R: RngCore + CryptoRng>(
         sk: &SecretKey,
         exponent: usize,
@@ -87,10 +94,21 @@ impl GaloisKey {
         Ok(Ciphertext {
             par: ct.par.clone(),
             seed: None,
-            c: vec![c0, c1],
+            c: smallvec![c0, c1],
             level: self.ksk.ciphertext_level,
+            tag: ct.tag.clone(),
+            noise_estimate: None,
         })
     }
+
+    /// Truncate the key so that it applies to ciphertexts at level `level`.
+    pub(crate) fn truncate_to_level(&mut self, level: usize) -> Result<()> {
+        self.ksk.truncate_to_level(level)?;
+        self.element =
+            SubstitutionExponent::new(self.ksk.par.ctx_at_level(level)?, self.element.exponent)
+                .map_err(Error::MathError)?;
+        Ok(())
+    }
 }
 
 impl From<&GaloisKey> for GaloisKeyProto {
@@ -104,8 +122,8 @@ impl From<&GaloisKey> for GaloisKeyProto {
 
 impl TryConvertFrom<&GaloisKeyProto> for GaloisKey {
     fn try_convert_from(value: &GaloisKeyProto, par: &Arc<BfvParameters>) -> Result<Self> {
-        if value.ksk.is_some() {
-            let ksk = KeySwitchingKey::try_convert_from(value.ksk.as_ref().unwrap(), par)?;
+        if let Some(ksk) = value.ksk.as_ref() {
+            let ksk = KeySwitchingKey::try_convert_from(ksk, par)?;
 
             let ctx = par.ctx_at_level(ksk.ciphertext_level)?;
             let element = SubstitutionExponent::new(ctx, value.exponent as usize)
@@ -118,7 +136,30 @@ impl TryConvertFrom<&GaloisKeyProto> for GaloisKey {
     }
 }
 
-#[cfg(test)]
+impl Serialize for GaloisKey {
+    fn to_bytes(&self) -> Vec<u8> {
+        GaloisKeyProto::from(self).encode_to_vec()
+    }
+}
+
+impl FheParametrized for GaloisKey {
+    type Parameters = BfvParameters;
+}
+
+impl DeserializeParametrized for GaloisKey {
+    type Error = Error;
+
+    fn from_bytes(bytes: &[u8], par: &Arc<Self::Parameters>) -> Result<Self> {
+        let gk = Message::decode(bytes);
+        if let Ok(gk) = gk {
+            GaloisKey::try_convert_from(&gk, par)
+        } else {
+            Err(Error::DefaultError("Invalid serialization".to_string()))
+        }
+    }
+}
+
+#[cfg(all(test, not(feature = "server")))]
 mod tests {
     extern crate alloc;
     use super::GaloisKey;
@@ -127,7 +168,9 @@ mod tests {
     use crate::Error;
     use alloc::vec;
     use alloc::vec::Vec;
-    use fhe_traits::{FheDecoder, FheDecrypter, FheEncoder, FheEncrypter};
+    use fhe_traits::{
+        DeserializeParametrized, FheDecoder, FheDecrypter, FheEncoder, FheEncrypter, Serialize,
+    };
     use rand::thread_rng;
 
     #[test]
@@ -191,6 +234,7 @@ mod tests {
             let gk = GaloisKey::new(&sk, 9, 0, 0, &mut rng)?;
             let proto = GaloisKeyProto::from(&gk);
             assert_eq!(gk, GaloisKey::try_convert_from(&proto, &params)?);
+            assert_eq!(gk, GaloisKey::from_bytes(&gk.to_bytes(), &params)?);
         }
         Ok(())
     }
@@ -0,0 +1,311 @@
+//! Bundle of the public and evaluation keys for the BFV encryption scheme.
+
+use crate::bfv::{
+    traits::TryConvertFrom, BfvParameters, EvaluationKey, PublicKey, RelinearizationKey,
+};
+use crate::proto::bfv::{
+    EvaluationKey as EvaluationKeyProto, KeyPackage as KeyPackageProto,
+    PublicKey as PublicKeyProto, RelinearizationKey as RelinearizationKeyProto,
+};
+use crate::{Error, Result};
+use fhe_traits::{DeserializeParametrized, FheParametrized, Serialize};
+use hashbrown::HashSet;
+use prost::Message;
+use sha2::{Digest, Sha256};
+extern crate alloc;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+/// Bundle of a [`PublicKey`] with an optional [`RelinearizationKey`] and an
+/// optional [`EvaluationKey`], serialized as a single message.
+///
+/// The serialization records the identifier of the parameters and a SHA-256
+/// digest of the keys, which are both verified when deserializing. The
+/// evaluation key can be partially loaded with
+/// [`KeyPackage::from_bytes_with_rotations`] to only keep the rotations needed
+/// by a computation.
+#[derive(Debug, PartialEq, Eq)]
+pub struct KeyPackage {
+    par: Arc<BfvParameters>,
+    pk: PublicKey,
+    rk: Option<RelinearizationKey>,
+    ek: Option<EvaluationKey>,
+}
+
+impl KeyPackage {
+    /// Create a [`KeyPackage`] containing the public key `pk`.
+    pub fn new(pk: PublicKey) -> Self {
+        Self {
+            par: pk.par.clone(),
+            pk,
+            rk: None,
+            ek: None,
+        }
+    }
+
+    /// Add a relinearization key to the package.
+    ///
+    /// Returns an error if the key does not use the parameters of the public
+    /// key.
+    pub fn with_relinearization_key(mut self, rk: RelinearizationKey) -> Result<Self> {
+        if rk.ksk.par != self.par {
+            return Err(Error::DefaultError(
+                "Incompatible BFV parameters".to_string(),
+            ));
+        }
+        self.rk = Some(rk);
+        Ok(self)
+    }
+
+    /// Add an evaluation key to the package.
+    ///
+    /// Returns an error if the key does not use the parameters of the public
+    /// key.
+    pub fn with_evaluation_key(mut self, ek: EvaluationKey) -> Result<Self> {
+        if ek.par != self.par {
+            return Err(Error::DefaultError(
+                "Incompatible BFV parameters".to_string(),
+            ));
+        }
+        self.ek = Some(ek);
+        Ok(self)
+    }
+
+    /// Returns the public key of the package.
+    pub fn public_key(&self) -> &PublicKey {
+        &self.pk
+    }
+
+    /// Returns the relinearization key of the package, if any.
+    pub fn relinearization_key(&self) -> Option<&RelinearizationKey> {
+        self.rk.as_ref()
+    }
+
+    /// Returns the evaluation key of the package, if any.
+    pub fn evaluation_key(&self) -> Option<&EvaluationKey> {
+        self.ek.as_ref()
+    }
+
+    /// Consume the package and returns its keys.
+    pub fn into_parts(self) -> (PublicKey, Option<RelinearizationKey>, Option<EvaluationKey>) {
+        (self.pk, self.rk, self.ek)
+    }
+
+    /// Deserialize a [`KeyPackage`], only keeping in the evaluation key the
+    /// Galois keys needed to rotate the columns by the indices in `columns`,
+    /// and to rotate the rows if `rows` is true.
+    ///
+    /// Returns an error if the package does not contain one of the requested
+    /// rotations, or under the same conditions as
+    /// [`KeyPackage::from_bytes`](DeserializeParametrized::from_bytes).
+    pub fn from_bytes_with_rotations(
+        bytes: &[u8],
+        par: &Arc<BfvParameters>,
+        columns: &[usize],
+        rows: bool,
+    ) -> Result<Self> {
+        let mut proto = Self::decode_verified(bytes, par)?;
+        if let Some(ek) = proto.ek.as_mut() {
+            let rot_to_gk_exponent = EvaluationKey::construct_rot_to_gk_exponent(par);
+            let mut exponents = HashSet::new();
+            for i in columns {
+                let exponent = rot_to_gk_exponent.get(i).ok_or_else(|| {
+                    Error::DefaultError("Invalid column rotation index".to_string())
+                })?;
+                exponents.insert(*exponent as u32);
+            }
+            if rows {
+                exponents.insert((2 * par.degree() - 1) as u32);
+            }
+            ek.gk.retain(|gk| exponents.contains(&gk.exponent));
+            if ek.gk.len() != exponents.len() {
+                return Err(Error::DefaultError(
+                    "The key package does not contain the requested rotations".to_string(),
+                ));
+            }
+        } else if !columns.is_empty() || rows {
+            return Err(Error::DefaultError(
+                "The key package does not contain an evaluation key".to_string(),
+            ));
+        }
+        Self::try_convert_from(&proto, par)
+    }
+
+    /// Compute the digest of a serialized package whose digest is empty.
+    fn digest(proto: &KeyPackageProto) -> Vec<u8> {
+        Sha256::digest(proto.encode_to_vec()).to_vec()
+    }
+
+    /// Decode a serialized package, and verify its digest and parameters.
+    fn decode_verified(bytes: &[u8], par: &Arc<BfvParameters>) -> Result<KeyPackageProto> {
+        let mut proto: KeyPackageProto =
+            Message::decode(bytes).map_err(|_| Error::SerializationError)?;
+        let digest = core::mem::take(&mut proto.digest);
+        if digest != Self::digest(&proto) {
+            return Err(Error::IntegrityCheckFailed);
+        }
+        if proto.params_id != par.id() {
+            return Err(Error::DefaultError(
+                "Incompatible BFV parameters".to_string(),
+            ));
+        }
+        Ok(proto)
+    }
+}
+
+impl From<&KeyPackage> for KeyPackageProto {
+    fn from(package: &KeyPackage) -> Self {
+        let mut proto = KeyPackageProto {
+            params_id: package.par.id(),
+            pk: Some(PublicKeyProto::from(&package.pk)),
+            rk: package.rk.as_ref().map(RelinearizationKeyProto::from),
+            ek: package.ek.as_ref().map(EvaluationKeyProto::from),
+            digest: Vec::new(),
+        };
+        proto.digest = KeyPackage::digest(&proto);
+        proto
+    }
+}
+
+impl TryConvertFrom<&KeyPackageProto> for KeyPackage {
+    fn try_convert_from(value: &KeyPackageProto, par: &Arc<BfvParameters>) -> Result<Self> {
+        let pk = value.pk.as_ref().ok_or(Error::SerializationError)?;
+        Ok(Self {
+            par: par.clone(),
+            pk: PublicKey::try_convert_from(pk, par)?,
+            rk: value
+                .rk
+                .as_ref()
+                .map(|rk| RelinearizationKey::try_convert_from(rk, par))
+                .transpose()?,
+            ek: value
+                .ek
+                .as_ref()
+                .map(|ek| EvaluationKey::try_convert_from(ek, par))
+                .transpose()?,
+        })
+    }
+}
+
+impl FheParametrized for KeyPackage {
+    type Parameters = BfvParameters;
+}
+
+impl Serialize for KeyPackage {
+    fn to_bytes(&self) -> Vec<u8> {
+        KeyPackageProto::from(self).encode_to_vec()
+    }
+}
+
+impl DeserializeParametrized for KeyPackage {
+    type Error = Error;
+
+    /// Deserialize a [`KeyPackage`].
+    ///
+    /// Returns [`Error::IntegrityCheckFailed`] if the digest does not match
+    /// the keys, and an error if the package was created for other
+    /// parameters.
+    fn from_bytes(bytes: &[u8], par: &Arc<Self::Parameters>) -> Result<Self> {
+        let proto = Self::decode_verified(bytes, par)?;
+        Self::try_convert_from(&proto, par)
+    }
+}
+
+#[cfg(all(test, not(feature = "server")))]
+mod tests {
+    use super::KeyPackage;
+    use crate::bfv::{
+        BfvParameters, Ciphertext, Encoding, EvaluationKeyBuilder, Plaintext, PublicKey,
+        RelinearizationKey, SecretKey,
+    };
+    use crate::Error;
+    use fhe_traits::{
+        DeserializeParametrized, FheDecoder, FheDecrypter, FheEncoder, FheEncrypter, Serialize,
+    };
+    use rand::thread_rng;
+    extern crate alloc;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn serialize() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let par = BfvParameters::default_arc(2, 16);
+        let sk = SecretKey::random(&par, &mut rng);
+        let package = KeyPackage::new(PublicKey::new(&sk, &mut rng))
+            .with_relinearization_key(RelinearizationKey::new(&sk, &mut rng)?)?
+            .with_evaluation_key(
+                EvaluationKeyBuilder::new(&sk)?
+                    .enable_column_rotation(1)?
+                    .enable_column_rotation(2)?
+                    .enable_row_rotation()?
+                    .build(&mut rng)?,
+            )?;
+        let bytes = package.to_bytes();
+        assert_eq!(KeyPackage::from_bytes(&bytes, &par)?, package);
+
+        let pk_only = KeyPackage::new(package.public_key().clone());
+        let decoded = KeyPackage::from_bytes(&pk_only.to_bytes(), &par)?;
+        assert!(decoded.relinearization_key().is_none());
+        assert!(decoded.evaluation_key().is_none());
+        assert_eq!(decoded, pk_only);
+
+        let mut corrupted = bytes.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        assert_eq!(
+            KeyPackage::from_bytes(&corrupted, &par),
+            Err(Error::IntegrityCheckFailed)
+        );
+        assert!(KeyPackage::from_bytes(&bytes, &BfvParameters::default_arc(3, 16)).is_err());
+
+        let other_sk = SecretKey::random(&BfvParameters::default_arc(3, 16), &mut rng);
+        assert!(KeyPackage::new(package.public_key().clone())
+            .with_relinearization_key(RelinearizationKey::new(&other_sk, &mut rng)?)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn partial_load() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let par = BfvParameters::default_arc(2, 16);
+        let sk = SecretKey::random(&par, &mut rng);
+        let package = KeyPackage::new(PublicKey::new(&sk, &mut rng)).with_evaluation_key(
+            EvaluationKeyBuilder::new(&sk)?
+                .enable_column_rotation(1)?
+                .enable_column_rotation(2)?
+                .enable_row_rotation()?
+                .build(&mut rng)?,
+        )?;
+        let bytes = package.to_bytes();
+
+        let partial = KeyPackage::from_bytes_with_rotations(&bytes, &par, &[1], false)?;
+        let ek = partial.evaluation_key().unwrap();
+        assert!(ek.supports_column_rotation_by(1));
+        assert!(!ek.supports_column_rotation_by(2));
+        assert!(!ek.supports_row_rotation());
+
+        let v = par.plaintext.random_vec(par.degree(), &mut rng);
+        let pt = Plaintext::try_encode(&v, Encoding::simd(), &par)?;
+        let ct: Ciphertext = partial.public_key().try_encrypt(&pt, &mut rng)?;
+        let rotated = ek.rotates_columns_by(&ct, 1)?;
+        let w = Vec::<u64>::try_decode(&sk.try_decrypt(&rotated)?, Encoding::simd())?;
+        let row = par.degree() / 2;
+        for i in 0..row {
+            assert_eq!(w[i], v[(i + 1) % row]);
+            assert_eq!(w[row + i], v[row + (i + 1) % row]);
+        }
+
+        let partial = KeyPackage::from_bytes_with_rotations(&bytes, &par, &[], true)?;
+        let ek = partial.evaluation_key().unwrap();
+        assert!(ek.supports_row_rotation());
+        assert!(!ek.supports_column_rotation_by(1));
+
+        assert!(KeyPackage::from_bytes_with_rotations(&bytes, &par, &[3], false).is_err());
+        assert!(KeyPackage::from_bytes_with_rotations(&bytes, &par, &[0], false).is_err());
+        let pk_only = KeyPackage::new(package.public_key().clone()).to_bytes();
+        assert!(KeyPackage::from_bytes_with_rotations(&pk_only, &par, &[1], false).is_err());
+        assert!(KeyPackage::from_bytes_with_rotations(&pk_only, &par, &[], false).is_ok());
+        Ok(())
+    }
+}
@@ -1,18 +1,23 @@
 //! Key-switching keys for the BFV encryption scheme
 
-use crate::bfv::{traits::TryConvertFrom as BfvTryConvertFrom, BfvParameters, SecretKey};
+#[cfg(not(feature = "server"))]
+use crate::bfv::SecretKey;
+use crate::bfv::{traits::TryConvertFrom as BfvTryConvertFrom, BfvParameters};
 use crate::proto::bfv::KeySwitchingKey as KeySwitchingKeyProto;
 use crate::{Error, Result};
 use fhe_math::rq::traits::TryConvertFrom;
 use fhe_math::rq::Context;
-use fhe_math::{
-    rns::RnsContext,
-    rq::{Poly, Representation},
-};
+use fhe_math::rq::{dot_product, Poly, Representation};
+#[cfg(not(feature = "server"))]
+use fhe_math::{rns::RnsContext, rq::Distribution};
 use fhe_traits::{DeserializeWithContext, Serialize};
-use itertools::{izip, Itertools};
+use itertools::Itertools;
+use ndarray::s;
+#[cfg(not(feature = "server"))]
 use num_bigint::BigUint;
-use rand::{Rng, RngCore, SeedableRng};
+#[cfg(not(feature = "server"))]
+use rand::{CryptoRng, RngCore};
+use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha8Rng;
 extern crate alloc;
 use alloc::boxed::Box;
@@ -20,6 +25,7 @@ use alloc::string::ToString;
 use alloc::sync::Arc;
 use alloc::vec;
 use alloc::vec::Vec;
+#[cfg(not(feature = "server"))]
 use zeroize::Zeroizing;
 
 /// Key switching key for the BFV encryption scheme.
@@ -52,7 +58,8 @@ pub struct KeySwitchingKey {
 impl KeySwitchingKey {
     /// Generate a [`KeySwitchingKey`] to this [`SecretKey`] from a polynomial
     /// `from`.
-    pub fn new<R: RngCore>(
+    #[cfg(not(feature = "server"))]
+    pub fn new<R: RngCore + CryptoRng>(
         sk: &SecretKey,
         from: &Poly,
         ciphertext_level: usize,
@@ -76,7 +83,7 @@ impl KeySwitchingKey {
             let log_modulus = modulus.next_power_of_two().ilog2() as usize;
             let log_base = log_modulus / 2;
 
-            let c1 = Self::generate_c1(ctx_ksk, seed, (log_modulus + log_base - 1) / log_base);
+            let c1 = Self::generate_c1(ctx_ksk, seed, log_modulus.div_ceil(log_base));
             let c0 = Self::generate_c0_decomposition(sk, from, &c1, rng, log_base)?;
 
             Ok(Self {
@@ -127,7 +134,8 @@ impl KeySwitchingKey {
     }
 
     /// Generate the c0's from the c1's and the secret key
-    fn generate_c0<R: RngCore>(
+    #[cfg(not(feature = "server"))]
+    fn generate_c0<R: RngCore + CryptoRng>(
         sk: &SecretKey,
         from: &Poly,
         c1: &[Poly],
@@ -153,36 +161,81 @@ impl KeySwitchingKey {
         s.change_representation(Representation::Ntt);
 
         let rns = RnsContext::new(&sk.par.moduli[..size])?;
+
+        #[cfg(not(feature = "parallel"))]
         let c0 = c1
             .iter()
             .enumerate()
-            .map(|(i, c1i)| {
-                let mut a_s = Zeroizing::new(c1i.clone());
-                a_s.disallow_variable_time_computations();
-                a_s.change_representation(Representation::Ntt);
-                *a_s.as_mut() *= s.as_ref();
-                a_s.change_representation(Representation::PowerBasis);
-
-                let mut b =
-                    Poly::small(a_s.ctx(), Representation::PowerBasis, sk.par.variance, rng)?;
-                b -= &a_s;
-
-                let gi = rns.get_garner(i).unwrap();
-                let g_i_from = Zeroizing::new(gi * from);
-                b += &g_i_from;
-
-                // It is now safe to enable variable time computations.
-                unsafe { b.allow_variable_time_computations() }
-                b.change_representation(Representation::NttShoup);
-                Ok(b)
-            })
+            .map(|(i, c1i)| Self::generate_c0_i(sk, &s, &rns, from, i, c1i, rng))
             .collect::<Result<Vec<Poly>>>()?;
 
+        #[cfg(feature = "parallel")]
+        let c0 = {
+            use rayon::prelude::*;
+
+            // Derive one seed per modulus from `rng` so that the c0's can be
+            // generated independently.
+            let seeds = Zeroizing::new(
+                (0..size)
+                    .map(|_| {
+                        let mut seed = <ChaCha8Rng as SeedableRng>::Seed::default();
+                        rng.fill(&mut seed);
+                        seed
+                    })
+                    .collect_vec(),
+            );
+            c1.par_iter()
+                .zip(seeds.par_iter())
+                .enumerate()
+                .map(|(i, (c1i, seed))| {
+                    let mut rng = ChaCha8Rng::from_seed(*seed);
+                    Self::generate_c0_i(sk, &s, &rns, from, i, c1i, &mut rng)
+                })
+                .collect::<Result<Vec<Poly>>>()?
+        };
+
         Ok(c0)
     }
 
+    /// Generate the i-th c0 from the i-th c1 and the secret key `s` in NTT
+    /// representation.
+    #[cfg(not(feature = "server"))]
+    fn generate_c0_i<R: RngCore + CryptoRng>(
+        sk: &SecretKey,
+        s: &Poly,
+        rns: &RnsContext,
+        from: &Poly,
+        i: usize,
+        c1i: &Poly,
+        rng: &mut R,
+    ) -> Result<Poly> {
+        let mut a_s = Zeroizing::new(c1i.clone());
+        a_s.disallow_variable_time_computations();
+        a_s.change_representation(Representation::Ntt);
+        *a_s.as_mut() *= s;
+        a_s.change_representation(Representation::PowerBasis);
+
+        let mut b = Poly::small(
+            a_s.ctx(),
+            Representation::PowerBasis,
+            Distribution::Cbd(sk.par.variance),
+            rng,
+        )?;
+        b -= &a_s;
+
+        let gi = rns.get_garner(i).unwrap();
+        let g_i_from = Zeroizing::new(gi * from);
+        b += &g_i_from;
+
+        // It is now safe to enable variable time computations.
+        unsafe { b.allow_variable_time_computations() }
+        b.change_representation(Representation::NttShoup);
+        Ok(b)
+    }
+
     /// Generate the c0's from the c1's and the secret key
-    fn generate_c0_decomposition<R: RngCore>(
+    #[cfg(not(feature = "server"))]
+    fn generate_c0_decomposition<R: RngCore + CryptoRng>(
         sk: &SecretKey,
         from: &Poly,
         c1: &[Poly],
@@ -217,8 +270,12 @@ impl KeySwitchingKey {
                 *a_s.as_mut() *= s.as_ref();
                 a_s.change_representation(Representation::PowerBasis);
 
-                let mut b =
-                    Poly::small(a_s.ctx(), Representation::PowerBasis, sk.par.variance, rng)?;
+                let mut b = Poly::small(
+                    a_s.ctx(),
+                    Representation::PowerBasis,
+                    Distribution::Cbd(sk.par.variance),
+                    rng,
+                )?;
                 b -= &a_s;
 
                 let power = BigUint::from(1u64 << (i * log_base));
@@ -236,6 +293,8 @@ impl KeySwitchingKey {
 
     /// Key switch a polynomial.
     pub fn key_switch(&self, p: &Poly) -> Result<(Poly, Poly)> {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record(crate::metrics::Metric::KeySwitch, 1);
         if self.log_base != 0 {
             return self.key_switch_decomposition(p);
         }
@@ -249,23 +308,18 @@ impl KeySwitchingKey {
             return Err(Error::DefaultError("Incorrect representation".to_string()));
         }
 
-        let mut c0 = Poly::zero(&self.ctx_ksk, Representation::Ntt);
-        let mut c1 = Poly::zero(&self.ctx_ksk, Representation::Ntt);
-        for (c2_i_coefficients, c0_i, c1_i) in izip!(
-            p.coefficients().outer_iter(),
-            self.c0.iter(),
-            self.c1.iter()
-        ) {
-            let mut c2_i = unsafe {
+        let c2 = p
+            .coefficients()
+            .outer_iter()
+            .map(|c2_i_coefficients| unsafe {
                 Poly::create_constant_ntt_polynomial_with_lazy_coefficients_and_variable_time(
                     c2_i_coefficients.as_slice().unwrap(),
                     &self.ctx_ksk,
                 )
-            };
-            c0 += &(&c2_i * c0_i);
-            c2_i *= c1_i;
-            c1 += &c2_i;
-        }
+            })
+            .collect_vec();
+        let c0 = dot_product(c2.iter(), self.c0.iter())?;
+        let c1 = dot_product(c2.iter(), self.c1.iter())?;
         Ok((c0, c1))
     }
 
@@ -291,26 +345,74 @@ impl KeySwitchingKey {
         let mut coefficients = p.coefficients().to_slice().unwrap().to_vec();
         let mut c2i = vec![];
         let mask = (1u64 << self.log_base) - 1;
-        (0..(log_modulus + self.log_base - 1) / self.log_base).for_each(|_| {
+        (0..log_modulus.div_ceil(self.log_base)).for_each(|_| {
             c2i.push(coefficients.iter().map(|c| c & mask).collect_vec());
             coefficients.iter_mut().for_each(|c| *c >>= self.log_base);
         });
 
-        let mut c0 = Poly::zero(&self.ctx_ksk, Representation::Ntt);
-        let mut c1 = Poly::zero(&self.ctx_ksk, Representation::Ntt);
-        for (c2_i_coefficients, c0_i, c1_i) in izip!(c2i.iter(), self.c0.iter(), self.c1.iter()) {
-            let mut c2_i = unsafe {
+        let c2 = c2i
+            .iter()
+            .map(|c2_i_coefficients| unsafe {
                 Poly::create_constant_ntt_polynomial_with_lazy_coefficients_and_variable_time(
                     c2_i_coefficients.as_slice(),
                     &self.ctx_ksk,
                 )
-            };
-            c0 += &(&c2_i * c0_i);
-            c2_i *= c1_i;
-            c1 += &c2_i;
-        }
+            })
+            .collect_vec();
+        let c0 = dot_product(c2.iter(), self.c0.iter())?;
+        let c1 = dot_product(c2.iter(), self.c1.iter())?;
         Ok((c0, c1))
     }
+
+    /// Truncate the key so that it key switches polynomials at level `level`,
+    /// by only keeping the components and the moduli used at that level.
+    ///
+    /// Since the RNS gadget vectors of the smaller moduli chain are the
+    /// reductions of the original ones, and the polynomials c1 sampled from the
+    /// seed restrict to the smaller context, the truncated key is a valid key
+    /// and keeps its seed. This requires the key to be at the same level as
+    /// the polynomials it key switches, and `level` to be larger or equal and
+    /// to have at least two moduli.
+    pub(crate) fn truncate_to_level(&mut self, level: usize) -> Result<()> {
+        if level == self.ciphertext_level && level == self.ksk_level {
+            return Ok(());
+        }
+        if level < self.ciphertext_level || self.ksk_level != self.ciphertext_level {
+            return Err(Error::DefaultError(
+                "The key cannot be truncated to this level".to_string(),
+            ));
+        }
+        let ctx = self.par.ctx_at_level(level)?.clone();
+        let size = ctx.moduli().len();
+        if size == 1 {
+            // With a single modulus, the key must use a decomposition basis.
+            return Err(Error::DefaultError(
+                "The key cannot be truncated to a level with a single modulus".to_string(),
+            ));
+        }
+        let truncate = |p: &Poly| -> Result<Poly> {
+            Poly::try_convert_from(
+                p.coefficients().slice(s![..size, ..]).to_owned(),
+                &ctx,
+                true,
+                Representation::NttShoup,
+            )
+            .map_err(Error::MathError)
+        };
+        self.c0 = self.c0[..size]
+            .iter()
+            .map(truncate)
+            .collect::<Result<Box<[Poly]>>>()?;
+        self.c1 = self.c1[..size]
+            .iter()
+            .map(truncate)
+            .collect::<Result<Box<[Poly]>>>()?;
+        self.ciphertext_level = level;
+        self.ctx_ciphertext = ctx.clone();
+        self.ksk_level = level;
+        self.ctx_ksk = ctx;
+        Ok(())
+    }
 }
 
 impl From<&KeySwitchingKey> for KeySwitchingKeyProto {
@@ -352,7 +454,7 @@ impl BfvTryConvertFrom<&KeySwitchingKeyProto> for KeySwitchingKey {
             } else {
                 let log_modulus: usize =
                     par.moduli().first().unwrap().next_power_of_two().ilog2() as usize;
-                c0_size = (log_modulus + log_base - 1) / log_base;
+                c0_size = log_modulus.div_ceil(log_base);
             }
         } else {
             c0_size = ctx_ciphertext.moduli().len();
@@ -409,7 +511,7 @@ impl BfvTryConvertFrom<&KeySwitchingKeyProto> for KeySwitchingKey {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, not(feature = "server")))]
 mod tests {
     use crate::bfv::{
         keys::key_switching_key::KeySwitchingKey, traits::TryConvertFrom, BfvParameters, SecretKey,
@@ -421,7 +523,7 @@ mod tests {
     use alloc::vec::Vec;
     use fhe_math::{
         rns::RnsContext,
-        rq::{traits::TryConvertFrom as TryConvertFromPoly, Poly, Representation},
+        rq::{traits::TryConvertFrom as TryConvertFromPoly, Distribution, Poly, Representation},
     };
     use num_bigint::BigUint;
     use rand::thread_rng;
@@ -435,7 +537,12 @@ mod tests {
         ] {
             let sk = SecretKey::random(&params, &mut rng);
             let ctx = params.ctx_at_level(0)?;
-            let p = Poly::small(ctx, Representation::PowerBasis, 10, &mut rng)?;
+            let p = Poly::small(
+                ctx,
+                Representation::PowerBasis,
+                Distribution::Cbd(10),
+                &mut rng,
+            )?;
             let ksk = KeySwitchingKey::new(&sk, &p, 0, 0, &mut rng);
             assert!(ksk.is_ok());
         }
@@ -452,7 +559,12 @@ mod tests {
             let level = params.moduli().len() - 1;
             let sk = SecretKey::random(&params, &mut rng);
             let ctx = params.ctx_at_level(level)?;
-            let p = Poly::small(ctx, Representation::PowerBasis, 10, &mut rng)?;
+            let p = Poly::small(
+                ctx,
+                Representation::PowerBasis,
+                Distribution::Cbd(10),
+                &mut rng,
+            )?;
             let ksk = KeySwitchingKey::new(&sk, &p, level, level, &mut rng);
             assert!(ksk.is_ok());
         }
@@ -466,7 +578,12 @@ mod tests {
             for _ in 0..100 {
                 let sk = SecretKey::random(&params, &mut rng);
                 let ctx = params.ctx_at_level(0)?;
-                let mut p = Poly::small(ctx, Representation::PowerBasis, 10, &mut rng)?;
+                let mut p = Poly::small(
+                    ctx,
+                    Representation::PowerBasis,
+                    Distribution::Cbd(10),
+                    &mut rng,
+                )?;
                 let ksk = KeySwitchingKey::new(&sk, &p, 0, 0, &mut rng)?;
                 let mut s = Poly::try_convert_from(
                     sk.coeffs.as_ref(),
@@ -504,7 +621,12 @@ mod tests {
             for _ in 0..100 {
                 let sk = SecretKey::random(&params, &mut rng);
                 let ctx = params.ctx_at_level(5)?;
-                let mut p = Poly::small(ctx, Representation::PowerBasis, 10, &mut rng)?;
+                let mut p = Poly::small(
+                    ctx,
+                    Representation::PowerBasis,
+                    Distribution::Cbd(10),
+                    &mut rng,
+                )?;
                 let ksk = KeySwitchingKey::new(&sk, &p, 5, 5, &mut rng)?;
                 let mut s = Poly::try_convert_from(
                     sk.coeffs.as_ref(),
@@ -547,7 +669,12 @@ mod tests {
         ] {
             let sk = SecretKey::random(&params, &mut rng);
             let ctx = params.ctx_at_level(0)?;
-            let p = Poly::small(ctx, Representation::PowerBasis, 10, &mut rng)?;
+            let p = Poly::small(
+                ctx,
+                Representation::PowerBasis,
+                Distribution::Cbd(10),
+                &mut rng,
+            )?;
             let ksk = KeySwitchingKey::new(&sk, &p, 0, 0, &mut rng)?;
             let ksk_proto = KeySwitchingKeyProto::from(&ksk);
             assert_eq!(ksk, KeySwitchingKey::try_convert_from(&ksk_proto, &params)?);
@@ -1,14 +1,34 @@
+#[cfg(not(feature = "client"))]
 mod evaluation_key;
+#[cfg(not(feature = "client"))]
 mod galois_key;
+#[cfg(not(feature = "client"))]
+mod key_package;
+#[cfg(not(feature = "client"))]
 mod key_switching_key;
 mod public_key;
+#[cfg(not(feature = "client"))]
+mod rekeying_key;
+#[cfg(not(feature = "client"))]
 mod relinearization_key;
+#[cfg(not(any(feature = "client", feature = "server")))]
 mod secret_key;
 
-pub use evaluation_key::{EvaluationKey, EvaluationKeyBuilder};
+#[cfg(not(feature = "client"))]
+pub use evaluation_key::EvaluationKey;
+#[cfg(not(any(feature = "client", feature = "server")))]
+pub use evaluation_key::EvaluationKeyBuilder;
+#[cfg(not(feature = "client"))]
 pub use galois_key::GaloisKey;
-pub use public_key::PublicKey;
+#[cfg(not(feature = "client"))]
+pub use key_package::KeyPackage;
+pub use public_key::{EncryptionRandomness, PublicKey};
+#[cfg(not(feature = "client"))]
+pub use rekeying_key::RekeyingKey;
+#[cfg(not(feature = "client"))]
 pub use relinearization_key::RelinearizationKey;
-pub use secret_key::SecretKey;
+#[cfg(not(any(feature = "client", feature = "server")))]
+pub use secret_key::{DecryptionProofData, SecretKey};
 
+#[cfg(not(feature = "client"))]
 pub(crate) use key_switching_key::KeySwitchingKey;
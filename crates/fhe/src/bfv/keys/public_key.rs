@@ -1,19 +1,27 @@
 //! Public keys for the BFV encryption scheme
 
 use crate::bfv::traits::TryConvertFrom;
-use crate::bfv::{BfvParameters, Ciphertext, Encoding, Plaintext};
+#[cfg(not(any(feature = "client", feature = "server")))]
+use crate::bfv::Encoding;
+use crate::bfv::{BfvParameters, Ciphertext, Plaintext};
 use crate::proto::bfv::{Ciphertext as CiphertextProto, PublicKey as PublicKeyProto};
 use crate::{Error, Result};
-use fhe_math::rq::{Poly, Representation};
+use fhe_math::rq::{Distribution, Poly, Representation};
 use fhe_traits::{DeserializeParametrized, FheEncrypter, FheParametrized, Serialize};
 use prost::Message;
-use rand::RngCore;
+#[cfg(feature = "testing")]
+use rand::SeedableRng;
+use rand::{CryptoRng, RngCore};
+#[cfg(feature = "testing")]
+use rand_chacha::ChaCha8Rng;
+use smallvec::smallvec;
 extern crate alloc;
 use alloc::sync::Arc;
-use alloc::vec;
 use alloc::vec::Vec;
+use core::fmt::Debug;
 use zeroize::Zeroizing;
 
+#[cfg(not(any(feature = "client", feature = "server")))]
 use super::SecretKey;
 
 /// Public key for the BFV encryption scheme.
@@ -23,9 +31,53 @@ pub struct PublicKey {
     pub(crate) c: Ciphertext,
 }
 
+/// Randomness of an encryption with a [`PublicKey`], returned by
+/// [`PublicKey::try_encrypt_with_randomness`] to build zero-knowledge proofs
+/// about the encrypted plaintext, e.g. proofs of plaintext knowledge or range
+/// proofs.
+///
+/// The ciphertext `(c0, c1)` of a plaintext `m` with the public key
+/// `(p0, p1)` at the level of the ciphertext is `c0 = u * p0 + e1 + m` and
+/// `c1 = u * p1 + e2`, where `m` is the scaled plaintext. The polynomials are
+/// in Ntt representation, and are zeroized when dropped: anyone holding them
+/// can decrypt the ciphertext. They are redacted from the Debug output, and
+/// are not comparable, so as not to leak them through the timing of a
+/// comparison.
+#[derive(Clone)]
+pub struct EncryptionRandomness {
+    u: Zeroizing<Poly>,
+    e1: Zeroizing<Poly>,
+    e2: Zeroizing<Poly>,
+}
+
+impl Debug for EncryptionRandomness {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("EncryptionRandomness")
+            .finish_non_exhaustive()
+    }
+}
+
+impl EncryptionRandomness {
+    /// Returns the ephemeral secret `u`.
+    pub fn u(&self) -> &Poly {
+        &self.u
+    }
+
+    /// Returns the error `e1` of the first polynomial of the ciphertext.
+    pub fn e1(&self) -> &Poly {
+        &self.e1
+    }
+
+    /// Returns the error `e2` of the second polynomial of the ciphertext.
+    pub fn e2(&self) -> &Poly {
+        &self.e2
+    }
+}
+
 impl PublicKey {
     /// Generate a new [`PublicKey`] from a [`SecretKey`].
-    pub fn new<R: RngCore>(sk: &SecretKey, rng: &mut R) -> Self {
+    #[cfg(not(any(feature = "client", feature = "server")))]
+    pub fn new<R: RngCore + CryptoRng>(sk: &SecretKey, rng: &mut R) -> Self {
         let zero = Plaintext::zero(Encoding::poly(), &sk.par).unwrap();
         let mut c: Ciphertext = sk.try_encrypt(&zero, rng).unwrap();
         // The polynomials of a public key should not allow for variable time
@@ -37,16 +89,51 @@ impl PublicKey {
             c,
         }
     }
-}
 
-impl FheParametrized for PublicKey {
-    type Parameters = BfvParameters;
-}
+    /// Generate a new [`PublicKey`] from a [`SecretKey`] using the random
+    /// number generator of the operating system.
+    #[cfg(all(feature = "os-rng", not(any(feature = "client", feature = "server"))))]
+    pub fn new_with_os_rng(sk: &SecretKey) -> Self {
+        Self::new(sk, &mut rand::rngs::OsRng)
+    }
 
-impl FheEncrypter<Plaintext, Ciphertext> for PublicKey {
-    type Error = Error;
+    /// Encrypt a [`Plaintext`] deterministically: all the randomness of the
+    /// encryption is derived from `seed`, so that the ciphertext is
+    /// reproducible byte for byte. This is only meant for test vectors and
+    /// conformance tests; encrypting with a fixed seed is insecure.
+    #[cfg(feature = "testing")]
+    pub fn try_encrypt_deterministic(
+        &self,
+        pt: &Plaintext,
+        seed: <ChaCha8Rng as SeedableRng>::Seed,
+    ) -> Result<Ciphertext> {
+        self.try_encrypt(pt, &mut ChaCha8Rng::from_seed(seed))
+    }
 
-    fn try_encrypt<R: RngCore>(&self, pt: &Plaintext, rng: &mut R) -> Result<Ciphertext> {
+    /// Encrypt a [`Plaintext`] directly at level `level`, whatever the level
+    /// of the plaintext, e.g. when the ciphertext would be switched to this
+    /// level right away: the ciphertext is smaller, and has the noise of a
+    /// fresh encryption rather than the noise of a modulus switching.
+    ///
+    /// Returns an error if the parameters do not have this level.
+    pub fn try_encrypt_at_level<R: RngCore + CryptoRng>(
+        &self,
+        pt: &Plaintext,
+        level: usize,
+        rng: &mut R,
+    ) -> Result<Ciphertext> {
+        self.try_encrypt(&pt.to_level(level)?, rng)
+    }
+
+    /// Encrypt a [`Plaintext`] and return the randomness of the encryption
+    /// along with the ciphertext, which is otherwise unrecoverable. This is
+    /// only meant for building zero-knowledge proofs about the encrypted
+    /// plaintext; the randomness must be kept secret.
+    pub fn try_encrypt_with_randomness<R: RngCore + CryptoRng>(
+        &self,
+        pt: &Plaintext,
+        rng: &mut R,
+    ) -> Result<(Ciphertext, EncryptionRandomness)> {
         let mut ct = self.c.clone();
         while ct.level != pt.level {
             ct.mod_switch_to_next_level()?;
@@ -56,26 +143,26 @@ impl FheEncrypter<Plaintext, Ciphertext> for PublicKey {
         let u = Zeroizing::new(Poly::small(
             ctx,
             Representation::Ntt,
-            self.par.variance,
+            Distribution::Cbd(self.par.variance),
             rng,
         )?);
         let e1 = Zeroizing::new(Poly::small(
             ctx,
             Representation::Ntt,
-            self.par.variance,
+            Distribution::Cbd(self.par.variance),
             rng,
         )?);
         let e2 = Zeroizing::new(Poly::small(
             ctx,
             Representation::Ntt,
-            self.par.variance,
+            Distribution::Cbd(self.par.variance),
             rng,
         )?);
 
-        let m = Zeroizing::new(pt.to_poly());
+        let m = pt.to_poly();
         let mut c0 = u.as_ref() * &ct[0];
         c0 += &e1;
-        c0 += &m;
+        c0 += m;
         let mut c1 = u.as_ref() * &ct[1];
         c1 += &e2;
 
@@ -85,12 +172,33 @@ impl FheEncrypter<Plaintext, Ciphertext> for PublicKey {
             c1.allow_variable_time_computations()
         }
 
-        Ok(Ciphertext {
-            par: self.par.clone(),
-            seed: None,
-            c: vec![c0, c1],
-            level: ct.level,
-        })
+        Ok((
+            Ciphertext {
+                par: self.par.clone(),
+                seed: None,
+                c: smallvec![c0, c1],
+                level: ct.level,
+                tag: Vec::new(),
+                noise_estimate: None,
+            },
+            EncryptionRandomness { u, e1, e2 },
+        ))
+    }
+}
+
+impl FheParametrized for PublicKey {
+    type Parameters = BfvParameters;
+}
+
+impl FheEncrypter<Plaintext, Ciphertext> for PublicKey {
+    type Error = Error;
+
+    fn try_encrypt<R: RngCore + CryptoRng>(
+        &self,
+        pt: &Plaintext,
+        rng: &mut R,
+    ) -> Result<Ciphertext> {
+        self.try_encrypt_with_randomness(pt, rng).map(|(ct, _)| ct)
     }
 }
 
@@ -108,14 +216,10 @@ impl Serialize for PublicKey {
     }
 }
 
-impl DeserializeParametrized for PublicKey {
-    type Error = Error;
-
-    fn from_bytes(bytes: &[u8], par: &Arc<Self::Parameters>) -> Result<Self> {
-        let proto: PublicKeyProto =
-            Message::decode(bytes).map_err(|_| Error::SerializationError)?;
-        if proto.c.is_some() {
-            let mut c = Ciphertext::try_convert_from(&proto.c.unwrap(), par)?;
+impl TryConvertFrom<&PublicKeyProto> for PublicKey {
+    fn try_convert_from(value: &PublicKeyProto, par: &Arc<BfvParameters>) -> Result<Self> {
+        if let Some(c) = value.c.as_ref() {
+            let mut c = Ciphertext::try_convert_from(c, par)?;
             if c.level != 0 {
                 Err(Error::SerializationError)
             } else {
@@ -134,14 +238,41 @@ impl DeserializeParametrized for PublicKey {
     }
 }
 
+impl DeserializeParametrized for PublicKey {
+    type Error = Error;
+
+    fn from_bytes(bytes: &[u8], par: &Arc<Self::Parameters>) -> Result<Self> {
+        let proto: PublicKeyProto =
+            Message::decode(bytes).map_err(|_| Error::SerializationError)?;
+        PublicKey::try_convert_from(&proto, par)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::PublicKey;
-    use crate::bfv::{parameters::BfvParameters, Encoding, Plaintext, SecretKey};
+    use crate::bfv::parameters::BfvParameters;
+    #[cfg(not(any(feature = "client", feature = "server")))]
+    use crate::bfv::{Encoding, Plaintext, SecretKey};
+    #[cfg(not(any(feature = "client", feature = "server")))]
     use crate::Error;
-    use fhe_traits::{DeserializeParametrized, FheDecrypter, FheEncoder, FheEncrypter, Serialize};
+    use fhe_traits::DeserializeParametrized;
+    #[cfg(not(any(feature = "client", feature = "server")))]
+    use fhe_traits::{FheDecoder, FheDecrypter, FheEncoder, FheEncrypter, Serialize};
+    #[cfg(not(any(feature = "client", feature = "server")))]
     use rand::thread_rng;
+    extern crate alloc;
+    #[cfg(not(any(feature = "client", feature = "server")))]
+    use alloc::{format, vec::Vec};
+
+    #[test]
+    fn from_bytes_errors() {
+        let params = BfvParameters::default_arc(2, 16);
+        assert!(PublicKey::from_bytes(&[], &params).is_err());
+        assert!(PublicKey::from_bytes(&[0xff; 16], &params).is_err());
+    }
 
+    #[cfg(not(any(feature = "client", feature = "server")))]
     #[test]
     fn keygen() -> Result<(), Error> {
         let mut rng = thread_rng();
@@ -156,6 +287,7 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(not(any(feature = "client", feature = "server")))]
     #[test]
     fn encrypt_decrypt() -> Result<(), Error> {
         let mut rng = thread_rng();
@@ -185,6 +317,86 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(all(feature = "testing", not(any(feature = "client", feature = "server"))))]
+    #[test]
+    fn encrypt_deterministic() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(2, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let pk = PublicKey::new(&sk, &mut rng);
+        let v = params.plaintext.random_vec(params.degree(), &mut rng);
+        let pt = Plaintext::try_encode(&v, Encoding::poly(), &params)?;
+
+        let ct1 = pk.try_encrypt_deterministic(&pt, [1u8; 32])?;
+        let ct2 = pk.try_encrypt_deterministic(&pt, [1u8; 32])?;
+        assert_eq!(ct1.to_bytes(), ct2.to_bytes());
+        assert_ne!(
+            ct1.to_bytes(),
+            pk.try_encrypt_deterministic(&pt, [2u8; 32])?.to_bytes()
+        );
+        assert_eq!(sk.try_decrypt(&ct1)?, pt);
+        Ok(())
+    }
+
+    #[cfg(not(any(feature = "client", feature = "server")))]
+    #[test]
+    fn encrypt_at_level() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(3, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let pk = PublicKey::new(&sk, &mut rng);
+        let v = params.plaintext.random_vec(params.degree(), &mut rng);
+        let pt = Plaintext::try_encode(&v, Encoding::simd(), &params)?;
+        for level in 0..=params.max_level() {
+            let ct = pk.try_encrypt_at_level(&pt, level, &mut rng)?;
+            assert_eq!(ct.level, level);
+            assert_eq!(
+                Vec::<u64>::try_decode(&sk.try_decrypt(&ct)?, Encoding::simd())?,
+                v
+            );
+
+            // The ciphertext is smaller than a fresh ciphertext at level 0.
+            if level > 0 {
+                assert!(ct.to_bytes().len() < pk.try_encrypt(&pt, &mut rng)?.to_bytes().len());
+            }
+        }
+        assert!(pk
+            .try_encrypt_at_level(&pt, params.max_level() + 1, &mut rng)
+            .is_err());
+        Ok(())
+    }
+
+    #[cfg(not(any(feature = "client", feature = "server")))]
+    #[test]
+    fn encrypt_with_randomness() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(2, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let pk = PublicKey::new(&sk, &mut rng);
+        for level in 0..=params.max_level() {
+            let v = params.plaintext.random_vec(params.degree(), &mut rng);
+            let pt = Plaintext::try_encode(&v, Encoding::poly_at_level(level), &params)?;
+            let (ct, randomness) = pk.try_encrypt_with_randomness(&pt, &mut rng)?;
+            assert_eq!(sk.try_decrypt(&ct)?, pt);
+            assert_eq!(format!("{randomness:?}"), "EncryptionRandomness { .. }");
+
+            // The ciphertext is recomputed from the randomness.
+            let mut c = pk.c.clone();
+            while c.level != level {
+                c.mod_switch_to_next_level()?;
+            }
+            let mut c0 = randomness.u() * &c[0];
+            c0 += randomness.e1();
+            c0 += pt.to_poly();
+            let mut c1 = randomness.u() * &c[1];
+            c1 += randomness.e2();
+            assert_eq!(ct[0].coefficients(), c0.coefficients());
+            assert_eq!(ct[1].coefficients(), c1.coefficients());
+        }
+        Ok(())
+    }
+
+    #[cfg(not(any(feature = "client", feature = "server")))]
     #[test]
     fn test_serialize() -> Result<(), Error> {
         let mut rng = thread_rng();
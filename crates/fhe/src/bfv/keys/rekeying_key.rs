@@ -0,0 +1,281 @@
+//! Rekeying keys for the BFV encryption scheme
+
+extern crate alloc;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::key_switching_key::KeySwitchingKey;
+use crate::bfv::traits::TryConvertFrom as _;
+#[cfg(not(feature = "server"))]
+use crate::bfv::SecretKey;
+use crate::bfv::{BfvParameters, Ciphertext};
+use crate::proto::bfv::{KeySwitchingKey as KeySwitchingKeyProto, RekeyingKey as RekeyingKeyProto};
+use crate::{Error, Result};
+use alloc::sync::Arc;
+use fhe_math::rq::Representation;
+#[cfg(not(feature = "server"))]
+use fhe_math::rq::{switcher::Switcher, traits::TryConvertFrom, Poly};
+use fhe_traits::{DeserializeParametrized, FheParametrized, Serialize};
+use prost::Message;
+#[cfg(not(feature = "server"))]
+use rand::{CryptoRng, RngCore};
+#[cfg(not(feature = "server"))]
+use zeroize::Zeroizing;
+
+/// Rekeying key for the BFV encryption scheme.
+/// A rekeying key is a special type of key switching key, which switches
+/// ciphertexts encrypted under an old secret key `s_old` into ciphertexts
+/// encrypting the same plaintexts under a new secret key `s_new`, without
+/// decrypting them. It is used to rotate the key of stored ciphertexts.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RekeyingKey {
+    pub(crate) ksk: KeySwitchingKey,
+}
+
+impl RekeyingKey {
+    /// Generate a [`RekeyingKey`] from the secret key `old_sk` to the secret
+    /// key `new_sk`.
+    #[cfg(not(feature = "server"))]
+    pub fn new<R: RngCore + CryptoRng>(
+        old_sk: &SecretKey,
+        new_sk: &SecretKey,
+        rng: &mut R,
+    ) -> Result<Self> {
+        Self::new_leveled(old_sk, new_sk, 0, 0, rng)
+    }
+
+    /// Generate a [`RekeyingKey`] from the secret key `old_sk` to the secret
+    /// key `new_sk`, for ciphertexts at level `ciphertext_level`.
+    #[cfg(not(feature = "server"))]
+    pub fn new_leveled<R: RngCore + CryptoRng>(
+        old_sk: &SecretKey,
+        new_sk: &SecretKey,
+        ciphertext_level: usize,
+        key_level: usize,
+        rng: &mut R,
+    ) -> Result<Self> {
+        if old_sk.par != new_sk.par {
+            return Err(Error::DefaultError(
+                "Incompatible BFV parameters".to_string(),
+            ));
+        }
+        let ctx_rekeying_key = new_sk.par.ctx_at_level(key_level)?;
+        let ctx_ciphertext = new_sk.par.ctx_at_level(ciphertext_level)?;
+
+        let s_old = Zeroizing::new(Poly::try_convert_from(
+            old_sk.coeffs.as_ref(),
+            ctx_ciphertext,
+            false,
+            Representation::PowerBasis,
+        )?);
+        let switcher_up = Switcher::new(ctx_ciphertext, ctx_rekeying_key)?;
+        let s_old_switched_up = Zeroizing::new(s_old.mod_switch_to(&switcher_up)?);
+        let ksk =
+            KeySwitchingKey::new(new_sk, &s_old_switched_up, ciphertext_level, key_level, rng)?;
+        Ok(Self { ksk })
+    }
+
+    /// Rekey a [`Ciphertext`] encrypted under the old secret key into a
+    /// [`Ciphertext`] encrypted under the new secret key.
+    pub fn rekey(&self, ct: &Ciphertext) -> Result<Ciphertext> {
+        if ct.par != self.ksk.par {
+            return Err(Error::DefaultError(
+                "Incompatible BFV parameters".to_string(),
+            ));
+        }
+        if ct.len() != 2 {
+            return Err(Error::DefaultError(
+                "Only supports rekeying of ciphertext with 2 parts".to_string(),
+            ));
+        }
+        if ct.level != self.ksk.ciphertext_level {
+            return Err(Error::DefaultError(
+                "Ciphertext has incorrect level".to_string(),
+            ));
+        }
+
+        let mut c1 = ct[1].clone();
+        c1.change_representation(Representation::PowerBasis);
+        let (mut c0, mut c1) = self.ksk.key_switch(&c1)?;
+
+        if c0.ctx() != ct[0].ctx() {
+            c0.change_representation(Representation::PowerBasis);
+            c1.change_representation(Representation::PowerBasis);
+            c0.mod_switch_down_to(ct[0].ctx())?;
+            c1.mod_switch_down_to(ct[1].ctx())?;
+            c0.change_representation(Representation::Ntt);
+            c1.change_representation(Representation::Ntt);
+        }
+
+        c0 += &ct[0];
+        let mut ct_rekeyed = Ciphertext::new(vec![c0, c1], &ct.par)?;
+        ct_rekeyed.tag = ct.tag.clone();
+        Ok(ct_rekeyed)
+    }
+
+    /// Rekey a stream of serialized ciphertexts.
+    ///
+    /// The ciphertexts read from `reader` are deserialized, rekeyed and
+    /// serialized one at a time, and each rekeyed ciphertext is passed to
+    /// `writer` before the next one is read, so that large archives can be
+    /// rekeyed in bounded memory. Returns the number of rekeyed ciphertexts,
+    /// or the first error returned while processing the stream or by `writer`.
+    pub fn rekey_stream<I, B, W>(&self, reader: I, mut writer: W) -> Result<usize>
+    where
+        I: IntoIterator<Item = B>,
+        B: AsRef<[u8]>,
+        W: FnMut(Vec<u8>) -> Result<()>,
+    {
+        let mut count = 0;
+        for bytes in reader {
+            let ct = Ciphertext::from_bytes(bytes.as_ref(), &self.ksk.par)?;
+            writer(self.rekey(&ct)?.to_bytes())?;
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+impl FheParametrized for RekeyingKey {
+    type Parameters = BfvParameters;
+}
+
+impl Serialize for RekeyingKey {
+    fn to_bytes(&self) -> Vec<u8> {
+        RekeyingKeyProto {
+            ksk: Some(KeySwitchingKeyProto::from(&self.ksk)),
+        }
+        .encode_to_vec()
+    }
+}
+
+impl DeserializeParametrized for RekeyingKey {
+    type Error = Error;
+
+    fn from_bytes(bytes: &[u8], par: &Arc<BfvParameters>) -> Result<Self> {
+        let proto: RekeyingKeyProto =
+            Message::decode(bytes).map_err(|_| Error::SerializationError)?;
+        let ksk = proto.ksk.as_ref().ok_or(Error::SerializationError)?;
+        Ok(Self {
+            ksk: KeySwitchingKey::try_convert_from(ksk, par)?,
+        })
+    }
+}
+
+#[cfg(all(test, not(feature = "server")))]
+mod tests {
+    use super::RekeyingKey;
+    use crate::bfv::{BfvParameters, Ciphertext, Encoding, Plaintext, PublicKey, SecretKey};
+    use crate::Error;
+    use fhe_traits::{
+        DeserializeParametrized, FheDecoder, FheDecrypter, FheEncoder, FheEncrypter, Serialize,
+    };
+    use rand::thread_rng;
+    extern crate alloc;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn rekey() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        for params in [
+            BfvParameters::default_arc(1, 16),
+            BfvParameters::default_arc(6, 16),
+        ] {
+            let old_sk = SecretKey::random(&params, &mut rng);
+            let new_sk = SecretKey::random(&params, &mut rng);
+            let new_pk = PublicKey::new(&new_sk, &mut rng);
+            let rk = RekeyingKey::new(&old_sk, &new_sk, &mut rng)?;
+            assert_eq!(RekeyingKey::from_bytes(&rk.to_bytes(), &params)?, rk);
+
+            for _ in 0..10 {
+                let v = params.plaintext.random_vec(params.degree(), &mut rng);
+                let pt = Plaintext::try_encode(&v, Encoding::poly(), &params)?;
+                let ct: Ciphertext = old_sk.try_encrypt(&pt, &mut rng)?;
+
+                let ct_new = rk.rekey(&ct)?;
+                let w = Vec::<u64>::try_decode(&new_sk.try_decrypt(&ct_new)?, Encoding::poly())?;
+                assert_eq!(w, v);
+
+                // The rekeyed ciphertext can be combined with fresh encryptions
+                // under the new key.
+                let ct_fresh: Ciphertext = new_pk.try_encrypt(&pt, &mut rng)?;
+                let w = Vec::<u64>::try_decode(
+                    &new_sk.try_decrypt(&(&ct_new - &ct_fresh))?,
+                    Encoding::poly(),
+                )?;
+                assert_eq!(w, &[0u64; 16]);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn rekey_leveled() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(5, 16);
+        let old_sk = SecretKey::random(&params, &mut rng);
+        let new_sk = SecretKey::random(&params, &mut rng);
+        for ciphertext_level in 0..params.max_level() {
+            for key_level in 0..=ciphertext_level {
+                let rk = RekeyingKey::new_leveled(
+                    &old_sk,
+                    &new_sk,
+                    ciphertext_level,
+                    key_level,
+                    &mut rng,
+                )?;
+
+                let v = params.plaintext.random_vec(params.degree(), &mut rng);
+                let pt =
+                    Plaintext::try_encode(&v, Encoding::poly_at_level(ciphertext_level), &params)?;
+                let ct: Ciphertext = old_sk.try_encrypt(&pt, &mut rng)?;
+                let ct_new = rk.rekey(&ct)?;
+                let w = Vec::<u64>::try_decode(&new_sk.try_decrypt(&ct_new)?, Encoding::poly())?;
+                assert_eq!(w, v);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn rekey_stream() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(6, 16);
+        let old_sk = SecretKey::random(&params, &mut rng);
+        let new_sk = SecretKey::random(&params, &mut rng);
+        let rk = RekeyingKey::new(&old_sk, &new_sk, &mut rng)?;
+
+        let values = (0..5)
+            .map(|_| params.plaintext.random_vec(params.degree(), &mut rng))
+            .collect::<Vec<_>>();
+        let archive = values
+            .iter()
+            .map(|v| {
+                let pt = Plaintext::try_encode(v, Encoding::poly(), &params)?;
+                let ct: Ciphertext = old_sk.try_encrypt(&pt, &mut rng)?;
+                Ok(ct.to_bytes())
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let mut rekeyed = Vec::new();
+        let count = rk.rekey_stream(&archive, |bytes| {
+            rekeyed.push(bytes);
+            Ok(())
+        })?;
+        assert_eq!(count, 5);
+        for (bytes, v) in rekeyed.iter().zip(values.iter()) {
+            let ct = Ciphertext::from_bytes(bytes, &params)?;
+            let w = Vec::<u64>::try_decode(&new_sk.try_decrypt(&ct)?, Encoding::poly())?;
+            assert_eq!(&w, v);
+        }
+
+        // Errors from the writer are propagated.
+        let e = rk.rekey_stream(&archive, |_| Err(Error::SerializationError));
+        assert_eq!(e, Err(Error::SerializationError));
+
+        // Invalid serializations are rejected.
+        assert!(rk.rekey_stream([[0u8; 3]], |_| Ok(())).is_err());
+        Ok(())
+    }
+}
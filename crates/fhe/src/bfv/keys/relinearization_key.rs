@@ -1,54 +1,106 @@
 //! Relinearization keys for the BFV encryption scheme
 
 extern crate alloc;
-use alloc::sync::Arc;
+use alloc::format;
 use alloc::string::ToString;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 
 use super::key_switching_key::KeySwitchingKey;
-use crate::bfv::{traits::TryConvertFrom, BfvParameters, Ciphertext, SecretKey};
+#[cfg(not(feature = "server"))]
+use crate::bfv::SecretKey;
+use crate::bfv::{traits::TryConvertFrom, BfvParameters, Ciphertext};
 use crate::proto::bfv::{
     KeySwitchingKey as KeySwitchingKeyProto, RelinearizationKey as RelinearizationKeyProto,
 };
 use crate::{Error, Result};
-use fhe_math::rq::{
-    switcher::Switcher, traits::TryConvertFrom as TryConvertFromPoly, Poly, Representation,
-};
+#[cfg(not(feature = "server"))]
+use fhe_math::rq::{switcher::Switcher, traits::TryConvertFrom as TryConvertFromPoly};
+use fhe_math::rq::{Poly, Representation};
 use fhe_traits::{DeserializeParametrized, FheParametrized, Serialize};
 use prost::Message;
+#[cfg(not(feature = "server"))]
 use rand::{CryptoRng, RngCore};
+#[cfg(not(feature = "server"))]
 use zeroize::Zeroizing;
 
 /// Relinearization key for the BFV encryption scheme.
 /// A relinearization key is a special type of key switching key,
 /// which switch from `s^2` to `s` where `s` is the secret key.
+/// It may also contain key switching keys from the higher powers `s^3`, ...,
+/// `s^k`, in order to relinearize ciphertexts of size up to `k + 1` at once.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct RelinearizationKey {
     pub(crate) ksk: KeySwitchingKey,
+
+    /// The key switching keys from `s^3`, ..., `s^k`.
+    pub(crate) ksk_powers: Vec<KeySwitchingKey>,
 }
 
 impl RelinearizationKey {
     /// Generate a [`RelinearizationKey`] from a [`SecretKey`].
+    #[cfg(not(feature = "server"))]
     pub fn new<R: RngCore + CryptoRng>(sk: &SecretKey, rng: &mut R) -> Result<Self> {
-        Self::new_leveled_internal(sk, 0, 0, rng)
+        Self::new_leveled_internal(sk, 0, 0, 2, rng)
+    }
+
+    /// Generate a [`RelinearizationKey`] from a [`SecretKey`] which
+    /// relinearizes ciphertexts of size up to `max_power + 1`, i.e. which
+    /// switches from `s^2`, ..., `s^max_power`.
+    #[cfg(not(feature = "server"))]
+    pub fn new_up_to_power<R: RngCore + CryptoRng>(
+        sk: &SecretKey,
+        max_power: usize,
+        rng: &mut R,
+    ) -> Result<Self> {
+        Self::new_leveled_internal(sk, 0, 0, max_power, rng)
+    }
+
+    /// Generate a [`RelinearizationKey`] from a [`SecretKey`] using the random
+    /// number generator of the operating system.
+    #[cfg(all(feature = "os-rng", not(feature = "server")))]
+    pub fn new_with_os_rng(sk: &SecretKey) -> Result<Self> {
+        Self::new(sk, &mut rand::rngs::OsRng)
     }
 
     /// Generate a [`RelinearizationKey`] from a [`SecretKey`].
+    #[cfg(not(feature = "server"))]
     pub fn new_leveled<R: RngCore + CryptoRng>(
         sk: &SecretKey,
         ciphertext_level: usize,
         key_level: usize,
         rng: &mut R,
     ) -> Result<Self> {
-        Self::new_leveled_internal(sk, ciphertext_level, key_level, rng)
+        Self::new_leveled_internal(sk, ciphertext_level, key_level, 2, rng)
     }
 
+    /// Generate a leveled [`RelinearizationKey`] from a [`SecretKey`] which
+    /// relinearizes ciphertexts of size up to `max_power + 1`.
+    #[cfg(not(feature = "server"))]
+    pub fn new_leveled_up_to_power<R: RngCore + CryptoRng>(
+        sk: &SecretKey,
+        ciphertext_level: usize,
+        key_level: usize,
+        max_power: usize,
+        rng: &mut R,
+    ) -> Result<Self> {
+        Self::new_leveled_internal(sk, ciphertext_level, key_level, max_power, rng)
+    }
+
+    #[cfg(not(feature = "server"))]
     fn new_leveled_internal<R: RngCore + CryptoRng>(
         sk: &SecretKey,
         ciphertext_level: usize,
         key_level: usize,
+        max_power: usize,
         rng: &mut R,
     ) -> Result<Self> {
+        if max_power < 2 {
+            return Err(Error::DefaultError(
+                "The maximum power should be at least 2".to_string(),
+            ));
+        }
+
         let ctx_relin_key = sk.par.ctx_at_level(key_level)?;
         let ctx_ciphertext = sk.par.ctx_at_level(ciphertext_level)?;
 
@@ -65,12 +117,33 @@ impl RelinearizationKey {
             Representation::PowerBasis,
         )?);
         s.change_representation(Representation::Ntt);
-        let mut s2 = Zeroizing::new(s.as_ref() * s.as_ref());
-        s2.change_representation(Representation::PowerBasis);
         let switcher_up = Switcher::new(ctx_ciphertext, ctx_relin_key)?;
-        let s2_switched_up = Zeroizing::new(s2.mod_switch_to(&switcher_up)?);
-        let ksk = KeySwitchingKey::new(sk, &s2_switched_up, ciphertext_level, key_level, rng)?;
-        Ok(Self { ksk })
+
+        // Generate the key switching keys from s^2, ..., s^max_power.
+        let mut si = Zeroizing::new(s.as_ref().clone());
+        let mut ksks = Vec::with_capacity(max_power - 1);
+        for _ in 2..=max_power {
+            *si.as_mut() *= s.as_ref();
+            let mut si_power_basis = Zeroizing::new(si.as_ref().clone());
+            si_power_basis.change_representation(Representation::PowerBasis);
+            let si_switched_up = Zeroizing::new(si_power_basis.mod_switch_to(&switcher_up)?);
+            ksks.push(KeySwitchingKey::new(
+                sk,
+                &si_switched_up,
+                ciphertext_level,
+                key_level,
+                rng,
+            )?);
+        }
+        let ksk_powers = ksks.split_off(1);
+        let ksk = ksks.pop().unwrap();
+        Ok(Self { ksk, ksk_powers })
+    }
+
+    /// Returns the largest power `k` of the secret key from which this key
+    /// switches, i.e. this key relinearizes ciphertexts of size up to `k + 1`.
+    pub fn max_power(&self) -> usize {
+        2 + self.ksk_powers.len()
     }
 
     /// Relinearize an "extended" ciphertext (c0, c1, c2) into a [`Ciphertext`]
@@ -106,6 +179,64 @@ impl RelinearizationKey {
         }
     }
 
+    /// Relinearize a ciphertext (c0, c1, ..., ck) of any size up to
+    /// [`RelinearizationKey::max_power`] + 1 into a [`Ciphertext`] of size 2,
+    /// so that several ciphertexts can be multiplied before relinearizing
+    /// once. Ciphertexts of size 2 are left unchanged.
+    pub fn relinearize_to_size_2(&self, ct: &mut Ciphertext) -> Result<()> {
+        if ct.len() < 2 {
+            Err(Error::TooFewValues(ct.len(), 2))
+        } else if ct.len() > self.max_power() + 1 {
+            Err(Error::DefaultError(format!(
+                "Relinearizing a ciphertext of size {} requires the powers of the secret key up to s^{}, but the key stops at s^{}",
+                ct.len(),
+                ct.len() - 1,
+                self.max_power()
+            )))
+        } else if ct.level != self.ksk.ciphertext_level {
+            Err(Error::DefaultError(
+                "Ciphertext has incorrect level".to_string(),
+            ))
+        } else {
+            for (i, ksk) in core::iter::once(&self.ksk)
+                .chain(self.ksk_powers.iter())
+                .enumerate()
+                .take(ct.len() - 2)
+            {
+                let mut ci = ct[i + 2].clone();
+                ci.change_representation(Representation::PowerBasis);
+                let (mut c0, mut c1) = ksk.key_switch(&ci)?;
+
+                if c0.ctx() != ct[0].ctx() {
+                    c0.change_representation(Representation::PowerBasis);
+                    c1.change_representation(Representation::PowerBasis);
+                    c0.mod_switch_down_to(ct[0].ctx())?;
+                    c1.mod_switch_down_to(ct[1].ctx())?;
+                    c0.change_representation(Representation::Ntt);
+                    c1.change_representation(Representation::Ntt);
+                }
+
+                ct[0] += &c0;
+                ct[1] += &c1;
+            }
+            ct.truncate(2);
+            Ok(())
+        }
+    }
+
+    /// Truncate the key so that it relinearizes ciphertexts at level `level`,
+    /// by dropping the components and the moduli that are not used at that
+    /// level. This reduces the size of the key and of its serialization.
+    ///
+    /// Returns an error if `level` is smaller than the ciphertext level of the
+    /// key, or if the key level differs from its ciphertext level.
+    pub fn truncate_to_level(&mut self, level: usize) -> Result<()> {
+        self.ksk.truncate_to_level(level)?;
+        self.ksk_powers
+            .iter_mut()
+            .try_for_each(|ksk| ksk.truncate_to_level(level))
+    }
+
     /// Relinearize using polynomials.
     pub(crate) fn relinearizes_poly(&self, c2: &Poly) -> Result<(Poly, Poly)> {
         self.ksk.key_switch(c2)
@@ -116,15 +247,25 @@ impl From<&RelinearizationKey> for RelinearizationKeyProto {
     fn from(value: &RelinearizationKey) -> Self {
         RelinearizationKeyProto {
             ksk: Some(KeySwitchingKeyProto::from(&value.ksk)),
+            ksk_powers: value
+                .ksk_powers
+                .iter()
+                .map(KeySwitchingKeyProto::from)
+                .collect(),
         }
     }
 }
 
 impl TryConvertFrom<&RelinearizationKeyProto> for RelinearizationKey {
     fn try_convert_from(value: &RelinearizationKeyProto, par: &Arc<BfvParameters>) -> Result<Self> {
-        if value.ksk.is_some() {
+        if let Some(ksk) = value.ksk.as_ref() {
             Ok(RelinearizationKey {
-                ksk: KeySwitchingKey::try_convert_from(value.ksk.as_ref().unwrap(), par)?,
+                ksk: KeySwitchingKey::try_convert_from(ksk, par)?,
+                ksk_powers: value
+                    .ksk_powers
+                    .iter()
+                    .map(|ksk| KeySwitchingKey::try_convert_from(ksk, par))
+                    .collect::<Result<Vec<_>>>()?,
             })
         } else {
             Err(Error::DefaultError("Invalid serialization".to_string()))
@@ -155,17 +296,23 @@ impl DeserializeParametrized for RelinearizationKey {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, not(feature = "server")))]
 mod tests {
     use super::RelinearizationKey;
-    use crate::bfv::{traits::TryConvertFrom, BfvParameters, Ciphertext, Encoding, SecretKey};
+    use crate::bfv::{
+        traits::TryConvertFrom, BfvParameters, Ciphertext, Encoding, Plaintext, SecretKey,
+    };
     use crate::proto::bfv::RelinearizationKey as RelinearizationKeyProto;
     use crate::Error;
-    use fhe_math::rq::{traits::TryConvertFrom as TryConvertFromPoly, Poly, Representation};
-    use fhe_traits::{FheDecoder, FheDecrypter};
+    use fhe_math::rq::{
+        traits::TryConvertFrom as TryConvertFromPoly, Distribution, Poly, Representation,
+    };
+    use fhe_traits::{
+        DeserializeParametrized, FheDecoder, FheDecrypter, FheEncoder, FheEncrypter, Serialize,
+    };
     use rand::thread_rng;
     extern crate alloc;
-    
+
     use alloc::vec;
     use alloc::vec::Vec;
 
@@ -192,7 +339,12 @@ mod tests {
                 // c1, c2) encrypting 0.
                 let mut c2 = Poly::random(ctx, Representation::Ntt, &mut rng);
                 let c1 = Poly::random(ctx, Representation::Ntt, &mut rng);
-                let mut c0 = Poly::small(ctx, Representation::PowerBasis, 16, &mut rng)?;
+                let mut c0 = Poly::small(
+                    ctx,
+                    Representation::PowerBasis,
+                    Distribution::Cbd(16),
+                    &mut rng,
+                )?;
                 c0.change_representation(Representation::Ntt);
                 c0 -= &(&c1 * &s);
                 c0 -= &(&c2 * &s2);
@@ -223,6 +375,47 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn truncate_to_level() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(6, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let rk = RelinearizationKey::new_up_to_power(&sk, 3, &mut rng)?;
+        let size = rk.to_bytes().len();
+
+        for level in 1..params.max_level() {
+            let mut truncated = RelinearizationKey::from_bytes(&rk.to_bytes(), &params)?;
+            truncated.truncate_to_level(level)?;
+            assert!(truncated.to_bytes().len() < size);
+            assert_eq!(
+                truncated,
+                RelinearizationKey::from_bytes(&truncated.to_bytes(), &params)?
+            );
+
+            let v = params.plaintext.random_vec(params.degree(), &mut rng);
+            let pt = Plaintext::try_encode(&v, Encoding::simd_at_level(level), &params)?;
+            let ct: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+            let mut ct3 = &(&ct * &ct) * &ct;
+            assert_eq!(ct3.len(), 4);
+            truncated.relinearize_to_size_2(&mut ct3)?;
+            let w = Vec::<u64>::try_decode(&sk.try_decrypt(&ct3)?, Encoding::simd_at_level(level))?;
+            let p = &params.plaintext;
+            let expected = v
+                .iter()
+                .map(|vi| p.mul(*vi, p.mul(*vi, *vi)))
+                .collect::<Vec<_>>();
+            assert_eq!(w, expected);
+
+            assert!(truncated.truncate_to_level(level - 1).is_err());
+        }
+
+        let mut truncated = RelinearizationKey::from_bytes(&rk.to_bytes(), &params)?;
+        assert!(truncated.truncate_to_level(params.max_level()).is_err());
+        let mut leveled = RelinearizationKey::new_leveled(&sk, 1, 0, &mut rng)?;
+        assert!(leveled.truncate_to_level(2).is_err());
+        Ok(())
+    }
+
     #[test]
     fn relinearization_leveled() -> Result<(), Error> {
         let mut rng = thread_rng();
@@ -252,7 +445,12 @@ mod tests {
                         // s^2, c1, c2) encrypting 0.
                         let mut c2 = Poly::random(ctx, Representation::Ntt, &mut rng);
                         let c1 = Poly::random(ctx, Representation::Ntt, &mut rng);
-                        let mut c0 = Poly::small(ctx, Representation::PowerBasis, 16, &mut rng)?;
+                        let mut c0 = Poly::small(
+                            ctx,
+                            Representation::PowerBasis,
+                            Distribution::Cbd(16),
+                            &mut rng,
+                        )?;
                         c0.change_representation(Representation::Ntt);
                         c0 -= &(&c1 * &s);
                         c0 -= &(&c2 * &s2);
@@ -286,6 +484,44 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn relinearize_to_size_2() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(6, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        assert!(RelinearizationKey::new_up_to_power(&sk, 1, &mut rng).is_err());
+        let rk = RelinearizationKey::new_up_to_power(&sk, 4, &mut rng)?;
+        assert_eq!(rk.max_power(), 4);
+        assert_eq!(RelinearizationKey::new(&sk, &mut rng)?.max_power(), 2);
+
+        let v = params.plaintext.random_vec(params.degree(), &mut rng);
+        let pt = Plaintext::try_encode(&v, Encoding::simd(), &params)?;
+        let ct: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+
+        // Multiply three ciphertexts, and relinearize once.
+        let mut ct3 = &(&ct * &ct) * &ct;
+        assert_eq!(ct3.len(), 4);
+        let mut expected = v.clone();
+        params.plaintext.mul_vec(&mut expected, &v);
+        params.plaintext.mul_vec(&mut expected, &v);
+        rk.relinearize_to_size_2(&mut ct3)?;
+        assert_eq!(ct3.len(), 2);
+        let w = Vec::<u64>::try_decode(&sk.try_decrypt(&ct3)?, Encoding::simd())?;
+        assert_eq!(w, expected);
+
+        // Ciphertexts of size 2 are left unchanged.
+        let mut ct2 = ct.clone();
+        rk.relinearize_to_size_2(&mut ct2)?;
+        assert_eq!(ct2, ct);
+
+        // The ciphertext is too large for a key with maximum power 2.
+        let mut ct3 = &(&ct * &ct) * &ct;
+        assert!(RelinearizationKey::new(&sk, &mut rng)?
+            .relinearize_to_size_2(&mut ct3)
+            .is_err());
+        Ok(())
+    }
+
     #[test]
     fn proto_conversion() -> Result<(), Error> {
         let mut rng = thread_rng();
@@ -297,6 +533,9 @@ mod tests {
             let rk = RelinearizationKey::new(&sk, &mut rng)?;
             let proto = RelinearizationKeyProto::from(&rk);
             assert_eq!(rk, RelinearizationKey::try_convert_from(&proto, &params)?);
+            let rk = RelinearizationKey::new_up_to_power(&sk, 3, &mut rng)?;
+            let proto = RelinearizationKeyProto::from(&rk);
+            assert_eq!(rk, RelinearizationKey::try_convert_from(&proto, &params)?);
         }
         Ok(())
     }
@@ -3,22 +3,21 @@
 use crate::bfv::{BfvParameters, Ciphertext, Plaintext};
 use crate::{Error, Result};
 use fhe_math::{
-    rq::{traits::TryConvertFrom, Poly, Representation},
+    rq::{traits::TryConvertFrom, Distribution, Poly, Representation},
     zq::Modulus,
 };
 use fhe_traits::{DeserializeParametrized, FheDecrypter, FheEncrypter, FheParametrized, Serialize};
-use fhe_util::sample_vec_cbd;
 use itertools::Itertools;
-use num_bigint::BigUint;
-use rand::{Rng, RngCore, SeedableRng};
+use rand::{CryptoRng, Rng, RngCore, SeedableRng};
 use rand_chacha::ChaCha8Rng;
+use smallvec::smallvec;
 extern crate alloc;
 use alloc::borrow::ToOwned;
 use alloc::boxed::Box;
 use alloc::string::ToString;
 use alloc::sync::Arc;
-use alloc::vec;
 use alloc::vec::Vec;
+use core::fmt::Debug;
 use zeroize::Zeroizing;
 use zeroize_derive::{Zeroize, ZeroizeOnDrop};
 
@@ -30,13 +29,131 @@ pub struct SecretKey {
     pub(crate) coeffs: Box<[i64]>,
 }
 
+/// Intermediate values of a decryption, returned by
+/// [`SecretKey::decrypt_with_proof_data`] for external proof systems.
+///
+/// The phase of a ciphertext `(c0, c1, ...)` is `c0 + c1*s + c2*s^2 + ...`,
+/// and equals `delta * m + e` where `delta * m` is the scaled plaintext and `e`
+/// is the noise; the plaintext is `m = round(t * phase / q) mod t`. The
+/// polynomials are in PowerBasis representation, at the level of the
+/// ciphertext, and are zeroized when dropped. They are redacted from the Debug
+/// output, and are not comparable, so as not to leak them through the timing
+/// of a comparison.
+#[derive(Clone)]
+pub struct DecryptionProofData {
+    phase: Zeroizing<Poly>,
+    noise: Zeroizing<Poly>,
+}
+
+impl Debug for DecryptionProofData {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DecryptionProofData")
+            .finish_non_exhaustive()
+    }
+}
+
+impl DecryptionProofData {
+    /// Returns the phase `c0 + c1*s + c2*s^2 + ...` of the ciphertext, before
+    /// rounding.
+    pub fn phase(&self) -> &Poly {
+        &self.phase
+    }
+
+    /// Returns the noise `e`, i.e. the phase minus the scaled plaintext.
+    pub fn noise(&self) -> &Poly {
+        &self.noise
+    }
+}
+
 impl SecretKey {
     /// Generate a random [`SecretKey`].
-    pub fn random<R: RngCore>(par: &Arc<BfvParameters>, rng: &mut R) -> Self {
-        let s_coefficients = sample_vec_cbd(par.degree(), par.variance, rng).unwrap();
+    pub fn random<R: RngCore + CryptoRng>(par: &Arc<BfvParameters>, rng: &mut R) -> Self {
+        let s_coefficients = Distribution::Cbd(par.variance)
+            .sample_vec(par.degree(), rng)
+            .unwrap();
         Self::new(s_coefficients, par)
     }
 
+    /// Generate a random [`SecretKey`] using the random number generator of
+    /// the operating system.
+    #[cfg(feature = "os-rng")]
+    pub fn random_with_os_rng(par: &Arc<BfvParameters>) -> Self {
+        Self::random(par, &mut rand::rngs::OsRng)
+    }
+
+    /// Encrypt a [`Plaintext`] deterministically: the polynomial `a` and the
+    /// error are both derived from `seed`, so that the ciphertext is
+    /// reproducible byte for byte. This is only meant for test vectors and
+    /// conformance tests; encrypting with a fixed seed is insecure.
+    #[cfg(feature = "testing")]
+    pub fn try_encrypt_deterministic(
+        &self,
+        pt: &Plaintext,
+        seed: <ChaCha8Rng as SeedableRng>::Seed,
+    ) -> Result<Ciphertext> {
+        self.reproduce_encryption(pt, seed)
+    }
+
+    /// Encrypt a [`Plaintext`] directly at level `level`, whatever the level
+    /// of the plaintext, e.g. when the ciphertext would be switched to this
+    /// level right away: the ciphertext is smaller, and has the noise of a
+    /// fresh encryption rather than the noise of a modulus switching.
+    ///
+    /// Returns an error if the parameters do not have this level.
+    pub fn try_encrypt_at_level<R: RngCore + CryptoRng>(
+        &self,
+        pt: &Plaintext,
+        level: usize,
+        rng: &mut R,
+    ) -> Result<Ciphertext> {
+        self.try_encrypt(&pt.to_level(level)?, rng)
+    }
+
+    /// Encrypt a [`Plaintext`] reproducibly: a fresh seed is drawn from `rng`,
+    /// and the polynomial `a` and the error are both derived from a ChaCha8
+    /// stream initialized with this seed. The seed is returned alongside the
+    /// ciphertext, so that it can be recorded and the encryption later
+    /// reproduced with [`SecretKey::reproduce_encryption`] or checked with
+    /// [`SecretKey::verify_encryption`] to audit the ciphertext.
+    ///
+    /// The seed reveals the error of the ciphertext: it must be kept as secret
+    /// as the plaintext, and never be reused. This is only meant for audits in
+    /// testing environments.
+    #[cfg(feature = "testing")]
+    pub fn try_encrypt_with_seed<R: RngCore + CryptoRng>(
+        &self,
+        pt: &Plaintext,
+        rng: &mut R,
+    ) -> Result<(Ciphertext, <ChaCha8Rng as SeedableRng>::Seed)> {
+        let mut seed = <ChaCha8Rng as SeedableRng>::Seed::default();
+        rng.fill(&mut seed);
+        let ct = self.reproduce_encryption(pt, seed)?;
+        Ok((ct, seed))
+    }
+
+    /// Reproduce the encryption of a [`Plaintext`] by
+    /// [`SecretKey::try_encrypt_with_seed`] from the recorded seed.
+    #[cfg(feature = "testing")]
+    pub fn reproduce_encryption(
+        &self,
+        pt: &Plaintext,
+        seed: <ChaCha8Rng as SeedableRng>::Seed,
+    ) -> Result<Ciphertext> {
+        self.try_encrypt(pt, &mut ChaCha8Rng::from_seed(seed))
+    }
+
+    /// Verify that `ct` is the encryption of `pt` by
+    /// [`SecretKey::try_encrypt_with_seed`] with the recorded seed `seed`.
+    #[cfg(feature = "testing")]
+    pub fn verify_encryption(
+        &self,
+        ct: &Ciphertext,
+        pt: &Plaintext,
+        seed: <ChaCha8Rng as SeedableRng>::Seed,
+    ) -> Result<bool> {
+        Ok(&self.reproduce_encryption(pt, seed)? == ct)
+    }
+
     /// Generate a [`SecretKey`] from its coefficients.
     pub(crate) fn new(coeffs: Vec<i64>, par: &Arc<BfvParameters>) -> Self {
         Self {
@@ -53,7 +170,7 @@ impl SecretKey {
     /// noise.
     pub unsafe fn measure_noise(&self, ct: &Ciphertext) -> Result<usize> {
         let plaintext = Zeroizing::new(self.try_decrypt(ct)?);
-        let m = Zeroizing::new(plaintext.to_poly());
+        let m = plaintext.to_poly();
 
         // Let's create a secret key with the ciphertext context
         let mut s = Zeroizing::new(Poly::try_convert_from(
@@ -76,22 +193,24 @@ impl SecretKey {
             *c.as_mut() += &cis;
             *si.as_mut() *= s.as_ref();
         }
-        *c.as_mut() -= &m;
+        *c.as_mut() -= m;
         c.change_representation(Representation::PowerBasis);
 
-        let ciphertext_modulus = ct[0].ctx().modulus();
-        let mut noise = 0usize;
-        for coeff in Vec::<BigUint>::from(c.as_ref()) {
-            noise = core::cmp::max(
-                noise,
-                core::cmp::min(coeff.bits(), (ciphertext_modulus - &coeff).bits()) as usize,
-            )
-        }
+        let noise = c
+            .to_signed_coefficients()?
+            .iter()
+            .map(|coeff| coeff.bits() as usize)
+            .max()
+            .unwrap_or_default();
 
         Ok(noise)
     }
 
-    pub(crate) fn encrypt_poly<R: RngCore>(&self, p: &Poly, rng: &mut R) -> Result<Ciphertext> {
+    pub(crate) fn encrypt_poly<R: RngCore + CryptoRng>(
+        &self,
+        p: &Poly,
+        rng: &mut R,
+    ) -> Result<Ciphertext> {
         assert_eq!(p.representation(), &Representation::Ntt);
 
         let level = self.par.level_of_ctx(p.ctx())?;
@@ -111,8 +230,13 @@ impl SecretKey {
         let mut a = Poly::random_from_seed(p.ctx(), Representation::Ntt, seed);
         let a_s = Zeroizing::new(&a * s.as_ref());
 
-        let mut b = Poly::small(p.ctx(), Representation::Ntt, self.par.variance, rng)
-            .map_err(Error::MathError)?;
+        let mut b = Poly::small(
+            p.ctx(),
+            Representation::Ntt,
+            Distribution::Cbd(self.par.variance),
+            rng,
+        )
+        .map_err(Error::MathError)?;
         b -= &a_s;
         b += p;
 
@@ -125,10 +249,110 @@ impl SecretKey {
         Ok(Ciphertext {
             par: self.par.clone(),
             seed: Some(seed),
-            c: vec![b, a],
+            c: smallvec![b, a],
             level,
+            tag: Vec::new(),
+            noise_estimate: None,
         })
     }
+
+    /// Decrypt a [`Ciphertext`] and return the intermediate values of the
+    /// decryption along with the plaintext, so that an external proof system
+    /// can attest that the plaintext is the decryption of the ciphertext, e.g.
+    /// in audited decryption ceremonies.
+    ///
+    /// The data reveals the noise of the ciphertext, which leaks information
+    /// about the secret key: it must only be given to the proof system.
+    pub fn decrypt_with_proof_data(
+        &self,
+        ct: &Ciphertext,
+    ) -> Result<(Plaintext, DecryptionProofData)> {
+        let phase = self.phase(ct)?;
+        let pt = self.decrypt_phase(&phase, ct)?;
+        let mut noise = Zeroizing::new(pt.to_poly().clone());
+        noise.change_representation(Representation::PowerBasis);
+        *noise.as_mut() = phase.as_ref() - noise.as_ref();
+        Ok((pt, DecryptionProofData { phase, noise }))
+    }
+
+    /// Computes the phase `c0 + c1*s + c2*s^2 + ...` of a ciphertext, in
+    /// PowerBasis representation.
+    fn phase(&self, ct: &Ciphertext) -> Result<Zeroizing<Poly>> {
+        if self.par != ct.par {
+            return Err(Error::DefaultError(
+                "Incompatible BFV parameters".to_string(),
+            ));
+        }
+
+        // Let's create a secret key with the ciphertext context
+        let mut s = Zeroizing::new(Poly::try_convert_from(
+            self.coeffs.as_ref(),
+            ct[0].ctx(),
+            false,
+            Representation::PowerBasis,
+        )?);
+        s.change_representation(Representation::Ntt);
+        let mut si = s.clone();
+
+        let mut c = Zeroizing::new(ct[0].clone());
+        c.disallow_variable_time_computations();
+
+        // Compute the phase c0 + c1*s + c2*s^2 + ... where the secret power
+        // s^k is computed on-the-fly
+        for i in 1..ct.len() {
+            let mut cis = Zeroizing::new(ct[i].clone());
+            cis.disallow_variable_time_computations();
+            *cis.as_mut() *= si.as_ref();
+            *c.as_mut() += &cis;
+            if i + 1 < ct.len() {
+                *si.as_mut() *= s.as_ref();
+            }
+        }
+        c.change_representation(Representation::PowerBasis);
+        Ok(c)
+    }
+
+    /// Scales the phase of a ciphertext to the plaintext.
+    fn decrypt_phase(&self, c: &Poly, ct: &Ciphertext) -> Result<Plaintext> {
+        // The ciphertexts switched to a single modulus, e.g. to compress
+        // them before sending them back, are scaled without the RNS scaler.
+        let w = if ct.level == self.par.max_level() {
+            scale_single_modulus(c, &self.par.plaintext)
+        } else {
+            Self::scale(&self.par, c, ct.level)?
+        };
+
+        let mut poly = Poly::try_convert_from(&w, ct[0].ctx(), false, Representation::PowerBasis)?;
+        poly.change_representation(Representation::Ntt);
+
+        Ok(Plaintext {
+            par: self.par.clone(),
+            value: w.into_boxed_slice(),
+            encoding: None,
+            poly_ntt: poly,
+            level: ct.level,
+            scaled_poly: Default::default(),
+        })
+    }
+
+    /// Scales the phase `c` of a ciphertext at the level `level` by `t / q`,
+    /// and reduces it modulo the plaintext modulus `t`.
+    pub(crate) fn scale(par: &BfvParameters, c: &Poly, level: usize) -> Result<Vec<u64>> {
+        let d = Zeroizing::new(c.scale(&par.scalers[level])?);
+
+        // TODO: Can we handle plaintext moduli that are BigUint?
+        let v = Zeroizing::new(
+            Vec::<u64>::from(d.as_ref())
+                .iter_mut()
+                .map(|vi| *vi + *par.plaintext)
+                .collect_vec(),
+        );
+        let mut w = v[..par.degree()].to_vec();
+        let q = Modulus::new(par.moduli[0]).map_err(Error::MathError)?;
+        q.reduce_vec(&mut w);
+        par.plaintext.reduce_vec(&mut w);
+        Ok(w)
+    }
 }
 
 impl FheParametrized for SecretKey {
@@ -199,10 +423,13 @@ impl DeserializeParametrized for SecretKey {
 impl FheEncrypter<Plaintext, Ciphertext> for SecretKey {
     type Error = Error;
 
-    fn try_encrypt<R: RngCore>(&self, pt: &Plaintext, rng: &mut R) -> Result<Ciphertext> {
+    fn try_encrypt<R: RngCore + CryptoRng>(
+        &self,
+        pt: &Plaintext,
+        rng: &mut R,
+    ) -> Result<Ciphertext> {
         assert_eq!(self.par, pt.par);
-        let m = Zeroizing::new(pt.to_poly());
-        self.encrypt_poly(m.as_ref(), rng)
+        self.encrypt_poly(pt.to_poly(), rng)
     }
 }
 
@@ -210,75 +437,97 @@ impl FheDecrypter<Plaintext, Ciphertext> for SecretKey {
     type Error = Error;
 
     fn try_decrypt(&self, ct: &Ciphertext) -> Result<Plaintext> {
-        if self.par != ct.par {
-            Err(Error::DefaultError(
-                "Incompatible BFV parameters".to_string(),
-            ))
-        } else {
-            // Let's create a secret key with the ciphertext context
-            let mut s = Zeroizing::new(Poly::try_convert_from(
-                self.coeffs.as_ref(),
-                ct[0].ctx(),
-                false,
-                Representation::PowerBasis,
-            )?);
-            s.change_representation(Representation::Ntt);
-            let mut si = s.clone();
-
-            let mut c = Zeroizing::new(ct[0].clone());
-            c.disallow_variable_time_computations();
-
-            // Compute the phase c0 + c1*s + c2*s^2 + ... where the secret power
-            // s^k is computed on-the-fly
-            for i in 1..ct.len() {
-                let mut cis = Zeroizing::new(ct[i].clone());
-                cis.disallow_variable_time_computations();
-                *cis.as_mut() *= si.as_ref();
-                *c.as_mut() += &cis;
-                if i + 1 < ct.len() {
-                    *si.as_mut() *= s.as_ref();
-                }
-            }
-            c.change_representation(Representation::PowerBasis);
-
-            let d = Zeroizing::new(c.scale(&self.par.scalers[ct.level])?);
+        let phase = self.phase(ct)?;
+        self.decrypt_phase(&phase, ct)
+    }
+}
 
-            // TODO: Can we handle plaintext moduli that are BigUint?
-            let v = Zeroizing::new(
-                Vec::<u64>::from(d.as_ref())
-                    .iter_mut()
-                    .map(|vi| *vi + *self.par.plaintext)
-                    .collect_vec(),
-            );
-            let mut w = v[..self.par.degree()].to_vec();
-            let q = Modulus::new(self.par.moduli[0]).map_err(Error::MathError)?;
-            q.reduce_vec(&mut w);
-            self.par.plaintext.reduce_vec(&mut w);
-
-            let mut poly =
-                Poly::try_convert_from(&w, ct[0].ctx(), false, Representation::PowerBasis)?;
-            poly.change_representation(Representation::Ntt);
-
-            let pt = Plaintext {
-                par: self.par.clone(),
-                value: w.into_boxed_slice(),
-                encoding: None,
-                poly_ntt: poly,
-                level: ct.level,
-            };
-
-            Ok(pt)
-        }
+/// Scales the coefficients `x` of a polynomial modulo a single prime `q`, in
+/// PowerBasis representation, to `round(t * x / q) mod t` in constant time.
+///
+/// The rounded quotient `(t * x + floor(q / 2) - r) / q`, where `r` is the
+/// remainder of `t * x + floor(q / 2)` modulo `q`, is an exact division, and is
+/// at most `t`; it is therefore computed modulo 2^64 by a multiplication by
+/// the inverse of `q` modulo 2^64.
+fn scale_single_modulus(c: &Poly, t: &Modulus) -> Vec<u64> {
+    let q = &c.ctx().moduli_operators()[0];
+    // Newton iteration, doubling the number of correct bits of the inverse of
+    // the odd modulus `q`, starting from 3 correct bits.
+    let mut q_inv = **q;
+    for _ in 0..5 {
+        q_inv = q_inv.wrapping_mul(2u64.wrapping_sub(q_inv.wrapping_mul(**q)));
     }
+    let half_q = (**q >> 1) as u128;
+    c.coefficients()
+        .row(0)
+        .iter()
+        .map(|x| {
+            let a = (**t as u128) * (*x as u128) + half_q;
+            let quotient = (a as u64)
+                .wrapping_sub(q.reduce_u128(a))
+                .wrapping_mul(q_inv);
+            t.reduce(quotient)
+        })
+        .collect_vec()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::SecretKey;
-    use crate::bfv::{parameters::BfvParameters, Encoding, Plaintext};
+    use super::{scale_single_modulus, SecretKey};
+    use crate::bfv::{parameters::BfvParameters, Ciphertext, Encoding, Plaintext};
     use crate::Error;
-    use fhe_traits::{FheDecrypter, FheEncoder, FheEncrypter};
+    use fhe_math::rq::{Poly, Representation};
+    use fhe_traits::{FheDecoder, FheDecrypter, FheEncoder, FheEncrypter};
     use rand::thread_rng;
+    extern crate alloc;
+    use alloc::format;
+    use alloc::vec::Vec;
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn encrypt_deterministic() -> Result<(), Error> {
+        use fhe_traits::Serialize;
+
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(2, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let v = params.plaintext.random_vec(params.degree(), &mut rng);
+        let pt = Plaintext::try_encode(&v, Encoding::poly(), &params)?;
+
+        let ct1 = sk.try_encrypt_deterministic(&pt, [1u8; 32])?;
+        let ct2 = sk.try_encrypt_deterministic(&pt, [1u8; 32])?;
+        let ct3 = sk.try_encrypt_deterministic(&pt, [2u8; 32])?;
+        assert_eq!(ct1.to_bytes(), ct2.to_bytes());
+        assert_ne!(ct1.to_bytes(), ct3.to_bytes());
+        assert_eq!(sk.try_decrypt(&ct1)?, pt);
+        Ok(())
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn encrypt_with_seed() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(2, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let v = params.plaintext.random_vec(params.degree(), &mut rng);
+        let pt = Plaintext::try_encode(&v, Encoding::simd(), &params)?;
+        let other = Plaintext::try_encode(&[1u64], Encoding::poly(), &params)?;
+
+        let (ct, seed) = sk.try_encrypt_with_seed(&pt, &mut rng)?;
+        assert_eq!(sk.try_decrypt(&ct)?, pt);
+        assert_eq!(sk.reproduce_encryption(&pt, seed)?, ct);
+        assert!(sk.verify_encryption(&ct, &pt, seed)?);
+        assert!(!sk.verify_encryption(&ct, &other, seed)?);
+
+        let (ct2, seed2) = sk.try_encrypt_with_seed(&pt, &mut rng)?;
+        assert_ne!(seed, seed2);
+        assert_ne!(ct, ct2);
+        assert!(!sk.verify_encryption(&ct2, &pt, seed)?);
+
+        let other_sk = SecretKey::random(&params, &mut rng);
+        assert!(!other_sk.verify_encryption(&ct, &pt, seed)?);
+        Ok(())
+    }
 
     #[test]
     fn keygen() {
@@ -300,7 +549,7 @@ mod tests {
             BfvParameters::default_arc(1, 16),
             BfvParameters::default_arc(6, 16),
         ] {
-            for level in 0..params.max_level() {
+            for level in 0..=params.max_level() {
                 for _ in 0..20 {
                     let sk = SecretKey::random(&params, &mut rng);
 
@@ -320,4 +569,120 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn single_modulus_scaling() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        for params in [
+            BfvParameters::default_arc(1, 16),
+            BfvParameters::default_arc(3, 16),
+        ] {
+            let level = params.max_level();
+            let ctx = params.ctx_at_level(level)?;
+            for _ in 0..20 {
+                let c = Poly::random(ctx, Representation::PowerBasis, &mut rng);
+                assert_eq!(
+                    scale_single_modulus(&c, &params.plaintext),
+                    SecretKey::scale(&params, &c, level)?
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn measure_noise() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(6, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let pt = Plaintext::try_encode(
+            &params.plaintext.random_vec(params.degree(), &mut rng),
+            Encoding::poly(),
+            &params,
+        )?;
+        let ct: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+        let noise = unsafe { sk.measure_noise(&ct)? };
+        assert!(noise > 0 && noise <= 8);
+
+        // The noise of a sum is at most one bit larger.
+        let noise_sum = unsafe { sk.measure_noise(&(&ct + &ct))? };
+        assert!(noise_sum <= noise + 1);
+        Ok(())
+    }
+
+    #[test]
+    fn encrypt_at_level() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(3, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let v = params.plaintext.random_vec(params.degree(), &mut rng);
+        let pt = Plaintext::try_encode(&v, Encoding::poly_at_level(1), &params)?;
+        for level in 0..=params.max_level() {
+            let ct = sk.try_encrypt_at_level(&pt, level, &mut rng)?;
+            assert_eq!(ct.level, level);
+            assert_eq!(
+                Vec::<u64>::try_decode(&sk.try_decrypt(&ct)?, Encoding::poly())?,
+                v
+            );
+        }
+        assert!(sk.try_encrypt_at_level(&pt, 3, &mut rng).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn decrypt_with_proof_data() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(3, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        for level in 0..=params.max_level() {
+            let pt = Plaintext::try_encode(
+                &params.plaintext.random_vec(params.degree(), &mut rng),
+                Encoding::poly_at_level(level),
+                &params,
+            )?;
+            let ct: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+            let (pt2, data) = sk.decrypt_with_proof_data(&ct)?;
+            assert_eq!(pt2, pt);
+            assert_eq!(format!("{data:?}"), "DecryptionProofData { .. }");
+
+            // The phase is the scaled plaintext plus the noise.
+            let mut m = pt.to_poly().clone();
+            m.change_representation(Representation::PowerBasis);
+            assert_eq!(
+                (data.phase() - data.noise()).coefficients(),
+                m.coefficients()
+            );
+            let noise = data
+                .noise()
+                .to_signed_coefficients()?
+                .iter()
+                .map(|coeff| coeff.bits() as usize)
+                .max()
+                .unwrap_or_default();
+            assert_eq!(noise, unsafe { sk.measure_noise(&ct)? });
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "os-rng")]
+    #[test]
+    fn os_rng() -> Result<(), Error> {
+        use crate::bfv::{PublicKey, RelinearizationKey};
+
+        let params = BfvParameters::default_arc(2, 16);
+        let sk = SecretKey::random_with_os_rng(&params);
+        let pk = PublicKey::new_with_os_rng(&sk);
+        let _rk = RelinearizationKey::new_with_os_rng(&sk)?;
+
+        let pt = Plaintext::try_encode(
+            &params
+                .plaintext
+                .random_vec(params.degree(), &mut rand::rngs::OsRng),
+            Encoding::poly(),
+            &params,
+        )?;
+        let ct = pk.try_encrypt(&pt, &mut rand::rngs::OsRng)?;
+        assert_eq!(sk.try_decrypt(&ct)?, pt);
+        Ok(())
+    }
 }
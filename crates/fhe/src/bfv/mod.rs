@@ -2,22 +2,93 @@
 
 //! The Brakerski-Fan-Vercauteren homomorphic encryption scheme
 
+mod aggregation;
+#[cfg(not(feature = "client"))]
+mod bigint;
+#[cfg(not(feature = "client"))]
+mod bytes;
 mod ciphertext;
+#[cfg(not(feature = "client"))]
+mod crt;
 mod encoding;
+#[cfg(not(feature = "client"))]
+mod evaluator;
+mod footprint;
+#[cfg(not(feature = "client"))]
+mod integrity;
 mod keys;
+#[cfg(not(feature = "client"))]
 mod ops;
 mod parameters;
 mod plaintext;
 mod plaintext_vec;
+#[cfg(not(feature = "client"))]
+mod rational;
+mod redundancy;
+#[cfg(not(feature = "client"))]
 mod rgsw_ciphertext;
+#[cfg(all(feature = "mmap", unix))]
+mod store;
+#[cfg(not(feature = "client"))]
+mod tensor;
+#[cfg(not(feature = "client"))]
+mod typed;
 
+#[cfg(all(feature = "kat", not(any(feature = "client", feature = "server"))))]
+pub mod kat;
 pub mod traits;
+#[cfg(not(feature = "client"))]
+pub mod transcipher;
+pub use aggregation::AggregationProfile;
+#[cfg(not(feature = "client"))]
+pub use bigint::BigIntEncoder;
+#[cfg(not(any(feature = "client", feature = "server")))]
+pub use bytes::decrypt_bytes;
+#[cfg(not(feature = "client"))]
+pub use bytes::{encrypt_bytes, ByteLayout};
 pub use ciphertext::Ciphertext;
-pub use encoding::Encoding;
+#[cfg(not(feature = "client"))]
+pub(crate) use ciphertext::CiphertextElements;
+#[cfg(not(any(feature = "client", feature = "server")))]
+pub use crt::CrtSecretKey;
+#[cfg(not(feature = "client"))]
+pub use crt::{CrtCiphertext, CrtEvaluator, CrtParameters};
+pub use encoding::{EncodeOptions, Encoding, OutOfRange};
+#[cfg(not(feature = "client"))]
+pub use evaluator::{
+    Evaluator, EvaluatorCheckpoint, KeyProvider, ModSwitchPolicy, NoiseTable, Operation,
+    OperationCounts, ProvenanceNode, ProvenanceRecorder, SlotMultiplexer,
+};
+pub use footprint::MemoryFootprint;
+#[cfg(not(feature = "client"))]
+pub use integrity::IntegrityKey;
+#[cfg(not(any(feature = "client", feature = "server")))]
+pub use keys::EvaluationKeyBuilder;
+#[cfg(not(any(feature = "client", feature = "server")))]
 pub(crate) use keys::KeySwitchingKey;
-pub use keys::{EvaluationKey, EvaluationKeyBuilder, PublicKey, RelinearizationKey, SecretKey};
-pub use ops::{dot_product_scalar, Multiplicator};
+#[cfg(not(any(feature = "client", feature = "server")))]
+pub use keys::{DecryptionProofData, SecretKey};
+pub use keys::{EncryptionRandomness, PublicKey};
+#[cfg(not(feature = "client"))]
+pub use keys::{EvaluationKey, GaloisKey, KeyPackage, RekeyingKey, RelinearizationKey};
+#[cfg(not(feature = "client"))]
+pub use ops::{
+    aggregate_then_multiply, apply_lut, argmax, bloom_filter_contains, conjugate,
+    dot_product_scalar, extract_slot, filtered_sum, group_by_sum, mean, prefix_sum, replicate,
+    string_equals, variance, BloomFilter, CiphertextStream, EncodedBloomFilter, EncryptedStatistic,
+    LookupTable, LutCache, LutReport, Multiplicator, RotFold, StringEncoder,
+};
 pub use parameters::{BfvParameters, BfvParametersBuilder};
 pub use plaintext::Plaintext;
 pub use plaintext_vec::PlaintextVec;
+#[cfg(not(feature = "client"))]
+pub use rational::EncryptedRational;
+pub use redundancy::Redundancy;
+#[cfg(not(feature = "client"))]
 pub use rgsw_ciphertext::RGSWCiphertext;
+#[cfg(all(feature = "mmap", unix))]
+pub use store::CiphertextStore;
+#[cfg(not(feature = "client"))]
+pub use tensor::EncryptedTensor;
+#[cfg(not(feature = "client"))]
+pub use typed::{ParamsHandle, Typed, TypedParameters};
@@ -0,0 +1,229 @@
+//! Database-style aggregation operators over encrypted columns.
+
+use crate::bfv::{Ciphertext, Multiplicator};
+use crate::{Error, Result};
+extern crate alloc;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+/// Sums a vector of ciphertexts using a balanced binary tree of additions.
+fn sum_tree(mut cts: Vec<Ciphertext>) -> Result<Ciphertext> {
+    if cts.is_empty() {
+        return Err(Error::TooFewValues(0, 1));
+    }
+    while cts.len() > 1 {
+        let mut next = Vec::with_capacity(cts.len().div_ceil(2));
+        let mut iter = cts.into_iter();
+        while let Some(mut lhs) = iter.next() {
+            if let Some(rhs) = iter.next() {
+                lhs += &rhs;
+            }
+            next.push(lhs);
+        }
+        cts = next;
+    }
+    Ok(cts.pop().unwrap())
+}
+
+/// Computes the slot-wise sum of the `values` ciphertexts whose corresponding
+/// `predicates` ciphertext encrypts 1, i.e. `sum_i values[i] * predicates[i]`.
+///
+/// The predicates must encrypt 0 or 1 in every slot; other values are not
+/// rejected (this cannot be checked homomorphically), and would weight the
+/// corresponding values instead of selecting them.
+///
+/// Depth and noise: this consumes one multiplicative level. Each product is
+/// computed using `multiplicator`, and the products are summed with a balanced
+/// tree of additions, so that the output noise is the noise of a single
+/// multiplication increased by an additive factor of `values.len()`.
+pub fn filtered_sum(
+    values: &[Ciphertext],
+    predicates: &[Ciphertext],
+    multiplicator: &Multiplicator,
+) -> Result<Ciphertext> {
+    if values.len() != predicates.len() {
+        return Err(Error::DefaultError(
+            "Mismatched number of values and predicates".to_string(),
+        ));
+    }
+    let products = values
+        .iter()
+        .zip(predicates.iter())
+        .map(|(v, p)| multiplicator.multiply(v, p))
+        .collect::<Result<Vec<_>>>()?;
+    sum_tree(products)
+}
+
+/// Computes the slot-wise sum of the `values` ciphertexts for every group:
+/// `selectors[g][i]` must encrypt 1 if the i-th value belongs to the group `g`
+/// and 0 otherwise. Returns one ciphertext per group.
+///
+/// Depth and noise: this consumes one multiplicative level, and each output
+/// has the same noise as the output of [`filtered_sum`]. The cost is
+/// `selectors.len() * values.len()` multiplications.
+pub fn group_by_sum(
+    values: &[Ciphertext],
+    selectors: &[Vec<Ciphertext>],
+    multiplicator: &Multiplicator,
+) -> Result<Vec<Ciphertext>> {
+    selectors
+        .iter()
+        .map(|selector| filtered_sum(values, selector, multiplicator))
+        .collect()
+}
+
+/// Computes the slot-wise sum of the `summands` ciphertexts, multiplied by the
+/// ciphertext `factor`, e.g. to weight an aggregate, with parameters selected
+/// by [`AggregationProfile::parameters`](crate::bfv::AggregationProfile).
+///
+/// The product is not relinearized, so that the computation requires no
+/// evaluation key and no modulus switching: the output has 3 polynomials, and
+/// is meant to be decrypted rather than multiplied further.
+///
+/// Depth and noise: this consumes one multiplicative level. The summands are
+/// added with a balanced tree of additions, which grows the noise by one bit
+/// per doubling of the number of summands, before the multiplication.
+pub fn aggregate_then_multiply(summands: &[Ciphertext], factor: &Ciphertext) -> Result<Ciphertext> {
+    if summands
+        .iter()
+        .any(|ct| ct.par != factor.par || ct.level != factor.level)
+    {
+        return Err(Error::DefaultError(
+            "Mismatched parameters or levels".to_string(),
+        ));
+    }
+    let sum = sum_tree(summands.to_vec())?;
+    Ok(&sum * factor)
+}
+
+#[cfg(all(test, not(feature = "server")))]
+mod tests {
+    use super::{aggregate_then_multiply, filtered_sum, group_by_sum};
+    use crate::bfv::{
+        AggregationProfile, BfvParameters, Ciphertext, Encoding, Multiplicator, Plaintext,
+        PublicKey, RelinearizationKey, SecretKey,
+    };
+    use crate::Error;
+    use fhe_traits::{FheDecoder, FheDecrypter, FheEncoder, FheEncrypter};
+    use itertools::Itertools;
+    use rand::{thread_rng, Rng};
+    extern crate alloc;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn filtered_and_grouped_sums() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(3, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let rk = RelinearizationKey::new(&sk, &mut rng)?;
+        let multiplicator = Multiplicator::default(&rk)?;
+        let num_values = 5;
+        let num_groups = 3;
+
+        let encrypt = |v: &[u64]| -> Result<Ciphertext, Error> {
+            let pt = Plaintext::try_encode(v, Encoding::simd(), &params)?;
+            sk.try_encrypt(&pt, &mut thread_rng())
+        };
+
+        let values = (0..num_values)
+            .map(|_| params.plaintext.random_vec(params.degree(), &mut rng))
+            .collect_vec();
+        // Assign every slot of every value to a random group.
+        let groups = (0..num_values)
+            .map(|_| {
+                (0..params.degree())
+                    .map(|_| rng.gen_range(0..num_groups))
+                    .collect_vec()
+            })
+            .collect_vec();
+        let selectors = (0..num_groups)
+            .map(|g| {
+                groups
+                    .iter()
+                    .map(|group| group.iter().map(|gi| (*gi == g) as u64).collect_vec())
+                    .collect_vec()
+            })
+            .collect_vec();
+
+        // Cleartext reference.
+        let expected = selectors
+            .iter()
+            .map(|selector| {
+                let mut sum = vec![0u64; params.degree()];
+                for (v, s) in values.iter().zip(selector.iter()) {
+                    let mut w = v.clone();
+                    params.plaintext.mul_vec(&mut w, s);
+                    params.plaintext.add_vec(&mut sum, &w);
+                }
+                sum
+            })
+            .collect_vec();
+
+        let ct_values = values
+            .iter()
+            .map(|v| encrypt(v))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let ct_selectors = selectors
+            .iter()
+            .map(|selector| {
+                selector
+                    .iter()
+                    .map(|s| encrypt(s))
+                    .collect::<Result<Vec<_>, Error>>()
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let ct = filtered_sum(&ct_values, &ct_selectors[0], &multiplicator)?;
+        let pt = sk.try_decrypt(&ct)?;
+        assert_eq!(Vec::<u64>::try_decode(&pt, Encoding::simd())?, expected[0]);
+
+        let cts = group_by_sum(&ct_values, &ct_selectors, &multiplicator)?;
+        assert_eq!(cts.len(), num_groups);
+        for (ct, e) in cts.iter().zip(expected.iter()) {
+            let pt = sk.try_decrypt(ct)?;
+            assert_eq!(&Vec::<u64>::try_decode(&pt, Encoding::simd())?, e);
+        }
+
+        assert!(filtered_sum(&ct_values, &ct_selectors[0][1..], &multiplicator).is_err());
+        assert_eq!(
+            filtered_sum(&[], &[], &multiplicator).unwrap_err(),
+            Error::TooFewValues(0, 1)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn aggregate_then_multiply_with_profile() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let num_summands = 10;
+        let params = AggregationProfile::new(num_summands, 1 << 8, 1 << 8)?.parameters()?;
+        let sk = SecretKey::random(&params, &mut rng);
+        let pk = PublicKey::new(&sk, &mut rng);
+        let encrypt = |v: &[u64]| -> Result<Ciphertext, Error> {
+            let pt = Plaintext::try_encode(v, Encoding::simd(), &params)?;
+            pk.try_encrypt(&pt, &mut thread_rng())
+        };
+
+        // Summands and factor of the maximum values.
+        let summands = (0..num_summands)
+            .map(|_| encrypt(&vec![255; params.degree()]))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let factor = encrypt(&(0..params.degree() as u64).map(|i| i % 256).collect_vec())?;
+        let ct = aggregate_then_multiply(&summands, &factor)?;
+        assert_eq!(ct.len(), 3);
+        let decrypted = Vec::<u64>::try_decode(&sk.try_decrypt(&ct)?, Encoding::simd())?;
+        for (i, d) in decrypted.iter().enumerate() {
+            assert_eq!(*d, num_summands * 255 * (i as u64 % 256));
+        }
+
+        assert!(aggregate_then_multiply(&[], &factor).is_err());
+        let other = BfvParameters::default_arc(1, 16);
+        let sk = SecretKey::random(&other, &mut rng);
+        let pt = Plaintext::zero(Encoding::poly(), &other)?;
+        let ct: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+        assert!(aggregate_then_multiply(&[ct], &factor).is_err());
+        Ok(())
+    }
+}
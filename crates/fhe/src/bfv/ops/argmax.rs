@@ -0,0 +1,111 @@
+//! Argmax over the slots of a ciphertext.
+
+use crate::bfv::{Ciphertext, EvaluationKey, LookupTable, RelinearizationKey};
+use crate::{Error, Result};
+extern crate alloc;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+/// Computes the encrypted one-hot indicator of the maximum of the slots of a
+/// SIMD-encoded ciphertext, i.e. a ciphertext encrypting 1 in the slot of the
+/// maximum and 0 in the other slots. When several slots are equal to the
+/// maximum, they all encrypt 1. The slots that do not contain a candidate
+/// should be set to 0.
+///
+/// The values must be smaller than `(t + 1) / 2`, where the plaintext modulus
+/// `t` must be prime, so that the sign of the difference of two values is
+/// well-defined modulo `t`. The maximum is computed by a tournament: each
+/// round compares the slots with their rotation by a power of two, computing
+/// `max(a, b) = b + relu(a - b)` with a [`LookupTable`], so that after
+/// `log2(n)` rounds every slot contains the maximum; the indicator is finally
+/// computed by a lookup table of the equality with 0 of the differences of
+/// the slots with the maximum. The evaluation key must support the inner sum
+/// (see [`EvaluationKey::supports_inner_sum`]), i.e. the rotations of the
+/// columns by the powers of two and the rotation of the rows, and `ct` must
+/// be at the level of the relinearization key.
+///
+/// Depth and noise: this evaluates `log2(n) + 1` lookup tables of degree
+/// smaller than `t`, each consuming about `log2(t)` multiplicative levels (see
+/// [`super::LutReport`]), i.e. about `(log2(n) + 1) * log2(t)` levels in
+/// total, as well as `log2(n)` key switchings.
+pub fn argmax(ct: &Ciphertext, ek: &EvaluationKey, rk: &RelinearizationKey) -> Result<Ciphertext> {
+    if ct.par != ek.par {
+        return Err(Error::DefaultError(
+            "Incompatible BFV parameters".to_string(),
+        ));
+    }
+    if !ek.supports_inner_sum() {
+        return Err(Error::DefaultError(
+            "This key does not support the rotations of the argmax".to_string(),
+        ));
+    }
+    let t = ct.par.plaintext();
+    let half = t.div_ceil(2);
+    let relu = (0..t)
+        .map(|d| if d < half { d } else { 0 })
+        .collect::<Vec<u64>>();
+    let relu = LookupTable::new(&relu, &ct.par)?;
+    let is_zero = (0..t).map(|d| (d == 0) as u64).collect::<Vec<u64>>();
+    let is_zero = LookupTable::new(&is_zero, &ct.par)?;
+
+    let max = |a: &Ciphertext, b: &Ciphertext| -> Result<Ciphertext> {
+        Ok(b + &relu.apply(&(a - b), rk)?.0)
+    };
+    let mut m = ct.clone();
+    let mut step = 1;
+    while step < ct.par.degree() / 2 {
+        m = max(&m, &ek.rotates_columns_by(&m, step)?)?;
+        step *= 2;
+    }
+    m = max(&m, &ek.rotates_rows(&m)?)?;
+    Ok(is_zero.apply(&(ct - &m), rk)?.0)
+}
+
+#[cfg(all(test, not(feature = "server")))]
+mod tests {
+    use super::argmax;
+    use crate::bfv::{
+        BfvParametersBuilder, Encoding, EvaluationKeyBuilder, Plaintext, RelinearizationKey,
+        SecretKey,
+    };
+    use crate::Error;
+    use fhe_traits::{FheDecoder, FheDecrypter, FheEncoder, FheEncrypter};
+    use rand::{thread_rng, Rng};
+    extern crate alloc;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn argmax_one_hot() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParametersBuilder::new()
+            .set_degree(8)
+            .set_plaintext_modulus(17)
+            .set_moduli_sizes(&[62; 10])
+            .build_arc()?;
+        let sk = SecretKey::random(&params, &mut rng);
+        let rk = RelinearizationKey::new(&sk, &mut rng)?;
+        let ek = EvaluationKeyBuilder::new(&sk)?
+            .enable_inner_sum()?
+            .build(&mut rng)?;
+
+        // Distinct values smaller than (t + 1) / 2 = 9.
+        let mut values = (0..9u64).collect::<Vec<u64>>();
+        values.swap(rng.gen_range(0..8), 8);
+        values.truncate(8);
+        let pt = Plaintext::try_encode(&values, Encoding::simd(), &params)?;
+        let ct = sk.try_encrypt(&pt, &mut rng)?;
+        let pt = sk.try_decrypt(&argmax(&ct, &ek, &rk)?)?;
+        let max = *values.iter().max().unwrap();
+        let expected = values
+            .iter()
+            .map(|v| (*v == max) as u64)
+            .collect::<Vec<u64>>();
+        assert_eq!(Vec::<u64>::try_decode(&pt, Encoding::simd())?, expected);
+
+        let ek = EvaluationKeyBuilder::new(&sk)?
+            .enable_row_rotation()?
+            .build(&mut rng)?;
+        assert!(argmax(&ct, &ek, &rk).is_err());
+        Ok(())
+    }
+}
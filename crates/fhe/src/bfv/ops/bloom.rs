@@ -0,0 +1,257 @@
+//! Membership queries of a Bloom filter over encrypted queries.
+//!
+//! The client encodes the positions of an item in a Bloom filter of `m` bits
+//! and `k` hash functions as `k` one-hot vectors of `m` coefficients of a
+//! Poly-encoded query; the server expands the query into `k * m` ciphertexts
+//! encrypting the bits of the one-hot vectors, selects the bit of the filter at
+//! each position by summing the ciphertexts at the positions of the bits set in
+//! the filter, and combines the `k` selected bits with a tree of
+//! multiplications, so that the server learns neither the item nor the result.
+
+use crate::bfv::{BfvParameters, Ciphertext, Encoding, EvaluationKey, Multiplicator, Plaintext};
+use crate::{Error, Result};
+use fhe_traits::FheEncoder;
+use sha2::{Digest, Sha256};
+extern crate alloc;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// A Bloom filter of `num_bits` bits and `num_hashes` hash functions, whose
+/// membership queries can be evaluated over encrypted queries with
+/// [`bloom_filter_contains`].
+///
+/// The `j`-th hash function maps an item to the first 8 bytes of the SHA-256
+/// digest of `j` as a little-endian `u32` followed by the item, modulo
+/// `num_bits`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BloomFilter {
+    bits: Vec<bool>,
+    num_hashes: usize,
+}
+
+/// A [`BloomFilter`] prepared to evaluate the membership queries of
+/// ciphertexts at a given level.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodedBloomFilter {
+    par: Arc<BfvParameters>,
+    level: usize,
+    bits: Vec<bool>,
+    num_hashes: usize,
+}
+
+impl BloomFilter {
+    /// Create an empty Bloom filter of `num_bits` bits and `num_hashes` hash
+    /// functions. Returns an error if `num_bits` or `num_hashes` is 0.
+    pub fn new(num_bits: usize, num_hashes: usize) -> Result<Self> {
+        if num_bits == 0 || num_hashes == 0 {
+            return Err(Error::DefaultError(
+                "Invalid Bloom filter dimensions".to_string(),
+            ));
+        }
+        Ok(Self {
+            bits: vec![false; num_bits],
+            num_hashes,
+        })
+    }
+
+    /// Returns the number of bits of the filter.
+    pub fn num_bits(&self) -> usize {
+        self.bits.len()
+    }
+
+    /// Returns the number of hash functions of the filter.
+    pub fn num_hashes(&self) -> usize {
+        self.num_hashes
+    }
+
+    /// Returns the positions of an item in the filter, one per hash function.
+    fn positions<'a>(&'a self, item: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+        (0..self.num_hashes).map(move |j| {
+            let digest = Sha256::new()
+                .chain_update((j as u32).to_le_bytes())
+                .chain_update(item)
+                .finalize();
+            let hash = u64::from_le_bytes(digest[..8].try_into().unwrap());
+            (hash % self.bits.len() as u64) as usize
+        })
+    }
+
+    /// Insert an item in the filter.
+    pub fn insert(&mut self, item: &[u8]) {
+        let positions = self.positions(item).collect::<Vec<_>>();
+        positions.into_iter().for_each(|i| self.bits[i] = true);
+    }
+
+    /// Returns whether the filter may contain an item, i.e. whether all the
+    /// bits at the positions of the item are set. An item which was not
+    /// inserted may be reported as contained, with a probability increasing
+    /// with the number of items in the filter.
+    pub fn contains(&self, item: &[u8]) -> bool {
+        self.positions(item).all(|i| self.bits[i])
+    }
+
+    /// Encode the membership query of an item in a Poly [`Plaintext`] at level
+    /// 0, to be encrypted by the client. The query only depends on the
+    /// dimensions of the filter, so that the client can compute it from an
+    /// empty filter of the same dimensions.
+    ///
+    /// Returns an error if `num_bits * num_hashes` is larger than the degree,
+    /// or if the plaintext modulus is even, since the expansion multiplies the
+    /// query by a power of two.
+    pub fn try_encode_query(&self, item: &[u8], par: &Arc<BfvParameters>) -> Result<Plaintext> {
+        let size = self.num_bits() * self.num_hashes;
+        if size > par.degree() {
+            return Err(Error::TooManyValues(size, par.degree()));
+        }
+        if par.plaintext() % 2 == 0 {
+            return Err(Error::DefaultError(
+                "The plaintext modulus must be odd".to_string(),
+            ));
+        }
+        // The expansion multiplies the query by 2^level.
+        let level = size.next_power_of_two().ilog2() as u64;
+        let inv = par.plaintext.pow(par.plaintext().div_ceil(2), level);
+        let mut query = vec![0u64; size];
+        self.positions(item)
+            .enumerate()
+            .for_each(|(j, i)| query[j * self.num_bits() + i] = inv);
+        Plaintext::try_encode(&query, Encoding::poly(), par)
+    }
+
+    /// Prepare the filter to evaluate the membership queries of ciphertexts at
+    /// level `level`.
+    ///
+    /// Returns an error if the parameters do not have this level.
+    pub fn try_encode(&self, par: &Arc<BfvParameters>, level: usize) -> Result<EncodedBloomFilter> {
+        if level > par.max_level() {
+            return Err(Error::DefaultError("Invalid level".to_string()));
+        }
+        Ok(EncodedBloomFilter {
+            par: par.clone(),
+            level,
+            bits: self.bits.clone(),
+            num_hashes: self.num_hashes,
+        })
+    }
+}
+
+/// Evaluates the membership query `query`, the encryption of a query encoded
+/// with [`BloomFilter::try_encode_query`], in the filter `filter`. Returns a
+/// ciphertext encrypting, in the constant coefficient of the Poly encoding, 1
+/// if the filter may contain the item of the query, and 0 otherwise.
+///
+/// The evaluation key must support the expansion of `num_bits * num_hashes`
+/// ciphertexts (see [`EvaluationKey::supports_expansion`]) at the level of the
+/// query, and the filter must be encoded at the same level.
+///
+/// Depth and noise: this consumes `ceil(log2(num_hashes))` multiplicative
+/// levels for the AND-tree; the noise of the query first grows with the
+/// `ceil(log2(num_bits * num_hashes))` key switchings of the expansion, and
+/// with a sum of up to `num_bits` expanded ciphertexts.
+pub fn bloom_filter_contains(
+    query: &Ciphertext,
+    filter: &EncodedBloomFilter,
+    ek: &EvaluationKey,
+    multiplicator: &Multiplicator,
+) -> Result<Ciphertext> {
+    if query.par != filter.par || query.par != ek.par {
+        return Err(Error::DefaultError(
+            "Incompatible BFV parameters".to_string(),
+        ));
+    }
+    if query.level != filter.level {
+        return Err(Error::DefaultError(
+            "The filter is not encoded at the level of the query".to_string(),
+        ));
+    }
+    let num_bits = filter.bits.len();
+    let expanded = ek.expands(query, num_bits * filter.num_hashes)?;
+    let zero = Plaintext::zero(Encoding::poly_at_level(query.level), &filter.par)?;
+    let mut bits = expanded
+        .chunks(num_bits)
+        .map(|one_hot| {
+            let mut bit = Ciphertext::zero(&filter.par);
+            one_hot
+                .iter()
+                .zip(filter.bits.iter())
+                .filter(|(_, set)| **set)
+                .for_each(|(ct, _)| bit += ct);
+            // When no bit is set, the selected bit is an encryption of 0 at the
+            // level of the query.
+            if bit.is_empty() {
+                bit = &one_hot[0] * &zero
+            }
+            bit
+        })
+        .collect::<Vec<Ciphertext>>();
+    while bits.len() > 1 {
+        bits = bits
+            .chunks(2)
+            .map(|pair| match pair {
+                [a, b] => multiplicator.multiply(a, b),
+                _ => Ok(pair[0].clone()),
+            })
+            .collect::<Result<Vec<Ciphertext>>>()?;
+    }
+    Ok(bits.pop().unwrap())
+}
+
+#[cfg(all(test, not(feature = "server")))]
+mod tests {
+    use super::{bloom_filter_contains, BloomFilter};
+    use crate::bfv::{
+        BfvParameters, Encoding, EvaluationKeyBuilder, Multiplicator, RelinearizationKey, SecretKey,
+    };
+    use crate::Error;
+    use fhe_traits::{FheDecoder, FheDecrypter, FheEncrypter};
+    use rand::thread_rng;
+    extern crate alloc;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn membership() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(4, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let rk = RelinearizationKey::new(&sk, &mut rng)?;
+        let multiplicator = Multiplicator::default(&rk)?;
+        let ek = EvaluationKeyBuilder::new(&sk)?
+            .enable_expansion(4)?
+            .build(&mut rng)?;
+
+        let mut filter = BloomFilter::new(5, 3)?;
+        assert_eq!(filter.num_bits(), 5);
+        assert_eq!(filter.num_hashes(), 3);
+        filter.insert(b"alice");
+        filter.insert(b"bob");
+        let encoded = filter.try_encode(&params, 0)?;
+        assert!(filter.try_encode(&params, params.max_level() + 1).is_err());
+
+        // The client only knows the dimensions of the filter.
+        let client = BloomFilter::new(5, 3)?;
+        for item in [b"alice".as_slice(), b"bob", b"carol", b"dave", b""] {
+            let query = sk.try_encrypt(&client.try_encode_query(item, &params)?, &mut rng)?;
+            let result = bloom_filter_contains(&query, &encoded, &ek, &multiplicator)?;
+            let pt = sk.try_decrypt(&result)?;
+            let value = Vec::<u64>::try_decode(&pt, Encoding::poly())?;
+            assert_eq!(value[0], filter.contains(item) as u64);
+            assert!(value[1..].iter().all(|v| *v == 0));
+        }
+        assert!(filter.contains(b"alice") && filter.contains(b"bob"));
+
+        // An empty filter contains no item.
+        let empty = client.try_encode(&params, 0)?;
+        let query = sk.try_encrypt(&client.try_encode_query(b"alice", &params)?, &mut rng)?;
+        let result = bloom_filter_contains(&query, &empty, &ek, &multiplicator)?;
+        let value = Vec::<u64>::try_decode(&sk.try_decrypt(&result)?, Encoding::poly())?;
+        assert!(value.iter().all(|v| *v == 0));
+
+        assert!(BloomFilter::new(0, 3).is_err());
+        assert!(BloomFilter::new(6, 3)?
+            .try_encode_query(b"alice", &params)
+            .is_err());
+        Ok(())
+    }
+}
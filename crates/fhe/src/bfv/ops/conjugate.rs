@@ -0,0 +1,88 @@
+//! Conjugation of ciphertexts.
+
+use crate::bfv::{Ciphertext, EvaluationKey};
+use crate::{Error, Result};
+extern crate alloc;
+use alloc::string::ToString;
+
+/// Applies the conjugation automorphism `x -> x^(-1)` to a ciphertext, i.e.
+/// the complex conjugation of the canonical embedding, using a key generated
+/// with [`crate::bfv::EvaluationKeyBuilder::enable_conjugation`].
+///
+/// With the Poly encoding, this maps the plaintext `a(x)` to `a(x^(-1))`, i.e.
+/// the coefficient `a_i` moves to the degree `n - i` with its sign flipped for
+/// all `i > 0`. With the SIMD encoding, this swaps the two rows of the
+/// plaintext, like [`EvaluationKey::rotates_rows`]. Together with the column
+/// rotations, i.e. the automorphisms `x -> x^(3^i)`, the conjugation generates
+/// all the automorphisms `x -> x^k` for odd `k`.
+///
+/// Depth and noise: this does not consume any multiplicative level; the noise
+/// grows with one key switching.
+pub fn conjugate(ct: &Ciphertext, ek: &EvaluationKey) -> Result<Ciphertext> {
+    if ct.par != ek.par {
+        return Err(Error::DefaultError(
+            "Incompatible BFV parameters".to_string(),
+        ));
+    }
+    if !ek.supports_conjugation() {
+        return Err(Error::DefaultError(
+            "This key does not support the conjugation".to_string(),
+        ));
+    }
+    ek.rotates_rows(ct)
+}
+
+#[cfg(all(test, not(feature = "server")))]
+mod tests {
+    use super::conjugate;
+    use crate::bfv::{BfvParameters, Encoding, EvaluationKeyBuilder, Plaintext, SecretKey};
+    use crate::Error;
+    use fhe_traits::{FheDecoder, FheDecrypter, FheEncoder, FheEncrypter};
+    use rand::thread_rng;
+    extern crate alloc;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn conjugation() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(2, 16);
+        let n = params.degree();
+        let t = params.plaintext();
+        let sk = SecretKey::random(&params, &mut rng);
+        let ek = EvaluationKeyBuilder::new(&sk)?
+            .enable_conjugation()?
+            .build(&mut rng)?;
+        assert!(ek.supports_conjugation());
+
+        // a(x) -> a(x^(-1)) with the Poly encoding.
+        let a = params.plaintext.random_vec(n, &mut rng);
+        let pt = Plaintext::try_encode(&a, Encoding::poly(), &params)?;
+        let ct = sk.try_encrypt(&pt, &mut rng)?;
+        let mut expected = vec![a[0]; n];
+        for i in 1..n {
+            expected[n - i] = (t - a[i]) % t;
+        }
+        let conjugated = conjugate(&ct, &ek)?;
+        let pt = sk.try_decrypt(&conjugated)?;
+        assert_eq!(Vec::<u64>::try_decode(&pt, Encoding::poly())?, expected);
+        // The conjugation is an involution.
+        let pt = sk.try_decrypt(&conjugate(&conjugated, &ek)?)?;
+        assert_eq!(Vec::<u64>::try_decode(&pt, Encoding::poly())?, a);
+
+        // The rows are swapped with the SIMD encoding.
+        let pt = Plaintext::try_encode(&a, Encoding::simd(), &params)?;
+        let ct = sk.try_encrypt(&pt, &mut rng)?;
+        let pt = sk.try_decrypt(&conjugate(&ct, &ek)?)?;
+        let mut expected = a[n / 2..].to_vec();
+        expected.extend_from_slice(&a[..n / 2]);
+        assert_eq!(Vec::<u64>::try_decode(&pt, Encoding::simd())?, expected);
+
+        let ek = EvaluationKeyBuilder::new(&sk)?
+            .enable_column_rotation(1)?
+            .build(&mut rng)?;
+        assert!(!ek.supports_conjugation());
+        assert!(conjugate(&ct, &ek).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,170 @@
+//! Division of ciphertexts by plaintext constants.
+
+use crate::bfv::{Ciphertext, Encoding, Plaintext};
+use crate::{Error, Result};
+use fhe_traits::FheEncoder;
+use fhe_util::inverse;
+extern crate alloc;
+use alloc::format;
+use alloc::vec::Vec;
+
+impl Ciphertext {
+    /// Returns the inverses of `k` modulo the plaintext modulus.
+    fn plaintext_inverses(&self, k: &[u64]) -> Result<Vec<u64>> {
+        let mut k_inv = self.par.plaintext.reduce_vec_new(k);
+        if self.par.plaintext.inv_vec(&mut k_inv).is_some() {
+            return Ok(k_inv);
+        }
+        // Fall back to individual inversions, which support a non-prime plaintext
+        // modulus and identify the value which is not invertible.
+        let t = self.par.plaintext();
+        k.iter()
+            .map(|ki| {
+                inverse(*ki % t, t).ok_or(Error::DefaultError(format!(
+                    "{ki} is not invertible modulo the plaintext modulus"
+                )))
+            })
+            .collect()
+    }
+
+    /// Divide the ciphertext by the constant `k`, i.e. multiply it by the
+    /// inverse of `k` modulo the plaintext modulus `t`. This applies to every
+    /// coefficient (or every slot) of the underlying plaintext, regardless of
+    /// its encoding.
+    ///
+    /// Returns an error if `gcd(k, t) != 1`.
+    pub fn div_plaintext(&self, k: u64) -> Result<Ciphertext> {
+        let k_inv = self.plaintext_inverses(&[k])?;
+        let pt = Plaintext::try_encode(&k_inv, Encoding::poly_at_level(self.level), &self.par)?;
+        Ok(self * &pt)
+    }
+
+    /// Divide the values of the plaintext by `2^bits`, e.g. to control the
+    /// growth of the magnitude of the values in a fixed-point pipeline, whose
+    /// values are encoded with [`EncodeOptions::headroom_bits`] bits of
+    /// headroom. This multiplies the ciphertext by the inverse of `2^bits`
+    /// modulo the plaintext modulus `t`, as [`Ciphertext::div_plaintext`]: the
+    /// division is exact for the values which are multiples of `2^bits`,
+    /// including the negative values decoded by the signed decoder, and the
+    /// noise grows by at most the size of `t`.
+    ///
+    /// Returns an error if `t` is even, or if `2^bits` is not smaller than `t`.
+    ///
+    /// [`EncodeOptions::headroom_bits`]: crate::bfv::EncodeOptions::headroom_bits
+    pub fn scale_down_plaintext(&self, bits: usize) -> Result<Ciphertext> {
+        let t = self.par.plaintext();
+        if bits >= 64 || (1u64 << bits) >= t {
+            return Err(Error::DefaultError(format!(
+                "Cannot scale down by 2^{bits}, which is not smaller than the plaintext modulus"
+            )));
+        }
+        self.div_plaintext(1 << bits)
+    }
+
+    /// Divide every slot of the ciphertext by the corresponding constant of
+    /// `k`, i.e. multiply it slot-wise by the inverses of `k` modulo the
+    /// plaintext modulus `t`. The ciphertext must use the SIMD encoding; if
+    /// `k` contains fewer values than the number of slots, the remaining slots
+    /// are set to zero.
+    ///
+    /// Returns an error if `gcd(k[i], t) != 1` for some `i`.
+    pub fn div_plaintext_vec(&self, k: &[u64]) -> Result<Ciphertext> {
+        let k_inv = self.plaintext_inverses(k)?;
+        let pt = Plaintext::try_encode(&k_inv, Encoding::simd_at_level(self.level), &self.par)?;
+        Ok(self * &pt)
+    }
+}
+
+#[cfg(all(test, not(feature = "server")))]
+mod tests {
+    use crate::bfv::{
+        BfvParameters, BfvParametersBuilder, Ciphertext, EncodeOptions, Encoding, Plaintext,
+        SecretKey,
+    };
+    use crate::Error;
+    use fhe_traits::{FheDecoder, FheDecrypter, FheEncoder, FheEncrypter};
+    use rand::{thread_rng, Rng};
+    extern crate alloc;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn div_plaintext() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(2, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let t = params.plaintext();
+
+        for encoding in [Encoding::poly(), Encoding::simd()] {
+            let v = params.plaintext.random_vec(params.degree(), &mut rng);
+            let pt = Plaintext::try_encode(&v, encoding.clone(), &params)?;
+            let ct: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+
+            let k = rng.gen_range(1..t);
+            let ct_div = ct.div_plaintext(k)?;
+            let mut w = Vec::<u64>::try_decode(&sk.try_decrypt(&ct_div)?, encoding.clone())?;
+            params.plaintext.scalar_mul_vec(&mut w, k);
+            assert_eq!(w, v);
+
+            assert!(ct.div_plaintext(0).is_err());
+            assert!(ct.div_plaintext(t).is_err());
+        }
+
+        let v = params.plaintext.random_vec(params.degree(), &mut rng);
+        let pt = Plaintext::try_encode(&v, Encoding::simd(), &params)?;
+        let ct: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+        let k = (0..params.degree())
+            .map(|_| rng.gen_range(1..t))
+            .collect::<Vec<_>>();
+        let ct_div = ct.div_plaintext_vec(&k)?;
+        let mut w = Vec::<u64>::try_decode(&sk.try_decrypt(&ct_div)?, Encoding::simd())?;
+        params.plaintext.mul_vec(&mut w, &k);
+        assert_eq!(w, v);
+
+        let mut k = k;
+        k[3] = 0;
+        assert!(ct.div_plaintext_vec(&k).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn scale_down_plaintext() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(2, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let options = EncodeOptions {
+            headroom_bits: 4,
+            ..Default::default()
+        };
+        let encoding = Encoding::simd().with_options(options);
+
+        // Fixed-point values with 2 fractional bits, whose products have 4
+        // fractional bits and are scaled back to 2 fractional bits. The
+        // headroom guarantees that the products do not wrap around.
+        let v = (0..params.degree() as i64)
+            .map(|i| (i - 8) << 2)
+            .collect::<Vec<_>>();
+        let k = (0..params.degree() as i64)
+            .map(|i| (i % 5 - 2) << 2)
+            .collect::<Vec<_>>();
+        let ct: Ciphertext =
+            sk.try_encrypt(&Plaintext::try_encode(&v, encoding, &params)?, &mut rng)?;
+        let ct = &ct * &Plaintext::try_encode(&k, Encoding::simd(), &params)?;
+        let ct = ct.scale_down_plaintext(2)?;
+        let w = Vec::<i64>::try_decode(&sk.try_decrypt(&ct)?, Encoding::simd())?;
+        for i in 0..params.degree() {
+            assert_eq!(w[i], (v[i] * k[i]) >> 2);
+        }
+
+        assert!(ct.scale_down_plaintext(11).is_err());
+        assert!(ct.scale_down_plaintext(64).is_err());
+        let even = BfvParametersBuilder::new()
+            .set_degree(16)
+            .set_plaintext_modulus(1 << 10)
+            .set_moduli_sizes(&[62])
+            .build_arc()?;
+        let ct = Ciphertext::zero(&even);
+        assert!(ct.scale_down_plaintext(1).is_err());
+        Ok(())
+    }
+}
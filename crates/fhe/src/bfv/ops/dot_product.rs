@@ -5,10 +5,9 @@ use itertools::{izip, Itertools};
 use ndarray::{Array, Array2};
 extern crate alloc;
 use alloc::string::ToString;
-use alloc::vec::Vec;
 
 use crate::{
-    bfv::{Ciphertext, Plaintext},
+    bfv::{Ciphertext, CiphertextElements, Plaintext},
     Error, Result,
 };
 
@@ -79,6 +78,8 @@ where
         ));
     }
 
+    let tag = Ciphertext::common_tag(ct.clone());
+
     let max_acc = ctx
         .moduli()
         .iter()
@@ -97,13 +98,15 @@ where
                 )
                 .map_err(Error::MathError)
             })
-            .collect::<Result<Vec<Poly>>>()?;
+            .collect::<Result<CiphertextElements>>()?;
 
         Ok(Ciphertext {
             par: ct_first.par.clone(),
             seed: None,
             c,
             level: ct_first.level,
+            tag,
+            noise_estimate: None,
         })
     } else {
         let mut acc = Array::zeros((ct_first.len(), ctx.moduli().len(), ct_first.par.degree()));
@@ -128,7 +131,7 @@ where
         }
 
         // Reduce
-        let mut c = Vec::with_capacity(ct_first.len());
+        let mut c = CiphertextElements::with_capacity(ct_first.len());
         for acci in acc.outer_iter() {
             let mut coeffs = Array2::zeros((ctx.moduli().len(), ct_first.par.degree()));
             for (mut outij, accij, q) in izip!(
@@ -153,11 +156,13 @@ where
             seed: None,
             c,
             level: ct_first.level,
+            tag,
+            noise_estimate: None,
         })
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, not(feature = "server")))]
 mod tests {
     use super::dot_product_scalar;
     use crate::bfv::{BfvParameters, Ciphertext, Encoding, Plaintext, SecretKey};
@@ -0,0 +1,96 @@
+//! Extraction of a slot of a ciphertext.
+
+use crate::bfv::{Ciphertext, Encoding, EvaluationKey, Plaintext};
+use crate::{Error, Result};
+use fhe_traits::FheEncoder;
+extern crate alloc;
+use alloc::string::ToString;
+use alloc::vec;
+
+/// Moves the value of the slot `slot_index` of a SIMD-encoded ciphertext to
+/// its slot 0, and sets all the other slots to 0, e.g. to post-process the
+/// result of an argmax one value at a time. To broadcast the value to all the
+/// slots instead, see [`crate::bfv::replicate`].
+///
+/// The slot is extracted by multiplying the ciphertext by a plaintext mask,
+/// and moved to the first column by a column rotation, then to the first row
+/// by a row rotation if needed. The evaluation key must support the column
+/// rotation by `slot_index % (n / 2)`, either directly or as a composition of
+/// rotations by powers of two (see
+/// [`EvaluationKey::composes_column_rotation_by`]), and the row rotation when
+/// `slot_index >= n / 2`.
+///
+/// Depth and noise: this does not consume any multiplicative level; the noise
+/// grows with one multiplication by a plaintext, and with up to one key
+/// switching per rotation.
+pub fn extract_slot(ct: &Ciphertext, slot_index: usize, ek: &EvaluationKey) -> Result<Ciphertext> {
+    if ct.par != ek.par {
+        return Err(Error::DefaultError(
+            "Incompatible BFV parameters".to_string(),
+        ));
+    }
+    if slot_index >= ct.par.degree() {
+        return Err(Error::DefaultError("Invalid slot index".to_string()));
+    }
+    let row_size = ct.par.degree() / 2;
+    let column = slot_index % row_size;
+
+    let mut mask = vec![0u64; ct.par.degree()];
+    mask[slot_index] = 1;
+    let mask = Plaintext::try_encode(&mask, Encoding::simd_at_level(ct.level), &ct.par)?;
+    let mut out = ct * &mask;
+    if column > 0 {
+        out = if ek.supports_column_rotation_by(column) {
+            ek.rotates_columns_by(&out, column)?
+        } else {
+            ek.composes_column_rotation_by(&out, column)?
+        };
+    }
+    if slot_index >= row_size {
+        out = ek.rotates_rows(&out)?;
+    }
+    Ok(out)
+}
+
+#[cfg(all(test, not(feature = "server")))]
+mod tests {
+    use super::extract_slot;
+    use crate::bfv::{BfvParameters, Encoding, EvaluationKeyBuilder, Plaintext, SecretKey};
+    use crate::Error;
+    use fhe_traits::{FheDecoder, FheDecrypter, FheEncoder, FheEncrypter};
+    use rand::thread_rng;
+    extern crate alloc;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn extract() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(2, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let ek = EvaluationKeyBuilder::new(&sk)?
+            .enable_power_of_two_rotations()?
+            .enable_row_rotation()?
+            .build(&mut rng)?;
+        let v = params.plaintext.random_vec(params.degree(), &mut rng);
+        let pt = Plaintext::try_encode(&v, Encoding::simd(), &params)?;
+        let ct = sk.try_encrypt(&pt, &mut rng)?;
+
+        for (slot_index, value) in v.iter().enumerate() {
+            let pt = sk.try_decrypt(&extract_slot(&ct, slot_index, &ek)?)?;
+            let mut expected = vec![0u64; params.degree()];
+            expected[0] = *value;
+            assert_eq!(Vec::<u64>::try_decode(&pt, Encoding::simd())?, expected);
+        }
+        assert!(extract_slot(&ct, 16, &ek).is_err());
+
+        // The slots of the second row require the row rotation.
+        let ek = EvaluationKeyBuilder::new(&sk)?
+            .enable_column_rotation(3)?
+            .build(&mut rng)?;
+        assert!(extract_slot(&ct, 3, &ek).is_ok());
+        assert!(extract_slot(&ct, 11, &ek).is_err());
+        assert!(extract_slot(&ct, 5, &ek).is_err());
+        Ok(())
+    }
+}
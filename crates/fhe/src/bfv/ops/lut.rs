@@ -0,0 +1,457 @@
+//! Evaluation of lookup tables over the slots of a ciphertext.
+
+use crate::bfv::{
+    BfvParameters, Ciphertext, Encoding, Multiplicator, Plaintext, RelinearizationKey,
+};
+use crate::{Error, Result};
+use fhe_math::zq::polynomial;
+use fhe_traits::FheEncoder;
+use fhe_util::is_prime;
+use hashbrown::HashMap;
+use sha2::{Digest, Sha256};
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+/// A lookup table over `Z_t`, where `t` is a prime plaintext modulus,
+/// interpolated into the unique polynomial `p` of degree smaller than `t` such
+/// that `p(a) = table[a]` for all `a` in `Z_t`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LookupTable {
+    par: Arc<BfvParameters>,
+    coefficients: Arc<Vec<u64>>,
+}
+
+/// Report on the evaluation of a [`LookupTable`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LutReport {
+    /// The degree of the interpolated polynomial.
+    pub degree: usize,
+    /// The multiplicative depth of the evaluation.
+    pub depth: usize,
+    /// The number of multiplications of ciphertexts.
+    pub multiplications: usize,
+    /// An estimate of the number of bits of noise added by the evaluation.
+    pub estimated_noise_growth: usize,
+}
+
+/// Intermediate value of the Paterson–Stockmeyer evaluation: a linear
+/// combination may be zero or a constant, which are kept in the clear.
+enum Value {
+    Zero,
+    Constant(u64),
+    Encrypted(Box<Ciphertext>, usize),
+}
+
+impl LookupTable {
+    /// Interpolate the lookup table `table`, which must contain exactly `t`
+    /// values smaller than the plaintext modulus `t`.
+    ///
+    /// Returns an error if the plaintext modulus is not prime.
+    pub fn new(table: &[u64], par: &Arc<BfvParameters>) -> Result<Self> {
+        Self::validate(table, par)?;
+        Ok(Self {
+            par: par.clone(),
+            coefficients: Arc::new(Self::interpolate(table, par)?),
+        })
+    }
+
+    fn validate(table: &[u64], par: &Arc<BfvParameters>) -> Result<()> {
+        let t = par.plaintext();
+        if !is_prime(t) {
+            return Err(Error::DefaultError(
+                "Lookup tables require a prime plaintext modulus".to_string(),
+            ));
+        }
+        if table.len() as u64 != t {
+            return Err(Error::DefaultError(
+                "The lookup table must contain one value per element of Z_t".to_string(),
+            ));
+        }
+        if table.iter().any(|v| *v >= t) {
+            return Err(Error::DefaultError(
+                "The lookup table values must be smaller than the plaintext modulus".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Interpolation over `Z_t` at all the elements of `Z_t`.
+    fn interpolate(table: &[u64], par: &Arc<BfvParameters>) -> Result<Vec<u64>> {
+        let points = (0..table.len() as u64).collect::<Vec<u64>>();
+        let mut coefficients = polynomial::interpolate(&points, table, &par.plaintext)?;
+        coefficients.resize(table.len(), 0);
+        Ok(coefficients)
+    }
+
+    /// Returns the coefficients of the interpolated polynomial, by increasing
+    /// degree.
+    pub fn coefficients(&self) -> &[u64] {
+        &self.coefficients
+    }
+
+    /// Returns the degree of the interpolated polynomial.
+    pub fn degree(&self) -> usize {
+        self.coefficients
+            .iter()
+            .rposition(|c| *c != 0)
+            .unwrap_or_default()
+    }
+
+    /// Parameters of the Paterson–Stockmeyer evaluation: the number of baby
+    /// steps `k`, a power of two, and the number of giant steps.
+    fn steps(&self) -> (usize, usize) {
+        let n = self.degree() + 1;
+        let mut k = 1;
+        while k * k < n {
+            k *= 2
+        }
+        let num_chunks = n.div_ceil(k);
+        (k, num_chunks.next_power_of_two().ilog2() as usize)
+    }
+
+    /// Evaluate the lookup table on each slot of the ciphertext `ct`, using the
+    /// Paterson–Stockmeyer algorithm, i.e. with about `2 sqrt(t)`
+    /// multiplications and depth about `log2(t)`.
+    ///
+    /// The ciphertext must be at the level of the relinearization key, and the
+    /// parameters must have a sufficient noise budget for the depth reported
+    /// in the returned [`LutReport`].
+    pub fn apply(
+        &self,
+        ct: &Ciphertext,
+        rk: &RelinearizationKey,
+    ) -> Result<(Ciphertext, LutReport)> {
+        if ct.par != self.par {
+            return Err(Error::DefaultError(
+                "Incompatible BFV parameters".to_string(),
+            ));
+        }
+        let multiplicator = Multiplicator::default(rk)?;
+        let mut multiplications = 0;
+        let mut mul = |a: &(Ciphertext, usize), b: &(Ciphertext, usize)| -> Result<_> {
+            multiplications += 1;
+            Ok((multiplicator.multiply(&a.0, &b.0)?, a.1.max(b.1) + 1))
+        };
+
+        // Baby steps: x^1, ..., x^k, each with optimal depth.
+        let (k, num_giant_steps) = self.steps();
+        let mut powers = Vec::with_capacity(k + 1);
+        powers.push((Ciphertext::zero(&self.par), 0));
+        powers.push((ct.clone(), 0));
+        for i in 2..=k {
+            let a = 1 << (usize::BITS - 1 - (i - 1).leading_zeros());
+            let p = mul(&powers[a], &powers[i - a])?;
+            powers.push(p);
+        }
+
+        // Giant steps: x^k, x^(2k), x^(4k), ...
+        let mut giant = Vec::with_capacity(num_giant_steps);
+        if num_giant_steps > 0 {
+            giant.push(powers[k].clone());
+        }
+        for j in 1..num_giant_steps {
+            let g = mul(&giant[j - 1], &giant[j - 1])?;
+            giant.push(g);
+        }
+
+        let value = self.evaluate(
+            &self.coefficients[..=self.degree()],
+            num_giant_steps,
+            k,
+            &powers,
+            &giant,
+            &mut mul,
+        )?;
+        let (ct, depth) = match value {
+            Value::Encrypted(ct, depth) => (*ct, depth),
+            Value::Constant(c) => (
+                &(ct * &self.constant(0, ct.level)?) + &self.constant(c, ct.level)?,
+                0,
+            ),
+            Value::Zero => (ct * &self.constant(0, ct.level)?, 0),
+        };
+
+        let t_bits = 64 - self.par.plaintext().leading_zeros() as usize;
+        let report = LutReport {
+            degree: self.degree(),
+            depth,
+            multiplications,
+            estimated_noise_growth: depth * (t_bits + self.par.degree().ilog2() as usize + 1),
+        };
+        Ok((ct, report))
+    }
+
+    /// Recursively evaluate the polynomial with coefficients `c`, of degree
+    /// smaller than `k * 2^j`.
+    fn evaluate<F>(
+        &self,
+        c: &[u64],
+        j: usize,
+        k: usize,
+        powers: &[(Ciphertext, usize)],
+        giant: &[(Ciphertext, usize)],
+        mul: &mut F,
+    ) -> Result<Value>
+    where
+        F: FnMut(&(Ciphertext, usize), &(Ciphertext, usize)) -> Result<(Ciphertext, usize)>,
+    {
+        if j == 0 {
+            return self.linear_combination(c, powers);
+        }
+        let split = k << (j - 1);
+        if c.len() <= split {
+            return self.evaluate(c, j - 1, k, powers, giant, mul);
+        }
+        let low = self.evaluate(&c[..split], j - 1, k, powers, giant, mul)?;
+        let high = self.evaluate(&c[split..], j - 1, k, powers, giant, mul)?;
+        let g = &giant[j - 1];
+        let product = match high {
+            Value::Zero => return Ok(low),
+            Value::Constant(h) => (&g.0 * &self.constant(h, g.0.level)?, g.1),
+            Value::Encrypted(h, depth) => mul(&(*h, depth), g)?,
+        };
+        self.add(product, low)
+    }
+
+    /// Compute `sum_i c[i] * x^i` from the baby steps.
+    fn linear_combination(&self, c: &[u64], powers: &[(Ciphertext, usize)]) -> Result<Value> {
+        let mut acc: Option<(Ciphertext, usize)> = None;
+        for (ci, (p, depth)) in c.iter().zip(powers.iter()).skip(1) {
+            if *ci == 0 {
+                continue;
+            }
+            let term = p * &self.constant(*ci, p.level)?;
+            acc = Some(match acc {
+                Some((a, d)) => (&a + &term, d.max(*depth)),
+                None => (term, *depth),
+            });
+        }
+        let c0 = c.first().copied().unwrap_or_default();
+        Ok(match acc {
+            Some(acc) => self.add(acc, Value::Constant(c0))?,
+            None if c0 == 0 => Value::Zero,
+            None => Value::Constant(c0),
+        })
+    }
+
+    fn add(&self, a: (Ciphertext, usize), b: Value) -> Result<Value> {
+        Ok(match b {
+            Value::Zero | Value::Constant(0) => Value::Encrypted(Box::new(a.0), a.1),
+            Value::Constant(c) => {
+                let pt = self.constant(c, a.0.level)?;
+                Value::Encrypted(Box::new(&a.0 + &pt), a.1)
+            }
+            Value::Encrypted(b, depth) => Value::Encrypted(Box::new(&a.0 + &*b), a.1.max(depth)),
+        })
+    }
+
+    /// The constant `c` as a plaintext, which is the same in every slot.
+    fn constant(&self, c: u64, level: usize) -> Result<Plaintext> {
+        Plaintext::try_encode(&[c], Encoding::poly_at_level(level), &self.par)
+    }
+}
+
+/// A cache of interpolated lookup tables, keyed by the plaintext modulus and
+/// a hash of the table, so that applying the same function repeatedly (e.g.
+/// across batches of ciphertexts) only interpolates it once.
+#[derive(Debug, Clone, Default)]
+pub struct LutCache {
+    entries: HashMap<(u64, [u8; 32]), Arc<Vec<u64>>>,
+    hits: usize,
+    misses: usize,
+}
+
+impl LutCache {
+    /// Create an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the interpolation of the lookup table `table`, computing it
+    /// only if it is not already in the cache. See [`LookupTable::new`].
+    pub fn get(&mut self, table: &[u64], par: &Arc<BfvParameters>) -> Result<LookupTable> {
+        LookupTable::validate(table, par)?;
+        let mut hasher = Sha256::new();
+        for v in table {
+            hasher.update(v.to_le_bytes());
+        }
+        let key = (par.plaintext(), hasher.finalize().into());
+        let coefficients = if let Some(coefficients) = self.entries.get(&key) {
+            self.hits += 1;
+            coefficients.clone()
+        } else {
+            self.misses += 1;
+            let coefficients = Arc::new(LookupTable::interpolate(table, par)?);
+            self.entries.insert(key, coefficients.clone());
+            coefficients
+        };
+        Ok(LookupTable {
+            par: par.clone(),
+            coefficients,
+        })
+    }
+
+    /// Evaluate the lookup table `table` on each slot of the ciphertext `ct`,
+    /// using the cached interpolation. See [`LookupTable::apply`].
+    pub fn apply(
+        &mut self,
+        ct: &Ciphertext,
+        table: &[u64],
+        rk: &RelinearizationKey,
+    ) -> Result<(Ciphertext, LutReport)> {
+        self.get(table, &ct.par)?.apply(ct, rk)
+    }
+
+    /// Returns the number of interpolated tables in the cache.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the number of lookups which were served from the cache, and
+    /// the number of lookups which required an interpolation.
+    pub fn stats(&self) -> (usize, usize) {
+        (self.hits, self.misses)
+    }
+
+    /// Remove all the interpolated tables from the cache.
+    pub fn clear(&mut self) {
+        self.entries.clear()
+    }
+}
+
+/// Evaluate the function `f: Z_t -> Z_t` defined by the lookup table `table`
+/// on each slot of the ciphertext `ct`, where `t` is the (prime) plaintext
+/// modulus. See [`LookupTable::apply`].
+pub fn apply_lut(
+    ct: &Ciphertext,
+    table: &[u64],
+    rk: &RelinearizationKey,
+) -> Result<(Ciphertext, LutReport)> {
+    LookupTable::new(table, &ct.par)?.apply(ct, rk)
+}
+
+#[cfg(all(test, not(feature = "server")))]
+mod tests {
+    use super::{apply_lut, LookupTable, LutCache};
+    use crate::bfv::{
+        BfvParameters, BfvParametersBuilder, Ciphertext, Encoding, Plaintext, RelinearizationKey,
+        SecretKey,
+    };
+    use crate::Error;
+    use fhe_traits::{FheDecoder, FheDecrypter, FheEncoder, FheEncrypter};
+    use rand::{thread_rng, Rng};
+    extern crate alloc;
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+
+    fn params() -> Arc<BfvParameters> {
+        BfvParametersBuilder::new()
+            .set_degree(16)
+            .set_plaintext_modulus(97)
+            .set_moduli_sizes(&[62, 62, 62, 62])
+            .build_arc()
+            .unwrap()
+    }
+
+    #[test]
+    fn interpolation() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let par = params();
+        let t = &par.plaintext;
+        let table = t.random_vec(97, &mut rng);
+        let lut = LookupTable::new(&table, &par)?;
+        for (a, fa) in table.iter().enumerate() {
+            let value = lut
+                .coefficients()
+                .iter()
+                .rev()
+                .fold(0, |acc, c| t.add(t.mul(acc, a as u64), *c));
+            assert_eq!(value, *fa);
+        }
+
+        let identity = (0..97).collect::<Vec<u64>>();
+        assert_eq!(LookupTable::new(&identity, &par)?.degree(), 1);
+        assert_eq!(LookupTable::new(&[5; 97], &par)?.degree(), 0);
+
+        assert!(LookupTable::new(&table[..96], &par).is_err());
+        assert!(LookupTable::new(&[97; 97], &par).is_err());
+        assert!(LookupTable::new(&[0; 16], &BfvParameters::default_arc(1, 16)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn apply() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let par = params();
+        let sk = SecretKey::random(&par, &mut rng);
+        let rk = RelinearizationKey::new(&sk, &mut rng)?;
+        let values = par.plaintext.random_vec(par.degree(), &mut rng);
+        let pt = Plaintext::try_encode(&values, Encoding::simd(), &par)?;
+        let ct: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+
+        let random = (0..97).map(|_| rng.gen_range(0..97)).collect::<Vec<u64>>();
+        let square = (0..97).map(|a| a * a % 97).collect::<Vec<u64>>();
+        let sign = (0..97).map(|a| u64::from(a > 48)).collect::<Vec<u64>>();
+        for table in [random, square, sign, [7; 97].to_vec(), [0; 97].to_vec()] {
+            let (ct_out, report) = apply_lut(&ct, &table, &rk)?;
+            let lut = LookupTable::new(&table, &par)?;
+            assert_eq!(report.degree, lut.degree());
+            if report.degree > 1 {
+                assert!(report.depth >= (report.degree as f64).log2().ceil() as usize);
+                assert!(report.depth <= 2 + (report.degree as f64).log2().ceil() as usize);
+                assert!(report.multiplications <= 3 * (report.degree as f64).sqrt() as usize + 3);
+            }
+            let pt_out = sk.try_decrypt(&ct_out)?;
+            let out = Vec::<u64>::try_decode(&pt_out, Encoding::simd())?;
+            let expected = values
+                .iter()
+                .map(|v| table[*v as usize])
+                .collect::<Vec<_>>();
+            assert_eq!(out, expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn cache() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let par = params();
+        let sk = SecretKey::random(&par, &mut rng);
+        let rk = RelinearizationKey::new(&sk, &mut rng)?;
+        let sign = (0..97).map(|a| u64::from(a > 48)).collect::<Vec<u64>>();
+        let square = (0..97).map(|a| a * a % 97).collect::<Vec<u64>>();
+
+        let mut cache = LutCache::new();
+        assert!(cache.is_empty());
+        assert_eq!(cache.get(&sign, &par)?, LookupTable::new(&sign, &par)?);
+        assert_eq!(cache.get(&sign, &par)?, LookupTable::new(&sign, &par)?);
+        assert_eq!(cache.get(&square, &par)?, LookupTable::new(&square, &par)?);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.stats(), (1, 2));
+        assert!(cache.get(&sign[..96], &par).is_err());
+        assert_eq!(cache.len(), 2);
+
+        for _ in 0..2 {
+            let values = par.plaintext.random_vec(par.degree(), &mut rng);
+            let pt = Plaintext::try_encode(&values, Encoding::simd(), &par)?;
+            let ct: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+            let (ct_out, _) = cache.apply(&ct, &sign, &rk)?;
+            let out = Vec::<u64>::try_decode(&sk.try_decrypt(&ct_out)?, Encoding::simd())?;
+            let expected = values.iter().map(|v| sign[*v as usize]).collect::<Vec<_>>();
+            assert_eq!(out, expected);
+        }
+        assert_eq!(cache.stats(), (3, 2));
+
+        cache.clear();
+        assert!(cache.is_empty());
+        Ok(())
+    }
+}
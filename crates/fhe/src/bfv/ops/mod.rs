@@ -1,19 +1,57 @@
 //! Operations over ciphertexts
 
+mod aggregation;
+pub use aggregation::{aggregate_then_multiply, filtered_sum, group_by_sum};
+
+mod argmax;
+pub use argmax::argmax;
+
+mod bloom;
+pub use bloom::{bloom_filter_contains, BloomFilter, EncodedBloomFilter};
+
+mod conjugate;
+pub use conjugate::conjugate;
+
+mod div;
+
 mod dot_product;
 pub use dot_product::dot_product_scalar;
 
+mod extract;
+pub use extract::extract_slot;
+
+mod lut;
+pub use lut::{apply_lut, LookupTable, LutCache, LutReport};
+
 mod mul;
 pub use mul::Multiplicator;
 
-use super::{Ciphertext, Plaintext};
+mod prefix_sum;
+pub use prefix_sum::prefix_sum;
+
+mod relinearize;
+
+mod replicate;
+pub use replicate::replicate;
+
+mod shift;
+
+mod statistics;
+pub use statistics::{mean, variance, EncryptedStatistic};
+
+mod stream;
+pub use stream::{CiphertextStream, RotFold};
+
+mod string;
+pub use string::{string_equals, StringEncoder};
+
+use super::{Ciphertext, CiphertextElements, Plaintext};
 use crate::{Error, Result};
 use core::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 use fhe_math::rq::{Poly, Representation};
-use itertools::{izip, Itertools as _};
+use itertools::izip;
+use smallvec::smallvec;
 extern crate alloc;
-use alloc::vec;
-use alloc::vec::Vec;
 
 impl Add<&Ciphertext> for &Ciphertext {
     type Output = Ciphertext;
@@ -25,6 +63,33 @@ impl Add<&Ciphertext> for &Ciphertext {
     }
 }
 
+// The operators taking an owned ciphertext reuse its polynomials instead of
+// cloning them, e.g. in the expression chains `&a + &b + &c`.
+impl Add<&Ciphertext> for Ciphertext {
+    type Output = Ciphertext;
+
+    fn add(mut self, rhs: &Ciphertext) -> Ciphertext {
+        self += rhs;
+        self
+    }
+}
+
+impl Add<Ciphertext> for &Ciphertext {
+    type Output = Ciphertext;
+
+    fn add(self, rhs: Ciphertext) -> Ciphertext {
+        rhs + self
+    }
+}
+
+impl Add<Ciphertext> for Ciphertext {
+    type Output = Ciphertext;
+
+    fn add(self, rhs: Ciphertext) -> Ciphertext {
+        self + &rhs
+    }
+}
+
 impl AddAssign<&Ciphertext> for Ciphertext {
     fn add_assign(&mut self, rhs: &Ciphertext) {
         assert_eq!(self.par, rhs.par);
@@ -33,9 +98,14 @@ impl AddAssign<&Ciphertext> for Ciphertext {
             *self = rhs.clone()
         } else if !rhs.is_empty() {
             assert_eq!(self.level, rhs.level);
-            assert_eq!(self.len(), rhs.len());
+            // The ciphertext with fewer polynomials is implicitly padded with
+            // zero polynomials, e.g. to accumulate products of ciphertexts
+            // before relinearizing the sum once.
+            let len = self.len();
             izip!(self.iter_mut(), rhs.iter()).for_each(|(c1i, c2i)| *c1i += c2i);
-            self.seed = None
+            self.c.extend(rhs.iter().skip(len).cloned());
+            self.seed = None;
+            self.merge_tag(rhs)
         }
     }
 }
@@ -58,14 +128,30 @@ impl Add<&Ciphertext> for &Plaintext {
     }
 }
 
+impl Add<&Plaintext> for Ciphertext {
+    type Output = Ciphertext;
+
+    fn add(mut self, rhs: &Plaintext) -> Ciphertext {
+        self += rhs;
+        self
+    }
+}
+
+impl Add<Ciphertext> for &Plaintext {
+    type Output = Ciphertext;
+
+    fn add(self, rhs: Ciphertext) -> Ciphertext {
+        rhs + self
+    }
+}
+
 impl AddAssign<&Plaintext> for Ciphertext {
     fn add_assign(&mut self, rhs: &Plaintext) {
         assert_eq!(self.par, rhs.par);
         assert!(!self.is_empty());
         assert_eq!(self.level, rhs.level);
 
-        let poly = rhs.to_poly();
-        self[0] += &poly;
+        self[0] += rhs.to_poly();
         self.seed = None
     }
 }
@@ -80,6 +166,31 @@ impl Sub<&Ciphertext> for &Ciphertext {
     }
 }
 
+impl Sub<&Ciphertext> for Ciphertext {
+    type Output = Ciphertext;
+
+    fn sub(mut self, rhs: &Ciphertext) -> Ciphertext {
+        self -= rhs;
+        self
+    }
+}
+
+impl Sub<Ciphertext> for &Ciphertext {
+    type Output = Ciphertext;
+
+    fn sub(self, rhs: Ciphertext) -> Ciphertext {
+        -rhs + self
+    }
+}
+
+impl Sub<Ciphertext> for Ciphertext {
+    type Output = Ciphertext;
+
+    fn sub(self, rhs: Ciphertext) -> Ciphertext {
+        self - &rhs
+    }
+}
+
 impl SubAssign<&Ciphertext> for Ciphertext {
     fn sub_assign(&mut self, rhs: &Ciphertext) {
         assert_eq!(self.par, rhs.par);
@@ -88,9 +199,13 @@ impl SubAssign<&Ciphertext> for Ciphertext {
             *self = -rhs
         } else if !rhs.is_empty() {
             assert_eq!(self.level, rhs.level);
-            assert_eq!(self.len(), rhs.len());
+            // The ciphertext with fewer polynomials is implicitly padded with
+            // zero polynomials, as for the addition.
+            let len = self.len();
             izip!(self.iter_mut(), rhs.iter()).for_each(|(c1i, c2i)| *c1i -= c2i);
-            self.seed = None
+            self.c.extend(rhs.iter().skip(len).map(|c2i| -c2i));
+            self.seed = None;
+            self.merge_tag(rhs)
         }
     }
 }
@@ -113,14 +228,30 @@ impl Sub<&Ciphertext> for &Plaintext {
     }
 }
 
+impl Sub<&Plaintext> for Ciphertext {
+    type Output = Ciphertext;
+
+    fn sub(mut self, rhs: &Plaintext) -> Ciphertext {
+        self -= rhs;
+        self
+    }
+}
+
+impl Sub<Ciphertext> for &Plaintext {
+    type Output = Ciphertext;
+
+    fn sub(self, rhs: Ciphertext) -> Ciphertext {
+        -(rhs - self)
+    }
+}
+
 impl SubAssign<&Plaintext> for Ciphertext {
     fn sub_assign(&mut self, rhs: &Plaintext) {
         assert_eq!(self.par, rhs.par);
         assert!(!self.is_empty());
         assert_eq!(self.level, rhs.level);
 
-        let poly = rhs.to_poly();
-        self.c[0] -= &poly;
+        self.c[0] -= rhs.to_poly();
         self.seed = None
     }
 }
@@ -129,12 +260,14 @@ impl Neg for &Ciphertext {
     type Output = Ciphertext;
 
     fn neg(self) -> Ciphertext {
-        let c = self.iter().map(|c1i| -c1i).collect_vec();
+        let c = self.iter().map(|c1i| -c1i).collect();
         Ciphertext {
             par: self.par.clone(),
             seed: None,
             c,
             level: self.level,
+            tag: self.tag.clone(),
+            noise_estimate: None,
         }
     }
 }
@@ -170,6 +303,60 @@ impl Mul<&Plaintext> for &Ciphertext {
     }
 }
 
+impl Mul<&Plaintext> for Ciphertext {
+    type Output = Ciphertext;
+
+    fn mul(mut self, rhs: &Plaintext) -> Ciphertext {
+        self *= rhs;
+        self
+    }
+}
+
+/// Minimum number of polynomials of both ciphertexts for which the tensor
+/// product uses the Karatsuba algorithm. In the NTT representation, a product
+/// of polynomials costs about as much as a few additions, so that Karatsuba
+/// only pays off for ciphertexts of size at least 4, i.e. when the
+/// relinearization is deliberately delayed.
+const KARATSUBA_THRESHOLD: usize = 4;
+
+/// Compute the tensor product of the ciphertexts `a` and `b` in NTT
+/// representation, i.e. the coefficients of the product of the polynomials
+/// `a[0] + a[1] * X + ...` and `b[0] + b[1] * X + ...`.
+fn tensor_product(a: &[Poly], b: &[Poly]) -> CiphertextElements {
+    if a.len() < KARATSUBA_THRESHOLD || b.len() < KARATSUBA_THRESHOLD {
+        let mut c = smallvec![Poly::zero(a[0].ctx(), Representation::Ntt); a.len() + b.len() - 1];
+        for i in 0..a.len() {
+            for j in 0..b.len() {
+                c[i + j] += &(&a[i] * &b[j])
+            }
+        }
+        return c;
+    }
+
+    // Split a = a0 + X^m * a1 and b = b0 + X^m * b1, and compute the middle
+    // term (a0 * b1 + a1 * b0) as (a0 + a1) * (b0 + b1) - a0 * b0 - a1 * b1.
+    let m = a.len().min(b.len()) / 2;
+    let (a0, a1) = a.split_at(m);
+    let (b0, b1) = b.split_at(m);
+    let z0 = tensor_product(a0, b0);
+    let z2 = tensor_product(a1, b1);
+    let sum = |x0: &[Poly], x1: &[Poly]| {
+        let mut s = x1.iter().cloned().collect::<CiphertextElements>();
+        izip!(s.iter_mut(), x0).for_each(|(si, xi)| *si += xi);
+        s
+    };
+    let mut z1 = tensor_product(&sum(a0, a1), &sum(b0, b1));
+    izip!(z1.iter_mut(), &z0).for_each(|(z1i, z0i)| *z1i -= z0i);
+    izip!(z1.iter_mut(), &z2).for_each(|(z1i, z2i)| *z1i -= z2i);
+
+    let mut c: CiphertextElements =
+        smallvec![Poly::zero(a[0].ctx(), Representation::Ntt); a.len() + b.len() - 1];
+    izip!(c.iter_mut(), &z0).for_each(|(ci, zi)| *ci += zi);
+    izip!(c[m..].iter_mut(), &z1).for_each(|(ci, zi)| *ci += zi);
+    izip!(c[2 * m..].iter_mut(), &z2).for_each(|(ci, zi)| *ci += zi);
+    c
+}
+
 impl Mul<&Ciphertext> for &Ciphertext {
     type Output = Ciphertext;
 
@@ -177,6 +364,8 @@ impl Mul<&Ciphertext> for &Ciphertext {
         if self.is_empty() {
             return self.clone();
         }
+        #[cfg(feature = "metrics")]
+        crate::metrics::record(crate::metrics::Metric::Multiplication, 1);
 
         if rhs == self {
             // Squaring operation
@@ -187,18 +376,13 @@ impl Mul<&Ciphertext> for &Ciphertext {
             let self_c = self
                 .iter()
                 .map(|ci| ci.scale(&mp.extender).map_err(Error::MathError))
-                .collect::<Result<Vec<Poly>>>()
+                .collect::<Result<CiphertextElements>>()
                 .unwrap();
             // println!("Extend: {:?}", now.elapsed().unwrap());
 
             // Multiply
             // now = std::time::SystemTime::now();
-            let mut c = vec![Poly::zero(&mp.to, Representation::Ntt); 2 * self_c.len() - 1];
-            for i in 0..self_c.len() {
-                for j in 0..self_c.len() {
-                    c[i + j] += &(&self_c[i] * &self_c[j])
-                }
-            }
+            let mut c = tensor_product(&self_c, &self_c);
             // println!("Multiply: {:?}", now.elapsed().unwrap());
 
             // Scale
@@ -211,7 +395,7 @@ impl Mul<&Ciphertext> for &Ciphertext {
                     ci.change_representation(Representation::Ntt);
                     Ok(ci)
                 })
-                .collect::<Result<Vec<Poly>>>()
+                .collect::<Result<CiphertextElements>>()
                 .unwrap();
             // println!("Scale: {:?}", now.elapsed().unwrap());
 
@@ -220,6 +404,8 @@ impl Mul<&Ciphertext> for &Ciphertext {
                 seed: None,
                 c,
                 level: rhs.level,
+                tag: self.tag.clone(),
+                noise_estimate: None,
             }
         } else {
             assert_eq!(self.par, rhs.par);
@@ -232,24 +418,18 @@ impl Mul<&Ciphertext> for &Ciphertext {
             let self_c = self
                 .iter()
                 .map(|ci| ci.scale(&mp.extender).map_err(Error::MathError))
-                .collect::<Result<Vec<Poly>>>()
+                .collect::<Result<CiphertextElements>>()
                 .unwrap();
             let other_c = rhs
                 .iter()
                 .map(|ci| ci.scale(&mp.extender).map_err(Error::MathError))
-                .collect::<Result<Vec<Poly>>>()
+                .collect::<Result<CiphertextElements>>()
                 .unwrap();
             // println!("Extend: {:?}", now.elapsed().unwrap());
 
             // Multiply
             // now = std::time::SystemTime::now();
-            let mut c =
-                vec![Poly::zero(&mp.to, Representation::Ntt); self_c.len() + other_c.len() - 1];
-            for i in 0..self_c.len() {
-                for j in 0..other_c.len() {
-                    c[i + j] += &(&self_c[i] * &other_c[j])
-                }
-            }
+            let mut c = tensor_product(&self_c, &other_c);
             // println!("Multiply: {:?}", now.elapsed().unwrap());
 
             // Scale
@@ -262,27 +442,33 @@ impl Mul<&Ciphertext> for &Ciphertext {
                     ci.change_representation(Representation::Ntt);
                     Ok(ci)
                 })
-                .collect::<Result<Vec<Poly>>>()
+                .collect::<Result<CiphertextElements>>()
                 .unwrap();
             // println!("Scale: {:?}", now.elapsed().unwrap());
 
-            Ciphertext {
+            let mut ct = Ciphertext {
                 par: self.par.clone(),
                 seed: None,
                 c,
                 level: rhs.level,
-            }
+                tag: self.tag.clone(),
+                noise_estimate: None,
+            };
+            ct.merge_tag(rhs);
+            ct
         }
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, not(feature = "server")))]
 mod tests {
     use crate::bfv::{
         encoding::EncodingEnum, BfvParameters, Ciphertext, Encoding, Plaintext, SecretKey,
     };
     use crate::Error;
+    use fhe_math::rq::{Poly, Representation};
     use fhe_traits::{FheDecoder, FheDecrypter, FheEncoder, FheEncrypter};
+    use itertools::Itertools;
     use rand::{rngs::OsRng, thread_rng};
     extern crate alloc;
     use alloc::vec;
@@ -377,6 +563,52 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn owned_operands() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(2, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let zero = Ciphertext::zero(&params);
+        for encoding in [Encoding::poly(), Encoding::simd()] {
+            let pt_a = Plaintext::try_encode(
+                &params.plaintext.random_vec(params.degree(), &mut rng),
+                encoding.clone(),
+                &params,
+            )?;
+            let pt_b = Plaintext::try_encode(
+                &params.plaintext.random_vec(params.degree(), &mut rng),
+                encoding.clone(),
+                &params,
+            )?;
+            let ct_a: Ciphertext = sk.try_encrypt(&pt_a, &mut rng)?;
+            let ct_b: Ciphertext = sk.try_encrypt(&pt_b, &mut rng)?;
+
+            assert_eq!(ct_a.clone() + &ct_b, &ct_a + &ct_b);
+            assert_eq!(&ct_a + ct_b.clone(), &ct_a + &ct_b);
+            assert_eq!(ct_a.clone() + ct_b.clone(), &ct_a + &ct_b);
+            assert_eq!(zero.clone() + &ct_a, ct_a);
+            assert_eq!(&ct_a + zero.clone(), ct_a);
+            assert_eq!(ct_a.clone() + &pt_b, &ct_a + &pt_b);
+            assert_eq!(&pt_b + ct_a.clone(), &ct_a + &pt_b);
+
+            assert_eq!(ct_a.clone() - &ct_b, &ct_a - &ct_b);
+            assert_eq!(&ct_a - ct_b.clone(), &ct_a - &ct_b);
+            assert_eq!(ct_a.clone() - ct_b.clone(), &ct_a - &ct_b);
+            assert_eq!(&ct_a - zero.clone(), ct_a);
+            assert_eq!(zero.clone() - &ct_a, -&ct_a);
+            assert_eq!(ct_a.clone() - &pt_b, &ct_a - &pt_b);
+            assert_eq!(&pt_b - ct_a.clone(), &pt_b - &ct_a);
+
+            assert_eq!(ct_a.clone() * &pt_b, &ct_a * &pt_b);
+
+            // A chain of operations only clones the first ciphertext.
+            let ct_c = &ct_a + &ct_b - &ct_a * &pt_b + &pt_a;
+            let expected = &(&(&ct_a + &ct_b) - &(&ct_a * &pt_b)) + &pt_a;
+            assert_eq!(ct_c, expected);
+        }
+        Ok(())
+    }
+
     #[test]
     fn sub() -> Result<(), Error> {
         let mut rng = thread_rng();
@@ -600,6 +832,48 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn add_sub_different_sizes() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let par = BfvParameters::default_arc(2, 16);
+        let sk = SecretKey::random(&par, &mut rng);
+        let v = (0..3)
+            .map(|_| par.plaintext.random_vec(par.degree(), &mut rng))
+            .collect_vec();
+        let ct = v
+            .iter()
+            .map(|vi| {
+                let pt = Plaintext::try_encode(vi, Encoding::simd(), &par)?;
+                sk.try_encrypt(&pt, &mut rng)
+            })
+            .collect::<Result<Vec<Ciphertext>, Error>>()?;
+
+        // Accumulate a product of size 3 with a ciphertext of size 2.
+        let product = &ct[0] * &ct[1];
+        assert_eq!(product.len(), 3);
+        let mut expected_product = v[0].clone();
+        par.plaintext.mul_vec(&mut expected_product, &v[1]);
+        let mut expected = expected_product.clone();
+        par.plaintext.add_vec(&mut expected, &v[2]);
+        for sum in [&product + &ct[2], &ct[2] + &product] {
+            assert_eq!(sum.len(), 3);
+            let pt = sk.try_decrypt(&sum)?;
+            assert_eq!(Vec::<u64>::try_decode(&pt, Encoding::simd())?, expected);
+        }
+
+        let mut expected = expected_product.clone();
+        par.plaintext.sub_vec(&mut expected, &v[2]);
+        let pt = sk.try_decrypt(&(&product - &ct[2]))?;
+        assert_eq!(Vec::<u64>::try_decode(&pt, Encoding::simd())?, expected);
+        let mut expected = v[2].clone();
+        par.plaintext.sub_vec(&mut expected, &expected_product);
+        let difference = &ct[2] - &product;
+        assert_eq!(difference.len(), 3);
+        let pt = sk.try_decrypt(&difference)?;
+        assert_eq!(Vec::<u64>::try_decode(&pt, Encoding::simd())?, expected);
+        Ok(())
+    }
+
     #[test]
     fn square() -> Result<(), Error> {
         let mut rng = thread_rng();
@@ -623,4 +897,50 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn tensor_product() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let par = BfvParameters::default_arc(2, 16);
+        let ctx = par.ctx_at_level(0)?;
+        for (la, lb) in [(1, 5), (3, 3), (4, 4), (4, 7), (5, 5), (9, 6), (12, 12)] {
+            let a = (0..la)
+                .map(|_| Poly::random(ctx, Representation::Ntt, &mut rng))
+                .collect_vec();
+            let b = (0..lb)
+                .map(|_| Poly::random(ctx, Representation::Ntt, &mut rng))
+                .collect_vec();
+            let mut expected = vec![Poly::zero(ctx, Representation::Ntt); la + lb - 1];
+            for i in 0..la {
+                for j in 0..lb {
+                    expected[i + j] += &(&a[i] * &b[j])
+                }
+            }
+            assert_eq!(
+                super::tensor_product(&a, &b).as_slice(),
+                expected.as_slice()
+            );
+        }
+
+        // Multiply ciphertexts of size 5, for which the Karatsuba algorithm is
+        // used.
+        let par = BfvParameters::default_arc(8, 16);
+        let v = par.plaintext.random_vec(par.degree(), &mut rng);
+        let sk = SecretKey::random(&par, &mut OsRng);
+        let pt = Plaintext::try_encode(&v, Encoding::simd(), &par)?;
+        let ct1: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+        let ct2: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+        let ct3 = &(&ct1 * &ct2) * &(&ct1 * &ct1);
+        let ct4 = &(&ct2 * &ct2) * &(&ct2 * &ct1);
+        assert_eq!(ct3.len(), 5);
+        let ct5 = &ct3 * &ct4;
+        assert_eq!(ct5.len(), 9);
+        let mut expected = v.clone();
+        for _ in 0..7 {
+            par.plaintext.mul_vec(&mut expected, &v);
+        }
+        let pt = sk.try_decrypt(&ct5)?;
+        assert_eq!(Vec::<u64>::try_decode(&pt, Encoding::simd())?, expected);
+        Ok(())
+    }
 }
@@ -1,12 +1,11 @@
 extern crate alloc;
 use alloc::sync::Arc;
 use alloc::string::ToString;
-use alloc::vec;
 use alloc::vec::Vec;
 
 use fhe_math::{
     rns::ScalingFactor,
-    rq::{scaler::Scaler, Context, Representation},
+    rq::{scaler::Scaler, Context, Poly, Representation},
     zq::primes::generate_prime,
 };
 use num_bigint::BigUint;
@@ -22,7 +21,7 @@ use crate::{
 /// - Whether `rhs` must be scaled;
 /// - The basis at which the multiplication will occur;
 /// - The scaling factor after multiplication;
-/// - Whether relinearization should be used.
+/// - Whether relinearization should be used, and whether it is delayed.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Multiplicator {
     par: Arc<BfvParameters>,
@@ -32,6 +31,7 @@ pub struct Multiplicator {
     pub(crate) base_ctx: Arc<Context>,
     pub(crate) mul_ctx: Arc<Context>,
     rk: Option<RelinearizationKey>,
+    delay_relinearization: bool,
     mod_switch: bool,
     level: usize,
 }
@@ -97,6 +97,7 @@ impl Multiplicator {
             base_ctx: base_ctx.clone(),
             mul_ctx,
             rk: None,
+            delay_relinearization: false,
             mod_switch: false,
             level,
         })
@@ -146,6 +147,33 @@ impl Multiplicator {
         Ok(())
     }
 
+    /// Delay the relinearization: the products are returned as ciphertexts of
+    /// size 3, to be relinearized only when required, e.g. before a rotation
+    /// or a serialization. Many circuits with a single multiplication
+    /// followed by linear operations never need to be relinearized.
+    pub fn enable_delayed_relinearization(&mut self) {
+        self.delay_relinearization = true
+    }
+
+    /// Reports whether the relinearization is delayed.
+    pub fn delays_relinearization(&self) -> bool {
+        self.delay_relinearization
+    }
+
+    /// Relinearize a ciphertext returned by [`Multiplicator::multiply`] with
+    /// delayed relinearization. Ciphertexts of size 2 are left unchanged.
+    pub fn relinearize(&self, ct: &mut Ciphertext) -> Result<()> {
+        if ct.len() == 2 {
+            return Ok(());
+        }
+        self.rk
+            .as_ref()
+            .ok_or(Error::DefaultError(
+                "Relinearization is not enabled".to_string(),
+            ))?
+            .relinearize_to_size_2(ct)
+    }
+
     /// Enable modulus switching after multiplication (and relinearization, if
     /// applicable).
     pub fn enable_mod_switching(&mut self) -> Result<()> {
@@ -176,12 +204,20 @@ impl Multiplicator {
                 "Multiplication can only be performed on ciphertexts of size 2".to_string(),
             ));
         }
+        #[cfg(feature = "metrics")]
+        crate::metrics::record(crate::metrics::Metric::Multiplication, 1);
 
         // Extend
-        let c00 = lhs[0].scale(&self.extender_lhs)?;
-        let c01 = lhs[1].scale(&self.extender_lhs)?;
-        let c10 = rhs[0].scale(&self.extender_rhs)?;
-        let c11 = rhs[1].scale(&self.extender_rhs)?;
+        let [c00, c01]: [Poly; 2] = self
+            .extender_lhs
+            .scale_many(&[&lhs[0], &lhs[1]])?
+            .try_into()
+            .unwrap();
+        let [c10, c11]: [Poly; 2] = self
+            .extender_rhs
+            .scale_many(&[&rhs[0], &rhs[1]])?
+            .try_into()
+            .unwrap();
 
         // Multiply
         let mut c0 = &c00 * &c10;
@@ -193,14 +229,10 @@ impl Multiplicator {
         c2.change_representation(Representation::PowerBasis);
 
         // Scale
-        let c0 = c0.scale(&self.down_scaler)?;
-        let c1 = c1.scale(&self.down_scaler)?;
-        let c2 = c2.scale(&self.down_scaler)?;
-
-        let mut c = vec![c0, c1, c2];
+        let mut c = self.down_scaler.scale_many(&[&c0, &c1, &c2])?;
 
         // Relinearize
-        if let Some(rk) = self.rk.as_ref() {
+        if let Some(rk) = self.rk.as_ref().filter(|_| !self.delay_relinearization) {
             #[allow(unused_mut)]
             let (mut c0r, mut c1r) = rk.relinearizes_poly(&c[2])?;
 
@@ -224,9 +256,12 @@ impl Multiplicator {
         let mut c = Ciphertext {
             par: self.par.clone(),
             seed: None,
-            c,
+            c: c.into(),
             level: self.level,
+            tag: lhs.tag.clone(),
+            noise_estimate: None,
         };
+        c.merge_tag(rhs);
 
         if self.mod_switch {
             c.mod_switch_to_next_level()?;
@@ -239,7 +274,7 @@ impl Multiplicator {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, not(feature = "server")))]
 mod tests {
     use crate::bfv::{
         BfvParameters, Ciphertext, Encoding, Plaintext, RelinearizationKey, SecretKey,
@@ -360,6 +395,41 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn mul_delayed_relin() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let par = BfvParameters::default_arc(3, 16);
+        let values = par.plaintext.random_vec(par.degree(), &mut rng);
+        let mut expected = values.clone();
+        par.plaintext.mul_vec(&mut expected, &values);
+
+        let sk = SecretKey::random(&par, &mut rng);
+        let rk = RelinearizationKey::new(&sk, &mut rng)?;
+        let pt = Plaintext::try_encode(&values, Encoding::simd(), &par)?;
+        let ct1: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+        let ct2: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+
+        let mut multiplicator = Multiplicator::default(&rk)?;
+        assert!(!multiplicator.delays_relinearization());
+        multiplicator.enable_delayed_relinearization();
+        assert!(multiplicator.delays_relinearization());
+
+        let mut ct3 = multiplicator.multiply(&ct1, &ct2)?;
+        assert_eq!(ct3.len(), 3);
+        let pt = sk.try_decrypt(&ct3)?;
+        assert_eq!(Vec::<u64>::try_decode(&pt, Encoding::simd())?, expected);
+
+        multiplicator.relinearize(&mut ct3)?;
+        assert_eq!(ct3.len(), 2);
+        let pt = sk.try_decrypt(&ct3)?;
+        assert_eq!(Vec::<u64>::try_decode(&pt, Encoding::simd())?, expected);
+
+        multiplicator.rk = None;
+        let mut ct3 = multiplicator.multiply(&ct1, &ct2)?;
+        assert!(multiplicator.relinearize(&mut ct3).is_err());
+        Ok(())
+    }
+
     #[test]
     fn different_mul_strategy() -> Result<(), Error> {
         // Implement the second multiplication strategy from <https://eprint.iacr.org/2021/204>
@@ -0,0 +1,121 @@
+//! Prefix sums over the slots of a ciphertext.
+
+use crate::bfv::{Ciphertext, Encoding, EvaluationKey, Plaintext};
+use crate::{Error, Result};
+use fhe_traits::FheEncoder;
+extern crate alloc;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+/// Computes the inclusive prefix sums of the `n` slots of a SIMD-encoded
+/// ciphertext, read as a flat vector: the slot `j` of the output encrypts the
+/// sum of the slots `0..=j` of `ct`.
+///
+/// The slots form a matrix of 2 rows of `n / 2` slots, so the prefix sums are
+/// first computed in each row with a log-depth network of column rotations,
+/// where the slots wrapping around are zeroed by a plaintext mask. The total of
+/// the first row is then broadcast to the second row, and added to it.
+///
+/// The evaluation key must support prefix sums (see
+/// [`EvaluationKey::supports_prefix_sum`]) at the level of `ct`.
+///
+/// Depth and noise: this does not consume any multiplicative level, but it
+/// performs `log2(n)` multiplications by a plaintext in sequence, so that the
+/// noise grows by about `log2(n) * (log2(t) + log2(n) / 2)` bits, and
+/// `2 * log2(n) - 1` key switchings.
+pub fn prefix_sum(ct: &Ciphertext, ek: &EvaluationKey) -> Result<Ciphertext> {
+    if ct.par != ek.par {
+        return Err(Error::DefaultError(
+            "Incompatible BFV parameters".to_string(),
+        ));
+    }
+    if !ek.supports_prefix_sum() {
+        return Err(Error::DefaultError(
+            "This key does not support the prefix sum functionality".to_string(),
+        ));
+    }
+    let n = ct.par.degree();
+    let half = n / 2;
+    let encoding = Encoding::simd_at_level(ct.level);
+    let mask = |keep: &dyn Fn(usize) -> bool| -> Result<Plaintext> {
+        let m = (0..n).map(|j| keep(j) as u64).collect::<Vec<_>>();
+        Plaintext::try_encode(&m, encoding.clone(), &ct.par)
+    };
+
+    // Prefix sums in each row: at step s, the slot j receives the slot j - s
+    // of its row, unless it would wrap around.
+    let mut out = ct.clone();
+    let mut s = 1;
+    while s < half {
+        let shifted = ek.rotates_columns_by(&out, half - s)?;
+        out += &(&shifted * &mask(&|j| j % half >= s)?);
+        s *= 2
+    }
+
+    // Broadcast the total of the first row, i.e. its last slot, to all the
+    // slots of the second row.
+    let mut carry = &out * &mask(&|j| j == half - 1)?;
+    let mut s = 1;
+    while s < half {
+        carry += &ek.rotates_columns_by(&carry, half - s)?;
+        s *= 2
+    }
+    out += &ek.rotates_rows(&carry)?;
+    Ok(out)
+}
+
+#[cfg(all(test, not(feature = "server")))]
+mod tests {
+    use super::prefix_sum;
+    use crate::bfv::{BfvParameters, Encoding, EvaluationKeyBuilder, Plaintext, SecretKey};
+    use crate::Error;
+    use fhe_traits::{FheDecoder, FheDecrypter, FheEncoder, FheEncrypter};
+    use rand::thread_rng;
+    extern crate alloc;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn prefix_sums() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        for params in [
+            BfvParameters::default_arc(3, 16),
+            BfvParameters::default_arc(3, 32),
+        ] {
+            let sk = SecretKey::random(&params, &mut rng);
+            // The plaintext masks consume most of the noise budget of a single
+            // modulus.
+            for level in 0..params.max_level() {
+                let ek = EvaluationKeyBuilder::new_leveled(&sk, level, 0)?
+                    .enable_prefix_sum()?
+                    .build(&mut rng)?;
+                assert!(ek.supports_prefix_sum());
+
+                let v = params.plaintext.random_vec(params.degree(), &mut rng);
+                let expected = v
+                    .iter()
+                    .scan(0, |acc, vi| {
+                        *acc = params.plaintext.add(*acc, *vi);
+                        Some(*acc)
+                    })
+                    .collect::<Vec<_>>();
+
+                let pt = Plaintext::try_encode(&v, Encoding::simd_at_level(level), &params)?;
+                let ct = sk.try_encrypt(&pt, &mut rng)?;
+                let pt = sk.try_decrypt(&prefix_sum(&ct, &ek)?)?;
+                assert_eq!(
+                    Vec::<u64>::try_decode(&pt, Encoding::simd_at_level(level))?,
+                    expected
+                );
+            }
+
+            let ek = EvaluationKeyBuilder::new(&sk)?
+                .enable_inner_sum()?
+                .build(&mut rng)?;
+            assert!(!ek.supports_prefix_sum());
+            let pt = Plaintext::try_encode(&[1u64], Encoding::simd(), &params)?;
+            let ct = sk.try_encrypt(&pt, &mut rng)?;
+            assert!(prefix_sum(&ct, &ek).is_err());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+//! Standalone relinearization of ciphertexts.
+
+use crate::bfv::{Ciphertext, RelinearizationKey};
+use crate::Result;
+
+impl Ciphertext {
+    /// Relinearize the ciphertext into a ciphertext of size 2, e.g. after
+    /// multiplications whose relinearization was delayed, or after receiving
+    /// a larger ciphertext from another party. Ciphertexts of size 2 are left
+    /// unchanged.
+    ///
+    /// Returns an error if the ciphertext is not at the ciphertext level of
+    /// `rk`, or if `rk` does not contain the key switching keys from the
+    /// powers of the secret key up to `s^(len - 1)`, where `len` is the size
+    /// of the ciphertext (see [`RelinearizationKey::new_up_to_power`]).
+    pub fn relinearize(&mut self, rk: &RelinearizationKey) -> Result<()> {
+        rk.relinearize_to_size_2(self)
+    }
+}
+
+#[cfg(all(test, not(feature = "server")))]
+mod tests {
+    use crate::bfv::{
+        BfvParameters, Ciphertext, Encoding, Plaintext, RelinearizationKey, SecretKey,
+    };
+    use crate::Error;
+    use fhe_traits::{
+        DeserializeParametrized, FheDecoder, FheDecrypter, FheEncoder, FheEncrypter, Serialize,
+    };
+    use rand::thread_rng;
+    extern crate alloc;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn relinearize() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(6, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let rk = RelinearizationKey::new(&sk, &mut rng)?;
+        let v = params.plaintext.random_vec(params.degree(), &mut rng);
+        let pt = Plaintext::try_encode(&v, Encoding::simd(), &params)?;
+        let ct: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+        let mut expected = v.clone();
+        params.plaintext.mul_vec(&mut expected, &v);
+
+        // A ciphertext of size 3 received serialized.
+        let bytes = (&ct * &ct).to_bytes();
+        let mut ct2 = Ciphertext::from_bytes(&bytes, &params)?;
+        assert_eq!(ct2.len(), 3);
+        ct2.relinearize(&rk)?;
+        assert_eq!(ct2.len(), 2);
+        let w = Vec::<u64>::try_decode(&sk.try_decrypt(&ct2)?, Encoding::simd())?;
+        assert_eq!(w, expected);
+
+        // Relinearizing a ciphertext of size 2 does not change it.
+        let mut unchanged = ct.clone();
+        unchanged.relinearize(&rk)?;
+        assert_eq!(unchanged, ct);
+
+        // The key does not switch from s^3, and the levels must match.
+        let mut ct3 = &(&ct * &ct) * &ct;
+        let err = ct3.relinearize(&rk).unwrap_err();
+        assert!(matches!(err, Error::DefaultError(msg) if msg.contains("up to s^3")));
+        ct3.relinearize(&RelinearizationKey::new_up_to_power(&sk, 3, &mut rng)?)?;
+        assert_eq!(ct3.len(), 2);
+        let mut ct2 = &ct * &ct;
+        ct2.mod_switch_to_next_level()?;
+        assert!(ct2.relinearize(&rk).is_err());
+        Ok(())
+    }
+}
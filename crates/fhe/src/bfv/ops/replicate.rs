@@ -0,0 +1,77 @@
+//! Replication of a slot of a ciphertext to all its slots.
+
+use crate::bfv::{Ciphertext, Encoding, EvaluationKey, Plaintext};
+use crate::{Error, Result};
+use fhe_traits::FheEncoder;
+extern crate alloc;
+use alloc::string::ToString;
+use alloc::vec;
+
+/// Broadcasts the value of the slot `slot_index` of a SIMD-encoded ciphertext
+/// to all its slots, e.g. to multiply a vector by an encrypted scalar.
+///
+/// The slot is extracted by multiplying the ciphertext by a plaintext mask, and
+/// broadcast with an inner sum, so that the evaluation key must support the
+/// inner sum at the level of `ct` (see [`EvaluationKey::supports_inner_sum`]).
+///
+/// Depth and noise: this does not consume any multiplicative level; the noise
+/// grows with one multiplication by a plaintext, i.e. by about
+/// `log2(t) + log2(n) / 2` bits, and with the `log2(n)` key switchings of the
+/// inner sum.
+pub fn replicate(ct: &Ciphertext, slot_index: usize, ek: &EvaluationKey) -> Result<Ciphertext> {
+    if ct.par != ek.par {
+        return Err(Error::DefaultError(
+            "Incompatible BFV parameters".to_string(),
+        ));
+    }
+    if slot_index >= ct.par.degree() {
+        return Err(Error::DefaultError("Invalid slot index".to_string()));
+    }
+    let mut mask = vec![0u64; ct.par.degree()];
+    mask[slot_index] = 1;
+    let mask = Plaintext::try_encode(&mask, Encoding::simd_at_level(ct.level), &ct.par)?;
+    ek.computes_inner_sum(&(ct * &mask))
+}
+
+#[cfg(all(test, not(feature = "server")))]
+mod tests {
+    use super::replicate;
+    use crate::bfv::{BfvParameters, Encoding, EvaluationKeyBuilder, Plaintext, SecretKey};
+    use crate::Error;
+    use fhe_traits::{FheDecoder, FheDecrypter, FheEncoder, FheEncrypter};
+    use rand::thread_rng;
+    extern crate alloc;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn replicate_slots() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(2, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        for level in 0..=params.max_level() {
+            let ek = EvaluationKeyBuilder::new_leveled(&sk, level, 0)?
+                .enable_inner_sum()?
+                .build(&mut rng)?;
+            let v = params.plaintext.random_vec(params.degree(), &mut rng);
+            let pt = Plaintext::try_encode(&v, Encoding::simd_at_level(level), &params)?;
+            let ct = sk.try_encrypt(&pt, &mut rng)?;
+            for slot_index in [0, 7, 8, 15] {
+                let pt = sk.try_decrypt(&replicate(&ct, slot_index, &ek)?)?;
+                assert_eq!(
+                    Vec::<u64>::try_decode(&pt, Encoding::simd_at_level(level))?,
+                    vec![v[slot_index]; params.degree()]
+                );
+            }
+            assert!(replicate(&ct, 16, &ek).is_err());
+        }
+
+        let ek = EvaluationKeyBuilder::new(&sk)?
+            .enable_row_rotation()?
+            .build(&mut rng)?;
+        let pt = Plaintext::try_encode(&[1u64], Encoding::simd(), &params)?;
+        let ct = sk.try_encrypt(&pt, &mut rng)?;
+        assert!(replicate(&ct, 0, &ek).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,161 @@
+//! Multiplication of ciphertexts by monomials.
+
+use crate::bfv::Ciphertext;
+use crate::Result;
+use fhe_math::rq::Representation;
+
+impl Ciphertext {
+    /// Multiply the ciphertext by the monomial `x^k`, without increasing its
+    /// noise. With the Poly encoding, this shifts the coefficients of the
+    /// plaintext by `k` degrees; since the polynomials are reduced modulo
+    /// `x^n + 1`, the coefficients shifted beyond the degree `n - 1` wrap
+    /// around with their sign flipped, e.g. shifting by `n` negates the
+    /// plaintext. Use [`crate::bfv::Encoding::poly_mul_safe`] to bound the
+    /// degree of the values so that they do not wrap around.
+    ///
+    /// Returns an error if the parameters use a prime cyclotomic ring.
+    pub fn shift(&self, k: usize) -> Result<Ciphertext> {
+        let two_n = 2 * self.par.degree();
+        let mut out = self.clone();
+        for poly in out.iter_mut() {
+            poly.change_representation(Representation::PowerBasis);
+            poly.multiply_inverse_power_of_x(two_n - k % two_n)?;
+            poly.change_representation(Representation::Ntt);
+        }
+        out.seed = None;
+        Ok(out)
+    }
+
+    /// Rotate the coefficients of the plaintext to the left by `k` with the
+    /// Poly encoding, i.e. the coefficient of degree `i` moves to the degree
+    /// `i - k`, without any Galois key, e.g. to realign the response of a PIR
+    /// query so that the requested element is in the first coefficients. A
+    /// negative `k` rotates the coefficients to the right.
+    ///
+    /// This multiplies the ciphertext by the monomial `x^(-k)`, so that, as
+    /// with [`Ciphertext::shift`], the coefficients which wrap around have
+    /// their sign flipped: when rotating to the left by `0 < k < n`, the
+    /// coefficient of degree `i < k` moves to the degree `n + i - k` negated,
+    /// and when rotating to the right, the coefficient of degree `i >= n - k`
+    /// moves to the degree `i + k - n` negated. The coefficients which do not
+    /// wrap around keep their sign, and rotating by `n` negates the plaintext.
+    ///
+    /// Returns an error if the parameters use a prime cyclotomic ring.
+    pub fn rotate_coefficients(&self, k: isize) -> Result<Ciphertext> {
+        let two_n = 2 * self.par.degree() as isize;
+        self.shift((two_n - k.rem_euclid(two_n)) as usize)
+    }
+}
+
+#[cfg(all(test, not(feature = "server")))]
+mod tests {
+    use crate::bfv::{
+        BfvParameters, Ciphertext, Encoding, Multiplicator, Plaintext, RelinearizationKey,
+        SecretKey,
+    };
+    use crate::Error;
+    use fhe_traits::{FheDecoder, FheDecrypter, FheEncoder, FheEncrypter};
+    use rand::thread_rng;
+    extern crate alloc;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn shift() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(2, 16);
+        let t = params.plaintext();
+        let sk = SecretKey::random(&params, &mut rng);
+        let a = params.plaintext.random_vec(params.degree(), &mut rng);
+        let pt = Plaintext::try_encode(&a, Encoding::poly(), &params)?;
+        let ct: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+
+        for k in [0, 1, 5, 16, 21, 32, 35] {
+            // The coefficients wrap around negacyclically.
+            let mut expected = vec![0u64; params.degree()];
+            for (i, ai) in a.iter().enumerate() {
+                let j = (i + k) % (2 * params.degree());
+                if j < params.degree() {
+                    expected[j] = *ai
+                } else {
+                    expected[j - params.degree()] = (t - ai) % t
+                }
+            }
+            let shifted = sk.try_decrypt(&ct.shift(k)?)?;
+            assert_eq!(
+                Vec::<u64>::try_decode(&shifted, Encoding::poly())?,
+                expected
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn rotate_coefficients() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(2, 16);
+        let n = params.degree() as isize;
+        let t = params.plaintext();
+        let sk = SecretKey::random(&params, &mut rng);
+        let a = params.plaintext.random_vec(params.degree(), &mut rng);
+        let pt = Plaintext::try_encode(&a, Encoding::poly(), &params)?;
+        let ct: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+
+        for k in [0, 1, 5, 15, -1, -7, 16, -16, 37, -40, isize::MIN] {
+            // The coefficient of degree i moves to the degree i - k, negated
+            // each time it wraps around; the subtraction wraps modulo a
+            // multiple of 2 * n.
+            let mut expected = vec![0u64; params.degree()];
+            for (i, ai) in a.iter().enumerate() {
+                let j = (i as isize).wrapping_sub(k).rem_euclid(2 * n);
+                if j < n {
+                    expected[j as usize] = *ai
+                } else {
+                    expected[(j - n) as usize] = (t - ai) % t
+                }
+            }
+            let rotated = sk.try_decrypt(&ct.rotate_coefficients(k)?)?;
+            assert_eq!(
+                Vec::<u64>::try_decode(&rotated, Encoding::poly())?,
+                expected
+            );
+        }
+
+        // Rotating to the left and to the right are inverse operations.
+        let rotated = ct.rotate_coefficients(3)?.rotate_coefficients(-3)?;
+        let pt = sk.try_decrypt(&rotated)?;
+        assert_eq!(Vec::<u64>::try_decode(&pt, Encoding::poly())?, a);
+        Ok(())
+    }
+
+    #[test]
+    fn poly_mul_safe() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(2, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let rk = RelinearizationKey::new(&sk, &mut rng)?;
+        let multiplicator = Multiplicator::default(&rk)?;
+
+        // Polynomials of degree 7 are multiplied without wrapping around.
+        let encoding = Encoding::poly_mul_safe(7);
+        let a = [1u64, 2, 3, 4, 5, 6, 7, 8];
+        let b = [8u64, 7, 6, 5, 4, 3, 2, 1];
+        let mut expected = vec![0u64; params.degree()];
+        for (i, ai) in a.iter().enumerate() {
+            for (j, bj) in b.iter().enumerate() {
+                expected[i + j] += ai * bj
+            }
+        }
+        let ct_a = sk.try_encrypt(
+            &Plaintext::try_encode(&a, encoding.clone(), &params)?,
+            &mut rng,
+        )?;
+        let ct_b = sk.try_encrypt(
+            &Plaintext::try_encode(&b, encoding.clone(), &params)?,
+            &mut rng,
+        )?;
+        let pt = sk.try_decrypt(&multiplicator.multiply(&ct_a, &ct_b)?)?;
+        assert_eq!(Vec::<u64>::try_decode(&pt, encoding)?, expected);
+        Ok(())
+    }
+}
@@ -0,0 +1,271 @@
+//! Statistical reducers over batches of encrypted values.
+
+#[cfg(not(feature = "server"))]
+use crate::bfv::SecretKey;
+use crate::bfv::{
+    traits::TryConvertFrom, BfvParameters, Ciphertext, Encoding, EvaluationKey, Multiplicator,
+    Plaintext,
+};
+use crate::proto::bfv::EncryptedStatistic as EncryptedStatisticProto;
+use crate::{Error, Result};
+use fhe_traits::{DeserializeParametrized, FheEncoder, FheParametrized, Serialize};
+#[cfg(not(feature = "server"))]
+use fhe_traits::{FheDecoder, FheDecrypter};
+use prost::Message;
+extern crate alloc;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+#[cfg(not(feature = "server"))]
+use zeroize::Zeroizing;
+
+/// A statistic encrypted as a fraction whose numerator is encrypted, in every
+/// slot of a ciphertext, and whose denominator is public.
+///
+/// The numerator is an integer smaller than the plaintext modulus `t`, so that
+/// the statistic is recovered exactly at decryption, without any reduction
+/// modulo `t`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncryptedStatistic {
+    numerator: Ciphertext,
+    denominator: u64,
+}
+
+impl EncryptedStatistic {
+    /// Returns the encrypted numerator.
+    pub fn numerator(&self) -> &Ciphertext {
+        &self.numerator
+    }
+
+    /// Returns the public denominator.
+    pub fn denominator(&self) -> u64 {
+        self.denominator
+    }
+
+    /// Decrypt the statistic, and returns its numerator and its denominator.
+    #[cfg(not(feature = "server"))]
+    pub fn try_decrypt(&self, sk: &SecretKey) -> Result<(u64, u64)> {
+        let pt = Zeroizing::new(sk.try_decrypt(&self.numerator)?);
+        let numerator = Vec::<u64>::try_decode(&pt, Encoding::simd_at_level(pt.level))?;
+        Ok((numerator[0], self.denominator))
+    }
+}
+
+impl FheParametrized for EncryptedStatistic {
+    type Parameters = BfvParameters;
+}
+
+impl Serialize for EncryptedStatistic {
+    fn to_bytes(&self) -> Vec<u8> {
+        EncryptedStatisticProto {
+            numerator: Some((&self.numerator).into()),
+            denominator: self.denominator,
+        }
+        .encode_to_vec()
+    }
+}
+
+impl DeserializeParametrized for EncryptedStatistic {
+    type Error = Error;
+
+    fn from_bytes(bytes: &[u8], par: &Arc<BfvParameters>) -> Result<Self> {
+        let proto: EncryptedStatisticProto =
+            Message::decode(bytes).map_err(|_| Error::SerializationError)?;
+        match proto.numerator.as_ref() {
+            Some(numerator) if proto.denominator != 0 => Ok(Self {
+                numerator: Ciphertext::try_convert_from(numerator, par)?,
+                denominator: proto.denominator,
+            }),
+            _ => Err(Error::SerializationError),
+        }
+    }
+}
+
+/// Checks that the batch of `values` can contain `count` values, and that a
+/// numerator of up to `max_numerator` (None if it overflows a u128) is smaller
+/// than the plaintext modulus. Returns the sum of the ciphertexts.
+fn check_batch(
+    values: &[Ciphertext],
+    count: usize,
+    ek: &EvaluationKey,
+    max_numerator: Option<u128>,
+) -> Result<Ciphertext> {
+    let first = values.first().ok_or(Error::TooFewValues(0, 1))?;
+    let capacity = values.len() * first.par.degree();
+    if count == 0 || count > capacity {
+        return Err(Error::DefaultError(
+            "The count must be positive and at most the number of slots".to_string(),
+        ));
+    }
+    if values
+        .iter()
+        .any(|ct| ct.par != ek.par || ct.level != ek.ciphertext_level)
+    {
+        return Err(Error::DefaultError(
+            "Mismatched parameters or levels".to_string(),
+        ));
+    }
+    if !matches!(max_numerator, Some(m) if m < ek.par.plaintext() as u128) {
+        return Err(Error::DefaultError(
+            "The plaintext modulus is too small for this statistic".to_string(),
+        ));
+    }
+    let mut sum = first.clone();
+    values[1..].iter().for_each(|ct| sum += ct);
+    Ok(sum)
+}
+
+/// Computes the mean of the `count` values stored in the slots of the batch of
+/// SIMD-encoded ciphertexts `values`, as the encrypted sum of the values over
+/// the public denominator `count`. The slots beyond the `count` values must
+/// encrypt 0.
+///
+/// The values must all be at most `max_value`, so that the sum of the values
+/// is smaller than the plaintext modulus `t`; an error is returned otherwise.
+/// The evaluation key must support the inner sum at the level of `values`.
+///
+/// Depth and noise: this does not consume any multiplicative level; the
+/// noise grows with `values.len()` additions and one inner sum.
+pub fn mean(
+    values: &[Ciphertext],
+    count: usize,
+    max_value: u64,
+    ek: &EvaluationKey,
+) -> Result<EncryptedStatistic> {
+    let max_sum = (count as u128).checked_mul(max_value as u128);
+    let sum = check_batch(values, count, ek, max_sum)?;
+    Ok(EncryptedStatistic {
+        numerator: ek.computes_inner_sum(&sum)?,
+        denominator: count as u64,
+    })
+}
+
+/// Computes the population variance of the `count` values stored in the slots
+/// of the batch of SIMD-encoded ciphertexts `values`, as the encrypted
+/// `count * sum(x^2) - sum(x)^2` over the public denominator `count^2`. The
+/// slots beyond the `count` values must encrypt 0. The standard deviation is
+/// the square root of the decrypted variance.
+///
+/// The values must all be at most `max_value`, so that `count^2 * max_value^2`
+/// is smaller than the plaintext modulus `t`; an error is returned otherwise.
+/// The evaluation key must support the inner sum at the level of `values`, and
+/// the multiplicator must not switch the modulus after the multiplications.
+///
+/// Depth and noise: this consumes one multiplicative level. The squares of
+/// the ciphertexts and the square of their sum are computed using
+/// `multiplicator`, and the noise grows further with one inner sum and a
+/// multiplication by the constant `count`.
+pub fn variance(
+    values: &[Ciphertext],
+    count: usize,
+    max_value: u64,
+    ek: &EvaluationKey,
+    multiplicator: &Multiplicator,
+) -> Result<EncryptedStatistic> {
+    let max_numerator = (count as u128)
+        .checked_mul(max_value as u128)
+        .and_then(|m| m.checked_mul(m));
+    let sum = check_batch(values, count, ek, max_numerator)?;
+
+    let squares = values
+        .iter()
+        .map(|ct| multiplicator.multiply(ct, ct))
+        .collect::<Result<Vec<_>>>()?;
+    if squares[0].level != ek.ciphertext_level {
+        return Err(Error::DefaultError(
+            "The multiplicator must not switch the modulus".to_string(),
+        ));
+    }
+    let mut sum_of_squares = squares[0].clone();
+    squares[1..].iter().for_each(|ct| sum_of_squares += ct);
+    let sum_of_squares = ek.computes_inner_sum(&sum_of_squares)?;
+    let sum = ek.computes_inner_sum(&sum)?;
+
+    let count_pt = Plaintext::try_encode(
+        &[count as u64],
+        Encoding::poly_at_level(sum_of_squares.level),
+        &ek.par,
+    )?;
+    let mut numerator = &sum_of_squares * &count_pt;
+    numerator -= &multiplicator.multiply(&sum, &sum)?;
+    Ok(EncryptedStatistic {
+        numerator,
+        denominator: (count * count) as u64,
+    })
+}
+
+#[cfg(all(test, not(feature = "server")))]
+mod tests {
+    use super::{mean, variance, EncryptedStatistic};
+    use crate::bfv::{
+        BfvParametersBuilder, Ciphertext, Encoding, EvaluationKeyBuilder, Multiplicator, Plaintext,
+        RelinearizationKey, SecretKey,
+    };
+    use crate::Error;
+    use fhe_math::zq::primes::generate_prime;
+    use fhe_traits::{DeserializeParametrized, FheEncoder, FheEncrypter, Serialize};
+    use itertools::Itertools;
+    use rand::{thread_rng, Rng};
+    extern crate alloc;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn mean_and_variance() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParametersBuilder::new()
+            .set_degree(16)
+            .set_plaintext_modulus(generate_prime(40, 32, 1 << 40).unwrap())
+            .set_moduli_sizes(&[62, 62, 62])
+            .build_arc()?;
+        let sk = SecretKey::random(&params, &mut rng);
+        let rk = RelinearizationKey::new(&sk, &mut rng)?;
+        let multiplicator = Multiplicator::default(&rk)?;
+        let ek = EvaluationKeyBuilder::new(&sk)?
+            .enable_inner_sum()?
+            .build(&mut rng)?;
+
+        // 40 values in 3 ciphertexts, the last slots being 0.
+        let max_value = 1000;
+        let count = 40;
+        let values = (0..count)
+            .map(|_| rng.gen_range(0..=max_value))
+            .collect_vec();
+        let ciphertexts = values
+            .chunks(params.degree())
+            .map(|chunk| {
+                let pt = Plaintext::try_encode(chunk, Encoding::simd(), &params)?;
+                sk.try_encrypt(&pt, &mut rng)
+            })
+            .collect::<Result<Vec<Ciphertext>, Error>>()?;
+        assert_eq!(ciphertexts.len(), 3);
+
+        let sum = values.iter().sum::<u64>();
+        let sum_of_squares = values.iter().map(|v| v * v).sum::<u64>();
+
+        let m = mean(&ciphertexts, count, max_value, &ek)?;
+        assert_eq!(m.try_decrypt(&sk)?, (sum, count as u64));
+        assert_eq!(m.denominator(), count as u64);
+        assert_eq!(EncryptedStatistic::from_bytes(&m.to_bytes(), &params)?, m);
+
+        let v = variance(&ciphertexts, count, max_value, &ek, &multiplicator)?;
+        assert_eq!(
+            v.try_decrypt(&sk)?,
+            (
+                count as u64 * sum_of_squares - sum * sum,
+                (count * count) as u64
+            )
+        );
+
+        // The statistics could overflow the plaintext modulus.
+        assert!(mean(&ciphertexts, count, u64::MAX, &ek).is_err());
+        assert!(variance(&ciphertexts, count, 1 << 20, &ek, &multiplicator).is_err());
+        // Invalid counts and batches.
+        assert!(mean(&ciphertexts, 0, max_value, &ek).is_err());
+        assert!(mean(&ciphertexts, 49, max_value, &ek).is_err());
+        assert_eq!(
+            mean(&[], 1, max_value, &ek).unwrap_err(),
+            Error::TooFewValues(0, 1)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,246 @@
+//! Streaming evaluation over collections of ciphertexts.
+
+use crate::bfv::{Ciphertext, EvaluationKey, Plaintext};
+use crate::{Error, Result};
+extern crate alloc;
+use alloc::string::ToString;
+
+/// A lazy stream of [`Ciphertext`] which can be processed with bounded memory.
+///
+/// The stream wraps any iterator of `Result<Ciphertext>` (for example, an
+/// iterator deserializing ciphertexts one at a time from storage), and
+/// provides combinators which consume the ciphertexts one by one instead of
+/// collecting them into a vector. Errors are propagated through the stream.
+pub struct CiphertextStream<I> {
+    inner: I,
+}
+
+impl<I> CiphertextStream<I>
+where
+    I: Iterator<Item = Result<Ciphertext>>,
+{
+    /// Creates a stream from an iterator of `Result<Ciphertext>`.
+    pub fn new(inner: I) -> Self {
+        Self { inner }
+    }
+
+    /// Multiplies every ciphertext of the stream by the plaintext `pt`.
+    pub fn map_mul_plaintext<'a>(
+        self,
+        pt: &'a Plaintext,
+    ) -> CiphertextStream<impl Iterator<Item = Result<Ciphertext>> + 'a>
+    where
+        I: 'a,
+    {
+        CiphertextStream::new(self.inner.map(move |ct| {
+            let ct = ct?;
+            if ct.par != pt.par || ct.level != pt.level {
+                Err(Error::DefaultError(
+                    "Mismatched parameters or levels".to_string(),
+                ))
+            } else {
+                Ok(&ct * pt)
+            }
+        }))
+    }
+
+    /// Sums all the ciphertexts of the stream, keeping a single accumulator in
+    /// memory. Returns an error if the stream is empty, or if the ciphertexts
+    /// have different parameters, levels or sizes.
+    pub fn reduce_add(mut self) -> Result<Ciphertext> {
+        let mut acc = self.inner.next().ok_or(Error::TooFewValues(0, 1))??;
+        for ct in self.inner {
+            let ct = ct?;
+            if ct.par != acc.par || ct.level != acc.level || ct.len() != acc.len() {
+                return Err(Error::DefaultError(
+                    "Mismatched parameters, levels or sizes".to_string(),
+                ));
+            }
+            acc += &ct;
+        }
+        Ok(acc)
+    }
+
+    /// Groups the stream into chunks of `chunk_size` ciphertexts, and folds
+    /// each chunk into a single ciphertext: the j-th ciphertext of a chunk is
+    /// rotated to the left by j columns, and the rotated ciphertexts are
+    /// summed. This packs the values stored in the first slot of each row of
+    /// several ciphertexts into distinct slots of a single ciphertext.
+    ///
+    /// The evaluation key must support the column rotations by 1, ...,
+    /// `chunk_size - 1`; the last chunk may be smaller than `chunk_size`.
+    pub fn chunked_rotfold(
+        self,
+        chunk_size: usize,
+        ek: &EvaluationKey,
+    ) -> Result<CiphertextStream<RotFold<'_, I>>> {
+        if chunk_size == 0 {
+            return Err(Error::DefaultError(
+                "The chunk size must be positive".to_string(),
+            ));
+        }
+        if let Some(i) = (1..chunk_size).find(|i| !ek.supports_column_rotation_by(*i)) {
+            return Err(Error::DefaultError(alloc::format!(
+                "The evaluation key does not support the column rotation by {i}"
+            )));
+        }
+        Ok(CiphertextStream::new(RotFold {
+            inner: self.inner,
+            chunk_size,
+            ek,
+        }))
+    }
+}
+
+impl<I> Iterator for CiphertextStream<I>
+where
+    I: Iterator<Item = Result<Ciphertext>>,
+{
+    type Item = Result<Ciphertext>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+/// Iterator returned by [`CiphertextStream::chunked_rotfold`].
+pub struct RotFold<'a, I> {
+    inner: I,
+    chunk_size: usize,
+    ek: &'a EvaluationKey,
+}
+
+impl<I> RotFold<'_, I>
+where
+    I: Iterator<Item = Result<Ciphertext>>,
+{
+    fn fold_chunk(&mut self, first: Ciphertext) -> Result<Ciphertext> {
+        let mut acc = first;
+        for j in 1..self.chunk_size {
+            if let Some(ct) = self.inner.next() {
+                let ct = ct?;
+                if ct.par != acc.par || ct.level != acc.level {
+                    return Err(Error::DefaultError(
+                        "Mismatched parameters or levels".to_string(),
+                    ));
+                }
+                acc += &self.ek.rotates_columns_by(&ct, j)?;
+            } else {
+                break;
+            }
+        }
+        Ok(acc)
+    }
+}
+
+impl<I> Iterator for RotFold<'_, I>
+where
+    I: Iterator<Item = Result<Ciphertext>>,
+{
+    type Item = Result<Ciphertext>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let first = self.inner.next()?;
+        Some(first.and_then(|first| self.fold_chunk(first)))
+    }
+}
+
+#[cfg(all(test, not(feature = "server")))]
+mod tests {
+    use super::CiphertextStream;
+    use crate::bfv::{BfvParameters, Encoding, EvaluationKeyBuilder, Plaintext, SecretKey};
+    use crate::Error;
+    use fhe_traits::{FheDecoder, FheDecrypter, FheEncoder, FheEncrypter};
+    use itertools::Itertools;
+    use rand::thread_rng;
+    extern crate alloc;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn map_mul_plaintext_and_reduce_add() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(2, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+
+        let values = (0..10)
+            .map(|_| params.plaintext.random_vec(params.degree(), &mut rng))
+            .collect_vec();
+        let scalar = params.plaintext.random_vec(params.degree(), &mut rng);
+        let pt_scalar = Plaintext::try_encode(&scalar, Encoding::simd(), &params)?;
+
+        let mut expected = vec![0u64; params.degree()];
+        for v in &values {
+            let mut w = v.clone();
+            params.plaintext.mul_vec(&mut w, &scalar);
+            params.plaintext.add_vec(&mut expected, &w);
+        }
+
+        let ct = CiphertextStream::new(values.iter().map(|v| {
+            let pt = Plaintext::try_encode(v, Encoding::simd(), &params)?;
+            sk.try_encrypt(&pt, &mut thread_rng())
+        }))
+        .map_mul_plaintext(&pt_scalar)
+        .reduce_add()?;
+
+        let pt = sk.try_decrypt(&ct)?;
+        assert_eq!(Vec::<u64>::try_decode(&pt, Encoding::simd())?, expected);
+
+        let empty = CiphertextStream::new(core::iter::empty());
+        assert_eq!(empty.reduce_add().unwrap_err(), Error::TooFewValues(0, 1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn chunked_rotfold() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(2, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let row_size = params.degree() / 2;
+        let chunk_size = 3;
+
+        let mut builder = EvaluationKeyBuilder::new(&sk)?;
+        builder.enable_column_rotation(1)?;
+        let ek = builder.build(&mut rng)?;
+        assert!(CiphertextStream::new(core::iter::empty())
+            .chunked_rotfold(chunk_size, &ek)
+            .is_err());
+        builder.enable_column_rotation(2)?;
+        let ek = builder.build(&mut rng)?;
+
+        // Each ciphertext encrypts its index in the first slot of each row.
+        let values = (0..7u64)
+            .map(|i| {
+                let mut v = vec![0u64; params.degree()];
+                v[0] = i + 1;
+                v[row_size] = i + 1;
+                v
+            })
+            .collect_vec();
+
+        let folded = CiphertextStream::new(values.iter().map(|v| {
+            let pt = Plaintext::try_encode(v, Encoding::simd(), &params)?;
+            sk.try_encrypt(&pt, &mut thread_rng())
+        }))
+        .chunked_rotfold(chunk_size, &ek)?
+        .collect::<Result<Vec<_>, Error>>()?;
+        assert_eq!(folded.len(), 3);
+
+        for (k, ct) in folded.iter().enumerate() {
+            let pt = sk.try_decrypt(ct)?;
+            let w = Vec::<u64>::try_decode(&pt, Encoding::simd())?;
+            let mut expected = vec![0u64; params.degree()];
+            for j in 0..chunk_size {
+                let index = k * chunk_size + j;
+                if index < values.len() {
+                    expected[(row_size - j) % row_size] = index as u64 + 1;
+                    expected[row_size + (row_size - j) % row_size] = index as u64 + 1;
+                }
+            }
+            assert_eq!(w, expected);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,231 @@
+//! Equality matching of encrypted byte strings.
+//!
+//! A record is a byte string of a fixed length `len`, stored one byte per slot
+//! in a group of consecutive SIMD slots of a row; the size of the groups is
+//! `len` rounded up to a power of two, and the padding slots are zero. The
+//! equality of a record with a plaintext string is tested byte by byte using
+//! Fermat's little theorem, `x == 0` if and only if `1 - x^(t - 1) == 1`, and
+//! the results of the bytes of a group are combined with a tree of rotations
+//! and multiplications.
+
+use super::super::encoding::EncodingEnum;
+use crate::bfv::{BfvParameters, Ciphertext, Encoding, EvaluationKey, Multiplicator, Plaintext};
+use crate::{Error, Result};
+use fhe_traits::{FheDecoder, FheEncoder};
+use fhe_util::is_prime;
+extern crate alloc;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Encoder of fixed-length byte strings in groups of SIMD slots, for the
+/// equality matching of [`string_equals`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringEncoder {
+    par: Arc<BfvParameters>,
+    len: usize,
+}
+
+impl StringEncoder {
+    /// Create an encoder of byte strings of `len` bytes.
+    ///
+    /// Returns an error if the parameters do not support the SIMD encoding, if
+    /// the plaintext modulus is not a prime larger than 255, or if `len`
+    /// rounded up to a power of two does not divide the number of slots in a
+    /// row.
+    pub fn new(par: &Arc<BfvParameters>, len: usize) -> Result<Self> {
+        if par.op.is_none() {
+            return Err(Error::EncodingNotSupported(EncodingEnum::Simd.to_string()));
+        }
+        if par.plaintext() <= 255 || !is_prime(par.plaintext()) {
+            return Err(Error::DefaultError(
+                "String matching requires a prime plaintext modulus larger than 255".to_string(),
+            ));
+        }
+        if len == 0 || (par.degree() / 2) % len.next_power_of_two() != 0 {
+            return Err(Error::DefaultError("Invalid string length".to_string()));
+        }
+        Ok(Self {
+            par: par.clone(),
+            len,
+        })
+    }
+
+    /// Returns the length of the strings.
+    pub fn string_len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns the number of slots of a group.
+    fn group_size(&self) -> usize {
+        self.len.next_power_of_two()
+    }
+
+    /// Returns the number of records stored in a plaintext.
+    pub fn num_records(&self) -> usize {
+        self.par.degree() / self.group_size()
+    }
+
+    /// Encode strings of exactly `len` bytes, one record per string, in a SIMD
+    /// [`Plaintext`] at level `level`. The records beyond the strings are
+    /// zero.
+    pub fn try_encode<S: AsRef<[u8]>>(&self, strings: &[S], level: usize) -> Result<Plaintext> {
+        if strings.len() > self.num_records() {
+            return Err(Error::TooManyValues(strings.len(), self.num_records()));
+        }
+        let mut slots = vec![0u64; self.par.degree()];
+        for (record, string) in strings.iter().enumerate() {
+            let string = string.as_ref();
+            if string.len() != self.len {
+                return Err(Error::DefaultError("Invalid string length".to_string()));
+            }
+            let start = record * self.group_size();
+            slots[start..start + self.len]
+                .iter_mut()
+                .zip(string)
+                .for_each(|(slot, byte)| *slot = *byte as u64);
+        }
+        Plaintext::try_encode(&slots, Encoding::simd_at_level(level), &self.par)
+    }
+
+    /// Decode the output of [`string_equals`], and returns whether each record
+    /// matched.
+    pub fn try_decode_matches(&self, pt: &Plaintext) -> Result<Vec<bool>> {
+        let slots = Vec::<u64>::try_decode(pt, Encoding::simd_at_level(pt.level))?;
+        Ok(slots
+            .iter()
+            .step_by(self.group_size())
+            .map(|slot| *slot == 1)
+            .collect())
+    }
+
+    /// Encode 1 in the first slot of every group, and 0 elsewhere.
+    fn first_slots(&self, level: usize) -> Result<Plaintext> {
+        let mut slots = vec![0u64; self.par.degree()];
+        slots
+            .iter_mut()
+            .step_by(self.group_size())
+            .for_each(|slot| *slot = 1);
+        Plaintext::try_encode(&slots, Encoding::simd_at_level(level), &self.par)
+    }
+}
+
+/// Computes `ct^e` for `e > 0` by square-and-multiply.
+fn pow(ct: &Ciphertext, mut e: u64, multiplicator: &Multiplicator) -> Result<Ciphertext> {
+    let mut base = ct.clone();
+    let mut result: Option<Ciphertext> = None;
+    loop {
+        if e & 1 == 1 {
+            result = Some(match result {
+                Some(r) => multiplicator.multiply(&r, &base)?,
+                None => base.clone(),
+            });
+        }
+        e >>= 1;
+        if e == 0 {
+            return Ok(result.unwrap());
+        }
+        base = multiplicator.multiply(&base, &base)?;
+    }
+}
+
+/// Compares every record encrypted in `ct`, encoded with a [`StringEncoder`]
+/// of strings of `pattern.len()` bytes, with the plaintext string `pattern`.
+/// Returns a ciphertext encrypting, in the first slot of the group of each
+/// record, 1 if the record equals `pattern` and 0 otherwise; the other slots
+/// encrypt 0. The output is decoded with
+/// [`StringEncoder::try_decode_matches`].
+///
+/// The records beyond the encoded strings are zero, and only match a pattern
+/// of zeros. The evaluation key must support the column rotations by the
+/// powers of two smaller than the size of the groups, and the multiplicator
+/// must not switch the modulus.
+///
+/// Depth and noise: this consumes `ceil(log2(t - 1))` multiplicative levels
+/// for the per-byte equality tests, where `t` is the plaintext modulus, and
+/// `log2(group_size)` for the AND-tree reduction, followed by one
+/// multiplication by a plaintext. With `t = 257`, the smallest modulus
+/// supporting bytes, the equality tests consume 8 levels.
+pub fn string_equals(
+    ct: &Ciphertext,
+    pattern: &[u8],
+    ek: &EvaluationKey,
+    multiplicator: &Multiplicator,
+) -> Result<Ciphertext> {
+    let encoder = StringEncoder::new(&ct.par, pattern.len())?;
+    let patterns = vec![pattern; encoder.num_records()];
+    let difference = ct - &encoder.try_encode(&patterns, ct.level)?;
+
+    // The bytes are equal if and only if their difference to the power t - 1
+    // is 0, and it is 1 otherwise.
+    let t = ct.par.plaintext();
+    let ones = Plaintext::try_encode(&[1u64], Encoding::poly_at_level(ct.level), &ct.par)?;
+    let mut matches = -pow(&difference, t - 1, multiplicator)? + &ones;
+
+    let mut step = 1;
+    while step < encoder.group_size() {
+        let rotated = ek.rotates_columns_by(&matches, step)?;
+        matches = multiplicator.multiply(&matches, &rotated)?;
+        step *= 2;
+    }
+    Ok(matches * &encoder.first_slots(ct.level)?)
+}
+
+#[cfg(all(test, not(feature = "server")))]
+mod tests {
+    use super::{string_equals, StringEncoder};
+    use crate::bfv::{
+        BfvParametersBuilder, EvaluationKeyBuilder, Multiplicator, RelinearizationKey, SecretKey,
+    };
+    use crate::Error;
+    use fhe_traits::{FheDecrypter, FheEncrypter};
+    use rand::thread_rng;
+
+    #[test]
+    fn string_equality() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParametersBuilder::new()
+            .set_degree(16)
+            .set_plaintext_modulus(257)
+            .set_moduli_sizes(&[62, 62, 62, 62, 62])
+            .build_arc()?;
+        let sk = SecretKey::random(&params, &mut rng);
+        let rk = RelinearizationKey::new(&sk, &mut rng)?;
+        let multiplicator = Multiplicator::default(&rk)?;
+        let ek = EvaluationKeyBuilder::new(&sk)?
+            .enable_power_of_two_rotations()?
+            .build(&mut rng)?;
+
+        // Records of 3 bytes in groups of 4 slots.
+        let encoder = StringEncoder::new(&params, 3)?;
+        assert_eq!(encoder.string_len(), 3);
+        assert_eq!(encoder.num_records(), 4);
+        let records: [&[u8]; 3] = [b"abc", b"abd", b"\xffbc"];
+        let ct = sk.try_encrypt(&encoder.try_encode(&records, 0)?, &mut rng)?;
+
+        for (pattern, expected) in [
+            (b"abc", [true, false, false, false]),
+            (b"abd", [false, true, false, false]),
+            (b"\xffbc", [false, false, true, false]),
+            (b"xyz", [false, false, false, false]),
+            (b"\0\0\0", [false, false, false, true]),
+        ] {
+            let matches = string_equals(&ct, pattern, &ek, &multiplicator)?;
+            let pt = sk.try_decrypt(&matches)?;
+            assert_eq!(encoder.try_decode_matches(&pt)?, expected);
+        }
+
+        // Invalid lengths and parameters.
+        assert!(encoder.try_encode(&[b"ab"], 0).is_err());
+        assert!(string_equals(&ct, b"abcdefghi", &ek, &multiplicator).is_err());
+        assert!(StringEncoder::new(&params, 16).is_err());
+        let small_t = BfvParametersBuilder::new()
+            .set_degree(16)
+            .set_plaintext_modulus(97)
+            .set_moduli_sizes(&[62])
+            .build_arc()?;
+        assert!(StringEncoder::new(&small_t, 3).is_err());
+        Ok(())
+    }
+}
@@ -3,21 +3,28 @@
 use crate::proto::bfv::Parameters;
 use crate::{Error, ParametersError, Result};
 use alloc::fmt::Debug;
+use core::hash::{Hash, Hasher};
 use fhe_math::{
-    ntt::NttOperator,
-    rns::{RnsContext, ScalingFactor},
-    rq::{scaler::Scaler, traits::TryConvertFrom, Context, Poly, Representation},
+    ntt::{prime_ntt_order, NttOperator},
+    rns::RnsContext,
+    rq::{traits::TryConvertFrom, Context, Poly, Representation},
     zq::{primes::generate_prime, Modulus},
 };
+#[cfg(not(feature = "client"))]
+use fhe_math::{rns::ScalingFactor, rq::scaler::Scaler};
 use fhe_traits::{Deserialize, FheParameters, Serialize};
+use fhe_util::is_prime;
 use hashbrown::HashMap;
-use itertools::Itertools;
+use itertools::{izip, Itertools};
+#[cfg(not(feature = "client"))]
 use num_bigint::BigUint;
 use num_traits::ToPrimitive;
 use prost::Message;
+use sha2::{Digest, Sha256};
 extern crate alloc;
 use alloc::borrow::ToOwned;
 use alloc::boxed::Box;
+use alloc::format;
 use alloc::string::ToString;
 use alloc::sync::Arc;
 use alloc::vec;
@@ -58,15 +65,27 @@ pub struct BfvParameters {
     pub(crate) q_mod_t: Box<[u64]>,
 
     /// Down scaler for the plaintext
+    #[cfg(not(feature = "client"))]
     pub(crate) scalers: Box<[Scaler]>,
 
     /// Plaintext Modulus
     pub(crate) plaintext: Modulus,
 
     // Parameters for the multiplications
+    #[cfg(not(feature = "client"))]
     pub(crate) mul_params: Box<[MultiplicationParameters]>,
 
     pub(crate) matrix_reps_index_map: Box<[usize]>,
+
+    /// Index of the prime cyclotomic ring, if the parameters do not use the
+    /// power-of-two cyclotomic ring.
+    prime_cyclotomic_index: Option<usize>,
+
+    /// Whether the SIMD precomputations were disabled by the builder.
+    batching_disabled: bool,
+
+    /// Identifier of the parameters, computed once by the builder.
+    id: u64,
 }
 
 impl Debug for BfvParameters {
@@ -91,6 +110,20 @@ impl Debug for BfvParameters {
 
 impl FheParameters for BfvParameters {}
 
+// Implement the hash manually from the serialized fields, which determine all
+// the other fields, so that the hash is consistent with the equality and is
+// the same across runs for a deterministic hasher.
+impl Hash for BfvParameters {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.polynomial_degree.hash(state);
+        self.plaintext_modulus.hash(state);
+        self.moduli.hash(state);
+        self.variance.hash(state);
+        self.prime_cyclotomic_index.hash(state);
+        self.batching_disabled.hash(state);
+    }
+}
+
 unsafe impl Send for BfvParameters {}
 
 impl BfvParameters {
@@ -99,6 +132,14 @@ impl BfvParameters {
         self.polynomial_degree
     }
 
+    /// Returns the index `m` of the cyclotomic ring, i.e. `2 * degree` for the
+    /// power-of-two cyclotomic ring `ZZ[x] / (x^degree + 1)`, and `degree + 1`
+    /// for the prime cyclotomic ring `ZZ[x] / (1 + x + ... + x^degree)`.
+    pub fn cyclotomic_index(&self) -> usize {
+        self.prime_cyclotomic_index
+            .unwrap_or(2 * self.polynomial_degree)
+    }
+
     /// Returns a reference to the ciphertext moduli
     pub fn moduli(&self) -> &[u64] {
         &self.moduli
@@ -114,6 +155,66 @@ impl BfvParameters {
         self.plaintext_modulus
     }
 
+    /// Returns a short identifier of the parameters, computed from a hash of
+    /// their serialization. It is meant to tell parameter sets apart in logs.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Deserialize parameters serialized by an earlier release, checking that
+    /// this release generates the same parameters from their description, i.e.
+    /// from their degree, plaintext modulus, variance and moduli sizes: the
+    /// same ciphertext moduli, and hence the same scaling polynomials `delta`
+    /// and the same scalers of the decryption.
+    ///
+    /// The serialized parameters hold the moduli themselves and always
+    /// deserialize to the same parameters; this detects a change in the
+    /// generation of the moduli, after which the parameters built from the
+    /// same moduli sizes would silently differ. Returns an error describing
+    /// every difference otherwise, e.g. for parameters whose moduli were not
+    /// generated from their sizes but set with
+    /// [`BfvParametersBuilder::set_moduli`].
+    pub fn migrate_from(bytes: &[u8]) -> Result<Arc<Self>> {
+        let serialized = Self::try_deserialize(bytes)?;
+        let mut builder = BfvParametersBuilder::new();
+        match serialized.prime_cyclotomic_index {
+            Some(m) => builder.set_prime_cyclotomic_index(m),
+            None => builder.set_degree(serialized.polynomial_degree),
+        };
+        if serialized.batching_disabled {
+            builder.disable_batching();
+        }
+        let generated = builder
+            .set_plaintext_modulus(serialized.plaintext_modulus)
+            .set_variance(serialized.variance)
+            .set_moduli_sizes(&serialized.moduli_sizes)
+            .build()?;
+
+        let mut differences = vec![];
+        for (i, (qi, gi)) in izip!(serialized.moduli(), generated.moduli()).enumerate() {
+            if qi != gi {
+                differences.push(format!(
+                    "modulus {i} is {qi} in the serialized parameters and {gi} in this release"
+                ));
+            }
+        }
+        let same_moduli = differences.is_empty();
+        if same_moduli && serialized.delta != generated.delta {
+            differences.push("the scaling polynomials delta differ".to_string());
+        }
+        #[cfg(not(feature = "client"))]
+        if same_moduli && serialized.scalers != generated.scalers {
+            differences.push("the scalers of the decryption differ".to_string());
+        }
+        if differences.is_empty() {
+            Ok(Arc::new(serialized))
+        } else {
+            Err(Error::ParametersError(ParametersError::MigrationMismatch(
+                differences.join(", "),
+            )))
+        }
+    }
+
     /// Returns the maximum level allowed by these parameters.
     pub fn max_level(&self) -> usize {
         self.moduli.len() - 1
@@ -188,17 +289,31 @@ impl BfvParameters {
         params
     }
 
+    /// Returns default parameters for tests.
     #[cfg(test)]
     pub fn default_arc(num_moduli: usize, degree: usize) -> Arc<Self> {
         if !degree.is_power_of_two() || degree < 8 {
             panic!("Invalid degree");
         }
+        Self::insecure_toy(num_moduli, degree).unwrap()
+    }
+
+    /// Returns toy parameters with `num_moduli` ciphertext moduli of 62 bits
+    /// and a small `degree`, e.g. 8 or 16, with the plaintext modulus 1153,
+    /// which supports the SIMD encoding for the degrees up to 64.
+    ///
+    /// These parameters are INSECURE, and only meant to write fast unit tests,
+    /// e.g. in the test suites of the crates depending on `fhe`.
+    ///
+    /// Returns an error if the degree is not a power of two larger than or
+    /// equal to 8, or if `num_moduli` is 0.
+    #[cfg(any(test, feature = "insecure-toys"))]
+    pub fn insecure_toy(num_moduli: usize, degree: usize) -> Result<Arc<Self>> {
         BfvParametersBuilder::new()
             .set_degree(degree)
             .set_plaintext_modulus(1153)
             .set_moduli_sizes(&vec![62usize; num_moduli])
             .build_arc()
-            .unwrap()
     }
 }
 
@@ -206,10 +321,12 @@ impl BfvParameters {
 #[derive(Debug)]
 pub struct BfvParametersBuilder {
     degree: usize,
+    prime_cyclotomic_index: Option<usize>,
     plaintext: u64,
     variance: usize,
     ciphertext_moduli: Vec<u64>,
     ciphertext_moduli_sizes: Vec<usize>,
+    disable_batching: bool,
 }
 
 impl BfvParametersBuilder {
@@ -218,10 +335,12 @@ impl BfvParametersBuilder {
     pub fn new() -> Self {
         Self {
             degree: Default::default(),
+            prime_cyclotomic_index: None,
             plaintext: Default::default(),
             variance: 10,
             ciphertext_moduli: Default::default(),
             ciphertext_moduli_sizes: Default::default(),
+            disable_batching: false,
         }
     }
 
@@ -232,6 +351,27 @@ impl BfvParametersBuilder {
         self
     }
 
+    /// Uses the prime cyclotomic ring `ZZ[x] / (1 + x + ... + x^(m - 1))` of
+    /// degree `m - 1` instead of the power-of-two cyclotomic ring. Returns an
+    /// error if `m` is not an odd prime. Only one of `set_degree` and
+    /// `set_prime_cyclotomic_index` can be specified.
+    ///
+    /// The SIMD encoding and the substitutions, and hence the rotations, are
+    /// not supported in prime cyclotomic rings.
+    pub fn set_prime_cyclotomic_index(&mut self, m: usize) -> &mut Self {
+        self.prime_cyclotomic_index = Some(m);
+        self
+    }
+
+    /// Disables the SIMD encoding, i.e. the batching of values in the slots of
+    /// the plaintexts, so that the plaintext NTT operator and the index map of
+    /// the slots are not precomputed, e.g. for applications only using the
+    /// Poly encoding. The encoders then return an error with the SIMD encoding.
+    pub fn disable_batching(&mut self) -> &mut Self {
+        self.disable_batching = true;
+        self
+    }
+
     /// Sets the plaintext modulus. Returns an error if the plaintext is not
     /// between 2 and 2^62 - 1.
     pub fn set_plaintext_modulus(&mut self, plaintext: u64) -> &mut Self {
@@ -262,8 +402,9 @@ impl BfvParametersBuilder {
         self
     }
 
-    /// Generate ciphertext moduli with the specified sizes
-    fn generate_moduli(moduli_sizes: &[usize], degree: usize) -> Result<Vec<u64>> {
+    /// Generate ciphertext moduli with the specified sizes, congruent to 1
+    /// modulo `ntt_order`.
+    fn generate_moduli(moduli_sizes: &[usize], degree: usize, ntt_order: u64) -> Result<Vec<u64>> {
         let mut moduli = vec![];
         for size in moduli_sizes {
             if *size > 62 || *size < 10 {
@@ -274,7 +415,7 @@ impl BfvParametersBuilder {
 
             let mut upper_bound = 1 << size;
             loop {
-                if let Some(prime) = generate_prime(*size, 2 * degree as u64, upper_bound) {
+                if let Some(prime) = generate_prime(*size, ntt_order, upper_bound) {
                     if !moduli.contains(&prime) {
                         moduli.push(prime);
                         break;
@@ -292,6 +433,46 @@ impl BfvParametersBuilder {
         Ok(moduli)
     }
 
+    /// Check that the ciphertext moduli are distinct primes congruent to 1
+    /// modulo `ntt_order`, and that they are compatible with the plaintext
+    /// modulus.
+    fn validate_moduli(moduli: &[u64], ntt_order: u64, plaintext: u64) -> Result<()> {
+        for (i, m) in moduli.iter().enumerate() {
+            if moduli[..i].contains(m) {
+                return Err(Error::ParametersError(ParametersError::InvalidModulus(
+                    *m,
+                    "it is specified more than once".to_string(),
+                )));
+            }
+            if *m >> 62 != 0 || *m % ntt_order != 1 || !is_prime(*m) {
+                return Err(Error::ParametersError(ParametersError::InvalidModulus(
+                    *m,
+                    format!(
+                        "it must be a prime of at most 62 bits congruent to 1 modulo {ntt_order}"
+                    ),
+                )));
+            }
+            if plaintext % *m == 0 {
+                return Err(Error::ParametersError(ParametersError::InvalidModulus(
+                    *m,
+                    format!("it is not coprime with the plaintext modulus {plaintext}"),
+                )));
+            }
+        }
+
+        // The plaintexts are decrypted in the context of the first modulus, which
+        // must also remain larger than the plaintext modulus at the last level.
+        if plaintext >= moduli[0] {
+            return Err(Error::ParametersError(ParametersError::InvalidPlaintext(
+                format!(
+                    "The plaintext modulus {plaintext} must be smaller than the first modulus {}",
+                    moduli[0]
+                ),
+            )));
+        }
+        Ok(())
+    }
+
     /// Build a new `BfvParameters` inside an `Arc`.
     pub fn build_arc(&self) -> Result<Arc<BfvParameters>> {
         self.build().map(Arc::new)
@@ -299,12 +480,38 @@ impl BfvParametersBuilder {
 
     /// Build a new `BfvParameters`.
     pub fn build(&self) -> Result<BfvParameters> {
-        // Check that the degree is a power of 2 (and large enough).
-        if self.degree < 8 || !self.degree.is_power_of_two() {
-            return Err(Error::ParametersError(ParametersError::InvalidDegree(
-                self.degree,
-            )));
-        }
+        // Check the ring, which is either the power-of-two cyclotomic ring of
+        // the specified degree, or a prime cyclotomic ring. The moduli must be
+        // congruent to 1 modulo `ntt_order` to support the NTT in this ring.
+        let (degree, ntt_order) = match self.prime_cyclotomic_index {
+            Some(_) if self.degree != 0 => {
+                return Err(Error::ParametersError(ParametersError::TooManySpecified(
+                    "Only one of `degree` and `prime_cyclotomic_index` can be specified"
+                        .to_string(),
+                )));
+            }
+            Some(m) => {
+                if m < 3 || !is_prime(m as u64) {
+                    return Err(Error::ParametersError(
+                        ParametersError::InvalidCyclotomicIndex(m),
+                    ));
+                }
+                (m - 1, prime_ntt_order(m))
+            }
+            None => {
+                // Check that the degree is a power of 2 (and large enough).
+                if self.degree < 8 || !self.degree.is_power_of_two() {
+                    return Err(Error::ParametersError(ParametersError::InvalidDegree(
+                        self.degree,
+                    )));
+                }
+                (self.degree, 2 * self.degree as u64)
+            }
+        };
+        let new_ctx = |moduli: &[u64]| match self.prime_cyclotomic_index {
+            Some(m) => Context::new_prime_cyclotomic_arc(moduli, m),
+            None => Context::new_arc(moduli, degree),
+        };
 
         // This checks that the plaintext modulus is valid.
         // TODO: Check bound on the plaintext modulus.
@@ -329,8 +536,9 @@ impl BfvParametersBuilder {
         // Get or generate the moduli
         let mut moduli = self.ciphertext_moduli.clone();
         if !self.ciphertext_moduli_sizes.is_empty() {
-            moduli = Self::generate_moduli(&self.ciphertext_moduli_sizes, self.degree)?
+            moduli = Self::generate_moduli(&self.ciphertext_moduli_sizes, degree, ntt_order)?
         }
+        Self::validate_moduli(&moduli, ntt_order, self.plaintext)?;
 
         // Recomputes the moduli sizes
         let moduli_sizes = moduli
@@ -339,18 +547,28 @@ impl BfvParametersBuilder {
             .collect_vec();
 
         // Create n+1 moduli of 62 bits for multiplication.
-        let mut extended_basis = Vec::with_capacity(moduli.len() + 1);
-        let mut upper_bound = 1 << 62;
-        while extended_basis.len() != moduli.len() + 1 {
-            upper_bound = generate_prime(62, 2 * self.degree as u64, upper_bound).unwrap();
-            if !extended_basis.contains(&upper_bound) && !moduli.contains(&upper_bound) {
-                extended_basis.push(upper_bound)
+        #[cfg(not(feature = "client"))]
+        let extended_basis = {
+            let mut extended_basis = Vec::with_capacity(moduli.len() + 1);
+            let mut upper_bound = 1 << 62;
+            while extended_basis.len() != moduli.len() + 1 {
+                upper_bound = generate_prime(62, ntt_order, upper_bound).unwrap();
+                if !extended_basis.contains(&upper_bound) && !moduli.contains(&upper_bound) {
+                    extended_basis.push(upper_bound)
+                }
             }
-        }
+            extended_basis
+        };
 
-        let op = NttOperator::new(&plaintext_modulus, self.degree);
+        // The SIMD encoding is only supported in the power-of-two cyclotomic ring.
+        let op = if self.prime_cyclotomic_index.is_none() && !self.disable_batching {
+            NttOperator::new(&plaintext_modulus, degree)
+        } else {
+            None
+        };
 
-        let plaintext_ctx = Context::new_arc(&moduli[..1], self.degree)?;
+        #[cfg(not(feature = "client"))]
+        let plaintext_ctx = new_ctx(&moduli[..1])?;
 
         let mut delta_rests = vec![];
         for m in &moduli {
@@ -361,11 +579,13 @@ impl BfvParametersBuilder {
         let mut ctx = Vec::with_capacity(moduli.len());
         let mut delta = Vec::with_capacity(moduli.len());
         let mut q_mod_t = Vec::with_capacity(moduli.len());
+        #[cfg(not(feature = "client"))]
         let mut scalers = Vec::with_capacity(moduli.len());
+        #[cfg(not(feature = "client"))]
         let mut mul_params = Vec::with_capacity(moduli.len());
         for i in 0..moduli.len() {
             let rns = RnsContext::new(&moduli[..moduli.len() - i])?;
-            let ctx_i = Context::new_arc(&moduli[..moduli.len() - i], self.degree)?;
+            let ctx_i = new_ctx(&moduli[..moduli.len() - i])?;
             let mut p = Poly::try_convert_from(
                 &[rns.lift((&delta_rests).into())],
                 &ctx_i,
@@ -377,49 +597,56 @@ impl BfvParametersBuilder {
 
             q_mod_t.push((rns.modulus() % *plaintext_modulus).to_u64().unwrap());
 
-            scalers.push(Scaler::new(
-                &ctx_i,
-                &plaintext_ctx,
-                ScalingFactor::new(&BigUint::from(*plaintext_modulus), rns.modulus()),
-            )?);
-
-            // For the first multiplication, we want to extend to a context that
-            // is ~60 bits larger.
-            let modulus_size = moduli_sizes[..moduli_sizes.len() - i].iter().sum::<usize>();
-            let n_moduli = (modulus_size + 60).div_ceil(62);
-            let mut mul_1_moduli = vec![];
-            mul_1_moduli.append(&mut moduli[..moduli_sizes.len() - i].to_vec());
-            mul_1_moduli.append(&mut extended_basis[..n_moduli].to_vec());
-            let mul_1_ctx = Context::new_arc(&mul_1_moduli, self.degree)?;
-            mul_params.push(MultiplicationParameters::new(
-                &ctx_i,
-                &mul_1_ctx,
-                ScalingFactor::one(),
-                ScalingFactor::new(&BigUint::from(*plaintext_modulus), ctx_i.modulus()),
-            )?);
+            // The client build neither decrypts nor multiplies ciphertexts.
+            #[cfg(not(feature = "client"))]
+            {
+                scalers.push(Scaler::new(
+                    &ctx_i,
+                    &plaintext_ctx,
+                    ScalingFactor::new(&BigUint::from(*plaintext_modulus), rns.modulus()),
+                )?);
+
+                // For the first multiplication, we want to extend to a context
+                // that is ~60 bits larger.
+                let modulus_size = moduli_sizes[..moduli_sizes.len() - i].iter().sum::<usize>();
+                let n_moduli = (modulus_size + 60).div_ceil(62);
+                let mut mul_1_moduli = vec![];
+                mul_1_moduli.append(&mut moduli[..moduli_sizes.len() - i].to_vec());
+                mul_1_moduli.append(&mut extended_basis[..n_moduli].to_vec());
+                let mul_1_ctx = new_ctx(&mul_1_moduli)?;
+                mul_params.push(MultiplicationParameters::new(
+                    &ctx_i,
+                    &mul_1_ctx,
+                    ScalingFactor::one(),
+                    ScalingFactor::new(&BigUint::from(*plaintext_modulus), ctx_i.modulus()),
+                )?);
+            }
 
             ctx.push(ctx_i);
         }
 
         // We use the same code as SEAL
         // https://github.com/microsoft/SEAL/blob/82b07db635132e297282649e2ab5908999089ad2/native/src/seal/batchencoder.cpp
-        let row_size = self.degree >> 1;
-        let m = self.degree << 1;
-        let gen = 3;
-        let mut pos = 1;
-        let mut matrix_reps_index_map = vec![0usize; self.degree];
-        for i in 0..row_size {
-            let index1 = (pos - 1) >> 1;
-            let index2 = (m - pos - 1) >> 1;
-            matrix_reps_index_map[i] = index1.reverse_bits() >> (self.degree.leading_zeros() + 1);
-            matrix_reps_index_map[row_size | i] =
-                index2.reverse_bits() >> (self.degree.leading_zeros() + 1);
-            pos *= gen;
-            pos &= m - 1;
+        let mut matrix_reps_index_map = vec![];
+        if op.is_some() {
+            let row_size = degree >> 1;
+            let m = degree << 1;
+            let gen = 3;
+            let mut pos = 1;
+            matrix_reps_index_map = vec![0usize; degree];
+            for i in 0..row_size {
+                let index1 = (pos - 1) >> 1;
+                let index2 = (m - pos - 1) >> 1;
+                matrix_reps_index_map[i] = index1.reverse_bits() >> (degree.leading_zeros() + 1);
+                matrix_reps_index_map[row_size | i] =
+                    index2.reverse_bits() >> (degree.leading_zeros() + 1);
+                pos *= gen;
+                pos &= m - 1;
+            }
         }
 
-        Ok(BfvParameters {
-            polynomial_degree: self.degree,
+        let mut par = BfvParameters {
+            polynomial_degree: degree,
             plaintext_modulus: self.plaintext,
             moduli: moduli.into(),
             moduli_sizes: moduli_sizes.into(),
@@ -428,11 +655,19 @@ impl BfvParametersBuilder {
             op: op.map(Arc::new),
             delta: delta.into(),
             q_mod_t: q_mod_t.into(),
+            #[cfg(not(feature = "client"))]
             scalers: scalers.into(),
             plaintext: plaintext_modulus,
+            #[cfg(not(feature = "client"))]
             mul_params: mul_params.into(),
             matrix_reps_index_map: matrix_reps_index_map.into(),
-        })
+            prime_cyclotomic_index: self.prime_cyclotomic_index,
+            batching_disabled: self.disable_batching,
+            id: 0,
+        };
+        let digest = Sha256::digest(par.to_bytes());
+        par.id = u64::from_be_bytes(digest[..8].try_into().unwrap());
+        Ok(par)
     }
 }
 
@@ -443,6 +678,8 @@ impl Serialize for BfvParameters {
             plaintext: self.plaintext_modulus,
             moduli: self.moduli.to_vec(),
             variance: self.variance as u32,
+            cyclotomic_index: self.prime_cyclotomic_index.unwrap_or_default() as u32,
+            disable_batching: self.batching_disabled,
         }
         .encode_to_vec()
     }
@@ -451,8 +688,16 @@ impl Serialize for BfvParameters {
 impl Deserialize for BfvParameters {
     fn try_deserialize(bytes: &[u8]) -> Result<Self> {
         let params: Parameters = Message::decode(bytes).map_err(|_| Error::SerializationError)?;
-        BfvParametersBuilder::new()
-            .set_degree(params.degree as usize)
+        let mut builder = BfvParametersBuilder::new();
+        if params.cyclotomic_index == 0 {
+            builder.set_degree(params.degree as usize);
+        } else {
+            builder.set_prime_cyclotomic_index(params.cyclotomic_index as usize);
+        }
+        if params.disable_batching {
+            builder.disable_batching();
+        }
+        builder
             .set_plaintext_modulus(params.plaintext)
             .set_moduli(&params.moduli)
             .set_variance(params.variance as usize)
@@ -462,6 +707,7 @@ impl Deserialize for BfvParameters {
 }
 
 /// Multiplication parameters
+#[cfg(not(feature = "client"))]
 #[derive(Debug, PartialEq, Eq, Default)]
 pub(crate) struct MultiplicationParameters {
     pub(crate) extender: Scaler,
@@ -470,6 +716,7 @@ pub(crate) struct MultiplicationParameters {
     pub(crate) to: Arc<Context>,
 }
 
+#[cfg(not(feature = "client"))]
 impl MultiplicationParameters {
     fn new(
         from: &Arc<Context>,
@@ -489,8 +736,21 @@ impl MultiplicationParameters {
 #[cfg(test)]
 mod tests {
     use super::{BfvParameters, BfvParametersBuilder};
-    use crate::Error;
+    #[cfg(not(any(feature = "client", feature = "server")))]
+    use crate::bfv::{
+        Ciphertext, Encoding, EvaluationKeyBuilder, Plaintext, RelinearizationKey, SecretKey,
+    };
+    use crate::{Error, ParametersError};
+    use fhe_math::zq::primes::generate_prime;
     use fhe_traits::{Deserialize, Serialize};
+    #[cfg(not(any(feature = "client", feature = "server")))]
+    use fhe_traits::{FheDecoder, FheDecrypter, FheEncoder, FheEncrypter};
+    #[cfg(not(any(feature = "client", feature = "server")))]
+    use rand::thread_rng;
+    extern crate alloc;
+    use alloc::format;
+    #[cfg(not(any(feature = "client", feature = "server")))]
+    use alloc::{vec, vec::Vec};
 
     // TODO: To fix when errors handling is fixed.
     // #[test]
@@ -579,6 +839,44 @@ mod tests {
     // 	Ok(())
     // }
 
+    #[test]
+    fn id() {
+        let params = BfvParameters::default_arc(2, 16);
+        assert_eq!(params.id(), BfvParameters::default_arc(2, 16).id());
+        assert_ne!(params.id(), BfvParameters::default_arc(3, 16).id());
+        assert_ne!(params.id(), BfvParameters::default_arc(2, 32).id());
+    }
+
+    #[test]
+    fn hash() -> Result<(), Error> {
+        extern crate std;
+        use core::hash::{Hash, Hasher};
+        use std::collections::hash_map::DefaultHasher;
+
+        let hash = |params: &BfvParameters| {
+            let mut hasher = DefaultHasher::new();
+            params.hash(&mut hasher);
+            hasher.finish()
+        };
+        let params = BfvParameters::default_arc(2, 16);
+        assert_eq!(hash(&params), hash(&BfvParameters::default_arc(2, 16)));
+        assert_eq!(
+            hash(&params),
+            hash(&BfvParameters::try_deserialize(&params.to_bytes())?)
+        );
+        assert_ne!(hash(&params), hash(&BfvParameters::default_arc(3, 16)));
+        assert_ne!(hash(&params), hash(&BfvParameters::default_arc(2, 32)));
+
+        // The parameters can be used as keys of a cache.
+        let mut cache = hashbrown::HashMap::new();
+        cache.insert(params.clone(), 1);
+        cache.insert(BfvParameters::default_arc(2, 16), 2);
+        cache.insert(BfvParameters::default_arc(3, 16), 3);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache[&params], 2);
+        Ok(())
+    }
+
     #[test]
     fn default() {
         let params = BfvParameters::default_arc(1, 16);
@@ -588,6 +886,13 @@ mod tests {
         let params = BfvParameters::default_arc(2, 16);
         assert_eq!(params.moduli.len(), 2);
         assert_eq!(params.degree(), 16);
+
+        let params = BfvParameters::insecure_toy(3, 8).unwrap();
+        assert_eq!(params.moduli.len(), 3);
+        assert_eq!(params.degree(), 8);
+        assert!(params.op.is_some());
+        assert!(BfvParameters::insecure_toy(1, 12).is_err());
+        assert!(BfvParameters::insecure_toy(0, 8).is_err());
     }
 
     #[test]
@@ -626,6 +931,52 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn validation() {
+        let build = |plaintext: u64, moduli: &[u64]| {
+            BfvParametersBuilder::new()
+                .set_degree(16)
+                .set_plaintext_modulus(plaintext)
+                .set_moduli(moduli)
+                .build()
+        };
+        let invalid_modulus = |result: Result<BfvParameters, Error>| match result {
+            Err(Error::ParametersError(ParametersError::InvalidModulus(m, _))) => Some(m),
+            _ => None,
+        };
+        assert!(build(1153, &[4611686018427387617, 2017]).is_ok());
+
+        // A modulus is not prime, or does not support the NTT of size 16.
+        assert_eq!(
+            invalid_modulus(build(1153, &[4611686018427387617, 2019])),
+            Some(2019)
+        );
+        assert_eq!(
+            invalid_modulus(build(1153, &[4611686018427387617, 2027])),
+            Some(2027)
+        );
+        // A modulus is larger than 62 bits.
+        assert_eq!(
+            invalid_modulus(build(1153, &[(1 << 63) + 1])),
+            Some((1 << 63) + 1)
+        );
+        // A modulus is repeated.
+        assert_eq!(
+            invalid_modulus(build(1153, &[4611686018427387617, 2017, 2017])),
+            Some(2017)
+        );
+        // The plaintext modulus is not coprime with a modulus.
+        assert_eq!(
+            invalid_modulus(build(2 * 2017, &[4611686018427387617, 2017])),
+            Some(2017)
+        );
+        // The plaintext modulus is larger than the first modulus.
+        assert!(matches!(
+            build(4099, &[2017, 4611686018427387617]),
+            Err(Error::ParametersError(ParametersError::InvalidPlaintext(_)))
+        ));
+    }
+
     #[test]
     fn serialize() -> Result<(), Error> {
         let params = BfvParametersBuilder::new()
@@ -638,4 +989,141 @@ mod tests {
         assert_eq!(BfvParameters::try_deserialize(&bytes)?, params);
         Ok(())
     }
+
+    #[test]
+    fn migrate_from() -> Result<(), Error> {
+        let params = BfvParametersBuilder::new()
+            .set_degree(16)
+            .set_plaintext_modulus(1153)
+            .set_moduli_sizes(&[62, 50, 40])
+            .set_variance(4)
+            .build()?;
+        assert_eq!(*BfvParameters::migrate_from(&params.to_bytes())?, params);
+        let no_batching = BfvParametersBuilder::new()
+            .set_degree(16)
+            .set_plaintext_modulus(1153)
+            .set_moduli_sizes(&[62])
+            .disable_batching()
+            .build()?;
+        assert_eq!(
+            *BfvParameters::migrate_from(&no_batching.to_bytes())?,
+            no_batching
+        );
+
+        // Parameters whose second modulus is another prime of the same size.
+        let other = generate_prime(50, 32, params.moduli[1]).unwrap();
+        let changed = BfvParametersBuilder::new()
+            .set_degree(16)
+            .set_plaintext_modulus(1153)
+            .set_moduli(&[params.moduli[0], other, params.moduli[2]])
+            .set_variance(4)
+            .build()?;
+        let expected = format!(
+            "modulus 1 is {other} in the serialized parameters and {} in this release",
+            params.moduli[1]
+        );
+        assert_eq!(
+            BfvParameters::migrate_from(&changed.to_bytes()).unwrap_err(),
+            Error::ParametersError(ParametersError::MigrationMismatch(expected))
+        );
+        assert!(BfvParameters::migrate_from(&[0xff]).is_err());
+        Ok(())
+    }
+
+    #[cfg(not(any(feature = "client", feature = "server")))]
+    #[test]
+    fn disable_batching() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParametersBuilder::new()
+            .set_degree(16)
+            .set_plaintext_modulus(1153)
+            .set_moduli_sizes(&[62, 62])
+            .disable_batching()
+            .build_arc()?;
+        assert!(params.op.is_none());
+        assert!(params.matrix_reps_index_map.is_empty());
+        assert_ne!(params.id(), BfvParameters::default_arc(2, 16).id());
+        assert_eq!(BfvParameters::try_deserialize(&params.to_bytes())?, *params);
+
+        let v = params.plaintext.random_vec(params.degree(), &mut rng);
+        assert!(matches!(
+            Plaintext::try_encode(&v, Encoding::simd(), &params),
+            Err(Error::EncodingNotSupported(_))
+        ));
+        let sk = SecretKey::random(&params, &mut rng);
+        let pt = Plaintext::try_encode(&v, Encoding::poly(), &params)?;
+        let ct: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+        let pt = sk.try_decrypt(&(&ct * &ct))?;
+        assert_eq!(Vec::<u64>::try_decode(&pt, Encoding::poly())?.len(), 16);
+        Ok(())
+    }
+
+    #[cfg(not(any(feature = "client", feature = "server")))]
+    #[test]
+    fn prime_cyclotomic() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let par = BfvParametersBuilder::new()
+            .set_prime_cyclotomic_index(17)
+            .set_plaintext_modulus(1153)
+            .set_moduli_sizes(&[62, 62])
+            .build_arc()?;
+        assert_eq!(par.degree(), 16);
+        assert_eq!(par.cyclotomic_index(), 17);
+        assert_eq!(BfvParameters::default_arc(1, 16).cyclotomic_index(), 32);
+        assert!(par.op.is_none());
+        assert_eq!(BfvParameters::try_deserialize(&par.to_bytes())?, *par);
+
+        // Multiply two polynomials modulo 1 + x + ... + x^16 homomorphically.
+        let sk = SecretKey::random(&par, &mut rng);
+        let rk = RelinearizationKey::new(&sk, &mut rng)?;
+        let a = par.plaintext.random_vec(16, &mut rng);
+        let b = par.plaintext.random_vec(16, &mut rng);
+        let ct_a: Ciphertext = sk.try_encrypt(
+            &Plaintext::try_encode(&a, Encoding::poly(), &par)?,
+            &mut rng,
+        )?;
+        let ct_b: Ciphertext = sk.try_encrypt(
+            &Plaintext::try_encode(&b, Encoding::poly(), &par)?,
+            &mut rng,
+        )?;
+        let mut ct_c = &ct_a * &ct_b;
+        rk.relinearizes(&mut ct_c)?;
+        let c = Vec::<u64>::try_decode(&sk.try_decrypt(&ct_c)?, Encoding::poly())?;
+
+        let t = &par.plaintext;
+        let mut expected = vec![0u64; 17];
+        for i in 0..16 {
+            for j in 0..16 {
+                expected[(i + j) % 17] = t.add(expected[(i + j) % 17], t.mul(a[i], b[j]));
+            }
+        }
+        let top = expected.pop().unwrap();
+        expected.iter_mut().for_each(|e| *e = t.sub(*e, top));
+        assert_eq!(c, expected);
+
+        // The SIMD encoding and the rotations are not supported.
+        assert!(Plaintext::try_encode(&a, Encoding::simd(), &par).is_err());
+        assert!(EvaluationKeyBuilder::new(&sk)?
+            .enable_column_rotation(1)
+            .and_then(|ek| ek.build(&mut rng))
+            .is_err());
+
+        assert!(BfvParametersBuilder::new()
+            .set_prime_cyclotomic_index(17)
+            .set_degree(16)
+            .set_plaintext_modulus(1153)
+            .set_moduli_sizes(&[62])
+            .build()
+            .is_err());
+        assert_eq!(
+            BfvParametersBuilder::new()
+                .set_prime_cyclotomic_index(15)
+                .set_plaintext_modulus(1153)
+                .set_moduli_sizes(&[62])
+                .build()
+                .unwrap_err(),
+            Error::ParametersError(ParametersError::InvalidCyclotomicIndex(15))
+        );
+        Ok(())
+    }
 }
@@ -1,23 +1,40 @@
 //! Plaintext type in the BFV encryption scheme.
+//!
+//! The encoders and decoders implemented in this module run in constant time:
+//! the SIMD slot permutation only depends on public indices, the NTT is the
+//! constant-time variant, and reductions and centering use the constant-time
+//! functions of [`fhe_math::zq::Modulus`]. The only variable time encoding
+//! path is [`fhe_traits::FheEncoderVariableTime`], implemented for
+//! [`PlaintextVec`].
+use crate::proto::bfv::Plaintext as PlaintextProto;
 use crate::{
     bfv::{BfvParameters, Encoding, PlaintextVec},
     Error, Result,
 };
+use core::fmt::Debug;
 use fhe_math::rq::{traits::TryConvertFrom, Context, Poly, Representation};
-use fhe_traits::{FheDecoder, FheEncoder, FheParametrized, FhePlaintext};
+use fhe_traits::{
+    DeserializeParametrized, FheDecoder, FheEncoder, FheParametrized, FhePlaintext, Serialize,
+};
+use prost::Message;
 extern crate alloc;
 use alloc::boxed::Box;
-use alloc::string::ToString;
+use alloc::format;
+use alloc::string::{String, ToString};
 use alloc::sync::Arc;
 use alloc::vec;
 use alloc::vec::Vec;
+use once_cell::race::OnceBox;
 use zeroize::{Zeroize as _, Zeroizing};
 use zeroize_derive::{Zeroize, ZeroizeOnDrop};
 
-use super::encoding::EncodingEnum;
+use super::encoding::{EncodeOptions, EncodingEnum, OutOfRange};
 
 /// A plaintext object, that encodes a vector according to a specific encoding.
-#[derive(Debug, Clone, Eq, Zeroize, ZeroizeOnDrop)]
+///
+/// The [`Debug`] implementation does not print the encoded values, so that
+/// they do not leak in logs; use [`Plaintext::dump`] to print them.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
 pub struct Plaintext {
     /// The parameters of the underlying BFV encryption scheme.
     #[zeroize(skip)]
@@ -32,6 +49,45 @@ pub struct Plaintext {
     /// The level of the plaintext
     #[zeroize(skip)]
     pub(crate) level: usize,
+    /// The scaled polynomial returned by [`Plaintext::to_poly`], computed on
+    /// first use.
+    pub(crate) scaled_poly: ScaledPoly,
+}
+
+/// Lazily computed scaled polynomial of a plaintext.
+///
+/// A plaintext is never modified after its creation, except when it is
+/// zeroized, which also clears the cache. The cache can be filled from
+/// several threads at once, in which case only one of the polynomials is
+/// kept.
+#[derive(Default)]
+pub(crate) struct ScaledPoly(pub(crate) OnceBox<Zeroizing<Poly>>);
+
+impl Clone for ScaledPoly {
+    fn clone(&self) -> Self {
+        let cache = Self::default();
+        if let Some(poly) = self.0.get() {
+            let _ = cache.0.set(Box::new(poly.clone()));
+        }
+        cache
+    }
+}
+
+impl zeroize::Zeroize for ScaledPoly {
+    fn zeroize(&mut self) {
+        // Dropping the cached polynomial zeroizes it.
+        *self = Self::default()
+    }
+}
+
+impl Debug for Plaintext {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Plaintext")
+            .field("params", &format_args!("{:016x}", self.par.id()))
+            .field("level", &self.level)
+            .field("encoding", &self.encoding)
+            .finish_non_exhaustive()
+    }
 }
 
 impl FheParametrized for Plaintext {
@@ -43,17 +99,33 @@ impl FhePlaintext for Plaintext {
 }
 
 impl Plaintext {
-    pub(crate) fn to_poly(&self) -> Poly {
-        let mut m_v = Zeroizing::new(self.value.clone());
-        self.par
-            .plaintext
-            .scalar_mul_vec(&mut m_v, self.par.q_mod_t[self.level]);
-        let ctx = self.par.ctx_at_level(self.level).unwrap();
-        let mut m =
-            Poly::try_convert_from(m_v.as_ref(), ctx, false, Representation::PowerBasis).unwrap();
-        m.change_representation(Representation::Ntt);
-        m *= &self.par.delta[self.level];
-        m
+    /// Returns the plaintext scaled by `delta = floor(q / t)` at its level, in
+    /// NTT representation, as added to a ciphertext. It is computed on first
+    /// use and cached, so that adding a plaintext to many ciphertexts only
+    /// computes it once.
+    pub(crate) fn to_poly(&self) -> &Poly {
+        self.scaled_poly.0.get_or_init(|| {
+            let mut m_v = Zeroizing::new(self.value.clone());
+            self.par
+                .plaintext
+                .scalar_mul_vec(&mut m_v, self.par.q_mod_t[self.level]);
+            let ctx = self.par.ctx_at_level(self.level).unwrap();
+            let mut m =
+                Poly::try_convert_from(m_v.as_ref(), ctx, false, Representation::PowerBasis)
+                    .unwrap();
+            m.change_representation(Representation::Ntt);
+            m *= &self.par.delta[self.level];
+            Box::new(Zeroizing::new(m))
+        })
+    }
+
+    /// Returns the full contents of the plaintext, including the encoded
+    /// values, for debugging purposes.
+    pub fn dump(&self) -> String {
+        format!(
+            "Plaintext {{ par: {:?}, value: {:?}, encoding: {:?}, poly_ntt: {:?}, level: {} }}",
+            self.par, self.value, self.encoding, self.poly_ntt, self.level
+        )
     }
 
     /// Generate a zero plaintext.
@@ -68,6 +140,7 @@ impl Plaintext {
             encoding: Some(encoding),
             poly_ntt,
             level,
+            scaled_poly: Default::default(),
         })
     }
 
@@ -75,6 +148,50 @@ impl Plaintext {
     pub fn level(&self) -> usize {
         self.par.level_of_ctx(self.poly_ntt.ctx()).unwrap()
     }
+
+    /// Returns the plaintext with the same values at level `level`, whose
+    /// scaled polynomial uses the `delta` of this level.
+    pub(crate) fn to_level(&self, level: usize) -> Result<Self> {
+        if level == self.level {
+            return Ok(self.clone());
+        }
+        let ctx = self.par.ctx_at_level(level)?;
+        let mut poly_ntt =
+            Poly::try_convert_from(self.value.as_ref(), ctx, false, Representation::PowerBasis)?;
+        poly_ntt.change_representation(Representation::Ntt);
+        Ok(Self {
+            par: self.par.clone(),
+            value: self.value.clone(),
+            encoding: self.encoding.clone().map(|mut encoding| {
+                encoding.level = level;
+                encoding
+            }),
+            poly_ntt,
+            level,
+            scaled_poly: Default::default(),
+        })
+    }
+
+    /// Decode the plaintext and apply the checked conversion `f` to each
+    /// decoded value, e.g. to decode small categorical values into an enum.
+    ///
+    /// Returns an error if the decoding fails, or if `f` returns None for one
+    /// of the values.
+    pub fn try_decode_map<T, O, F>(&self, encoding: O, mut f: F) -> Result<Vec<T>>
+    where
+        O: Into<Option<Encoding>>,
+        F: FnMut(u64) -> Option<T>,
+    {
+        let v = Zeroizing::new(Vec::<u64>::try_decode(self, encoding)?);
+        v.iter()
+            .enumerate()
+            .map(|(i, vi)| {
+                f(*vi).ok_or_else(|| {
+                    Error::DefaultError(alloc::format!("Invalid value at index {i}"))
+                })
+            })
+            .collect()
+    }
 }
 
 unsafe impl Send for Plaintext {}
@@ -85,13 +202,74 @@ impl PartialEq for Plaintext {
     fn eq(&self, other: &Self) -> bool {
         let mut eq = self.par == other.par;
         eq &= self.value == other.value;
-        if self.encoding.is_some() && other.encoding.is_some() {
-            eq &= self.encoding.as_ref().unwrap() == other.encoding.as_ref().unwrap()
+        if let (Some(e1), Some(e2)) = (self.encoding.as_ref(), other.encoding.as_ref()) {
+            eq &= e1 == e2
         }
         eq
     }
 }
 
+impl Eq for Plaintext {}
+
+// Serialization.
+impl From<&Plaintext> for PlaintextProto {
+    fn from(pt: &Plaintext) -> Self {
+        PlaintextProto {
+            value: pt.value.to_vec(),
+            level: pt.level as u32,
+            encoding: match pt.encoding.as_ref().map(|e| &e.encoding) {
+                None => 0,
+                Some(EncodingEnum::Poly) => 1,
+                Some(EncodingEnum::Simd) => 2,
+            },
+        }
+    }
+}
+
+impl Serialize for Plaintext {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut proto = PlaintextProto::from(self);
+        let bytes = proto.encode_to_vec();
+        proto.value.zeroize();
+        bytes
+    }
+}
+
+impl DeserializeParametrized for Plaintext {
+    type Error = Error;
+
+    fn from_bytes(bytes: &[u8], par: &Arc<BfvParameters>) -> Result<Self> {
+        let mut proto: PlaintextProto =
+            Message::decode(bytes).map_err(|_| Error::SerializationError)?;
+        let level = proto.level as usize;
+        let encoding = match proto.encoding {
+            0 => None,
+            1 => Some(Encoding::poly_at_level(level)),
+            2 if par.op.is_some() => Some(Encoding::simd_at_level(level)),
+            _ => return Err(Error::SerializationError),
+        };
+        if proto.value.len() != par.degree() || proto.value.iter().any(|v| *v >= *par.plaintext) {
+            proto.value.zeroize();
+            return Err(Error::SerializationError);
+        }
+        let ctx = par
+            .ctx_at_level(level)
+            .map_err(|_| Error::SerializationError)?;
+        let value = core::mem::take(&mut proto.value).into_boxed_slice();
+        let mut poly_ntt =
+            Poly::try_convert_from(value.as_ref(), ctx, false, Representation::PowerBasis)?;
+        poly_ntt.change_representation(Representation::Ntt);
+        Ok(Self {
+            par: par.clone(),
+            value,
+            encoding,
+            poly_ntt,
+            level,
+            scaled_poly: Default::default(),
+        })
+    }
+}
+
 // Conversions.
 impl TryConvertFrom<&Plaintext> for Poly {
     fn try_convert_from<R>(
@@ -159,8 +337,28 @@ impl<'a> FheEncoder<&'a [u64]> for Plaintext {
 impl<'a> FheEncoder<&'a [i64]> for Plaintext {
     type Error = Error;
     fn try_encode(value: &'a [i64], encoding: Encoding, par: &Arc<BfvParameters>) -> Result<Self> {
+        if encoding.options.on_out_of_range == OutOfRange::Error {
+            // The decoder for signed values returns values in [-(t - t/2), t/2).
+            let t = encoding.options.range_bound(*par.plaintext);
+            let min = -((t - (t >> 1)) as i64);
+            let max = (t >> 1) as i64;
+            if value
+                .iter()
+                .fold(false, |acc, vi| acc | (*vi < min) | (*vi >= max))
+            {
+                return Err(Error::DefaultError(
+                    "Value out of the range of the plaintext modulus".to_string(),
+                ));
+            }
+        }
         let w = Zeroizing::new(par.plaintext.reduce_vec_i64(value));
-        Plaintext::try_encode(w.as_ref() as &[u64], encoding, par)
+        // The headroom applies to the signed values checked above, and not to
+        // their reductions modulo t.
+        let options = EncodeOptions {
+            headroom_bits: 0,
+            ..encoding.options
+        };
+        Plaintext::try_encode(w.as_ref() as &[u64], encoding.with_options(options), par)
     }
 }
 
@@ -217,8 +415,23 @@ impl FheDecoder<Plaintext> for Vec<i64> {
     where
         E: Into<Option<Encoding>>,
     {
-        let v = Vec::<u64>::try_decode(pt, encoding)?;
-        Ok(unsafe { pt.par.plaintext.center_vec_vt(&v) })
+        let v = Zeroizing::new(Vec::<u64>::try_decode(pt, encoding)?);
+        Ok(pt.par.plaintext.center_vec(&v))
+    }
+
+    type Error = Error;
+}
+
+impl FheDecoder<Plaintext> for Vec<bool> {
+    fn try_decode<E>(pt: &Plaintext, encoding: E) -> Result<Vec<bool>>
+    where
+        E: Into<Option<Encoding>>,
+    {
+        pt.try_decode_map(encoding, |v| match v {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        })
     }
 
     type Error = Error;
@@ -228,16 +441,31 @@ impl FheDecoder<Plaintext> for Vec<i64> {
 mod tests {
     use super::{Encoding, Plaintext};
     use crate::bfv::parameters::{BfvParameters, BfvParametersBuilder};
+    use crate::bfv::{EncodeOptions, OutOfRange};
     use crate::Error;
     use fhe_math::rq::{Poly, Representation};
-    use fhe_traits::{FheDecoder, FheEncoder};
-    use rand::thread_rng;
-    use zeroize::Zeroize;
+    use fhe_traits::{DeserializeParametrized, FheDecoder, FheEncoder, Serialize};
+    use rand::{thread_rng, Rng};
+    use zeroize::{Zeroize, Zeroizing};
     extern crate alloc;
     use alloc::boxed::Box;
     use alloc::string::ToString;
     use alloc::vec::Vec;
 
+    #[test]
+    fn debug() -> Result<(), Error> {
+        let params = BfvParameters::default_arc(1, 16);
+        let pt = Plaintext::try_encode(&[1023u64, 1031], Encoding::poly(), &params)?;
+        let debug = alloc::format!("{pt:?}");
+        assert!(debug.starts_with(&alloc::format!(
+            "Plaintext {{ params: {:016x}, level: 0, encoding: Some(",
+            params.id()
+        )));
+        assert!(!debug.contains("1023"));
+        assert!(pt.dump().contains("1023, 1031"));
+        Ok(())
+    }
+
     #[test]
     fn try_encode() -> Result<(), Error> {
         let mut rng = thread_rng();
@@ -257,6 +485,14 @@ mod tests {
         let plaintext = Plaintext::try_encode(&[1u64], Encoding::poly(), &params);
         assert!(plaintext.is_ok());
 
+        // The maximum degree is checked, and must be smaller than n / 2.
+        assert!(Plaintext::try_encode(&a[..8], Encoding::poly_mul_safe(7), &params).is_ok());
+        assert!(
+            Plaintext::try_encode(&[1u64, 0, 0, 0], Encoding::poly_mul_safe(1), &params).is_ok()
+        );
+        assert!(Plaintext::try_encode(&[0u64, 0, 1], Encoding::poly_mul_safe(1), &params).is_err());
+        assert!(Plaintext::try_encode(&[1u64], Encoding::poly_mul_safe(8), &params).is_err());
+
         // The following parameters do not allow for Simd encoding
         let params = BfvParametersBuilder::new()
             .set_degree(16)
@@ -300,6 +536,98 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn encode_out_of_range() -> Result<(), Error> {
+        let params = BfvParameters::default_arc(1, 16);
+        let t = *params.plaintext;
+        let reduce = EncodeOptions {
+            on_out_of_range: OutOfRange::Reduce,
+            ..Default::default()
+        };
+        let headroom = EncodeOptions {
+            headroom_bits: 3,
+            ..Default::default()
+        };
+
+        for encoding in [Encoding::poly(), Encoding::simd()] {
+            assert!(Plaintext::try_encode(&[t - 1], encoding.clone(), &params).is_ok());
+            assert!(Plaintext::try_encode(&[t], encoding.clone(), &params).is_err());
+            let pt = Plaintext::try_encode(
+                &[t + 1, 2 * t],
+                encoding.clone().with_options(reduce),
+                &params,
+            )?;
+            let b = Vec::<u64>::try_decode(&pt, encoding.clone())?;
+            assert_eq!(&b[..2], &[1, 0]);
+
+            let min = -((t - (t >> 1)) as i64);
+            let max = (t >> 1) as i64 - 1;
+            let pt = Plaintext::try_encode(&[min, max], encoding.clone(), &params)?;
+            let b = Vec::<i64>::try_decode(&pt, encoding.clone())?;
+            assert_eq!(&b[..2], &[min, max]);
+            assert!(Plaintext::try_encode(&[min - 1], encoding.clone(), &params).is_err());
+            assert!(Plaintext::try_encode(&[max + 1], encoding.clone(), &params).is_err());
+            let pt = Plaintext::try_encode(
+                &[max + 1, -(t as i64)],
+                encoding.clone().with_options(reduce),
+                &params,
+            )?;
+            let b = Vec::<i64>::try_decode(&pt, encoding.clone())?;
+            assert_eq!(&b[..2], &[min, 0]);
+
+            // The headroom reduces the range of the values.
+            let encoding = encoding.with_options(headroom);
+            let bound = t >> 3;
+            assert!(Plaintext::try_encode(&[bound - 1], encoding.clone(), &params).is_ok());
+            assert!(Plaintext::try_encode(&[bound], encoding.clone(), &params).is_err());
+            let (min, max) = (-((bound - (bound >> 1)) as i64), (bound >> 1) as i64 - 1);
+            assert!(Plaintext::try_encode(&[min, max], encoding.clone(), &params).is_ok());
+            assert!(Plaintext::try_encode(&[min - 1], encoding.clone(), &params).is_err());
+            assert!(Plaintext::try_encode(&[max + 1], encoding, &params).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn decode_bool_and_map() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(1, 16);
+        let a = (0..params.degree())
+            .map(|_| rng.gen_range(0..2u64))
+            .collect::<Vec<_>>();
+
+        for encoding in [Encoding::poly(), Encoding::simd()] {
+            let pt = Plaintext::try_encode(&a, encoding.clone(), &params)?;
+            let b = Vec::<bool>::try_decode(&pt, encoding.clone())?;
+            assert_eq!(b, a.iter().map(|ai| *ai == 1).collect::<Vec<_>>());
+
+            let pt = Plaintext::try_encode(&[0u64, 1, 2], encoding.clone(), &params)?;
+            assert!(Vec::<bool>::try_decode(&pt, encoding.clone()).is_err());
+
+            #[derive(Debug, PartialEq)]
+            enum Color {
+                Red,
+                Green,
+                Blue,
+            }
+            let to_color = |v| match v {
+                0 => Some(Color::Red),
+                1 => Some(Color::Green),
+                2 => Some(Color::Blue),
+                _ => None,
+            };
+            let colors = pt.try_decode_map(encoding.clone(), to_color)?;
+            assert_eq!(&colors[..3], &[Color::Red, Color::Green, Color::Blue]);
+            assert!(colors[3..].iter().all(|c| *c == Color::Red));
+
+            let pt = Plaintext::try_encode(&[3u64], encoding.clone(), &params)?;
+            assert!(pt.try_decode_map(encoding, to_color).is_err());
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn partial_eq() -> Result<(), Error> {
         let mut rng = thread_rng();
@@ -370,6 +698,32 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn to_poly_cache() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(2, 16);
+        let a = params.plaintext.random_vec(params.degree(), &mut rng);
+        for level in 0..2 {
+            let mut plaintext = Plaintext::try_encode(&a, Encoding::poly_at_level(level), &params)?;
+            assert!(plaintext.scaled_poly.0.get().is_none());
+            let poly = plaintext.to_poly().clone();
+            assert_eq!(poly.ctx(), &params.ctx[level]);
+            assert!(core::ptr::eq(plaintext.to_poly(), plaintext.to_poly()));
+
+            // The cache is cloned, and cleared when the plaintext is zeroized.
+            let cloned = plaintext.clone();
+            assert_eq!(
+                cloned.scaled_poly.0.get(),
+                Some(&Zeroizing::new(poly.clone()))
+            );
+            plaintext.zeroize();
+            assert!(plaintext.scaled_poly.0.get().is_none());
+            assert!(plaintext.to_poly().coefficients().iter().all(|c| *c == 0));
+            assert_eq!(cloned.to_poly(), &poly);
+        }
+        Ok(())
+    }
+
     #[test]
     fn zeroize() -> Result<(), Error> {
         let mut rng = thread_rng();
@@ -400,4 +754,39 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn serialize() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(2, 16);
+        let a = params.plaintext.random_vec(params.degree(), &mut rng);
+
+        for encoding in [
+            Encoding::poly(),
+            Encoding::simd(),
+            Encoding::poly_at_level(1),
+            Encoding::simd_at_level(1),
+        ] {
+            let plaintext = Plaintext::try_encode(&a, encoding.clone(), &params)?;
+            let deserialized = Plaintext::from_bytes(&plaintext.to_bytes(), &params)?;
+            assert_eq!(deserialized, plaintext);
+            assert_eq!(deserialized.encoding, Some(encoding.clone()));
+            assert_eq!(deserialized.poly_ntt, plaintext.poly_ntt);
+            assert_eq!(Vec::<u64>::try_decode(&deserialized, encoding)?, a);
+        }
+
+        let mut plaintext = Plaintext::try_encode(&a, Encoding::poly(), &params)?;
+        plaintext.encoding = None;
+        let deserialized = Plaintext::from_bytes(&plaintext.to_bytes(), &params)?;
+        assert_eq!(deserialized.encoding, None);
+
+        // The plaintext must have the degree of the parameters.
+        let other = BfvParameters::default_arc(2, 32);
+        assert_eq!(
+            Plaintext::from_bytes(&plaintext.to_bytes(), &other).unwrap_err(),
+            Error::SerializationError
+        );
+
+        Ok(())
+    }
 }
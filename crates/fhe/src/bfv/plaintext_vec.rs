@@ -7,6 +7,7 @@ use alloc::vec::Vec;
 
 use fhe_math::rq::{traits::TryConvertFrom, Poly, Representation};
 use fhe_traits::{FheEncoder, FheEncoderVariableTime, FheParametrized, FhePlaintext};
+use zeroize::Zeroizing;
 use zeroize_derive::{Zeroize, ZeroizeOnDrop};
 
 use crate::{
@@ -14,7 +15,57 @@ use crate::{
     Error, Result,
 };
 
-use super::encoding::EncodingEnum;
+use super::encoding::{EncodingEnum, OutOfRange};
+
+/// Check that the values are smaller than the plaintext modulus, minus the
+/// headroom of the encoding, or returns
+/// their reduction if the options of the encoding allow it.
+fn check_range(
+    value: &[u64],
+    encoding: &Encoding,
+    par: &BfvParameters,
+) -> Result<Option<Zeroizing<Vec<u64>>>> {
+    match encoding.options.on_out_of_range {
+        OutOfRange::Error => {
+            let t = encoding.options.range_bound(*par.plaintext);
+            if value.iter().fold(false, |acc, vi| acc | (*vi >= t)) {
+                Err(Error::DefaultError(
+                    "Value larger than the plaintext modulus".to_string(),
+                ))
+            } else {
+                Ok(None)
+            }
+        }
+        OutOfRange::Reduce => {
+            let mut reduced = Zeroizing::new(value.to_vec());
+            par.plaintext.reduce_vec(&mut reduced);
+            Ok(Some(reduced))
+        }
+    }
+}
+
+/// Check that the values have no non-zero coefficient beyond the maximum
+/// degree of the encoding, and that the maximum degree is such that products
+/// of two values do not wrap around.
+fn check_degree(value: &[u64], encoding: &Encoding, par: &BfvParameters) -> Result<()> {
+    if let Some(max_degree) = encoding.max_degree {
+        if 2 * max_degree >= par.degree() {
+            return Err(Error::DefaultError(
+                "The maximum degree must be smaller than half the degree".to_string(),
+            ));
+        }
+        if value
+            .iter()
+            .skip(max_degree + 1)
+            .fold(false, |acc, vi| acc | (*vi != 0))
+        {
+            return Err(Error::DefaultError(
+                "Value of degree larger than the maximum degree".to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
 
 /// A wrapper around a vector of plaintext which implements the [`FhePlaintext`]
 /// trait, and therefore can be encoded to / decoded from.
@@ -51,6 +102,9 @@ impl FheEncoderVariableTime<&[u64]> for PlaintextVec {
         if encoding.encoding == EncodingEnum::Simd && par.op.is_none() {
             return Err(Error::EncodingNotSupported(EncodingEnum::Simd.to_string()));
         }
+        let reduced = check_range(value, &encoding, par)?;
+        let value = reduced.as_ref().map_or(value, |v| v.as_slice());
+        check_degree(value, &encoding, par)?;
         let ctx = par.ctx_at_level(encoding.level)?;
         let num_plaintexts = value.len().div_ceil(par.degree());
 
@@ -82,6 +136,7 @@ impl FheEncoderVariableTime<&[u64]> for PlaintextVec {
                         encoding: Some(encoding.clone()),
                         poly_ntt: poly,
                         level: encoding.level,
+                        scaled_poly: Default::default(),
                     })
                 })
                 .collect::<Result<Vec<Plaintext>>>()?,
@@ -98,6 +153,9 @@ impl FheEncoder<&[u64]> for PlaintextVec {
         if encoding.encoding == EncodingEnum::Simd && par.op.is_none() {
             return Err(Error::EncodingNotSupported(EncodingEnum::Simd.to_string()));
         }
+        let reduced = check_range(value, &encoding, par)?;
+        let value = reduced.as_ref().map_or(value, |v| v.as_slice());
+        check_degree(value, &encoding, par)?;
         let ctx = par.ctx_at_level(encoding.level)?;
         let num_plaintexts = value.len().div_ceil(par.degree());
 
@@ -129,6 +187,7 @@ impl FheEncoder<&[u64]> for PlaintextVec {
                         encoding: Some(encoding.clone()),
                         poly_ntt: poly,
                         level: encoding.level,
+                        scaled_poly: Default::default(),
                     })
                 })
                 .collect::<Result<Vec<Plaintext>>>()?,
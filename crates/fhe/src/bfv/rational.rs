@@ -0,0 +1,205 @@
+//! Encrypted rational numbers for the BFV encryption scheme.
+
+use crate::bfv::{traits::TryConvertFrom, BfvParameters, Ciphertext, Multiplicator};
+#[cfg(not(feature = "server"))]
+use crate::bfv::{Encoding, SecretKey};
+use crate::proto::bfv::EncryptedRational as EncryptedRationalProto;
+use crate::{Error, Result};
+use fhe_traits::{DeserializeParametrized, FheParametrized, Serialize};
+#[cfg(not(feature = "server"))]
+use fhe_traits::{FheDecoder, FheDecrypter};
+use prost::Message;
+extern crate alloc;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+#[cfg(not(feature = "server"))]
+use zeroize::Zeroizing;
+
+/// A rational number encrypted as a pair of ciphertexts encrypting its
+/// numerator and its denominator.
+///
+/// Additions and multiplications are computed on the numerators and
+/// denominators without reducing the fraction, and therefore consume one
+/// multiplicative level each. The fraction is only reduced modulo the
+/// plaintext modulus `t` at decryption.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncryptedRational {
+    numerator: Ciphertext,
+    denominator: Ciphertext,
+}
+
+impl EncryptedRational {
+    /// Create an encrypted rational from the encryptions of its numerator and
+    /// of its denominator.
+    pub fn new(numerator: Ciphertext, denominator: Ciphertext) -> Result<Self> {
+        if numerator.par != denominator.par || numerator.level != denominator.level {
+            return Err(Error::DefaultError(
+                "Mismatched parameters or levels".to_string(),
+            ));
+        }
+        Ok(Self {
+            numerator,
+            denominator,
+        })
+    }
+
+    /// Returns the encrypted numerator.
+    pub fn numerator(&self) -> &Ciphertext {
+        &self.numerator
+    }
+
+    /// Returns the encrypted denominator.
+    pub fn denominator(&self) -> &Ciphertext {
+        &self.denominator
+    }
+
+    /// Homomorphically add two rationals, computing `a/b + c/d = (ad + bc) /
+    /// bd`.
+    pub fn add(&self, other: &Self, multiplicator: &Multiplicator) -> Result<Self> {
+        let mut numerator = multiplicator.multiply(&self.numerator, &other.denominator)?;
+        numerator += &multiplicator.multiply(&self.denominator, &other.numerator)?;
+        let denominator = multiplicator.multiply(&self.denominator, &other.denominator)?;
+        Self::new(numerator, denominator)
+    }
+
+    /// Homomorphically multiply two rationals, computing `a/b * c/d = ac /
+    /// bd`.
+    pub fn mul(&self, other: &Self, multiplicator: &Multiplicator) -> Result<Self> {
+        let numerator = multiplicator.multiply(&self.numerator, &other.numerator)?;
+        let denominator = multiplicator.multiply(&self.denominator, &other.denominator)?;
+        Self::new(numerator, denominator)
+    }
+
+    /// Decrypt the rational, and decode the numerator and the denominator
+    /// using the encoding `encoding`. Returns the slot-wise reduction of the
+    /// fraction modulo the plaintext modulus `t`, i.e. `a * b^(-1) mod t`.
+    ///
+    /// Returns an error if a denominator is not invertible modulo `t`, which
+    /// is always the case when `t` is not prime.
+    #[cfg(not(feature = "server"))]
+    pub fn try_decrypt(&self, sk: &SecretKey, encoding: Encoding) -> Result<Vec<u64>> {
+        let pt_numerator = Zeroizing::new(sk.try_decrypt(&self.numerator)?);
+        let pt_denominator = Zeroizing::new(sk.try_decrypt(&self.denominator)?);
+        let mut numerator = Vec::<u64>::try_decode(&pt_numerator, encoding.clone())?;
+        let mut denominator = Zeroizing::new(Vec::<u64>::try_decode(&pt_denominator, encoding)?);
+        sk.par
+            .plaintext
+            .inv_vec(&mut denominator)
+            .ok_or(Error::DefaultError(
+                "The denominator is not invertible".to_string(),
+            ))?;
+        sk.par.plaintext.mul_vec(&mut numerator, &denominator);
+        Ok(numerator)
+    }
+}
+
+impl FheParametrized for EncryptedRational {
+    type Parameters = BfvParameters;
+}
+
+impl Serialize for EncryptedRational {
+    fn to_bytes(&self) -> Vec<u8> {
+        EncryptedRationalProto {
+            numerator: Some((&self.numerator).into()),
+            denominator: Some((&self.denominator).into()),
+        }
+        .encode_to_vec()
+    }
+}
+
+impl DeserializeParametrized for EncryptedRational {
+    type Error = Error;
+
+    fn from_bytes(bytes: &[u8], par: &Arc<BfvParameters>) -> Result<Self> {
+        let proto: EncryptedRationalProto =
+            Message::decode(bytes).map_err(|_| Error::SerializationError)?;
+        match (proto.numerator.as_ref(), proto.denominator.as_ref()) {
+            (Some(numerator), Some(denominator)) => Self::new(
+                Ciphertext::try_convert_from(numerator, par)?,
+                Ciphertext::try_convert_from(denominator, par)?,
+            )
+            .map_err(|_| Error::SerializationError),
+            _ => Err(Error::SerializationError),
+        }
+    }
+}
+
+#[cfg(all(test, not(feature = "server")))]
+mod tests {
+    use super::EncryptedRational;
+    use crate::bfv::{
+        BfvParameters, Encoding, Multiplicator, Plaintext, RelinearizationKey, SecretKey,
+    };
+    use crate::Error;
+    use fhe_traits::{DeserializeParametrized, FheEncoder, FheEncrypter, Serialize};
+    use rand::thread_rng;
+    extern crate alloc;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn rational_ops() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(3, 16);
+        let t = &params.plaintext;
+        let sk = SecretKey::random(&params, &mut rng);
+        let rk = RelinearizationKey::new(&sk, &mut rng)?;
+        let multiplicator = Multiplicator::default(&rk)?;
+
+        let mut encrypt = |num: &[u64], den: &[u64]| -> Result<EncryptedRational, Error> {
+            let pt_num = Plaintext::try_encode(num, Encoding::simd(), &params)?;
+            let pt_den = Plaintext::try_encode(den, Encoding::simd(), &params)?;
+            EncryptedRational::new(
+                sk.try_encrypt(&pt_num, &mut rng)?,
+                sk.try_encrypt(&pt_den, &mut rng)?,
+            )
+        };
+        let reduce = |num: &[u64], den: &[u64]| -> Vec<u64> {
+            num.iter()
+                .zip(den.iter())
+                .map(|(n, d)| t.mul(*n, t.inv(*d).unwrap()))
+                .collect()
+        };
+
+        // Compute 1/2 + 1/3 and 1/2 * 1/3 in every slot.
+        let x = encrypt(&[1; 16], &[2; 16])?;
+        let y = encrypt(&[1; 16], &[3; 16])?;
+        assert_eq!(
+            x.try_decrypt(&sk, Encoding::simd())?,
+            reduce(&[1], &[2]).repeat(16)
+        );
+
+        let sum = x.add(&y, &multiplicator)?;
+        assert_eq!(
+            sum.try_decrypt(&sk, Encoding::simd())?,
+            reduce(&[5], &[6]).repeat(16)
+        );
+
+        let product = x.mul(&y, &multiplicator)?;
+        assert_eq!(
+            product.try_decrypt(&sk, Encoding::simd())?,
+            reduce(&[1], &[6]).repeat(16)
+        );
+
+        let z = encrypt(&[1; 16], &[0; 16])?;
+        assert!(z.try_decrypt(&sk, Encoding::simd()).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn serialize() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(2, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let pt_num = Plaintext::try_encode(&[2u64], Encoding::poly(), &params)?;
+        let pt_den = Plaintext::try_encode(&[3u64], Encoding::poly(), &params)?;
+        let x = EncryptedRational::new(
+            sk.try_encrypt(&pt_num, &mut rng)?,
+            sk.try_encrypt(&pt_den, &mut rng)?,
+        )?;
+        assert_eq!(EncryptedRational::from_bytes(&x.to_bytes(), &params)?, x);
+        assert!(EncryptedRational::from_bytes(&x.numerator().to_bytes(), &params).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,198 @@
+//! Redundancy slots detecting the decryption of overly noisy ciphertexts.
+//!
+//! A [`Redundancy`] reserves the last slots of a SIMD plaintext to store a
+//! copy of the first data slots. When the noise of a ciphertext exceeds its
+//! noise budget, the decrypted slots are essentially random, so that the
+//! copies do not match anymore, and the decryption fails with
+//! [`Error::NoiseBudgetExceeded`] instead of silently returning incorrect
+//! values.
+//!
+//! Unlike the tags of an [`IntegrityKey`](crate::bfv::IntegrityKey), the
+//! copies do not require a secret, and are preserved by all the slot-wise
+//! operations: additions, subtractions and multiplications of ciphertexts and
+//! of plaintexts encoded with the same [`Redundancy`]. They are not preserved
+//! by rotations.
+
+use super::encoding::EncodingEnum;
+use crate::bfv::{BfvParameters, Encoding, Plaintext};
+#[cfg(not(any(feature = "client", feature = "server")))]
+use crate::bfv::{Ciphertext, SecretKey};
+use crate::{Error, Result};
+#[cfg(not(any(feature = "client", feature = "server")))]
+use fhe_traits::FheDecrypter;
+use fhe_traits::{FheDecoder, FheEncoder};
+extern crate alloc;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use zeroize::Zeroizing;
+
+/// Layout of SIMD plaintexts reserving redundancy slots, which trades slots
+/// for the detection of the ciphertexts whose noise budget is exhausted.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Redundancy {
+    par: Arc<BfvParameters>,
+    redundant_slots: usize,
+}
+
+impl Redundancy {
+    /// Create a [`Redundancy`] reserving `redundant_slots` slots, i.e. an
+    /// overhead of `redundant_slots / degree`. A ciphertext whose noise budget
+    /// is exhausted passes the verification with probability about
+    /// `t^(-redundant_slots)`, where `t` is the plaintext modulus.
+    ///
+    /// Returns an error if the parameters do not support the SIMD encoding,
+    /// or if `redundant_slots` is 0 or larger than half the degree.
+    pub fn new(par: &Arc<BfvParameters>, redundant_slots: usize) -> Result<Self> {
+        if par.op.is_none() {
+            return Err(Error::EncodingNotSupported(EncodingEnum::Simd.to_string()));
+        }
+        if redundant_slots == 0 || 2 * redundant_slots > par.degree() {
+            return Err(Error::DefaultError(
+                "Invalid number of redundant slots".to_string(),
+            ));
+        }
+        Ok(Self {
+            par: par.clone(),
+            redundant_slots,
+        })
+    }
+
+    /// Create the [`Redundancy`] with the fewest slots such that a ciphertext
+    /// whose noise budget is exhausted passes the verification with
+    /// probability at most about `2^(-detection_bits)`.
+    pub fn with_detection_bits(par: &Arc<BfvParameters>, detection_bits: usize) -> Result<Self> {
+        let plaintext_bits = par.plaintext.ilog2() as usize;
+        Self::new(par, detection_bits.div_ceil(plaintext_bits).max(1))
+    }
+
+    /// Returns the number of redundant slots.
+    pub fn redundant_slots(&self) -> usize {
+        self.redundant_slots
+    }
+
+    /// Returns the number of slots available for data.
+    pub fn data_slots(&self) -> usize {
+        self.par.degree() - self.redundant_slots
+    }
+
+    /// Encode `values` in a SIMD [`Plaintext`] at level `level`, and copy the
+    /// first data slots in the redundant slots.
+    pub fn try_encode(&self, values: &[u64], level: usize) -> Result<Plaintext> {
+        if values.len() > self.data_slots() {
+            return Err(Error::TooManyValues(values.len(), self.data_slots()));
+        }
+        let mut v = Zeroizing::new(self.par.plaintext.reduce_vec_new(values));
+        v.resize(self.data_slots(), 0);
+        v.extend_from_within(..self.redundant_slots);
+        Plaintext::try_encode(v.as_slice(), Encoding::simd_at_level(level), &self.par)
+    }
+
+    /// Decode a SIMD [`Plaintext`] and verify its redundant slots. Returns the
+    /// data slots, or [`Error::NoiseBudgetExceeded`] if a redundant slot does
+    /// not match its data slot.
+    pub fn try_decode(&self, pt: &Plaintext) -> Result<Vec<u64>> {
+        let mut v = Vec::<u64>::try_decode(pt, Encoding::simd_at_level(pt.level))?;
+        let copies = Zeroizing::new(v.split_off(self.data_slots()));
+        if copies.as_slice() == &v[..self.redundant_slots] {
+            Ok(v)
+        } else {
+            Err(Error::NoiseBudgetExceeded)
+        }
+    }
+
+    /// Decrypt a [`Ciphertext`] with the secret key `sk` and verify the
+    /// redundant slots of the underlying plaintext.
+    #[cfg(not(any(feature = "client", feature = "server")))]
+    pub fn try_decrypt(&self, sk: &SecretKey, ct: &Ciphertext) -> Result<Vec<u64>> {
+        if self.par != ct.par {
+            return Err(Error::DefaultError(
+                "Incompatible BFV parameters".to_string(),
+            ));
+        }
+        let pt = Zeroizing::new(sk.try_decrypt(ct)?);
+        self.try_decode(&pt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Redundancy;
+    use crate::bfv::BfvParameters;
+    use crate::Error;
+    use rand::thread_rng;
+
+    #[test]
+    fn encode_decode() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(2, 16);
+        let redundancy = Redundancy::new(&params, 3)?;
+        let a = params
+            .plaintext
+            .random_vec(redundancy.data_slots(), &mut rng);
+        let pt = redundancy.try_encode(&a, 0)?;
+        assert_eq!(redundancy.try_decode(&pt)?, a);
+
+        let other = Redundancy::new(&params, 4)?;
+        assert_eq!(other.try_decode(&pt), Err(Error::NoiseBudgetExceeded));
+        Ok(())
+    }
+
+    #[cfg(not(any(feature = "client", feature = "server")))]
+    #[test]
+    fn redundancy() -> Result<(), Error> {
+        use crate::bfv::{Ciphertext, RelinearizationKey, SecretKey};
+        use fhe_math::rq::{Poly, Representation};
+        use fhe_traits::FheEncrypter;
+
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(2, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let rk = RelinearizationKey::new(&sk, &mut rng)?;
+        let redundancy = Redundancy::new(&params, 3)?;
+        assert_eq!(redundancy.data_slots(), 13);
+        assert!(Redundancy::new(&params, 0).is_err());
+        assert!(Redundancy::new(&params, 9).is_err());
+        // The plaintext modulus 1153 has 10 bits.
+        assert_eq!(
+            Redundancy::with_detection_bits(&params, 40)?.redundant_slots(),
+            4
+        );
+        assert_eq!(
+            Redundancy::with_detection_bits(&params, 1)?.redundant_slots(),
+            1
+        );
+
+        let a = params
+            .plaintext
+            .random_vec(redundancy.data_slots(), &mut rng);
+        let b = params
+            .plaintext
+            .random_vec(redundancy.data_slots(), &mut rng);
+        assert!(redundancy.try_encode(&[0u64; 14], 0).is_err());
+        let pt_b = redundancy.try_encode(&b, 0)?;
+        let ct_a: Ciphertext = sk.try_encrypt(&redundancy.try_encode(&a, 0)?, &mut rng)?;
+        let ct_b: Ciphertext = sk.try_encrypt(&pt_b, &mut rng)?;
+        assert_eq!(redundancy.try_decrypt(&sk, &ct_a)?, a);
+
+        // The copies are preserved by additions and multiplications.
+        let mut ab = a.clone();
+        params.plaintext.mul_vec(&mut ab, &b);
+        let mut expected = ab.clone();
+        params.plaintext.add_vec(&mut expected, &b);
+        let mut ct = &ct_a * &ct_b;
+        rk.relinearizes(&mut ct)?;
+        assert_eq!(redundancy.try_decrypt(&sk, &(&ct + &ct_b))?, expected);
+        assert_eq!(redundancy.try_decrypt(&sk, &(&ct_a * &pt_b))?, ab);
+
+        // A ciphertext whose noise budget is exhausted has random phase.
+        let mut noisy = ct_a.clone();
+        let random = Poly::random(noisy.c[0].ctx(), Representation::Ntt, &mut rng);
+        noisy.c[0] += &random;
+        assert_eq!(
+            redundancy.try_decrypt(&sk, &noisy),
+            Err(Error::NoiseBudgetExceeded)
+        );
+        Ok(())
+    }
+}
@@ -1,23 +1,27 @@
 use core::ops::Mul;
 extern crate alloc;
-use alloc::vec;
 use alloc::vec::Vec;
 
 use crate::proto::bfv::{
     KeySwitchingKey as KeySwitchingKeyProto, RgswCiphertext as RGSWCiphertextProto,
 };
 use crate::{Error, Result};
-use fhe_math::rq::{traits::TryConvertFrom as TryConvertFromPoly, Poly, Representation};
-use fhe_traits::{
-    DeserializeParametrized, FheCiphertext, FheEncrypter, FheParametrized, Serialize,
-};
+use fhe_math::rq::Representation;
+#[cfg(not(feature = "server"))]
+use fhe_math::rq::{traits::TryConvertFrom as TryConvertFromPoly, Poly};
+#[cfg(not(feature = "server"))]
+use fhe_traits::FheEncrypter;
+use fhe_traits::{DeserializeParametrized, FheCiphertext, FheParametrized, Serialize};
 use prost::Message;
-use rand::RngCore;
+#[cfg(not(feature = "server"))]
+use rand::{CryptoRng, RngCore};
+use smallvec::smallvec;
+#[cfg(not(feature = "server"))]
 use zeroize::Zeroizing;
 
-use super::{
-    keys::KeySwitchingKey, traits::TryConvertFrom, BfvParameters, Ciphertext, Plaintext, SecretKey,
-};
+use super::{keys::KeySwitchingKey, traits::TryConvertFrom, BfvParameters, Ciphertext};
+#[cfg(not(feature = "server"))]
+use super::{Plaintext, SecretKey};
 
 /// A RGSW ciphertext encrypting a plaintext.
 #[derive(Debug, PartialEq, Eq)]
@@ -80,10 +84,15 @@ impl Serialize for RGSWCiphertext {
 
 impl FheCiphertext for RGSWCiphertext {}
 
+#[cfg(not(feature = "server"))]
 impl FheEncrypter<Plaintext, RGSWCiphertext> for SecretKey {
     type Error = Error;
 
-    fn try_encrypt<R: RngCore>(&self, pt: &Plaintext, rng: &mut R) -> Result<RGSWCiphertext> {
+    fn try_encrypt<R: RngCore + CryptoRng>(
+        &self,
+        pt: &Plaintext,
+        rng: &mut R,
+    ) -> Result<RGSWCiphertext> {
         let level = pt.level;
         let ctx = self.par.ctx_at_level(level)?;
 
@@ -131,8 +140,10 @@ impl Mul<&RGSWCiphertext> for &Ciphertext {
         Ciphertext {
             par: self.par.clone(),
             seed: None,
-            c: vec![&c0 + &c0p, &c1 + &c1p],
+            c: smallvec![&c0 + &c0p, &c1 + &c1p],
             level: self.level,
+            tag: self.tag.clone(),
+            noise_estimate: None,
         }
     }
 }
@@ -145,7 +156,7 @@ impl Mul<&Ciphertext> for &RGSWCiphertext {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, not(feature = "server")))]
 mod tests {
     use crate::Error;
 
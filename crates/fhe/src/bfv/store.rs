@@ -0,0 +1,345 @@
+//! Out-of-core storage of ciphertexts in a memory-mapped file.
+//!
+//! The file starts with a header of 16 bytes, made of a magic number and of
+//! the identifier of the parameters (see [`BfvParameters::id`]), followed by
+//! the serializations of the ciphertexts, each prefixed by its length as a
+//! little-endian u64. The index of the records is rebuilt when opening the
+//! file, by only reading their length prefixes, and the ciphertexts are only
+//! deserialized when accessed.
+
+extern crate alloc;
+extern crate std;
+
+use crate::bfv::{BfvParameters, Ciphertext};
+use crate::{Error, Result};
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ops::Range;
+use fhe_traits::{DeserializeParametrized, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{Seek, SeekFrom, Write};
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+
+/// Magic number at the start of the file.
+const MAGIC: &[u8; 8] = b"FHECTS01";
+
+/// Size of the header of the file.
+const HEADER_SIZE: usize = 16;
+
+/// Size of the length prefix of a record.
+const PREFIX_SIZE: usize = 8;
+
+fn io_error(e: std::io::Error) -> Error {
+    Error::DefaultError(e.to_string())
+}
+
+/// Read-only memory mapping of a file.
+#[derive(Debug)]
+struct Mmap {
+    ptr: *mut libc::c_void,
+    len: usize,
+}
+
+// The mapping is read-only and owned by this struct.
+unsafe impl Send for Mmap {}
+unsafe impl Sync for Mmap {}
+
+impl Mmap {
+    /// Map the first `len` bytes of the file, which must not be empty.
+    fn new(file: &File, len: usize) -> Result<Self> {
+        debug_assert!(len > 0);
+        // Safety: the arguments describe a valid read-only mapping of the file,
+        // and the result is checked before being used.
+        let ptr = unsafe {
+            libc::mmap(
+                core::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io_error(std::io::Error::last_os_error()));
+        }
+        Ok(Self { ptr, len })
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        // Safety: the mapping is valid for `len` bytes until it is dropped, and
+        // the records are only ever appended to the file.
+        unsafe { core::slice::from_raw_parts(self.ptr as *const u8, self.len) }
+    }
+}
+
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        // Safety: the pointer and the length are those of the mapping.
+        unsafe {
+            libc::munmap(self.ptr, self.len);
+        }
+    }
+}
+
+/// Append-only store of ciphertexts backed by a memory-mapped file, to hold
+/// more ciphertexts than fit in memory, e.g. the database of a PIR server.
+///
+/// The ciphertexts are identified by their index in the store, and are
+/// deserialized lazily when accessed with [`CiphertextStore::get`]; the pages
+/// of the file are loaded in memory by the operating system as needed.
+#[derive(Debug)]
+pub struct CiphertextStore {
+    par: Arc<BfvParameters>,
+    file: File,
+    map: Option<Mmap>,
+    /// Range of the serialization of each ciphertext in the file.
+    index: Vec<Range<usize>>,
+}
+
+impl CiphertextStore {
+    /// Create an empty store in the file at `path` for ciphertexts with the
+    /// parameters `par`, truncating the file if it exists.
+    pub fn create<P: AsRef<Path>>(path: P, par: &Arc<BfvParameters>) -> Result<Self> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)
+            .map_err(io_error)?;
+        file.write_all(MAGIC).map_err(io_error)?;
+        file.write_all(&par.id().to_le_bytes()).map_err(io_error)?;
+        let mut store = Self {
+            par: par.clone(),
+            file,
+            map: None,
+            index: Vec::new(),
+        };
+        store.remap()?;
+        Ok(store)
+    }
+
+    /// Open the store in the file at `path`, for ciphertexts with the
+    /// parameters `par`.
+    ///
+    /// Returns an error if the file is not a store created with these
+    /// parameters, or if its last record is truncated.
+    pub fn open<P: AsRef<Path>>(path: P, par: &Arc<BfvParameters>) -> Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)
+            .map_err(io_error)?;
+        let mut store = Self {
+            par: par.clone(),
+            file,
+            map: None,
+            index: Vec::new(),
+        };
+        store.remap()?;
+
+        let bytes = store.bytes();
+        if bytes.len() < HEADER_SIZE
+            || &bytes[..8] != MAGIC
+            || bytes[8..HEADER_SIZE] != par.id().to_le_bytes()
+        {
+            return Err(Error::SerializationError);
+        }
+        let mut index = Vec::new();
+        let mut offset = HEADER_SIZE;
+        while offset < bytes.len() {
+            let start = offset + PREFIX_SIZE;
+            let len = bytes
+                .get(offset..start)
+                .map(|prefix| u64::from_le_bytes(prefix.try_into().unwrap()) as usize)
+                .ok_or(Error::SerializationError)?;
+            let end = start.checked_add(len).ok_or(Error::SerializationError)?;
+            if end > bytes.len() {
+                return Err(Error::SerializationError);
+            }
+            index.push(start..end);
+            offset = end;
+        }
+        store.index = index;
+        Ok(store)
+    }
+
+    /// Returns the parameters of the ciphertexts.
+    pub fn parameters(&self) -> &Arc<BfvParameters> {
+        &self.par
+    }
+
+    /// Returns the number of ciphertexts in the store.
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Returns whether the store is empty.
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Append a ciphertext to the store, and returns its identifier.
+    pub fn append(&mut self, ct: &Ciphertext) -> Result<usize> {
+        Ok(self.append_all(core::slice::from_ref(ct))?.start)
+    }
+
+    /// Append ciphertexts to the store, and returns the range of their
+    /// identifiers. The file is mapped again once per call, so that appending
+    /// many ciphertexts at once is faster than appending them one by one.
+    ///
+    /// If writing to or mapping the file fails, the file is truncated to its
+    /// previous length, so that the store is left unchanged, and the error of
+    /// the write or of the mapping is returned.
+    pub fn append_all(&mut self, cts: &[Ciphertext]) -> Result<Range<usize>> {
+        if cts.iter().any(|ct| ct.par != self.par) {
+            return Err(Error::DefaultError(
+                "Incompatible BFV parameters".to_string(),
+            ));
+        }
+        let first = self.index.len();
+        let old_len = self.file.seek(SeekFrom::End(0)).map_err(io_error)?;
+        let mut offset = old_len as usize;
+        let mut buffer = Vec::new();
+        for ct in cts {
+            let bytes = ct.to_bytes();
+            buffer.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+            buffer.extend_from_slice(&bytes);
+            self.index
+                .push(offset + PREFIX_SIZE..offset + PREFIX_SIZE + bytes.len());
+            offset += PREFIX_SIZE + bytes.len();
+        }
+        if let Err(e) = self
+            .file
+            .write_all(&buffer)
+            .map_err(io_error)
+            .and_then(|_| self.remap())
+        {
+            // The error of the truncation is ignored in favor of the first one.
+            self.index.truncate(first);
+            let _ = self.file.set_len(old_len);
+            return Err(e);
+        }
+        Ok(first..self.index.len())
+    }
+
+    /// Returns the serialization of the ciphertext with identifier `id`,
+    /// without copying it, or None if there is no such ciphertext.
+    ///
+    /// The slice borrows the shared mapping of the file: if the file is
+    /// truncated by another process while the slice is alive, reading it
+    /// raises a `SIGBUS` signal. The file must therefore only be appended to
+    /// while the store is open.
+    pub fn get_bytes(&self, id: usize) -> Option<&[u8]> {
+        self.index.get(id).map(|range| &self.bytes()[range.clone()])
+    }
+
+    /// Deserialize the ciphertext with identifier `id`.
+    ///
+    /// Returns [`Error::UnknownIdentifier`] if there is no such ciphertext.
+    pub fn get(&self, id: usize) -> Result<Ciphertext> {
+        let bytes = self.get_bytes(id).ok_or(Error::UnknownIdentifier(id))?;
+        Ciphertext::from_bytes(bytes, &self.par)
+    }
+
+    /// Returns an iterator deserializing the ciphertexts of the store in the
+    /// order of their identifiers.
+    pub fn iter(&self) -> impl Iterator<Item = Result<Ciphertext>> + '_ {
+        (0..self.len()).map(|id| self.get(id))
+    }
+
+    /// Flush the appended ciphertexts to the disk.
+    pub fn sync(&self) -> Result<()> {
+        self.file.sync_data().map_err(io_error)
+    }
+
+    fn bytes(&self) -> &[u8] {
+        self.map.as_ref().map_or(&[], |map| map.as_slice())
+    }
+
+    /// Map the whole file again, after it grew. The previous mapping is kept
+    /// if the new one cannot be created.
+    fn remap(&mut self) -> Result<()> {
+        let len = self.file.metadata().map_err(io_error)?.len() as usize;
+        let map = if len > 0 {
+            Some(Mmap::new(&self.file, len)?)
+        } else {
+            None
+        };
+        self.map = map;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(any(feature = "client", feature = "server"))))]
+mod tests {
+    use super::CiphertextStore;
+    use crate::bfv::{BfvParameters, Ciphertext, Encoding, Plaintext, SecretKey};
+    use crate::Error;
+    use fhe_traits::{FheEncoder, FheEncrypter, Serialize};
+    use rand::thread_rng;
+    extern crate alloc;
+    extern crate std;
+    use alloc::vec::Vec;
+    use std::io::Write;
+
+    #[test]
+    fn append_and_reopen() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(2, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let cts = (0..10)
+            .map(|i| {
+                let pt = Plaintext::try_encode(&[i as u64], Encoding::poly(), &params)?;
+                sk.try_encrypt(&pt, &mut rng)
+            })
+            .collect::<Result<Vec<Ciphertext>, Error>>()?;
+
+        let path = std::env::temp_dir().join(alloc::format!(
+            "fhe-store-{}-{}",
+            std::process::id(),
+            params.id()
+        ));
+        let mut store = CiphertextStore::create(&path, &params)?;
+        assert!(store.is_empty());
+        assert_eq!(store.append(&cts[0])?, 0);
+        assert_eq!(store.append_all(&cts[1..])?, 1..10);
+        assert_eq!(store.len(), 10);
+        assert_eq!(store.get(3)?, cts[3]);
+        assert_eq!(store.get_bytes(4), Some(cts[4].to_bytes().as_slice()));
+        assert_eq!(store.get(10).unwrap_err(), Error::UnknownIdentifier(10));
+        assert_eq!(store.get_bytes(10), None);
+        store.sync()?;
+        drop(store);
+
+        let mut store = CiphertextStore::open(&path, &params)?;
+        assert_eq!(store.len(), 10);
+        assert_eq!(store.iter().collect::<Result<Vec<_>, Error>>()?, cts);
+        assert_eq!(store.append(&cts[0])?, 10);
+        assert_eq!(store.get(10)?, cts[0]);
+        drop(store);
+
+        // The parameters must match, and a truncated record is detected.
+        let other = BfvParameters::default_arc(1, 16);
+        assert_eq!(
+            CiphertextStore::open(&path, &other).unwrap_err(),
+            Error::SerializationError
+        );
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        file.write_all(&100u64.to_le_bytes()).unwrap();
+        drop(file);
+        assert_eq!(
+            CiphertextStore::open(&path, &params).unwrap_err(),
+            Error::SerializationError
+        );
+
+        std::fs::remove_file(&path).unwrap();
+        Ok(())
+    }
+}
@@ -0,0 +1,474 @@
+//! Encrypted tensors with shape-aware operations.
+//!
+//! An [`EncryptedTensor`] stores its elements in row-major order in the first
+//! row of SIMD slots of consecutive ciphertexts, i.e. `degree / 2` elements
+//! per ciphertext, and the second row is left to zero. The operations which
+//! move elements across slots are expressed as linear maps: each output
+//! element is a weighted sum of input elements, and the map is computed with
+//! one column rotation and one plaintext multiplication per distinct pair of
+//! input ciphertext and rotation index. The rotations are decomposed in powers
+//! of two, so that an evaluation key supporting the column rotations returned
+//! by [`EncryptedTensor::rotations`] supports all the operations.
+
+use super::encoding::EncodingEnum;
+#[cfg(not(feature = "server"))]
+use crate::bfv::SecretKey;
+use crate::bfv::{
+    traits::TryConvertFrom, BfvParameters, Ciphertext, Encoding, EvaluationKey, Plaintext,
+};
+use crate::proto::bfv::{Ciphertext as CiphertextProto, EncryptedTensor as EncryptedTensorProto};
+use crate::{Error, Result};
+use fhe_traits::{DeserializeParametrized, FheEncoder, FheEncrypter, FheParametrized, Serialize};
+#[cfg(not(feature = "server"))]
+use fhe_traits::{FheDecoder, FheDecrypter};
+use hashbrown::HashMap;
+use prost::Message;
+use rand::{CryptoRng, RngCore};
+extern crate alloc;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Tensor of elements of `Z_t` encrypted across the slots of ciphertexts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncryptedTensor {
+    par: Arc<BfvParameters>,
+    shape: Vec<usize>,
+    ciphertexts: Vec<Ciphertext>,
+}
+
+impl EncryptedTensor {
+    /// Returns the number of elements stored in each ciphertext.
+    pub fn slots_per_ciphertext(par: &BfvParameters) -> usize {
+        par.degree() / 2
+    }
+
+    /// Returns the column rotations that an [`EvaluationKey`] must support to
+    /// compute the operations on encrypted tensors.
+    pub fn rotations(par: &BfvParameters) -> Vec<usize> {
+        let slots = Self::slots_per_ciphertext(par);
+        (0..slots.ilog2()).map(|i| 1 << i).collect()
+    }
+
+    /// Encrypt the tensor of shape `shape` whose elements are `values` in
+    /// row-major order.
+    ///
+    /// Returns an error if the parameters do not support the SIMD encoding, or
+    /// if the number of values does not match the shape.
+    pub fn encrypt<E, R>(
+        values: &[u64],
+        shape: &[usize],
+        par: &Arc<BfvParameters>,
+        encrypter: &E,
+        rng: &mut R,
+    ) -> Result<Self>
+    where
+        E: FheEncrypter<Plaintext, Ciphertext, Error = Error, Parameters = BfvParameters>,
+        R: RngCore + CryptoRng,
+    {
+        if par.op.is_none() {
+            return Err(Error::EncodingNotSupported(EncodingEnum::Simd.to_string()));
+        }
+        let len = shape.iter().product::<usize>();
+        if values.len() > len {
+            return Err(Error::TooManyValues(values.len(), len));
+        } else if values.len() < len {
+            return Err(Error::TooFewValues(values.len(), len));
+        }
+        let slots = Self::slots_per_ciphertext(par);
+        let ciphertexts = (0..len.div_ceil(slots).max(1))
+            .map(|i| {
+                let chunk = &values[(i * slots).min(len)..((i + 1) * slots).min(len)];
+                let pt = Plaintext::try_encode(chunk, Encoding::simd(), par)?;
+                encrypter.try_encrypt(&pt, rng)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            par: par.clone(),
+            shape: shape.to_vec(),
+            ciphertexts,
+        })
+    }
+
+    /// Decrypt the tensor, and returns its elements in row-major order.
+    #[cfg(not(feature = "server"))]
+    pub fn decrypt(&self, sk: &SecretKey) -> Result<Vec<u64>> {
+        let slots = Self::slots_per_ciphertext(&self.par);
+        let mut values = Vec::with_capacity(self.ciphertexts.len() * slots);
+        for ct in &self.ciphertexts {
+            let pt = sk.try_decrypt(ct)?;
+            let v = Vec::<u64>::try_decode(&pt, Encoding::simd_at_level(ct.level))?;
+            values.extend_from_slice(&v[..slots]);
+        }
+        values.truncate(self.len());
+        Ok(values)
+    }
+
+    /// Returns the shape of the tensor.
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    /// Returns the row-major strides of the tensor, in number of elements.
+    pub fn strides(&self) -> Vec<usize> {
+        let mut strides = vec![1; self.shape.len()];
+        for i in (1..self.shape.len()).rev() {
+            strides[i - 1] = strides[i] * self.shape[i];
+        }
+        strides
+    }
+
+    /// Returns the number of elements of the tensor.
+    pub fn len(&self) -> usize {
+        self.shape.iter().product()
+    }
+
+    /// Returns whether the tensor has no element.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the ciphertexts storing the tensor.
+    pub fn ciphertexts(&self) -> &[Ciphertext] {
+        &self.ciphertexts
+    }
+
+    /// Returns the index of the ciphertext and of the slot storing the
+    /// element at `index`, or `None` if the index is out of bounds.
+    pub fn position(&self, index: &[usize]) -> Option<(usize, usize)> {
+        if index.len() != self.shape.len() || index.iter().zip(&self.shape).any(|(i, s)| i >= s) {
+            return None;
+        }
+        let flat = index
+            .iter()
+            .zip(self.strides())
+            .map(|(i, s)| i * s)
+            .sum::<usize>();
+        let slots = Self::slots_per_ciphertext(&self.par);
+        Some((flat / slots, flat % slots))
+    }
+
+    /// Element-wise addition of two tensors of the same shape.
+    pub fn add(&self, other: &Self) -> Result<Self> {
+        if self.par != other.par {
+            return Err(Error::DefaultError(
+                "Incompatible BFV parameters".to_string(),
+            ));
+        }
+        if self.shape != other.shape {
+            return Err(Error::DefaultError("Mismatched shapes".to_string()));
+        }
+        let ciphertexts = self
+            .ciphertexts
+            .iter()
+            .zip(&other.ciphertexts)
+            .map(|(a, b)| {
+                if a.level != b.level {
+                    Err(Error::DefaultError("Mismatched levels".to_string()))
+                } else {
+                    Ok(a + b)
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            par: self.par.clone(),
+            shape: self.shape.clone(),
+            ciphertexts,
+        })
+    }
+
+    /// Element-wise multiplication by a plaintext tensor of the same shape,
+    /// whose elements are `values` in row-major order.
+    pub fn mul_plain(&self, values: &[u64]) -> Result<Self> {
+        if values.len() > self.len() {
+            return Err(Error::TooManyValues(values.len(), self.len()));
+        } else if values.len() < self.len() {
+            return Err(Error::TooFewValues(values.len(), self.len()));
+        }
+        let slots = Self::slots_per_ciphertext(&self.par);
+        let ciphertexts = self
+            .ciphertexts
+            .iter()
+            .enumerate()
+            .map(|(i, ct)| {
+                let chunk =
+                    &values[(i * slots).min(values.len())..((i + 1) * slots).min(values.len())];
+                let pt =
+                    Plaintext::try_encode(chunk, Encoding::simd_at_level(ct.level), &self.par)?;
+                Ok(ct * &pt)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            par: self.par.clone(),
+            shape: self.shape.clone(),
+            ciphertexts,
+        })
+    }
+
+    /// Multiply the matrix stored in this tensor of shape `[m, k]` by the
+    /// plaintext matrix of shape `[k, columns]` whose elements are `matrix`
+    /// in row-major order. The result has shape `[m, columns]`.
+    pub fn matmul_plain(&self, matrix: &[u64], columns: usize, ek: &EvaluationKey) -> Result<Self> {
+        if self.shape.len() != 2 {
+            return Err(Error::DefaultError(
+                "The tensor is not a matrix".to_string(),
+            ));
+        }
+        let (m, k) = (self.shape[0], self.shape[1]);
+        if matrix.len() != k * columns {
+            return Err(Error::DefaultError(
+                "The plaintext matrix has an incorrect number of elements".to_string(),
+            ));
+        }
+        let map = (0..m).flat_map(|i| {
+            (0..columns).flat_map(move |j| {
+                (0..k).map(move |l| (i * columns + j, i * k + l, matrix[l * columns + j]))
+            })
+        });
+        self.linear_map(&[m, columns], map, ek)
+    }
+
+    /// Sum the elements along the axis `axis`. The result has the shape of
+    /// the tensor without this axis.
+    pub fn sum(&self, axis: usize, ek: &EvaluationKey) -> Result<Self> {
+        if axis >= self.shape.len() {
+            return Err(Error::DefaultError("Invalid axis".to_string()));
+        }
+        let stride = self.strides()[axis];
+        let size = self.shape[axis];
+        let outer = self.shape[..axis].iter().product::<usize>();
+        let mut shape = self.shape.clone();
+        shape.remove(axis);
+        let map = (0..outer).flat_map(|o| {
+            (0..stride).flat_map(move |i| {
+                (0..size).map(move |j| (o * stride + i, (o * size + j) * stride + i, 1))
+            })
+        });
+        self.linear_map(&shape, map, ek)
+    }
+
+    /// Compute the tensor of shape `shape` whose element at index `i` is the
+    /// sum of `w * x[j]` for the triples `(i, j, w)` of `map`, where `x` is
+    /// this tensor and the indices are row-major.
+    fn linear_map<I: Iterator<Item = (usize, usize, u64)>>(
+        &self,
+        shape: &[usize],
+        map: I,
+        ek: &EvaluationKey,
+    ) -> Result<Self> {
+        let slots = Self::slots_per_ciphertext(&self.par);
+        let len = shape.iter().product::<usize>();
+        let num_ciphertexts = len.div_ceil(slots).max(1);
+        let p = &self.par.plaintext;
+
+        // Group the weights by output ciphertext, input ciphertext and rotation.
+        let mut weights = HashMap::<(usize, usize, usize), Vec<u64>>::new();
+        for (i, j, w) in map {
+            let (a, u) = (i / slots, i % slots);
+            let (b, v) = (j / slots, j % slots);
+            let slot = &mut weights
+                .entry((a, b, (v + slots - u) % slots))
+                .or_insert_with(|| vec![0; slots])[u];
+            *slot = p.add(*slot, p.reduce(w));
+        }
+
+        let mut rotated = HashMap::<(usize, usize), Ciphertext>::new();
+        let mut ciphertexts = vec![Ciphertext::zero(&self.par); num_ciphertexts];
+        for ((a, b, r), w) in weights {
+            if !rotated.contains_key(&(b, r)) {
+                rotated.insert((b, r), Self::rotate(&self.ciphertexts[b], r, ek)?);
+            }
+            let ct = &rotated[&(b, r)];
+            let pt = Plaintext::try_encode(&w, Encoding::simd_at_level(ct.level), &self.par)?;
+            ciphertexts[a] += &(ct * &pt);
+        }
+
+        // Output ciphertexts without contribution encrypt zero.
+        for ct in ciphertexts.iter_mut().filter(|ct| ct.is_empty()) {
+            let level = self.ciphertexts[0].level;
+            *ct =
+                &self.ciphertexts[0] * &Plaintext::zero(Encoding::simd_at_level(level), &self.par)?;
+        }
+
+        Ok(Self {
+            par: self.par.clone(),
+            shape: shape.to_vec(),
+            ciphertexts,
+        })
+    }
+
+    /// Rotate the columns of `ct` by `r` as a sequence of rotations by powers
+    /// of two.
+    fn rotate(ct: &Ciphertext, r: usize, ek: &EvaluationKey) -> Result<Ciphertext> {
+        let mut out = ct.clone();
+        let mut i = 0;
+        while r >> i != 0 {
+            if (r >> i) & 1 == 1 {
+                out = ek.rotates_columns_by(&out, 1 << i)?;
+            }
+            i += 1;
+        }
+        Ok(out)
+    }
+}
+
+impl FheParametrized for EncryptedTensor {
+    type Parameters = BfvParameters;
+}
+
+impl Serialize for EncryptedTensor {
+    fn to_bytes(&self) -> Vec<u8> {
+        EncryptedTensorProto {
+            shape: self.shape.iter().map(|d| *d as u64).collect(),
+            ciphertexts: self.ciphertexts.iter().map(CiphertextProto::from).collect(),
+        }
+        .encode_to_vec()
+    }
+}
+
+impl DeserializeParametrized for EncryptedTensor {
+    type Error = Error;
+
+    fn from_bytes(bytes: &[u8], par: &Arc<BfvParameters>) -> Result<Self> {
+        let proto: EncryptedTensorProto =
+            Message::decode(bytes).map_err(|_| Error::SerializationError)?;
+        let shape = proto.shape.iter().map(|d| *d as usize).collect::<Vec<_>>();
+        let len = shape
+            .iter()
+            .try_fold(1usize, |acc, d| acc.checked_mul(*d))
+            .ok_or(Error::SerializationError)?;
+        let slots = Self::slots_per_ciphertext(par);
+        if proto.ciphertexts.len() != len.div_ceil(slots).max(1) {
+            return Err(Error::SerializationError);
+        }
+        let ciphertexts = proto
+            .ciphertexts
+            .iter()
+            .map(|ct| Ciphertext::try_convert_from(ct, par))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            par: par.clone(),
+            shape,
+            ciphertexts,
+        })
+    }
+}
+
+#[cfg(all(test, not(feature = "server")))]
+mod tests {
+    use super::EncryptedTensor;
+    use crate::bfv::{BfvParameters, EvaluationKeyBuilder, SecretKey};
+    use crate::Error;
+    use fhe_traits::{DeserializeParametrized, Serialize};
+    use rand::thread_rng;
+    extern crate alloc;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn tensor_ops() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let par = BfvParameters::default_arc(3, 16);
+        let p = &par.plaintext;
+        let sk = SecretKey::random(&par, &mut rng);
+        let mut builder = EvaluationKeyBuilder::new(&sk)?;
+        for r in EncryptedTensor::rotations(&par) {
+            builder.enable_column_rotation(r)?;
+        }
+        let ek = builder.build(&mut rng)?;
+
+        // A tensor of shape [3, 2, 4] spans three ciphertexts of 8 slots.
+        let shape = [3, 2, 4];
+        let x = p.random_vec(24, &mut rng);
+        let y = p.random_vec(24, &mut rng);
+        let tx = EncryptedTensor::encrypt(&x, &shape, &par, &sk, &mut rng)?;
+        let ty = EncryptedTensor::encrypt(&y, &shape, &par, &sk, &mut rng)?;
+        assert_eq!(tx.ciphertexts().len(), 3);
+        assert_eq!(tx.strides(), vec![8, 4, 1]);
+        assert_eq!(tx.position(&[2, 1, 3]), Some((2, 7)));
+        assert_eq!(tx.position(&[3, 0, 0]), None);
+        assert_eq!(tx.decrypt(&sk)?, x);
+        assert_eq!(EncryptedTensor::from_bytes(&tx.to_bytes(), &par)?, tx);
+
+        let sum = tx.add(&ty)?;
+        let expected = x
+            .iter()
+            .zip(&y)
+            .map(|(a, b)| p.add(*a, *b))
+            .collect::<Vec<_>>();
+        assert_eq!(sum.decrypt(&sk)?, expected);
+
+        let product = tx.mul_plain(&y)?;
+        let expected = x
+            .iter()
+            .zip(&y)
+            .map(|(a, b)| p.mul(*a, *b))
+            .collect::<Vec<_>>();
+        assert_eq!(product.decrypt(&sk)?, expected);
+
+        for axis in 0..3 {
+            let summed = tx.sum(axis, &ek)?;
+            let mut shape = shape.to_vec();
+            shape.remove(axis);
+            assert_eq!(summed.shape(), shape.as_slice());
+            let mut expected = vec![0u64; shape.iter().product()];
+            for (i, xi) in x.iter().enumerate() {
+                let index = [i / 8, (i / 4) % 2, i % 4];
+                let out = match axis {
+                    0 => index[1] * 4 + index[2],
+                    1 => index[0] * 4 + index[2],
+                    _ => index[0] * 2 + index[1],
+                };
+                expected[out] = p.add(expected[out], *xi);
+            }
+            assert_eq!(summed.decrypt(&sk)?, expected);
+        }
+        let total = tx.sum(0, &ek)?.sum(0, &ek)?.sum(0, &ek)?;
+        assert!(total.shape().is_empty());
+        assert_eq!(
+            total.decrypt(&sk)?,
+            vec![x.iter().fold(0, |acc, xi| p.add(acc, *xi))]
+        );
+
+        assert!(tx.sum(3, &ek).is_err());
+        assert!(tx.mul_plain(&y[1..]).is_err());
+        assert!(tx.add(&tx.sum(0, &ek)?).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn matmul() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let par = BfvParameters::default_arc(3, 16);
+        let p = &par.plaintext;
+        let sk = SecretKey::random(&par, &mut rng);
+        let mut builder = EvaluationKeyBuilder::new(&sk)?;
+        for r in EncryptedTensor::rotations(&par) {
+            builder.enable_column_rotation(r)?;
+        }
+        let ek = builder.build(&mut rng)?;
+
+        let (m, k, n) = (3, 4, 5);
+        let a = p.random_vec(m * k, &mut rng);
+        let b = p.random_vec(k * n, &mut rng);
+        let ta = EncryptedTensor::encrypt(&a, &[m, k], &par, &sk, &mut rng)?;
+        let tc = ta.matmul_plain(&b, n, &ek)?;
+        assert_eq!(tc.shape(), &[m, n]);
+        let mut expected = vec![0u64; m * n];
+        for i in 0..m {
+            for j in 0..n {
+                for l in 0..k {
+                    expected[i * n + j] =
+                        p.add(expected[i * n + j], p.mul(a[i * k + l], b[l * n + j]));
+                }
+            }
+        }
+        assert_eq!(tc.decrypt(&sk)?, expected);
+
+        assert!(ta.matmul_plain(&b[1..], n, &ek).is_err());
+        let t3 = EncryptedTensor::encrypt(&a, &[m, 2, 2], &par, &sk, &mut rng)?;
+        assert!(t3.matmul_plain(&b, n, &ek).is_err());
+        Ok(())
+    }
+}
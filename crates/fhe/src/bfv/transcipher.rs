@@ -0,0 +1,463 @@
+//! Transciphering: homomorphic decryption of a symmetric cipher over `Z_t`.
+//!
+//! Instead of uploading BFV ciphertexts, a client encrypts its data with a
+//! symmetric [`Cipher`] whose ciphertexts are vectors of `Z_t`, i.e. as large
+//! as the data, and uploads once an [`EncryptedKey`], the BFV encryption of
+//! the symmetric key. The server then homomorphically evaluates the decryption
+//! of up to `degree` symmetric ciphertexts in parallel, one per slot, to obtain
+//! BFV ciphertexts of the data.
+//!
+//! The cipher follows the structure of PASTA: a keystream block is computed
+//! from the key by alternating affine layers, with matrices and round
+//! constants derived from the nonce by an extendable output function, and
+//! Feistel S-boxes `x_i <- x_i + x_{i-1}^2`, so that the homomorphic
+//! decryption has multiplicative depth equal to the number of rounds. It is
+//! not interoperable with the reference PASTA implementation.
+//!
+//! # Security
+//!
+//! This is an unanalyzed construction: no cryptanalysis supports its security,
+//! even with the minimum state size [`MIN_STATE_SIZE`] and number of rounds
+//! [`MIN_ROUNDS`] enforced by [`Cipher::new`]. It must not protect real data.
+//!
+//! ```
+//! # // The secret key is not available with the `server` feature.
+//! # #[cfg(not(feature = "server"))]
+//! # fn main() -> Result<(), fhe::Error> {
+//! # use fhe::bfv::{BfvParametersBuilder, Encoding, RelinearizationKey, SecretKey};
+//! # use fhe::bfv::transcipher::{Cipher, EncryptedKey};
+//! # use fhe_traits::{FheDecoder, FheDecrypter};
+//! # use rand::thread_rng;
+//! # let mut rng = thread_rng();
+//! let par = BfvParametersBuilder::new()
+//!     .set_degree(16)
+//!     .set_plaintext_modulus(65537)
+//!     .set_moduli_sizes(&[62; 4])
+//!     .build_arc()?;
+//! let sk = SecretKey::random(&par, &mut rng);
+//! let rk = RelinearizationKey::new(&sk, &mut rng)?;
+//!
+//! // The client encrypts its data with the symmetric cipher.
+//! let cipher = Cipher::new(&par, 4, 3)?;
+//! let key = cipher.random_key(&mut rng);
+//! let encrypted_key = EncryptedKey::new(&cipher, &key, &sk, &mut rng)?;
+//! let message = vec![1, 2, 3, 4];
+//! let ciphertext = cipher.encrypt(&key, 42, &message)?;
+//!
+//! // The server decrypts it homomorphically.
+//! let cts = cipher.transcipher(&encrypted_key, &[42], &[ciphertext], &rk)?;
+//! for (ct, mi) in cts.iter().zip(message.iter()) {
+//!     let values = Vec::<u64>::try_decode(&sk.try_decrypt(ct)?, Encoding::simd())?;
+//!     assert_eq!(values[0], *mi);
+//! }
+//! # Ok(())
+//! # }
+//! # #[cfg(feature = "server")]
+//! # fn main() {}
+//! ```
+
+use crate::bfv::{
+    BfvParameters, Ciphertext, Encoding, Multiplicator, Plaintext, RelinearizationKey,
+};
+use crate::{Error, Result};
+use fhe_math::zq::Modulus;
+use fhe_traits::{FheEncoder, FheEncrypter};
+use fhe_util::is_prime;
+use rand::{CryptoRng, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+extern crate alloc;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Minimum number of elements of `Z_t` per block.
+pub const MIN_STATE_SIZE: usize = 4;
+
+/// Minimum number of rounds.
+pub const MIN_ROUNDS: usize = 3;
+
+/// A PASTA-style stream cipher over `Z_t`, where `t` is the plaintext modulus
+/// of BFV parameters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cipher {
+    par: Arc<BfvParameters>,
+    state_size: usize,
+    rounds: usize,
+}
+
+/// The BFV encryption of the key of a [`Cipher`]: the `i`-th ciphertext
+/// encrypts the `i`-th element of the key in every slot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncryptedKey {
+    cts: Vec<Ciphertext>,
+}
+
+impl EncryptedKey {
+    /// Encrypt the key `key` of the cipher `cipher` with `encrypter`, e.g. a
+    /// [`crate::bfv::SecretKey`] or a [`crate::bfv::PublicKey`].
+    ///
+    /// Returns an error if the parameters do not support the SIMD encoding.
+    pub fn new<E, R>(cipher: &Cipher, key: &[u64], encrypter: &E, rng: &mut R) -> Result<Self>
+    where
+        E: FheEncrypter<Plaintext, Ciphertext, Error = Error, Parameters = BfvParameters>,
+        R: RngCore + CryptoRng,
+    {
+        cipher.check_len(key)?;
+        let cts = key
+            .iter()
+            .map(|ki| {
+                let slots = Zeroizing::new(vec![*ki; cipher.par.degree()]);
+                let pt = Plaintext::try_encode(slots.as_slice(), Encoding::simd(), &cipher.par)?;
+                encrypter.try_encrypt(&pt, rng)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { cts })
+    }
+
+    /// Returns the ciphertexts encrypting the elements of the key.
+    pub fn ciphertexts(&self) -> &[Ciphertext] {
+        &self.cts
+    }
+}
+
+impl Cipher {
+    /// Create a cipher over the plaintext modulus of `par`, with `state_size`
+    /// elements of `Z_t` per block and `rounds` rounds.
+    ///
+    /// Returns an error if the plaintext modulus is not prime, or if the state
+    /// size or the number of rounds are below [`MIN_STATE_SIZE`] and
+    /// [`MIN_ROUNDS`].
+    pub fn new(par: &Arc<BfvParameters>, state_size: usize, rounds: usize) -> Result<Self> {
+        if state_size < MIN_STATE_SIZE {
+            return Err(Error::TooFewValues(state_size, MIN_STATE_SIZE));
+        }
+        if rounds < MIN_ROUNDS {
+            return Err(Error::TooFewValues(rounds, MIN_ROUNDS));
+        }
+        if !is_prime(par.plaintext()) {
+            return Err(Error::DefaultError(
+                "The plaintext modulus must be prime".to_string(),
+            ));
+        }
+        Ok(Self {
+            par: par.clone(),
+            state_size,
+            rounds,
+        })
+    }
+
+    /// Returns the number of elements of `Z_t` per block.
+    pub fn state_size(&self) -> usize {
+        self.state_size
+    }
+
+    /// Returns the number of rounds, which is the multiplicative depth of the
+    /// homomorphic decryption.
+    pub fn rounds(&self) -> usize {
+        self.rounds
+    }
+
+    /// Generate a random key.
+    pub fn random_key<R: RngCore + CryptoRng>(&self, rng: &mut R) -> Vec<u64> {
+        self.par.plaintext.random_vec(self.state_size, rng)
+    }
+
+    /// Compute the keystream block for the nonce `nonce`.
+    pub fn keystream(&self, key: &[u64], nonce: u64) -> Result<Zeroizing<Vec<u64>>> {
+        self.check_len(key)?;
+        let t = &self.par.plaintext;
+        let mut state = Zeroizing::new(t.reduce_vec_new(key));
+        for round in 0..=self.rounds {
+            let (matrix, constants) = self.round_constants(nonce, round);
+            state = Zeroizing::new(
+                matrix
+                    .chunks(self.state_size)
+                    .zip(constants.iter())
+                    .map(|(row, c)| {
+                        row.iter()
+                            .zip(state.iter())
+                            .fold(*c, |acc, (m, s)| t.add(acc, t.mul(*m, *s)))
+                    })
+                    .collect(),
+            );
+            if round < self.rounds {
+                for i in (1..self.state_size).rev() {
+                    state[i] = t.add(state[i], t.mul(state[i - 1], state[i - 1]));
+                }
+            }
+        }
+        Ok(state)
+    }
+
+    /// Encrypt the block `message` of `state_size` elements of `Z_t` with the
+    /// nonce `nonce`, which must not be reused with the same key.
+    pub fn encrypt(&self, key: &[u64], nonce: u64, message: &[u64]) -> Result<Vec<u64>> {
+        self.check_len(message)?;
+        let t = &self.par.plaintext;
+        let mut c = t.reduce_vec_new(message);
+        t.add_vec(&mut c, &self.keystream(key, nonce)?);
+        Ok(c)
+    }
+
+    /// Decrypt the block `ciphertext` encrypted with the nonce `nonce`.
+    pub fn decrypt(&self, key: &[u64], nonce: u64, ciphertext: &[u64]) -> Result<Vec<u64>> {
+        self.check_len(ciphertext)?;
+        let t = &self.par.plaintext;
+        let mut m = t.reduce_vec_new(ciphertext);
+        t.sub_vec(&mut m, &self.keystream(key, nonce)?);
+        Ok(m)
+    }
+
+    /// Homomorphically decrypt the blocks `ciphertexts`, encrypted with the
+    /// nonces `nonces`, using the encrypted key `key`.
+    ///
+    /// Returns `state_size` BFV ciphertexts such that the `b`-th slot of the
+    /// `j`-th ciphertext encrypts the `j`-th element of the `b`-th block. At
+    /// most `degree` blocks can be decrypted at once, and the encrypted key
+    /// must be at the level of the relinearization key.
+    pub fn transcipher(
+        &self,
+        key: &EncryptedKey,
+        nonces: &[u64],
+        ciphertexts: &[Vec<u64>],
+        rk: &RelinearizationKey,
+    ) -> Result<Vec<Ciphertext>> {
+        if key.cts.len() != self.state_size || key.cts.iter().any(|ct| ct.par != self.par) {
+            return Err(Error::DefaultError(
+                "The encrypted key does not match the cipher".to_string(),
+            ));
+        }
+        if nonces.len() != ciphertexts.len() {
+            return Err(Error::DefaultError(
+                "The number of nonces and ciphertexts differ".to_string(),
+            ));
+        }
+        if nonces.len() > self.par.degree() {
+            return Err(Error::TooManyValues(nonces.len(), self.par.degree()));
+        }
+        ciphertexts.iter().try_for_each(|c| self.check_len(c))?;
+
+        let level = key.cts[0].level;
+        let multiplicator = Multiplicator::default(rk)?;
+        let t = &self.par.plaintext;
+        let encode =
+            |slots: &[u64]| Plaintext::try_encode(slots, Encoding::simd_at_level(level), &self.par);
+
+        let mut state = key.cts.clone();
+        for round in 0..=self.rounds {
+            // The matrices and round constants of the blocks, in the slots.
+            let mut matrices = vec![vec![0u64; self.par.degree()]; self.state_size.pow(2)];
+            let mut constants = vec![vec![0u64; self.par.degree()]; self.state_size];
+            for (b, nonce) in nonces.iter().enumerate() {
+                let (matrix, constant) = self.round_constants(*nonce, round);
+                matrix
+                    .iter()
+                    .zip(matrices.iter_mut())
+                    .for_each(|(m, slots)| slots[b] = *m);
+                constant
+                    .iter()
+                    .zip(constants.iter_mut())
+                    .for_each(|(c, slots)| slots[b] = *c);
+            }
+
+            state = matrices
+                .chunks(self.state_size)
+                .zip(constants.iter())
+                .map(|(row, c)| {
+                    let mut acc = Ciphertext::zero(&self.par);
+                    for (m, s) in row.iter().zip(state.iter()) {
+                        acc += &(s * &encode(m)?);
+                    }
+                    acc += &encode(c)?;
+                    Ok(acc)
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            if round < self.rounds {
+                let squares = state[..self.state_size - 1]
+                    .iter()
+                    .map(|s| multiplicator.multiply(s, s))
+                    .collect::<Result<Vec<_>>>()?;
+                state
+                    .iter_mut()
+                    .skip(1)
+                    .zip(squares.iter())
+                    .for_each(|(s, sq)| *s += sq);
+            }
+        }
+
+        // The data is the symmetric ciphertext minus the keystream.
+        (0..self.state_size)
+            .map(|j| {
+                let mut slots = vec![0u64; self.par.degree()];
+                ciphertexts
+                    .iter()
+                    .enumerate()
+                    .for_each(|(b, c)| slots[b] = t.reduce(c[j]));
+                Ok(&(-&state[j]) + &encode(&slots)?)
+            })
+            .collect()
+    }
+
+    /// The matrix, in row-major order, and the constants of the affine layer
+    /// of round `round` for the nonce `nonce`, derived from the nonce with
+    /// ChaCha8 as an extendable output function. Singular matrices are
+    /// rejected and sampled again.
+    fn round_constants(&self, nonce: u64, round: usize) -> (Vec<u64>, Vec<u64>) {
+        let mut hasher = Sha256::new();
+        hasher.update(b"fhe.rs transcipher");
+        hasher.update(nonce.to_le_bytes());
+        hasher.update((round as u64).to_le_bytes());
+        let mut rng = ChaCha8Rng::from_seed(hasher.finalize().into());
+        let t = &self.par.plaintext;
+        let matrix = loop {
+            let matrix = t.random_vec(self.state_size * self.state_size, &mut rng);
+            if is_invertible(t, matrix.clone(), self.state_size) {
+                break matrix;
+            }
+        };
+        let constants = t.random_vec(self.state_size, &mut rng);
+        (matrix, constants)
+    }
+
+    fn check_len(&self, v: &[u64]) -> Result<()> {
+        match v.len() {
+            n if n < self.state_size => Err(Error::TooFewValues(n, self.state_size)),
+            n if n > self.state_size => Err(Error::TooManyValues(n, self.state_size)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Returns whether the `n x n` matrix `m`, in row-major order, is invertible
+/// modulo the prime `t`, by Gaussian elimination.
+fn is_invertible(t: &Modulus, mut m: Vec<u64>, n: usize) -> bool {
+    for col in 0..n {
+        let Some(pivot) = (col..n).find(|row| m[row * n + col] != 0) else {
+            return false;
+        };
+        for j in 0..n {
+            m.swap(col * n + j, pivot * n + j);
+        }
+        let inv = t.inv(m[col * n + col]).unwrap();
+        for row in col + 1..n {
+            let factor = t.mul(m[row * n + col], inv);
+            for j in col..n {
+                m[row * n + j] = t.sub(m[row * n + j], t.mul(factor, m[col * n + j]));
+            }
+        }
+    }
+    true
+}
+
+#[cfg(all(test, not(feature = "server")))]
+mod tests {
+    use super::{is_invertible, Cipher, EncryptedKey, MIN_ROUNDS, MIN_STATE_SIZE};
+    use crate::bfv::{
+        BfvParameters, BfvParametersBuilder, Encoding, PublicKey, RelinearizationKey, SecretKey,
+    };
+    use crate::Error;
+    use fhe_traits::{FheDecoder, FheDecrypter};
+    use rand::{thread_rng, Rng};
+    extern crate alloc;
+    use alloc::sync::Arc;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    fn params() -> Arc<BfvParameters> {
+        BfvParametersBuilder::new()
+            .set_degree(16)
+            .set_plaintext_modulus(65537)
+            .set_moduli_sizes(&[62, 62, 62, 62])
+            .build_arc()
+            .unwrap()
+    }
+
+    #[test]
+    fn symmetric() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let cipher = Cipher::new(&params(), 8, 3)?;
+        let key = cipher.random_key(&mut rng);
+        let message = (0..8)
+            .map(|_| rng.gen_range(0..65537))
+            .collect::<Vec<u64>>();
+
+        let c = cipher.encrypt(&key, 1, &message)?;
+        assert_ne!(c, message);
+        assert_eq!(cipher.decrypt(&key, 1, &c)?, message);
+        assert_ne!(cipher.decrypt(&key, 2, &c)?, message);
+        assert_ne!(cipher.keystream(&key, 1)?, cipher.keystream(&key, 2)?);
+
+        assert!(cipher.encrypt(&key, 1, &message[..7]).is_err());
+        assert!(cipher.keystream(&key[..7], 1).is_err());
+        assert_eq!(
+            Cipher::new(&params(), 1, 3).unwrap_err(),
+            Error::TooFewValues(1, MIN_STATE_SIZE)
+        );
+        assert_eq!(
+            Cipher::new(&params(), 8, 1).unwrap_err(),
+            Error::TooFewValues(1, MIN_ROUNDS)
+        );
+        let composite = BfvParametersBuilder::new()
+            .set_degree(16)
+            .set_plaintext_modulus(1024)
+            .set_moduli_sizes(&[62])
+            .build_arc()?;
+        assert!(Cipher::new(&composite, 8, 3).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn invertible() {
+        let t = params().plaintext.clone();
+        assert!(is_invertible(&t, vec![1, 2, 3, 4], 2));
+        assert!(is_invertible(&t, vec![0, 1, 1, 0], 2));
+        assert!(!is_invertible(&t, vec![1, 2, 2, 4], 2));
+        assert!(!is_invertible(&t, vec![0, 0, 1, 1], 2));
+    }
+
+    #[test]
+    fn transcipher() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let par = params();
+        let sk = SecretKey::random(&par, &mut rng);
+        let pk = PublicKey::new(&sk, &mut rng);
+        let rk = RelinearizationKey::new(&sk, &mut rng)?;
+        let cipher = Cipher::new(&par, 4, 3)?;
+        let key = cipher.random_key(&mut rng);
+        let encrypted_key = EncryptedKey::new(&cipher, &key, &pk, &mut rng)?;
+        assert_eq!(encrypted_key.ciphertexts().len(), 4);
+
+        let nonces = (0..10).map(|_| rng.gen::<u64>()).collect::<Vec<_>>();
+        let messages = nonces
+            .iter()
+            .map(|_| par.plaintext.random_vec(4, &mut rng))
+            .collect::<Vec<_>>();
+        let ciphertexts = nonces
+            .iter()
+            .zip(messages.iter())
+            .map(|(n, m)| cipher.encrypt(&key, *n, m))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let cts = cipher.transcipher(&encrypted_key, &nonces, &ciphertexts, &rk)?;
+        assert_eq!(cts.len(), 4);
+        for (j, ct) in cts.iter().enumerate() {
+            let values = Vec::<u64>::try_decode(&sk.try_decrypt(ct)?, Encoding::simd())?;
+            for (b, m) in messages.iter().enumerate() {
+                assert_eq!(values[b], m[j]);
+            }
+        }
+
+        assert!(cipher
+            .transcipher(&encrypted_key, &nonces[..9], &ciphertexts, &rk)
+            .is_err());
+        let other = Cipher::new(&par, 5, 3)?;
+        assert!(other
+            .transcipher(&encrypted_key, &nonces, &ciphertexts, &rk)
+            .is_err());
+        Ok(())
+    }
+}
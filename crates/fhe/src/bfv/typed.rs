@@ -0,0 +1,231 @@
+//! Checked parameter typing for ciphertexts and plaintexts.
+//!
+//! A [`ParamsHandle`] identifies a parameter set by the identity of its
+//! `Arc<BfvParameters>`, which is cheaper than comparing the parameters by
+//! value and catches objects created from distinct (even if equal) parameter
+//! instances. On top of it, [`TypedParameters<P>`] tags ciphertexts and
+//! plaintexts with a marker type `P`, so that mixing objects created from
+//! different parameter sets is a compile-time error instead of an assertion
+//! failure inside the operators.
+
+use crate::bfv::{BfvParameters, Ciphertext, Plaintext};
+use crate::{Error, Result};
+use core::marker::PhantomData;
+use core::ops::{Add, Deref, Mul, Sub};
+extern crate alloc;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+
+/// A token identifying a parameter set by pointer identity.
+#[derive(Debug, Clone)]
+pub struct ParamsHandle {
+    par: Arc<BfvParameters>,
+}
+
+impl ParamsHandle {
+    /// Creates a handle for the parameters `par`.
+    pub fn new(par: &Arc<BfvParameters>) -> Self {
+        Self { par: par.clone() }
+    }
+
+    /// Returns the parameters identified by this handle.
+    pub fn parameters(&self) -> &Arc<BfvParameters> {
+        &self.par
+    }
+
+    /// Returns an error if `other` does not identify the same parameters.
+    pub fn check(&self, other: &ParamsHandle) -> Result<()> {
+        if self == other {
+            Ok(())
+        } else {
+            Err(Error::DefaultError("Mismatched parameters".to_string()))
+        }
+    }
+}
+
+impl PartialEq for ParamsHandle {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.par, &other.par)
+    }
+}
+
+impl Eq for ParamsHandle {}
+
+impl Ciphertext {
+    /// Returns the handle of the parameters of this ciphertext.
+    pub fn params_handle(&self) -> ParamsHandle {
+        ParamsHandle::new(&self.par)
+    }
+}
+
+impl Plaintext {
+    /// Returns the handle of the parameters of this plaintext.
+    pub fn params_handle(&self) -> ParamsHandle {
+        ParamsHandle::new(&self.par)
+    }
+}
+
+/// Parameters tagged with the marker type `P`.
+///
+/// The marker type is chosen by the user, typically as an empty struct
+/// declared once per parameter set. Only one [`TypedParameters`] should be
+/// created per marker type; objects tagged by it can then only be combined
+/// with objects tagged with the same marker.
+#[derive(Debug)]
+pub struct TypedParameters<P> {
+    handle: ParamsHandle,
+    marker: PhantomData<fn() -> P>,
+}
+
+impl<P> Clone for TypedParameters<P> {
+    fn clone(&self) -> Self {
+        Self {
+            handle: self.handle.clone(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<P> TypedParameters<P> {
+    /// Tags the parameters `par` with the marker type `P`.
+    pub fn new(par: &Arc<BfvParameters>) -> Self {
+        Self {
+            handle: ParamsHandle::new(par),
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the handle of the underlying parameters.
+    pub fn handle(&self) -> &ParamsHandle {
+        &self.handle
+    }
+
+    /// Tags a ciphertext. Returns an error if the ciphertext was not created
+    /// with the underlying parameters.
+    pub fn tag_ciphertext(&self, ct: Ciphertext) -> Result<Typed<P, Ciphertext>> {
+        self.handle.check(&ct.params_handle())?;
+        Ok(Typed::new(ct))
+    }
+
+    /// Tags a plaintext. Returns an error if the plaintext was not created
+    /// with the underlying parameters.
+    pub fn tag_plaintext(&self, pt: Plaintext) -> Result<Typed<P, Plaintext>> {
+        self.handle.check(&pt.params_handle())?;
+        Ok(Typed::new(pt))
+    }
+}
+
+/// A value tagged with the parameters marker type `P`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Typed<P, T> {
+    inner: T,
+    marker: PhantomData<fn() -> P>,
+}
+
+impl<P, T: Clone> Clone for Typed<P, T> {
+    fn clone(&self) -> Self {
+        Self::new(self.inner.clone())
+    }
+}
+
+impl<P, T> Typed<P, T> {
+    fn new(inner: T) -> Self {
+        Self {
+            inner,
+            marker: PhantomData,
+        }
+    }
+
+    /// Removes the tag, and returns the underlying value.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<P, T> Deref for Typed<P, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+macro_rules! impl_typed_op {
+    ($trait:ident, $method:ident, $rhs:ty) => {
+        impl<P> $trait<&Typed<P, $rhs>> for &Typed<P, Ciphertext> {
+            type Output = Typed<P, Ciphertext>;
+
+            fn $method(self, rhs: &Typed<P, $rhs>) -> Typed<P, Ciphertext> {
+                Typed::new((&self.inner).$method(&rhs.inner))
+            }
+        }
+    };
+}
+
+impl_typed_op!(Add, add, Ciphertext);
+impl_typed_op!(Add, add, Plaintext);
+impl_typed_op!(Sub, sub, Ciphertext);
+impl_typed_op!(Sub, sub, Plaintext);
+impl_typed_op!(Mul, mul, Ciphertext);
+impl_typed_op!(Mul, mul, Plaintext);
+
+#[cfg(all(test, not(feature = "server")))]
+mod tests {
+    use super::{ParamsHandle, TypedParameters};
+    use crate::bfv::{BfvParameters, Encoding, Plaintext, SecretKey};
+    use crate::Error;
+    use fhe_traits::{FheDecoder, FheDecrypter, FheEncoder, FheEncrypter};
+    use rand::thread_rng;
+    extern crate alloc;
+    use alloc::vec::Vec;
+
+    #[derive(Debug)]
+    struct Params;
+
+    #[test]
+    fn handle() {
+        let params = BfvParameters::default_arc(1, 16);
+        let other = BfvParameters::default_arc(1, 16);
+        assert_eq!(params, other);
+        assert_eq!(
+            ParamsHandle::new(&params),
+            ParamsHandle::new(&params.clone())
+        );
+        assert_ne!(ParamsHandle::new(&params), ParamsHandle::new(&other));
+        assert!(ParamsHandle::new(&params)
+            .check(&ParamsHandle::new(&other))
+            .is_err());
+    }
+
+    #[test]
+    fn typed_ops() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(2, 16);
+        let other = BfvParameters::default_arc(2, 16);
+        let typed = TypedParameters::<Params>::new(&params);
+        let sk = SecretKey::random(&params, &mut rng);
+
+        let a = params.plaintext.random_vec(params.degree(), &mut rng);
+        let b = params.plaintext.random_vec(params.degree(), &mut rng);
+        let pt_a = Plaintext::try_encode(&a, Encoding::simd(), &params)?;
+        let pt_b = Plaintext::try_encode(&b, Encoding::simd(), &params)?;
+        let ct_a = typed.tag_ciphertext(sk.try_encrypt(&pt_a, &mut rng)?)?;
+        let ct_b = typed.tag_ciphertext(sk.try_encrypt(&pt_b, &mut rng)?)?;
+        let pt_b = typed.tag_plaintext(pt_b)?;
+
+        let mut expected = a.clone();
+        params.plaintext.add_vec(&mut expected, &b);
+        let pt = sk.try_decrypt(&(&ct_a + &ct_b))?;
+        assert_eq!(Vec::<u64>::try_decode(&pt, Encoding::simd())?, expected);
+
+        let mut expected = a.clone();
+        params.plaintext.mul_vec(&mut expected, &b);
+        let pt = sk.try_decrypt(&(&ct_a * &pt_b).into_inner())?;
+        assert_eq!(Vec::<u64>::try_decode(&pt, Encoding::simd())?, expected);
+
+        let pt_other = Plaintext::try_encode(&a, Encoding::simd(), &other)?;
+        assert!(typed.tag_plaintext(pt_other).is_err());
+
+        Ok(())
+    }
+}
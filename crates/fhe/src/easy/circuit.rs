@@ -0,0 +1,143 @@
+//! Arithmetic circuits evaluated by [`super::evaluate`].
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+/// A wire of a [`Circuit`], i.e. an input or the output of a gate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Wire(pub(crate) usize);
+
+/// A gate of a [`Circuit`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Gate {
+    Input(usize),
+    Add(Wire, Wire),
+    Sub(Wire, Wire),
+    Mul(Wire, Wire),
+    Neg(Wire),
+}
+
+/// An arithmetic circuit over vectors of values modulo the plaintext modulus,
+/// where additions, subtractions, negations and multiplications are computed
+/// slot-wise.
+///
+/// ```
+/// use fhe::easy::Circuit;
+///
+/// // Compute (x + y) * x.
+/// let mut circuit = Circuit::new(2);
+/// let (x, y) = (circuit.input(0), circuit.input(1));
+/// let s = circuit.add(x, y);
+/// let p = circuit.mul(s, x);
+/// circuit.output(p);
+/// assert_eq!(circuit.depth(), 1);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Circuit {
+    pub(crate) num_inputs: usize,
+    pub(crate) gates: Vec<Gate>,
+    pub(crate) depths: Vec<usize>,
+    pub(crate) outputs: Vec<Wire>,
+}
+
+impl Circuit {
+    /// Create a circuit with `num_inputs` inputs.
+    pub fn new(num_inputs: usize) -> Self {
+        Self {
+            num_inputs,
+            gates: (0..num_inputs).map(Gate::Input).collect(),
+            depths: alloc::vec![0; num_inputs],
+            outputs: Vec::new(),
+        }
+    }
+
+    /// Returns the number of inputs of the circuit.
+    pub fn num_inputs(&self) -> usize {
+        self.num_inputs
+    }
+
+    /// Returns the wire of the i-th input.
+    ///
+    /// Panics if `i` is not smaller than the number of inputs.
+    pub fn input(&self, i: usize) -> Wire {
+        assert!(i < self.num_inputs, "Invalid input index");
+        Wire(i)
+    }
+
+    /// Returns the wire of the sum of `a` and `b`.
+    pub fn add(&mut self, a: Wire, b: Wire) -> Wire {
+        self.push(Gate::Add(a, b), self.depth_of(a).max(self.depth_of(b)))
+    }
+
+    /// Returns the wire of the difference of `a` and `b`.
+    pub fn sub(&mut self, a: Wire, b: Wire) -> Wire {
+        self.push(Gate::Sub(a, b), self.depth_of(a).max(self.depth_of(b)))
+    }
+
+    /// Returns the wire of the product of `a` and `b`.
+    pub fn mul(&mut self, a: Wire, b: Wire) -> Wire {
+        self.push(Gate::Mul(a, b), self.depth_of(a).max(self.depth_of(b)) + 1)
+    }
+
+    /// Returns the wire of the negation of `a`.
+    pub fn neg(&mut self, a: Wire) -> Wire {
+        self.push(Gate::Neg(a), self.depth_of(a))
+    }
+
+    /// Mark the wire `a` as an output of the circuit. The outputs are returned
+    /// in the order in which they are marked.
+    pub fn output(&mut self, a: Wire) {
+        assert!(a.0 < self.gates.len(), "Invalid wire");
+        self.outputs.push(a)
+    }
+
+    /// Returns the multiplicative depth of the circuit, i.e. the largest
+    /// number of multiplications on a path from an input to an output.
+    pub fn depth(&self) -> usize {
+        self.outputs
+            .iter()
+            .map(|o| self.depths[o.0])
+            .max()
+            .unwrap_or_default()
+    }
+
+    fn depth_of(&self, a: Wire) -> usize {
+        *self.depths.get(a.0).expect("Invalid wire")
+    }
+
+    fn push(&mut self, gate: Gate, depth: usize) -> Wire {
+        self.gates.push(gate);
+        self.depths.push(depth);
+        Wire(self.gates.len() - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Circuit;
+
+    #[test]
+    fn depth() {
+        let mut circuit = Circuit::new(3);
+        assert_eq!(circuit.num_inputs(), 3);
+        assert_eq!(circuit.depth(), 0);
+
+        let (x, y, z) = (circuit.input(0), circuit.input(1), circuit.input(2));
+        let xy = circuit.mul(x, y);
+        let xyz = circuit.mul(xy, z);
+        let s = circuit.add(xy, z);
+        let d = circuit.sub(s, x);
+        let n = circuit.neg(d);
+
+        circuit.output(n);
+        assert_eq!(circuit.depth(), 1);
+        circuit.output(xyz);
+        assert_eq!(circuit.depth(), 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_input() {
+        Circuit::new(2).input(2);
+    }
+}
@@ -0,0 +1,372 @@
+//! A high-level interface to the BFV encryption scheme with safe defaults.
+//!
+//! This module hides the parameters, encodings, levels and key types of the
+//! [`crate::bfv`] module behind a few calls: [`keygen`] selects parameters for
+//! a security level and a multiplicative depth and generates the keys,
+//! [`encrypt`] encrypts a vector of values, [`evaluate`] evaluates a
+//! [`Circuit`] on encrypted vectors, and [`decrypt`] decrypts the result.
+//!
+//! ```
+//! use fhe::easy::{decrypt, encrypt, evaluate, keygen, Circuit, Security};
+//! use rand::thread_rng;
+//!
+//! let mut rng = thread_rng();
+//! let (client_key, server_key) = keygen(Security::Classical128, 1, &mut rng)?;
+//!
+//! // Compute x * y + x slot-wise.
+//! let mut circuit = Circuit::new(2);
+//! let (x, y) = (circuit.input(0), circuit.input(1));
+//! let p = circuit.mul(x, y);
+//! let s = circuit.add(p, x);
+//! circuit.output(s);
+//!
+//! let x = encrypt(&client_key, &[1, 2, 3], &mut rng)?;
+//! let y = encrypt(&client_key, &[4, 5, 6], &mut rng)?;
+//! let outputs = evaluate(&server_key, &circuit, &[x, y])?;
+//! assert_eq!(decrypt(&client_key, &outputs[0])?, &[5, 12, 21]);
+//! # Ok::<(), fhe::Error>(())
+//! ```
+
+mod circuit;
+
+pub use circuit::{Circuit, Wire};
+
+use crate::bfv::{
+    BfvParameters, BfvParametersBuilder, Ciphertext, Encoding, Multiplicator, Plaintext,
+    RelinearizationKey, SecretKey,
+};
+use crate::{Error, ParametersError, Result};
+use circuit::Gate;
+use fhe_math::zq::primes::generate_prime;
+use fhe_traits::{FheDecoder, FheDecrypter, FheEncoder, FheEncrypter};
+use rand::{CryptoRng, RngCore};
+extern crate alloc;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Number of bits of the plaintext modulus.
+const PLAINTEXT_NBITS: usize = 20;
+
+/// Number of bits of ciphertext modulus required to decrypt a fresh
+/// ciphertext.
+const FRESH_NBITS: usize = 60;
+
+/// Number of bits of ciphertext modulus consumed by a multiplication, in
+/// addition to the size of the plaintext modulus and of the degree.
+const MUL_MARGIN_NBITS: usize = 10;
+
+/// Security level, according to the <https://homomorphicencryption.org>
+/// standard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Security {
+    /// About 128 bits of classical security.
+    Classical128,
+    /// About 192 bits of classical security.
+    Classical192,
+    /// About 256 bits of classical security.
+    Classical256,
+}
+
+impl Security {
+    /// Maximum size of the ciphertext modulus for each degree.
+    fn max_modulus_sizes(&self) -> [(usize, usize); 5] {
+        match self {
+            Security::Classical128 => [
+                (2048, 54),
+                (4096, 109),
+                (8192, 218),
+                (16384, 438),
+                (32768, 881),
+            ],
+            Security::Classical192 => [
+                (2048, 37),
+                (4096, 75),
+                (8192, 152),
+                (16384, 305),
+                (32768, 611),
+            ],
+            Security::Classical256 => [
+                (2048, 29),
+                (4096, 58),
+                (8192, 118),
+                (16384, 237),
+                (32768, 476),
+            ],
+        }
+    }
+
+    /// Select the smallest parameters at this security level supporting
+    /// circuits of multiplicative depth `depth`.
+    fn parameters(&self, depth: usize) -> Result<Arc<BfvParameters>> {
+        for (degree, max_size) in self.max_modulus_sizes() {
+            let size = FRESH_NBITS
+                + depth * (PLAINTEXT_NBITS + degree.ilog2() as usize + MUL_MARGIN_NBITS);
+            // Key switching requires at least two moduli.
+            let num_moduli = size.div_ceil(62).max(2);
+            let moduli_size = size.div_ceil(num_moduli);
+            if num_moduli * moduli_size > max_size {
+                continue;
+            }
+            let plaintext_modulus = generate_prime(
+                PLAINTEXT_NBITS,
+                2 * degree as u64,
+                (1 << PLAINTEXT_NBITS) - 1,
+            )
+            .ok_or(Error::ParametersError(ParametersError::InvalidPlaintext(
+                "No suitable plaintext modulus".to_string(),
+            )))?;
+            return BfvParametersBuilder::new()
+                .set_degree(degree)
+                .set_plaintext_modulus(plaintext_modulus)
+                .set_moduli_sizes(&vec![moduli_size; num_moduli])
+                .build_arc();
+        }
+        Err(Error::DefaultError(
+            "The depth is too large for this security level".to_string(),
+        ))
+    }
+}
+
+/// The key of the client, used to encrypt and decrypt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientKey {
+    sk: SecretKey,
+    depth: usize,
+}
+
+impl ClientKey {
+    /// Returns the plaintext modulus; the encrypted values and the results of
+    /// the evaluations are integers modulo the plaintext modulus.
+    pub fn plaintext_modulus(&self) -> u64 {
+        *self.sk.par.plaintext
+    }
+
+    /// Returns the maximum number of values in an encrypted vector.
+    pub fn slots(&self) -> usize {
+        self.sk.par.degree()
+    }
+
+    /// Returns the maximum multiplicative depth of the circuits.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+}
+
+/// The key of the server, used to evaluate circuits on encrypted vectors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerKey {
+    par: Arc<BfvParameters>,
+    multiplicator: Multiplicator,
+    depth: usize,
+}
+
+impl ServerKey {
+    /// Returns the maximum multiplicative depth of the circuits.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+}
+
+/// An encrypted vector of values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncryptedVector {
+    ct: Ciphertext,
+    len: usize,
+}
+
+impl EncryptedVector {
+    /// Returns the number of values in the vector.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the vector is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// Generate the keys for circuits of multiplicative depth up to `depth` at
+/// the security level `security`. The [`ClientKey`] must be kept secret, while
+/// the [`ServerKey`] can be sent to the party evaluating the circuits.
+pub fn keygen<R: RngCore + CryptoRng>(
+    security: Security,
+    depth: usize,
+    rng: &mut R,
+) -> Result<(ClientKey, ServerKey)> {
+    let par = security.parameters(depth)?;
+    let sk = SecretKey::random(&par, rng);
+    let rk = RelinearizationKey::new(&sk, rng)?;
+    let multiplicator = Multiplicator::default(&rk)?;
+    Ok((
+        ClientKey { sk, depth },
+        ServerKey {
+            par,
+            multiplicator,
+            depth,
+        },
+    ))
+}
+
+/// Encrypt a vector of values modulo the plaintext modulus.
+///
+/// Returns an error if there are more values than slots, or if a value is
+/// larger than or equal to the plaintext modulus.
+pub fn encrypt<R: RngCore + CryptoRng>(
+    key: &ClientKey,
+    values: &[u64],
+    rng: &mut R,
+) -> Result<EncryptedVector> {
+    let pt = Plaintext::try_encode(values, Encoding::simd(), &key.sk.par)?;
+    Ok(EncryptedVector {
+        ct: key.sk.try_encrypt(&pt, rng)?,
+        len: values.len(),
+    })
+}
+
+/// Decrypt an encrypted vector.
+pub fn decrypt(key: &ClientKey, value: &EncryptedVector) -> Result<Vec<u64>> {
+    let pt = key.sk.try_decrypt(&value.ct)?;
+    let mut values = Vec::<u64>::try_decode(&pt, Encoding::simd())?;
+    values.truncate(value.len);
+    Ok(values)
+}
+
+/// Evaluate the circuit `circuit` on the encrypted vectors `inputs`, and
+/// returns the encrypted outputs. The number of values of each output is the
+/// largest number of values of the inputs.
+///
+/// Returns an error if the number of inputs is incorrect, if the inputs were
+/// not encrypted under the client key corresponding to the server key, or if
+/// the depth of the circuit is larger than the depth of the keys.
+pub fn evaluate(
+    key: &ServerKey,
+    circuit: &Circuit,
+    inputs: &[EncryptedVector],
+) -> Result<Vec<EncryptedVector>> {
+    if circuit.depth() > key.depth {
+        return Err(Error::DefaultError(
+            "The depth of the circuit is too large".to_string(),
+        ));
+    }
+    if inputs.len() != circuit.num_inputs() {
+        return Err(Error::DefaultError(
+            "Incorrect number of inputs".to_string(),
+        ));
+    }
+    if inputs.iter().any(|input| input.ct.par != key.par) {
+        return Err(Error::DefaultError(
+            "Incompatible BFV parameters".to_string(),
+        ));
+    }
+
+    let len = inputs
+        .iter()
+        .map(|input| input.len)
+        .max()
+        .unwrap_or_default();
+    let mut wires: Vec<Ciphertext> = Vec::with_capacity(circuit.gates.len());
+    for gate in &circuit.gates {
+        let ct = match gate {
+            Gate::Input(i) => inputs[*i].ct.clone(),
+            Gate::Add(a, b) => &wires[a.0] + &wires[b.0],
+            Gate::Sub(a, b) => &wires[a.0] - &wires[b.0],
+            Gate::Mul(a, b) => key.multiplicator.multiply(&wires[a.0], &wires[b.0])?,
+            Gate::Neg(a) => -&wires[a.0],
+        };
+        wires.push(ct);
+    }
+
+    Ok(circuit
+        .outputs
+        .iter()
+        .map(|o| EncryptedVector {
+            ct: wires[o.0].clone(),
+            len,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decrypt, encrypt, evaluate, keygen, Circuit, Security};
+    use crate::Error;
+    use rand::{thread_rng, Rng};
+    extern crate alloc;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn parameters() -> Result<(), Error> {
+        for security in [
+            Security::Classical128,
+            Security::Classical192,
+            Security::Classical256,
+        ] {
+            let mut previous_degree = 0;
+            for depth in 0..6 {
+                let par = security.parameters(depth)?;
+                assert!(par.degree() >= previous_degree);
+                assert_eq!(*par.plaintext % (2 * par.degree() as u64), 1);
+                previous_degree = par.degree();
+            }
+        }
+        assert!(Security::Classical128.parameters(100).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn evaluate_circuit() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let (client_key, server_key) = keygen(Security::Classical128, 2, &mut rng)?;
+        assert_eq!(client_key.depth(), 2);
+        assert_eq!(server_key.depth(), 2);
+        let t = client_key.plaintext_modulus();
+
+        // Compute x * y * z - (x + y) and -x.
+        let mut circuit = Circuit::new(3);
+        let (x, y, z) = (circuit.input(0), circuit.input(1), circuit.input(2));
+        let xy = circuit.mul(x, y);
+        let xyz = circuit.mul(xy, z);
+        let s = circuit.add(x, y);
+        let d = circuit.sub(xyz, s);
+        let n = circuit.neg(x);
+        circuit.output(d);
+        circuit.output(n);
+
+        let values = (0..3)
+            .map(|_| {
+                (0..client_key.slots())
+                    .map(|_| rng.gen_range(0..t))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let inputs = values
+            .iter()
+            .map(|v| encrypt(&client_key, v, &mut rng))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let outputs = evaluate(&server_key, &circuit, &inputs)?;
+        assert_eq!(outputs.len(), 2);
+
+        let expected_d = (0..client_key.slots())
+            .map(|i| {
+                let (x, y, z) = (values[0][i], values[1][i], values[2][i]);
+                let xyz = ((x * y) % t) * z % t;
+                (xyz + 2 * t - x - y) % t
+            })
+            .collect::<Vec<_>>();
+        let expected_n = values[0].iter().map(|x| (t - x) % t).collect::<Vec<_>>();
+        assert_eq!(decrypt(&client_key, &outputs[0])?, expected_d);
+        assert_eq!(decrypt(&client_key, &outputs[1])?, expected_n);
+
+        // The circuit is too deep for keys of depth 1.
+        let (_, server_key_1) = keygen(Security::Classical128, 1, &mut rng)?;
+        assert!(evaluate(&server_key_1, &circuit, &inputs).is_err());
+
+        // Incorrect number of inputs, and values out of range.
+        assert!(evaluate(&server_key, &circuit, &inputs[..2]).is_err());
+        assert!(encrypt(&client_key, &[t], &mut rng).is_err());
+        Ok(())
+    }
+}
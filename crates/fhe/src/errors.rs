@@ -37,6 +37,19 @@ pub enum Error {
     #[error("Does not support {0} encoding")]
     EncodingNotSupported(String),
 
+    /// Indicates that the integrity tag of a plaintext is incorrect.
+    #[error("Integrity check failed")]
+    IntegrityCheckFailed,
+
+    /// Indicates that the noise of a ciphertext exceeded its noise budget, so
+    /// that its decryption is incorrect.
+    #[error("Noise budget exceeded")]
+    NoiseBudgetExceeded,
+
+    /// Indicates that no value has the given identifier.
+    #[error("Unknown identifier: {0}")]
+    UnknownIdentifier(usize),
+
     /// Indicates a parameter error.
     #[error("{0}")]
     ParametersError(ParametersError),
@@ -60,6 +73,10 @@ pub enum ParametersError {
     #[error("Invalid degree: {0} is not a power of 2 larger than 8")]
     InvalidDegree(usize),
 
+    /// Indicates that the index of a prime cyclotomic ring is invalid.
+    #[error("Invalid cyclotomic index: {0} is not an odd prime")]
+    InvalidCyclotomicIndex(usize),
+
     /// Indicates that the moduli sizes are invalid.
     #[error("Invalid modulus size: {0}, expected an integer between {1} and {2}")]
     InvalidModulusSize(usize, usize, usize),
@@ -72,6 +89,10 @@ pub enum ParametersError {
     #[error("{0}")]
     InvalidPlaintext(String),
 
+    /// Indicates that a ciphertext modulus is invalid.
+    #[error("Invalid ciphertext modulus {0}: {1}")]
+    InvalidModulus(u64, String),
+
     /// Indicates that too many parameters were specified.
     #[error("{0}")]
     TooManySpecified(String),
@@ -79,6 +100,11 @@ pub enum ParametersError {
     /// Indicates that too few parameters were specified.
     #[error("{0}")]
     TooFewSpecified(String),
+
+    /// Indicates that serialized parameters differ from the parameters
+    /// generated by this release from the same description.
+    #[error("Parameters differ from this release: {0}")]
+    MigrationMismatch(String),
 }
 
 #[cfg(test)]
@@ -114,6 +140,10 @@ mod tests {
             Error::EncodingNotSupported("test".to_string()).to_string(),
             "Does not support test encoding"
         );
+        assert_eq!(
+            Error::UnknownIdentifier(3).to_string(),
+            "Unknown identifier: 3"
+        );
         assert_eq!(
             Error::ParametersError(ParametersError::InvalidDegree(10)).to_string(),
             ParametersError::InvalidDegree(10).to_string()
@@ -126,6 +156,10 @@ mod tests {
             ParametersError::InvalidDegree(10).to_string(),
             "Invalid degree: 10 is not a power of 2 larger than 8"
         );
+        assert_eq!(
+            ParametersError::InvalidCyclotomicIndex(15).to_string(),
+            "Invalid cyclotomic index: 15 is not an odd prime"
+        );
         assert_eq!(
             ParametersError::InvalidModulusSize(1, 2, 3).to_string(),
             "Invalid modulus size: 1, expected an integer between 2 and 3"
@@ -138,6 +172,10 @@ mod tests {
             ParametersError::InvalidPlaintext("test".to_string()).to_string(),
             "test"
         );
+        assert_eq!(
+            ParametersError::InvalidModulus(17, "test".to_string()).to_string(),
+            "Invalid ciphertext modulus 17: test"
+        );
         assert_eq!(
             ParametersError::TooManySpecified("test".to_string()).to_string(),
             "test"
@@ -146,5 +184,9 @@ mod tests {
             ParametersError::TooFewSpecified("test".to_string()).to_string(),
             "test"
         );
+        assert_eq!(
+            ParametersError::MigrationMismatch("test".to_string()).to_string(),
+            "Parameters differ from this release: test"
+        );
     }
 }
@@ -2,16 +2,49 @@
 #![crate_name = "fhe"]
 #![crate_type = "lib"]
 #![warn(missing_docs, unused_imports)]
-#![doc = include_str!("../README.md")]
+// The example of the README needs the secret key, which is not available with
+// the `client` and `server` features.
+#![cfg_attr(
+    not(any(feature = "client", feature = "server")),
+    doc = include_str!("../README.md")
+)]
+#![cfg_attr(
+    feature = "client",
+    doc = "Client build of `fhe`, restricted to the encoding and the public-key encryption."
+)]
+#![cfg_attr(
+    feature = "server",
+    doc = "Server build of `fhe`, without the secret key and the decryption."
+)]
 
+#[cfg(all(
+    feature = "bench-report",
+    not(any(feature = "client", feature = "server"))
+))]
+mod bench_report;
 mod errors;
 
 pub mod bfv;
+#[cfg(not(any(feature = "client", feature = "server")))]
+#[forbid(unsafe_code)]
+pub mod easy;
+#[cfg(not(any(feature = "client", feature = "server")))]
 pub mod mbfv;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(not(any(feature = "client", feature = "server")))]
+pub mod mkbfv;
 pub mod proto;
+#[cfg(all(
+    feature = "bench-report",
+    not(any(feature = "client", feature = "server"))
+))]
+pub use bench_report::{bench_report, BenchEntry, BenchReport};
 pub use errors::{Error, ParametersError, Result};
 
 // Test the source code included in the README.
+#[cfg(not(any(feature = "client", feature = "server")))]
 #[macro_use]
 extern crate doc_comment;
+#[cfg(not(any(feature = "client", feature = "server")))]
 doctest!("../README.md");
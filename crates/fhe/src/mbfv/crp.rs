@@ -3,12 +3,15 @@ use alloc::sync::Arc;
 use alloc::vec::Vec;
 
 use crate::bfv::BfvParameters;
-use crate::Result;
+use crate::proto::mbfv::CommonRandomPoly as CommonRandomPolyProto;
+use crate::{Error, Result};
 use fhe_math::rq::Poly;
+use fhe_traits::{DeserializeParametrized, DeserializeWithContext, FheParametrized, Serialize};
+use prost::Message;
 use rand::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
 
 /// A polynomial sampled from a random _common reference string_.
-// TODO CRS->CRP implementation. For now just a random polynomial.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct CommonRandomPoly {
     pub(crate) poly: Poly,
@@ -43,4 +46,195 @@ impl CommonRandomPoly {
         let poly = Poly::random(ctx, fhe_math::rq::Representation::Ntt, rng);
         Ok(Self { poly })
     }
+
+    /// Derive a CRP from a seed shared by all the parties.
+    ///
+    /// The polynomial is sampled with
+    /// [`Poly::random_from_seed_wide`], so that every party can verifiably
+    /// recompute it from the seed, and its coefficients have a bias bounded by
+    /// `2^(-64)`.
+    pub fn new_from_seed(par: &Arc<BfvParameters>, seed: [u8; 32]) -> Result<Self> {
+        Self::new_leveled_from_seed(par, 0, seed)
+    }
+
+    /// Derive a leveled CRP from a seed shared by all the parties.
+    pub fn new_leveled_from_seed(
+        par: &Arc<BfvParameters>,
+        level: usize,
+        seed: [u8; 32],
+    ) -> Result<Self> {
+        let ctx = par.ctx_at_level(level)?;
+        let poly = Poly::random_from_seed_wide(ctx, fhe_math::rq::Representation::Ntt, seed);
+        Ok(Self { poly })
+    }
+}
+
+impl FheParametrized for CommonRandomPoly {
+    type Parameters = BfvParameters;
+}
+
+impl Serialize for CommonRandomPoly {
+    fn to_bytes(&self) -> Vec<u8> {
+        CommonRandomPolyProto {
+            poly: self.poly.to_bytes(),
+            num_moduli: self.poly.ctx().moduli().len() as u32,
+        }
+        .encode_to_vec()
+    }
+}
+
+impl DeserializeParametrized for CommonRandomPoly {
+    type Error = Error;
+
+    fn from_bytes(bytes: &[u8], par: &Arc<BfvParameters>) -> Result<Self> {
+        let proto: CommonRandomPolyProto =
+            Message::decode(bytes).map_err(|_| Error::SerializationError)?;
+        let level = par
+            .moduli()
+            .len()
+            .checked_sub(proto.num_moduli as usize)
+            .ok_or(Error::SerializationError)?;
+        let ctx = par
+            .ctx_at_level(level)
+            .map_err(|_| Error::SerializationError)?;
+        Ok(Self {
+            poly: Poly::from_bytes(&proto.poly, ctx)?,
+        })
+    }
+}
+
+/// A public _common reference string_, from which all the parties
+/// deterministically derive the [`CommonRandomPoly`] used by the protocols.
+///
+/// Each polynomial is identified by a domain, typically naming the protocol
+/// and its round, and an index within this domain. Distinct domains and
+/// indices yield independent polynomials, so that a single seed can be shared
+/// once by the parties and used for all their protocols.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Crp {
+    par: Arc<BfvParameters>,
+    seed: [u8; 32],
+}
+
+impl Crp {
+    /// Create a common reference string from a public seed.
+    pub fn new(par: &Arc<BfvParameters>, seed: [u8; 32]) -> Self {
+        Self {
+            par: par.clone(),
+            seed,
+        }
+    }
+
+    /// Generate a common reference string from a random seed.
+    pub fn random<R: RngCore + CryptoRng>(par: &Arc<BfvParameters>, rng: &mut R) -> Self {
+        let mut seed = [0u8; 32];
+        rng.fill_bytes(&mut seed);
+        Self::new(par, seed)
+    }
+
+    /// Returns the public seed.
+    pub fn seed(&self) -> [u8; 32] {
+        self.seed
+    }
+
+    /// Derive the seed of the polynomial at index `index` and level `level`
+    /// of the domain `domain`.
+    fn derive_seed(&self, domain: &str, index: usize, level: usize) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(b"fhe.rs/mbfv/crp");
+        hasher.update(self.seed);
+        hasher.update((domain.len() as u64).to_le_bytes());
+        hasher.update(domain.as_bytes());
+        hasher.update((index as u64).to_le_bytes());
+        hasher.update((level as u64).to_le_bytes());
+        hasher.finalize().into()
+    }
+
+    /// Derive the CRP at index `index` of the domain `domain`.
+    pub fn poly(&self, domain: &str, index: usize) -> Result<CommonRandomPoly> {
+        self.poly_leveled(domain, index, 0)
+    }
+
+    /// Derive the leveled CRP at index `index` of the domain `domain`.
+    pub fn poly_leveled(
+        &self,
+        domain: &str,
+        index: usize,
+        level: usize,
+    ) -> Result<CommonRandomPoly> {
+        CommonRandomPoly::new_leveled_from_seed(
+            &self.par,
+            level,
+            self.derive_seed(domain, index, level),
+        )
+    }
+
+    /// Derive the vector of CRPs of the domain `domain`.
+    ///
+    /// The size of the vector is equal to the number of ciphertext moduli, as
+    /// required for the relinearization key generation protocol.
+    pub fn poly_vec(&self, domain: &str) -> Result<Vec<CommonRandomPoly>> {
+        (0..self.par.moduli().len())
+            .map(|i| self.poly(domain, i))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CommonRandomPoly, Crp};
+    use crate::bfv::{BfvParameters, PublicKey, SecretKey};
+    use crate::mbfv::{Aggregate, PublicKeyShare};
+    use crate::Error;
+    use fhe_traits::{DeserializeParametrized, Serialize};
+    use rand::thread_rng;
+    extern crate alloc;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn domain_separation() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let par = BfvParameters::default_arc(3, 16);
+        let crp = Crp::random(&par, &mut rng);
+        let other = Crp::new(&par, crp.seed());
+        assert_eq!(crp, other);
+
+        assert_eq!(crp.poly("pk", 0)?, other.poly("pk", 0)?);
+        assert_ne!(crp.poly("pk", 0)?, crp.poly("pk", 1)?);
+        assert_ne!(crp.poly("pk", 0)?, crp.poly("rlk", 0)?);
+        assert_ne!(crp.poly("pk", 0)?, crp.poly_leveled("pk", 0, 1)?);
+        assert_eq!(
+            crp.poly_leveled("pk", 0, 1)?,
+            other.poly_leveled("pk", 0, 1)?
+        );
+
+        let v = crp.poly_vec("rlk")?;
+        assert_eq!(v.len(), 3);
+        assert_eq!(v, other.poly_vec("rlk")?);
+        assert_eq!(v[1], crp.poly("rlk", 1)?);
+
+        for level in 0..3 {
+            let poly = crp.poly_leveled("pk", 0, level)?;
+            assert_eq!(CommonRandomPoly::from_bytes(&poly.to_bytes(), &par)?, poly);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn public_key_gen() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let par = BfvParameters::default_arc(1, 16);
+        let crp = Crp::random(&par, &mut rng);
+
+        // Each party derives the CRP from the shared seed.
+        let shares = (0..3)
+            .map(|_| {
+                let sk = SecretKey::random(&par, &mut rng);
+                let crp = Crp::new(&par, crp.seed()).poly("pk", 0)?;
+                PublicKeyShare::new(&sk, crp, &mut rng)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let _pk = PublicKey::from_shares(shares)?;
+        Ok(())
+    }
 }
@@ -10,7 +10,7 @@ pub mod round;
 mod secret_key_switch;
 
 pub use aggregate::{Aggregate, AggregateIter};
-pub use crp::CommonRandomPoly;
+pub use crp::{CommonRandomPoly, Crp};
 pub use public_key_gen::PublicKeyShare;
 pub use public_key_switch::PublicKeySwitchShare;
 pub use relin_key_gen::{RelinKeyGenerator, RelinKeyShare};
@@ -1,11 +1,15 @@
 extern crate alloc;
 use alloc::sync::Arc;
 use alloc::vec;
+use alloc::vec::Vec;
 
 use crate::bfv::{BfvParameters, Ciphertext, PublicKey, SecretKey};
 use crate::errors::Result;
+use crate::proto::mbfv::PublicKeyShare as PublicKeyShareProto;
 use crate::Error;
-use fhe_math::rq::{traits::TryConvertFrom, Poly, Representation};
+use fhe_math::rq::{traits::TryConvertFrom, Distribution, Poly, Representation};
+use fhe_traits::{DeserializeParametrized, DeserializeWithContext, FheParametrized, Serialize};
+use prost::Message;
 use rand::{CryptoRng, RngCore};
 use zeroize::Zeroizing;
 
@@ -50,7 +54,12 @@ impl PublicKeyShare {
         s.change_representation(Representation::Ntt);
 
         // Sample error
-        let e = Zeroizing::new(Poly::small(ctx, Representation::Ntt, par.variance, rng)?);
+        let e = Zeroizing::new(Poly::small(
+            ctx,
+            Representation::Ntt,
+            Distribution::Cbd(par.variance),
+            rng,
+        )?);
         // Create p0_i share
         let mut p0_share = -crp.poly.clone();
         p0_share.disallow_variable_time_computations();
@@ -63,6 +72,37 @@ impl PublicKeyShare {
     }
 }
 
+impl FheParametrized for PublicKeyShare {
+    type Parameters = BfvParameters;
+}
+
+impl Serialize for PublicKeyShare {
+    fn to_bytes(&self) -> Vec<u8> {
+        PublicKeyShareProto {
+            crp: self.crp.poly.to_bytes(),
+            p0_share: self.p0_share.to_bytes(),
+        }
+        .encode_to_vec()
+    }
+}
+
+impl DeserializeParametrized for PublicKeyShare {
+    type Error = Error;
+
+    fn from_bytes(bytes: &[u8], par: &Arc<BfvParameters>) -> Result<Self> {
+        let proto: PublicKeyShareProto =
+            Message::decode(bytes).map_err(|_| Error::SerializationError)?;
+        let ctx = par.ctx_at_level(0)?;
+        Ok(Self {
+            par: par.clone(),
+            crp: CommonRandomPoly {
+                poly: Poly::from_bytes(&proto.crp, ctx)?,
+            },
+            p0_share: Poly::from_bytes(&proto.p0_share, ctx)?,
+        })
+    }
+}
+
 impl Aggregate<PublicKeyShare> for PublicKey {
     fn from_shares<T>(iter: T) -> Result<Self>
     where
@@ -84,7 +124,7 @@ impl Aggregate<PublicKeyShare> for PublicKey {
 
 #[cfg(test)]
 mod tests {
-    use fhe_traits::{FheEncoder, FheEncrypter};
+    use fhe_traits::{DeserializeParametrized, FheEncoder, FheEncrypter, Serialize};
     use rand::thread_rng;
     extern crate alloc;
     use alloc::vec;
@@ -136,4 +176,19 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn serialize() {
+        let mut rng = thread_rng();
+        let par = BfvParameters::default_arc(2, 16);
+        let crp = CommonRandomPoly::new(&par, &mut rng).unwrap();
+        let sk_share = SecretKey::random(&par, &mut rng);
+        let pk_share = PublicKeyShare::new(&sk_share, crp, &mut rng).unwrap();
+        assert_eq!(
+            PublicKeyShare::from_bytes(&pk_share.to_bytes(), &par).unwrap(),
+            pk_share
+        );
+        let other = BfvParameters::default_arc(1, 16);
+        assert!(PublicKeyShare::from_bytes(&pk_share.to_bytes(), &other).is_err());
+    }
 }
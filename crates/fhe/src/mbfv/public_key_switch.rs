@@ -2,14 +2,18 @@ extern crate alloc;
 use alloc::string::ToString;
 use alloc::sync::Arc;
 use alloc::vec;
+use alloc::vec::Vec;
 
 use fhe_math::rq::traits::TryConvertFrom;
-use fhe_math::rq::{Poly, Representation};
+use fhe_math::rq::{Distribution, Poly, Representation};
+use fhe_traits::{DeserializeParametrized, DeserializeWithContext, FheParametrized, Serialize};
 
+use prost::Message;
 use rand::{CryptoRng, RngCore};
 use zeroize::Zeroizing;
 
 use crate::bfv::{BfvParameters, Ciphertext, PublicKey, SecretKey};
+use crate::proto::mbfv::PublicKeySwitchShare as PublicKeySwitchShareProto;
 use crate::{Error, Result};
 
 use super::Aggregate;
@@ -62,10 +66,25 @@ impl PublicKeySwitchShare {
         s.change_representation(Representation::Ntt);
         s.disallow_variable_time_computations();
 
-        let u = Zeroizing::new(Poly::small(ctx, Representation::Ntt, par.variance, rng)?);
+        let u = Zeroizing::new(Poly::small(
+            ctx,
+            Representation::Ntt,
+            Distribution::Cbd(par.variance),
+            rng,
+        )?);
         // TODO this should be exponential in ciphertext noise!
-        let e0 = Zeroizing::new(Poly::small(ctx, Representation::Ntt, par.variance, rng)?);
-        let e1 = Zeroizing::new(Poly::small(ctx, Representation::Ntt, par.variance, rng)?);
+        let e0 = Zeroizing::new(Poly::small(
+            ctx,
+            Representation::Ntt,
+            Distribution::Cbd(par.variance),
+            rng,
+        )?);
+        let e1 = Zeroizing::new(Poly::small(
+            ctx,
+            Representation::Ntt,
+            Distribution::Cbd(par.variance),
+            rng,
+        )?);
 
         let mut h0 = pk_ct[0].clone();
         h0.disallow_variable_time_computations();
@@ -93,6 +112,40 @@ impl PublicKeySwitchShare {
     }
 }
 
+impl FheParametrized for PublicKeySwitchShare {
+    type Parameters = BfvParameters;
+}
+
+impl Serialize for PublicKeySwitchShare {
+    fn to_bytes(&self) -> Vec<u8> {
+        PublicKeySwitchShareProto {
+            c0: self.c0.to_bytes(),
+            h0_share: self.h0_share.to_bytes(),
+            h1_share: self.h1_share.to_bytes(),
+            level: self.par.level_of_ctx(self.c0.ctx()).unwrap() as u32,
+        }
+        .encode_to_vec()
+    }
+}
+
+impl DeserializeParametrized for PublicKeySwitchShare {
+    type Error = Error;
+
+    fn from_bytes(bytes: &[u8], par: &Arc<BfvParameters>) -> Result<Self> {
+        let proto: PublicKeySwitchShareProto =
+            Message::decode(bytes).map_err(|_| Error::SerializationError)?;
+        let ctx = par
+            .ctx_at_level(proto.level as usize)
+            .map_err(|_| Error::SerializationError)?;
+        Ok(Self {
+            par: par.clone(),
+            c0: Poly::from_bytes(&proto.c0, ctx)?,
+            h0_share: Poly::from_bytes(&proto.h0_share, ctx)?,
+            h1_share: Poly::from_bytes(&proto.h1_share, ctx)?,
+        })
+    }
+}
+
 impl Aggregate<PublicKeySwitchShare> for Ciphertext {
     fn from_shares<T>(iter: T) -> Result<Self>
     where
@@ -120,7 +173,7 @@ mod tests {
     use alloc::vec;
     use alloc::vec::Vec;
 
-    use fhe_traits::{FheDecrypter, FheEncoder, FheEncrypter};
+    use fhe_traits::{DeserializeParametrized, FheDecrypter, FheEncoder, FheEncrypter, Serialize};
     use rand::thread_rng;
 
     use crate::{
@@ -185,4 +238,25 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn serialize() {
+        let mut rng = thread_rng();
+        let par = BfvParameters::default_arc(2, 16);
+        let sk = SecretKey::random(&par, &mut rng);
+        let sk_out = SecretKey::random(&par, &mut rng);
+        let pk_out = PublicKey::new(&sk_out, &mut rng);
+        for level in 0..=par.max_level() {
+            let pt =
+                Plaintext::try_encode(&[1u64, 2, 3], Encoding::poly_at_level(level), &par).unwrap();
+            let ct = sk.try_encrypt(&pt, &mut rng).unwrap();
+            let share = PublicKeySwitchShare::new(&sk, &pk_out, &ct, &mut rng).unwrap();
+            let bytes = share.to_bytes();
+            let deserialized = PublicKeySwitchShare::from_bytes(&bytes, &par).unwrap();
+            assert_eq!(deserialized.to_bytes(), bytes);
+
+            let ct_out = [deserialized].into_iter().aggregate().unwrap();
+            assert_eq!(sk_out.try_decrypt(&ct_out).unwrap(), pt);
+        }
+    }
 }
@@ -7,10 +7,13 @@ use alloc::vec::Vec;
 
 use crate::bfv::{BfvParameters, KeySwitchingKey, RelinearizationKey, SecretKey};
 use crate::errors::Result;
+use crate::proto::mbfv::RelinKeyShare as RelinKeyShareProto;
 use crate::Error;
 use fhe_math::rns::RnsContext;
-use fhe_math::rq::{traits::TryConvertFrom, Poly, Representation};
+use fhe_math::rq::{traits::TryConvertFrom, Distribution, Poly, Representation};
+use fhe_traits::{DeserializeParametrized, DeserializeWithContext, FheParametrized, Serialize};
 use itertools::izip;
+use prost::Message;
 use rand::{CryptoRng, RngCore};
 use zeroize::Zeroizing;
 
@@ -98,7 +101,12 @@ impl<'a, 'b> RelinKeyGenerator<'a, 'b> {
                     .to_string(),
             ))
         } else {
-            let u = Zeroizing::new(Poly::small(ctx, Representation::Ntt, par.variance, rng)?);
+            let u = Zeroizing::new(Poly::small(
+                ctx,
+                Representation::Ntt,
+                Distribution::Cbd(par.variance),
+                rng,
+            )?);
             Ok(Self { sk_share, crp, u })
         }
     }
@@ -169,7 +177,12 @@ impl RelinKeyShare<R1> {
                 let mut w_s = Zeroizing::new(w * s.as_ref());
                 w_s.change_representation(Representation::Ntt);
 
-                let e = Zeroizing::new(Poly::small(ctx, Representation::Ntt, par.variance, rng)?);
+                let e = Zeroizing::new(Poly::small(
+                    ctx,
+                    Representation::Ntt,
+                    Distribution::Cbd(par.variance),
+                    rng,
+                )?);
 
                 let mut h = -a.poly.clone();
                 h.disallow_variable_time_computations();
@@ -204,7 +217,12 @@ impl RelinKeyShare<R1> {
                 let mut h = a.poly.clone();
                 h.disallow_variable_time_computations();
                 h.change_representation(Representation::Ntt);
-                let e = Zeroizing::new(Poly::small(ctx, Representation::Ntt, par.variance, rng)?);
+                let e = Zeroizing::new(Poly::small(
+                    ctx,
+                    Representation::Ntt,
+                    Distribution::Cbd(par.variance),
+                    rng,
+                )?);
                 h *= s.as_ref();
                 h += e.as_ref();
                 Ok(h)
@@ -275,7 +293,12 @@ impl RelinKeyShare<R2> {
         let h0 = r1_h0
             .iter()
             .map(|h| {
-                let e = Zeroizing::new(Poly::small(ctx, Representation::Ntt, par.variance, rng)?);
+                let e = Zeroizing::new(Poly::small(
+                    ctx,
+                    Representation::Ntt,
+                    Distribution::Cbd(par.variance),
+                    rng,
+                )?);
 
                 let mut h_prime = h.clone();
                 h_prime.disallow_variable_time_computations();
@@ -313,7 +336,12 @@ impl RelinKeyShare<R2> {
                 let mut h_prime = h.clone();
                 h_prime.disallow_variable_time_computations();
                 h_prime.change_representation(Representation::Ntt);
-                let e = Zeroizing::new(Poly::small(ctx, Representation::Ntt, par.variance, rng)?);
+                let e = Zeroizing::new(Poly::small(
+                    ctx,
+                    Representation::Ntt,
+                    Distribution::Cbd(par.variance),
+                    rng,
+                )?);
                 h_prime *= u_s.as_ref();
                 h_prime += e.as_ref();
                 Ok(h_prime)
@@ -369,26 +397,97 @@ impl Aggregate<RelinKeyShare<R2>> for RelinearizationKey {
             ctx_ksk: ctx.clone(),
             log_base: 0,
         };
-        Ok(RelinearizationKey { ksk })
+        Ok(RelinearizationKey {
+            ksk,
+            ksk_powers: Vec::new(),
+        })
+    }
+}
+
+impl<R: Round> FheParametrized for RelinKeyShare<R> {
+    type Parameters = BfvParameters;
+}
+
+impl<R: Round> From<&RelinKeyShare<R>> for RelinKeyShareProto {
+    fn from(share: &RelinKeyShare<R>) -> Self {
+        RelinKeyShareProto {
+            h0: share.h0.iter().map(|h| h.to_bytes()).collect(),
+            h1: share.h1.iter().map(|h| h.to_bytes()).collect(),
+            last_round: share
+                .last_round
+                .as_ref()
+                .map(|r1| Box::new(r1.as_ref().into())),
+        }
+    }
+}
+
+impl<R: Round> RelinKeyShare<R> {
+    fn try_from_proto(value: &RelinKeyShareProto, par: &Arc<BfvParameters>) -> Result<Self> {
+        let ctx = par.ctx_at_level(0)?;
+        if value.h0.len() != ctx.moduli().len() || value.h1.len() != ctx.moduli().len() {
+            return Err(Error::SerializationError);
+        }
+        let h0 = value
+            .h0
+            .iter()
+            .map(|h| Poly::from_bytes(h, ctx).map_err(Error::MathError))
+            .collect::<Result<Box<[Poly]>>>()?;
+        let h1 = value
+            .h1
+            .iter()
+            .map(|h| Poly::from_bytes(h, ctx).map_err(Error::MathError))
+            .collect::<Result<Box<[Poly]>>>()?;
+        let last_round = value
+            .last_round
+            .as_ref()
+            .map(|r1| RelinKeyShare::<R1Aggregated>::try_from_proto(r1, par).map(Arc::new))
+            .transpose()?;
+        Ok(Self {
+            par: par.clone(),
+            h0,
+            h1,
+            last_round,
+            _phantom_data: PhantomData,
+        })
+    }
+}
+
+impl<R: Round> Serialize for RelinKeyShare<R> {
+    fn to_bytes(&self) -> Vec<u8> {
+        RelinKeyShareProto::from(self).encode_to_vec()
+    }
+}
+
+impl<R: Round> DeserializeParametrized for RelinKeyShare<R> {
+    type Error = Error;
+
+    fn from_bytes(bytes: &[u8], par: &Arc<BfvParameters>) -> Result<Self> {
+        let proto: RelinKeyShareProto =
+            Message::decode(bytes).map_err(|_| Error::SerializationError)?;
+        Self::try_from_proto(&proto, par)
     }
 }
 
 #[cfg(test)]
 mod tests {
     extern crate alloc;
-    
+
     use alloc::sync::Arc;
     use alloc::vec;
     use alloc::vec::Vec;
 
-    use fhe_traits::{FheDecoder, FheEncoder, FheEncrypter};
+    use fhe_traits::{DeserializeParametrized, FheDecoder, FheEncoder, FheEncrypter, Serialize};
     use rand::thread_rng;
 
     use crate::{
-        bfv::{BfvParameters, Encoding, Multiplicator, Plaintext, PublicKey, SecretKey},
+        bfv::{
+            BfvParameters, Encoding, Multiplicator, Plaintext, PublicKey, RelinearizationKey,
+            SecretKey,
+        },
         mbfv::{
+            round::{R1Aggregated, R1, R2},
             Aggregate as _, AggregateIter, CommonRandomPoly, DecryptionShare, PublicKeyShare,
-            RelinKeyGenerator,
+            RelinKeyGenerator, RelinKeyShare,
         },
     };
 
@@ -476,4 +575,36 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn serialize() {
+        let mut rng = thread_rng();
+        let par = BfvParameters::default_arc(3, 16);
+        let crp = CommonRandomPoly::new_vec(&par, &mut rng).unwrap();
+        let sk_share = SecretKey::random(&par, &mut rng);
+        let generator = RelinKeyGenerator::new(&sk_share, &crp, &mut rng).unwrap();
+
+        let r1 = generator.round_1(&mut rng).unwrap();
+        let deserialized = RelinKeyShare::<R1>::from_bytes(&r1.to_bytes(), &par).unwrap();
+        assert_eq!(deserialized, r1);
+
+        let r1_aggregated = [r1]
+            .into_iter()
+            .aggregate::<RelinKeyShare<R1Aggregated>>()
+            .unwrap();
+        let deserialized =
+            RelinKeyShare::<R1Aggregated>::from_bytes(&r1_aggregated.to_bytes(), &par).unwrap();
+        assert_eq!(deserialized, r1_aggregated);
+
+        let r2 = generator
+            .round_2(&Arc::new(r1_aggregated), &mut rng)
+            .unwrap();
+        let deserialized = RelinKeyShare::<R2>::from_bytes(&r2.to_bytes(), &par).unwrap();
+        assert_eq!(deserialized, r2);
+        let rlk = RelinearizationKey::from_shares([deserialized]).unwrap();
+        assert_eq!(rlk, RelinearizationKey::from_shares([r2]).unwrap());
+
+        let other = BfvParameters::default_arc(2, 16);
+        assert!(RelinKeyShare::<R1>::from_bytes(&crp[0].to_bytes(), &other).is_err());
+    }
 }
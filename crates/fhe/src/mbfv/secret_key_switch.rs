@@ -5,14 +5,19 @@ use alloc::vec;
 use alloc::vec::Vec;
 
 use fhe_math::{
-    rq::{traits::TryConvertFrom, Poly, Representation},
+    rq::{traits::TryConvertFrom, Distribution, Poly, Representation},
     zq::Modulus,
 };
+use fhe_traits::{DeserializeParametrized, DeserializeWithContext, FheParametrized, Serialize};
 use itertools::Itertools;
+use prost::Message;
 use rand::{CryptoRng, RngCore};
 use zeroize::Zeroizing;
 
+use crate::bfv::traits::TryConvertFrom as _;
 use crate::bfv::{BfvParameters, Ciphertext, Plaintext, SecretKey};
+use crate::proto::bfv::Ciphertext as CiphertextProto;
+use crate::proto::mbfv::SecretKeySwitchShare as SecretKeySwitchShareProto;
 use crate::{Error, Result};
 
 use super::Aggregate;
@@ -78,7 +83,7 @@ impl SecretKeySwitchShare {
         let e = Zeroizing::new(Poly::small(
             ct[0].ctx(),
             Representation::Ntt,
-            par.variance,
+            Distribution::Cbd(par.variance),
             rng,
         )?);
 
@@ -92,6 +97,40 @@ impl SecretKeySwitchShare {
     }
 }
 
+impl FheParametrized for SecretKeySwitchShare {
+    type Parameters = BfvParameters;
+}
+
+impl Serialize for SecretKeySwitchShare {
+    fn to_bytes(&self) -> Vec<u8> {
+        SecretKeySwitchShareProto {
+            ct: Some(CiphertextProto::from(self.ct.as_ref())),
+            h_share: self.h_share.to_bytes(),
+        }
+        .encode_to_vec()
+    }
+}
+
+impl DeserializeParametrized for SecretKeySwitchShare {
+    type Error = Error;
+
+    fn from_bytes(bytes: &[u8], par: &Arc<BfvParameters>) -> Result<Self> {
+        let proto: SecretKeySwitchShareProto =
+            Message::decode(bytes).map_err(|_| Error::SerializationError)?;
+        let ct =
+            Ciphertext::try_convert_from(proto.ct.as_ref().ok_or(Error::SerializationError)?, par)?;
+        if ct.len() != 2 {
+            return Err(Error::SerializationError);
+        }
+        let h_share = Poly::from_bytes(&proto.h_share, ct[0].ctx())?;
+        Ok(Self {
+            par: par.clone(),
+            ct: Arc::new(ct),
+            h_share,
+        })
+    }
+}
+
 impl Aggregate<SecretKeySwitchShare> for Ciphertext {
     fn from_shares<T>(iter: T) -> Result<Self>
     where
@@ -139,6 +178,26 @@ impl DecryptionShare {
     }
 }
 
+impl FheParametrized for DecryptionShare {
+    type Parameters = BfvParameters;
+}
+
+impl Serialize for DecryptionShare {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.sks_share.to_bytes()
+    }
+}
+
+impl DeserializeParametrized for DecryptionShare {
+    type Error = Error;
+
+    fn from_bytes(bytes: &[u8], par: &Arc<BfvParameters>) -> Result<Self> {
+        Ok(Self {
+            sks_share: SecretKeySwitchShare::from_bytes(bytes, par)?,
+        })
+    }
+}
+
 impl Aggregate<DecryptionShare> for Plaintext {
     fn from_shares<T>(iter: T) -> Result<Self>
     where
@@ -174,6 +233,7 @@ impl Aggregate<DecryptionShare> for Plaintext {
             encoding: None,
             poly_ntt: poly,
             level: ct.level,
+            scaled_poly: Default::default(),
         };
 
         Ok(pt)
@@ -183,16 +243,18 @@ impl Aggregate<DecryptionShare> for Plaintext {
 #[cfg(test)]
 mod tests {
     extern crate alloc;
-    
+
     use alloc::sync::Arc;
     use alloc::vec;
     use alloc::vec::Vec;
 
-    use fhe_traits::{FheDecoder, FheEncoder, FheEncrypter};
+    use fhe_traits::{
+        DeserializeParametrized, FheDecoder, FheDecrypter, FheEncoder, FheEncrypter, Serialize,
+    };
     use rand::thread_rng;
 
     use crate::{
-        bfv::{BfvParameters, Encoding, Plaintext, PublicKey, SecretKey},
+        bfv::{BfvParameters, Ciphertext, Encoding, Plaintext, PublicKey, SecretKey},
         mbfv::{
             Aggregate, AggregateIter, CommonRandomPoly, DecryptionShare, PublicKeyShare,
             SecretKeySwitchShare,
@@ -378,4 +440,28 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn serialize() {
+        let mut rng = thread_rng();
+        let par = BfvParameters::default_arc(2, 16);
+        let sk = SecretKey::random(&par, &mut rng);
+        let sk_out = SecretKey::random(&par, &mut rng);
+        for level in 0..=par.max_level() {
+            let pt =
+                Plaintext::try_encode(&[1u64, 2, 3], Encoding::poly_at_level(level), &par).unwrap();
+            let ct: Arc<Ciphertext> = Arc::new(sk.try_encrypt(&pt, &mut rng).unwrap());
+
+            let share = SecretKeySwitchShare::new(&sk, &sk_out, ct.clone(), &mut rng).unwrap();
+            let bytes = share.to_bytes();
+            let deserialized = SecretKeySwitchShare::from_bytes(&bytes, &par).unwrap();
+            assert_eq!(deserialized.to_bytes(), bytes);
+            let ct_out = Ciphertext::from_shares([deserialized]).unwrap();
+            assert_eq!(sk_out.try_decrypt(&ct_out).unwrap(), pt);
+
+            let share = DecryptionShare::new(&sk, &ct, &mut rng).unwrap();
+            let deserialized = DecryptionShare::from_bytes(&share.to_bytes(), &par).unwrap();
+            assert_eq!(Plaintext::from_shares([deserialized]).unwrap(), pt);
+        }
+    }
 }
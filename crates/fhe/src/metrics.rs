@@ -0,0 +1,184 @@
+//! Counters of the operations of the library, enabled by the `metrics`
+//! feature, to monitor an evaluation server.
+//!
+//! The counters are global to the program and updated atomically, so that
+//! they can be read from a monitoring thread with [`snapshot`], and exported
+//! in the Prometheus text format with [`Metrics::to_prometheus`]. A callback
+//! can also be registered with [`set_callback`] to forward the events to
+//! another metrics library as they happen.
+
+use core::sync::atomic::{AtomicPtr, AtomicU64, Ordering};
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+
+/// An event counted by the library.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    /// A multiplication of two ciphertexts.
+    Multiplication,
+    /// A key switching, e.g. in a relinearization or a rotation.
+    KeySwitch,
+    /// Serialized bytes of a ciphertext.
+    BytesSerialized,
+    /// An operation of an [`Evaluator`](crate::bfv::Evaluator) whose
+    /// estimated noise exhausts the noise budget of its output. The noise is
+    /// only estimated, and this event only counted, when the evaluator records
+    /// the provenance, see
+    /// [`Evaluator::enable_provenance`](crate::bfv::Evaluator::enable_provenance).
+    NoiseBudgetExhaustion,
+}
+
+impl Metric {
+    const ALL: [Metric; 4] = [
+        Metric::Multiplication,
+        Metric::KeySwitch,
+        Metric::BytesSerialized,
+        Metric::NoiseBudgetExhaustion,
+    ];
+}
+
+static COUNTERS: [AtomicU64; 4] = [
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+];
+
+/// The registered callback, or null. The callbacks are leaked when replaced,
+/// as they may still be read by another thread.
+static CALLBACK: AtomicPtr<fn(Metric, u64)> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Values of the counters, returned by [`snapshot`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Metrics {
+    /// Number of multiplications of two ciphertexts.
+    pub multiplications: u64,
+    /// Number of key switchings.
+    pub key_switches: u64,
+    /// Number of NTTs of polynomials, counting once per modulus.
+    pub ntts: u64,
+    /// Number of bytes of serialized ciphertexts.
+    pub bytes_serialized: u64,
+    /// Number of operations which exhausted the estimated noise budget, only
+    /// counted by the evaluators which record the provenance.
+    pub noise_budget_exhaustions: u64,
+}
+
+impl Metrics {
+    /// Format the counters in the Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        [
+            ("multiplications", "Multiplications of two ciphertexts.", self.multiplications),
+            ("key_switches", "Key switchings.", self.key_switches),
+            ("ntts", "NTTs of polynomials, per modulus.", self.ntts),
+            ("bytes_serialized", "Bytes of serialized ciphertexts.", self.bytes_serialized),
+            (
+                "noise_budget_exhaustions",
+                "Operations which exhausted the estimated noise budget.",
+                self.noise_budget_exhaustions,
+            ),
+        ]
+        .iter()
+        .map(|(name, help, value)| {
+            format!(
+                "# HELP fhe_{name}_total {help}\n# TYPE fhe_{name}_total counter\nfhe_{name}_total {value}\n"
+            )
+        })
+        .collect()
+    }
+}
+
+/// Returns the current values of the counters.
+pub fn snapshot() -> Metrics {
+    let load = |metric: Metric| COUNTERS[metric as usize].load(Ordering::Relaxed);
+    Metrics {
+        multiplications: load(Metric::Multiplication),
+        key_switches: load(Metric::KeySwitch),
+        ntts: fhe_math::metrics::ntts(),
+        bytes_serialized: load(Metric::BytesSerialized),
+        noise_budget_exhaustions: load(Metric::NoiseBudgetExhaustion),
+    }
+}
+
+/// Reset all the counters to 0.
+pub fn reset() {
+    Metric::ALL
+        .iter()
+        .for_each(|metric| COUNTERS[*metric as usize].store(0, Ordering::Relaxed));
+    fhe_math::metrics::reset()
+}
+
+/// Register a callback invoked with each event and its value, e.g. the number
+/// of bytes serialized, or remove it with `None`. The NTTs are too frequent to
+/// be reported to the callback, and are only counted.
+pub fn set_callback(callback: Option<fn(Metric, u64)>) {
+    let ptr = callback.map_or(core::ptr::null_mut(), |f| Box::into_raw(Box::new(f)));
+    CALLBACK.store(ptr, Ordering::Release)
+}
+
+/// Count an event.
+pub(crate) fn record(metric: Metric, value: u64) {
+    COUNTERS[metric as usize].fetch_add(value, Ordering::Relaxed);
+    let callback = CALLBACK.load(Ordering::Acquire);
+    // Safety: the only non-null pointers stored come from `Box::into_raw`, and
+    // are never freed.
+    if let Some(callback) = unsafe { callback.as_ref() } {
+        callback(metric, value)
+    }
+}
+
+#[cfg(all(test, not(any(feature = "client", feature = "server"))))]
+mod tests {
+    use super::{reset, set_callback, snapshot, Metric};
+    use crate::bfv::{BfvParameters, Ciphertext, Encoding, Plaintext, RelinearizationKey};
+    use crate::bfv::{Evaluator, SecretKey};
+    use crate::Error;
+    use core::sync::atomic::{AtomicU64, Ordering};
+    use fhe_traits::{FheEncoder, FheEncrypter, Serialize};
+    use rand::thread_rng;
+
+    static CALLBACK_BYTES: AtomicU64 = AtomicU64::new(0);
+
+    fn callback(metric: Metric, value: u64) {
+        if metric == Metric::BytesSerialized {
+            CALLBACK_BYTES.fetch_add(value, Ordering::Relaxed);
+        }
+    }
+
+    // The counters are global, and other tests run concurrently, so that only
+    // lower bounds are checked.
+    #[test]
+    fn counters() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default_arc(2, 16);
+        let sk = SecretKey::random(&params, &mut rng);
+        let rk = RelinearizationKey::new(&sk, &mut rng)?;
+        let mut evaluator = Evaluator::new(&params).with_relinearization_key(&rk)?;
+        evaluator.enable_provenance();
+        let pt = Plaintext::try_encode(&[1u64, 2, 3], Encoding::simd(), &params)?;
+        let ct: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+
+        reset();
+        set_callback(Some(callback));
+        let mut product = evaluator.mul(&ct, &ct)?;
+        for _ in 0..4 {
+            product = evaluator.mul(&product, &product)?;
+        }
+        let bytes = product.to_bytes().len() as u64;
+        set_callback(None);
+
+        let metrics = snapshot();
+        assert!(metrics.multiplications >= 5);
+        assert!(metrics.key_switches >= 5);
+        assert!(metrics.ntts > 0);
+        assert!(metrics.bytes_serialized >= bytes);
+        assert!(metrics.noise_budget_exhaustions >= 1);
+        assert!(CALLBACK_BYTES.load(Ordering::Relaxed) >= bytes);
+        assert!(metrics
+            .to_prometheus()
+            .contains("# TYPE fhe_multiplications_total counter\n"));
+        Ok(())
+    }
+}
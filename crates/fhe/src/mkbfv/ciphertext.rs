@@ -0,0 +1,124 @@
+extern crate alloc;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use fhe_math::rq::{Poly, Representation};
+
+use crate::bfv::{BfvParameters, Ciphertext};
+use crate::{Error, Result};
+
+/// A ciphertext encrypted under the concatenation of the secret keys of
+/// several parties: the phase is `c0 + c_1 * s_1 + ... + c_k * s_k`, where
+/// `s_i` is the secret key of the i-th party of [`MultiKeyCiphertext::parties`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MultiKeyCiphertext {
+    pub(crate) par: Arc<BfvParameters>,
+    /// The identifiers of the parties, sorted in increasing order.
+    pub(crate) parties: Vec<usize>,
+    /// The polynomial `c0`, followed by one polynomial per party.
+    pub(crate) c: Vec<Poly>,
+}
+
+impl MultiKeyCiphertext {
+    /// Convert a BFV ciphertext encrypted under the secret key of the party
+    /// identified by `party` into a multi-key ciphertext.
+    ///
+    /// Returns an error if the ciphertext is not at level 0 or is not of
+    /// size 2.
+    pub fn new(ct: &Ciphertext, party: usize) -> Result<Self> {
+        if ct.level != 0 {
+            return Err(Error::DefaultError(
+                "Multi-key ciphertexts are only supported at level 0".to_string(),
+            ));
+        }
+        if ct.len() != 2 {
+            return Err(Error::TooManyValues(ct.len(), 2));
+        }
+        let c = ct
+            .iter()
+            .map(|ci| {
+                let mut ci = ci.clone();
+                ci.change_representation(Representation::Ntt);
+                ci
+            })
+            .collect();
+        Ok(Self {
+            par: ct.par.clone(),
+            parties: vec![party],
+            c,
+        })
+    }
+
+    /// Returns the identifiers of the parties whose secret keys are needed to
+    /// decrypt the ciphertext, in increasing order.
+    pub fn parties(&self) -> &[usize] {
+        &self.parties
+    }
+
+    /// Extend the ciphertext to the concatenated secret key of the `parties`,
+    /// which must include the parties of the ciphertext: the polynomials of
+    /// the new parties are zero, so that the phase is unchanged.
+    pub fn extend(&self, parties: &[usize]) -> Result<Self> {
+        let mut parties = parties.to_vec();
+        parties.sort_unstable();
+        parties.dedup();
+        if !self
+            .parties
+            .iter()
+            .all(|p| parties.binary_search(p).is_ok())
+        {
+            return Err(Error::DefaultError(
+                "The parties must include the parties of the ciphertext".to_string(),
+            ));
+        }
+        let mut c = Vec::with_capacity(parties.len() + 1);
+        c.push(self.c[0].clone());
+        for party in &parties {
+            match self.parties.binary_search(party) {
+                Ok(i) => c.push(self.c[i + 1].clone()),
+                Err(_) => c.push(Poly::zero(self.c[0].ctx(), Representation::Ntt)),
+            }
+        }
+        Ok(Self {
+            par: self.par.clone(),
+            parties,
+            c,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fhe_traits::{FheEncoder, FheEncrypter};
+    use rand::thread_rng;
+
+    use super::MultiKeyCiphertext;
+    use crate::bfv::{BfvParameters, Ciphertext, Encoding, Plaintext, SecretKey};
+    use crate::Error;
+
+    #[test]
+    fn extend() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let par = BfvParameters::default_arc(2, 16);
+        let sk = SecretKey::random(&par, &mut rng);
+        let pt = Plaintext::try_encode(&[1u64, 2, 3], Encoding::poly(), &par)?;
+        let ct = MultiKeyCiphertext::new(&sk.try_encrypt(&pt, &mut rng)?, 3)?;
+        assert_eq!(ct.parties(), &[3]);
+
+        let extended = ct.extend(&[5, 3, 1])?;
+        assert_eq!(extended.parties(), &[1, 3, 5]);
+        assert_eq!(extended.c.len(), 4);
+        assert_eq!(extended.c[0], ct.c[0]);
+        assert_eq!(extended.c[2], ct.c[1]);
+        assert!(extended.c[1].coefficients().iter().all(|c| *c == 0));
+        assert_eq!(extended.extend(&[3, 1, 5])?, extended);
+        assert!(extended.extend(&[1, 3]).is_err());
+
+        let mut ct: Ciphertext = sk.try_encrypt(&pt, &mut rng)?;
+        ct.mod_switch_to_last_level()?;
+        assert!(MultiKeyCiphertext::new(&ct, 0).is_err());
+        Ok(())
+    }
+}
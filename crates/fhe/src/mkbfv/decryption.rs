@@ -0,0 +1,115 @@
+extern crate alloc;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+
+use fhe_math::rq::{traits::TryConvertFrom, Distribution, Poly, Representation};
+use itertools::Itertools;
+use rand::{CryptoRng, RngCore};
+use zeroize::Zeroizing;
+
+use super::MultiKeyCiphertext;
+use crate::bfv::{BfvParameters, Plaintext, SecretKey};
+use crate::{Error, Result};
+
+/// A party's share in the decryption of a [`MultiKeyCiphertext`], i.e. the
+/// product of its polynomial of the ciphertext with its secret key, plus a
+/// fresh error. Combine the shares of all the parties of the ciphertext with
+/// [`MultiKeyCiphertext::try_decrypt_with_shares`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PartialDecryption {
+    pub(crate) par: Arc<BfvParameters>,
+    pub(crate) party: usize,
+    pub(crate) share: Poly,
+}
+
+impl PartialDecryption {
+    /// Compute the share of the party identified by `party`.
+    ///
+    /// 1. *Private input*: BFV secret key of the party
+    /// 2. *Public input*: Ciphertext to decrypt
+    // 3. *Public input*: TODO: variance of the ciphertext noise
+    pub fn new<R: RngCore + CryptoRng>(
+        sk: &SecretKey,
+        party: usize,
+        ct: &MultiKeyCiphertext,
+        rng: &mut R,
+    ) -> Result<Self> {
+        if sk.par != ct.par {
+            return Err(Error::DefaultError(
+                "Incompatible BFV parameters".to_string(),
+            ));
+        }
+        let i = ct.parties.binary_search(&party).map_err(|_| {
+            Error::DefaultError("The party is not a party of the ciphertext".to_string())
+        })?;
+
+        let ctx = ct.c[0].ctx();
+        let mut s = Zeroizing::new(Poly::try_convert_from(
+            sk.coeffs.as_ref(),
+            ctx,
+            false,
+            Representation::PowerBasis,
+        )?);
+        s.change_representation(Representation::Ntt);
+        let e = Zeroizing::new(Poly::small(
+            ctx,
+            Representation::Ntt,
+            Distribution::Cbd(ct.par.variance),
+            rng,
+        )?);
+
+        let mut share = ct.c[i + 1].clone();
+        share.disallow_variable_time_computations();
+        share *= s.as_ref();
+        share += e.as_ref();
+        Ok(Self {
+            par: ct.par.clone(),
+            party,
+            share,
+        })
+    }
+}
+
+impl MultiKeyCiphertext {
+    /// Decrypt the ciphertext from the [`PartialDecryption`]s of all its
+    /// parties.
+    ///
+    /// Returns an error if a party of the ciphertext has no share, or if a
+    /// share does not belong to a party of the ciphertext.
+    pub fn try_decrypt_with_shares(&self, shares: &[PartialDecryption]) -> Result<Plaintext> {
+        if shares.iter().any(|share| share.par != self.par) {
+            return Err(Error::DefaultError(
+                "Incompatible BFV parameters".to_string(),
+            ));
+        }
+        let parties = shares
+            .iter()
+            .map(|share| share.party)
+            .sorted()
+            .collect_vec();
+        if parties != self.parties {
+            return Err(Error::DefaultError(
+                "The shares must be those of the parties of the ciphertext".to_string(),
+            ));
+        }
+
+        let mut c = Zeroizing::new(self.c[0].clone());
+        c.disallow_variable_time_computations();
+        shares.iter().for_each(|share| *c.as_mut() += &share.share);
+        c.change_representation(Representation::PowerBasis);
+
+        let w = SecretKey::scale(&self.par, &c, 0)?;
+
+        let mut poly = Poly::try_convert_from(&w, c.ctx(), false, Representation::PowerBasis)?;
+        poly.change_representation(Representation::Ntt);
+
+        Ok(Plaintext {
+            par: self.par.clone(),
+            value: w.into_boxed_slice(),
+            encoding: None,
+            poly_ntt: poly,
+            level: 0,
+            scaled_poly: Default::default(),
+        })
+    }
+}
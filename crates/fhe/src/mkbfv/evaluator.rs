@@ -0,0 +1,288 @@
+extern crate alloc;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use fhe_math::{
+    rns::ScalingFactor,
+    rq::{scaler::Scaler, Context, Poly, Representation},
+    zq::primes::generate_prime,
+};
+use itertools::{izip, Itertools};
+use num_bigint::BigUint;
+
+use super::{MultiKeyCiphertext, PartyKey};
+use crate::bfv::BfvParameters;
+use crate::{Error, Result};
+
+/// An evaluator of additions and multiplications of [`MultiKeyCiphertext`]s,
+/// using the [`PartyKey`]s published by the parties.
+///
+/// The ciphertexts of different parties are extended to the union of their
+/// parties before being combined, and the products are relinearized with the
+/// multi-key relinearization of "Algorithm 1" of [Efficient Multi-Key
+/// Homomorphic Encryption with Packed Ciphertexts](https://eprint.iacr.org/2019/524.pdf),
+/// so that a product of ciphertexts of `k` parties has `k + 1` polynomials.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiKeyEvaluator {
+    par: Arc<BfvParameters>,
+    /// The keys of the parties, sorted by party identifier.
+    keys: Vec<PartyKey>,
+    extender: Scaler,
+    down_scaler: Scaler,
+}
+
+impl MultiKeyEvaluator {
+    /// Create an evaluator with the keys of the parties.
+    ///
+    /// Returns an error if there is no key, if the keys have different
+    /// parameters, or if two keys belong to the same party.
+    pub fn new(keys: &[PartyKey]) -> Result<Self> {
+        let par = keys.first().ok_or(Error::TooFewValues(0, 1))?.par.clone();
+        if keys.iter().any(|key| key.par != par) {
+            return Err(Error::DefaultError(
+                "Incompatible BFV parameters".to_string(),
+            ));
+        }
+        let keys = keys
+            .iter()
+            .cloned()
+            .sorted_by_key(|key| key.party)
+            .collect_vec();
+        if keys.windows(2).any(|w| w[0].party == w[1].party) {
+            return Err(Error::DefaultError(
+                "Duplicate party identifier".to_string(),
+            ));
+        }
+
+        // The tensor product is computed in an extended basis, as in the
+        // default multiplication strategy of the BFV scheme.
+        let ctx = par.ctx_at_level(0)?;
+        let modulus_size = par.moduli_sizes().iter().sum::<usize>();
+        let n_moduli = (modulus_size + 60).div_ceil(62);
+        let mut extended_basis = ctx.moduli().to_vec();
+        let mut upper_bound = 1 << 62;
+        while extended_basis.len() != ctx.moduli().len() + n_moduli {
+            upper_bound = generate_prime(62, 2 * par.degree() as u64, upper_bound).unwrap();
+            if !extended_basis.contains(&upper_bound) {
+                extended_basis.push(upper_bound)
+            }
+        }
+        let mul_ctx = Arc::new(Context::new(&extended_basis, par.degree())?);
+        let extender = Scaler::new(ctx, &mul_ctx, ScalingFactor::one())?;
+        let down_scaler = Scaler::new(
+            &mul_ctx,
+            ctx,
+            ScalingFactor::new(&BigUint::from(*par.plaintext), ctx.modulus()),
+        )?;
+
+        Ok(Self {
+            par,
+            keys,
+            extender,
+            down_scaler,
+        })
+    }
+
+    /// Returns the key of a party.
+    fn party_key(&self, party: usize) -> Result<&PartyKey> {
+        self.keys
+            .binary_search_by_key(&party, |key| key.party)
+            .map(|i| &self.keys[i])
+            .map_err(|_| Error::DefaultError("Missing party key".to_string()))
+    }
+
+    /// Extend two ciphertexts to the union of their parties.
+    fn extend_pair(
+        &self,
+        lhs: &MultiKeyCiphertext,
+        rhs: &MultiKeyCiphertext,
+    ) -> Result<(MultiKeyCiphertext, MultiKeyCiphertext)> {
+        if lhs.par != self.par || rhs.par != self.par {
+            return Err(Error::DefaultError(
+                "Incompatible BFV parameters".to_string(),
+            ));
+        }
+        let parties = lhs
+            .parties
+            .iter()
+            .merge(rhs.parties.iter())
+            .dedup()
+            .copied()
+            .collect_vec();
+        Ok((lhs.extend(&parties)?, rhs.extend(&parties)?))
+    }
+
+    /// Add two multi-key ciphertexts, possibly of different parties.
+    pub fn add(
+        &self,
+        lhs: &MultiKeyCiphertext,
+        rhs: &MultiKeyCiphertext,
+    ) -> Result<MultiKeyCiphertext> {
+        let (mut lhs, rhs) = self.extend_pair(lhs, rhs)?;
+        izip!(lhs.c.iter_mut(), rhs.c.iter()).for_each(|(l, r)| *l += r);
+        Ok(lhs)
+    }
+
+    /// Multiply two multi-key ciphertexts, possibly of different parties, and
+    /// relinearize the product using the keys of the parties of the product.
+    pub fn mul(
+        &self,
+        lhs: &MultiKeyCiphertext,
+        rhs: &MultiKeyCiphertext,
+    ) -> Result<MultiKeyCiphertext> {
+        let (lhs, rhs) = self.extend_pair(lhs, rhs)?;
+        let keys = lhs
+            .parties
+            .iter()
+            .map(|party| self.party_key(*party))
+            .collect::<Result<Vec<_>>>()?;
+        let k = keys.len();
+
+        // Tensor the ciphertexts, combining the symmetric terms, and scale the
+        // terms by t / q.
+        let lhs_ext = self.extender.scale_many(&lhs.c.iter().collect_vec())?;
+        let rhs_ext = self.extender.scale_many(&rhs.c.iter().collect_vec())?;
+        let mut tensor = Vec::with_capacity((k + 1) * (k + 2) / 2);
+        for i in 0..=k {
+            for j in i..=k {
+                let mut cij = &lhs_ext[i] * &rhs_ext[j];
+                if i != j {
+                    cij += &(&lhs_ext[j] * &rhs_ext[i]);
+                }
+                cij.change_representation(Representation::PowerBasis);
+                tensor.push(cij);
+            }
+        }
+        let tensor = self.down_scaler.scale_many(&tensor.iter().collect_vec())?;
+
+        // Relinearize the terms in s_i * s_j.
+        let mut c = tensor[..=k].to_vec();
+        c.iter_mut()
+            .for_each(|ci| ci.change_representation(Representation::Ntt));
+        let mut terms = tensor[k + 1..].iter();
+        for i in 1..=k {
+            for j in i..=k {
+                let cij = terms.next().unwrap();
+                let (ki, kj) = (keys[i - 1], keys[j - 1]);
+                let mut cij_prime = gadget_product(cij, &kj.b);
+                cij_prime.change_representation(Representation::PowerBasis);
+                c[0] += &gadget_product(&cij_prime, &ki.d0);
+                c[i] += &gadget_product(&cij_prime, &ki.d1);
+                c[j] += &gadget_product(cij, &ki.d2);
+            }
+        }
+
+        Ok(MultiKeyCiphertext {
+            par: self.par.clone(),
+            parties: lhs.parties,
+            c,
+        })
+    }
+}
+
+/// Computes the gadget product of the polynomial `p`, in PowerBasis
+/// representation, decomposed in the RNS basis, with the vector `v`.
+fn gadget_product(p: &Poly, v: &[Poly]) -> Poly {
+    let ctx = v[0].ctx();
+    let mut out = Poly::zero(ctx, Representation::Ntt);
+    for (pi_coefficients, vi) in izip!(p.coefficients().outer_iter(), v.iter()) {
+        let pi = unsafe {
+            Poly::create_constant_ntt_polynomial_with_lazy_coefficients_and_variable_time(
+                pi_coefficients.as_slice().unwrap(),
+                ctx,
+            )
+        };
+        out.fma(&pi, vi);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+    use alloc::vec::Vec;
+
+    use fhe_traits::{FheDecoder, FheEncoder, FheEncrypter};
+    use rand::thread_rng;
+
+    use super::MultiKeyEvaluator;
+    use crate::bfv::{BfvParameters, Encoding, Plaintext, SecretKey};
+    use crate::mbfv::CommonRandomPoly;
+    use crate::mkbfv::{MultiKeyCiphertext, PartialDecryption, PartyKey};
+    use crate::Error;
+
+    #[test]
+    fn add_and_mul_between_parties() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let par = BfvParameters::default_arc(3, 16);
+        let crp = CommonRandomPoly::new_vec(&par, &mut rng)?;
+        let num_parties = 3;
+
+        // Every party generates its keys without interaction.
+        let sks = (0..num_parties)
+            .map(|_| SecretKey::random(&par, &mut rng))
+            .collect::<Vec<_>>();
+        let keys = sks
+            .iter()
+            .enumerate()
+            .map(|(i, sk)| PartyKey::new(sk, 10 * i, &crp, &mut rng))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let evaluator = MultiKeyEvaluator::new(&keys)?;
+
+        let values = (0..num_parties)
+            .map(|_| par.plaintext.random_vec(par.degree(), &mut rng))
+            .collect::<Vec<_>>();
+        let cts = sks
+            .iter()
+            .zip(values.iter())
+            .enumerate()
+            .map(|(i, (sk, v))| {
+                let pt = Plaintext::try_encode(v, Encoding::simd(), &par)?;
+                MultiKeyCiphertext::new(&sk.try_encrypt(&pt, &mut thread_rng())?, 10 * i)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let decrypt = |ct: &MultiKeyCiphertext| -> Result<Vec<u64>, Error> {
+            let shares = ct
+                .parties()
+                .iter()
+                .map(|party| {
+                    PartialDecryption::new(&sks[party / 10], *party, ct, &mut thread_rng())
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            Vec::<u64>::try_decode(&ct.try_decrypt_with_shares(&shares)?, Encoding::simd())
+        };
+
+        // (v0 + v1) * v2 + v0 * v0
+        let sum = evaluator.add(&cts[0], &cts[1])?;
+        assert_eq!(sum.parties(), &[0, 10]);
+        let product = evaluator.mul(&sum, &cts[2])?;
+        assert_eq!(product.parties(), &[0, 10, 20]);
+        assert_eq!(product.c.len(), 4);
+        let square = evaluator.mul(&cts[0], &cts[0])?;
+        assert_eq!(square.parties(), &[0]);
+        let result = evaluator.add(&product, &square)?;
+
+        let t = &par.plaintext;
+        let expected = (0..par.degree())
+            .map(|i| {
+                t.add(
+                    t.mul(t.add(values[0][i], values[1][i]), values[2][i]),
+                    t.mul(values[0][i], values[0][i]),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(decrypt(&result)?, expected);
+
+        // All the parties of the ciphertext must take part in the decryption.
+        let share = PartialDecryption::new(&sks[0], 0, &result, &mut rng)?;
+        assert!(result.try_decrypt_with_shares(&[share]).is_err());
+
+        // The keys of the parties of the product are required.
+        let evaluator = MultiKeyEvaluator::new(&keys[..2])?;
+        assert!(evaluator.mul(&sum, &cts[2]).is_err());
+        assert!(MultiKeyEvaluator::new(&[keys[0].clone(), keys[0].clone()]).is_err());
+        Ok(())
+    }
+}
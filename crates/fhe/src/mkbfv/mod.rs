@@ -0,0 +1,23 @@
+//! An experimental Multi-Key BFV scheme, as described by Hao Chen et. al. in
+//! [Efficient Multi-Key Homomorphic Encryption with Packed Ciphertexts](https://eprint.iacr.org/2019/524.pdf).
+//!
+//! Unlike the [multiparty BFV scheme](crate::mbfv), there is no joint key
+//! generation: every party encrypts under its own secret key, and publishes a
+//! [`PartyKey`] generated from a common random polynomial vector. The
+//! ciphertexts of different parties are combined on the fly into
+//! [`MultiKeyCiphertext`]s, which are decrypted with the
+//! [`PartialDecryption`]s of all their parties.
+//!
+//! This module is experimental: the ciphertexts are only supported at level
+//! 0, the noise of the partial decryptions is not adapted to the noise of the
+//! ciphertexts, and the keys and ciphertexts cannot be serialized yet.
+
+mod ciphertext;
+mod decryption;
+mod evaluator;
+mod party_key;
+
+pub use ciphertext::MultiKeyCiphertext;
+pub use decryption::PartialDecryption;
+pub use evaluator::MultiKeyEvaluator;
+pub use party_key::PartyKey;
@@ -0,0 +1,146 @@
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use fhe_math::rns::RnsContext;
+use fhe_math::rq::{traits::TryConvertFrom, Distribution, Poly, Representation};
+use rand::{CryptoRng, RngCore};
+use zeroize::Zeroizing;
+
+use crate::bfv::{BfvParameters, SecretKey};
+use crate::mbfv::CommonRandomPoly;
+use crate::{Error, Result};
+
+/// The public key material published by a party of a multi-key computation,
+/// as described in "Section 3" of [Efficient Multi-Key Homomorphic Encryption
+/// with Packed Ciphertexts](https://eprint.iacr.org/2019/524.pdf).
+///
+/// The key is generated independently by each party from its own BFV secret
+/// key and a common random polynomial vector: it contains the gadget public
+/// key `b = -s * a + e`, and the uni-encryption `(d0, d1, d2)` of the secret
+/// key `s` used to relinearize the products of multi-key ciphertexts.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PartyKey {
+    pub(crate) par: Arc<BfvParameters>,
+    pub(crate) party: usize,
+    pub(crate) b: Box<[Poly]>,
+    pub(crate) d0: Box<[Poly]>,
+    pub(crate) d1: Box<[Poly]>,
+    pub(crate) d2: Box<[Poly]>,
+}
+
+impl PartyKey {
+    /// Generate the key of the party identified by `party`.
+    ///
+    /// 1. *Private input*: BFV secret key of the party
+    /// 2. *Public input*: common random polynomial vector, shared by all the
+    ///    parties, e.g. derived from a public seed
+    pub fn new<R: RngCore + CryptoRng>(
+        sk: &SecretKey,
+        party: usize,
+        crp: &[CommonRandomPoly],
+        rng: &mut R,
+    ) -> Result<Self> {
+        let par = sk.par.clone();
+        let ctx = par.ctx_at_level(0)?;
+        if ctx.moduli().len() == 1 {
+            return Err(Error::DefaultError(
+                "These parameters do not support key switching".to_string(),
+            ));
+        }
+        if crp.len() != ctx.moduli().len() {
+            return Err(Error::DefaultError(
+                "The size of the CRP polynomial vector must equal the number of ciphertext moduli."
+                    .to_string(),
+            ));
+        }
+        if crp.iter().any(|a| a.poly.ctx() != ctx) {
+            return Err(Error::DefaultError(
+                "Incompatible BFV parameters".to_string(),
+            ));
+        }
+
+        let s = Zeroizing::new(Poly::try_convert_from(
+            sk.coeffs.as_ref(),
+            ctx,
+            false,
+            Representation::PowerBasis,
+        )?);
+        let mut s_ntt = s.clone();
+        s_ntt.change_representation(Representation::Ntt);
+        let mut r = Zeroizing::new(Poly::small(
+            ctx,
+            Representation::PowerBasis,
+            Distribution::Cbd(par.variance),
+            rng,
+        )?);
+        let mut r_ntt = r.clone();
+        r_ntt.change_representation(Representation::Ntt);
+        r.disallow_variable_time_computations();
+
+        let error = |rng: &mut R| -> Result<Zeroizing<Poly>> {
+            Ok(Zeroizing::new(Poly::small(
+                ctx,
+                Representation::Ntt,
+                Distribution::Cbd(par.variance),
+                rng,
+            )?))
+        };
+
+        let rns = RnsContext::new(ctx.moduli())?;
+        let mut b = Vec::with_capacity(crp.len());
+        let mut d0 = Vec::with_capacity(crp.len());
+        let mut d1 = Vec::with_capacity(crp.len());
+        let mut d2 = Vec::with_capacity(crp.len());
+        for (i, a) in crp.iter().enumerate() {
+            let g = rns.get_garner(i).unwrap();
+            let mut a = a.poly.clone();
+            a.disallow_variable_time_computations();
+            a.change_representation(Representation::Ntt);
+
+            // b_i = -s * a_i + e
+            let mut bi = -&a;
+            bi *= s_ntt.as_ref();
+            bi += error(rng)?.as_ref();
+
+            // d1_i is uniformly random, and d0_i = -s * d1_i + e + r * g_i
+            let mut d1i = Poly::random(ctx, Representation::Ntt, rng);
+            d1i.disallow_variable_time_computations();
+            let mut d0i = -&d1i;
+            d0i *= s_ntt.as_ref();
+            d0i += error(rng)?.as_ref();
+            let mut rg = Zeroizing::new(g * r.as_ref());
+            rg.change_representation(Representation::Ntt);
+            d0i += rg.as_ref();
+
+            // d2_i = r * a_i + e + s * g_i
+            let mut d2i = a;
+            d2i *= r_ntt.as_ref();
+            d2i += error(rng)?.as_ref();
+            let mut sg = Zeroizing::new(g * s.as_ref());
+            sg.change_representation(Representation::Ntt);
+            d2i += sg.as_ref();
+
+            b.push(bi);
+            d0.push(d0i);
+            d1.push(d1i);
+            d2.push(d2i);
+        }
+
+        Ok(Self {
+            par,
+            party,
+            b: b.into_boxed_slice(),
+            d0: d0.into_boxed_slice(),
+            d1: d1.into_boxed_slice(),
+            d2: d2.into_boxed_slice(),
+        })
+    }
+
+    /// Returns the identifier of the party.
+    pub fn party(&self) -> usize {
+        self.party
+    }
+}
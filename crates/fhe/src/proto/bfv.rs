@@ -9,6 +9,17 @@ pub struct Ciphertext {
     pub seed: ::prost::alloc::vec::Vec<u8>,
     #[prost(uint32, tag = "3")]
     pub level: u32,
+    #[prost(bytes = "vec", tag = "4")]
+    pub tag: ::prost::alloc::vec::Vec<u8>,
+}
+/// Ciphertext serialized by the legacy `bfv` crate, always at level 0.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LegacyCiphertext {
+    #[prost(bytes = "vec", tag = "1")]
+    pub c0: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub c1: ::prost::alloc::vec::Vec<u8>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -39,6 +50,8 @@ pub struct KeySwitchingKey {
 pub struct RelinearizationKey {
     #[prost(message, optional, tag = "1")]
     pub ksk: ::core::option::Option<KeySwitchingKey>,
+    #[prost(message, repeated, tag = "2")]
+    pub ksk_powers: ::prost::alloc::vec::Vec<KeySwitchingKey>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -69,6 +82,10 @@ pub struct Parameters {
     pub plaintext: u64,
     #[prost(uint32, tag = "4")]
     pub variance: u32,
+    #[prost(uint32, tag = "5")]
+    pub cyclotomic_index: u32,
+    #[prost(bool, tag = "6")]
+    pub disable_batching: bool,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -76,3 +93,94 @@ pub struct PublicKey {
     #[prost(message, optional, tag = "1")]
     pub c: ::core::option::Option<Ciphertext>,
 }
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct KeyPackage {
+    #[prost(uint64, tag = "1")]
+    pub params_id: u64,
+    #[prost(message, optional, tag = "2")]
+    pub pk: ::core::option::Option<PublicKey>,
+    #[prost(message, optional, tag = "3")]
+    pub rk: ::core::option::Option<RelinearizationKey>,
+    #[prost(message, optional, tag = "4")]
+    pub ek: ::core::option::Option<EvaluationKey>,
+    #[prost(bytes = "vec", tag = "5")]
+    pub digest: ::prost::alloc::vec::Vec<u8>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Plaintext {
+    #[prost(uint64, repeated, tag = "1")]
+    pub value: ::prost::alloc::vec::Vec<u64>,
+    #[prost(uint32, tag = "2")]
+    pub level: u32,
+    /// 0 if the encoding is unknown, 1 for Poly, 2 for Simd.
+    #[prost(uint32, tag = "3")]
+    pub encoding: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct EncryptedRational {
+    #[prost(message, optional, tag = "1")]
+    pub numerator: ::core::option::Option<Ciphertext>,
+    #[prost(message, optional, tag = "2")]
+    pub denominator: ::core::option::Option<Ciphertext>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct EncryptedStatistic {
+    #[prost(message, optional, tag = "1")]
+    pub numerator: ::core::option::Option<Ciphertext>,
+    #[prost(uint64, tag = "2")]
+    pub denominator: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RekeyingKey {
+    #[prost(message, optional, tag = "1")]
+    pub ksk: ::core::option::Option<KeySwitchingKey>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct EncryptedTensor {
+    #[prost(uint64, repeated, tag = "1")]
+    pub shape: ::prost::alloc::vec::Vec<u64>,
+    #[prost(message, repeated, tag = "2")]
+    pub ciphertexts: ::prost::alloc::vec::Vec<Ciphertext>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NoiseTable {
+    #[prost(uint64, tag = "1")]
+    pub parameters_id: u64,
+    #[prost(uint32, tag = "2")]
+    pub fresh: u32,
+    #[prost(uint32, tag = "3")]
+    pub add: u32,
+    #[prost(uint32, tag = "4")]
+    pub mul_plaintext: u32,
+    #[prost(uint32, tag = "5")]
+    pub mul: u32,
+    #[prost(uint32, repeated, tag = "6")]
+    pub key_switching: ::prost::alloc::vec::Vec<u32>,
+    #[prost(uint32, tag = "7")]
+    pub mod_switch: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct EvaluatorCheckpoint {
+    #[prost(uint64, tag = "1")]
+    pub params_id: u64,
+    #[prost(message, repeated, tag = "2")]
+    pub accumulators: ::prost::alloc::vec::Vec<Ciphertext>,
+    #[prost(uint32, repeated, tag = "3")]
+    pub levels: ::prost::alloc::vec::Vec<u32>,
+    #[prost(uint64, tag = "4")]
+    pub operations: u64,
+    #[prost(uint64, tag = "5")]
+    pub multiplications: u64,
+    #[prost(uint64, tag = "6")]
+    pub rotations: u64,
+    #[prost(bytes = "vec", tag = "7")]
+    pub digest: ::prost::alloc::vec::Vec<u8>,
+}
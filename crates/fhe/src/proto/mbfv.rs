@@ -0,0 +1,48 @@
+#![allow(missing_docs)]
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CommonRandomPoly {
+    #[prost(bytes = "vec", tag = "1")]
+    pub poly: ::prost::alloc::vec::Vec<u8>,
+    #[prost(uint32, tag = "2")]
+    pub num_moduli: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PublicKeyShare {
+    #[prost(bytes = "vec", tag = "1")]
+    pub crp: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub p0_share: ::prost::alloc::vec::Vec<u8>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PublicKeySwitchShare {
+    #[prost(bytes = "vec", tag = "1")]
+    pub c0: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub h0_share: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub h1_share: ::prost::alloc::vec::Vec<u8>,
+    #[prost(uint32, tag = "4")]
+    pub level: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SecretKeySwitchShare {
+    #[prost(message, optional, tag = "1")]
+    pub ct: ::core::option::Option<super::bfv::Ciphertext>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub h_share: ::prost::alloc::vec::Vec<u8>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RelinKeyShare {
+    #[prost(bytes = "vec", repeated, tag = "1")]
+    pub h0: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+    #[prost(bytes = "vec", repeated, tag = "2")]
+    pub h1: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+    #[prost(message, optional, boxed, tag = "3")]
+    pub last_round: ::core::option::Option<::prost::alloc::boxed::Box<RelinKeyShare>>,
+}
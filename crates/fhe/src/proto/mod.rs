@@ -2,3 +2,6 @@
 
 /// Protobuf for the BFV encryption scheme.
 pub mod bfv;
+
+/// Protobuf for the multiparty BFV protocols.
+pub mod mbfv;